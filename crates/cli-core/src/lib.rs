@@ -3,6 +3,7 @@ pub mod types;
 
 pub mod checksums;
 pub mod logging;
+pub mod styling;
 pub mod terminal;
 mod url_utils;
 
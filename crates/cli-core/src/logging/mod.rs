@@ -3,9 +3,11 @@ mod logger;
 mod multi_select;
 mod progress_bars;
 mod select;
+mod text_input;
 
 pub use log_action_with_progress::*;
 pub use logger::*;
 pub use multi_select::*;
 pub use progress_bars::*;
 pub use select::*;
+pub use text_input::*;
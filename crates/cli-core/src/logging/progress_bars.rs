@@ -1,10 +1,10 @@
-use crossterm::style::Stylize;
 use crossterm::tty::IsTty;
 use parking_lot::RwLock;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
 use crate::logging::{Logger, LoggerRefreshItemKind, LoggerTextItem};
+use crate::styling::{blue, cyan};
 
 // Inspired by Indicatif, but this custom implementation allows for more control over
 // what's going on under the hood and it works better with the multi-threading model
@@ -14,14 +14,19 @@ use crate::logging::{Logger, LoggerRefreshItemKind, LoggerTextItem};
 pub enum ProgressBarStyle {
   Download,
   Action,
+  /// Processing files, which shows the file count and estimated time remaining.
+  Files,
 }
 
+/// Files progress bars are only displayed after this amount of time so quick runs don't flash a progress bar.
+const FILES_DISPLAY_DELAY: Duration = Duration::from_secs(1);
+
 #[derive(Clone)]
 pub struct ProgressBar {
   id: usize,
   start_time: SystemTime,
   progress_bars: ProgressBars,
-  message: String,
+  message: Arc<RwLock<String>>,
   size: usize,
   style: ProgressBarStyle,
   pos: Arc<RwLock<usize>>,
@@ -33,6 +38,16 @@ impl ProgressBar {
     *pos = new_pos;
   }
 
+  pub fn increment_position(&self) {
+    let mut pos = self.pos.write();
+    *pos += 1;
+  }
+
+  pub fn set_message(&self, message: String) {
+    let mut current_message = self.message.write();
+    *current_message = message;
+  }
+
   pub fn finish(&self) {
     self.progress_bars.finish_progress(self.id);
   }
@@ -80,7 +95,7 @@ impl ProgressBars {
       id,
       progress_bars: self.clone(),
       start_time: SystemTime::now(),
-      message,
+      message: Arc::new(RwLock::new(message)),
       size: total_size,
       style,
       pos: Arc::new(RwLock::new(0)),
@@ -123,22 +138,28 @@ impl ProgressBars {
 
           let terminal_width = crate::terminal::get_terminal_width().unwrap();
           let mut text = String::new();
-          for (i, progress_bar) in internal_state.progress_bars.iter().enumerate() {
-            if i > 0 {
+          for progress_bar in internal_state.progress_bars.iter() {
+            let elapsed = progress_bar.start_time.elapsed().unwrap_or_default();
+            if progress_bar.style == ProgressBarStyle::Files && elapsed < FILES_DISPLAY_DELAY {
+              continue;
+            }
+            if !text.is_empty() {
               text.push('\n');
             }
-            text.push_str(&progress_bar.message);
+            text.push_str(&progress_bar.message.read());
             text.push('\n');
             text.push_str(&get_progress_bar_text(
               terminal_width,
               *progress_bar.pos.read(),
               progress_bar.size,
               progress_bar.style,
-              progress_bar.start_time.elapsed().unwrap(),
+              elapsed,
             ));
           }
 
-          logger.set_refresh_item(LoggerRefreshItemKind::ProgressBars, vec![LoggerTextItem::Text(text)]);
+          if !text.is_empty() {
+            logger.set_refresh_item(LoggerRefreshItemKind::ProgressBars, vec![LoggerTextItem::Text(text)]);
+          }
         }
 
         std::thread::sleep(Duration::from_millis(100));
@@ -149,10 +170,10 @@ impl ProgressBars {
 
 fn get_progress_bar_text(terminal_width: u16, pos: usize, total: usize, pb_style: ProgressBarStyle, duration: Duration) -> String {
   let total = std::cmp::max(pos, total); // increase the total when pos > total
-  let bytes_text = if pb_style == ProgressBarStyle::Download {
-    format!(" {}/{}", get_bytes_text(pos, total), get_bytes_text(total, total))
-  } else {
-    String::new()
+  let suffix_text = match pb_style {
+    ProgressBarStyle::Download => format!(" {}/{}", get_bytes_text(pos, total), get_bytes_text(total, total)),
+    ProgressBarStyle::Files => get_files_text(pos, total, duration),
+    ProgressBarStyle::Action => String::new(),
   };
 
   let elapsed_text = get_elapsed_text(duration);
@@ -160,22 +181,21 @@ fn get_progress_bar_text(terminal_width: u16, pos: usize, total: usize, pb_style
   text.push_str(&elapsed_text);
   // get progress bar
   let percent = pos as f32 / total as f32;
-  // don't include the bytes text in this because a string going from X.XXMB to XX.XXMB should not adjust the progress bar
+  // don't include the suffix text in this because a string going from X.XXMB to XX.XXMB should not adjust the progress bar
   let total_bars = (std::cmp::min(50, terminal_width - 15) as usize) - elapsed_text.len() - 1 - 2;
   let completed_bars = (total_bars as f32 * percent).floor() as usize;
   text.push_str(" [");
   if completed_bars != total_bars {
     if completed_bars > 0 {
-      text.push_str(&format!("{}", format!("{}{}", "#".repeat(completed_bars - 1), ">").cyan()))
+      text.push_str(&cyan(format!("{}{}", "#".repeat(completed_bars - 1), ">")))
     }
-    text.push_str(&format!("{}", "-".repeat(total_bars - completed_bars).blue()))
+    text.push_str(&blue("-".repeat(total_bars - completed_bars)))
   } else {
-    text.push_str(&format!("{}", "#".repeat(completed_bars).cyan()))
+    text.push_str(&cyan("#".repeat(completed_bars)))
   }
   text.push(']');
 
-  // bytes text
-  text.push_str(&bytes_text);
+  text.push_str(&suffix_text);
 
   text
 }
@@ -196,12 +216,25 @@ fn get_bytes_text(byte_count: usize, total_bytes: usize) -> String {
   }
 }
 
+fn get_files_text(pos: usize, total: usize, elapsed: Duration) -> String {
+  let mut text = format!(" {}/{}", pos, total);
+  if pos > 0 && pos < total {
+    let remaining = elapsed.mul_f64((total - pos) as f64 / pos as f64);
+    text.push_str(&format!(" ETA {}", get_duration_text(remaining)));
+  }
+  text
+}
+
 fn get_elapsed_text(elapsed: Duration) -> String {
-  let elapsed_secs = elapsed.as_secs();
-  let seconds = elapsed_secs % 60;
-  let minutes = (elapsed_secs / 60) % 60;
-  let hours = (elapsed_secs / 60) / 60;
-  format!("[{:0>2}:{:0>2}:{:0>2}]", hours, minutes, seconds)
+  format!("[{}]", get_duration_text(elapsed))
+}
+
+fn get_duration_text(duration: Duration) -> String {
+  let secs = duration.as_secs();
+  let seconds = secs % 60;
+  let minutes = (secs / 60) % 60;
+  let hours = (secs / 60) / 60;
+  format!("{:0>2}:{:0>2}:{:0>2}", hours, minutes, seconds)
 }
 
 #[cfg(test)]
@@ -236,4 +269,12 @@ mod test {
     assert_eq!(get_elapsed_text(Duration::from_secs(60 * 60 * 99)), "[99:00:00]");
     assert_eq!(get_elapsed_text(Duration::from_secs(60 * 60 * 120)), "[120:00:00]");
   }
+
+  #[test]
+  fn it_should_get_files_text() {
+    assert_eq!(get_files_text(0, 10, Duration::from_secs(1)), " 0/10");
+    assert_eq!(get_files_text(2, 10, Duration::from_secs(5)), " 2/10 ETA 00:00:20");
+    assert_eq!(get_files_text(50, 100, Duration::from_secs(90)), " 50/100 ETA 00:01:30");
+    assert_eq!(get_files_text(10, 10, Duration::from_secs(5)), " 10/10");
+  }
 }
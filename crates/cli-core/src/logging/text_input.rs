@@ -0,0 +1,50 @@
+use crate::logging::{Logger, LoggerRefreshItemKind, LoggerTextItem};
+use crate::terminal::read_terminal_event;
+use crate::types::ErrBox;
+use crossterm::event::{Event, KeyCode};
+
+/// Prompts for a line of text returning the entered text.
+pub fn show_text_input(logger: &Logger, context_name: &str, prompt: &str) -> Result<String, ErrBox> {
+  let mut text = String::new();
+
+  loop {
+    logger.set_refresh_item(LoggerRefreshItemKind::Selection, render_text_input(prompt, &text));
+
+    match read_terminal_event()? {
+      Event::Key(key_event) => match &key_event.code {
+        KeyCode::Char(c) => {
+          text.push(*c);
+        }
+        KeyCode::Backspace => {
+          text.pop();
+        }
+        KeyCode::Enter => {
+          break;
+        }
+        KeyCode::Esc => {
+          logger.remove_refresh_item(LoggerRefreshItemKind::Selection);
+          return err!("Input cancelled.");
+        }
+        _ => {}
+      },
+      _ => {
+        // cause a refresh anyway
+      }
+    }
+  }
+  logger.remove_refresh_item(LoggerRefreshItemKind::Selection);
+
+  logger.log_text_items(&render_text_input(prompt, &text), context_name, crate::terminal::get_terminal_width());
+
+  Ok(text)
+}
+
+fn render_text_input(prompt: &str, text: &str) -> Vec<LoggerTextItem> {
+  vec![
+    LoggerTextItem::Text(prompt.to_string()),
+    LoggerTextItem::HangingText {
+      text: format!("> {}", text),
+      indent: 2,
+    },
+  ]
+}
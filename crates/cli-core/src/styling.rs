@@ -0,0 +1,109 @@
+use crossterm::style::{StyledContent, Stylize};
+use crossterm::tty::IsTty;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether text should be styled with colors and attributes.
+///
+/// This is on by default and should be set at startup via `set_color_mode`.
+static USE_COLORS: AtomicBool = AtomicBool::new(true);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ColorMode {
+  /// Always output colors.
+  Always,
+  /// Output colors when outputting to a terminal and the `NO_COLOR` environment variable is not set.
+  Auto,
+  /// Never output colors.
+  Never,
+}
+
+impl ColorMode {
+  pub fn parse(text: &str) -> Option<ColorMode> {
+    match text {
+      "always" => Some(ColorMode::Always),
+      "auto" => Some(ColorMode::Auto),
+      "never" => Some(ColorMode::Never),
+      _ => None,
+    }
+  }
+}
+
+/// Sets whether text is styled based on the color mode, the `NO_COLOR` environment
+/// variable (https://no-color.org), and whether stdout and stderr are terminals.
+pub fn set_color_mode(color_mode: ColorMode) {
+  let no_color = std::env::var("NO_COLOR").map(|value| !value.is_empty()).unwrap_or(false);
+  let is_terminal = std::io::stdout().is_tty() && std::io::stderr().is_tty();
+  USE_COLORS.store(get_use_colors(color_mode, no_color, is_terminal), Ordering::SeqCst);
+}
+
+pub fn use_colors() -> bool {
+  USE_COLORS.load(Ordering::SeqCst)
+}
+
+fn get_use_colors(color_mode: ColorMode, no_color: bool, is_terminal: bool) -> bool {
+  match color_mode {
+    ColorMode::Always => true,
+    ColorMode::Never => false,
+    ColorMode::Auto => !no_color && is_terminal,
+  }
+}
+
+pub fn bold(text: impl Display) -> String {
+  style_text(text, |text| text.bold())
+}
+
+pub fn red(text: impl Display) -> String {
+  style_text(text, |text| text.red())
+}
+
+pub fn bold_red(text: impl Display) -> String {
+  style_text(text, |text| text.bold().red())
+}
+
+pub fn cyan(text: impl Display) -> String {
+  style_text(text, |text| text.cyan())
+}
+
+pub fn blue(text: impl Display) -> String {
+  style_text(text, |text| text.blue())
+}
+
+pub fn white_on_green(text: impl Display) -> String {
+  style_text(text, |text| text.white().on_green())
+}
+
+pub fn white_on_red(text: impl Display) -> String {
+  style_text(text, |text| text.white().on_red())
+}
+
+fn style_text(text: impl Display, style: impl FnOnce(StyledContent<String>) -> StyledContent<String>) -> String {
+  let text = text.to_string();
+  if use_colors() {
+    style(text.stylize()).to_string()
+  } else {
+    text
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn it_should_get_use_colors() {
+    assert!(get_use_colors(ColorMode::Always, true, false));
+    assert!(!get_use_colors(ColorMode::Never, false, true));
+    assert!(get_use_colors(ColorMode::Auto, false, true));
+    assert!(!get_use_colors(ColorMode::Auto, true, true));
+    assert!(!get_use_colors(ColorMode::Auto, false, false));
+  }
+
+  #[test]
+  fn it_should_parse_color_mode() {
+    assert_eq!(ColorMode::parse("always"), Some(ColorMode::Always));
+    assert_eq!(ColorMode::parse("auto"), Some(ColorMode::Auto));
+    assert_eq!(ColorMode::parse("never"), Some(ColorMode::Never));
+    assert_eq!(ColorMode::parse("other"), None);
+  }
+}
@@ -1,9 +1,17 @@
 use crate::logging::{ProgressBarStyle, ProgressBars};
 use crate::types::ErrBox;
 use std::io::Read;
+use std::time::Duration;
+use url::Url;
+
+/// Number of times a request is retried when it fails because of a network
+/// error or a server error status (ex. on a flaky network).
+const MAX_RETRIES: u32 = 3;
+/// Number of redirects followed by `fetch_url`.
+const MAX_REDIRECTS: u32 = 5;
 
 pub fn download_url(url: &str, progress_bars: &Option<ProgressBars>, read_env_var: impl Fn(&str) -> Option<String>) -> Result<Vec<u8>, ErrBox> {
-  let resp = match build_agent(url, read_env_var)?.get(url).call() {
+  let resp = match call_with_retries(build_agent(url, read_env_var)?.build().get(url)) {
     Ok(resp) => resp,
     Err(err) => return err!("Error downloading {}. Error: {:?}", url, err),
   };
@@ -21,6 +29,132 @@ pub fn download_url(url: &str, progress_bars: &Option<ProgressBars>, read_env_va
   }
 }
 
+/// A response from `fetch_url`.
+pub struct FetchResponse {
+  pub status: u16,
+  pub headers: Vec<(String, String)>,
+  pub bytes: Vec<u8>,
+}
+
+impl FetchResponse {
+  /// Gets the value of the header with the provided name (case insensitive).
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self
+      .headers
+      .iter()
+      .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_str())
+  }
+}
+
+/// Fetches the url with the provided request headers. Unlike `download_url`, this
+/// does not error for unsuccessful status codes so the response may be inspected.
+///
+/// Redirects are followed without sending the `Authorization` header to other origins
+/// (ex. a registry redirecting to a CDN or S3 bucket).
+pub fn fetch_url(
+  url: &str,
+  headers: &[(&str, &str)],
+  progress_bars: &Option<ProgressBars>,
+  read_env_var: impl Fn(&str) -> Option<String>,
+) -> Result<FetchResponse, ErrBox> {
+  let original_url = match Url::parse(url) {
+    Ok(url) => url,
+    Err(err) => return err!("Error parsing url {}. {}", url, err.to_string()),
+  };
+  let mut current_url = original_url.clone();
+  let mut redirect_count = 0;
+  let resp = loop {
+    let mut request = build_agent(current_url.as_str(), &read_env_var)?.redirects(0).build().get(current_url.as_str());
+    for (name, value) in get_request_headers(headers, &original_url, &current_url) {
+      request = request.set(name, value);
+    }
+    let resp = match call_with_retries(request) {
+      Ok(resp) => resp,
+      Err(ureq::Error::Status(_, resp)) => resp,
+      Err(err) => return err!("Error downloading {}. Error: {:?}", url, err),
+    };
+    let location = match resp.header("Location") {
+      Some(location) if is_redirect_status(resp.status()) => location,
+      _ => break resp,
+    };
+    if redirect_count == MAX_REDIRECTS {
+      return err!("Error downloading {}. Too many redirects.", url);
+    }
+    current_url = match current_url.join(location) {
+      Ok(url) => url,
+      Err(err) => return err!("Error downloading {}. Invalid redirect location {}. {}", url, location, err.to_string()),
+    };
+    redirect_count += 1;
+  };
+  let status = resp.status();
+  let headers: Vec<(String, String)> = resp
+    .headers_names()
+    .into_iter()
+    .filter_map(|name| resp.header(&name).map(|value| value.to_string()).map(|value| (name, value)))
+    .collect();
+  let mut reader = resp.into_reader();
+  let bytes = if status == 200 {
+    let total_size = get_content_length(&headers);
+    inner_download(url, &mut reader, total_size, progress_bars)
+  } else {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map(|_| bytes).map_err(ErrBox::from)
+  };
+  match bytes {
+    Ok(bytes) => Ok(FetchResponse { status, headers, bytes }),
+    Err(err) => err!("Error downloading {}. {}", url, err.to_string()),
+  }
+}
+
+/// Sends the request, retrying it with an exponential backoff when it fails in a way that may succeed later.
+fn call_with_retries(request: ureq::Request) -> Result<ureq::Response, ureq::Error> {
+  let mut attempt = 0;
+  loop {
+    match request.clone().call() {
+      Err(err) if attempt < MAX_RETRIES && is_retryable_error(&err) => {
+        std::thread::sleep(get_retry_delay(attempt));
+        attempt += 1;
+      }
+      result => return result,
+    }
+  }
+}
+
+fn is_redirect_status(status: u16) -> bool {
+  matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+/// Gets the headers to send to the url, which excludes the credentials when the url has
+/// a different origin than the original url because of a redirect.
+fn get_request_headers<'a>(headers: &[(&'a str, &'a str)], original_url: &Url, url: &Url) -> Vec<(&'a str, &'a str)> {
+  let is_same_origin = original_url.origin() == url.origin();
+  headers
+    .iter()
+    .filter(|(name, _)| is_same_origin || !name.eq_ignore_ascii_case("Authorization"))
+    .copied()
+    .collect()
+}
+
+fn is_retryable_error(err: &ureq::Error) -> bool {
+  match err {
+    ureq::Error::Status(status, _) => *status == 429 || *status >= 500,
+    ureq::Error::Transport(transport) => matches!(transport.kind(), ureq::ErrorKind::Dns | ureq::ErrorKind::ConnectionFailed | ureq::ErrorKind::Io),
+  }
+}
+
+fn get_retry_delay(attempt: u32) -> Duration {
+  Duration::from_millis(500 * 2u64.pow(attempt))
+}
+
+fn get_content_length(headers: &[(String, String)]) -> usize {
+  headers
+    .iter()
+    .find(|(name, _)| name.eq_ignore_ascii_case("Content-Length"))
+    .and_then(|(_, value)| value.parse::<usize>().ok())
+    .unwrap_or(0)
+}
+
 fn inner_download(url: &str, reader: &mut impl Read, total_size: usize, progress_bars: &Option<ProgressBars>) -> Result<Vec<u8>, ErrBox> {
   let mut final_bytes = Vec::with_capacity(total_size);
   if let Some(progress_bars) = &progress_bars {
@@ -42,12 +176,12 @@ fn inner_download(url: &str, reader: &mut impl Read, total_size: usize, progress
   Ok(final_bytes)
 }
 
-fn build_agent(url: &str, read_env_var: impl Fn(&str) -> Option<String>) -> Result<ureq::Agent, ErrBox> {
+fn build_agent(url: &str, read_env_var: impl Fn(&str) -> Option<String>) -> Result<ureq::AgentBuilder, ErrBox> {
   let mut agent = ureq::AgentBuilder::new();
   if let Some(proxy_url) = get_proxy_url(url, read_env_var) {
     agent = agent.proxy(ureq::Proxy::new(proxy_url)?);
   }
-  Ok(agent.build())
+  Ok(agent)
 }
 
 fn get_proxy_url(url: &str, read_env_var: impl Fn(&str) -> Option<String>) -> Option<String> {
@@ -64,3 +198,33 @@ fn get_proxy_url(url: &str, read_env_var: impl Fn(&str) -> Option<String>) -> Op
 fn read_proxy_env_var(env_var_name: &str, read_env_var: impl Fn(&str) -> Option<String>) -> Option<String> {
   read_env_var(&env_var_name.to_uppercase()).or_else(|| read_env_var(&env_var_name.to_lowercase()))
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_get_retry_delay() {
+    assert_eq!(get_retry_delay(0), Duration::from_millis(500));
+    assert_eq!(get_retry_delay(1), Duration::from_millis(1000));
+    assert_eq!(get_retry_delay(2), Duration::from_millis(2000));
+  }
+
+  #[test]
+  fn should_only_send_authorization_header_to_original_origin() {
+    let headers = [("Accept", "*/*"), ("Authorization", "Bearer token")];
+    let original_url = Url::parse("https://ghcr.io/v2/org/plugin/blobs/sha256:1").unwrap();
+    let same_origin_url = Url::parse("https://ghcr.io/v2/other").unwrap();
+    assert_eq!(get_request_headers(&headers, &original_url, &same_origin_url), headers.to_vec());
+    for url in [
+      "https://pkg-containers.githubusercontent.com/blob",
+      "http://ghcr.io/v2/other",
+      "https://ghcr.io:8080/v2/other",
+    ]
+    .iter()
+    {
+      let url = Url::parse(url).unwrap();
+      assert_eq!(get_request_headers(&headers, &original_url, &url), vec![("Accept", "*/*")]);
+    }
+  }
+}
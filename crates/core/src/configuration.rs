@@ -139,6 +139,12 @@ pub struct GlobalConfiguration {
   pub use_tabs: Option<bool>,
   pub indent_width: Option<u8>,
   pub new_line_kind: Option<NewLineKind>,
+  /// Applied by the CLI after formatting so files start the same regardless of the plugin.
+  pub trim_leading_blank_lines: Option<bool>,
+  /// Applied by the CLI after formatting so files end the same regardless of the plugin.
+  pub single_trailing_new_line: Option<bool>,
+  /// The maximum time in milliseconds the CLI lets a Wasm plugin take to format a file.
+  pub timeout_ms: Option<u32>,
 }
 
 pub const DEFAULT_GLOBAL_CONFIGURATION: DefaultGlobalConfiguration = DefaultGlobalConfiguration {
@@ -191,6 +197,9 @@ pub fn resolve_global_config(config: ConfigKeyMap, options: &ResolveGlobalConfig
     use_tabs: get_nullable_value(&mut config, "useTabs", &mut diagnostics),
     indent_width: get_nullable_value(&mut config, "indentWidth", &mut diagnostics),
     new_line_kind: get_nullable_value(&mut config, "newLineKind", &mut diagnostics),
+    trim_leading_blank_lines: get_nullable_value(&mut config, "trimLeadingBlankLines", &mut diagnostics),
+    single_trailing_new_line: get_nullable_value(&mut config, "singleTrailingNewLine", &mut diagnostics),
+    timeout_ms: get_nullable_value(&mut config, "timeoutMs", &mut diagnostics),
   };
 
   if options.check_unknown_property_diagnostics {
@@ -21,7 +21,6 @@ impl<'a, T> GraphNode<'a, T> {
     }
   }
 
-  #[cfg(any(feature = "tracing", debug_assertions))]
   pub fn borrow_item(&self) -> &T {
     &self.item
   }
@@ -10,6 +10,21 @@ pub fn is_start_of_line_indented(condition_context: &ConditionResolverContext) -
   condition_context.writer_info.line_start_indent_level > condition_context.writer_info.indent_level
 }
 
+/// Gets if the writer is at the start of a line that has indentation or alignment
+/// (ex. the start of the second line of a list whose items are indented).
+pub fn is_at_start_of_line_indented(condition_context: &ConditionResolverContext) -> bool {
+  condition_context.writer_info.is_start_of_line() && condition_context.writer_info.column_number > 0
+}
+
+/// Gets the number of columns left on the current line before the line width is reached.
+/// Returns 0 when the line width has been reached or exceeded.
+///
+/// This is useful for fill-style wrapping where as many items as fit are placed on a line
+/// (ex. `line_width_remaining(context) >= item_width`).
+pub fn line_width_remaining(condition_context: &ConditionResolverContext) -> u32 {
+  condition_context.get_max_width().saturating_sub(condition_context.writer_info.column_number)
+}
+
 pub fn is_multiple_lines(condition_context: &mut ConditionResolverContext, start_info: &Info, end_info: &Info) -> Option<bool> {
   let start_info = condition_context.get_resolved_info(start_info)?;
   let end_info = condition_context.get_resolved_info(end_info)?;
@@ -158,3 +158,20 @@ pub fn if_false(name: &'static str, resolver: impl Fn(&mut ConditionResolverCont
     },
   )
 }
+
+/// Same as `if_true_or`, but each path is only created the first time it's taken.
+pub fn if_true_or_lazy(
+  name: &'static str,
+  resolver: impl Fn(&mut ConditionResolverContext) -> Option<bool> + 'static,
+  true_path: impl FnOnce() -> PrintItems + 'static,
+  false_path: impl FnOnce() -> PrintItems + 'static,
+) -> Condition {
+  Condition::new_lazy(
+    name,
+    LazyConditionProperties {
+      true_path: Some(Box::new(true_path)),
+      false_path: Some(Box::new(false_path)),
+      condition: Rc::new(Box::new(resolver)),
+    },
+  )
+}
@@ -1,3 +1,8 @@
+//! Code to help build a code formatter.
+//!
+//! The items exported from this module are the public API and follow semantic versioning. The
+//! `internal` module exposes implementation details that may change in any release.
+
 pub mod condition_resolvers;
 pub mod conditions;
 pub mod parser_helpers;
@@ -18,9 +23,25 @@ pub mod utils;
 pub use print::{format, print, PrintOptions};
 #[cfg(feature = "tracing")]
 pub use print::{trace_printing, TracingResult};
-pub use print_items::*;
+pub use print_items::{
+  Anchor, ArenaText, Condition, ConditionProperties, ConditionReference, ConditionResolver, ConditionResolverContext, CreateConditionPath, Info,
+  LazyConditionProperties, Measurement, PrintItemPath, PrintItems, Signal, StringContainer, WriterInfo,
+};
+#[cfg(feature = "tracing")]
+pub use print_items::{Trace, TraceAnchor, TraceCondition, TraceInfo, TracePrintItem, TracePrintNode, TraceWriterNode};
+pub use write_items::WriteItem;
+
+#[cfg(feature = "tracing")]
+use print_items::*;
 use print_write_items::*;
 use printer::*;
 #[cfg(feature = "tracing")]
 use tracing::*;
-pub use write_items::*;
+
+/// Implementation details of the printer.
+///
+/// These are not covered by semantic versioning and may change in any release.
+#[doc(hidden)]
+pub mod internal {
+  pub use super::print_items::{PrintItem, PrintItemsIterator, PrintNode, PrintNodeCell};
+}
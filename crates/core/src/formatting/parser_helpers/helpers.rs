@@ -48,6 +48,33 @@ pub fn with_indent_times(item: PrintItems, times: u32) -> PrintItems {
   items
 }
 
+/// Indents the items only when they end up spanning multiple lines.
+pub fn with_indent_if_broken(item: PrintItems) -> PrintItems {
+  if item.is_empty() {
+    return item;
+  }
+
+  let mut items = PrintItems::new();
+  items.push_signal(Signal::StartGroupIndentIfBroken);
+  items.extend(item);
+  items.push_signal(Signal::FinishGroupIndentIfBroken);
+  items
+}
+
+/// Makes every possible newline directly within the items a newline when
+/// the items end up spanning multiple lines.
+pub fn with_force_expand_if_broken(item: PrintItems) -> PrintItems {
+  if item.is_empty() {
+    return item;
+  }
+
+  let mut items = PrintItems::new();
+  items.push_signal(Signal::StartForceExpand);
+  items.extend(item);
+  items.push_signal(Signal::FinishForceExpand);
+  items
+}
+
 pub fn with_no_new_lines(item: PrintItems) -> PrintItems {
   if item.is_empty() {
     return item;
@@ -72,6 +99,26 @@ pub fn new_line_group(item: PrintItems) -> PrintItems {
   items
 }
 
+/// Packs as many items on each line as fit within the line width, only breaking
+/// between items where necessary (ex. JSX children or a long list of words).
+///
+/// The separator is printed after every item except the last (ex. `","` or empty) and
+/// is followed by a space or newline. An item only breaks within itself when it
+/// doesn't fit on a line on its own.
+pub fn parse_fill(items: Vec<PrintItems>, separator: PrintItems) -> PrintItems {
+  let separator = separator.into_rc_path();
+  let mut result = PrintItems::new();
+  for (i, item) in items.into_iter().enumerate() {
+    if i > 0 {
+      result.extend(separator.clone().into());
+      result.push_signal(Signal::SpaceOrNewLine);
+    }
+    // the newline group makes the printer prefer breaking between the items over within them
+    result.extend(new_line_group(item));
+  }
+  result
+}
+
 /// Parses a string as is and ignores its indent.
 pub fn parse_raw_string(text: &str) -> PrintItems {
   parse_raw_string_lines(text, parse_string)
@@ -124,6 +171,36 @@ fn parse_string_lines(text: &str, parse_line: impl Fn(&str) -> PrintItems) -> Pr
   items
 }
 
+/// Parses text in the arena to a series of PrintItems without copying it.
+///
+/// Prefer this over `parse_string` for large amounts of text taken from the
+/// original file (ex. copy the file text once with `ArenaText::new` and slice it).
+pub fn parse_arena_string(text: ArenaText) -> PrintItems {
+  let mut items = PrintItems::new();
+
+  for (i, line) in text.lines().enumerate() {
+    if i > 0 {
+      items.push_signal(Signal::NewLine);
+    }
+
+    for (i, part) in line.split('\t').enumerate() {
+      if i > 0 {
+        items.push_signal(Signal::Tab);
+      }
+      if !part.is_empty() {
+        items.push_arena_text(part);
+      }
+    }
+  }
+
+  // using .lines() will remove the last line, so add it back if it exists
+  if text.as_str().ends_with('\n') {
+    items.push_signal(Signal::NewLine)
+  }
+
+  items
+}
+
 fn parse_string_line(line: &str) -> PrintItems {
   let mut items = PrintItems::new();
   for (i, line) in line.split('\t').enumerate() {
@@ -163,7 +240,7 @@ pub fn surround_with_newlines_indented_if_multi_line(inner_items: PrintItems, in
       }),
       condition: Rc::new(move |context| {
         // clear the end info when the start info changes
-        if context.has_info_moved(&start_info)? {
+        if !context.is_layout_stable(&start_info)? {
           context.clear_info(&end_info);
         }
         condition_resolvers::is_multiple_lines(context, &start_info, &end_info)
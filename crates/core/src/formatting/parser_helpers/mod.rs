@@ -1,5 +1,7 @@
 mod helpers;
+mod parse_formatted_text;
 mod parse_separated_values;
 
 pub use helpers::*;
+pub use parse_formatted_text::*;
 pub use parse_separated_values::*;
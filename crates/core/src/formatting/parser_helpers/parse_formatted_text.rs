@@ -0,0 +1,123 @@
+use super::super::print_items::*;
+use super::super::write_items::*;
+
+/// Parses text that was output by a formatter into print items that keep its indentation.
+/// Use this to embed the output of one formatter in another formatter's print items.
+///
+/// Unlike `parse_raw_string`, the indentation at the start of each line (tabs or
+/// `indent_width` spaces) becomes indent signals, so the text is indented relative to where
+/// it's embedded and uses the indentation settings of the printer. Any leading spaces that
+/// don't make up a full indent are kept as spaces.
+pub fn parse_formatted_text(text: &str, indent_width: u8) -> PrintItems {
+  let indent_width = std::cmp::max(indent_width, 1) as u32;
+  let mut builder = IndentedItemsBuilder::new();
+
+  for (i, line) in text.lines().enumerate() {
+    if i > 0 {
+      builder.items.push_signal(Signal::NewLine);
+    }
+
+    let line_text = line.trim_start();
+    if line_text.is_empty() {
+      continue;
+    }
+    let indent_text = &line[..line.len() - line_text.len()];
+    let indent_column = indent_text.chars().map(|c| if c == '\t' { indent_width } else { 1 }).sum::<u32>();
+    builder.set_indent_level(indent_column / indent_width);
+    builder.push_spaces(indent_column % indent_width);
+    for (i, part) in line_text.split('\t').enumerate() {
+      if i > 0 {
+        builder.items.push_signal(Signal::Tab);
+      }
+      if !part.is_empty() {
+        builder.items.push_str(part);
+      }
+    }
+  }
+
+  // using .lines() will remove the last line, so add it back if it exists
+  if text.ends_with('\n') {
+    builder.items.push_signal(Signal::NewLine);
+  }
+
+  builder.finish()
+}
+
+/// Parses the write items output by a printer back into print items that keep the
+/// indentation of each line. Alignment is kept as spaces.
+pub fn parse_write_items<'a>(write_items: impl IntoIterator<Item = &'a WriteItem<'a>>) -> PrintItems {
+  let mut builder = IndentedItemsBuilder::new();
+  let mut is_start_of_line = true;
+
+  for write_item in write_items.into_iter() {
+    match write_item {
+      WriteItem::NewLine => {
+        builder.items.push_signal(Signal::NewLine);
+        is_start_of_line = true;
+        continue;
+      }
+      WriteItem::Indent(times) => {
+        if is_start_of_line {
+          builder.set_indent_level(*times as u32);
+        } else {
+          for _ in 0..*times {
+            builder.items.push_signal(Signal::SingleIndent);
+          }
+        }
+      }
+      _ => {
+        if is_start_of_line {
+          builder.set_indent_level(0);
+        }
+        match write_item {
+          WriteItem::String(text) => builder.items.push_str(&text.text),
+          WriteItem::Alignment(width) => builder.push_spaces(*width),
+          WriteItem::Tab => builder.items.push_signal(Signal::Tab),
+          WriteItem::Space => builder.items.push_str(" "),
+          WriteItem::NewLine | WriteItem::Indent(_) => unreachable!(),
+        }
+      }
+    }
+    is_start_of_line = false;
+  }
+
+  builder.finish()
+}
+
+struct IndentedItemsBuilder {
+  items: PrintItems,
+  indent_level: u32,
+}
+
+impl IndentedItemsBuilder {
+  fn new() -> Self {
+    IndentedItemsBuilder {
+      items: PrintItems::new(),
+      indent_level: 0,
+    }
+  }
+
+  /// Changes the indentation for the current line. This must be done before writing
+  /// to the line because the printer indents a line when it's first written to.
+  fn set_indent_level(&mut self, indent_level: u32) {
+    while self.indent_level < indent_level {
+      self.items.push_signal(Signal::StartIndent);
+      self.indent_level += 1;
+    }
+    while self.indent_level > indent_level {
+      self.items.push_signal(Signal::FinishIndent);
+      self.indent_level -= 1;
+    }
+  }
+
+  fn push_spaces(&mut self, count: u32) {
+    if count > 0 {
+      self.items.push_string(" ".repeat(count as usize));
+    }
+  }
+
+  fn finish(mut self) -> PrintItems {
+    self.set_indent_level(0);
+    self.items
+  }
+}
@@ -401,7 +401,7 @@ fn get_clearer_resolutions_on_start_change_condition(value_datas: Rc<RefCell<Vec
     ConditionProperties {
       condition: Rc::new(move |condition_context| {
         // when the start info position changes, clear all the infos so they get re-evaluated again
-        if condition_context.has_info_moved(&start_info)? {
+        if !condition_context.is_layout_stable(&start_info)? {
           for value_data in value_datas.borrow().iter() {
             condition_context.clear_info(&value_data.start_info);
           }
@@ -1,10 +1,14 @@
 use bumpalo::Bump;
 use std::cell::RefCell;
 
-use super::utils::{with_bump_allocator, with_bump_allocator_mut};
+use super::utils::{reset_allocator, with_bump_allocator, with_bump_allocator_mut};
 use super::*;
 
 /// Options for printing the print items.
+///
+/// Create these with `PrintOptions::new` and set any other options on the result, since
+/// options may be added in a minor release.
+#[non_exhaustive]
 pub struct PrintOptions {
   /// The width the printer will attempt to keep the line under.
   pub max_width: u32,
@@ -12,15 +16,34 @@ pub struct PrintOptions {
   pub indent_width: u8,
   /// Whether to use tabs for indenting.
   pub use_tabs: bool,
+  /// When using tabs, whether to use spaces for any alignment beyond the
+  /// indentation (see `Signal::StartAlignment`). This is sometimes called "smart tabs".
+  pub smart_tabs: bool,
   /// The newline character to use when doing a new line.
   pub new_line_text: &'static str,
+  /// The maximum number of consecutive blank lines to allow. Any new lines
+  /// that would exceed this are ignored. `None` allows any number of blank lines.
+  pub max_blank_lines: Option<u32>,
 }
 
 impl PrintOptions {
+  /// Creates the options without smart tabs and without a limit on blank lines.
+  pub fn new(max_width: u32, indent_width: u8, use_tabs: bool, new_line_text: &'static str) -> Self {
+    PrintOptions {
+      max_width,
+      indent_width,
+      use_tabs,
+      smart_tabs: false,
+      new_line_text,
+      max_blank_lines: None,
+    }
+  }
+
   pub(super) fn to_printer_options(&self) -> PrinterOptions {
     PrinterOptions {
       indent_width: self.indent_width,
       max_width: self.max_width,
+      max_blank_lines: self.max_blank_lines,
       #[cfg(feature = "tracing")]
       enable_tracing: false,
     }
@@ -29,6 +52,7 @@ impl PrintOptions {
   pub(super) fn to_write_items_printer_options(&self) -> WriteItemsPrinterOptions {
     WriteItemsPrinterOptions {
       use_tabs: self.use_tabs,
+      smart_tabs: self.smart_tabs,
       new_line_text: self.new_line_text,
       indent_width: self.indent_width,
     }
@@ -47,7 +71,7 @@ pub fn format(get_print_items: impl FnOnce() -> PrintItems, options: PrintOption
   with_bump_allocator_mut(|bump| {
     let result = print_with_allocator(bump, &print_items, &options);
     if decrement_formatting_count() {
-      bump.reset();
+      reset_allocator(bump);
     }
     result
   })
@@ -113,7 +137,7 @@ pub fn trace_printing(get_print_items: impl FnOnce() -> PrintItems, options: Pri
     };
 
     if decrement_formatting_count() {
-      bump.reset();
+      reset_allocator(bump);
     }
     result
   });
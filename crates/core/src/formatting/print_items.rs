@@ -1,9 +1,9 @@
-use std::cell::UnsafeCell;
+use std::cell::{Cell, UnsafeCell};
 use std::mem;
 use std::rc::Rc;
 
 use super::printer::Printer;
-use super::utils::{with_bump_allocator, CounterCell};
+use super::utils::{store_owned_string, with_bump_allocator, CounterCell};
 
 /** Print Items */
 
@@ -64,12 +64,28 @@ impl PrintItems {
   }
 
   pub fn push_str(&mut self, item: &str) {
-    self.push_string(item.to_string());
+    // safe because the text is only referenced by these print items
+    self.push_arena_text(unsafe { ArenaText::new(item) });
   }
 
   pub fn push_string(&mut self, item: String) {
+    self.push_string_container(StringContainer::new(item));
+  }
+
+  /// Pushes text that's already in the arena without copying it.
+  pub fn push_arena_text(&mut self, text: ArenaText) {
+    self.push_string_container(StringContainer::from_arena_text(text));
+  }
+
+  /// Pushes text that's already in the arena along with its pre-computed character
+  /// count. This avoids copying and measuring the text again.
+  pub fn push_arena_text_with_char_count(&mut self, text: ArenaText, char_count: u32) {
+    self.push_string_container(StringContainer::from_arena_text_with_char_count(text, char_count));
+  }
+
+  fn push_string_container(&mut self, string_container: StringContainer) {
     let string_container = with_bump_allocator(|bump| {
-      let result = bump.alloc(StringContainer::new(item));
+      let result = bump.alloc(string_container);
       unsafe { std::mem::transmute::<&StringContainer, UnsafePrintLifetime<StringContainer>>(result) }
     });
     self.push_item_internal(PrintItem::String(string_container));
@@ -87,6 +103,11 @@ impl PrintItems {
     self.push_item_internal(PrintItem::Info(info));
   }
 
+  /// Pushes a point that's padded with spaces to the column of the anchor.
+  pub fn push_align(&mut self, anchor: Anchor) {
+    self.push_item_internal(PrintItem::Align(anchor));
+  }
+
   pub fn push_signal(&mut self, signal: Signal) {
     self.push_item_internal(PrintItem::Signal(signal));
   }
@@ -120,18 +141,24 @@ impl PrintItems {
         match item {
           PrintItem::Signal(signal) => text.push_str(&get_line(format!("Signal::{:?}", signal), &indent_text)),
           PrintItem::Info(info) => text.push_str(&get_line(format!("Info: {}", info.name), &indent_text)),
+          PrintItem::Align(anchor) => text.push_str(&get_line(format!("Align: {}", anchor.name), &indent_text)),
           PrintItem::Condition(condition) => {
             text.push_str(&get_line(format!("Condition: {}", condition.name), &indent_text));
-            if let Some(true_path) = &condition.true_path {
+            // don't create any lazy paths here as that would be surprising
+            if condition.true_path.is_lazy() {
+              text.push_str(&get_line(String::from("  true: (lazy)"), &indent_text));
+            } else if let Some(true_path) = condition.true_path.get_if_created() {
               text.push_str(&get_line(String::from("  true:"), &indent_text));
-              text.push_str(&get_items_as_text(true_path.clone(), format!("{}    ", &indent_text)));
+              text.push_str(&get_items_as_text(true_path, format!("{}    ", &indent_text)));
             }
-            if let Some(false_path) = &condition.false_path {
+            if condition.false_path.is_lazy() {
+              text.push_str(&get_line(String::from("  false: (lazy)"), &indent_text));
+            } else if let Some(false_path) = condition.false_path.get_if_created() {
               text.push_str(&get_line(String::from("  false:"), &indent_text));
-              text.push_str(&get_items_as_text(false_path.clone(), format!("{}    ", &indent_text)));
+              text.push_str(&get_items_as_text(false_path, format!("{}    ", &indent_text)));
             }
           }
-          PrintItem::String(str_text) => text.push_str(&get_line(format!("`{}`", str_text.text.to_string()), &indent_text)),
+          PrintItem::String(str_text) => text.push_str(&get_line(format!("`{}`", str_text.text), &indent_text)),
           PrintItem::RcPath(path) => text.push_str(&get_items_as_text(path.clone(), indent_text.clone())),
         }
       }
@@ -178,7 +205,8 @@ impl Iterator for PrintItemsIterator {
 impl Into<PrintItems> for &'static str {
   fn into(self) -> PrintItems {
     let mut items = PrintItems::new();
-    items.push_str(self);
+    // static strings outlive the arena, so they don't need to be copied
+    items.push_arena_text(ArenaText { text: self });
     items
   }
 }
@@ -257,6 +285,7 @@ pub enum TracePrintItem {
   String(String),
   Condition(TraceCondition),
   Info(TraceInfo),
+  Align(TraceAnchor),
   Signal(Signal),
   /// Identifier to the print node.
   RcPath(usize),
@@ -270,6 +299,14 @@ pub struct TraceInfo {
   pub name: String,
 }
 
+#[cfg(feature = "tracing")]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceAnchor {
+  pub anchor_id: usize,
+  pub name: String,
+}
+
 #[cfg(feature = "tracing")]
 #[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -407,14 +444,19 @@ pub enum PrintItem {
   String(UnsafePrintLifetime<StringContainer>),
   Condition(UnsafePrintLifetime<Condition>),
   Info(Info),
+  Align(Anchor),
   Signal(Signal),
   RcPath(PrintItemPath),
 }
 
 #[derive(Clone, PartialEq, Copy, Debug, serde::Serialize)]
+#[non_exhaustive]
 pub enum Signal {
   /// Signal that a new line should occur based on the printer settings.
   NewLine,
+  /// Signal that a new line should occur based on the printer settings,
+  /// but only when something has been written on the current line.
+  ForceNewLineIfNotBlank,
   /// Signal that a tab should occur based on the printer settings.
   Tab,
   /// Signal that the current location could be a newline when
@@ -448,6 +490,26 @@ pub enum Signal {
   FinishForceNoNewLines,
   /// Signal that a space should occur if not trailing.
   SpaceIfNotTrailing,
+  /// Signal the start of a section where any new lines should be aligned to the
+  /// current column. The alignment beyond the indentation is written as spaces
+  /// when using smart tabs.
+  StartAlignment,
+  /// Signal the end of an alignment section.
+  FinishAlignment,
+  /// Signal the start of a group that should be indented only when it spans
+  /// multiple lines. The printer prints the group once without indentation and
+  /// only prints it again indented when it ends on a different line than it started.
+  StartGroupIndentIfBroken,
+  /// Signal the end of a group that should be indented if broken.
+  FinishGroupIndentIfBroken,
+  /// Signal the start of a group that should be expanded when it spans multiple
+  /// lines. The printer prints the group once and only when it ends on a different
+  /// line than it started prints it again with every `PossibleNewLine` and
+  /// `SpaceOrNewLine` directly within the group as a newline. This is the inverse
+  /// of `StartForceNoNewLines`.
+  StartForceExpand,
+  /// Signal the end of a group that should be expanded if broken.
+  FinishForceExpand,
 }
 
 /// Can be used to get information at a certain location being printed. These
@@ -457,6 +519,9 @@ pub enum Signal {
 pub struct Info {
   /// Unique identifier.
   id: usize,
+  /// If the printer should store a save point at this info so that
+  /// conditions can request a reflow back to it.
+  pub(super) is_reflowable: bool,
   /// Name for debugging purposes.
   #[cfg(debug_assertions)]
   name: &'static str,
@@ -467,9 +532,20 @@ thread_local! {
 }
 
 impl Info {
-  pub fn new(_name: &'static str) -> Info {
+  pub fn new(name: &'static str) -> Info {
+    Info::new_internal(name, false)
+  }
+
+  /// Creates an info that conditions can request to reflow back to
+  /// using `ConditionResolverContext::request_reflow`.
+  pub fn new_reflowable(name: &'static str) -> Info {
+    Info::new_internal(name, true)
+  }
+
+  fn new_internal(_name: &'static str, is_reflowable: bool) -> Info {
     Info {
       id: INFO_COUNTER.with(|counter| counter.increment()),
+      is_reflowable,
       #[cfg(debug_assertions)]
       name: _name,
     }
@@ -489,6 +565,48 @@ impl Info {
   }
 }
 
+/// A column that text on different lines can be aligned to.
+///
+/// Every `PrintItem::Align` of an anchor is padded with spaces to the maximum column
+/// the anchor's align points are at. Since this column is only known once all of them
+/// are printed, the printer prints from the first align point again when an align
+/// point found afterwards changes the maximum column.
+#[derive(Clone, PartialEq, Copy, Debug)]
+pub struct Anchor {
+  /// Unique identifier.
+  id: usize,
+  /// Name for debugging purposes.
+  #[cfg(debug_assertions)]
+  name: &'static str,
+}
+
+thread_local! {
+    static ANCHOR_COUNTER: CounterCell = CounterCell::new();
+}
+
+impl Anchor {
+  pub fn new(_name: &'static str) -> Anchor {
+    Anchor {
+      id: ANCHOR_COUNTER.with(|counter| counter.increment()),
+      #[cfg(debug_assertions)]
+      name: _name,
+    }
+  }
+
+  #[inline]
+  pub fn get_unique_id(&self) -> usize {
+    self.id
+  }
+
+  #[inline]
+  pub fn get_name(&self) -> &'static str {
+    #[cfg(debug_assertions)]
+    return self.name;
+    #[cfg(not(debug_assertions))]
+    return "anchor";
+  }
+}
+
 /// Conditionally print items based on a condition.
 ///
 /// These conditions are extremely flexible and can even be resolved based on
@@ -506,9 +624,9 @@ pub struct Condition {
   /// The condition to resolve.
   pub(super) condition: Rc<ConditionResolver>,
   /// The items to print when the condition is true.
-  pub(super) true_path: Option<PrintItemPath>,
+  pub(super) true_path: ConditionPath,
   /// The items to print when the condition is false or undefined (not yet resolved).
-  pub(super) false_path: Option<PrintItemPath>,
+  pub(super) false_path: ConditionPath,
   /// Any infos that should cause the re-evaluation of this condition.
   /// This is only done on request for performance reasons.
   pub(super) dependent_infos: Option<Vec<Info>>,
@@ -523,6 +641,20 @@ impl Condition {
     Condition::new_internal(name, properties, None)
   }
 
+  /// Creates a condition whose paths are only created the first time they're taken.
+  ///
+  /// This is useful for avoiding the cost of creating print items for paths that
+  /// are rarely taken, such as the false path of a deeply nested condition.
+  pub fn new_lazy(name: &'static str, properties: LazyConditionProperties) -> Condition {
+    Condition::new_with_paths(
+      name,
+      properties.condition,
+      ConditionPath::new_lazy(properties.true_path),
+      ConditionPath::new_lazy(properties.false_path),
+      None,
+    )
+  }
+
   pub fn new_true() -> Condition {
     Condition::new_internal(
       "trueCondition",
@@ -551,15 +683,31 @@ impl Condition {
     Condition::new_internal(name, properties, Some(dependent_infos))
   }
 
-  fn new_internal(_name: &'static str, properties: ConditionProperties, dependent_infos: Option<Vec<Info>>) -> Condition {
+  fn new_internal(name: &'static str, properties: ConditionProperties, dependent_infos: Option<Vec<Info>>) -> Condition {
+    Condition::new_with_paths(
+      name,
+      properties.condition,
+      ConditionPath::new(properties.true_path),
+      ConditionPath::new(properties.false_path),
+      dependent_infos,
+    )
+  }
+
+  fn new_with_paths(
+    _name: &'static str,
+    condition: Rc<ConditionResolver>,
+    true_path: ConditionPath,
+    false_path: ConditionPath,
+    dependent_infos: Option<Vec<Info>>,
+  ) -> Condition {
     Condition {
       id: CONDITION_COUNTER.with(|counter| counter.increment()),
       is_stored: dependent_infos.is_some(),
       #[cfg(debug_assertions)]
       name: _name,
-      condition: properties.condition,
-      true_path: properties.true_path.map(|x| x.first_node).flatten(),
-      false_path: properties.false_path.map(|x| x.first_node).flatten(),
+      condition,
+      true_path,
+      false_path,
       dependent_infos,
     }
   }
@@ -577,14 +725,16 @@ impl Condition {
     return "condition";
   }
 
+  /// Gets the items to print when the condition is true, creating them if lazy.
   #[inline]
-  pub fn get_true_path(&self) -> &Option<PrintItemPath> {
-    &self.true_path
+  pub fn get_true_path(&self) -> Option<PrintItemPath> {
+    self.true_path.get()
   }
 
+  /// Gets the items to print when the condition is false, creating them if lazy.
   #[inline]
-  pub fn get_false_path(&self) -> &Option<PrintItemPath> {
-    &self.false_path
+  pub fn get_false_path(&self) -> Option<PrintItemPath> {
+    self.false_path.get()
   }
 
   #[inline]
@@ -639,6 +789,86 @@ pub struct ConditionProperties {
   pub false_path: Option<PrintItems>,
 }
 
+/// Function used to create the print items of a lazy condition path.
+pub type CreateConditionPath = Box<dyn FnOnce() -> PrintItems>;
+
+/// Properties for a lazy condition.
+pub struct LazyConditionProperties {
+  /// The condition to resolve.
+  pub condition: Rc<ConditionResolver>,
+  /// Creates the items to print when the condition is true. This is only
+  /// called the first time the condition resolves to true.
+  pub true_path: Option<CreateConditionPath>,
+  /// Creates the items to print when the condition is false or undefined (not yet
+  /// resolved). This is only called the first time the false path is taken.
+  pub false_path: Option<CreateConditionPath>,
+}
+
+/// The print items of a condition's path, which may be created lazily.
+#[derive(Clone)]
+pub(super) enum ConditionPath {
+  Created(Option<PrintItemPath>),
+  /// Shared between clones of the condition so the path is only created once.
+  Lazy(Rc<LazyConditionPath>),
+}
+
+pub(super) struct LazyConditionPath {
+  path: Cell<Option<PrintItemPath>>,
+  create_path: Cell<Option<CreateConditionPath>>,
+}
+
+impl ConditionPath {
+  fn new(items: Option<PrintItems>) -> ConditionPath {
+    ConditionPath::Created(items.and_then(|items| items.first_node))
+  }
+
+  fn new_lazy(create_path: Option<CreateConditionPath>) -> ConditionPath {
+    match create_path {
+      Some(create_path) => ConditionPath::Lazy(Rc::new(LazyConditionPath {
+        path: Cell::new(None),
+        create_path: Cell::new(Some(create_path)),
+      })),
+      None => ConditionPath::Created(None),
+    }
+  }
+
+  /// Gets the path, creating it when lazy and not yet created. The created
+  /// print items are allocated in the arena and reused from then on.
+  pub(super) fn get(&self) -> Option<PrintItemPath> {
+    match self {
+      ConditionPath::Created(path) => *path,
+      ConditionPath::Lazy(lazy) => {
+        if let Some(create_path) = lazy.create_path.take() {
+          lazy.path.set(create_path().first_node);
+        }
+        lazy.path.get()
+      }
+    }
+  }
+
+  /// Gets the path without creating it when lazy.
+  pub(super) fn get_if_created(&self) -> Option<PrintItemPath> {
+    match self {
+      ConditionPath::Created(path) => *path,
+      ConditionPath::Lazy(lazy) => lazy.path.get(),
+    }
+  }
+
+  /// Gets if the path is lazy and hasn't been created yet.
+  pub(super) fn is_lazy(&self) -> bool {
+    match self {
+      ConditionPath::Created(_) => false,
+      ConditionPath::Lazy(lazy) => {
+        // the closure needs to be taken out of the cell to check it
+        let create_path = lazy.create_path.take();
+        let is_lazy = create_path.is_some();
+        lazy.create_path.set(create_path);
+        is_lazy
+      }
+    }
+  }
+}
+
 /// Function used to resolve a condition.
 pub type ConditionResolver = dyn Fn(&mut ConditionResolverContext) -> Option<bool>;
 
@@ -672,31 +902,166 @@ impl<'a, 'b> ConditionResolverContext<'a, 'b> {
 
   /// Gets if the provided info has moved positions since the last check.
   /// Returns None when the info can't be resolved. Returns Some(false) the first time this is called.
+  ///
+  /// Prefer using `is_layout_stable`, which has the same behaviour, but reads better in conditions.
   pub fn has_info_moved(&mut self, info: &Info) -> Option<bool> {
     self.printer.has_info_moved(info)
   }
+
+  /// Gets if the provided info is at the same position as the last time this was
+  /// called for it. Use this to implement "reflow until stable" constructs.
+  ///
+  /// Conditions may be re-evaluated after the printer backtracks, which can cause
+  /// infos that were resolved using a previous layout to become stale. When an info
+  /// is not stable, any infos resolved relative to it should be cleared (see `clear_info`)
+  /// so that they get resolved again with the new layout.
+  ///
+  /// * Returns `None` when the info hasn't been resolved yet.
+  /// * Returns `Some(true)` the first time this is called for an info.
+  /// * Returns `Some(false)` when the info moved since the last call. The new position
+  ///   is then stored, so a subsequent call will return `Some(true)` if it doesn't move again.
+  ///
+  /// Note: The position is stored per info and shared between all conditions, so only
+  /// one condition should check the stability of an info.
+  pub fn is_layout_stable(&mut self, info: &Info) -> Option<bool> {
+    self.printer.has_info_moved(info).map(|has_moved| !has_moved)
+  }
+
+  /// Gets the width the printer attempts to keep the lines within (the line width).
+  pub fn get_max_width(&self) -> u32 {
+    self.printer.get_max_width()
+  }
+
+  /// Lays out the provided print items starting at the condition's location without
+  /// printing them. Use this to choose between layouts (ex. whether a fragment fits
+  /// on the current line) without resorting to look ahead infos.
+  ///
+  /// The print items should be self contained, so any infos or conditions they
+  /// reference outside of themselves will be unresolved when measuring. Create
+  /// separate print items for the layout that's printed since these may only be
+  /// used for measuring.
+  pub fn measure(&self, print_items: &PrintItems) -> Measurement {
+    self.printer.measure(print_items)
+  }
+
+  /// Requests that the printer goes back to the provided info and prints
+  /// from there again once the condition is resolved. Use this to implement
+  /// constructs like "if the child broke, break the parent".
+  ///
+  /// The info must have been created with `Info::new_reflowable` and already
+  /// printed. Returns `false` when the reflow won't happen, which is the case
+  /// when the info hasn't been printed or was reflowed to too many times (this
+  /// protects against conditions that never settle on a layout).
+  pub fn request_reflow(&mut self, info: &Info) -> bool {
+    self.printer.request_reflow(info)
+  }
+}
+
+/// Text that has been copied into the arena used for the print items.
+///
+/// Slices of this text can be pushed to the print items any number of times
+/// without allocating or copying the text again.
+#[derive(Clone)]
+pub struct ArenaText {
+  text: UnsafePrintLifetime<str>,
+}
+
+impl ArenaText {
+  /// Copies the text into the arena.
+  ///
+  /// # Safety
+  ///
+  /// The arena is reset once the print items are printed, so this must be called within
+  /// the closure provided to `dprint_core::formatting::format` and the returned text
+  /// (along with its slices) must not be used after that closure returns.
+  pub unsafe fn new(text: &str) -> ArenaText {
+    let text = with_bump_allocator(|bump| {
+      let result = bump.alloc_str(text);
+      std::mem::transmute::<&str, UnsafePrintLifetime<str>>(result)
+    });
+    ArenaText { text }
+  }
+
+  /// Gets a slice of the text using the provided byte range without copying it.
+  ///
+  /// Panics when the range is out of bounds or not on a char boundary.
+  pub fn slice(&self, range: std::ops::Range<usize>) -> ArenaText {
+    ArenaText { text: &self.text[range] }
+  }
+
+  #[inline]
+  pub fn as_str(&self) -> &str {
+    self.text
+  }
+
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.text.len()
+  }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.text.is_empty()
+  }
+
+  /// Splits the text by the provided character without copying it.
+  pub(super) fn split(&self, c: char) -> impl Iterator<Item = ArenaText> {
+    self.text.split(c).map(|text| ArenaText { text })
+  }
+
+  /// Gets the lines of the text (see `str::lines`) without copying it.
+  pub(super) fn lines(&self) -> impl Iterator<Item = ArenaText> {
+    self.text.lines().map(|text| ArenaText { text })
+  }
 }
 
 /// A container that holds the string's value and character count.
 #[derive(Clone)]
 pub struct StringContainer {
-  /// The string value.
-  pub text: String,
+  /// The string value, which is stored in the arena.
+  pub text: UnsafePrintLifetime<str>,
   /// The cached character count.
   /// It is much faster to cache this than to recompute it all the time.
   pub(super) char_count: u32,
 }
 
 impl StringContainer {
-  /// Creates a new string container.
+  /// Creates a new string container moving the text into the arena's storage.
   pub fn new(text: String) -> StringContainer {
     let char_count = text.chars().count() as u32;
-    StringContainer { text, char_count }
+    StringContainer {
+      text: store_owned_string(text),
+      char_count,
+    }
+  }
+
+  /// Creates a new string container from text that's already in the arena.
+  pub fn from_arena_text(text: ArenaText) -> StringContainer {
+    let char_count = text.as_str().chars().count() as u32;
+    StringContainer::from_arena_text_with_char_count(text, char_count)
   }
+
+  /// Creates a new string container from text that's already in the arena and its
+  /// pre-computed character count.
+  pub fn from_arena_text_with_char_count(text: ArenaText, char_count: u32) -> StringContainer {
+    debug_assert_eq!(text.as_str().chars().count() as u32, char_count);
+    StringContainer { text: text.text, char_count }
+  }
+}
+
+/// The size of print items laid out at a location (see `ConditionResolverContext::measure`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Measurement {
+  /// The number of columns of the widest line. The first line is measured from
+  /// the column the print items started at and the other lines include their indentation.
+  pub width: u32,
+  /// The number of lines the print items span.
+  pub line_count: u32,
 }
 
 /// Information about a certain location being printed.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct WriterInfo {
   pub line_number: u32,
   pub column_number: u32,
@@ -11,6 +11,8 @@ pub struct WriteItemsPrinterOptions {
   pub indent_width: u8,
   /// Whether to use tabs for indenting.
   pub use_tabs: bool,
+  /// Whether to use spaces for any alignment beyond the indentation when using tabs.
+  pub smart_tabs: bool,
   /// The newline character to use when doing a new line.
   pub new_line_text: &'static str,
 }
@@ -18,6 +20,8 @@ pub struct WriteItemsPrinterOptions {
 pub struct WriteItemsPrinter {
   indent_string: String,
   new_line_text: &'static str,
+  /// The number of columns to write as a tab when aligning, if any.
+  alignment_tab_width: Option<u32>,
 }
 
 impl WriteItemsPrinter {
@@ -29,6 +33,11 @@ impl WriteItemsPrinter {
         " ".repeat(options.indent_width as usize)
       },
       new_line_text: options.new_line_text,
+      alignment_tab_width: if options.use_tabs && !options.smart_tabs {
+        Some(std::cmp::max(1, options.indent_width as u32))
+      } else {
+        None
+      },
     }
   }
 
@@ -48,6 +57,13 @@ impl WriteItemsPrinter {
     // todo: cache indent strings?
     match item {
       WriteItem::Indent(times) => final_string.push_str(&self.indent_string.repeat(*times as usize)),
+      WriteItem::Alignment(columns) => match self.alignment_tab_width {
+        Some(tab_width) => {
+          final_string.push_str(&"\t".repeat((columns / tab_width) as usize));
+          final_string.push_str(&" ".repeat((columns % tab_width) as usize));
+        }
+        None => final_string.push_str(&" ".repeat(*columns as usize)),
+      },
       WriteItem::NewLine => final_string.push_str(&self.new_line_text),
       WriteItem::Tab => final_string.push('\t'),
       WriteItem::Space => final_string.push(' '),
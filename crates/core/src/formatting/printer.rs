@@ -18,6 +18,43 @@ struct SavePoint<'a> {
   pub look_ahead_condition_save_points: FnvHashMap<usize, &'a SavePoint<'a>>,
   pub look_ahead_info_save_points: FnvHashMap<usize, &'a SavePoint<'a>>,
   pub next_node_stack: Vec<Option<PrintItemPath>>,
+  pub indent_if_broken_groups: Vec<IndentIfBrokenGroup<'a>>,
+  pub force_expand_groups: Vec<ForceExpandGroup<'a>>,
+}
+
+/// A group started by `Signal::StartGroupIndentIfBroken`.
+#[derive(Clone, Copy)]
+struct IndentIfBrokenGroup<'a> {
+  /// Address of the start signal's node.
+  id: usize,
+  start_line_number: u32,
+  /// Save point to print the group again indented or `None` when it's already indented.
+  save_point: Option<&'a SavePoint<'a>>,
+}
+
+/// A group started by `Signal::StartForceExpand`.
+#[derive(Clone, Copy)]
+struct ForceExpandGroup<'a> {
+  /// Address of the start signal's node.
+  id: usize,
+  start_line_number: u32,
+  /// New line group depth of the group's own possible newlines.
+  new_line_group_depth: u16,
+  /// Save point to print the group again expanded or `None` when it's already expanded.
+  save_point: Option<&'a SavePoint<'a>>,
+}
+
+/// The align points printed for an `Anchor`.
+struct AnchorState<'a> {
+  /// Address of the node of the first align point.
+  first_align_id: usize,
+  /// Save point to print from the first align point again.
+  save_point: &'a SavePoint<'a>,
+  /// Columns of the align points before they were padded.
+  columns: FnvHashMap<usize, u32>,
+  /// Column the align points were padded to since printing the first align point.
+  padded_column: u32,
+  reprint_count: u8,
 }
 
 struct PrintItemContainer<'a> {
@@ -46,10 +83,17 @@ pub struct PrinterOptions {
   pub max_width: u32,
   /// The number of columns to count when indenting or using a tab.
   pub indent_width: u8,
+  /// The maximum number of consecutive blank lines to allow.
+  pub max_blank_lines: Option<u32>,
   #[cfg(feature = "tracing")]
   pub enable_tracing: bool,
 }
 
+/// The maximum number of times a condition can request a reflow back to an info.
+const MAX_REFLOWS_PER_INFO: u8 = 10;
+/// The maximum number of times the printer will print from an anchor's first align point again.
+const MAX_REPRINTS_PER_ANCHOR: u8 = 10;
+
 // todo: Needs slight redesign. See issue #71 and #195.
 
 pub struct Printer<'a> {
@@ -69,6 +113,16 @@ pub struct Printer<'a> {
   skip_moving_next: bool,
   resolving_save_point: Option<&'a SavePoint<'a>>,
   stored_info_positions: FnvHashMap<usize, (u32, u32)>,
+  reflow_save_points: FnvHashMap<usize, &'a SavePoint<'a>>,
+  reflow_counts: FnvHashMap<usize, u8>,
+  pending_reflow_save_point: Option<&'a SavePoint<'a>>,
+  indent_if_broken_groups: Vec<IndentIfBrokenGroup<'a>>,
+  /// Group that was broken and should be indented when its start signal is printed again.
+  pending_broken_group_id: Option<usize>,
+  force_expand_groups: Vec<ForceExpandGroup<'a>>,
+  /// Group that was broken and should be expanded when its start signal is printed again.
+  pending_expanded_group_id: Option<usize>,
+  anchors: FnvHashMap<usize, AnchorState<'a>>,
   #[cfg(feature = "tracing")]
   traces: Option<Vec<Trace>>,
   #[cfg(feature = "tracing")]
@@ -87,6 +141,7 @@ impl<'a> Printer<'a> {
         bump,
         WriterOptions {
           indent_width: options.indent_width,
+          max_blank_lines: options.max_blank_lines,
           #[cfg(feature = "tracing")]
           enable_tracing: options.enable_tracing,
         },
@@ -101,6 +156,14 @@ impl<'a> Printer<'a> {
       skip_moving_next: false,
       resolving_save_point: None,
       stored_info_positions: FnvHashMap::default(),
+      reflow_save_points: FnvHashMap::default(),
+      reflow_counts: FnvHashMap::default(),
+      pending_reflow_save_point: None,
+      indent_if_broken_groups: Vec::new(),
+      pending_broken_group_id: None,
+      force_expand_groups: Vec::new(),
+      pending_expanded_group_id: None,
+      anchors: FnvHashMap::default(),
       #[cfg(feature = "tracing")]
       traces: if options.enable_tracing { Some(Vec::new()) } else { None },
       #[cfg(feature = "tracing")]
@@ -125,7 +188,68 @@ impl<'a> Printer<'a> {
     }
   }
 
+  /// Gets the width the printer attempts to keep the lines within.
+  pub fn get_max_width(&self) -> u32 {
+    self.max_width
+  }
+
+  /// Lays out the print items starting from the current writer state without
+  /// writing them, which allows choosing between layouts before printing one.
+  pub fn measure(&self, print_items: &PrintItems) -> Measurement {
+    let mut printer = Printer::new(
+      self.bump,
+      print_items.first_node,
+      PrinterOptions {
+        indent_width: self.writer.get_indent_width(),
+        max_width: self.max_width,
+        max_blank_lines: self.writer.get_max_blank_lines(),
+        #[cfg(feature = "tracing")]
+        enable_tracing: false,
+      },
+    );
+    // when re-resolving a condition, the writer will be past the condition's location
+    let start_state = match &self.resolving_save_point {
+      Some(save_point) => save_point.writer_state.without_items(),
+      None => self.writer.get_state().without_items(),
+    };
+    printer.writer.set_state(start_state);
+    let mut line_start_column = printer.writer.get_line_column();
+    let mut column = if printer.writer.is_line_blank() { 0 } else { line_start_column };
+    printer.print_nodes();
+
+    let indent_width = self.writer.get_indent_width() as u32;
+    let mut width = 0;
+    let mut line_count = 1;
+    for item in printer.writer.get_items() {
+      match item {
+        WriteItem::String(text) => column += text.char_count,
+        WriteItem::Indent(times) => column += *times as u32 * indent_width,
+        WriteItem::Alignment(columns) => column += columns,
+        WriteItem::Tab => column += indent_width,
+        WriteItem::Space => column += 1,
+        WriteItem::NewLine => {
+          width = std::cmp::max(width, column.saturating_sub(line_start_column));
+          line_start_column = 0;
+          column = 0;
+          line_count += 1;
+        }
+      }
+    }
+    width = std::cmp::max(width, column.saturating_sub(line_start_column));
+
+    Measurement { width, line_count }
+  }
+
   fn inner_print(&mut self) {
+    self.print_nodes();
+
+    #[cfg(debug_assertions)]
+    self.verify_no_look_ahead_save_points();
+    #[cfg(debug_assertions)]
+    self.ensure_counts_zero();
+  }
+
+  fn print_nodes(&mut self) {
     while let Some(current_node) = &self.current_node {
       let current_node = unsafe { &*current_node.get_node() }; // ok because values won't be mutated while printing
       self.handle_print_node(current_node);
@@ -145,11 +269,6 @@ impl<'a> Printer<'a> {
         self.current_node = self.next_node_stack.pop().flatten();
       }
     }
-
-    #[cfg(debug_assertions)]
-    self.verify_no_look_ahead_save_points();
-    #[cfg(debug_assertions)]
-    self.ensure_counts_zero();
   }
 
   #[cfg(feature = "tracing")]
@@ -211,12 +330,28 @@ impl<'a> Printer<'a> {
     Some(false)
   }
 
+  pub fn request_reflow(&mut self, info: &Info) -> bool {
+    let info_id = info.get_unique_id();
+    let save_point = match self.reflow_save_points.get(&info_id) {
+      Some(save_point) => *save_point,
+      None => return false,
+    };
+    let reflow_count = self.reflow_counts.entry(info_id).or_insert(0);
+    if *reflow_count >= MAX_REFLOWS_PER_INFO {
+      return false;
+    }
+    *reflow_count += 1;
+    self.pending_reflow_save_point = Some(save_point);
+    true
+  }
+
   #[inline]
   fn handle_print_node(&mut self, print_node: &PrintNode) {
     match &print_node.item {
       PrintItem::String(text) => self.handle_string(text),
       PrintItem::Condition(condition) => self.handle_condition(condition, &print_node.next),
       PrintItem::Info(info) => self.handle_info(info),
+      PrintItem::Align(anchor) => self.handle_align(anchor),
       PrintItem::Signal(signal) => self.handle_signal(signal),
       PrintItem::RcPath(rc_path) => self.handle_rc_path(rc_path, &print_node.next),
     }
@@ -239,6 +374,8 @@ impl<'a> Printer<'a> {
       look_ahead_condition_save_points: self.look_ahead_condition_save_points.clone(),
       look_ahead_info_save_points: self.look_ahead_info_save_points.clone_map(),
       next_node_stack: self.next_node_stack.clone(),
+      indent_if_broken_groups: self.indent_if_broken_groups.clone(),
+      force_expand_groups: self.force_expand_groups.clone(),
     })
   }
 
@@ -280,6 +417,8 @@ impl<'a> Printer<'a> {
     self.look_ahead_condition_save_points = save_point.look_ahead_condition_save_points.clone();
     self.look_ahead_info_save_points.replace_map(save_point.look_ahead_info_save_points.clone());
     self.next_node_stack = save_point.next_node_stack.clone();
+    self.indent_if_broken_groups = save_point.indent_if_broken_groups.clone();
+    self.force_expand_groups = save_point.force_expand_groups.clone();
 
     if is_for_new_line {
       self.write_new_line();
@@ -297,6 +436,11 @@ impl<'a> Printer<'a> {
         }
       }
       Signal::Tab => self.writer.tab(),
+      Signal::ForceNewLineIfNotBlank => {
+        if self.allow_new_lines() && !self.writer.is_line_blank() {
+          self.write_new_line()
+        }
+      }
       Signal::ExpectNewLine => {
         // just always allow this for now since it's most likely a comment...
         self.writer.mark_expect_new_line();
@@ -304,12 +448,18 @@ impl<'a> Printer<'a> {
       }
       Signal::PossibleNewLine => {
         if self.allow_new_lines() {
-          self.mark_possible_new_line_if_able()
+          if self.is_force_expanding() {
+            self.new_line_if_not_blank();
+          } else {
+            self.mark_possible_new_line_if_able()
+          }
         }
       }
       Signal::SpaceOrNewLine => {
         if self.allow_new_lines() {
-          if self.is_above_max_width(1) {
+          if self.is_force_expanding() {
+            self.new_line_if_not_blank();
+          } else if self.is_above_max_width(1) {
             let optional_save_state = std::mem::replace(&mut self.possible_new_line_save_point, None);
             if optional_save_state.is_none() {
               self.write_new_line();
@@ -340,13 +490,143 @@ impl<'a> Printer<'a> {
       Signal::StartForceNoNewLines => self.force_no_newlines_depth += 1,
       Signal::FinishForceNoNewLines => self.force_no_newlines_depth -= 1,
       Signal::SpaceIfNotTrailing => self.writer.space_if_not_trailing(),
+      Signal::StartAlignment => self.writer.start_alignment(),
+      Signal::FinishAlignment => self.writer.finish_alignment(),
+      Signal::StartGroupIndentIfBroken => self.start_group_indent_if_broken(),
+      Signal::FinishGroupIndentIfBroken => self.finish_group_indent_if_broken(),
+      Signal::StartForceExpand => self.start_force_expand(),
+      Signal::FinishForceExpand => self.finish_force_expand(),
     }
   }
 
+  fn new_line_if_not_blank(&mut self) {
+    if !self.writer.is_line_blank() {
+      self.write_new_line();
+    }
+  }
+
+  /// Gets if the current location is directly within a group that was broken and
+  /// so every possible newline should be a newline.
+  fn is_force_expanding(&self) -> bool {
+    match self.force_expand_groups.last() {
+      Some(group) => group.save_point.is_none() && group.new_line_group_depth == self.new_line_group_depth,
+      None => false,
+    }
+  }
+
+  fn start_force_expand(&mut self) {
+    let current_node = self.current_node.unwrap();
+    let group_id = current_node as *const PrintNodeCell as usize;
+    let save_point = if self.pending_expanded_group_id == Some(group_id) {
+      self.pending_expanded_group_id = None;
+      None
+    } else {
+      // restoring this will handle the start signal again
+      Some(self.create_save_point("forceExpand", Some(current_node)))
+    };
+    self.force_expand_groups.push(ForceExpandGroup {
+      id: group_id,
+      start_line_number: self.writer.get_line_number(),
+      new_line_group_depth: self.new_line_group_depth,
+      save_point,
+    });
+  }
+
+  fn finish_force_expand(&mut self) {
+    let group = self
+      .force_expand_groups
+      .pop()
+      .expect("Found a FinishForceExpand signal without a StartForceExpand signal.");
+    if let Some(save_point) = group.save_point {
+      if self.writer.get_line_number() != group.start_line_number {
+        // the group spans multiple lines, so print it again with every possible newline as a newline
+        self.update_state_to_save_point(save_point, false);
+        self.pending_expanded_group_id = Some(group.id);
+      }
+    }
+  }
+
+  fn start_group_indent_if_broken(&mut self) {
+    let current_node = self.current_node.unwrap();
+    let group_id = current_node as *const PrintNodeCell as usize;
+    let save_point = if self.pending_broken_group_id == Some(group_id) {
+      self.pending_broken_group_id = None;
+      self.writer.start_indent();
+      None
+    } else {
+      // restoring this will handle the start signal again
+      Some(self.create_save_point("groupIndentIfBroken", Some(current_node)))
+    };
+    self.indent_if_broken_groups.push(IndentIfBrokenGroup {
+      id: group_id,
+      start_line_number: self.writer.get_line_number(),
+      save_point,
+    });
+  }
+
+  fn finish_group_indent_if_broken(&mut self) {
+    let group = self
+      .indent_if_broken_groups
+      .pop()
+      .expect("Found a FinishGroupIndentIfBroken signal without a StartGroupIndentIfBroken signal.");
+    match group.save_point {
+      None => self.writer.finish_indent(),
+      Some(save_point) => {
+        if self.writer.get_line_number() != group.start_line_number {
+          // the group spans multiple lines, so print it again indented
+          self.update_state_to_save_point(save_point, false);
+          self.pending_broken_group_id = Some(group.id);
+        }
+      }
+    }
+  }
+
+  fn handle_align(&mut self, anchor: &Anchor) {
+    let current_node = self.current_node.unwrap();
+    let align_id = current_node as *const PrintNodeCell as usize;
+    let column = self.writer.get_line_column();
+    let is_first_align = match self.anchors.get(&anchor.get_unique_id()) {
+      Some(anchor_state) => anchor_state.first_align_id == align_id,
+      None => true,
+    };
+    let save_point = if is_first_align {
+      // restoring this will handle the first align point again with the current state of the earlier items
+      Some(self.create_save_point(anchor.get_name(), Some(current_node)))
+    } else {
+      None
+    };
+    let anchor_state = self.anchors.entry(anchor.get_unique_id()).or_insert_with(|| AnchorState {
+      first_align_id: align_id,
+      save_point: save_point.unwrap(),
+      columns: FnvHashMap::default(),
+      padded_column: column,
+      reprint_count: 0,
+    });
+    anchor_state.columns.insert(align_id, column);
+    let max_column = anchor_state.columns.values().copied().max().unwrap();
+    if let Some(save_point) = save_point {
+      anchor_state.save_point = save_point;
+      anchor_state.padded_column = max_column;
+    } else if max_column != anchor_state.padded_column && anchor_state.reprint_count < MAX_REPRINTS_PER_ANCHOR {
+      // the previous align points were padded to a different column, so print them again
+      anchor_state.reprint_count += 1;
+      let save_point = anchor_state.save_point;
+      self.update_state_to_save_point(save_point, false);
+      return;
+    }
+
+    self.writer.pad_to_column(max_column);
+  }
+
   #[inline]
   fn handle_info(&mut self, info: &Info) {
     let info_id = info.get_unique_id();
     self.resolved_infos.insert(info_id, self.get_writer_info());
+    if info.is_reflowable {
+      // restoring this will print the info again, which will store a new save point
+      let save_point = self.create_save_point(info.get_name(), self.current_node);
+      self.reflow_save_points.insert(info_id, save_point);
+    }
     let option_save_point = self.look_ahead_info_save_points.remove(&info_id);
     if let Some(save_point) = option_save_point {
       self.update_state_to_save_point(save_point, false);
@@ -365,6 +645,10 @@ impl<'a> Printer<'a> {
           let mut context = ConditionResolverContext::new(self, save_point.writer_state.get_writer_info(self.writer.get_indent_width()));
           let condition_value = condition.resolve(&mut context);
           self.resolving_save_point.take();
+          if let Some(save_point) = self.pending_reflow_save_point.take() {
+            self.update_state_to_save_point(save_point, false);
+            return;
+          }
           if let Some(condition_value) = condition_value {
             if condition_value != resolved_condition_value {
               self.update_state_to_save_point(save_point, false);
@@ -402,6 +686,11 @@ impl<'a> Printer<'a> {
       self.resolved_conditions.insert(condition_id, condition_value);
     }
 
+    if let Some(save_point) = self.pending_reflow_save_point.take() {
+      self.update_state_to_save_point(save_point, false);
+      return;
+    }
+
     let save_point = self.look_ahead_condition_save_points.get(&condition_id);
     if condition_value.is_some() && save_point.is_some() {
       let save_point = self.look_ahead_condition_save_points.remove(&condition_id);
@@ -410,14 +699,14 @@ impl<'a> Printer<'a> {
     }
 
     if condition_value.is_some() && condition_value.unwrap() {
-      if let Some(true_path) = condition.true_path {
-        self.current_node = Some(true_path.clone());
+      if let Some(true_path) = condition.get_true_path() {
+        self.current_node = Some(true_path);
         self.next_node_stack.push(next_node.clone());
         self.skip_moving_next = true;
       }
     } else {
-      if let Some(false_path) = condition.false_path {
-        self.current_node = Some(false_path.clone());
+      if let Some(false_path) = condition.get_false_path() {
+        self.current_node = Some(false_path);
         self.next_node_stack.push(next_node.clone());
         self.skip_moving_next = true;
       }
@@ -509,6 +798,18 @@ impl<'a> Printer<'a> {
         self.writer.get_indentation_level()
       );
     }
+    if self.writer.get_alignment() != 0 {
+      panic!(
+        "Debug panic! The writer alignment was not zero after printing. {0}",
+        self.writer.get_alignment()
+      );
+    }
+    if !self.indent_if_broken_groups.is_empty() {
+      panic!(
+        "Debug panic! The indent if broken groups were not finished after printing. {0}",
+        self.indent_if_broken_groups.len()
+      );
+    }
     if self.writer.get_ignore_indent_count() != 0 {
       panic!(
         "Debug panic! The writer ignore indent count was not zero after printing. {0}",
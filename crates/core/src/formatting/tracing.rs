@@ -25,11 +25,18 @@ pub fn get_trace_print_nodes(start_node: Option<PrintItemPath>) -> Vec<TracePrin
         info_id: info.get_unique_id(),
         name: info.get_name().to_string(),
       }),
+      PrintItem::Align(anchor) => TracePrintItem::Align(TraceAnchor {
+        anchor_id: anchor.get_unique_id(),
+        name: anchor.get_name().to_string(),
+      }),
       PrintItem::Condition(condition) => {
-        if let Some(true_path) = condition.get_true_path() {
+        // only trace the lazy paths that were created while printing
+        let true_path = condition.true_path.get_if_created();
+        let false_path = condition.false_path.get_if_created();
+        if let Some(true_path) = true_path {
           path_stack.push(true_path);
         }
-        if let Some(false_path) = condition.get_false_path() {
+        if let Some(false_path) = false_path {
           path_stack.push(false_path);
         }
         TracePrintItem::Condition(TraceCondition {
@@ -40,8 +47,8 @@ pub fn get_trace_print_nodes(start_node: Option<PrintItemPath>) -> Vec<TracePrin
             .dependent_infos
             .as_ref()
             .map(|infos| infos.iter().map(|i| i.get_unique_id()).collect()),
-          true_path: condition.get_true_path().map(|p| p.get_node_id()),
-          false_path: condition.get_false_path().map(|p| p.get_node_id()),
+          true_path: true_path.map(|p| p.get_node_id()),
+          false_path: false_path.map(|p| p.get_node_id()),
         })
       }
       PrintItem::Signal(signal) => TracePrintItem::Signal(signal),
@@ -1,8 +1,10 @@
 use bumpalo::Bump;
+use std::cell::RefCell;
 use std::cell::UnsafeCell;
 
 thread_local! {
     static THREAD_LOCAL_BUMP_ALLOCATOR: UnsafeCell<Bump> = UnsafeCell::new(Bump::new());
+    static THREAD_LOCAL_OWNED_STRINGS: RefCell<Vec<String>> = RefCell::new(Vec::new());
 }
 
 pub fn with_bump_allocator<TReturn>(action: impl FnOnce(&Bump) -> TReturn) -> TReturn {
@@ -19,3 +21,23 @@ pub fn with_bump_allocator_mut<TReturn>(action: impl FnMut(&mut Bump) -> TReturn
     action(&mut *bump)
   })
 }
+
+/// Moves the string into storage that lives until the allocator is reset. This
+/// avoids copying strings that are already owned into the allocator.
+///
+/// The returned reference is only valid until `reset_allocator` is called.
+pub fn store_owned_string(text: String) -> &'static str {
+  THREAD_LOCAL_OWNED_STRINGS.with(|strings_cell| {
+    let mut strings = strings_cell.borrow_mut();
+    strings.push(text);
+    // moving the string into the vector doesn't move its heap allocation
+    let text = strings.last().unwrap().as_str();
+    unsafe { std::mem::transmute::<&str, &'static str>(text) }
+  })
+}
+
+/// Resets the allocator and drops the stored owned strings.
+pub fn reset_allocator(bump: &mut Bump) {
+  bump.reset();
+  THREAD_LOCAL_OWNED_STRINGS.with(|strings_cell| strings_cell.borrow_mut().clear());
+}
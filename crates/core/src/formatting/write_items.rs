@@ -1,9 +1,12 @@
 use super::StringContainer;
 
 #[derive(Clone)]
+#[non_exhaustive]
 pub enum WriteItem<'a> {
   String(&'a StringContainer),
   Indent(u8),
+  /// Columns to align to beyond the indentation at the start of a line.
+  Alignment(u32),
   NewLine,
   Tab,
   Space,
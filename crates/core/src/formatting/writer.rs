@@ -9,11 +9,15 @@ pub struct WriterState<'a> {
   current_line_column: u32,
   current_line_number: u32,
   last_line_indent_level: u8,
+  last_line_alignment: u32,
   indent_level: u8,
+  /// The stack of columns to align new lines to beyond the indentation.
+  alignments: Option<&'a GraphNode<'a, u32>>,
   expect_newline_next: bool,
   indent_queue_count: u8,
   last_was_not_trailing_space: bool,
   ignore_indent_count: u8,
+  new_lines_in_row: u32,
   items: Option<&'a GraphNode<'a, WriteItem<'a>>>,
 }
 
@@ -29,17 +33,27 @@ impl<'a> WriterState<'a> {
   }
 }
 
+impl<'a> WriterState<'a> {
+  /// Gets a copy of the state without the written items.
+  pub fn without_items(&self) -> WriterState<'a> {
+    WriterState { items: None, ..self.clone() }
+  }
+}
+
 impl<'a> Clone for WriterState<'a> {
   fn clone(&self) -> WriterState<'a> {
     WriterState {
       current_line_column: self.current_line_column,
       current_line_number: self.current_line_number,
       last_line_indent_level: self.last_line_indent_level,
+      last_line_alignment: self.last_line_alignment,
       indent_level: self.indent_level,
+      alignments: self.alignments,
       expect_newline_next: self.expect_newline_next,
       indent_queue_count: self.indent_queue_count,
       last_was_not_trailing_space: self.last_was_not_trailing_space,
       ignore_indent_count: self.ignore_indent_count,
+      new_lines_in_row: self.new_lines_in_row,
       items: self.items.clone(),
     }
   }
@@ -47,6 +61,7 @@ impl<'a> Clone for WriterState<'a> {
 
 pub struct WriterOptions {
   pub indent_width: u8,
+  pub max_blank_lines: Option<u32>,
   #[cfg(feature = "tracing")]
   pub enable_tracing: bool,
 }
@@ -55,6 +70,7 @@ pub struct Writer<'a> {
   bump: &'a Bump,
   state: WriterState<'a>,
   indent_width: u8,
+  max_blank_lines: Option<u32>,
   #[cfg(feature = "tracing")]
   nodes: Option<Vec<&'a GraphNode<'a, WriteItem<'a>>>>,
 }
@@ -64,15 +80,20 @@ impl<'a> Writer<'a> {
     Writer {
       bump,
       indent_width: options.indent_width,
+      max_blank_lines: options.max_blank_lines,
       state: WriterState {
         current_line_column: 0,
         current_line_number: 0,
         last_line_indent_level: 0,
+        last_line_alignment: 0,
         indent_level: 0,
+        alignments: None,
         expect_newline_next: false,
         indent_queue_count: 0,
         last_was_not_trailing_space: false,
         ignore_indent_count: 0,
+        // the start of the text is treated like the start of a line after a new line
+        new_lines_in_row: 1,
         items: None,
       },
       #[cfg(feature = "tracing")]
@@ -114,6 +135,27 @@ impl<'a> Writer<'a> {
     }
   }
 
+  /// Aligns any new lines to the current column until `finish_alignment` is called.
+  pub fn start_alignment(&mut self) {
+    let indent_column = (self.state.indent_level as u32) * (self.indent_width as u32);
+    let alignment = self.get_line_column().saturating_sub(indent_column);
+    let previous = self.state.alignments.take();
+    self.state.alignments = Some(self.bump.alloc(GraphNode::new(alignment, previous)));
+  }
+
+  pub fn finish_alignment(&mut self) {
+    match self.state.alignments {
+      Some(alignments) => self.state.alignments = *alignments.borrow_previous(),
+      None => panic!("For some reason finish_alignment was called without a corresponding start_alignment."),
+    }
+  }
+
+  /// Gets the number of columns new lines are aligned to beyond the indentation.
+  #[inline]
+  pub fn get_alignment(&self) -> u32 {
+    get_alignment(&self.state)
+  }
+
   pub fn start_ignoring_indent(&mut self) {
     self.state.ignore_indent_count += 1;
   }
@@ -133,6 +175,13 @@ impl<'a> Writer<'a> {
     }
   }
 
+  /// Writes spaces until the current line reaches the provided column.
+  pub fn pad_to_column(&mut self, column: u32) {
+    while self.get_line_column() < column {
+      self.space();
+    }
+  }
+
   pub fn queue_indent(&mut self) {
     self.state.indent_queue_count += 1;
   }
@@ -152,6 +201,11 @@ impl<'a> Writer<'a> {
     self.indent_width
   }
 
+  #[inline]
+  pub fn get_max_blank_lines(&self) -> Option<u32> {
+    self.max_blank_lines
+  }
+
   #[cfg(debug_assertions)]
   pub fn get_ignore_indent_count(&self) -> u8 {
     self.state.ignore_indent_count
@@ -165,7 +219,7 @@ impl<'a> Writer<'a> {
   #[inline]
   pub fn get_line_column(&self) -> u32 {
     if self.state.current_line_column == 0 {
-      (self.indent_width as u32) * (self.state.indent_level as u32)
+      (self.indent_width as u32) * (self.state.indent_level as u32) + self.get_alignment()
     } else {
       self.state.current_line_column
     }
@@ -176,7 +230,21 @@ impl<'a> Writer<'a> {
     self.state.current_line_number
   }
 
+  /// Gets if nothing has been written on the current line.
+  #[inline]
+  pub fn is_line_blank(&self) -> bool {
+    self.state.current_line_column == 0
+  }
+
   pub fn new_line(&mut self) {
+    if let Some(max_blank_lines) = self.max_blank_lines {
+      // ignore any new lines that would exceed the maximum number of blank lines
+      if self.state.new_lines_in_row > max_blank_lines {
+        self.state.expect_newline_next = false;
+        return;
+      }
+    }
+
     if self.state.last_was_not_trailing_space {
       self.pop_item();
       self.state.last_was_not_trailing_space = false;
@@ -185,18 +253,22 @@ impl<'a> Writer<'a> {
     self.state.current_line_column = 0;
     self.state.current_line_number += 1;
     self.state.last_line_indent_level = self.state.indent_level;
+    self.state.last_line_alignment = self.get_alignment();
     self.state.expect_newline_next = false;
+    self.state.new_lines_in_row += 1;
     self.push_item(WriteItem::NewLine);
   }
 
   pub fn single_indent(&mut self) {
     self.handle_first_column();
+    self.state.new_lines_in_row = 0;
     self.state.current_line_column += self.indent_width as u32;
     self.push_item(WriteItem::Indent(1));
   }
 
   pub fn tab(&mut self) {
     self.handle_first_column();
+    self.state.new_lines_in_row = 0;
     self.state.current_line_column += self.indent_width as u32;
     self.push_item(WriteItem::Tab);
   }
@@ -209,6 +281,7 @@ impl<'a> Writer<'a> {
 
   pub fn write(&mut self, text: &'a StringContainer) {
     self.handle_first_column();
+    self.state.new_lines_in_row = 0;
     self.state.current_line_column += text.char_count;
     self.push_item(WriteItem::String(text));
   }
@@ -220,8 +293,10 @@ impl<'a> Writer<'a> {
 
     self.state.last_was_not_trailing_space = false;
 
+    let is_line_start = self.state.current_line_column == 0 && self.state.ignore_indent_count == 0;
+
     // add the indentation if necessary
-    if self.state.current_line_column == 0 && self.state.indent_level > 0 && self.state.ignore_indent_count == 0 {
+    if is_line_start && self.state.indent_level > 0 {
       // update the indent level again since on the first column
       self.state.last_line_indent_level = self.state.indent_level;
 
@@ -234,6 +309,16 @@ impl<'a> Writer<'a> {
         self.push_item(WriteItem::Indent(self.state.indent_level));
       }
     }
+
+    // then align to the column of the alignment section
+    if is_line_start {
+      let alignment = self.get_alignment();
+      self.state.last_line_alignment = alignment;
+      if alignment > 0 {
+        self.state.current_line_column += alignment;
+        self.push_item(WriteItem::Alignment(alignment));
+      }
+    }
   }
 
   fn push_item(&mut self, item: WriteItem<'a>) {
@@ -284,6 +369,7 @@ impl<'a> Writer<'a> {
       write_items.iter(),
       super::WriteItemsPrinterOptions {
         use_tabs: false,
+        smart_tabs: false,
         new_line_text: "\n",
         indent_width: self.indent_width,
       },
@@ -305,7 +391,12 @@ impl<'a> Writer<'a> {
 
 #[inline]
 fn get_line_start_column_number(writer_state: &WriterState, indent_width: u8) -> u32 {
-  (writer_state.last_line_indent_level as u32) * (indent_width as u32)
+  (writer_state.last_line_indent_level as u32) * (indent_width as u32) + writer_state.last_line_alignment
+}
+
+#[inline]
+fn get_alignment(writer_state: &WriterState) -> u32 {
+  writer_state.alignments.map(|node| *node.borrow_item()).unwrap_or(0)
 }
 
 #[cfg(test)]
@@ -376,12 +467,45 @@ mod test {
     });
   }
 
+  #[test]
+  fn newline_squashes_blank_lines_above_max() {
+    with_bump_allocator_mut(|bump| {
+      let mut writer = create_writer_with_max_blank_lines(&bump, Some(1));
+      writer.new_line();
+      write_text(&mut writer, "1", &bump);
+      writer.new_line();
+      writer.new_line();
+      writer.new_line();
+      writer.new_line();
+      write_text(&mut writer, "2", &bump);
+      writer.new_line();
+      write_text(&mut writer, "3", &bump);
+      assert_writer_equal(writer, "\n1\n\n2\n3");
+      bump.reset();
+    });
+  }
+
+  #[test]
+  fn newline_squashes_all_blank_lines_when_max_zero() {
+    with_bump_allocator_mut(|bump| {
+      let mut writer = create_writer_with_max_blank_lines(&bump, Some(0));
+      writer.new_line();
+      write_text(&mut writer, "1", &bump);
+      writer.new_line();
+      writer.new_line();
+      write_text(&mut writer, "2", &bump);
+      assert_writer_equal(writer, "1\n2");
+      bump.reset();
+    });
+  }
+
   fn assert_writer_equal(writer: Writer, text: &str) {
     let result = print_write_items(
       writer.get_items(),
       WriteItemsPrinterOptions {
         indent_width: 2,
         use_tabs: false,
+        smart_tabs: false,
         new_line_text: "\n",
       },
     );
@@ -390,17 +514,25 @@ mod test {
 
   fn write_text(writer: &mut Writer, text: &'static str, bump: &Bump) {
     let string_container = {
-      let result = bump.alloc(StringContainer::new(String::from(text)));
+      let result = bump.alloc(StringContainer {
+        text,
+        char_count: text.chars().count() as u32,
+      });
       unsafe { std::mem::transmute::<&StringContainer, &'static StringContainer>(result) }
     };
     writer.write(string_container);
   }
 
   fn create_writer<'a>(bump: &'a Bump) -> Writer<'a> {
+    create_writer_with_max_blank_lines(bump, None)
+  }
+
+  fn create_writer_with_max_blank_lines<'a>(bump: &'a Bump, max_blank_lines: Option<u32>) -> Writer<'a> {
     Writer::new(
       bump,
       WriterOptions {
         indent_width: 2,
+        max_blank_lines,
         #[cfg(feature = "tracing")]
         enable_tracing: false,
       },
@@ -0,0 +1,280 @@
+//! Utilities for working with JSONC text (ex. dprint configuration files) that retain the comments.
+
+use std::ops::Range;
+
+use crate::types::ErrBox;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsoncTokenKind {
+  OpenBrace,
+  CloseBrace,
+  OpenBracket,
+  CloseBracket,
+  Colon,
+  Comma,
+  /// A string literal including its quotes.
+  String,
+  /// Numbers and keywords (`true`, `false`, and `null`).
+  Word,
+  LineComment,
+  BlockComment,
+}
+
+impl JsoncTokenKind {
+  pub fn is_comment(&self) -> bool {
+    matches!(self, JsoncTokenKind::LineComment | JsoncTokenKind::BlockComment)
+  }
+
+  fn is_open(&self) -> bool {
+    matches!(self, JsoncTokenKind::OpenBrace | JsoncTokenKind::OpenBracket)
+  }
+
+  fn is_close(&self) -> bool {
+    matches!(self, JsoncTokenKind::CloseBrace | JsoncTokenKind::CloseBracket)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsoncToken<'a> {
+  pub kind: JsoncTokenKind,
+  pub text: &'a str,
+  /// Byte range of the token in the source text.
+  pub range: Range<usize>,
+  /// Number of newlines between the previous token (or start of the text) and this token.
+  pub newlines_before: usize,
+}
+
+/// Splits the JSONC text into tokens, including the comments.
+///
+/// Note: This only checks the text is lexically valid and does not verify that the tokens are in a valid order.
+pub fn tokenize_jsonc(text: &str) -> Result<Vec<JsoncToken<'_>>, ErrBox> {
+  let mut tokens = Vec::new();
+  let mut newlines_before = 0;
+  let mut chars = text.char_indices().peekable();
+
+  while let Some((start, c)) = chars.next() {
+    let kind = match c {
+      '\n' => {
+        newlines_before += 1;
+        continue;
+      }
+      c if c.is_whitespace() || c == '\u{FEFF}' => continue,
+      '{' => JsoncTokenKind::OpenBrace,
+      '}' => JsoncTokenKind::CloseBrace,
+      '[' => JsoncTokenKind::OpenBracket,
+      ']' => JsoncTokenKind::CloseBracket,
+      ':' => JsoncTokenKind::Colon,
+      ',' => JsoncTokenKind::Comma,
+      '"' => {
+        let mut is_escaped = false;
+        loop {
+          match chars.next() {
+            Some((_, '\\')) if !is_escaped => is_escaped = true,
+            Some((_, '"')) if !is_escaped => break,
+            Some((_, '\n')) | None => return err!("Unterminated string literal at position {}.", start),
+            Some(_) => is_escaped = false,
+          }
+        }
+        JsoncTokenKind::String
+      }
+      '/' => match chars.next() {
+        Some((_, '/')) => {
+          while chars.peek().map(|(_, c)| *c != '\n').unwrap_or(false) {
+            chars.next();
+          }
+          JsoncTokenKind::LineComment
+        }
+        Some((_, '*')) => {
+          let mut previous_char = ' ';
+          loop {
+            match chars.next() {
+              Some((_, '/')) if previous_char == '*' => break,
+              Some((_, c)) => previous_char = c,
+              None => return err!("Unterminated comment at position {}.", start),
+            }
+          }
+          JsoncTokenKind::BlockComment
+        }
+        _ => return err!("Unexpected character '/' at position {}.", start),
+      },
+      c if is_word_char(c) => {
+        while chars.peek().map(|(_, c)| is_word_char(*c)).unwrap_or(false) {
+          chars.next();
+        }
+        JsoncTokenKind::Word
+      }
+      _ => return err!("Unexpected character '{}' at position {}.", c, start),
+    };
+    let end = chars.peek().map(|(index, _)| *index).unwrap_or(text.len());
+    // exclude the carriage return of a line comment
+    let token_text = text[start..end].trim_end();
+    tokens.push(JsoncToken {
+      kind,
+      text: token_text,
+      range: start..start + token_text.len(),
+      newlines_before,
+    });
+    newlines_before = 0;
+  }
+
+  Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '-' || c == '+' || c == '.'
+}
+
+/// Formats JSONC text while keeping the comments.
+///
+/// Objects and arrays are kept on a single line unless they contain a newline, in
+/// which case each value is put on its own line indented by two spaces.
+pub fn format_jsonc_text(text: &str) -> Result<String, ErrBox> {
+  let tokens = tokenize_jsonc(text)?;
+  let multi_line_containers = get_multi_line_containers(&tokens)?;
+  let mut result = String::new();
+  // whether each open container is multi-line
+  let mut container_stack: Vec<bool> = Vec::new();
+
+  for (i, token) in tokens.iter().enumerate() {
+    let is_multi_line = container_stack.last().copied().unwrap_or(true);
+    if token.kind.is_close() {
+      container_stack.pop();
+    }
+    let depth = container_stack.len();
+
+    if i > 0 {
+      let previous_kind = tokens[i - 1].kind;
+      match token.kind {
+        JsoncTokenKind::Comma | JsoncTokenKind::Colon => {}
+        kind if kind.is_close() => {
+          if is_multi_line {
+            push_newline(&mut result, depth, false);
+          }
+        }
+        _ if previous_kind.is_open() => {
+          if is_multi_line {
+            push_newline(&mut result, depth, false);
+          }
+        }
+        _ if previous_kind == JsoncTokenKind::Colon => result.push(' '),
+        _ if token.newlines_before > 0 && is_multi_line => push_newline(&mut result, depth, token.newlines_before > 1),
+        kind if kind.is_comment() => result.push(' '),
+        _ if previous_kind == JsoncTokenKind::Comma && is_multi_line => push_newline(&mut result, depth, false),
+        _ => result.push(' '),
+      }
+    }
+
+    result.push_str(token.text);
+    if token.kind.is_open() {
+      container_stack.push(multi_line_containers.contains(&i));
+    }
+  }
+
+  result.push('\n');
+  Ok(result)
+}
+
+fn push_newline(result: &mut String, depth: usize, include_blank_line: bool) {
+  if include_blank_line {
+    result.push('\n');
+  }
+  result.push('\n');
+  result.push_str(&"  ".repeat(depth));
+}
+
+/// Gets the indexes of the open tokens whose object or array should be multi-line.
+fn get_multi_line_containers(tokens: &[JsoncToken]) -> Result<Vec<usize>, ErrBox> {
+  let mut multi_line_containers = Vec::new();
+  let mut stack: Vec<(usize, bool)> = Vec::new();
+  for (i, token) in tokens.iter().enumerate() {
+    if token.newlines_before > 0 {
+      if let Some((_, is_multi_line)) = stack.last_mut() {
+        *is_multi_line = true;
+      }
+    }
+    if token.kind.is_open() {
+      stack.push((i, false));
+    } else if token.kind.is_close() {
+      let (open_index, is_multi_line) = match stack.pop() {
+        Some(value) => value,
+        None => return err!("Unexpected '{}'.", token.text),
+      };
+      let is_matching_close = matches!(
+        (tokens[open_index].kind, token.kind),
+        (JsoncTokenKind::OpenBrace, JsoncTokenKind::CloseBrace) | (JsoncTokenKind::OpenBracket, JsoncTokenKind::CloseBracket)
+      );
+      if !is_matching_close {
+        return err!("Expected the close of '{}', but found '{}'.", tokens[open_index].text, token.text);
+      }
+      if is_multi_line {
+        multi_line_containers.push(open_index);
+      }
+    }
+  }
+  if let Some((open_index, _)) = stack.last() {
+    return err!("Expected the close of '{}'.", tokens[*open_index].text);
+  }
+  Ok(multi_line_containers)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_tokenize_with_comments() {
+    let text = "{\n  // a\r\n  \"b\": [1, true] /* c */\n}";
+    let tokens = tokenize_jsonc(text).unwrap();
+    assert_eq!(
+      tokens.iter().map(|token| (token.kind, token.text, token.newlines_before)).collect::<Vec<_>>(),
+      vec![
+        (JsoncTokenKind::OpenBrace, "{", 0),
+        (JsoncTokenKind::LineComment, "// a", 1),
+        (JsoncTokenKind::String, "\"b\"", 1),
+        (JsoncTokenKind::Colon, ":", 0),
+        (JsoncTokenKind::OpenBracket, "[", 0),
+        (JsoncTokenKind::Word, "1", 0),
+        (JsoncTokenKind::Comma, ",", 0),
+        (JsoncTokenKind::Word, "true", 0),
+        (JsoncTokenKind::CloseBracket, "]", 0),
+        (JsoncTokenKind::BlockComment, "/* c */", 0),
+        (JsoncTokenKind::CloseBrace, "}", 1),
+      ]
+    );
+    for token in tokens.iter() {
+      assert_eq!(&text[token.range.clone()], token.text);
+    }
+  }
+
+  #[test]
+  fn should_format_single_and_multi_line_containers() {
+    assert_eq!(format_jsonc_text("{}").unwrap(), "{}\n");
+    assert_eq!(
+      format_jsonc_text("{\"a\":[1,2 , 3],\"b\":{ \"c\":true }}").unwrap(),
+      "{\"a\": [1, 2, 3], \"b\": {\"c\": true}}\n"
+    );
+    assert_eq!(
+      format_jsonc_text("{\n\"a\":[\n1,2],\n\n\n    \"b\":{\n}, \"c\": null}").unwrap(),
+      "{\n  \"a\": [\n    1,\n    2\n  ],\n\n  \"b\": {\n  },\n  \"c\": null\n}\n"
+    );
+  }
+
+  #[test]
+  fn should_format_and_keep_comments() {
+    assert_eq!(
+      format_jsonc_text("// leading\n{\n  \"a\": 1, // trailing\n  /* block */ \"b\": \"//\\\"\",\n\"plugins\": [\n// specify\n]\n}").unwrap(),
+      "// leading\n{\n  \"a\": 1, // trailing\n  /* block */ \"b\": \"//\\\"\",\n  \"plugins\": [\n    // specify\n  ]\n}\n"
+    );
+  }
+
+  #[test]
+  fn should_error_for_invalid_text() {
+    assert_eq!(format_jsonc_text("{\"a\": 1").err().unwrap().to_string(), "Expected the close of '{'.");
+    assert_eq!(format_jsonc_text("[}").err().unwrap().to_string(), "Expected the close of '[', but found '}'.");
+    assert_eq!(
+      format_jsonc_text("{\"a").err().unwrap().to_string(),
+      "Unterminated string literal at position 1."
+    );
+    assert_eq!(format_jsonc_text("/* a").err().unwrap().to_string(), "Unterminated comment at position 0.");
+  }
+}
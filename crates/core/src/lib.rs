@@ -6,4 +6,6 @@ pub mod formatting;
 
 pub mod configuration;
 
+pub mod jsonc;
+
 pub mod plugins;
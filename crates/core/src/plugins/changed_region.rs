@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// A region of the file text that was changed by formatting.
+///
+/// Plugins may report these so the CLI can write only the changed bytes of very
+/// large files and reporters can show where a file was changed.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedRegion {
+  /// The start byte index in the provided file text.
+  pub start: usize,
+  /// The end byte index (exclusive) in the provided file text.
+  pub end: usize,
+  /// The text that replaces the range.
+  pub new_text: String,
+}
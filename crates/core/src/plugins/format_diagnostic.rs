@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// A problem a plugin encountered while formatting a file.
+///
+/// Warnings and info are displayed to the user alongside the formatted output
+/// and do not cause formatting to fail. Errors describe why formatting failed
+/// (ex. a parse error) and are displayed along with the plugin's format error.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatDiagnostic {
+  /// The message that should be displayed to the user.
+  pub message: String,
+  /// The severity of the diagnostic.
+  pub severity: FormatDiagnosticSeverity,
+  /// The range in the provided file text the diagnostic applies to.
+  #[serde(default)]
+  pub span: Option<FormatDiagnosticSpan>,
+}
+
+/// The severity of a format diagnostic.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum FormatDiagnosticSeverity {
+  /// Only reported along with a format error.
+  Error,
+  Warning,
+  Info,
+}
+
+/// A byte range in the file text provided to the plugin.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatDiagnosticSpan {
+  pub start: usize,
+  pub end: usize,
+}
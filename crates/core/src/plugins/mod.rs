@@ -1,3 +1,5 @@
+mod changed_region;
+mod format_diagnostic;
 mod plugin_handler;
 mod plugin_info;
 
@@ -6,5 +8,7 @@ pub mod process;
 #[cfg(feature = "wasm")]
 pub mod wasm;
 
+pub use changed_region::*;
+pub use format_diagnostic::*;
 pub use plugin_handler::*;
 pub use plugin_info::*;
@@ -1,5 +1,5 @@
 use crate::configuration::{ConfigKeyMap, GlobalConfiguration, ResolveConfigurationResult};
-use crate::plugins::PluginInfo;
+use crate::plugins::{ChangedRegion, FormatDiagnostic, PluginInfo};
 use crate::types::ErrBox;
 use serde::Serialize;
 use std::path::Path;
@@ -20,4 +20,29 @@ pub trait PluginHandler<TConfiguration: Clone + Serialize> {
     config: &TConfiguration,
     format_with_host: impl FnMut(&Path, String, &ConfigKeyMap) -> Result<String, ErrBox>,
   ) -> Result<String, ErrBox>;
+  /// Called before the plugin is shut down so it may clean up any resources (ex. temporary files).
+  ///
+  /// Process plugins are killed if they don't exit shortly after this is called.
+  fn shutdown(&mut self) {}
+  /// Takes the diagnostics found while formatting the last file. When formatting
+  /// failed, this is also called to get any diagnostics with an error severity.
+  ///
+  /// This is only used when `supports_format_diagnostics` is true in the plugin info.
+  fn take_format_diagnostics(&mut self) -> Vec<FormatDiagnostic> {
+    Vec::new()
+  }
+  /// Takes the regions of the file text that were changed when formatting the last file.
+  /// Returning `None` causes the CLI to treat the whole file as changed.
+  ///
+  /// This is only used when `supports_changed_regions` is true in the plugin info.
+  fn take_changed_regions(&mut self) -> Option<Vec<ChangedRegion>> {
+    None
+  }
+  /// Formats the provided file text and gets the JSON serialized printer trace
+  /// (see `dprint_core::formatting::trace_printing`) for the `dprint output-trace` command.
+  ///
+  /// Plugins that support this should enable the `tracing` feature of dprint-core.
+  fn trace_text(&mut self, _file_path: &Path, _file_text: &str, _config: &TConfiguration) -> Result<String, ErrBox> {
+    err!("This plugin does not support tracing.")
+  }
 }
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
 
 /// Information about a plugin.
+///
+/// Create this with `PluginInfo::new` and set any of the capability flags on the
+/// result, since flags may be added in a minor release.
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct PluginInfo {
   /// The name of the plugin.
   pub name: String,
@@ -19,4 +23,51 @@ pub struct PluginInfo {
   pub help_url: String,
   /// Schema url for the plugin configuration.
   pub config_schema_url: String,
+  /// If the plugin can format a portion of a file. When true, the CLI handles
+  /// `dprint-ignore-start` and `dprint-ignore-end` ranges by only providing the
+  /// text outside those ranges to the plugin. Otherwise, the plugin is expected
+  /// to handle these directives itself. This also allows formatting only a
+  /// range of a file (ex. `dprint fmt --stdin <file> --range <start>-<end>`).
+  #[serde(default)]
+  pub supports_partial_format: bool,
+  /// If the plugin reports non-fatal diagnostics after formatting a file.
+  /// When true, the CLI will request them after each format.
+  #[serde(default)]
+  pub supports_format_diagnostics: bool,
+  /// If the plugin only changes whitespace when formatting. When true, the CLI
+  /// can verify this with `--verify-whitespace-only` in order to catch bugs
+  /// where the plugin drops or alters code.
+  #[serde(default)]
+  pub only_changes_whitespace: bool,
+  /// If the plugin reports the regions of the file text that changed after formatting
+  /// a file. When true, the CLI will request them after each format that changes the text.
+  #[serde(default)]
+  pub supports_changed_regions: bool,
+}
+
+impl PluginInfo {
+  /// Creates the plugin information with all the capability flags set to false.
+  pub fn new(
+    name: String,
+    version: String,
+    config_key: String,
+    file_extensions: Vec<String>,
+    file_names: Vec<String>,
+    help_url: String,
+    config_schema_url: String,
+  ) -> Self {
+    PluginInfo {
+      name,
+      version,
+      config_key,
+      file_extensions,
+      file_names,
+      help_url,
+      config_schema_url,
+      supports_partial_format: false,
+      supports_format_diagnostics: false,
+      only_changes_whitespace: false,
+      supports_changed_regions: false,
+    }
+  }
 }
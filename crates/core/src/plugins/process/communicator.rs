@@ -1,15 +1,52 @@
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
-
-use super::{FormatResult, HostFormatResult, MessageKind, ResponseKind, StdIoMessenger, StdIoReaderWriter, PLUGIN_SCHEMA_VERSION};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{
+  read_schema_version_response, write_schema_version_request, FormatResult, HostFormatResult, MessageKind, MessagePart, PluginMessage, PluginMessageBody,
+  ResponseKind, StdIoMessenger, StdIoReaderWriter, MIN_PLUGIN_SCHEMA_VERSION, PLUGIN_SCHEMA_VERSION,
+};
 use crate::configuration::{ConfigKeyMap, ConfigurationDiagnostic, GlobalConfiguration};
-use crate::plugins::PluginInfo;
+use crate::plugins::{ChangedRegion, FormatDiagnostic, PluginInfo};
 use crate::types::ErrBox;
 
+/// The amount of time to wait for a process plugin to exit after
+/// being told to close before it's killed.
+const PLUGIN_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// Communicates with a process plugin.
 pub struct ProcessPluginCommunicator {
   child: Child,
-  messenger: StdIoMessenger<ChildStdout, ChildStdin>,
+  reader: ChildStdout,
+  writer: Arc<Mutex<ChildStdin>>,
+  /// The schema version of the plugin, which determines how messages are written.
+  schema_version: u32,
+  last_message_id: u32,
+  /// The id of the format message in progress or 0 when not formatting.
+  format_message_id: Arc<AtomicU32>,
+}
+
+/// Cancels the format in progress from another thread (ex. when the text of
+/// an editor's buffer changed while it was being formatted).
+#[derive(Clone)]
+pub struct ProcessPluginFormatCanceller {
+  writer: Arc<Mutex<ChildStdin>>,
+  format_message_id: Arc<AtomicU32>,
+}
+
+impl ProcessPluginFormatCanceller {
+  /// Tells the plugin to cancel the format in progress, which causes formatting to error
+  /// with a cancellation message. Does nothing when not formatting.
+  pub fn cancel(&self) -> Result<(), ErrBox> {
+    let message_id = self.format_message_id.load(Ordering::SeqCst);
+    if message_id == 0 {
+      return Ok(());
+    }
+    PluginMessage::new(message_id, MessageKind::CancelFormat as u32, Vec::new()).write_to(&mut *self.writer.lock().unwrap())
+  }
 }
 
 impl Drop for ProcessPluginCommunicator {
@@ -64,8 +101,16 @@ impl ProcessPluginCommunicator {
       }
     });
 
-    let messenger = StdIoMessenger::new(StdIoReaderWriter::new(child.stdout.take().unwrap(), child.stdin.take().unwrap()));
-    let mut communicator = ProcessPluginCommunicator { child, messenger };
+    let reader = child.stdout.take().unwrap();
+    let writer = Arc::new(Mutex::new(child.stdin.take().unwrap()));
+    let mut communicator = ProcessPluginCommunicator {
+      child,
+      reader,
+      writer,
+      schema_version: PLUGIN_SCHEMA_VERSION,
+      last_message_id: 0,
+      format_message_id: Arc::new(AtomicU32::new(0)),
+    };
 
     communicator.verify_plugin_schema_version()?;
 
@@ -73,8 +118,22 @@ impl ProcessPluginCommunicator {
   }
 
   fn kill(&mut self) -> Result<(), ErrBox> {
-    // attempt to exit nicely
-    let _ignore = self.messenger.send_message(MessageKind::Close as u32, Vec::new());
+    // attempt to exit nicely, giving the plugin some time to clean up
+    let close_result = if self.is_legacy_schema() {
+      self.with_legacy_messenger(|messenger| messenger.send_message(MessageKind::Close as u32, Vec::new()))
+    } else {
+      let message_id = self.next_message_id();
+      self.send_message(message_id, MessageKind::Close as u32, Vec::new())
+    };
+    if close_result.is_ok() {
+      let start_instant = Instant::now();
+      while start_instant.elapsed() < PLUGIN_SHUTDOWN_TIMEOUT {
+        if self.child.try_wait()?.is_some() {
+          return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+      }
+    }
 
     // now ensure kill
     self.child.kill()?;
@@ -111,48 +170,70 @@ impl ProcessPluginCommunicator {
     Ok(serde_json::from_slice(&bytes)?)
   }
 
+  /// Gets a canceller that may be used from another thread to cancel the format in progress.
+  ///
+  /// Cancelling does nothing for plugins using schema version 3.
+  pub fn create_format_canceller(&self) -> ProcessPluginFormatCanceller {
+    ProcessPluginFormatCanceller {
+      writer: self.writer.clone(),
+      format_message_id: self.format_message_id.clone(),
+    }
+  }
+
   pub fn format_text(
     &mut self,
     file_path: &Path,
     file_text: &str,
     override_config: &ConfigKeyMap,
     format_with_host: impl Fn(PathBuf, String, ConfigKeyMap) -> Result<Option<String>, ErrBox>,
+  ) -> Result<String, ErrBox> {
+    if self.is_legacy_schema() {
+      return self.format_text_legacy(file_path, file_text, override_config, format_with_host);
+    }
+
+    let message_id = self.next_message_id();
+    self.format_message_id.store(message_id, Ordering::SeqCst);
+    let result = self.format_text_with_id(message_id, file_path, file_text, override_config, format_with_host);
+    self.format_message_id.store(0, Ordering::SeqCst);
+    result
+  }
+
+  fn format_text_with_id(
+    &mut self,
+    message_id: u32,
+    file_path: &Path,
+    file_text: &str,
+    override_config: &ConfigKeyMap,
+    format_with_host: impl Fn(PathBuf, String, ConfigKeyMap) -> Result<Option<String>, ErrBox>,
   ) -> Result<String, ErrBox> {
     let override_config = serde_json::to_vec(override_config)?;
     // send message
-    self.messenger.send_message(
+    self.send_message(
+      message_id,
       MessageKind::FormatText as u32,
       vec![file_path.into(), file_text.into(), (&override_config).into()],
     )?;
 
     loop {
-      self.messenger.read_response()?;
-      let format_result = self.messenger.read_code()?;
-      match format_result.into() {
-        FormatResult::NoChange => {
-          self.messenger.read_zero_part_message()?;
-          break Ok(String::from(file_text));
-        }
-        FormatResult::Change => break Ok(self.messenger.read_single_part_string_message()?),
+      let mut body = self.read_response(message_id)?;
+      match body.take_u32()?.into() {
+        FormatResult::NoChange => break Ok(String::from(file_text)),
+        FormatResult::Change => break Ok(body.take_string()?),
         FormatResult::RequestTextFormat => {
-          let mut message_parts = self.messenger.read_multi_part_message(3)?;
-          let file_path = message_parts.take_path_buf()?;
-          let file_text = message_parts.take_string()?;
-          let override_config = serde_json::from_slice(&message_parts.take_part()?)?;
+          let file_path = body.take_path_buf()?;
+          let file_text = body.take_string()?;
+          let override_config = serde_json::from_slice(&body.take_part()?)?;
 
+          // the plugin knows the id of the format message, so it's used for the result
           match format_with_host(file_path, file_text, override_config) {
             Ok(Some(formatted_text)) => {
-              self
-                .messenger
-                .send_message(HostFormatResult::Change as u32, vec![formatted_text.as_str().into()])?;
+              self.send_message(message_id, HostFormatResult::Change as u32, vec![formatted_text.as_str().into()])?;
             }
             Ok(None) => {
-              self.messenger.send_message(HostFormatResult::NoChange as u32, vec![])?;
+              self.send_message(message_id, HostFormatResult::NoChange as u32, vec![])?;
             }
             Err(err) => {
-              self
-                .messenger
-                .send_message(HostFormatResult::Error as u32, vec![err.to_string().as_str().into()])?;
+              self.send_message(message_id, HostFormatResult::Error as u32, vec![err.to_string().as_str().into()])?;
             }
           }
         }
@@ -160,20 +241,93 @@ impl ProcessPluginCommunicator {
     }
   }
 
+  /// Formats the text using the messages of schema version 3, which don't have ids.
+  fn format_text_legacy(
+    &mut self,
+    file_path: &Path,
+    file_text: &str,
+    override_config: &ConfigKeyMap,
+    format_with_host: impl Fn(PathBuf, String, ConfigKeyMap) -> Result<Option<String>, ErrBox>,
+  ) -> Result<String, ErrBox> {
+    let override_config = serde_json::to_vec(override_config)?;
+    self.with_legacy_messenger(|messenger| {
+      messenger.send_message(
+        MessageKind::FormatText as u32,
+        vec![file_path.into(), file_text.into(), (&override_config).into()],
+      )?;
+
+      loop {
+        read_legacy_response(messenger)?;
+        match messenger.read_code()?.into() {
+          FormatResult::NoChange => {
+            messenger.read_zero_part_message()?;
+            break Ok(String::from(file_text));
+          }
+          FormatResult::Change => break Ok(messenger.read_single_part_string_message()?),
+          FormatResult::RequestTextFormat => {
+            let mut message_parts = messenger.read_multi_part_message(3)?;
+            let file_path = message_parts.take_path_buf()?;
+            let file_text = message_parts.take_string()?;
+            let override_config = serde_json::from_slice(&message_parts.take_part()?)?;
+
+            match format_with_host(file_path, file_text, override_config) {
+              Ok(Some(formatted_text)) => messenger.send_message(HostFormatResult::Change as u32, vec![formatted_text.as_str().into()])?,
+              Ok(None) => messenger.send_message(HostFormatResult::NoChange as u32, vec![])?,
+              Err(err) => messenger.send_message(HostFormatResult::Error as u32, vec![err.to_string().as_str().into()])?,
+            }
+          }
+        }
+      }
+    })
+  }
+
+  /// Gets the diagnostics found while formatting the last file.
+  /// Only call this when the plugin info says the plugin supports format diagnostics.
+  pub fn get_format_diagnostics(&mut self) -> Result<Vec<FormatDiagnostic>, ErrBox> {
+    let bytes = self.get_bytes(MessageKind::GetFormatDiagnostics)?;
+    Ok(serde_json::from_slice(&bytes)?)
+  }
+
+  /// Gets the regions of the file text changed when formatting the last file.
+  /// Only call this when the plugin info says the plugin supports changed regions.
+  pub fn get_changed_regions(&mut self) -> Result<Option<Vec<ChangedRegion>>, ErrBox> {
+    let bytes = self.get_bytes(MessageKind::GetChangedRegions)?;
+    Ok(serde_json::from_slice(&bytes)?)
+  }
+
+  /// Formats the text and gets the JSON serialized printer trace.
+  pub fn trace_text(&mut self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox> {
+    if self.is_legacy_schema() {
+      return err!("The plugin uses schema version {}, which does not support tracing.", self.schema_version);
+    }
+    let override_config = serde_json::to_vec(override_config)?;
+    let message_id = self.next_message_id();
+    self.send_message(
+      message_id,
+      MessageKind::TraceText as u32,
+      vec![file_path.into(), file_text.into(), (&override_config).into()],
+    )?;
+    self.read_response(message_id)?.take_string()
+  }
+
   /// Checks if the process is functioning.
   /// Only use this after an error has occurred to tell if the process should be recreated.
   pub fn is_process_alive(&mut self) -> bool {
+    if let Ok(Some(_)) = self.child.try_wait() {
+      return false; // exited
+    }
     let result = self.get_plugin_schema_version();
     if let Ok(plugin_schema_version) = result {
-      plugin_schema_version == PLUGIN_SCHEMA_VERSION
+      plugin_schema_version == self.schema_version
     } else {
       false
     }
   }
 
-  fn get_plugin_schema_version(&mut self) -> Result<u32, ErrBox> {
-    match self.get_u32(MessageKind::GetPluginSchemaVersion) {
-      Ok(response) => Ok(response),
+  fn verify_plugin_schema_version(&mut self) -> Result<(), ErrBox> {
+    // the schema version is negotiated before any messages are sent in the current format
+    let plugin_schema_version = match self.request_plugin_schema_version() {
+      Ok(plugin_schema_version) => plugin_schema_version,
       Err(err) => {
         return err!(
           concat!(
@@ -183,21 +337,20 @@ impl ProcessPluginCommunicator {
           err
         );
       }
-    }
-  }
-
-  fn verify_plugin_schema_version(&mut self) -> Result<(), ErrBox> {
-    let plugin_schema_version = self.get_plugin_schema_version()?;
-    if plugin_schema_version != PLUGIN_SCHEMA_VERSION {
+    };
+    if !(MIN_PLUGIN_SCHEMA_VERSION..=PLUGIN_SCHEMA_VERSION).contains(&plugin_schema_version) {
       return err!(
         concat!(
-          "The plugin schema version was {}, but expected {}. ",
+          "The plugin schema version was {}, but expected {} to {}. ",
           "This may indicate you are using an old version of the dprint CLI or plugin and should upgrade."
         ),
         plugin_schema_version,
+        MIN_PLUGIN_SCHEMA_VERSION,
         PLUGIN_SCHEMA_VERSION
       );
     }
+    // plugins using an older schema version keep using its messages after the handshake
+    self.schema_version = plugin_schema_version;
 
     Ok(())
   }
@@ -207,37 +360,97 @@ impl ProcessPluginCommunicator {
     Ok(String::from_utf8(bytes)?)
   }
 
-  fn get_bytes(&mut self, message_kind: MessageKind) -> Result<Vec<u8>, ErrBox> {
-    self.messenger.send_message(message_kind as u32, Vec::new())?;
-    self.messenger.read_response()?;
-    self.messenger.read_single_part_message()
+  fn request_plugin_schema_version(&mut self) -> Result<u32, ErrBox> {
+    write_schema_version_request(&mut *self.writer.lock().unwrap())?;
+    read_schema_version_response(&mut self.reader)
   }
 
-  fn get_u32(&mut self, message_kind: MessageKind) -> Result<u32, ErrBox> {
-    self.messenger.send_message(message_kind as u32, Vec::new())?;
-    self.messenger.read_response()?;
-    self.messenger.read_single_part_u32_message()
+  fn get_plugin_schema_version(&mut self) -> Result<u32, ErrBox> {
+    if self.is_legacy_schema() {
+      return self.with_legacy_messenger(|messenger| {
+        messenger.send_message(MessageKind::GetPluginSchemaVersion as u32, Vec::new())?;
+        read_legacy_response(messenger)?;
+        messenger.read_single_part_u32_message()
+      });
+    }
+    self.get_response(MessageKind::GetPluginSchemaVersion, Vec::new())?.take_u32()
+  }
+
+  fn get_bytes(&mut self, message_kind: MessageKind) -> Result<Vec<u8>, ErrBox> {
+    if self.is_legacy_schema() {
+      return self.with_legacy_messenger(|messenger| {
+        messenger.send_message(message_kind as u32, Vec::new())?;
+        read_legacy_response(messenger)?;
+        messenger.read_single_part_message()
+      });
+    }
+    self.get_response(message_kind, Vec::new())?.take_part()
   }
 
   fn send_data(&mut self, message_kind: MessageKind, data: &[u8]) -> Result<(), ErrBox> {
-    self.messenger.send_message(message_kind as u32, vec![data.into()])?;
-    self.messenger.read_response()?;
-    self.messenger.read_zero_part_message()
+    if self.is_legacy_schema() {
+      return self.with_legacy_messenger(|messenger| {
+        messenger.send_message(message_kind as u32, vec![data.into()])?;
+        read_legacy_response(messenger)?;
+        messenger.read_zero_part_message()
+      });
+    }
+    self.get_response(message_kind, vec![data.into()])?;
+    Ok(())
   }
-}
 
-trait StdIoMessengerExtensions {
-  fn read_response(&mut self) -> Result<(), ErrBox>;
-}
+  /// Gets if the plugin uses schema version 3, whose messages aren't length prefixed and don't have ids.
+  fn is_legacy_schema(&self) -> bool {
+    self.schema_version < PLUGIN_SCHEMA_VERSION
+  }
 
-impl StdIoMessengerExtensions for StdIoMessenger<ChildStdout, ChildStdin> {
-  fn read_response(&mut self) -> Result<(), ErrBox> {
-    let response_kind = self.read_code()?;
-    match response_kind.into() {
-      ResponseKind::Success => Ok(()),
-      ResponseKind::Error => {
-        err!("{}", self.read_single_part_error_message()?)
-      }
+  fn with_legacy_messenger<T>(
+    &mut self,
+    action: impl FnOnce(&mut StdIoMessenger<&mut ChildStdout, &mut ChildStdin>) -> Result<T, ErrBox>,
+  ) -> Result<T, ErrBox> {
+    let mut writer = self.writer.lock().unwrap();
+    let mut messenger = StdIoMessenger::new(StdIoReaderWriter::new(&mut self.reader, &mut *writer));
+    action(&mut messenger)
+  }
+
+  fn get_response(&mut self, message_kind: MessageKind, message_parts: Vec<MessagePart>) -> Result<PluginMessageBody, ErrBox> {
+    let message_id = self.next_message_id();
+    self.send_message(message_id, message_kind as u32, message_parts)?;
+    self.read_response(message_id)
+  }
+
+  fn send_message(&mut self, message_id: u32, kind: u32, message_parts: Vec<MessagePart>) -> Result<(), ErrBox> {
+    PluginMessage::new(message_id, kind, message_parts).write_to(&mut *self.writer.lock().unwrap())
+  }
+
+  fn read_response(&mut self, message_id: u32) -> Result<PluginMessageBody, ErrBox> {
+    let message = PluginMessage::read_from(&mut self.reader)?;
+    if message.id != message_id {
+      return err!(
+        "Expected a response for message {}, but received a response for message {}.",
+        message_id,
+        message.id
+      );
+    }
+    let response_kind = message.kind.into();
+    let mut body = message.into_body();
+    match response_kind {
+      ResponseKind::Success => Ok(body),
+      ResponseKind::Error => err!("{}", body.take_string()?),
+      ResponseKind::Cancelled => err!("Formatting was cancelled."),
     }
   }
+
+  fn next_message_id(&mut self) -> u32 {
+    // zero is not used as a message id
+    self.last_message_id = self.last_message_id.checked_add(1).unwrap_or(1);
+    self.last_message_id
+  }
+}
+
+fn read_legacy_response(messenger: &mut StdIoMessenger<impl Read, impl Write>) -> Result<(), ErrBox> {
+  match messenger.read_code()?.into() {
+    ResponseKind::Success => Ok(()),
+    ResponseKind::Error | ResponseKind::Cancelled => err!("{}", messenger.read_single_part_error_message()?),
+  }
 }
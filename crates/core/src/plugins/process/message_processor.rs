@@ -1,10 +1,15 @@
 use serde::Serialize;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{Read, Write};
 use std::path::Path;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
 
-use super::{FormatResult, HostFormatResult, MessageKind, MessagePart, ResponseKind, StdIoMessenger, StdIoReaderWriter, PLUGIN_SCHEMA_VERSION};
+use super::{
+  read_schema_version_request, write_schema_version_response, FormatResult, HostFormatResult, MessageKind, MessagePart, PluginMessage, ResponseKind,
+  PLUGIN_SCHEMA_VERSION,
+};
 use crate::configuration::{ConfigKeyMap, GlobalConfiguration, ResolveConfigurationResult};
 use crate::plugins::PluginHandler;
 use crate::types::ErrBox;
@@ -16,11 +21,28 @@ struct MessageProcessorState<TConfiguration: Clone + Serialize> {
 }
 
 /// Handles the process' messages based on the provided handler.
-pub fn handle_process_stdio_messages<THandler: PluginHandler<TConfiguration>, TConfiguration: Clone + Serialize>(mut handler: THandler) -> Result<(), ErrBox> {
-  let stdin = std::io::stdin();
-  let stdout = std::io::stdout();
-  let reader_writer = StdIoReaderWriter::new(stdin, stdout);
-  let mut messenger = StdIoMessenger::new(reader_writer);
+pub fn handle_process_stdio_messages<THandler: PluginHandler<TConfiguration>, TConfiguration: Clone + Serialize>(handler: THandler) -> Result<(), ErrBox> {
+  handle_messages(std::io::stdin(), std::io::stdout(), handler)
+}
+
+/// Handles the messages read from the reader based on the provided handler and writes the responses to the writer.
+///
+/// The messages are read on a separate thread so that a cancellation received while formatting is
+/// known once the handler returns. The handler is not interrupted.
+pub fn handle_messages<TRead: Read + Send + 'static, TWrite: Write, THandler: PluginHandler<TConfiguration>, TConfiguration: Clone + Serialize>(
+  mut reader: TRead,
+  mut writer: TWrite,
+  mut handler: THandler,
+) -> Result<(), ErrBox> {
+  read_schema_version_request(&mut reader)?;
+  write_schema_version_response(&mut writer, PLUGIN_SCHEMA_VERSION)?;
+
+  let cancelled_format_ids = Arc::new(Mutex::new(HashSet::new()));
+  let mut context = MessageContext {
+    writer,
+    receiver: start_reader_thread(reader, cancelled_format_ids.clone()),
+    cancelled_format_ids,
+  };
   let mut state = MessageProcessorState {
     global_config: None,
     config: None,
@@ -28,86 +50,133 @@ pub fn handle_process_stdio_messages<THandler: PluginHandler<TConfiguration>, TC
   };
 
   loop {
-    let message_kind = messenger.read_code()?.into();
+    let message = context.receive()?;
+    let message_id = message.id;
 
-    match handle_message_kind(message_kind, &mut messenger, &mut handler, &mut state) {
-      Err(err) => messenger.send_error_response(&err.to_string())?,
+    match handle_message(message, &mut context, &mut handler, &mut state) {
+      Err(err) => context.send_error_response(message_id, &err.to_string())?,
       Ok(true) => {}
       Ok(false) => return Ok(()),
     }
   }
 }
 
-fn handle_message_kind<TRead: Read, TWrite: Write, TConfiguration: Clone + Serialize, THandler: PluginHandler<TConfiguration>>(
-  message_kind: MessageKind,
-  messenger: &mut StdIoMessenger<TRead, TWrite>,
+fn start_reader_thread<TRead: Read + Send + 'static>(
+  mut reader: TRead,
+  cancelled_format_ids: Arc<Mutex<HashSet<u32>>>,
+) -> Receiver<Result<PluginMessage, ErrBox>> {
+  let (sender, receiver) = channel();
+  std::thread::spawn(move || loop {
+    match PluginMessage::read_from(&mut reader) {
+      Ok(message) => {
+        // cancellations are handled here since the main thread may be busy formatting
+        if message.kind == MessageKind::CancelFormat as u32 {
+          cancelled_format_ids.lock().unwrap().insert(message.id);
+        } else if sender.send(Ok(message)).is_err() {
+          return;
+        }
+      }
+      Err(err) => {
+        let _ = sender.send(Err(err));
+        return;
+      }
+    }
+  });
+  receiver
+}
+
+fn handle_message<TWrite: Write, TConfiguration: Clone + Serialize, THandler: PluginHandler<TConfiguration>>(
+  message: PluginMessage,
+  context: &mut MessageContext<TWrite>,
   handler: &mut THandler,
   state: &mut MessageProcessorState<TConfiguration>,
 ) -> Result<bool, ErrBox> {
+  let message_id = message.id;
+  let message_kind = message.kind.into();
+  let mut body = message.into_body();
+
   match message_kind {
     MessageKind::Close => {
-      messenger.read_zero_part_message()?;
+      handler.shutdown();
       return Ok(false);
     }
-    MessageKind::GetPluginSchemaVersion => {
-      messenger.read_zero_part_message()?;
-      messenger.send_response(vec![PLUGIN_SCHEMA_VERSION.into()])?
-    }
-    MessageKind::GetPluginInfo => {
-      messenger.read_zero_part_message()?;
-      messenger.send_response(vec![serde_json::to_vec(&handler.get_plugin_info())?.into()])?
-    }
-    MessageKind::GetLicenseText => {
-      messenger.read_zero_part_message()?;
-      messenger.send_response(vec![handler.get_license_text().into()])?
-    }
+    MessageKind::GetPluginSchemaVersion => context.send_response(message_id, vec![PLUGIN_SCHEMA_VERSION.into()])?,
+    MessageKind::GetPluginInfo => context.send_response(message_id, vec![serde_json::to_vec(&handler.get_plugin_info())?.into()])?,
+    MessageKind::GetLicenseText => context.send_response(message_id, vec![handler.get_license_text().into()])?,
     MessageKind::SetGlobalConfig => {
-      let message_data = messenger.read_single_part_message()?;
+      let message_data = body.take_part()?;
       state.global_config = Some(serde_json::from_slice(&message_data)?);
       state.resolved_config_result.take();
-      messenger.send_response(Vec::new())?;
+      context.send_response(message_id, Vec::new())?;
     }
     MessageKind::SetPluginConfig => {
-      let message_data = messenger.read_single_part_message()?;
+      let message_data = body.take_part()?;
       let plugin_config = serde_json::from_slice(&message_data)?;
       state.resolved_config_result.take();
       state.config = Some(plugin_config);
-      messenger.send_response(Vec::new())?;
+      context.send_response(message_id, Vec::new())?;
     }
     MessageKind::GetResolvedConfig => {
-      messenger.read_zero_part_message()?;
       ensure_resolved_config(handler, state)?;
       let resolved_config = get_resolved_config_result(state)?;
-      messenger.send_response(vec![serde_json::to_vec(&resolved_config.config)?.into()])?
+      context.send_response(message_id, vec![serde_json::to_vec(&resolved_config.config)?.into()])?
     }
     MessageKind::GetConfigDiagnostics => {
-      messenger.read_zero_part_message()?;
       ensure_resolved_config(handler, state)?;
       let resolved_config = get_resolved_config_result(state)?;
-      messenger.send_response(vec![serde_json::to_vec(&resolved_config.diagnostics)?.into()])?
+      context.send_response(message_id, vec![serde_json::to_vec(&resolved_config.diagnostics)?.into()])?
     }
     MessageKind::FormatText => {
-      let mut parts = messenger.read_multi_part_message(3)?;
       ensure_resolved_config(handler, state)?;
-      let file_path = parts.take_path_buf()?;
-      let file_text = parts.take_string()?;
-      let override_config: ConfigKeyMap = serde_json::from_slice(&parts.take_part()?)?;
+      let file_path = body.take_path_buf()?;
+      let file_text = body.take_string()?;
+      let override_config: ConfigKeyMap = serde_json::from_slice(&body.take_part()?)?;
       let config = if !override_config.is_empty() {
         Cow::Owned(create_resolved_config_result(handler, state, override_config)?.config)
       } else {
         Cow::Borrowed(&get_resolved_config_result(state)?.config)
       };
 
-      let formatted_text = handler.format_text(&file_path, &file_text, &config, |file_path, file_text, override_config| {
-        format_with_host(messenger, file_path, file_text, override_config)
-      })?;
-
-      if formatted_text == file_text {
-        messenger.send_response(vec![(FormatResult::NoChange as u32).into()])?;
+      // the ids of format messages increase, so any lower ids are cancellations that arrived too late
+      context.cancelled_format_ids.lock().unwrap().retain(|id| *id >= message_id);
+      let result = if context.is_format_cancelled(message_id) {
+        None
       } else {
-        messenger.send_response(vec![(FormatResult::Change as u32).into(), formatted_text.into()])?;
+        Some(handler.format_text(&file_path, &file_text, &config, |file_path, file_text, override_config| {
+          format_with_host(context, message_id, file_path, file_text, override_config)
+        }))
+      };
+
+      if context.take_format_cancelled(message_id) {
+        context.send_message(message_id, ResponseKind::Cancelled as u32, Vec::new())?;
+      } else if let Some(result) = result {
+        let formatted_text = result?;
+        if formatted_text == file_text {
+          context.send_response(message_id, vec![(FormatResult::NoChange as u32).into()])?;
+        } else {
+          context.send_response(message_id, vec![(FormatResult::Change as u32).into(), formatted_text.into()])?;
+        }
       }
     }
+    MessageKind::CancelFormat => {
+      // handled when read
+    }
+    MessageKind::GetFormatDiagnostics => context.send_response(message_id, vec![serde_json::to_vec(&handler.take_format_diagnostics())?.into()])?,
+    MessageKind::GetChangedRegions => context.send_response(message_id, vec![serde_json::to_vec(&handler.take_changed_regions())?.into()])?,
+    MessageKind::TraceText => {
+      ensure_resolved_config(handler, state)?;
+      let file_path = body.take_path_buf()?;
+      let file_text = body.take_string()?;
+      let override_config: ConfigKeyMap = serde_json::from_slice(&body.take_part()?)?;
+      let config = if !override_config.is_empty() {
+        Cow::Owned(create_resolved_config_result(handler, state, override_config)?.config)
+      } else {
+        Cow::Borrowed(&get_resolved_config_result(state)?.config)
+      };
+
+      let trace_json = handler.trace_text(&file_path, &file_text, &config)?;
+      context.send_response(message_id, vec![trace_json.into()])?
+    }
   }
 
   Ok(true)
@@ -150,43 +219,167 @@ fn get_resolved_config_result<TConfiguration: Clone + Serialize>(
   )
 }
 
-fn format_with_host<TRead: Read, TWrite: Write>(
-  messenger: &mut StdIoMessenger<TRead, TWrite>,
+fn format_with_host<TWrite: Write>(
+  context: &mut MessageContext<TWrite>,
+  message_id: u32,
   file_path: &Path,
   file_text: String,
   override_config: &ConfigKeyMap,
 ) -> Result<String, ErrBox> {
-  messenger.send_response(vec![
-    (FormatResult::RequestTextFormat as u32).into(),
-    file_path.into(),
-    file_text.as_str().into(),
-    (&serde_json::to_vec(&override_config)?).into(),
-  ])?;
-
-  let format_result = messenger.read_code()?.into();
-  match format_result {
-    HostFormatResult::Change => messenger.read_single_part_string_message(),
-    HostFormatResult::NoChange => {
-      messenger.read_zero_part_message()?; // ensures success is read
-      Ok(file_text)
-    }
-    HostFormatResult::Error => {
-      err!("{}", messenger.read_single_part_error_message()?)
-    }
+  if context.is_format_cancelled(message_id) {
+    return err!("Formatting was cancelled.");
+  }
+
+  context.send_response(
+    message_id,
+    vec![
+      (FormatResult::RequestTextFormat as u32).into(),
+      file_path.into(),
+      file_text.as_str().into(),
+      (&serde_json::to_vec(&override_config)?).into(),
+    ],
+  )?;
+
+  // the host responds with the id of the format message
+  let message = context.receive()?;
+  if message.id != message_id {
+    return err!("Expected a host format result for message {}, but received message {}.", message_id, message.id);
   }
+  let host_format_result = message.kind.into();
+  let mut body = message.into_body();
+  match host_format_result {
+    HostFormatResult::Change => body.take_string(),
+    HostFormatResult::NoChange => Ok(file_text),
+    HostFormatResult::Error => err!("{}", body.take_string()?),
+  }
+}
+
+struct MessageContext<TWrite: Write> {
+  writer: TWrite,
+  receiver: Receiver<Result<PluginMessage, ErrBox>>,
+  /// Ids of the format messages the host cancelled.
+  cancelled_format_ids: Arc<Mutex<HashSet<u32>>>,
 }
 
-trait StdIoMessengerExtensions {
-  fn send_response(&mut self, message_parts: Vec<MessagePart>) -> Result<(), ErrBox>;
-  fn send_error_response(&mut self, error_message: &str) -> Result<(), ErrBox>;
+impl<TWrite: Write> MessageContext<TWrite> {
+  fn receive(&self) -> Result<PluginMessage, ErrBox> {
+    match self.receiver.recv() {
+      Ok(result) => result,
+      Err(_) => err!("Stopped reading messages."),
+    }
+  }
+
+  fn is_format_cancelled(&self, message_id: u32) -> bool {
+    self.cancelled_format_ids.lock().unwrap().contains(&message_id)
+  }
+
+  fn take_format_cancelled(&self, message_id: u32) -> bool {
+    self.cancelled_format_ids.lock().unwrap().remove(&message_id)
+  }
+
+  fn send_response(&mut self, message_id: u32, message_parts: Vec<MessagePart>) -> Result<(), ErrBox> {
+    self.send_message(message_id, ResponseKind::Success as u32, message_parts)
+  }
+
+  fn send_error_response(&mut self, message_id: u32, error_message: &str) -> Result<(), ErrBox> {
+    self.send_message(message_id, ResponseKind::Error as u32, vec![error_message.into()])
+  }
+
+  fn send_message(&mut self, message_id: u32, kind: u32, message_parts: Vec<MessagePart>) -> Result<(), ErrBox> {
+    PluginMessage::new(message_id, kind, message_parts).write_to(&mut self.writer)
+  }
 }
 
-impl<TRead: Read, TWrite: Write> StdIoMessengerExtensions for StdIoMessenger<TRead, TWrite> {
-  fn send_response(&mut self, message_parts: Vec<MessagePart>) -> Result<(), ErrBox> {
-    self.send_message(ResponseKind::Success as u32, message_parts)
+#[cfg(test)]
+mod test {
+  use std::io::Cursor;
+
+  use super::super::{read_schema_version_response, write_schema_version_request};
+  use super::*;
+  use crate::plugins::PluginInfo;
+
+  struct TestPluginHandler {}
+
+  impl PluginHandler<()> for TestPluginHandler {
+    fn resolve_config(&mut self, _: ConfigKeyMap, _: &GlobalConfiguration) -> ResolveConfigurationResult<()> {
+      ResolveConfigurationResult {
+        config: (),
+        diagnostics: Vec::new(),
+      }
+    }
+
+    fn get_plugin_info(&mut self) -> PluginInfo {
+      unimplemented!()
+    }
+
+    fn get_license_text(&mut self) -> String {
+      unimplemented!()
+    }
+
+    fn format_text(
+      &mut self,
+      _: &Path,
+      file_text: &str,
+      _: &(),
+      _: impl FnMut(&Path, String, &ConfigKeyMap) -> Result<String, ErrBox>,
+    ) -> Result<String, ErrBox> {
+      Ok(file_text.to_uppercase())
+    }
   }
 
-  fn send_error_response(&mut self, error_message: &str) -> Result<(), ErrBox> {
-    self.send_message(ResponseKind::Error as u32, vec![error_message.into()])
+  #[test]
+  fn should_respond_cancelled_for_cancelled_format() {
+    let global_config = GlobalConfiguration {
+      line_width: None,
+      use_tabs: None,
+      indent_width: None,
+      new_line_kind: None,
+      trim_leading_blank_lines: None,
+      single_trailing_new_line: None,
+      timeout_ms: None,
+    };
+    let mut input = Vec::new();
+    write_schema_version_request(&mut input).unwrap();
+    let messages = vec![
+      PluginMessage::new(
+        1,
+        MessageKind::SetGlobalConfig as u32,
+        vec![(&serde_json::to_vec(&global_config).unwrap()).into()],
+      ),
+      PluginMessage::new(2, MessageKind::SetPluginConfig as u32, vec![(&b"{}".to_vec()).into()]),
+      PluginMessage::new(3, MessageKind::CancelFormat as u32, Vec::new()),
+      PluginMessage::new(
+        3,
+        MessageKind::FormatText as u32,
+        vec![Path::new("file.txt").into(), "a".into(), (&b"{}".to_vec()).into()],
+      ),
+      PluginMessage::new(
+        4,
+        MessageKind::FormatText as u32,
+        vec![Path::new("file.txt").into(), "b".into(), (&b"{}".to_vec()).into()],
+      ),
+      PluginMessage::new(5, MessageKind::Close as u32, Vec::new()),
+    ];
+    for message in messages {
+      message.write_to(&mut input).unwrap();
+    }
+
+    let mut output = Vec::new();
+    handle_messages(Cursor::new(input), &mut output, TestPluginHandler {}).unwrap();
+
+    let mut output = Cursor::new(output);
+    assert_eq!(read_schema_version_response(&mut output).unwrap(), PLUGIN_SCHEMA_VERSION);
+    let responses = (0..4).map(|_| PluginMessage::read_from(&mut output).unwrap()).collect::<Vec<_>>();
+    let ids_and_kinds = responses.iter().map(|message| (message.id, message.kind)).collect::<Vec<_>>();
+    let success = ResponseKind::Success as u32;
+    assert_eq!(
+      ids_and_kinds,
+      vec![(1, success), (2, success), (3, ResponseKind::Cancelled as u32), (4, success)]
+    );
+
+    let mut body = responses.into_iter().last().unwrap().into_body();
+    assert_eq!(body.take_u32().unwrap(), FormatResult::Change as u32);
+    assert_eq!(body.take_string().unwrap(), "B");
+    assert_eq!(output.position() as usize, output.get_ref().len());
   }
 }
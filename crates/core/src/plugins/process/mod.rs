@@ -2,6 +2,7 @@ mod communicator;
 mod message_processor;
 mod messenger;
 mod parent_process_checker;
+mod plugin_message;
 mod shared_types;
 mod stdio_reader_writer;
 
@@ -9,5 +10,6 @@ pub use communicator::*;
 pub use message_processor::*;
 pub use messenger::*;
 pub use parent_process_checker::*;
-use shared_types::*;
+pub use plugin_message::*;
+pub use shared_types::*;
 pub use stdio_reader_writer::*;
@@ -0,0 +1,163 @@
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use super::MessagePart;
+use crate::types::ErrBox;
+
+/// Bytes written at the end of each message to verify the stream is still in sync.
+const SUCCESS_BYTES: [u8; 4] = [255, 255, 255, 255];
+
+/// A message sent between the CLI and a process plugin (schema version 4).
+///
+/// Messages are length prefixed, which allows either side to send a message while the
+/// other is still sending one (ex. a format being cancelled while the plugin is sending a
+/// request to format some text with the host). A message is written as:
+///
+/// 1. Id (u32) - Requests use a new id and responses use the id of the request.
+/// 2. Kind (u32) - The `MessageKind` of a request or `ResponseKind` of a response.
+/// 3. Body length (u32)
+/// 4. Body - The parts of the message. Numbers are written as a u32 and variable
+///    data as a u32 length followed by the data.
+/// 5. Success bytes (4 bytes of 255)
+///
+/// All numbers are big endian.
+pub struct PluginMessage {
+  pub id: u32,
+  pub kind: u32,
+  body: Vec<u8>,
+}
+
+impl PluginMessage {
+  pub fn new(id: u32, kind: u32, parts: Vec<MessagePart>) -> Self {
+    let mut body = Vec::new();
+    for part in parts {
+      match part {
+        MessagePart::Number(value) => body.extend_from_slice(&value.to_be_bytes()),
+        MessagePart::VariableData(data) => {
+          body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+          body.extend_from_slice(&data);
+        }
+      }
+    }
+    PluginMessage { id, kind, body }
+  }
+
+  /// Reads the next message, blocking until it's received.
+  pub fn read_from(reader: &mut impl Read) -> Result<Self, ErrBox> {
+    let id = read_u32(reader)?;
+    let kind = read_u32(reader)?;
+    let body_len = read_u32(reader)? as usize;
+    let mut body = vec![0; body_len];
+    reader.read_exact(&mut body)?;
+    let mut success_bytes = [0; 4];
+    reader.read_exact(&mut success_bytes)?;
+    if success_bytes != SUCCESS_BYTES {
+      return err!(
+        "Catastrophic error reading from process. Did not receive the success bytes at end of message. Found: {:?}",
+        success_bytes
+      );
+    }
+    Ok(PluginMessage { id, kind, body })
+  }
+
+  /// Writes the message in a single write so messages from multiple threads aren't interleaved
+  /// when the writer is shared.
+  pub fn write_to(&self, writer: &mut impl Write) -> Result<(), ErrBox> {
+    let mut bytes = Vec::with_capacity(self.body.len() + 16);
+    bytes.extend_from_slice(&self.id.to_be_bytes());
+    bytes.extend_from_slice(&self.kind.to_be_bytes());
+    bytes.extend_from_slice(&(self.body.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&self.body);
+    bytes.extend_from_slice(&SUCCESS_BYTES);
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+    Ok(())
+  }
+
+  /// Gets a reader for taking the parts of the body in the order they were written.
+  pub fn into_body(self) -> PluginMessageBody {
+    PluginMessageBody { bytes: self.body, index: 0 }
+  }
+}
+
+/// The parts of a message's body.
+pub struct PluginMessageBody {
+  bytes: Vec<u8>,
+  index: usize,
+}
+
+impl PluginMessageBody {
+  pub fn take_u32(&mut self) -> Result<u32, ErrBox> {
+    let bytes = self.take_bytes(4)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+  }
+
+  pub fn take_part(&mut self) -> Result<Vec<u8>, ErrBox> {
+    let len = self.take_u32()? as usize;
+    Ok(self.take_bytes(len)?.to_vec())
+  }
+
+  pub fn take_string(&mut self) -> Result<String, ErrBox> {
+    Ok(String::from_utf8(self.take_part()?)?)
+  }
+
+  pub fn take_path_buf(&mut self) -> Result<PathBuf, ErrBox> {
+    Ok(PathBuf::from(self.take_string()?))
+  }
+
+  fn take_bytes(&mut self, len: usize) -> Result<&[u8], ErrBox> {
+    let start = self.index;
+    if start + len > self.bytes.len() {
+      return err!("Programming error: Expected to take message part.");
+    }
+    self.index += len;
+    Ok(&self.bytes[start..self.index])
+  }
+}
+
+/// The schema version is checked with the version 3 framing (a u32 message kind followed by the
+/// success bytes) so that plugins and CLIs using an older schema version can say they need to be upgraded.
+pub fn write_schema_version_request(writer: &mut impl Write) -> Result<(), ErrBox> {
+  writer.write_all(&0u32.to_be_bytes())?;
+  writer.write_all(&SUCCESS_BYTES)?;
+  writer.flush()?;
+  Ok(())
+}
+
+/// Reads the schema version request sent with the version 3 framing. The message kind must
+/// be `MessageKind::GetPluginSchemaVersion`.
+pub fn read_schema_version_request(reader: &mut impl Read) -> Result<(), ErrBox> {
+  let kind = read_u32(reader)?;
+  read_u32(reader)?; // success bytes
+  if kind != 0 {
+    return err!("Expected the schema version to be requested first, but received message kind {}.", kind);
+  }
+  Ok(())
+}
+
+/// Responds with the schema version using the version 3 framing (success response kind,
+/// then the version, then the success bytes).
+pub fn write_schema_version_response(writer: &mut impl Write, schema_version: u32) -> Result<(), ErrBox> {
+  writer.write_all(&0u32.to_be_bytes())?;
+  writer.write_all(&schema_version.to_be_bytes())?;
+  writer.write_all(&SUCCESS_BYTES)?;
+  writer.flush()?;
+  Ok(())
+}
+
+/// Reads the schema version from a response sent with the version 3 framing.
+pub fn read_schema_version_response(reader: &mut impl Read) -> Result<u32, ErrBox> {
+  let response_kind = read_u32(reader)?;
+  if response_kind != 0 {
+    return err!("Error response when getting the plugin schema version.");
+  }
+  let schema_version = read_u32(reader)?;
+  read_u32(reader)?; // success bytes
+  Ok(schema_version)
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, ErrBox> {
+  let mut bytes = [0; 4];
+  reader.read_exact(&mut bytes)?;
+  Ok(u32::from_be_bytes(bytes))
+}
@@ -2,7 +2,10 @@ use std::borrow::Cow;
 use std::path::Path;
 
 /// The process plugin schema version.
-pub const PLUGIN_SCHEMA_VERSION: u32 = 3;
+pub const PLUGIN_SCHEMA_VERSION: u32 = 4;
+/// The oldest process plugin schema version the CLI communicates with. Messages
+/// to these plugins are sent without ids, so their formatting can't be cancelled.
+pub const MIN_PLUGIN_SCHEMA_VERSION: u32 = 3;
 
 /// Kinds of messages that process plugins must handle.
 #[derive(Debug)]
@@ -16,7 +19,18 @@ pub enum MessageKind {
   GetConfigDiagnostics = 6,
   /// Returns a format result part, then a file text part.
   FormatText = 7,
+  /// Tells the plugin to shut down. The plugin should exit shortly after.
   Close = 8,
+  /// Gets the diagnostics found while formatting the last file.
+  GetFormatDiagnostics = 9,
+  /// Returns the JSON serialized printer trace of formatting a file.
+  TraceText = 10,
+  /// Cancels formatting the file of the `FormatText` message with the same id. The
+  /// plugin responds to that message with `ResponseKind::Cancelled` instead of the
+  /// formatted text. No response is sent for this message.
+  CancelFormat = 11,
+  /// Gets the regions of the file text changed when formatting the last file.
+  GetChangedRegions = 12,
 }
 
 // todo: generate with a macro
@@ -32,6 +46,10 @@ impl From<u32> for MessageKind {
       6 => MessageKind::GetConfigDiagnostics,
       7 => MessageKind::FormatText,
       8 => MessageKind::Close,
+      9 => MessageKind::GetFormatDiagnostics,
+      10 => MessageKind::TraceText,
+      11 => MessageKind::CancelFormat,
+      12 => MessageKind::GetChangedRegions,
       _ => unreachable!("Unexpected message kind: {}", kind),
     }
   }
@@ -42,6 +60,8 @@ impl From<u32> for MessageKind {
 pub enum ResponseKind {
   Success = 0,
   Error = 1,
+  /// The request was cancelled before it finished.
+  Cancelled = 2,
 }
 
 // todo: generate with a macro
@@ -50,6 +70,7 @@ impl From<u32> for ResponseKind {
     match orig {
       0 => ResponseKind::Success,
       1 => ResponseKind::Error,
+      2 => ResponseKind::Cancelled,
       _ => unreachable!("Unexpected response kind: {}", orig),
     }
   }
@@ -175,6 +175,51 @@ pub mod macros {
         set_shared_bytes_str(error_text)
       }
 
+      #[no_mangle]
+      pub fn get_format_diagnostics() -> usize {
+        let diagnostics = unsafe { WASM_PLUGIN.get().take_format_diagnostics() };
+        set_shared_bytes(serde_json::to_vec(&diagnostics).unwrap())
+      }
+
+      #[no_mangle]
+      pub fn get_changed_regions() -> usize {
+        let changed_regions = unsafe { WASM_PLUGIN.get().take_changed_regions() };
+        set_shared_bytes(serde_json::to_vec(&changed_regions).unwrap())
+      }
+
+      #[no_mangle]
+      pub fn trace() -> u8 {
+        ensure_initialized();
+        let config = unsafe {
+          if let Some(override_config) = OVERRIDE_CONFIG.get().take() {
+            std::borrow::Cow::Owned(create_resolved_config_result(override_config).config)
+          } else {
+            std::borrow::Cow::Borrowed(&get_resolved_config_result().config)
+          }
+        };
+        let file_path = unsafe { FILE_PATH.get().take().expect("Expected the file path to be set.") };
+        let file_text = take_string_from_shared_bytes();
+
+        // the trace is retrieved with get_formatted_text
+        match unsafe { WASM_PLUGIN.get().trace_text(&file_path, &file_text, &config) } {
+          Ok(trace_json) => {
+            unsafe { FORMATTED_TEXT.get().replace(trace_json) };
+            1
+          }
+          Err(err_text) => {
+            unsafe { ERROR_TEXT.get().replace(err_text.to_string()) };
+            2 // error
+          }
+        }
+      }
+
+      // SHUTDOWN
+
+      #[no_mangle]
+      pub fn shutdown() {
+        unsafe { WASM_PLUGIN.get().shutdown() };
+      }
+
       // INFORMATION & CONFIGURATION
 
       static RESOLVE_CONFIGURATION_RESULT: StaticCell<Option<dprint_core::configuration::ResolveConfigurationResult<Configuration>>> = StaticCell::new(None);
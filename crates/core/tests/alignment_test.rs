@@ -0,0 +1,68 @@
+extern crate dprint_core;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_align_with_spaces_when_using_smart_tabs() {
+  assert_eq!(format(get_print_items, get_print_options_with_tabs(true, true)), "x\n\tcall(a,\n\t     b)");
+}
+
+#[test]
+fn it_should_align_with_tabs_then_spaces_when_not_using_smart_tabs() {
+  assert_eq!(format(get_print_items, get_print_options_with_tabs(true, false)), "x\n\tcall(a,\n\t\t\t b)");
+}
+
+#[test]
+fn it_should_align_with_spaces_when_not_using_tabs() {
+  assert_eq!(format(get_print_items, get_print_options_with_tabs(false, false)), "x\n  call(a,\n       b)");
+}
+
+#[test]
+fn it_should_use_alignment_for_line_start_column() {
+  let text = format(
+    || {
+      let mut items = PrintItems::new();
+      items.push_str("call(");
+      items.push_signal(Signal::StartAlignment);
+      items.push_str("a,");
+      items.push_signal(Signal::NewLine);
+      items.push_condition(conditions::if_true(
+        "isStartOfLine",
+        |context| Some(context.writer_info.is_start_of_line()),
+        "b".into(),
+      ));
+      items.push_signal(Signal::FinishAlignment);
+      items.push_str(")");
+      items
+    },
+    get_print_options_with_tabs(true, true),
+  );
+
+  assert_eq!(text, "call(a,\n     b)");
+}
+
+fn get_print_items() -> PrintItems {
+  let mut items = PrintItems::new();
+  items.push_str("x");
+  items.push_signal(Signal::NewLine);
+  items.push_signal(Signal::StartIndent);
+  items.push_str("call(");
+  items.push_signal(Signal::StartAlignment);
+  items.push_str("a,");
+  items.push_signal(Signal::NewLine);
+  items.push_str("b");
+  items.push_signal(Signal::FinishAlignment);
+  items.push_str(")");
+  items.push_signal(Signal::FinishIndent);
+  items
+}
+
+fn get_print_options_with_tabs(use_tabs: bool, smart_tabs: bool) -> PrintOptions {
+  let mut options = get_print_options(40);
+  options.use_tabs = use_tabs;
+  options.smart_tabs = smart_tabs;
+  options
+}
@@ -0,0 +1,114 @@
+extern crate dprint_core;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_align_to_max_column_of_anchor() {
+  let text = format(
+    || {
+      let anchor = Anchor::new("values");
+      let mut items = PrintItems::new();
+      items.extend(get_assignment_items("a", "1", anchor));
+      items.push_signal(Signal::NewLine);
+      items.extend(get_assignment_items("bbb", "2", anchor));
+      items.push_signal(Signal::NewLine);
+      items.extend(get_assignment_items("cc", "3", anchor));
+      items
+    },
+    get_print_options(40),
+  );
+  assert_eq!(text, "a   = 1\nbbb = 2\ncc  = 3");
+}
+
+#[test]
+fn it_should_align_anchors_separately() {
+  let text = format(
+    || {
+      let first_anchor = Anchor::new("first");
+      let second_anchor = Anchor::new("second");
+      let mut items = PrintItems::new();
+      items.extend(get_assignment_items("a", "1", first_anchor));
+      items.push_signal(Signal::NewLine);
+      items.extend(get_assignment_items("bb", "2", first_anchor));
+      items.push_signal(Signal::NewLine);
+      items.push_signal(Signal::NewLine);
+      items.extend(get_assignment_items("c", "3", second_anchor));
+      items.push_signal(Signal::NewLine);
+      items.extend(get_assignment_items("dddd", "4", second_anchor));
+      items
+    },
+    get_print_options(40),
+  );
+  assert_eq!(text, "a  = 1\nbb = 2\n\nc    = 3\ndddd = 4");
+}
+
+#[test]
+fn it_should_align_within_indentation() {
+  let text = format(
+    || {
+      let anchor = Anchor::new("comments");
+      let mut items = PrintItems::new();
+      items.push_signal(Signal::StartIndent);
+      items.push_str("call();");
+      items.push_align(anchor);
+      items.push_str(" // a");
+      items.push_signal(Signal::NewLine);
+      items.push_str("x;");
+      items.push_align(anchor);
+      items.push_str(" // b");
+      items.push_signal(Signal::FinishIndent);
+      items
+    },
+    get_print_options(40),
+  );
+  assert_eq!(text, "  call(); // a\n  x;      // b");
+}
+
+#[test]
+fn it_should_realign_when_earlier_content_changes() {
+  let is_long = Rc::new(Cell::new(false));
+  let text = format(
+    || {
+      let anchor = Anchor::new("values");
+      let end_info = Info::new("end");
+      let mut items = PrintItems::new();
+      // resolves to a longer name once the end info is known, which changes the column of the first align point
+      items.push_condition(conditions::if_true_or(
+        "longNameWhenEndKnown",
+        {
+          let is_long = is_long.clone();
+          move |context| {
+            let is_end_known = context.get_resolved_info(&end_info).is_some();
+            is_long.set(is_end_known);
+            Some(is_end_known)
+          }
+        },
+        "aaaaa".into(),
+        "a".into(),
+      ));
+      items.push_align(anchor);
+      items.push_str(" = 1");
+      items.push_signal(Signal::NewLine);
+      items.extend(get_assignment_items("bb", "2", anchor));
+      items.push_info(end_info);
+      items
+    },
+    get_print_options(40),
+  );
+  assert!(is_long.get());
+  assert_eq!(text, "aaaaa = 1\nbb    = 2");
+}
+
+fn get_assignment_items(name: &str, value: &str, anchor: Anchor) -> PrintItems {
+  let mut items = PrintItems::new();
+  items.push_str(name);
+  items.push_align(anchor);
+  items.push_str(&format!(" = {}", value));
+  items
+}
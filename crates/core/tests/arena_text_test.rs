@@ -0,0 +1,45 @@
+extern crate dprint_core;
+
+use dprint_core::formatting::parser_helpers::parse_arena_string;
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_print_arena_text_slices() {
+  let file_text = "let a = 5;\n\tlet b = 6;\n";
+  let text = format(
+    || {
+      // the arena text is only used within this closure
+      let arena_text = unsafe { ArenaText::new(file_text) };
+      let mut items = PrintItems::new();
+      items.push_arena_text(arena_text.slice(0..3));
+      items.push_str(" ");
+      items.push_arena_text_with_char_count(arena_text.slice(4..5), 1);
+      items.push_signal(Signal::NewLine);
+      items.extend(parse_arena_string(arena_text));
+      items
+    },
+    get_print_options(10),
+  );
+
+  assert_eq!(text, "let a\nlet a = 5;\n\tlet b = 6;\n");
+}
+
+#[test]
+fn it_should_use_arena_text_char_count_for_line_width() {
+  let text = format(
+    || {
+      let arena_text = unsafe { ArenaText::new("aaaaé bbbbé") };
+      let mut items = PrintItems::new();
+      items.push_arena_text_with_char_count(arena_text.slice(0..6), 5);
+      items.push_signal(Signal::SpaceOrNewLine);
+      items.push_arena_text_with_char_count(arena_text.slice(7..13), 5);
+      items
+    },
+    get_print_options(10),
+  );
+
+  assert_eq!(text, "aaaaé\nbbbbé");
+}
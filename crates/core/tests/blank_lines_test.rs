@@ -0,0 +1,47 @@
+extern crate dprint_core;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_force_new_line_only_when_line_not_blank() {
+  let text = format(
+    || {
+      let mut items = PrintItems::new();
+      items.push_signal(Signal::ForceNewLineIfNotBlank);
+      items.push_str("a");
+      items.push_signal(Signal::ForceNewLineIfNotBlank);
+      items.push_signal(Signal::ForceNewLineIfNotBlank);
+      items.push_str("b");
+      items
+    },
+    get_print_options_with_max_blank_lines(None),
+  );
+
+  assert_eq!(text, "a\nb");
+}
+
+#[test]
+fn it_should_squash_blank_lines_to_max() {
+  let get_print_items = || {
+    let mut items = PrintItems::new();
+    items.push_str("a");
+    for _ in 0..4 {
+      items.push_signal(Signal::NewLine);
+    }
+    items.push_str("b");
+    items
+  };
+
+  assert_eq!(format(get_print_items, get_print_options_with_max_blank_lines(None)), "a\n\n\n\nb");
+  assert_eq!(format(get_print_items, get_print_options_with_max_blank_lines(Some(1))), "a\n\nb");
+  assert_eq!(format(get_print_items, get_print_options_with_max_blank_lines(Some(0))), "a\nb");
+}
+
+fn get_print_options_with_max_blank_lines(max_blank_lines: Option<u32>) -> PrintOptions {
+  let mut options = get_print_options(40);
+  options.max_blank_lines = max_blank_lines;
+  options
+}
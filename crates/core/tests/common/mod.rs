@@ -0,0 +1,6 @@
+use dprint_core::formatting::PrintOptions;
+
+/// Gets the options for printing with the provided max width, an indent width of 2, and spaces.
+pub fn get_print_options(max_width: u32) -> PrintOptions {
+  PrintOptions::new(max_width, 2, false, "\n")
+}
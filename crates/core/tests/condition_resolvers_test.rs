@@ -0,0 +1,107 @@
+extern crate dprint_core;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_get_line_width_remaining() {
+  let results = Rc::new(RefCell::new(Vec::new()));
+  format(
+    || {
+      let mut items = PrintItems::new();
+      items.push_condition(create_recording_condition(results.clone(), condition_resolvers::line_width_remaining));
+      items.push_str("aaaa");
+      items.push_condition(create_recording_condition(results.clone(), condition_resolvers::line_width_remaining));
+      items.push_str("bbbbbbb");
+      items.push_condition(create_recording_condition(results.clone(), condition_resolvers::line_width_remaining));
+      items
+    },
+    get_print_options(10),
+  );
+
+  assert_eq!(*results.borrow(), vec![10, 6, 0]);
+}
+
+#[test]
+fn it_should_get_if_at_start_of_line_indented() {
+  let results = Rc::new(RefCell::new(Vec::new()));
+  format(
+    || {
+      let mut items = PrintItems::new();
+      items.push_condition(create_recording_condition(results.clone(), condition_resolvers::is_at_start_of_line_indented));
+      items.push_str("{");
+      items.push_signal(Signal::NewLine);
+      items.extend(parser_helpers::with_indent({
+        let mut items = PrintItems::new();
+        items.push_condition(create_recording_condition(results.clone(), condition_resolvers::is_at_start_of_line_indented));
+        items.push_str("a");
+        items.push_condition(create_recording_condition(results.clone(), condition_resolvers::is_at_start_of_line_indented));
+        items
+      }));
+      items.push_signal(Signal::NewLine);
+      items.push_str("}");
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(*results.borrow(), vec![false, true, false]);
+}
+
+#[test]
+fn it_should_fill_lines_using_line_width_remaining() {
+  fn get_text(item_texts: &'static [&'static str], max_width: u32) -> String {
+    format(
+      || {
+        let mut items = PrintItems::new();
+        items.push_str("[");
+        items.extend(parser_helpers::with_indent({
+          let mut items = PrintItems::new();
+          for (i, item_text) in item_texts.iter().enumerate() {
+            if i > 0 {
+              items.push_str(",");
+              // the separating space and the item must fit on the line
+              let item_width = item_text.len() as u32 + 1;
+              items.push_condition(Condition::new(
+                "fitsOnLine",
+                ConditionProperties {
+                  condition: Rc::new(move |context| Some(condition_resolvers::line_width_remaining(context) >= item_width)),
+                  true_path: Some(" ".into()),
+                  false_path: Some(Signal::NewLine.into()),
+                },
+              ));
+            }
+            items.push_str(item_text);
+          }
+          items
+        }));
+        items.push_str("]");
+        items
+      },
+      get_print_options(max_width),
+    )
+  }
+
+  let item_texts = &["aaa", "bbb", "ccc", "ddd", "eee"];
+  assert_eq!(get_text(item_texts, 40), "[aaa, bbb, ccc, ddd, eee]");
+  assert_eq!(get_text(item_texts, 14), "[aaa, bbb, ccc,\n  ddd, eee]");
+}
+
+fn create_recording_condition<T: 'static>(results: Rc<RefCell<Vec<T>>>, get_value: impl Fn(&ConditionResolverContext) -> T + 'static) -> Condition {
+  Condition::new(
+    "recordValue",
+    ConditionProperties {
+      condition: Rc::new(move |context| {
+        results.borrow_mut().push(get_value(context));
+        None
+      }),
+      true_path: None,
+      false_path: None,
+    },
+  )
+}
@@ -0,0 +1,75 @@
+extern crate dprint_core;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_keep_items_on_one_line_when_they_fit() {
+  assert_eq!(format_fill(&["aaa", "bbb", "ccc"], ",", 40), "[aaa, bbb, ccc]");
+}
+
+#[test]
+fn it_should_fill_each_line_with_as_many_items_as_fit() {
+  let item_texts = &["aaa", "bbb", "ccc", "ddd", "eee", "fff", "ggg"];
+  assert_eq!(format_fill(item_texts, ",", 15), "[aaa, bbb, ccc,\n  ddd, eee,\n  fff, ggg]");
+  assert_eq!(format_fill(item_texts, "", 10), "[aaa bbb\n  ccc ddd\n  eee fff\n  ggg]");
+}
+
+#[test]
+fn it_should_break_between_items_before_within_an_item() {
+  let text = format(
+    || {
+      let mut items = Vec::new();
+      items.push("aaa".into());
+      items.push(get_call_items("bbb", "ccc"));
+      items.push("ddd".into());
+      parser_helpers::parse_fill(items, ",".into())
+    },
+    get_print_options(18),
+  );
+
+  assert_eq!(text, "aaa,\ncall(bbb, ccc),\nddd");
+}
+
+#[test]
+fn it_should_break_within_an_item_that_does_not_fit_on_its_own_line() {
+  let text = format(
+    || {
+      let mut items = Vec::new();
+      items.push("aaa".into());
+      items.push(get_call_items("bbbbbbbbbb", "cccccccccc"));
+      parser_helpers::parse_fill(items, ",".into())
+    },
+    get_print_options(20),
+  );
+
+  assert_eq!(text, "aaa,\ncall(bbbbbbbbbb,\ncccccccccc)");
+}
+
+fn format_fill(item_texts: &'static [&'static str], separator: &'static str, max_width: u32) -> String {
+  format(
+    || {
+      let mut items = PrintItems::new();
+      items.push_str("[");
+      items.extend(parser_helpers::with_indent(parser_helpers::parse_fill(
+        item_texts.iter().map(|text| (*text).into()).collect(),
+        separator.into(),
+      )));
+      items.push_str("]");
+      items
+    },
+    get_print_options(max_width),
+  )
+}
+
+fn get_call_items(arg1: &str, arg2: &str) -> PrintItems {
+  let mut items = PrintItems::new();
+  items.push_str("call(");
+  items.push_str(&format!("{},", arg1));
+  items.push_signal(Signal::SpaceOrNewLine);
+  items.push_str(arg2);
+  items.push_str(")");
+  items
+}
@@ -0,0 +1,103 @@
+extern crate dprint_core;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_not_expand_group_on_single_line() {
+  assert_eq!(format(|| get_array_items(&["a", "b", "c"]), get_print_options(40)), "[a, b, c]");
+}
+
+#[test]
+fn it_should_expand_group_when_an_element_wraps() {
+  assert_eq!(
+    format(|| get_array_items(&["aaaaaa", "bbbbbb", "cccccc"]), get_print_options(20)),
+    "[\n  aaaaaa,\n  bbbbbb,\n  cccccc\n]"
+  );
+}
+
+#[test]
+fn it_should_expand_group_with_new_line() {
+  let text = format(
+    || {
+      let mut group = PrintItems::new();
+      group.push_str("a,");
+      group.push_signal(Signal::SpaceOrNewLine);
+      group.push_str("b");
+      group.push_signal(Signal::NewLine);
+      group.push_str("c,");
+      group.push_signal(Signal::SpaceOrNewLine);
+      group.push_str("d");
+      parser_helpers::with_force_expand_if_broken(group)
+    },
+    get_print_options(40),
+  );
+  assert_eq!(text, "a,\nb\nc,\nd");
+}
+
+#[test]
+fn it_should_not_expand_nested_new_line_groups() {
+  let text = format(
+    || {
+      let mut inner_group = PrintItems::new();
+      inner_group.push_str("x,");
+      inner_group.push_signal(Signal::SpaceOrNewLine);
+      inner_group.push_str("y");
+
+      let mut group = PrintItems::new();
+      group.push_str("a,");
+      group.push_signal(Signal::SpaceOrNewLine);
+      group.push_str("call(");
+      group.extend(parser_helpers::new_line_group(inner_group));
+      group.push_str(")");
+      group.push_signal(Signal::NewLine);
+      group.push_str("b");
+      parser_helpers::with_force_expand_if_broken(group)
+    },
+    get_print_options(40),
+  );
+  assert_eq!(text, "a,\ncall(x, y)\nb");
+}
+
+#[test]
+fn it_should_only_expand_broken_groups() {
+  let text = format(
+    || {
+      let mut items = get_array_items(&["a", "b"]);
+      items.push_signal(Signal::NewLine);
+      items.extend(get_array_items(&["aaaaaa", "bbbbbb", "cccccc"]));
+      items
+    },
+    get_print_options(20),
+  );
+  assert_eq!(text, "[a, b]\n[\n  aaaaaa,\n  bbbbbb,\n  cccccc\n]");
+}
+
+#[test]
+fn it_should_not_expand_when_forcing_no_new_lines() {
+  let text = format(
+    || parser_helpers::with_no_new_lines(get_array_items(&["aaaaaa", "bbbbbb", "cccccc"])),
+    get_print_options(20),
+  );
+  assert_eq!(text, "[aaaaaa, bbbbbb, cccccc]");
+}
+
+fn get_array_items(elements: &[&str]) -> PrintItems {
+  let mut group = PrintItems::new();
+  group.push_str("[");
+  group.push_signal(Signal::PossibleNewLine);
+  group.push_signal(Signal::StartIndent);
+  for (i, element) in elements.iter().enumerate() {
+    if i > 0 {
+      group.push_str(",");
+      group.push_signal(Signal::SpaceOrNewLine);
+    }
+    group.push_str(element);
+  }
+  group.push_signal(Signal::FinishIndent);
+  group.push_signal(Signal::PossibleNewLine);
+  group.push_str("]");
+  parser_helpers::with_force_expand_if_broken(group)
+}
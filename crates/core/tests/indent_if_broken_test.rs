@@ -0,0 +1,95 @@
+extern crate dprint_core;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_not_indent_group_on_single_line() {
+  assert_eq!(format(|| get_call_items("x = ", "a", "b"), get_print_options(40)), "x = call(a, b)");
+}
+
+#[test]
+fn it_should_indent_group_that_exceeds_line_width() {
+  assert_eq!(
+    format(|| get_call_items("x = ", "aaaaaaaaaa", "bbbbbbbbbb"), get_print_options(20)),
+    "x = call(aaaaaaaaaa,\n  bbbbbbbbbb)"
+  );
+}
+
+#[test]
+fn it_should_indent_group_with_new_line() {
+  let text = format(
+    || {
+      let mut group = PrintItems::new();
+      group.push_str("a");
+      group.push_signal(Signal::NewLine);
+      group.push_str("b");
+
+      let mut items = PrintItems::new();
+      items.push_str("x = ");
+      items.extend(parser_helpers::with_indent_if_broken(group));
+      items.push_signal(Signal::NewLine);
+      items.push_str("y");
+      items
+    },
+    get_print_options(40),
+  );
+  assert_eq!(text, "x = a\n  b\ny");
+}
+
+#[test]
+fn it_should_only_indent_broken_groups() {
+  let text = format(
+    || {
+      let mut items = get_call_items("x = ", "aaaaaaaaaa", "bbbbbbbbbb");
+      items.push_signal(Signal::NewLine);
+      items.extend(get_call_items("y = ", "a", "b"));
+      items
+    },
+    get_print_options(20),
+  );
+  assert_eq!(text, "x = call(aaaaaaaaaa,\n  bbbbbbbbbb)\ny = call(a, b)");
+}
+
+#[test]
+fn it_should_indent_nested_broken_groups() {
+  let text = format(
+    || {
+      let mut inner_group = PrintItems::new();
+      inner_group.push_str("b");
+      inner_group.push_signal(Signal::NewLine);
+      inner_group.push_str("c");
+
+      let mut outer_group = PrintItems::new();
+      outer_group.push_str("a");
+      outer_group.push_signal(Signal::NewLine);
+      outer_group.push_str("y = ");
+      outer_group.extend(parser_helpers::with_indent_if_broken(inner_group));
+      outer_group.push_signal(Signal::NewLine);
+      outer_group.push_str("d");
+
+      let mut items = PrintItems::new();
+      items.push_str("x = ");
+      items.extend(parser_helpers::with_indent_if_broken(outer_group));
+      items
+    },
+    get_print_options(40),
+  );
+  assert_eq!(text, "x = a\n  y = b\n    c\n  d");
+}
+
+fn get_call_items(prefix: &str, arg1: &str, arg2: &str) -> PrintItems {
+  let mut group = PrintItems::new();
+  group.push_str("call(");
+  group.push_str(&format!("{},", arg1));
+  group.push_signal(Signal::SpaceOrNewLine);
+  group.push_str(arg2);
+  group.push_str(")");
+
+  let mut items = PrintItems::new();
+  items.push_str(prefix);
+  items.extend(parser_helpers::with_indent_if_broken(group));
+  items
+}
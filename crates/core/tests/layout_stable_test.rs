@@ -0,0 +1,64 @@
+extern crate dprint_core;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_be_stable_when_info_does_not_move() {
+  let results = Rc::new(RefCell::new(Vec::new()));
+  let text = format(
+    || {
+      let info = Info::new("start");
+      let mut items = PrintItems::new();
+      items.push_str("a");
+      items.push_info(info);
+      items.push_condition(create_recording_condition(info, results.clone()));
+      items.push_str("b");
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(text, "ab");
+  assert_eq!(*results.borrow(), vec![Some(true)]);
+}
+
+#[test]
+fn it_should_be_none_when_info_not_resolved() {
+  let results = Rc::new(RefCell::new(Vec::new()));
+  let text = format(
+    || {
+      let info = Info::new("end");
+      let mut items = PrintItems::new();
+      items.push_str("a");
+      items.push_condition(create_recording_condition(info, results.clone()));
+      items.push_str("b");
+      items.push_info(info);
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(text, "ab");
+  // the printer will go back and re-evaluate the condition once the info is resolved
+  assert_eq!(*results.borrow(), vec![None, Some(true)]);
+}
+
+fn create_recording_condition(info: Info, results: Rc<RefCell<Vec<Option<bool>>>>) -> Condition {
+  Condition::new(
+    "recordIsLayoutStable",
+    ConditionProperties {
+      condition: Rc::new(move |context| {
+        results.borrow_mut().push(context.is_layout_stable(&info));
+        None
+      }),
+      true_path: None,
+      false_path: None,
+    },
+  )
+}
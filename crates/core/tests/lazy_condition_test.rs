@@ -0,0 +1,78 @@
+extern crate dprint_core;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_only_create_taken_paths() {
+  let created_paths = Rc::new(RefCell::new(Vec::new()));
+  let text = format(
+    || {
+      let mut items = PrintItems::new();
+      items.push_condition(create_recording_condition(Some(true), created_paths.clone()));
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(text, "true");
+  assert_eq!(*created_paths.borrow(), vec!["true"]);
+}
+
+#[test]
+fn it_should_create_each_path_once_when_reevaluated() {
+  let created_paths = Rc::new(RefCell::new(Vec::new()));
+  let text = format(
+    || {
+      let end_info = Info::new("end");
+      let mut items = PrintItems::new();
+      items.push_condition(conditions::if_true_or_lazy(
+        "isEndResolved",
+        move |context| context.get_resolved_info(&end_info).map(|_| true),
+        {
+          let created_paths = created_paths.clone();
+          move || {
+            created_paths.borrow_mut().push("true");
+            "true".into()
+          }
+        },
+        {
+          let created_paths = created_paths.clone();
+          move || {
+            created_paths.borrow_mut().push("false");
+            "false".into()
+          }
+        },
+      ));
+      items.push_info(end_info);
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(text, "true");
+  assert_eq!(*created_paths.borrow(), vec!["false", "true"]);
+}
+
+fn create_recording_condition(value: Option<bool>, created_paths: Rc<RefCell<Vec<&'static str>>>) -> Condition {
+  conditions::if_true_or_lazy(
+    "recordCreatedPaths",
+    move |_| value,
+    {
+      let created_paths = created_paths.clone();
+      move || {
+        created_paths.borrow_mut().push("true");
+        "true".into()
+      }
+    },
+    move || {
+      created_paths.borrow_mut().push("false");
+      "false".into()
+    },
+  )
+}
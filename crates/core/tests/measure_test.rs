@@ -0,0 +1,162 @@
+extern crate dprint_core;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_measure_single_line() {
+  let results = Rc::new(RefCell::new(Vec::new()));
+  let text = format(
+    || {
+      let mut items = PrintItems::new();
+      items.push_str("x = ");
+      items.push_condition(create_recording_condition(|| get_call_items("a", "b"), results.clone()));
+      items.push_str("y");
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(text, "x = y");
+  assert_eq!(*results.borrow(), vec![Measurement { width: 10, line_count: 1 }]);
+}
+
+#[test]
+fn it_should_measure_multiple_lines() {
+  let results = Rc::new(RefCell::new(Vec::new()));
+  let text = format(
+    || {
+      let get_fragment = || {
+        let mut items = PrintItems::new();
+        items.push_str("a");
+        items.push_signal(Signal::NewLine);
+        items.extend(parser_helpers::with_indent({
+          let mut items = PrintItems::new();
+          items.push_str("bbb");
+          items
+        }));
+        items.push_signal(Signal::NewLine);
+        items.push_str("c");
+        items
+      };
+      let mut items = PrintItems::new();
+      items.push_str("x = ");
+      items.push_condition(create_recording_condition(get_fragment, results.clone()));
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(text, "x = ");
+  assert_eq!(*results.borrow(), vec![Measurement { width: 5, line_count: 3 }]);
+}
+
+#[test]
+fn it_should_measure_fragment_exceeding_line_width() {
+  let results = Rc::new(RefCell::new(Vec::new()));
+  format(
+    || {
+      let mut items = PrintItems::new();
+      items.push_str("x = ");
+      items.push_condition(create_recording_condition(|| get_call_items("aaaaaaaaaa", "bbbbbbbbbb"), results.clone()));
+      items
+    },
+    get_print_options(20),
+  );
+
+  // the possible new line in the fragment is used when measuring
+  assert_eq!(*results.borrow(), vec![Measurement { width: 16, line_count: 2 }]);
+}
+
+#[test]
+fn it_should_measure_from_indentation_at_start_of_line() {
+  let results = Rc::new(RefCell::new(Vec::new()));
+  format(
+    || {
+      let mut items = PrintItems::new();
+      items.push_str("{");
+      items.push_signal(Signal::NewLine);
+      items.extend(parser_helpers::with_indent({
+        let mut items = PrintItems::new();
+        items.push_condition(create_recording_condition(|| get_call_items("a", "b"), results.clone()));
+        items
+      }));
+      items.push_str("}");
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(*results.borrow(), vec![Measurement { width: 10, line_count: 1 }]);
+}
+
+#[test]
+fn it_should_choose_layout_based_on_measurement() {
+  fn get_items(first_arg: &'static str, max_width: u32) -> String {
+    format(
+      || {
+        let mut items = PrintItems::new();
+        items.push_str("x = ");
+        items.push_condition(Condition::new(
+          "fitsOnLine",
+          ConditionProperties {
+            condition: Rc::new(move |context| {
+              let measurement = context.measure(&get_call_items(first_arg, "b"));
+              Some(measurement.line_count == 1 && context.writer_info.column_number + measurement.width <= max_width)
+            }),
+            true_path: Some(get_call_items(first_arg, "b")),
+            false_path: Some({
+              let mut items = PrintItems::new();
+              items.push_str("call(");
+              items.extend(parser_helpers::with_indent({
+                let mut items = PrintItems::new();
+                items.push_signal(Signal::NewLine);
+                items.push_str(&format!("{},", first_arg));
+                items.push_signal(Signal::NewLine);
+                items.push_str("b");
+                items
+              }));
+              items.push_signal(Signal::NewLine);
+              items.push_str(")");
+              items
+            }),
+          },
+        ));
+        items
+      },
+      get_print_options(max_width),
+    )
+  }
+
+  assert_eq!(get_items("a", 20), "x = call(a, b)");
+  assert_eq!(get_items("aaaaaaaaaa", 20), "x = call(\n  aaaaaaaaaa,\n  b\n)");
+}
+
+fn create_recording_condition(get_fragment: impl Fn() -> PrintItems + 'static, results: Rc<RefCell<Vec<Measurement>>>) -> Condition {
+  Condition::new(
+    "recordMeasurement",
+    ConditionProperties {
+      condition: Rc::new(move |context| {
+        results.borrow_mut().push(context.measure(&get_fragment()));
+        None
+      }),
+      true_path: None,
+      false_path: None,
+    },
+  )
+}
+
+fn get_call_items(arg1: &str, arg2: &str) -> PrintItems {
+  let mut items = PrintItems::new();
+  items.push_str("call(");
+  items.push_str(&format!("{},", arg1));
+  items.push_signal(Signal::SpaceOrNewLine);
+  items.push_str(arg2);
+  items.push_str(")");
+  items
+}
@@ -0,0 +1,72 @@
+extern crate dprint_core;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_indent_formatted_text_relative_to_where_embedded() {
+  let text = format(
+    || {
+      let mut items = PrintItems::new();
+      items.push_str("{");
+      items.push_signal(Signal::NewLine);
+      items.extend(parser_helpers::with_indent(parser_helpers::parse_formatted_text(
+        "if (a) {\n    call(\n        b\n    );\n\n}",
+        4,
+      )));
+      items.push_signal(Signal::NewLine);
+      items.push_str("}");
+      items
+    },
+    get_print_options_with_tabs(false),
+  );
+
+  assert_eq!(text, "{\n  if (a) {\n    call(\n      b\n    );\n\n  }\n}");
+}
+
+#[test]
+fn it_should_keep_partial_indentation_as_spaces() {
+  let text = format(
+    || parser_helpers::parse_formatted_text("/**\n * a\n\t * b\n */\n", 4),
+    get_print_options_with_tabs(true),
+  );
+
+  assert_eq!(text, "/**\n * a\n\t * b\n */\n");
+}
+
+#[test]
+fn it_should_parse_write_items() {
+  let text = format(
+    || {
+      let a = StringContainer::new("a(".to_string());
+      let b = StringContainer::new("b".to_string());
+      let c = StringContainer::new(")".to_string());
+      let write_items = vec![
+        WriteItem::String(&a),
+        WriteItem::NewLine,
+        WriteItem::Indent(1),
+        WriteItem::Alignment(1),
+        WriteItem::String(&b),
+        WriteItem::NewLine,
+        WriteItem::String(&c),
+      ];
+      let mut items = PrintItems::new();
+      items.push_str("x = ");
+      items.push_signal(Signal::StartIndent);
+      items.extend(parser_helpers::parse_write_items(&write_items));
+      items.push_signal(Signal::FinishIndent);
+      items
+    },
+    get_print_options_with_tabs(false),
+  );
+
+  assert_eq!(text, "x = a(\n     b\n  )");
+}
+
+fn get_print_options_with_tabs(use_tabs: bool) -> PrintOptions {
+  let mut options = get_print_options(40);
+  options.use_tabs = use_tabs;
+  options
+}
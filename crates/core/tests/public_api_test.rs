@@ -0,0 +1,189 @@
+// Checks the semver-guarded API of `dprint_core::formatting`. The named imports and
+// the signature constants below fail to compile when an item is removed or its
+// signature changes, and `exported_items_should_not_change` fails when an item is
+// added to or removed from the module's exports. Update this along with a minor
+// version bump (the crate is pre-1.0).
+#![allow(dead_code)]
+
+use std::rc::Rc;
+
+use dprint_core::formatting::{
+  format, print, Anchor, ArenaText, Condition, ConditionProperties, ConditionReference, ConditionResolver, ConditionResolverContext, CreateConditionPath, Info,
+  LazyConditionProperties, Measurement, PrintItemPath, PrintItems, PrintOptions, Signal, StringContainer, WriteItem, WriterInfo,
+};
+
+const PRINT: fn(PrintItems, PrintOptions) -> String = print;
+const PRINT_OPTIONS_NEW: fn(u32, u8, bool, &'static str) -> PrintOptions = PrintOptions::new;
+
+const PRINT_ITEMS_NEW: fn() -> PrintItems = PrintItems::new;
+const PRINT_ITEMS_INTO_RC_PATH: fn(PrintItems) -> Option<PrintItemPath> = PrintItems::into_rc_path;
+const PRINT_ITEMS_EXTEND: fn(&mut PrintItems, PrintItems) = PrintItems::extend;
+const PRINT_ITEMS_PUSH_STR: fn(&mut PrintItems, &str) = PrintItems::push_str;
+const PRINT_ITEMS_PUSH_STRING: fn(&mut PrintItems, String) = PrintItems::push_string;
+const PRINT_ITEMS_PUSH_ARENA_TEXT: fn(&mut PrintItems, ArenaText) = PrintItems::push_arena_text;
+const PRINT_ITEMS_PUSH_ARENA_TEXT_WITH_CHAR_COUNT: fn(&mut PrintItems, ArenaText, u32) = PrintItems::push_arena_text_with_char_count;
+const PRINT_ITEMS_PUSH_CONDITION: fn(&mut PrintItems, Condition) = PrintItems::push_condition;
+const PRINT_ITEMS_PUSH_INFO: fn(&mut PrintItems, Info) = PrintItems::push_info;
+const PRINT_ITEMS_PUSH_ALIGN: fn(&mut PrintItems, Anchor) = PrintItems::push_align;
+const PRINT_ITEMS_PUSH_SIGNAL: fn(&mut PrintItems, Signal) = PrintItems::push_signal;
+const PRINT_ITEMS_PUSH_PATH: fn(&mut PrintItems, PrintItemPath) = PrintItems::push_path;
+const PRINT_ITEMS_PUSH_OPTIONAL_PATH: fn(&mut PrintItems, Option<PrintItemPath>) = PrintItems::push_optional_path;
+const PRINT_ITEMS_IS_EMPTY: fn(&PrintItems) -> bool = PrintItems::is_empty;
+const PRINT_ITEMS_GET_AS_TEXT: fn(&PrintItems) -> String = PrintItems::get_as_text;
+
+const INFO_NEW: fn(&'static str) -> Info = Info::new;
+const INFO_NEW_REFLOWABLE: fn(&'static str) -> Info = Info::new_reflowable;
+const INFO_GET_UNIQUE_ID: fn(&Info) -> usize = Info::get_unique_id;
+const INFO_GET_NAME: fn(&Info) -> &'static str = Info::get_name;
+const ANCHOR_NEW: fn(&'static str) -> Anchor = Anchor::new;
+
+const CONDITION_NEW: fn(&'static str, ConditionProperties) -> Condition = Condition::new;
+const CONDITION_NEW_LAZY: fn(&'static str, LazyConditionProperties) -> Condition = Condition::new_lazy;
+const CONDITION_NEW_TRUE: fn() -> Condition = Condition::new_true;
+const CONDITION_NEW_FALSE: fn() -> Condition = Condition::new_false;
+const CONDITION_NEW_WITH_DEPENDENT_INFOS: fn(&'static str, ConditionProperties, Vec<Info>) -> Condition = Condition::new_with_dependent_infos;
+const CONDITION_GET_TRUE_PATH: fn(&Condition) -> Option<PrintItemPath> = Condition::get_true_path;
+const CONDITION_GET_FALSE_PATH: fn(&Condition) -> Option<PrintItemPath> = Condition::get_false_path;
+const CONDITION_GET_REFERENCE: fn(&mut Condition) -> ConditionReference = Condition::get_reference;
+
+fn format_signature(get_print_items: impl FnOnce() -> PrintItems + 'static, options: PrintOptions) -> String {
+  format(get_print_items, options)
+}
+
+fn condition_resolver_context_signatures<'b, 'c: 'b>() {
+  let _: fn(&mut ConditionResolverContext<'b, 'c>, &ConditionReference) -> Option<bool> = ConditionResolverContext::get_resolved_condition;
+  let _: for<'a> fn(&'a ConditionResolverContext<'b, 'c>, &Info) -> Option<&'a WriterInfo> = ConditionResolverContext::get_resolved_info;
+  let _: fn(&mut ConditionResolverContext<'b, 'c>, &Info) = ConditionResolverContext::clear_info;
+  let _: fn(&mut ConditionResolverContext<'b, 'c>, &Info) -> Option<bool> = ConditionResolverContext::has_info_moved;
+  let _: fn(&mut ConditionResolverContext<'b, 'c>, &Info) -> Option<bool> = ConditionResolverContext::is_layout_stable;
+  let _: fn(&ConditionResolverContext<'b, 'c>) -> u32 = ConditionResolverContext::get_max_width;
+  let _: fn(&ConditionResolverContext<'b, 'c>, &PrintItems) -> Measurement = ConditionResolverContext::measure;
+  let _: fn(&mut ConditionResolverContext<'b, 'c>, &Info) -> bool = ConditionResolverContext::request_reflow;
+}
+
+const WRITER_INFO_IS_START_OF_LINE: fn(&WriterInfo) -> bool = WriterInfo::is_start_of_line;
+const WRITER_INFO_GET_LINE_AND_COLUMN: fn(&WriterInfo) -> (u32, u32) = WriterInfo::get_line_and_column;
+
+const ARENA_TEXT_NEW: unsafe fn(&str) -> ArenaText = ArenaText::new;
+const STRING_CONTAINER_NEW: fn(String) -> StringContainer = StringContainer::new;
+const STRING_CONTAINER_FROM_ARENA_TEXT: fn(ArenaText) -> StringContainer = StringContainer::from_arena_text;
+
+fn public_fields(options: &PrintOptions, writer_info: &WriterInfo, measurement: Measurement) {
+  let _: (u32, u8, bool, bool, &str, Option<u32>) = (
+    options.max_width,
+    options.indent_width,
+    options.use_tabs,
+    options.smart_tabs,
+    options.new_line_text,
+    options.max_blank_lines,
+  );
+  let _: (u32, u32, u8, u8, u32) = (
+    writer_info.line_number,
+    writer_info.column_number,
+    writer_info.indent_level,
+    writer_info.line_start_indent_level,
+    writer_info.line_start_column_number,
+  );
+  let Measurement { width: _, line_count: _ } = measurement;
+}
+
+fn condition_properties(condition: Rc<ConditionResolver>, create_path: CreateConditionPath) {
+  let _ = ConditionProperties {
+    condition: condition.clone(),
+    true_path: Some(PrintItems::new()),
+    false_path: None,
+  };
+  let _ = LazyConditionProperties {
+    condition,
+    true_path: Some(create_path),
+    false_path: None,
+  };
+}
+
+fn write_item_variants(item: &WriteItem) {
+  // a wildcard arm is required because the enum is non-exhaustive
+  #[allow(unreachable_patterns)]
+  match item {
+    WriteItem::String(text) => {
+      let _: &StringContainer = text;
+    }
+    WriteItem::Indent(_) | WriteItem::Alignment(_) | WriteItem::NewLine | WriteItem::Tab | WriteItem::Space => {}
+    _ => {}
+  }
+}
+
+/// Gets the names of the items exported at the top level of the module's source.
+fn get_exported_names(module_text: &str) -> Vec<String> {
+  let mut names = Vec::new();
+  let mut statement = String::new();
+  for line in module_text.lines() {
+    if statement.is_empty() {
+      if let Some(name) = line.strip_prefix("pub mod ") {
+        names.push(name.trim_end_matches(|c| c == ';' || c == '{' || c == ' ').to_string());
+        continue;
+      }
+      if !line.starts_with("pub use ") {
+        continue;
+      }
+    }
+    statement.push_str(line);
+    if line.ends_with(';') {
+      let uses = statement.trim_start_matches("pub use ").trim_end_matches(';');
+      let uses = match uses.find('{') {
+        Some(index) => &uses[index + 1..uses.len() - 1],
+        None => &uses[uses.rfind(':').map(|index| index + 1).unwrap_or(0)..],
+      };
+      names.extend(uses.split(',').map(|name| name.trim().to_string()).filter(|name| !name.is_empty()));
+      statement.clear();
+    }
+  }
+  names.sort();
+  names
+}
+
+#[test]
+fn exported_items_should_not_change() {
+  let mut expected = vec![
+    // modules
+    "condition_resolvers",
+    "conditions",
+    "internal",
+    "parser_helpers",
+    "tokens",
+    "utils",
+    // printing
+    "format",
+    "print",
+    "PrintOptions",
+    // print items
+    "Anchor",
+    "ArenaText",
+    "Condition",
+    "ConditionProperties",
+    "ConditionReference",
+    "ConditionResolver",
+    "ConditionResolverContext",
+    "CreateConditionPath",
+    "Info",
+    "LazyConditionProperties",
+    "Measurement",
+    "PrintItemPath",
+    "PrintItems",
+    "Signal",
+    "StringContainer",
+    "WriteItem",
+    "WriterInfo",
+    // tracing feature
+    "trace_printing",
+    "Trace",
+    "TraceAnchor",
+    "TraceCondition",
+    "TraceInfo",
+    "TracePrintItem",
+    "TracePrintNode",
+    "TraceWriterNode",
+    "TracingResult",
+  ];
+  expected.sort_unstable();
+  assert_eq!(get_exported_names(include_str!("../src/formatting/mod.rs")), expected);
+}
@@ -0,0 +1,107 @@
+extern crate dprint_core;
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use dprint_core::formatting::*;
+
+mod common;
+use common::get_print_options;
+
+#[test]
+fn it_should_reflow_to_info_when_requested() {
+  let child_broke = Rc::new(Cell::new(false));
+  let text = format(
+    || {
+      let start_info = Info::new_reflowable("start");
+      let mut items = PrintItems::new();
+      items.push_info(start_info);
+      items.push_condition(conditions::if_true_or(
+        "breakIfChildBroke",
+        {
+          let child_broke = child_broke.clone();
+          move |_| Some(child_broke.get())
+        },
+        {
+          let mut items = PrintItems::new();
+          items.push_str("[");
+          items.push_signal(Signal::NewLine);
+          items
+        },
+        "[".into(),
+      ));
+      items.push_str("a");
+      items.push_signal(Signal::NewLine);
+      items.push_condition(conditions::if_true(
+        "requestReflowIfBroke",
+        {
+          let child_broke = child_broke.clone();
+          move |context| {
+            if context.writer_info.line_number > 0 && !child_broke.get() {
+              child_broke.set(true);
+              assert!(context.request_reflow(&start_info));
+            }
+            Some(false)
+          }
+        },
+        PrintItems::new(),
+      ));
+      items.push_str("b");
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(text, "[\na\nb");
+}
+
+#[test]
+fn it_should_not_reflow_to_info_that_is_not_reflowable() {
+  let results = Rc::new(RefCell::new(Vec::new()));
+  let text = format(
+    || {
+      let start_info = Info::new("start");
+      let mut items = PrintItems::new();
+      items.push_info(start_info);
+      items.push_str("a");
+      items.push_condition(create_reflow_requesting_condition(start_info, results.clone()));
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(text, "a");
+  assert_eq!(*results.borrow(), vec![false]);
+}
+
+#[test]
+fn it_should_stop_reflowing_to_info_after_too_many_requests() {
+  let results = Rc::new(RefCell::new(Vec::new()));
+  let text = format(
+    || {
+      let start_info = Info::new_reflowable("start");
+      let mut items = PrintItems::new();
+      items.push_info(start_info);
+      items.push_str("a");
+      items.push_condition(create_reflow_requesting_condition(start_info, results.clone()));
+      items
+    },
+    get_print_options(40),
+  );
+
+  assert_eq!(text, "a");
+  let mut expected = vec![true; 10];
+  expected.push(false);
+  assert_eq!(*results.borrow(), expected);
+}
+
+fn create_reflow_requesting_condition(info: Info, results: Rc<RefCell<Vec<bool>>>) -> Condition {
+  conditions::if_true(
+    "alwaysRequestReflow",
+    move |context| {
+      results.borrow_mut().push(context.request_reflow(&info));
+      Some(false)
+    },
+    PrintItems::new(),
+  )
+}
@@ -128,12 +128,7 @@ fn it_formats_as_multi_line_when_items_exceed_print_width() {
 fn do_test(expr: &ArrayLiteralExpression, expected_text: &str) {
   let result = dprint_core::formatting::format(
     || parse_node(Node::ArrayLiteralExpression(expr)),
-    PrintOptions {
-      indent_width: 2,
-      max_width: 40,
-      use_tabs: false,
-      new_line_text: "\n",
-    },
+    PrintOptions::new(40, 2, false, "\n"),
   );
   assert_eq!(result, expected_text);
 }
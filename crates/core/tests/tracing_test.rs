@@ -22,12 +22,7 @@ fn test_tracing() {
       });
       print_items
     },
-    PrintOptions {
-      indent_width: 4,
-      use_tabs: false,
-      max_width: 80,
-      new_line_text: "\n",
-    },
+    PrintOptions::new(80, 4, false, "\n"),
   );
 
   // very basic test just to ensure it's working
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use dprint_cli_core::styling::bold;
+use dprint_cli_core::types::ErrBox;
+
+use crate::environment::Environment;
+use crate::plugins::PluginPools;
+use crate::utils::{read_archive_members, write_archive_members, ArchiveKind, GlobMatcher, GlobMatcherOptions};
+
+use super::format::format_with_plugin_pools;
+use super::ArchiveMemberPattern;
+
+/// Formats the files inside the archives matching the patterns, then writes the archives that changed.
+pub fn format_archive_members<TEnvironment: Environment>(
+  patterns: &[ArchiveMemberPattern],
+  environment: &TEnvironment,
+  plugin_pools: &Arc<PluginPools<TEnvironment>>,
+) -> Result<(), ErrBox> {
+  let mut formatted_files_count = 0;
+  let mut error_count = 0;
+  for pattern in patterns {
+    let archive = format_archive(pattern, environment, plugin_pools)?;
+    error_count += archive.error_count;
+    if !archive.changed_members.is_empty() {
+      let new_bytes = write_archive_members(archive.kind, &archive.bytes, &archive.changed_members)?;
+      environment.write_file_bytes(&archive.file_path, &new_bytes)?;
+      formatted_files_count += archive.changed_members.len();
+    }
+  }
+
+  if formatted_files_count > 0 {
+    let count_text = bold(formatted_files_count);
+    environment.log(&environment.messages().get_plural("fmt.formatted", formatted_files_count, &count_text, &[]));
+  }
+  if error_count > 0 {
+    err!("{}", environment.messages().get("format.errorCount", &[("count", &error_count)]))
+  } else {
+    Ok(())
+  }
+}
+
+/// Outputs the files inside the archives matching the patterns that aren't formatted.
+pub fn check_archive_members<TEnvironment: Environment>(
+  patterns: &[ArchiveMemberPattern],
+  environment: &TEnvironment,
+  plugin_pools: &Arc<PluginPools<TEnvironment>>,
+) -> Result<(), ErrBox> {
+  let mut not_formatted_files_count = 0;
+  let mut error_count = 0;
+  for pattern in patterns {
+    let archive = format_archive(pattern, environment, plugin_pools)?;
+    error_count += archive.error_count;
+    let mut member_names = archive.changed_members.keys().collect::<Vec<_>>();
+    member_names.sort();
+    for member_name in member_names {
+      environment.log(&format!("{}!{}", archive.file_path.display(), member_name));
+    }
+    not_formatted_files_count += archive.changed_members.len();
+  }
+
+  if error_count > 0 {
+    err!("{}", environment.messages().get("format.errorCount", &[("count", &error_count)]))
+  } else if not_formatted_files_count > 0 {
+    let count_text = bold(not_formatted_files_count);
+    err!(
+      "{}",
+      environment
+        .messages()
+        .get_plural("check.notFormatted", not_formatted_files_count, &count_text, &[])
+    )
+  } else {
+    Ok(())
+  }
+}
+
+struct FormattedArchive {
+  file_path: PathBuf,
+  kind: ArchiveKind,
+  bytes: Vec<u8>,
+  /// The formatted text of the members that changed by their names.
+  changed_members: HashMap<String, Vec<u8>>,
+  error_count: usize,
+}
+
+fn format_archive<TEnvironment: Environment>(
+  pattern: &ArchiveMemberPattern,
+  environment: &TEnvironment,
+  plugin_pools: &Arc<PluginPools<TEnvironment>>,
+) -> Result<FormattedArchive, ErrBox> {
+  // the archive kind is validated when parsing the pattern
+  let kind = ArchiveKind::from_path(&pattern.archive_path).unwrap();
+  let file_path = environment.cwd().join(&pattern.archive_path);
+  let bytes = environment.read_file_bytes(&file_path)?;
+  let members = match read_archive_members(kind, &bytes) {
+    Ok(members) => members,
+    Err(err) => return err!("Error reading archive {}. {}", file_path.display(), err.to_string()),
+  };
+  let member_matcher = GlobMatcher::new(&[pattern.member_pattern.clone()], &GlobMatcherOptions { case_insensitive: false })?;
+
+  let mut changed_members = HashMap::new();
+  let mut error_count = 0;
+  for member in members.into_iter().filter(|member| member_matcher.is_match(&member.name)) {
+    let file_text = match String::from_utf8(member.bytes) {
+      Ok(file_text) => file_text,
+      Err(_) => {
+        log_verbose!(environment, "Skipping non-UTF-8 file in archive: {}!{}", file_path.display(), member.name);
+        continue;
+      }
+    };
+    // the plugin is selected based on the path of the file within the archive
+    match format_with_plugin_pools(Path::new(&member.name), &file_text, None, environment, plugin_pools) {
+      Ok(formatted_text) => {
+        if formatted_text != file_text {
+          changed_members.insert(member.name, formatted_text.into_owned().into_bytes());
+        }
+      }
+      Err(err) => {
+        environment.log_error(&format!(
+          "Error formatting {}!{}. Message: {}",
+          file_path.display(),
+          member.name,
+          err.to_string()
+        ));
+        error_count += 1;
+      }
+    }
+  }
+
+  Ok(FormattedArchive {
+    file_path,
+    kind,
+    bytes,
+    changed_members,
+    error_count,
+  })
+}
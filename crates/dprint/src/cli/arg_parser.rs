@@ -1,16 +1,35 @@
+use std::ops::Range;
+
 use super::StdInReader;
+use crate::environment::OutputFormat;
+use crate::utils::ArchiveKind;
+use dprint_cli_core::styling::ColorMode;
 use dprint_core::types::ErrBox;
 
 pub struct CliArgs {
   pub sub_command: SubCommand,
   pub verbose: bool,
+  pub color_mode: ColorMode,
+  pub output_format: OutputFormat,
   pub plugins: Vec<String>,
   pub config: Option<String>,
+  /// Additional configuration files that are merged on top of the main configuration file in order.
+  pub config_overlays: Vec<String>,
+  /// Directory to write each plugin's format requests and responses to.
+  pub debug_plugin_io: Option<String>,
   // It depends on the command whether these will exist... it
   // was just a lot easier to store these on a global object.
   pub incremental: bool,
+  /// Path to store the incremental file at instead of in the cache directory.
+  pub incremental_cache_path: Option<String>,
   pub file_patterns: Vec<String>,
   pub exclude_file_patterns: Vec<String>,
+  /// Configuration keys of the plugins to limit formatting to.
+  pub languages: Vec<String>,
+  /// Owners in the CODEOWNERS file to limit formatting to the files of.
+  pub owned_by: Vec<String>,
+  /// If warnings should fail the command.
+  pub warnings_as_errors: bool,
   pub allow_node_modules: bool,
 }
 
@@ -18,52 +37,204 @@ impl CliArgs {
   pub fn is_silent_output(&self) -> bool {
     match self.sub_command {
       SubCommand::StdInFmt(..) => true,
+      SubCommand::StdInJsonFmt(..) => true,
+      // stdout is used for the protocol messages
+      SubCommand::Lsp => true,
       _ => false,
     }
   }
 
-  fn new_with_sub_command(sub_command: SubCommand) -> CliArgs {
+  pub fn new_with_sub_command(sub_command: SubCommand) -> CliArgs {
     CliArgs {
       sub_command,
       verbose: false,
+      color_mode: ColorMode::Auto,
+      output_format: OutputFormat::Text,
       config: None,
+      config_overlays: Vec::new(),
+      debug_plugin_io: None,
       plugins: Vec::new(),
       incremental: false,
+      incremental_cache_path: None,
       allow_node_modules: false,
       file_patterns: Vec::new(),
       exclude_file_patterns: Vec::new(),
+      languages: Vec::new(),
+      owned_by: Vec::new(),
+      warnings_as_errors: false,
     }
   }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum SubCommand {
-  Check,
-  Fmt,
-  Init,
+  Check(CheckSubCommand),
+  Fmt(FmtSubCommand),
+  CheckConfig,
+  LintWidth,
+  Init(InitSubCommand),
+  SetupHooks(SetupHooksSubCommand),
   ClearCache,
+  UpgradePlugins,
   OutputFilePaths,
-  OutputResolvedConfig,
+  OutputResolvedConfig(OutputResolvedConfigSubCommand),
   OutputFormatTimes,
+  OutputTrace(OutputTraceSubCommand),
   Version,
   License,
   Help(String),
   EditorInfo, // todo: deprecate
   EditorService(EditorServiceSubCommand),
+  Lsp,
   StdInFmt(StdInFmtSubCommand),
+  StdInJsonFmt(StdInJsonFmtSubCommand),
+  Plugin(PluginSubCommand),
+  Config(ConfigSubCommand),
   #[cfg(target_os = "windows")]
   Hidden(HiddenSubCommand),
 }
 
+#[derive(Debug, PartialEq)]
+pub struct CheckSubCommand {
+  /// File path to write an SVG badge of the formatting statistics to.
+  pub badge: Option<String>,
+  /// What to output for the files that aren't formatted.
+  pub output_kind: CheckOutputKind,
+  /// Whether to verify plugins that declare they only change whitespace actually do.
+  pub verify_whitespace_only: bool,
+  /// Patterns of the files inside archives to check instead of files on the file system.
+  pub archive_member_patterns: Vec<ArchiveMemberPattern>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CheckOutputKind {
+  /// The differences between each file and its formatted text.
+  Difference,
+  /// A unified diff of each file and its formatted text.
+  UnifiedDiff,
+  /// Only the paths of the files, which is useful for piping to other commands.
+  ListDifferent,
+  /// Only the number of checked files.
+  Summary,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FmtSubCommand {
+  /// File path to write a JSON record of the changed files to.
+  pub change_log: Option<String>,
+  /// Whether to verify plugins that declare they only change whitespace actually do.
+  pub verify_whitespace_only: bool,
+  /// Patterns of the files inside archives to format instead of files on the file system.
+  pub archive_member_patterns: Vec<ArchiveMemberPattern>,
+  /// File path to write a speedscope profile of the run to.
+  pub profile: Option<String>,
+  /// Whether to only format the files staged in git and stage the formatting changes.
+  pub staged: bool,
+}
+
+/// A pattern of files inside an archive (ex. `assets.zip!**/*.json`).
+#[derive(Debug, PartialEq, Clone)]
+pub struct ArchiveMemberPattern {
+  pub archive_path: String,
+  pub member_pattern: String,
+}
+
+impl ArchiveMemberPattern {
+  pub fn parse(text: &str) -> Option<ArchiveMemberPattern> {
+    let separator_index = text.find('!')?;
+    let archive_path = &text[..separator_index];
+    let member_pattern = &text[separator_index + 1..];
+    if ArchiveKind::from_path(archive_path).is_none() || member_pattern.is_empty() {
+      return None;
+    }
+    Some(ArchiveMemberPattern {
+      archive_path: archive_path.to_string(),
+      member_pattern: member_pattern.to_string(),
+    })
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InitSubCommand {
+  pub vscode: bool,
+  /// Name, url, or file path of a configuration file template to use.
+  pub template: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SetupHooksSubCommand {
+  pub framework: Option<HookFramework>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HookFramework {
+  Husky,
+  PreCommit,
+  Lefthook,
+}
+
+impl HookFramework {
+  pub fn name(&self) -> &'static str {
+    match self {
+      HookFramework::Husky => "husky",
+      HookFramework::PreCommit => "pre-commit",
+      HookFramework::Lefthook => "lefthook",
+    }
+  }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct EditorServiceSubCommand {
   pub parent_pid: u32,
+  /// File to log the protocol messages to.
+  pub trace_protocol: Option<String>,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct StdInFmtSubCommand {
   pub file_name_or_path: String,
   pub file_text: String,
+  /// Configuration text that overrides the resolved configuration.
+  pub plugin_config: Option<String>,
+  /// Byte range of the text to format. The text outside this range is left as-is.
+  pub range: Option<Range<usize>>,
+  /// Extension used to select the plugin instead of the extension of the file path.
+  pub ext: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StdInJsonFmtSubCommand {
+  /// JSON array of the files to format with their paths and text.
+  pub json_text: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct OutputResolvedConfigSubCommand {
+  /// Name or configuration key of a plugin to only output the configuration of,
+  /// which includes where each value came from.
+  pub plugin: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct OutputTraceSubCommand {
+  /// Path of the file to format with tracing enabled.
+  pub file_path: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PluginSubCommand {
+  Replay(PluginReplaySubCommand),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PluginReplaySubCommand {
+  /// Plugin directory created by `--debug-plugin-io`.
+  pub dir_path: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigSubCommand {
+  Normalize,
 }
 
 #[derive(Debug, PartialEq)]
@@ -105,22 +276,82 @@ pub fn parse_args<TStdInReader: StdInReader>(args: Vec<String>, std_in_reader: &
         SubCommand::StdInFmt(StdInFmtSubCommand {
           file_name_or_path,
           file_text: std_in_reader.read()?,
+          plugin_config: matches.value_of("plugin-config").map(String::from),
+          range: match matches.value_of("range") {
+            Some(range_text) => Some(parse_range(range_text)?),
+            None => None,
+          },
+          ext: matches.value_of("ext").map(|ext| ext.trim_start_matches('.').to_string()),
+        })
+      } else if matches.is_present("stdin-json") {
+        SubCommand::StdInJsonFmt(StdInJsonFmtSubCommand {
+          json_text: std_in_reader.read()?,
         })
       } else {
-        SubCommand::Fmt
+        SubCommand::Fmt(FmtSubCommand {
+          change_log: matches.value_of("change-log").map(String::from),
+          verify_whitespace_only: matches.is_present("verify-whitespace-only"),
+          archive_member_patterns: parse_archive_member_patterns(matches)?,
+          profile: matches.value_of("profile").map(String::from),
+          staged: matches.is_present("staged"),
+        })
       }
     }
-    ("check", _) => SubCommand::Check,
-    ("init", _) => SubCommand::Init,
+    ("check", Some(matches)) => SubCommand::Check(CheckSubCommand {
+      badge: matches.value_of("badge").map(String::from),
+      output_kind: if matches.is_present("diff") {
+        CheckOutputKind::UnifiedDiff
+      } else if matches.is_present("list-different") {
+        CheckOutputKind::ListDifferent
+      } else if matches.is_present("summary") {
+        CheckOutputKind::Summary
+      } else {
+        CheckOutputKind::Difference
+      },
+      verify_whitespace_only: matches.is_present("verify-whitespace-only"),
+      archive_member_patterns: parse_archive_member_patterns(matches)?,
+    }),
+    ("check-config", _) => SubCommand::CheckConfig,
+    ("lint-width", _) => SubCommand::LintWidth,
+    ("init", Some(matches)) => SubCommand::Init(InitSubCommand {
+      vscode: matches.is_present("vscode"),
+      template: matches.value_of("template").map(String::from),
+    }),
+    ("setup-hooks", Some(matches)) => SubCommand::SetupHooks(SetupHooksSubCommand {
+      framework: matches.value_of("framework").map(|value| match value {
+        "husky" => HookFramework::Husky,
+        "pre-commit" => HookFramework::PreCommit,
+        "lefthook" => HookFramework::Lefthook,
+        _ => unreachable!(),
+      }),
+    }),
     ("clear-cache", _) => SubCommand::ClearCache,
+    ("upgrade-plugins", _) => SubCommand::UpgradePlugins,
     ("output-file-paths", _) => SubCommand::OutputFilePaths,
-    ("output-resolved-config", _) => SubCommand::OutputResolvedConfig,
+    ("output-resolved-config", Some(matches)) => SubCommand::OutputResolvedConfig(OutputResolvedConfigSubCommand {
+      plugin: matches.value_of("plugin").map(String::from),
+    }),
     ("output-format-times", _) => SubCommand::OutputFormatTimes,
+    ("output-trace", Some(matches)) => SubCommand::OutputTrace(OutputTraceSubCommand {
+      file_path: matches.value_of("file").map(String::from).unwrap(),
+    }),
     ("version", _) => SubCommand::Version,
     ("license", _) => SubCommand::License,
     ("editor-info", _) => SubCommand::EditorInfo,
+    ("lsp", _) => SubCommand::Lsp,
+    ("plugin", Some(matches)) => SubCommand::Plugin(match matches.subcommand() {
+      ("replay", Some(matches)) => PluginSubCommand::Replay(PluginReplaySubCommand {
+        dir_path: matches.value_of("dump-dir").map(String::from).unwrap(),
+      }),
+      _ => unreachable!(),
+    }),
+    ("config", Some(matches)) => SubCommand::Config(match matches.subcommand() {
+      ("normalize", _) => ConfigSubCommand::Normalize,
+      _ => unreachable!(),
+    }),
     ("editor-service", Some(matches)) => SubCommand::EditorService(EditorServiceSubCommand {
       parent_pid: matches.value_of("parent-pid").map(|v| v.parse::<u32>().ok()).flatten().unwrap(),
+      trace_protocol: matches.value_of("trace-protocol").map(String::from),
     }),
     #[cfg(target_os = "windows")]
     ("hidden", Some(matches)) => SubCommand::Hidden(match matches.subcommand() {
@@ -136,19 +367,74 @@ pub fn parse_args<TStdInReader: StdInReader>(args: Vec<String>, std_in_reader: &
     (_, Some(matches)) => Some(matches),
     _ => None,
   };
+  // the first configuration file is the main one and the others are merged on top of it
+  let mut config_paths = values_to_vec(matches.values_of("config"))
+    .iter()
+    .flat_map(|value| value.split(','))
+    .map(|path| path.trim().to_string())
+    .filter(|path| !path.is_empty())
+    .collect::<Vec<_>>();
 
   Ok(CliArgs {
     sub_command,
     verbose: matches.is_present("verbose"),
-    config: matches.value_of("config").map(String::from),
+    color_mode: matches.value_of("color").and_then(ColorMode::parse).unwrap_or(ColorMode::Auto),
+    output_format: match matches.value_of("output-format") {
+      Some("json") => OutputFormat::Json,
+      _ => OutputFormat::Text,
+    },
+    config: if config_paths.is_empty() { None } else { Some(config_paths.remove(0)) },
+    config_overlays: config_paths,
+    debug_plugin_io: matches.value_of("debug-plugin-io").map(String::from),
     plugins: values_to_vec(matches.values_of("plugins")),
     incremental: sub_command_matches.map(|m| m.is_present("incremental")).unwrap_or(false),
+    incremental_cache_path: sub_command_matches.and_then(|m| m.value_of("incremental-cache-path")).map(String::from),
     allow_node_modules: sub_command_matches.map(|m| m.is_present("allow-node-modules")).unwrap_or(false),
     file_patterns: sub_command_matches.map(|m| values_to_vec(m.values_of("files"))).unwrap_or(Vec::new()),
     exclude_file_patterns: sub_command_matches.map(|m| values_to_vec(m.values_of("excludes"))).unwrap_or(Vec::new()),
+    languages: sub_command_matches.map(|m| values_to_vec(m.values_of("language"))).unwrap_or(Vec::new()),
+    owned_by: sub_command_matches.map(|m| values_to_vec(m.values_of("owned-by"))).unwrap_or(Vec::new()),
+    warnings_as_errors: sub_command_matches.map(|m| m.is_present("warnings-as-errors")).unwrap_or(false),
   })
 }
 
+/// Parses a byte range in the format `<start>-<end>` (ex. `10-20`).
+fn parse_range(text: &str) -> Result<Range<usize>, ErrBox> {
+  let parts = text.split('-').map(|part| part.trim().parse::<usize>().ok()).collect::<Vec<_>>();
+  match parts.as_slice() {
+    [Some(start), Some(end)] if start <= end => Ok(*start..*end),
+    _ => err!(
+      "Invalid range '{}'. Expected the format <start>-<end> where the start is less than or equal to the end (ex. 10-20).",
+      text
+    ),
+  }
+}
+
+/// Parses the file patterns as archive member patterns when the experimental archives flag is specified.
+fn parse_archive_member_patterns(matches: &clap::ArgMatches) -> Result<Vec<ArchiveMemberPattern>, ErrBox> {
+  if !matches.is_present("experimental-archives") {
+    return Ok(Vec::new());
+  }
+
+  let file_patterns = values_to_vec(matches.values_of("files"));
+  if file_patterns.is_empty() {
+    return err!("Expected archive member patterns (ex. assets.zip!**/*.json) when specifying --experimental-archives.");
+  }
+  let mut patterns = Vec::with_capacity(file_patterns.len());
+  for file_pattern in file_patterns {
+    match ArchiveMemberPattern::parse(&file_pattern) {
+      Some(pattern) => patterns.push(pattern),
+      None => {
+        return err!(
+          "Expected only archive member patterns (ex. assets.zip!**/*.json) when specifying --experimental-archives, but found: {}",
+          file_pattern
+        )
+      }
+    }
+  }
+  Ok(patterns)
+}
+
 fn values_to_vec(values: Option<clap::Values>) -> Vec<String> {
   values.map(|x| x.map(std::string::ToString::to_string).collect()).unwrap_or(Vec::new())
 }
@@ -194,8 +480,11 @@ OPTIONS:
 {unified}
 
 ENVIRONMENT VARIABLES:
-    DPRINT_CACHE_DIR    The directory to store the dprint cache. Note that
-                        this directory may be periodically deleted by the CLI.
+    DPRINT_CACHE_DIR      The directory to store the dprint cache. Note that
+                          this directory may be periodically deleted by the CLI.
+    DPRINT_MAX_THREADS    Limits the number of threads the CLI will use for
+                          formatting. Defaults to the number of available CPUs.
+    NO_COLOR              Disables colored output when `--color` is `auto`.
 
 {after-help}"#)
         .after_help(
@@ -225,12 +514,40 @@ EXAMPLES:
         .subcommand(
             SubCommand::with_name("init")
                 .about("Initializes a configuration file in the current directory.")
+                .arg(
+                    Arg::with_name("vscode")
+                        .long("vscode")
+                        .help("Also writes VS Code settings that recommend the dprint extension and set it as the default formatter.")
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::with_name("template")
+                        .long("template")
+                        .value_name("name/url")
+                        .help("Creates the configuration file from a template instead, prompting for the values of any placeholders in it.")
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("setup-hooks")
+                .about("Sets up a git pre-commit hook that formats the staged files.")
+                .arg(
+                    Arg::with_name("framework")
+                        .long("framework")
+                        .value_name("framework")
+                        .help("The git hook manager to use. Defaults to the one detected in the current directory.")
+                        .possible_values(&["husky", "pre-commit", "lefthook"])
+                        .takes_value(true)
+                )
         )
         .subcommand(
             SubCommand::with_name("fmt")
                 .about("Formats the source files and writes the result to the file system.")
                 .add_resolve_file_path_args()
                 .add_incremental_arg()
+                .add_verify_whitespace_only_arg()
+                .add_experimental_archives_arg()
+                .add_warnings_as_errors_arg()
                 .arg(
                     Arg::with_name("stdin")
                         .long("stdin")
@@ -239,12 +556,105 @@ EXAMPLES:
                         .required(false)
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::with_name("stdin-json")
+                        .long("stdin-json")
+                        .help("Format the files provided to stdin as a JSON array of objects with \"path\" and \"text\" properties, then output the results to stdout as JSON. This avoids starting dprint for each file.")
+                        .conflicts_with("stdin")
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::with_name("plugin-config")
+                        .long("plugin-config")
+                        .value_name("json")
+                        .help("JSON configuration that overrides the configuration file when formatting stdin (ex. '{ \"lineWidth\": 40 }').")
+                        .requires("stdin")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("ext")
+                        .long("ext")
+                        .value_name("extension")
+                        .help("Extension used to select the plugin when formatting stdin. The file path provided to --stdin is still used for the inclusion and exclusion rules (ex. for unsaved files whose name doesn't match their language).")
+                        .requires("stdin")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("range")
+                        .long("range")
+                        .value_name("start-end")
+                        .help("Byte range of the text to format when formatting stdin (ex. 10-20). The text outside the range is left as-is. Only supported by plugins that can format a portion of a file.")
+                        .requires("stdin")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("change-log")
+                        .long("change-log")
+                        .value_name("path.json")
+                        .help("Writes a JSON record of every changed file to the path (file path, plugin, bytes and SHA-256 hash before and after, and duration).")
+                        .conflicts_with_all(&["stdin", "stdin-json"])
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("profile")
+                        .long("profile")
+                        .value_name("path.json")
+                        .help("Writes a profile of the run to the path that can be opened in https://www.speedscope.app (time spent in each phase, file, and plugin on each thread).")
+                        .conflicts_with_all(&["stdin", "stdin-json"])
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("staged")
+                        .long("staged")
+                        .help("Only formats the files staged in git, then stages the formatting changes. Files that also have unstaged changes are formatted, but not staged. Useful in a pre-commit hook.")
+                        .conflicts_with_all(&["stdin", "stdin-json", "experimental-archives"])
+                        .takes_value(false)
+                )
         )
         .subcommand(
             SubCommand::with_name("check")
                 .about("Checks for any files that haven't been formatted.")
                 .add_resolve_file_path_args()
                 .add_incremental_arg()
+                .add_verify_whitespace_only_arg()
+                .add_experimental_archives_arg()
+                .add_warnings_as_errors_arg()
+                .arg(
+                    Arg::with_name("badge")
+                        .long("badge")
+                        .value_name("path.svg")
+                        .help("Writes an SVG badge with the percentage of formatted files to the path and the statistics to a JSON file beside it.")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("diff")
+                        .long("diff")
+                        .help("Outputs a unified diff between each file and its formatted text.")
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::with_name("list-different")
+                        .long("list-different")
+                        .help("Outputs only the paths of the files that aren't formatted, one per line (ex. for piping to xargs).")
+                        .conflicts_with_all(&["diff", "summary"])
+                        .takes_value(false)
+                )
+                .arg(
+                    Arg::with_name("summary")
+                        .long("summary")
+                        .help("Outputs only the number of checked files and the number of files that aren't formatted.")
+                        .conflicts_with("diff")
+                        .takes_value(false)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("check-config")
+                .about("Checks the configuration of each plugin for any diagnostics without formatting files.")
+        )
+        .subcommand(
+            SubCommand::with_name("lint-width")
+                .about("Reports the lines of the files that exceed the line width of the plugin formatting them.")
+                .add_resolve_file_path_args()
         )
         .subcommand(
             SubCommand::with_name("output-file-paths")
@@ -254,20 +664,69 @@ EXAMPLES:
         .subcommand(
             SubCommand::with_name("output-resolved-config")
                 .about("Prints the resolved configuration for the plugins based on the args and configuration.")
+                .arg(
+                    Arg::with_name("plugin")
+                        .long("plugin")
+                        .value_name("name")
+                        .help("Only prints the configuration of the plugin with this name or configuration key, including where each value came from.")
+                        .takes_value(true)
+                )
         )
         .subcommand(
             SubCommand::with_name("output-format-times")
                 .about("Prints the amount of time it takes to format each file. Use this for debugging.")
                 .add_resolve_file_path_args()
         )
+        .subcommand(
+            SubCommand::with_name("output-trace")
+                .about("Prints an HTML page visualizing how a plugin printed a file. Use this for debugging plugins.")
+                .arg(
+                    Arg::with_name("file")
+                        .help("Path of the file to format.")
+                        .takes_value(true)
+                        .required(true)
+                )
+        )
         .subcommand(
             SubCommand::with_name("clear-cache")
                 .about("Deletes the plugin cache directory.")
         )
+        .subcommand(
+            SubCommand::with_name("upgrade-plugins")
+                .about("Upgrades the plugins in the configuration file to their latest versions.")
+        )
+        .subcommand(
+            SubCommand::with_name("config")
+                .about("Commands for maintaining the configuration file.")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("normalize")
+                        .about("Sorts and removes duplicates from the plugins, includes, and excludes of the configuration file.")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("plugin")
+                .about("Commands for debugging plugins.")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("replay")
+                        .about("Replays the format requests written by --debug-plugin-io against a plugin and outputs any responses that differ.")
+                        .arg(
+                            Arg::with_name("dump-dir")
+                                .help("Plugin directory created by --debug-plugin-io (ex. ./plugin-io/dprint-plugin-typescript).")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                )
+        )
         .subcommand(
             SubCommand::with_name("license")
                 .about("Outputs the software license.")
         )
+        .subcommand(
+            SubCommand::with_name("lsp")
+                .about("Starts a Language Server Protocol server over stdio for formatting in editors.")
+        )
         .subcommand(
             SubCommand::with_name("editor-info")
                 .setting(AppSettings::Hidden)
@@ -281,14 +740,23 @@ EXAMPLES:
                         .required(true)
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::with_name("trace-protocol")
+                        .long("trace-protocol")
+                        .value_name("file")
+                        .help("Logs each protocol message with timestamps and durations to the file.")
+                        .takes_value(true)
+                )
         )
         .arg(
             Arg::with_name("config")
                 .long("config")
                 .short("c")
-                .help("Path or url to JSON configuration file. Defaults to dprint.json or .dprint.json in current or ancestor directory when not provided.")
+                .help("Path or url to JSON configuration file. Defaults to dprint.json or .dprint.json in current or ancestor directory when not provided. Specify multiple times or as a comma separated list to merge configuration files in order.")
                 .global(true)
-                .takes_value(true),
+                .takes_value(true)
+                .number_of_values(1)
+                .multiple(true),
         )
         .arg(
             Arg::with_name("plugins")
@@ -306,6 +774,32 @@ EXAMPLES:
                 .global(true)
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("color")
+                .long("color")
+                .value_name("when")
+                .help("When to output colors.")
+                .possible_values(&["always", "auto", "never"])
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("output-format")
+                .long("output-format")
+                .value_name("format")
+                .help("How to output the results of check, fmt, and output-format-times. The json format outputs a JSON object per line.")
+                .possible_values(&["text", "json"])
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("debug-plugin-io")
+                .long("debug-plugin-io")
+                .value_name("directory")
+                .help("Writes each plugin's format requests and responses to the specified directory. Use this for debugging plugins. Note that this writes the text of the formatted files.")
+                .global(true)
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("version")
                 .short("v")
@@ -338,6 +832,9 @@ EXAMPLES:
 trait ClapExtensions {
   fn add_resolve_file_path_args(self) -> Self;
   fn add_incremental_arg(self) -> Self;
+  fn add_verify_whitespace_only_arg(self) -> Self;
+  fn add_experimental_archives_arg(self) -> Self;
+  fn add_warnings_as_errors_arg(self) -> Self;
 }
 
 impl<'a, 'b> ClapExtensions for clap::App<'a, 'b> {
@@ -358,6 +855,24 @@ impl<'a, 'b> ClapExtensions for clap::App<'a, 'b> {
           .takes_value(true)
           .multiple(true),
       )
+      .arg(
+        Arg::with_name("language")
+          .long("language")
+          .value_name("config-key")
+          .help("Only formats the files of the plugin with the specified configuration key (ex. json). Specify multiple times for multiple plugins.")
+          .takes_value(true)
+          .number_of_values(1)
+          .multiple(true),
+      )
+      .arg(
+        Arg::with_name("owned-by")
+          .long("owned-by")
+          .value_name("owner")
+          .help("Only formats the files owned by the specified team or user in the CODEOWNERS file (ex. @org/team). Specify multiple times for multiple owners.")
+          .takes_value(true)
+          .number_of_values(1)
+          .multiple(true),
+      )
       .arg(
         Arg::with_name("allow-node-modules")
           .long("allow-node-modules")
@@ -367,11 +882,49 @@ impl<'a, 'b> ClapExtensions for clap::App<'a, 'b> {
   }
 
   fn add_incremental_arg(self) -> Self {
+    use clap::Arg;
+    self
+      .arg(
+        Arg::with_name("incremental")
+          .long("incremental")
+          .help("Only format files when they change. This may alternatively be specified in the configuration file.")
+          .takes_value(false),
+      )
+      .arg(
+        Arg::with_name("incremental-cache-path")
+          .long("incremental-cache-path")
+          .value_name("path")
+          .help("Stores the incremental file at the specified path instead of in the cache directory (ex. to persist it between CI runs). Implies --incremental.")
+          .takes_value(true),
+      )
+  }
+
+  fn add_verify_whitespace_only_arg(self) -> Self {
+    use clap::Arg;
+    self.arg(
+      Arg::with_name("verify-whitespace-only")
+        .long("verify-whitespace-only")
+        .help("Errors for files where a plugin that declares it only changes whitespace changed more than whitespace. Useful for catching plugin bugs in CI.")
+        .takes_value(false),
+    )
+  }
+
+  fn add_experimental_archives_arg(self) -> Self {
+    use clap::Arg;
+    self.arg(
+      Arg::with_name("experimental-archives")
+        .long("experimental-archives")
+        .help("Experimental. Treats the file patterns as patterns of files inside zip or tar archives (ex. 'assets.zip!**/*.json') and rewrites the archives deterministically.")
+        .takes_value(false),
+    )
+  }
+
+  fn add_warnings_as_errors_arg(self) -> Self {
     use clap::Arg;
     self.arg(
-      Arg::with_name("incremental")
-        .long("incremental")
-        .help("Only format files when they change. This may alternatively be specified in the configuration file.")
+      Arg::with_name("warnings-as-errors")
+        .long("warnings-as-errors")
+        .help("Fails when there are warnings (ex. skipped files or plugin diagnostics). This may alternatively be specified in the configuration file.")
         .takes_value(false),
     )
   }
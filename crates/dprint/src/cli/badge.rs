@@ -0,0 +1,114 @@
+use dprint_core::types::ErrBox;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::environment::Environment;
+
+/// Statistics about how many of the checked files are formatted.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckStats {
+  pub checked_files: usize,
+  pub not_formatted_files: usize,
+  /// Percentage of the checked files that are formatted, rounded down.
+  pub clean_percentage: usize,
+}
+
+impl CheckStats {
+  pub fn new(checked_files: usize, not_formatted_files: usize) -> CheckStats {
+    let clean_percentage = if checked_files == 0 {
+      100
+    } else {
+      // round down so that 100% is only shown when every file is formatted
+      (checked_files - not_formatted_files) * 100 / checked_files
+    };
+    CheckStats {
+      checked_files,
+      not_formatted_files,
+      clean_percentage,
+    }
+  }
+}
+
+/// Writes an SVG badge (ex. "formatting: 98% clean") to the file path and the
+/// statistics as JSON to the same file path with a `.json` extension.
+pub fn write_check_badge(file_path: &Path, stats: &CheckStats, environment: &impl Environment) -> Result<(), ErrBox> {
+  if let Some(parent) = file_path.parent() {
+    environment.mk_dir_all(parent)?;
+  }
+  environment.write_file(file_path, &render_badge_svg("formatting", &format!("{}% clean", stats.clean_percentage), get_badge_color(stats)))?;
+  environment.write_file(&file_path.with_extension("json"), &serde_json::to_string_pretty(stats)?)?;
+  Ok(())
+}
+
+fn get_badge_color(stats: &CheckStats) -> &'static str {
+  if stats.not_formatted_files == 0 {
+    "#4c1"
+  } else if stats.clean_percentage >= 90 {
+    "#dfb317"
+  } else {
+    "#e05d44"
+  }
+}
+
+/// Renders a badge in the style of https://shields.io
+fn render_badge_svg(label: &str, message: &str, color: &str) -> String {
+  let label_width = get_text_width(label);
+  let message_width = get_text_width(message);
+  let width = label_width + message_width;
+  format!(
+    concat!(
+      r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">"#,
+      r#"<title>{label}: {message}</title>"#,
+      r#"<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>"#,
+      r#"<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>"#,
+      r#"<g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g>"#,
+      r#"<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">"#,
+      r#"<text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text><text x="{label_x}" y="14">{label}</text>"#,
+      r#"<text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text><text x="{message_x}" y="14">{message}</text>"#,
+      "</g></svg>\n"
+    ),
+    width = width,
+    label = label,
+    message = message,
+    color = color,
+    label_width = label_width,
+    message_width = message_width,
+    label_x = label_width as f32 / 2.0,
+    message_x = label_width as f32 + message_width as f32 / 2.0,
+  )
+}
+
+/// Gets an approximate width of the text with padding in an 11px Verdana font.
+fn get_text_width(text: &str) -> usize {
+  text.chars().count() * 7 + 10
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_get_clean_percentage() {
+    assert_eq!(CheckStats::new(0, 0).clean_percentage, 100);
+    assert_eq!(CheckStats::new(50, 1).clean_percentage, 98);
+    assert_eq!(CheckStats::new(1000, 1).clean_percentage, 99);
+    assert_eq!(CheckStats::new(4, 4).clean_percentage, 0);
+  }
+
+  #[test]
+  fn should_write_badge_and_stats() {
+    let environment = TestEnvironment::new();
+    write_check_badge(Path::new("/badges/formatting.svg"), &CheckStats::new(50, 1), &environment).unwrap();
+    let svg = environment.read_file("/badges/formatting.svg").unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("formatting: 98% clean"));
+    assert!(svg.contains("#dfb317"));
+    assert_eq!(
+      environment.read_file("/badges/formatting.json").unwrap(),
+      "{\n  \"checkedFiles\": 50,\n  \"notFormattedFiles\": 1,\n  \"cleanPercentage\": 98\n}"
+    );
+  }
+}
@@ -0,0 +1,52 @@
+use dprint_cli_core::checksums::get_sha256_checksum;
+use dprint_core::types::ErrBox;
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::environment::Environment;
+
+/// A record of a file that was changed by `dprint fmt`.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeLogEntry {
+  pub file_path: String,
+  /// Name of the plugin the file was formatted with.
+  pub plugin: String,
+  pub bytes_before: usize,
+  pub bytes_after: usize,
+  pub duration_ms: u64,
+  /// SHA-256 hash of the file's bytes before formatting.
+  pub hash_before: String,
+  /// SHA-256 hash of the file's bytes after formatting.
+  pub hash_after: String,
+}
+
+impl ChangeLogEntry {
+  pub fn new(file_path: &Path, plugin: String, text_before: &str, text_after: &str, duration: Duration) -> ChangeLogEntry {
+    ChangeLogEntry {
+      file_path: file_path.display().to_string(),
+      plugin,
+      bytes_before: text_before.len(),
+      bytes_after: text_after.len(),
+      duration_ms: duration.as_millis() as u64,
+      hash_before: get_sha256_checksum(text_before.as_bytes()),
+      hash_after: get_sha256_checksum(text_after.as_bytes()),
+    }
+  }
+}
+
+#[derive(Serialize)]
+struct ChangeLog<'a> {
+  files: &'a [ChangeLogEntry],
+}
+
+/// Writes the changed files as JSON to the file path sorted by file path.
+pub fn write_change_log(file_path: &Path, mut entries: Vec<ChangeLogEntry>, environment: &impl Environment) -> Result<(), ErrBox> {
+  entries.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+  if let Some(parent) = file_path.parent() {
+    environment.mk_dir_all(parent)?;
+  }
+  environment.write_file(file_path, &serde_json::to_string_pretty(&ChangeLog { files: &entries })?)?;
+  Ok(())
+}
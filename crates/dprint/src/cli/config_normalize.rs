@@ -0,0 +1,243 @@
+use dprint_core::types::ErrBox;
+use jsonc_parser::ast::{ObjectPropName, Value};
+use jsonc_parser::{parse_to_ast, ParseOptions};
+
+use crate::cache::Cache;
+use crate::environment::Environment;
+
+use super::configuration::resolve_main_config_path;
+use super::CliArgs;
+
+/// Sorts the plugins and removes the duplicates from the plugins, includes, and excludes of the
+/// configuration file, writing them with one item per line. The rest of the configuration file's
+/// text is left as-is in order to maintain the formatting and comments.
+pub fn normalize_config_file<TEnvironment: Environment>(args: &CliArgs, cache: &Cache<TEnvironment>, environment: &TEnvironment) -> Result<(), ErrBox> {
+  let config_path = resolve_main_config_path(args, cache, environment)?;
+  if config_path.resolved_path.is_remote() {
+    return err!("Cannot normalize a remote configuration file.");
+  }
+  let config_file_path = config_path.resolved_path.file_path;
+  let config_file_text = match environment.read_file(&config_file_path) {
+    Ok(text) => text,
+    Err(err) => return err!("Error reading configuration file {}. {}", config_file_path.display(), err.to_string()),
+  };
+  let new_config_file_text = match get_normalized_config_text(&config_file_text, environment) {
+    Ok(text) => text,
+    Err(err) => return err!("Error normalizing configuration file {}. {}", config_file_path.display(), err.to_string()),
+  };
+
+  if new_config_file_text == config_file_text {
+    environment.log("The configuration file is already normalized.");
+  } else {
+    environment.write_file(&config_file_path, &new_config_file_text)?;
+    environment.log(&format!("Normalized {}", config_file_path.display()));
+  }
+
+  Ok(())
+}
+
+fn get_normalized_config_text(text: &str, environment: &impl Environment) -> Result<String, ErrBox> {
+  let parse_result = parse_to_ast(
+    text,
+    &ParseOptions {
+      comments: false,
+      tokens: false,
+    },
+  )?;
+  let root = match parse_result.value {
+    Some(Value::Object(root)) => root,
+    _ => return err!("Expected a root object."),
+  };
+
+  let mut replacements = Vec::new();
+  for prop in root.properties.iter() {
+    let prop_name = match &prop.name {
+      ObjectPropName::String(lit) => &*lit.value,
+      ObjectPropName::Word(lit) => &*lit.value,
+    };
+    if !matches!(prop_name, "plugins" | "includes" | "excludes") {
+      continue;
+    }
+    let array = match &prop.value {
+      Value::Array(array) => array,
+      _ => return err!("Expected the '{}' property to be an array of strings.", prop_name),
+    };
+
+    let mut items = Vec::with_capacity(array.elements.len());
+    // the text between the items, which only has commas and whitespace unless there are comments
+    let mut separator_text = String::new();
+    let mut last_end = array.range.start + 1;
+    for element in array.elements.iter() {
+      match element {
+        Value::StringLit(lit) => {
+          separator_text.push_str(&text[last_end..lit.range.start]);
+          last_end = lit.range.end;
+          items.push(lit.value.to_string());
+        }
+        _ => return err!("Expected the '{}' property to be an array of strings.", prop_name),
+      }
+    }
+    separator_text.push_str(&text[last_end..array.range.end - 1]);
+    if separator_text.chars().any(|c| !c.is_whitespace() && c != ',') {
+      environment.log_warning(&format!(
+        "Warning: Skipped normalizing the '{}' property because it contains comments.",
+        prop_name
+      ));
+      continue;
+    }
+
+    let items = if prop_name == "plugins" {
+      get_normalized_plugins(items)
+    } else {
+      get_normalized_patterns(items)
+    };
+    let array_text = get_array_text(&items, get_line_indent(text, prop.range.start), text.contains("\r\n"));
+    replacements.push((array.range.start, array.range.end, array_text));
+  }
+
+  let mut new_text = text.to_string();
+  // replace from the end so the positions of the earlier properties stay the same
+  for (start, end, replacement) in replacements.into_iter().rev() {
+    new_text.replace_range(start..end, &replacement);
+  }
+  Ok(new_text)
+}
+
+fn get_normalized_plugins(mut plugins: Vec<String>) -> Vec<String> {
+  plugins.sort();
+  plugins.dedup();
+  plugins
+}
+
+fn get_normalized_patterns(patterns: Vec<String>) -> Vec<String> {
+  let mut result: Vec<String> = Vec::with_capacity(patterns.len());
+  // keep the last of any duplicates because the last matching pattern wins
+  for pattern in patterns.into_iter().rev().map(|pattern| get_normalized_pattern(&pattern)) {
+    if !result.contains(&pattern) {
+      result.push(pattern);
+    }
+  }
+  result.reverse();
+  result
+}
+
+/// Normalizes a pattern (ex. `.\src\\**/./*.ts` to `./src/**/*.ts`).
+fn get_normalized_pattern(pattern: &str) -> String {
+  // backslashes are always treated as path separators
+  let mut pattern = pattern.trim().replace("\\", "/");
+  while pattern.contains("//") {
+    pattern = pattern.replace("//", "/");
+  }
+  while pattern.contains("/./") {
+    pattern = pattern.replace("/./", "/");
+  }
+  pattern
+}
+
+fn get_array_text(items: &[String], indent: &str, use_crlf: bool) -> String {
+  if items.is_empty() {
+    return "[]".to_string();
+  }
+
+  let new_line = if use_crlf { "\r\n" } else { "\n" };
+  // the properties of the root object have one level of indentation
+  let item_indent = if indent.is_empty() { "  ".to_string() } else { indent.repeat(2) };
+  let items_text = items
+    .iter()
+    .map(|item| format!("{}{}", item_indent, serde_json::to_string(item).unwrap()))
+    .collect::<Vec<_>>()
+    .join(&format!(",{}", new_line));
+  format!("[{}{}{}{}]", new_line, items_text, new_line, indent)
+}
+
+fn get_line_indent(text: &str, pos: usize) -> &str {
+  let line_start = text[..pos].rfind('\n').map(|index| index + 1).unwrap_or(0);
+  let line_text = &text[line_start..pos];
+  &line_text[..line_text.len() - line_text.trim_start().len()]
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_normalize_config_text() {
+    let environment = TestEnvironment::new();
+    let text = r#"{
+  // some comment
+  "lineWidth": 100,
+  "includes": ["src\\**\\*.ts", "!src/generated/**", "src//**/*.ts"],
+  "excludes": [],
+  "typescript": {
+    "plugins": ["not", "changed"]
+  },
+  "plugins": [
+    "https://plugins.dprint.dev/typescript-0.19.0.wasm",
+    "https://plugins.dprint.dev/json-0.2.3.wasm",
+    "https://plugins.dprint.dev/typescript-0.19.0.wasm"
+  ]
+}
+"#;
+    assert_eq!(
+      get_normalized_config_text(text, &environment).unwrap(),
+      r#"{
+  // some comment
+  "lineWidth": 100,
+  "includes": [
+    "!src/generated/**",
+    "src/**/*.ts"
+  ],
+  "excludes": [],
+  "typescript": {
+    "plugins": ["not", "changed"]
+  },
+  "plugins": [
+    "https://plugins.dprint.dev/json-0.2.3.wasm",
+    "https://plugins.dprint.dev/typescript-0.19.0.wasm"
+  ]
+}
+"#
+    );
+  }
+
+  #[test]
+  fn should_use_indentation_of_file() {
+    let environment = TestEnvironment::new();
+    let text = "{\r\n    \"excludes\": [\"a\", \"b\"]\r\n}";
+    assert_eq!(
+      get_normalized_config_text(text, &environment).unwrap(),
+      "{\r\n    \"excludes\": [\r\n        \"a\",\r\n        \"b\"\r\n    ]\r\n}"
+    );
+  }
+
+  #[test]
+  fn should_skip_arrays_with_comments() {
+    let environment = TestEnvironment::new();
+    let text = r#"{
+  "plugins": [
+    "b", // comment
+    "a"
+  ]
+}"#;
+    assert_eq!(get_normalized_config_text(text, &environment).unwrap(), text);
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Warning: Skipped normalizing the 'plugins' property because it contains comments."]
+    );
+  }
+
+  #[test]
+  fn should_error_for_non_string_items() {
+    let environment = TestEnvironment::new();
+    let err = get_normalized_config_text(r#"{ "includes": [1] }"#, &environment).err().unwrap();
+    assert_eq!(err.to_string(), "Expected the 'includes' property to be an array of strings.");
+  }
+
+  #[test]
+  fn should_normalize_pattern() {
+    assert_eq!(get_normalized_pattern(".\\src\\\\**/./*.ts"), "./src/**/*.ts");
+    assert_eq!(get_normalized_pattern(" **/target/ "), "**/target/");
+  }
+}
@@ -0,0 +1,174 @@
+use dprint_core::configuration::{ConfigKeyMap, GlobalConfiguration};
+use dprint_core::types::ErrBox;
+use serde_json::{Map, Value};
+
+/// Global configuration properties that plugins are expected to inherit when they're not
+/// specified in the plugin's configuration.
+const INHERITED_GLOBAL_KEYS: [&str; 4] = ["lineWidth", "indentWidth", "useTabs", "newLineKind"];
+
+/// Where the value of a property in a plugin's resolved configuration came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigValueSource {
+  /// Specified in the plugin's configuration.
+  Plugin,
+  /// Inherited from the global configuration.
+  Global,
+  /// The plugin's default value.
+  Default,
+}
+
+impl ConfigValueSource {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ConfigValueSource::Plugin => "plugin",
+      ConfigValueSource::Global => "global",
+      ConfigValueSource::Default => "default",
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ResolvedConfigValue {
+  pub key: String,
+  pub value: Value,
+  pub source: ConfigValueSource,
+}
+
+/// A global configuration property the plugin resolved to a different value even though
+/// the plugin's configuration didn't specify it.
+#[derive(Debug, PartialEq)]
+pub struct UninheritedGlobalValue {
+  pub key: String,
+  pub global_value: Value,
+  pub resolved_value: Value,
+}
+
+/// Gets the properties of the plugin's resolved configuration along with where each value came from.
+pub fn get_resolved_config_values(
+  resolved_config_text: &str,
+  plugin_config: &ConfigKeyMap,
+  global_config: &GlobalConfiguration,
+) -> Result<Vec<ResolvedConfigValue>, ErrBox> {
+  let resolved_config = parse_resolved_config(resolved_config_text)?;
+  let global_values = get_inherited_global_values(global_config)?;
+  let mut values = Vec::with_capacity(resolved_config.len());
+  for (key, value) in resolved_config {
+    let source = if plugin_config.contains_key(&key) {
+      ConfigValueSource::Plugin
+    } else if global_values.get(&key) == Some(&value) {
+      ConfigValueSource::Global
+    } else {
+      ConfigValueSource::Default
+    };
+    values.push(ResolvedConfigValue { key, value, source });
+  }
+  values.sort_by(|a, b| a.key.cmp(&b.key));
+  Ok(values)
+}
+
+/// Gets the configured global properties the plugin didn't inherit.
+pub fn get_uninherited_global_values(
+  resolved_config_text: &str,
+  plugin_config: &ConfigKeyMap,
+  global_config: &GlobalConfiguration,
+) -> Result<Vec<UninheritedGlobalValue>, ErrBox> {
+  let resolved_config = parse_resolved_config(resolved_config_text)?;
+  let mut values = Vec::new();
+  for (key, global_value) in get_inherited_global_values(global_config)? {
+    if plugin_config.contains_key(&key) {
+      continue;
+    }
+    // plugins without the property don't support it, so there's nothing to inherit
+    if let Some(resolved_value) = resolved_config.get(&key) {
+      if *resolved_value != global_value {
+        values.push(UninheritedGlobalValue {
+          key,
+          global_value,
+          resolved_value: resolved_value.clone(),
+        });
+      }
+    }
+  }
+  values.sort_by(|a, b| a.key.cmp(&b.key));
+  Ok(values)
+}
+
+fn parse_resolved_config(resolved_config_text: &str) -> Result<Map<String, Value>, ErrBox> {
+  match serde_json::from_str(resolved_config_text)? {
+    Value::Object(resolved_config) => Ok(resolved_config),
+    _ => err!("Expected the plugin's resolved configuration to be an object."),
+  }
+}
+
+/// Gets the global properties plugins inherit that are specified in the configuration.
+fn get_inherited_global_values(global_config: &GlobalConfiguration) -> Result<Map<String, Value>, ErrBox> {
+  let global_values = match serde_json::to_value(global_config)? {
+    Value::Object(global_values) => global_values,
+    _ => unreachable!(),
+  };
+  Ok(
+    global_values
+      .into_iter()
+      .filter(|(key, value)| !value.is_null() && INHERITED_GLOBAL_KEYS.contains(&key.as_str()))
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod test {
+  use dprint_core::configuration::{ConfigKeyValue, NewLineKind};
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+  use std::collections::HashMap;
+
+  use super::*;
+
+  fn get_global_config() -> GlobalConfiguration {
+    GlobalConfiguration {
+      line_width: Some(80),
+      use_tabs: Some(true),
+      indent_width: None,
+      new_line_kind: Some(NewLineKind::CarriageReturnLineFeed),
+      trim_leading_blank_lines: None,
+      single_trailing_new_line: None,
+      timeout_ms: None,
+    }
+  }
+
+  #[test]
+  fn should_get_sources_of_resolved_config_values() {
+    let mut plugin_config = HashMap::new();
+    plugin_config.insert("semiColons".to_string(), ConfigKeyValue::from_str("prefer"));
+    plugin_config.insert("useTabs".to_string(), ConfigKeyValue::from_bool(true));
+    let resolved_config_text = r#"{"semiColons":"prefer","useTabs":true,"lineWidth":80,"indentWidth":4,"newLineKind":"lf","quoteStyle":"double"}"#;
+    let values = get_resolved_config_values(resolved_config_text, &plugin_config, &get_global_config()).unwrap();
+    let values = values.iter().map(|value| (value.key.as_str(), value.source)).collect::<Vec<_>>();
+    assert_eq!(
+      values,
+      vec![
+        ("indentWidth", ConfigValueSource::Default),
+        ("lineWidth", ConfigValueSource::Global),
+        ("newLineKind", ConfigValueSource::Default),
+        ("quoteStyle", ConfigValueSource::Default),
+        ("semiColons", ConfigValueSource::Plugin),
+        ("useTabs", ConfigValueSource::Plugin),
+      ]
+    );
+  }
+
+  #[test]
+  fn should_get_uninherited_global_values() {
+    let mut plugin_config = HashMap::new();
+    plugin_config.insert("useTabs".to_string(), ConfigKeyValue::from_bool(false));
+    let resolved_config_text = r#"{"useTabs":false,"lineWidth":80,"indentWidth":4,"newLineKind":"lf"}"#;
+    let values = get_uninherited_global_values(resolved_config_text, &plugin_config, &get_global_config()).unwrap();
+    assert_eq!(
+      values,
+      vec![UninheritedGlobalValue {
+        key: "newLineKind".to_string(),
+        global_value: json!("crlf"),
+        resolved_value: json!("lf"),
+      }]
+    );
+  }
+}
@@ -2,4 +2,4 @@ mod resolve_config;
 mod resolve_main_config_path;
 
 pub use resolve_config::*;
-use resolve_main_config_path::*;
+pub use resolve_main_config_path::*;
@@ -1,18 +1,22 @@
-use crossterm::style::Stylize;
-use dprint_core::configuration::ConfigKeyValue;
+use dprint_cli_core::styling::bold;
+use dprint_core::configuration::{resolve_global_config, ConfigKeyValue, ResolveGlobalConfigOptions};
 use dprint_core::types::ErrBox;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use url::Url;
 
 use crate::cache::Cache;
 use crate::cli::CliArgs;
-use crate::configuration::{deserialize_config, ConfigMap, ConfigMapValue};
+use crate::configuration::{deserialize_config, ConfigMap, ConfigMapValue, ConfigOverride, RawPluginConfig};
 use crate::environment::Environment;
-use crate::plugins::{parse_plugin_source_reference, PluginSourceReference};
-use crate::utils::{resolve_url_or_file_path, PathSource, ResolvedPath};
+use crate::plugins::{parse_plugin_path_source, parse_plugin_source_reference, PluginSourceReference};
+use crate::utils::{is_negated_glob, resolve_url_or_file_path, InvalidUtf8Policy, MixedLineEndingsPolicy, PathSource, Phase, ResolvedPath, TextEncoding};
 
 use super::resolve_main_config_path;
 
+/// Files with a line longer than this many bytes (ex. minified files) are skipped by default.
+const DEFAULT_MAX_LINE_LENGTH: usize = 1_000_000;
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct ResolvedConfig {
   pub resolved_path: ResolvedPath,
@@ -20,8 +24,39 @@ pub struct ResolvedConfig {
   pub base_path: PathBuf,
   pub includes: Vec<String>,
   pub excludes: Vec<String>,
+  /// If the built-in excludes (ex. `node_modules` and `dist` directories) should be used.
+  pub use_default_excludes: bool,
+  /// If the `.ignore` files used by other tools (ex. ripgrep) should be used along with `.dprintignore` files.
+  pub use_dot_ignore_files: bool,
   pub plugins: Vec<PluginSourceReference>,
   pub incremental: bool,
+  /// Patterns of the files that should always be formatted even when incremental
+  /// formatting is enabled and they haven't changed.
+  pub force_format: Vec<String>,
+  /// If the next plugin matching a file should be used when the first one fails formatting it.
+  pub fallback_on_error: bool,
+  /// If configuration should be filled in from the Prettier and .editorconfig files in the base path.
+  pub prettier_compat: bool,
+  /// If formatted output should be cached based on the file text and plugins.
+  pub result_cache: bool,
+  /// Url of a remote cache to get formatted output from.
+  pub result_cache_remote_url: Option<String>,
+  /// Configuration that applies to the files matching its patterns.
+  pub overrides: Vec<ConfigOverride>,
+  /// What to do with files that contain invalid UTF-8.
+  pub invalid_utf8: InvalidUtf8Policy,
+  /// What to do with files that contain both CRLF and LF line endings.
+  pub mixed_line_endings: MixedLineEndingsPolicy,
+  /// Maximum number of plugins that keep instances running in the editor service.
+  pub plugin_pool_size: Option<usize>,
+  /// Files with a line longer than this many bytes are skipped. `None` when disabled.
+  pub max_line_length: Option<usize>,
+  /// If `check` and `fmt` should fail when they output warnings.
+  pub warnings_as_errors: bool,
+  /// The encodings of the files matching each pattern, ordered from the most to least specific pattern.
+  pub encodings: Vec<(String, TextEncoding)>,
+  /// The file patterns formatted by each plugin, by configuration key, that replace the plugin's default file extensions and names.
+  pub associations: HashMap<String, Vec<String>>,
   pub config_map: ConfigMap,
 }
 
@@ -29,9 +64,18 @@ pub fn resolve_config_from_args<TEnvironment: Environment>(
   args: &CliArgs,
   cache: &Cache<TEnvironment>,
   environment: &TEnvironment,
+) -> Result<ResolvedConfig, ErrBox> {
+  environment
+    .phase_timings()
+    .measure(Phase::ConfigResolution, || resolve_config_from_args_inner(args, cache, environment))
+}
+
+fn resolve_config_from_args_inner<TEnvironment: Environment>(
+  args: &CliArgs,
+  cache: &Cache<TEnvironment>,
+  environment: &TEnvironment,
 ) -> Result<ResolvedConfig, ErrBox> {
   let resolved_config_path = resolve_main_config_path(args, cache, environment)?;
-  let base_source = resolved_config_path.resolved_path.source.parent();
   let config_file_path = &resolved_config_path.resolved_path.file_path;
   let main_config_map = get_config_map_from_path(config_file_path, environment)?;
 
@@ -43,15 +87,26 @@ pub fn resolve_config_from_args<TEnvironment: Environment>(
         HashMap::new()
       } else {
         return err!(
-          "No config file found at {}. Did you mean to create (dprint init) or specify one (--config <path>)?\n  Error: {}",
-          config_file_path.display(),
-          err.to_string(),
+          "{}",
+          environment
+            .messages()
+            .get("config.notFound", &[("path", &config_file_path.display()), ("error", &err)])
         );
       }
     }
   };
 
+  let base_source = get_config_base_source(&resolved_config_path.resolved_path, &mut main_config_map, environment)?;
+
   let plugins_vec = take_plugins_array_from_config_map(&mut main_config_map, &base_source)?; // always take this out of the config map
+  let plugin_overrides = take_plugin_overrides_from_config_map(&mut main_config_map, &base_source)?;
+  let plugin_mirrors = take_plugin_mirrors_from_config_map(&mut main_config_map)?;
+  // a remote configuration should never be able to change where plugins are loaded from
+  let (plugin_overrides, plugin_mirrors) = if resolved_config_path.resolved_path.is_local() {
+    (plugin_overrides, plugin_mirrors)
+  } else {
+    (Vec::new(), Vec::new())
+  };
   let plugins = filter_duplicate_plugin_sources(if args.plugins.is_empty() {
     // filter out any non-wasm plugins from remote config
     if !resolved_config_path.resolved_path.is_local() {
@@ -81,14 +136,33 @@ pub fn resolve_config_from_args<TEnvironment: Environment>(
     let removed_excludes = main_config_map.remove("excludes").is_some(); // NEVER REMOVE THIS STATEMENT
     let was_removed = removed_includes || removed_excludes;
     if was_removed && resolved_config_path.resolved_path.is_first_download {
-      environment.log_error(&get_warn_includes_excludes_message());
+      environment.log_warning(&get_warn_includes_excludes_message());
     }
   }
   // =========
 
   let includes = take_array_from_config_map(&mut main_config_map, "includes")?;
   let excludes = take_array_from_config_map(&mut main_config_map, "excludes")?;
+  // a remote configuration should never be able to expand what files get formatted
+  let use_default_excludes = take_bool_from_config_map(&mut main_config_map, "useDefaultExcludes", true)? || !resolved_config_path.resolved_path.is_local();
+  let use_dot_ignore_files = take_bool_from_config_map(&mut main_config_map, "useDotIgnoreFiles", false)?;
   let incremental = take_bool_from_config_map(&mut main_config_map, "incremental", false)?;
+  let force_format = take_array_from_config_map(&mut main_config_map, "forceFormat")?;
+  let fallback_on_error = take_bool_from_config_map(&mut main_config_map, "fallbackOnError", false)?;
+  let prettier_compat = take_bool_from_config_map(&mut main_config_map, "prettierCompat", false)?;
+  let (result_cache, result_cache_remote_url) = take_result_cache_from_config_map(&mut main_config_map)?;
+  let invalid_utf8 = take_invalid_utf8_from_config_map(&mut main_config_map)?;
+  let mixed_line_endings = take_mixed_line_endings_from_config_map(&mut main_config_map)?;
+  let plugin_pool_size = take_plugin_pool_size_from_config_map(&mut main_config_map)?;
+  let max_line_length = take_max_line_length_from_config_map(&mut main_config_map)?;
+  let warnings_as_errors = take_bool_from_config_map(&mut main_config_map, "warningsAsErrors", false)?;
+  let encodings = take_encodings_from_config_map(&mut main_config_map)?;
+  // a remote configuration should never be able to change what gets written to files
+  let (result_cache_remote_url, encodings) = if resolved_config_path.resolved_path.is_local() {
+    (result_cache_remote_url, encodings)
+  } else {
+    (None, Vec::new())
+  };
   main_config_map.remove("projectType"); // this was an old config property that's no longer used
   let extends = take_extends(&mut main_config_map)?;
   let mut resolved_config = ResolvedConfig {
@@ -97,37 +171,144 @@ pub fn resolve_config_from_args<TEnvironment: Environment>(
     config_map: main_config_map,
     includes,
     excludes,
+    use_default_excludes,
+    use_dot_ignore_files,
     plugins,
     incremental,
+    force_format,
+    fallback_on_error,
+    prettier_compat,
+    result_cache,
+    result_cache_remote_url,
+    overrides: Vec::new(),
+    invalid_utf8,
+    mixed_line_endings,
+    plugin_pool_size,
+    max_line_length,
+    warnings_as_errors,
+    encodings,
+    associations: HashMap::new(),
   };
 
   // resolve extends
-  resolve_extends(&mut resolved_config, extends, &base_source, cache, environment)?;
+  let mut extends_chain = vec![get_extends_chain_item(&resolved_config.resolved_path, environment)];
+  resolve_extends(&mut resolved_config, extends, &base_source, &mut extends_chain, cache, environment)?;
+  resolve_config_overlays(&mut resolved_config, &args.config_overlays, cache, environment)?;
+  apply_plugin_overrides(&mut resolved_config, plugin_overrides, environment);
+  apply_plugin_mirrors(&mut resolved_config, &plugin_mirrors, environment)?;
+  resolved_config.overrides = take_overrides_from_config_map(&mut resolved_config.config_map)?;
+  resolved_config.associations = take_associations_from_config_map(&mut resolved_config.config_map);
   remove_locked_properties(&mut resolved_config);
 
   Ok(resolved_config)
 }
 
+/// Applies configuration provided on the command line (ex. via `--plugin-config`) on top of
+/// the resolved configuration. Properties in the provided text take precedence.
+pub fn apply_config_overrides(resolved_config: &mut ResolvedConfig, config_text: &str) -> Result<(), ErrBox> {
+  let config_map = match deserialize_config(config_text) {
+    Ok(map) => map,
+    Err(err) => return err!("Error deserializing plugin config. {}", err.to_string()),
+  };
+
+  for (key, value) in config_map {
+    if matches!(key.as_str(), "plugins" | "includes" | "excludes" | "useDefaultExcludes" | "useDotIgnoreFiles" | "extends" | "incremental" | "forceFormat" | "fallbackOnError" | "prettierCompat" | "experimentalResultCache" | "pathsRelativeToSymlink" | "overrides" | "invalidUtf8" | "mixedLineEndings" | "pluginPoolSize" | "maxLineLength" | "warningsAsErrors" | "pluginOverrides" | "pluginMirrors" | "encodings") {
+      return err!("The '{}' property is not supported in the plugin config overrides.", key);
+    }
+
+    match value {
+      ConfigMapValue::PluginConfig(_) => {
+        return err!("The 'associations' property of '{}' is not supported in the plugin config overrides.", key);
+      }
+      ConfigMapValue::HashMap(obj) => {
+        if let Some(ConfigMapValue::HashMap(resolved_config_obj)) = resolved_config.config_map.get_mut(&key) {
+          resolved_config_obj.extend(obj);
+        } else {
+          resolved_config.config_map.insert(key, ConfigMapValue::HashMap(obj));
+        }
+      }
+      value => {
+        resolved_config.config_map.insert(key, value);
+      }
+    }
+  }
+
+  remove_locked_properties(resolved_config);
+
+  Ok(())
+}
+
 fn resolve_extends<TEnvironment: Environment>(
   resolved_config: &mut ResolvedConfig,
   extends: Vec<String>,
   base_path: &PathSource,
+  extends_chain: &mut Vec<ExtendsChainItem>,
   cache: &Cache<TEnvironment>,
   environment: &TEnvironment,
 ) -> Result<(), ErrBox> {
   for url_or_file_path in extends {
     let resolved_path = resolve_url_or_file_path(&url_or_file_path, base_path, cache, environment)?;
-    match handle_config_file(&resolved_path, resolved_config, cache, environment) {
-      Ok(extends) => extends,
-      Err(err) => return err!("Error with '{}'. {}", resolved_path.source.display(), err.to_string()),
+    let chain_item = get_extends_chain_item(&resolved_path, environment);
+    // only the configuration files currently being resolved are checked, so it's ok
+    // for two files to extend the same base configuration
+    if extends_chain.iter().any(|item| item.file_path == chain_item.file_path) {
+      let mut chain_text = extends_chain.iter().map(|item| item.display.as_str()).collect::<Vec<_>>();
+      chain_text.push(&chain_item.display);
+      return err!("Circular extends detected: {}", chain_text.join(" -> "));
+    }
+
+    extends_chain.push(chain_item);
+    let result = handle_config_file(&resolved_path, resolved_config, extends_chain, cache, environment);
+    extends_chain.pop();
+    if let Err(err) = result {
+      return err!("Error with '{}'. {}", resolved_path.source.display(), err.to_string());
+    }
+  }
+  Ok(())
+}
+
+/// Merges the additional configuration files specified on the command line on top of the
+/// configuration in order. These are handled like `extends`, but their properties take precedence.
+fn resolve_config_overlays<TEnvironment: Environment>(
+  resolved_config: &mut ResolvedConfig,
+  config_overlays: &[String],
+  cache: &Cache<TEnvironment>,
+  environment: &TEnvironment,
+) -> Result<(), ErrBox> {
+  let base_path = PathSource::new_local(environment.cwd());
+  for url_or_file_path in config_overlays {
+    // resolve the overlay into an empty configuration map, then merge the previous configuration into it
+    let config_map = std::mem::take(&mut resolved_config.config_map);
+    resolve_extends(resolved_config, vec![url_or_file_path.clone()], &base_path, &mut Vec::new(), cache, environment)?;
+    if let Err(err) = merge_config_map(&mut resolved_config.config_map, config_map) {
+      return err!("Error with '{}'. {}", url_or_file_path, err.to_string());
     }
   }
   Ok(())
 }
 
+struct ExtendsChainItem {
+  /// Path used to identify the configuration file. For remote files, this is the cached file.
+  file_path: PathBuf,
+  display: String,
+}
+
+fn get_extends_chain_item(resolved_path: &ResolvedPath, environment: &impl Environment) -> ExtendsChainItem {
+  let file_path = environment
+    .canonicalize(&resolved_path.file_path)
+    .unwrap_or_else(|_| resolved_path.file_path.clone());
+  let display = if resolved_path.is_local() {
+    file_path.display().to_string()
+  } else {
+    resolved_path.source.display()
+  };
+  ExtendsChainItem { file_path, display }
+}
+
 fn handle_config_file<'a, TEnvironment: Environment>(
   resolved_path: &ResolvedPath,
   resolved_config: &mut ResolvedConfig,
+  extends_chain: &mut Vec<ExtendsChainItem>,
   cache: &Cache<TEnvironment>,
   environment: &TEnvironment,
 ) -> Result<(), ErrBox> {
@@ -137,9 +318,11 @@ fn handle_config_file<'a, TEnvironment: Environment>(
     Err(err) => return Err(err),
   };
   let extends = take_extends(&mut new_config_map)?;
+  let base_source = get_config_base_source(resolved_path, &mut new_config_map, environment)?;
 
   // Discard any properties that shouldn't be inherited
   new_config_map.remove("projectType");
+  new_config_map.remove("pluginOverrides");
   // IMPORTANT
   // =========
   // Remove the includes and excludes from all referenced configuration since
@@ -148,9 +331,10 @@ fn handle_config_file<'a, TEnvironment: Environment>(
   // control over what files get formatted.
   new_config_map.remove("includes"); // NEVER REMOVE THIS STATEMENT
   new_config_map.remove("excludes"); // NEVER REMOVE THIS STATEMENT
-                                     // Also remove any non-wasm plugins, but only for remote configurations.
-                                     // The assumption here is that the user won't be malicious to themselves.
-  let plugins = take_plugins_array_from_config_map(&mut new_config_map, &resolved_path.source.parent())?;
+  new_config_map.remove("useDefaultExcludes");
+  // Also remove any non-wasm plugins, but only for remote configurations.
+  // The assumption here is that the user won't be malicious to themselves.
+  let plugins = take_plugins_array_from_config_map(&mut new_config_map, &base_source)?;
   let plugins = if !resolved_path.is_local() {
     filter_non_wasm_plugins(plugins, environment)
   } else {
@@ -161,57 +345,107 @@ fn handle_config_file<'a, TEnvironment: Environment>(
   // combine plugins
   resolved_config.plugins.extend(plugins);
 
-  for (key, value) in new_config_map {
+  merge_config_map(&mut resolved_config.config_map, new_config_map)?;
+
+  resolve_extends(resolved_config, extends, &base_source, extends_chain, cache, environment)?;
+
+  Ok(())
+}
+
+/// Merges the properties of the extended configuration into the configuration map. Properties
+/// in the configuration map take precedence.
+fn merge_config_map(config_map: &mut ConfigMap, extended_config_map: ConfigMap) -> Result<(), ErrBox> {
+  for (key, value) in extended_config_map {
     match value {
       ConfigMapValue::KeyValue(key_value) => {
-        if !resolved_config.config_map.contains_key(&key) {
-          resolved_config.config_map.insert(key, ConfigMapValue::KeyValue(key_value));
+        if !config_map.contains_key(&key) {
+          config_map.insert(key, ConfigMapValue::KeyValue(key_value));
         }
       }
       ConfigMapValue::Vec(items) => {
-        if !resolved_config.config_map.contains_key(&key) {
-          resolved_config.config_map.insert(key, ConfigMapValue::Vec(items));
+        if !config_map.contains_key(&key) {
+          config_map.insert(key, ConfigMapValue::Vec(items));
         }
       }
-      ConfigMapValue::HashMap(obj) => {
-        if let Some(resolved_config_obj) = resolved_config.config_map.get_mut(&key) {
-          match resolved_config_obj {
-            ConfigMapValue::HashMap(resolved_config_obj) => {
-              // check for locked configuration
-              if let Some(ConfigKeyValue::Bool(is_locked)) = obj.get("locked") {
-                if *is_locked && !resolved_config_obj.is_empty() {
-                  return err!(
-                    concat!(
-                      "The configuration for \"{}\" was locked, but a parent configuration specified it. ",
-                      "Locked configurations cannot have their properties overridden."
-                    ),
-                    key
-                  );
-                }
-              }
-
-              for (key, value) in obj {
-                if !resolved_config_obj.contains_key(&key) {
-                  resolved_config_obj.insert(key, value);
-                }
-              }
-            }
+      ConfigMapValue::MapVec(items) => match config_map.get_mut(&key) {
+        Some(ConfigMapValue::MapVec(config_items)) => {
+          // the items of the extending configuration come last so they take precedence
+          let extending_items = std::mem::replace(config_items, items);
+          config_items.extend(extending_items);
+        }
+        Some(_) => {
+          // ignore...
+        }
+        None => {
+          config_map.insert(key, ConfigMapValue::MapVec(items));
+        }
+      },
+      ConfigMapValue::HashMap(_) | ConfigMapValue::PluginConfig(_) => {
+        if let Some(config_value) = config_map.get_mut(&key) {
+          let (obj, associations) = match value {
+            ConfigMapValue::PluginConfig(plugin_config) => (plugin_config.properties, Some(plugin_config.associations)),
+            ConfigMapValue::HashMap(obj) => (obj, None),
+            _ => unreachable!(),
+          };
+          let has_associations = matches!(config_value, ConfigMapValue::PluginConfig(_));
+          let config_obj = match config_value {
+            ConfigMapValue::HashMap(config_obj) => config_obj,
+            ConfigMapValue::PluginConfig(config_plugin_config) => &mut config_plugin_config.properties,
             _ => {
               // ignore...
+              continue;
+            }
+          };
+
+          // check for locked configuration
+          if let Some(ConfigKeyValue::Bool(is_locked)) = obj.get("locked") {
+            if *is_locked && (!config_obj.is_empty() || has_associations) {
+              return err!(
+                concat!(
+                  "The configuration for \"{}\" was locked, but a parent configuration specified it. ",
+                  "Locked configurations cannot have their properties overridden."
+                ),
+                key
+              );
             }
           }
+
+          for (key, value) in obj {
+            if !config_obj.contains_key(&key) {
+              config_obj.insert(key, value);
+            }
+          }
+
+          // use the extended configuration's associations when they weren't specified
+          if let (Some(associations), ConfigMapValue::HashMap(config_obj)) = (associations, &mut *config_value) {
+            let properties = std::mem::take(config_obj);
+            *config_value = ConfigMapValue::PluginConfig(RawPluginConfig { associations, properties });
+          }
         } else {
-          resolved_config.config_map.insert(key, ConfigMapValue::HashMap(obj));
+          config_map.insert(key, value);
         }
       }
     }
   }
 
-  resolve_extends(resolved_config, extends, &resolved_path.source.parent(), cache, environment)?;
-
   Ok(())
 }
 
+/// Gets the source that relative paths in the configuration file (extends and plugins) should be resolved from.
+///
+/// When a local configuration file is a symlink (ex. managed by a dotfile manager), this is
+/// the directory of the file the symlink points to unless `pathsRelativeToSymlink` is set.
+fn get_config_base_source(resolved_path: &ResolvedPath, config_map: &mut ConfigMap, environment: &impl Environment) -> Result<PathSource, ErrBox> {
+  let paths_relative_to_symlink = take_bool_from_config_map(config_map, "pathsRelativeToSymlink", false)?;
+  if resolved_path.is_local() && !paths_relative_to_symlink {
+    // the file might not exist when plugins are specified on the command line
+    if let Ok(real_path) = environment.canonicalize(&resolved_path.file_path) {
+      return Ok(PathSource::new_local(real_path).parent());
+    }
+  }
+  Ok(resolved_path.source.parent())
+}
+
 fn take_extends(config_map: &mut ConfigMap) -> Result<Vec<String>, ErrBox> {
   match config_map.remove("extends") {
     Some(ConfigMapValue::KeyValue(ConfigKeyValue::String(url_or_file_path))) => Ok(vec![url_or_file_path]),
@@ -244,6 +478,94 @@ fn take_plugins_array_from_config_map(config_map: &mut ConfigMap, base_path: &Pa
   Ok(plugins)
 }
 
+/// Takes the `pluginOverrides` property, which maps the url or file path of a plugin to the
+/// url or file path of a plugin to use instead (ex. a local build of a fork).
+fn take_plugin_overrides_from_config_map(config_map: &mut ConfigMap, base_path: &PathSource) -> Result<Vec<(PathSource, PluginSourceReference)>, ErrBox> {
+  let obj = match config_map.remove("pluginOverrides") {
+    Some(ConfigMapValue::HashMap(obj)) => obj,
+    Some(_) => return err!("Expected object in 'pluginOverrides' property."),
+    None => return Ok(Vec::new()),
+  };
+
+  let mut plugin_overrides = Vec::with_capacity(obj.len());
+  for (key, value) in obj {
+    let replacement = match value {
+      ConfigKeyValue::String(value) => parse_plugin_source_reference(&value, base_path)?,
+      _ => return err!("Expected string value for '{}' in 'pluginOverrides' property.", key),
+    };
+    plugin_overrides.push((parse_plugin_path_source(&key, base_path)?, replacement));
+  }
+  Ok(plugin_overrides)
+}
+
+fn apply_plugin_overrides(resolved_config: &mut ResolvedConfig, plugin_overrides: Vec<(PathSource, PluginSourceReference)>, environment: &impl Environment) {
+  if plugin_overrides.is_empty() {
+    return;
+  }
+
+  for (path_source, replacement) in plugin_overrides {
+    let mut was_applied = false;
+    for plugin in resolved_config.plugins.iter_mut() {
+      if plugin.path_source == path_source {
+        log_verbose!(environment, "Overriding plugin {} with {}", plugin.display(), replacement.display());
+        *plugin = replacement.clone();
+        was_applied = true;
+      }
+    }
+    if !was_applied {
+      environment.log_warning(&format!("Warning: The plugin override for '{}' did not match any plugin.", path_source.display()));
+    }
+  }
+
+  let plugins = std::mem::take(&mut resolved_config.plugins);
+  resolved_config.plugins = filter_duplicate_plugin_sources(plugins);
+}
+
+/// Takes the `pluginMirrors` property, which maps a url prefix of plugins to the url prefix
+/// of a mirror to download them from instead (ex. an internal mirror in a corporate network).
+fn take_plugin_mirrors_from_config_map(config_map: &mut ConfigMap) -> Result<Vec<(String, String)>, ErrBox> {
+  let obj = match config_map.remove("pluginMirrors") {
+    Some(ConfigMapValue::HashMap(obj)) => obj,
+    Some(_) => return err!("Expected object in 'pluginMirrors' property."),
+    None => return Ok(Vec::new()),
+  };
+
+  let mut plugin_mirrors = Vec::with_capacity(obj.len());
+  for (key, value) in obj {
+    match value {
+      ConfigKeyValue::String(value) => plugin_mirrors.push((key, value)),
+      _ => return err!("Expected string value for '{}' in 'pluginMirrors' property.", key),
+    }
+  }
+  // prefer the most specific prefix when more than one matches
+  plugin_mirrors.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+  Ok(plugin_mirrors)
+}
+
+fn apply_plugin_mirrors(resolved_config: &mut ResolvedConfig, plugin_mirrors: &[(String, String)], environment: &impl Environment) -> Result<(), ErrBox> {
+  if plugin_mirrors.is_empty() {
+    return Ok(());
+  }
+
+  for plugin in resolved_config.plugins.iter_mut() {
+    let url = match &plugin.path_source {
+      PathSource::Remote(remote) => remote.url.as_str().to_string(),
+      PathSource::Local(_) => continue,
+    };
+    if let Some((prefix, mirror_prefix)) = plugin_mirrors.iter().find(|(prefix, _)| url.starts_with(prefix.as_str())) {
+      let mirror_url = format!("{}{}", mirror_prefix, &url[prefix.len()..]);
+      let mirror_url = match Url::parse(&mirror_url) {
+        Ok(mirror_url) => mirror_url,
+        Err(err) => return err!("Error parsing mirror url '{}' of plugin {}. {}", mirror_url, url, err.to_string()),
+      };
+      log_verbose!(environment, "Using mirror {} for plugin {}", mirror_url, url);
+      plugin.path_source = PathSource::new_remote(mirror_url);
+    }
+  }
+
+  Ok(())
+}
+
 fn take_array_from_config_map(config_map: &mut ConfigMap, property_name: &str) -> Result<Vec<String>, ErrBox> {
   let mut result = Vec::new();
   if let Some(value) = config_map.remove(property_name) {
@@ -257,6 +579,78 @@ fn take_array_from_config_map(config_map: &mut ConfigMap, property_name: &str) -
   Ok(result)
 }
 
+/// Takes the `overrides` property, which is an array of objects containing a `files` (or `includes` and `excludes`) property
+/// along with global configuration properties and plugin configuration objects that apply to the matching files.
+fn take_overrides_from_config_map(config_map: &mut ConfigMap) -> Result<Vec<ConfigOverride>, ErrBox> {
+  let items = match config_map.remove("overrides") {
+    Some(ConfigMapValue::MapVec(items)) => items,
+    Some(ConfigMapValue::Vec(items)) if items.is_empty() => Vec::new(),
+    Some(_) => return err!("Expected array of objects in 'overrides' property."),
+    None => Vec::new(),
+  };
+
+  let mut overrides = Vec::with_capacity(items.len());
+  for (index, mut config_map) in items.into_iter().enumerate() {
+    let mut files = take_override_patterns(&mut config_map, "files", index)?;
+    files.extend(take_override_patterns(&mut config_map, "includes", index)?);
+    if files.is_empty() {
+      return err!(
+        "Expected string or non-empty array of strings in 'files' or 'includes' property of 'overrides' item {}.",
+        index
+      );
+    }
+    for exclude in take_override_patterns(&mut config_map, "excludes", index)? {
+      if is_negated_glob(&exclude) {
+        return err!("Unexpected negated pattern '{}' in 'excludes' property of 'overrides' item {}.", exclude, index);
+      }
+      files.push(format!("!{}", exclude));
+    }
+
+    // this is applied to the plugin instance rather than to each file
+    if config_map.contains_key("timeoutMs") {
+      return err!("The 'timeoutMs' property is not supported in 'overrides' item {}.", index);
+    }
+
+    // only global configuration properties are allowed outside of the plugin configuration objects
+    let mut global_config = HashMap::new();
+    for (key, value) in config_map.iter() {
+      match value {
+        ConfigMapValue::KeyValue(value) => {
+          global_config.insert(key.clone(), value.clone());
+        }
+        ConfigMapValue::HashMap(_) => {}
+        ConfigMapValue::PluginConfig(_) => return err!("The 'associations' property of '{}' is not supported in 'overrides' item {}.", key, index),
+        ConfigMapValue::Vec(_) | ConfigMapValue::MapVec(_) => return err!("Unexpected array property '{}' in 'overrides' item {}.", key, index),
+      }
+    }
+    let global_config_result = resolve_global_config(
+      global_config,
+      &ResolveGlobalConfigOptions {
+        check_unknown_property_diagnostics: true,
+      },
+    );
+    if let Some(diagnostic) = global_config_result.diagnostics.first() {
+      return err!("Error in 'overrides' item {}. {}", index, diagnostic.message);
+    }
+
+    overrides.push(ConfigOverride { files, config_map });
+  }
+  Ok(overrides)
+}
+
+fn take_override_patterns(config_map: &mut ConfigMap, property_name: &str, index: usize) -> Result<Vec<String>, ErrBox> {
+  match config_map.remove(property_name) {
+    Some(ConfigMapValue::KeyValue(ConfigKeyValue::String(file_pattern))) => Ok(vec![file_pattern]),
+    Some(ConfigMapValue::Vec(file_patterns)) if !file_patterns.is_empty() => Ok(file_patterns),
+    None => Ok(Vec::new()),
+    _ => err!(
+      "Expected string or non-empty array of strings in '{}' property of 'overrides' item {}.",
+      property_name,
+      index
+    ),
+  }
+}
+
 fn take_bool_from_config_map(config_map: &mut ConfigMap, property_name: &str, default_value: bool) -> Result<bool, ErrBox> {
   let mut result = default_value;
   if let Some(value) = config_map.remove(property_name) {
@@ -270,9 +664,89 @@ fn take_bool_from_config_map(config_map: &mut ConfigMap, property_name: &str, de
   Ok(result)
 }
 
+/// Takes the `experimentalResultCache` property, which may either be a boolean or the url of a remote cache.
+fn take_result_cache_from_config_map(config_map: &mut ConfigMap) -> Result<(bool, Option<String>), ErrBox> {
+  match config_map.remove("experimentalResultCache") {
+    Some(ConfigMapValue::KeyValue(ConfigKeyValue::Bool(value))) => Ok((value, None)),
+    Some(ConfigMapValue::KeyValue(ConfigKeyValue::String(url))) => Ok((true, Some(url))),
+    Some(_) => err!("Expected boolean or string in 'experimentalResultCache' property."),
+    None => Ok((false, None)),
+  }
+}
+
+fn take_invalid_utf8_from_config_map(config_map: &mut ConfigMap) -> Result<InvalidUtf8Policy, ErrBox> {
+  match config_map.remove("invalidUtf8") {
+    Some(ConfigMapValue::KeyValue(ConfigKeyValue::String(value))) => match InvalidUtf8Policy::parse(&value) {
+      Some(policy) => Ok(policy),
+      None => err!("Expected \"error\", \"replace\", or \"skip\" in 'invalidUtf8' property, but found \"{}\".", value),
+    },
+    Some(_) => err!("Expected string in 'invalidUtf8' property."),
+    None => Ok(InvalidUtf8Policy::Error),
+  }
+}
+
+fn take_mixed_line_endings_from_config_map(config_map: &mut ConfigMap) -> Result<MixedLineEndingsPolicy, ErrBox> {
+  match config_map.remove("mixedLineEndings") {
+    Some(ConfigMapValue::KeyValue(ConfigKeyValue::String(value))) => match MixedLineEndingsPolicy::parse(&value) {
+      Some(policy) => Ok(policy),
+      None => err!(
+        "Expected \"ignore\", \"report\", \"lf\", \"crlf\", or \"auto\" in 'mixedLineEndings' property, but found \"{}\".",
+        value
+      ),
+    },
+    Some(_) => err!("Expected string in 'mixedLineEndings' property."),
+    None => Ok(MixedLineEndingsPolicy::Ignore),
+  }
+}
+
+fn take_plugin_pool_size_from_config_map(config_map: &mut ConfigMap) -> Result<Option<usize>, ErrBox> {
+  match config_map.remove("pluginPoolSize") {
+    Some(ConfigMapValue::KeyValue(ConfigKeyValue::Number(value))) if value > 0 => Ok(Some(value as usize)),
+    Some(_) => err!("Expected positive number in 'pluginPoolSize' property."),
+    None => Ok(None),
+  }
+}
+
+fn take_max_line_length_from_config_map(config_map: &mut ConfigMap) -> Result<Option<usize>, ErrBox> {
+  match config_map.remove("maxLineLength") {
+    Some(ConfigMapValue::KeyValue(ConfigKeyValue::Number(0))) => Ok(None),
+    Some(ConfigMapValue::KeyValue(ConfigKeyValue::Number(value))) if value > 0 => Ok(Some(value as usize)),
+    Some(_) => err!("Expected non-negative number in 'maxLineLength' property."),
+    None => Ok(Some(DEFAULT_MAX_LINE_LENGTH)),
+  }
+}
+
+fn take_encodings_from_config_map(config_map: &mut ConfigMap) -> Result<Vec<(String, TextEncoding)>, ErrBox> {
+  let obj = match config_map.remove("encodings") {
+    Some(ConfigMapValue::HashMap(obj)) => obj,
+    Some(_) => return err!("Expected object in 'encodings' property."),
+    None => return Ok(Vec::new()),
+  };
+
+  let mut encodings = Vec::with_capacity(obj.len());
+  for (pattern, value) in obj {
+    let encoding = match &value {
+      ConfigKeyValue::String(value) => TextEncoding::parse(value),
+      _ => None,
+    };
+    match encoding {
+      Some(encoding) => encodings.push((pattern, encoding)),
+      None => {
+        return err!(
+          "Expected \"utf-8\", \"utf-16le\", \"utf-16be\", \"windows-1252\", or \"iso-8859-1\" for '{}' in 'encodings' property.",
+          pattern
+        )
+      }
+    }
+  }
+  // prefer the most specific pattern when more than one matches
+  encodings.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+  Ok(encodings)
+}
+
 fn filter_non_wasm_plugins(plugins: Vec<PluginSourceReference>, environment: &impl Environment) -> Vec<PluginSourceReference> {
   if plugins.iter().any(|plugin| !plugin.is_wasm_plugin()) {
-    environment.log_error(&get_warn_non_wasm_plugins_message());
+    environment.log_warning(&get_warn_non_wasm_plugins_message());
     plugins.into_iter().filter(|plugin| plugin.is_wasm_plugin()).collect()
   } else {
     plugins
@@ -282,17 +756,29 @@ fn filter_non_wasm_plugins(plugins: Vec<PluginSourceReference>, environment: &im
 fn get_warn_includes_excludes_message() -> String {
   format!(
     "{} The 'includes' and 'excludes' properties are ignored for security reasons on remote configuration.",
-    "Note: ".bold().to_string()
+    bold("Note: ")
   )
 }
 
 fn get_warn_non_wasm_plugins_message() -> String {
   format!(
     "{} Non-wasm plugins are ignored for security reasons on remote configuration.",
-    "Note: ".bold().to_string()
+    bold("Note: ")
   )
 }
 
+/// Takes the `associations` out of the plugin configuration objects so the plugins don't receive them.
+fn take_associations_from_config_map(config_map: &mut ConfigMap) -> HashMap<String, Vec<String>> {
+  let mut associations = HashMap::new();
+  for (key, value) in config_map.iter_mut() {
+    if let ConfigMapValue::PluginConfig(plugin_config) = value {
+      associations.insert(key.clone(), std::mem::take(&mut plugin_config.associations));
+      *value = ConfigMapValue::HashMap(std::mem::take(&mut plugin_config.properties));
+    }
+  }
+  associations
+}
+
 fn remove_locked_properties(resolved_config: &mut ResolvedConfig) {
   // Remove this property on each sub configuration as it's not useful
   // for the caller to know about.
@@ -323,12 +809,14 @@ mod tests {
   use super::*;
 
   fn get_result(url: &str, environment: &impl Environment) -> Result<ResolvedConfig, ErrBox> {
+    get_result_for_args(vec!["-c", url], environment)
+  }
+
+  fn get_result_for_args(args: Vec<&str>, environment: &impl Environment) -> Result<ResolvedConfig, ErrBox> {
     let stdin_reader = TestStdInReader::new();
-    let args = parse_args(
-      vec![String::from(""), String::from("check"), String::from("-c"), String::from(url)],
-      &stdin_reader,
-    )
-    .unwrap();
+    let mut args = args.into_iter().map(String::from).collect::<Vec<_>>();
+    args.splice(0..0, vec![String::from(""), String::from("check")]);
+    let args = parse_args(args, &stdin_reader).unwrap();
     let cache = Cache::new(environment.to_owned());
     resolve_config_from_args(&args, &cache, &environment)
   }
@@ -891,27 +1379,23 @@ mod tests {
   }
 
   #[test]
-  fn it_should_error_extending_locked_config() {
+  fn it_should_error_for_circular_local_extends() {
     let environment = TestEnvironment::new();
-    environment.add_remote_file(
-      "https://dprint.dev/test.json",
-      r#"{
-            "test": {
-                "locked": true,
-                "prop": 6,
-                "other": "test"
-            }
-        }"#
-        .as_bytes(),
-    );
     environment
       .write_file(
         &PathBuf::from("/test.json"),
         r#"{
-            "extends": "https://dprint.dev/test.json",
-            "test": {
-                "prop": 5
-            }
+            "extends": "dir/test.json",
+            "prop1": 1
+        }"#,
+      )
+      .unwrap();
+    environment
+      .write_file(
+        &PathBuf::from("/dir/test.json"),
+        r#"{
+            "extends": "../test.json",
+            "prop2": 2
         }"#,
       )
       .unwrap();
@@ -919,87 +1403,262 @@ mod tests {
     let result = get_result("/test.json", &environment).err().unwrap();
     assert_eq!(
       result.to_string(),
-      concat!(
-        "Error with 'https://dprint.dev/test.json'. ",
-        "The configuration for \"test\" was locked, but a parent configuration specified it. ",
-        "Locked configurations cannot have their properties overridden."
-      )
+      "Error with '/dir/test.json'. Circular extends detected: /test.json -> /dir/test.json -> /test.json"
     );
   }
 
   #[test]
-  fn it_should_get_locked_config() {
+  fn it_should_error_for_circular_remote_extends() {
     let environment = TestEnvironment::new();
     environment.add_remote_file(
       "https://dprint.dev/test.json",
       r#"{
-            "test": {
-                "locked": true,
-                "prop": 6,
-                "other": "test"
-            }
+            "extends": "https://dprint.dev/test2.json",
+            "prop1": 1
+        }"#
+        .as_bytes(),
+    );
+    environment.add_remote_file(
+      "https://dprint.dev/test2.json",
+      r#"{
+            "extends": "test.json",
+            "prop2": 2
         }"#
         .as_bytes(),
     );
+
+    let result = get_result("https://dprint.dev/test.json", &environment).err().unwrap();
+    assert_eq!(
+      result.to_string(),
+      concat!(
+        "Error with 'https://dprint.dev/test2.json'. Circular extends detected: ",
+        "https://dprint.dev/test.json -> https://dprint.dev/test2.json -> https://dprint.dev/test.json"
+      )
+    );
+  }
+
+  #[test]
+  fn it_should_allow_extending_the_same_config_multiple_times() {
+    let environment = TestEnvironment::new();
     environment
       .write_file(
         &PathBuf::from("/test.json"),
         r#"{
-            "extends": "https://dprint.dev/test.json"
+            "extends": ["a.json", "b.json"],
+            "prop1": 1
         }"#,
       )
       .unwrap();
+    environment
+      .write_file(&PathBuf::from("/a.json"), r#"{ "extends": "base.json", "prop2": 2 }"#)
+      .unwrap();
+    environment
+      .write_file(&PathBuf::from("/b.json"), r#"{ "extends": "base.json", "prop3": 3 }"#)
+      .unwrap();
+    environment.write_file(&PathBuf::from("/base.json"), r#"{ "prop4": 4 }"#).unwrap();
 
     let result = get_result("/test.json", &environment).unwrap();
     assert_eq!(environment.take_logged_messages().len(), 0);
-    let mut expected_config_map = HashMap::new();
-    expected_config_map.insert(
-      String::from("test"),
-      ConfigMapValue::HashMap({
-        let mut obj = HashMap::new();
-        obj.insert(String::from("prop"), ConfigKeyValue::from_i32(6));
-        obj.insert(String::from("other"), ConfigKeyValue::from_str("test"));
-        obj
-      }),
-    );
 
+    let mut expected_config_map = HashMap::new();
+    expected_config_map.insert(String::from("prop1"), ConfigMapValue::from_i32(1));
+    expected_config_map.insert(String::from("prop2"), ConfigMapValue::from_i32(2));
+    expected_config_map.insert(String::from("prop3"), ConfigMapValue::from_i32(3));
+    expected_config_map.insert(String::from("prop4"), ConfigMapValue::from_i32(4));
     assert_eq!(result.config_map, expected_config_map);
   }
 
   #[test]
-  fn it_should_handle_locked_on_upstream_config() {
+  fn it_should_merge_config_overlays_in_order() {
     let environment = TestEnvironment::new();
-    environment.add_remote_file(
-      "https://dprint.dev/test.json",
-      r#"{
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"],
+            "includes": ["**/*.txt"],
+            "lineWidth": 120,
+            "indentWidth": 2,
             "test": {
-                "prop": 6,
+                "prop": 5,
                 "other": "test"
             }
-        }"#
-        .as_bytes(),
-    );
+        }"#,
+      )
+      .unwrap();
     environment
       .write_file(
-        &PathBuf::from("/test.json"),
+        &PathBuf::from("/overlay1.json"),
         r#"{
-            "extends": "https://dprint.dev/test.json",
+            "plugins": ["https://plugins.dprint.dev/test-plugin2.wasm"],
+            "includes": ["other"],
+            "lineWidth": 100,
             "test": {
-                "locked": true,
-                "prop": 7
+                "prop": 6
             }
         }"#,
       )
       .unwrap();
+    environment.write_file(&PathBuf::from("/overlay2.json"), r#"{ "lineWidth": 80 }"#).unwrap();
 
-    let result = get_result("/test.json", &environment).unwrap();
+    let result = get_result_for_args(vec!["-c", "/test.json", "--config", "/overlay1.json,/overlay2.json"], &environment).unwrap();
     assert_eq!(environment.take_logged_messages().len(), 0);
-    let mut expected_config_map = HashMap::new();
-    expected_config_map.insert(
-      String::from("test"),
-      ConfigMapValue::HashMap({
-        let mut obj = HashMap::new();
-        obj.insert(String::from("prop"), ConfigKeyValue::from_i32(7));
+    // overlays can't change what files get formatted
+    assert_eq!(result.includes, vec!["**/*.txt"]);
+    assert_eq!(
+      result.plugins,
+      vec![
+        PluginSourceReference::new_remote_from_str("https://plugins.dprint.dev/test-plugin.wasm"),
+        PluginSourceReference::new_remote_from_str("https://plugins.dprint.dev/test-plugin2.wasm"),
+      ]
+    );
+
+    let mut expected_config_map = HashMap::new();
+    expected_config_map.insert(String::from("lineWidth"), ConfigMapValue::from_i32(80));
+    expected_config_map.insert(String::from("indentWidth"), ConfigMapValue::from_i32(2));
+    expected_config_map.insert(
+      String::from("test"),
+      ConfigMapValue::HashMap({
+        let mut obj = HashMap::new();
+        obj.insert(String::from("prop"), ConfigKeyValue::from_i32(6));
+        obj.insert(String::from("other"), ConfigKeyValue::from_str("test"));
+        obj
+      }),
+    );
+    assert_eq!(result.config_map, expected_config_map);
+  }
+
+  #[test]
+  fn it_should_error_when_config_overlay_overrides_locked_config() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(&PathBuf::from("/test.json"), r#"{ "test": { "locked": true, "prop": 5 } }"#)
+      .unwrap();
+    environment.write_file(&PathBuf::from("/overlay.json"), r#"{ "test": { "prop": 6 } }"#).unwrap();
+
+    let result = get_result_for_args(vec!["-c", "/test.json", "-c", "/overlay.json"], &environment)
+      .err()
+      .unwrap();
+    assert_eq!(
+      result.to_string(),
+      concat!(
+        "Error with '/overlay.json'. ",
+        "The configuration for \"test\" was locked, but a parent configuration specified it. ",
+        "Locked configurations cannot have their properties overridden."
+      )
+    );
+  }
+
+  #[test]
+  fn it_should_error_extending_locked_config() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://dprint.dev/test.json",
+      r#"{
+            "test": {
+                "locked": true,
+                "prop": 6,
+                "other": "test"
+            }
+        }"#
+        .as_bytes(),
+    );
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "extends": "https://dprint.dev/test.json",
+            "test": {
+                "prop": 5
+            }
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).err().unwrap();
+    assert_eq!(
+      result.to_string(),
+      concat!(
+        "Error with 'https://dprint.dev/test.json'. ",
+        "The configuration for \"test\" was locked, but a parent configuration specified it. ",
+        "Locked configurations cannot have their properties overridden."
+      )
+    );
+  }
+
+  #[test]
+  fn it_should_get_locked_config() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://dprint.dev/test.json",
+      r#"{
+            "test": {
+                "locked": true,
+                "prop": 6,
+                "other": "test"
+            }
+        }"#
+        .as_bytes(),
+    );
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "extends": "https://dprint.dev/test.json"
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    let mut expected_config_map = HashMap::new();
+    expected_config_map.insert(
+      String::from("test"),
+      ConfigMapValue::HashMap({
+        let mut obj = HashMap::new();
+        obj.insert(String::from("prop"), ConfigKeyValue::from_i32(6));
+        obj.insert(String::from("other"), ConfigKeyValue::from_str("test"));
+        obj
+      }),
+    );
+
+    assert_eq!(result.config_map, expected_config_map);
+  }
+
+  #[test]
+  fn it_should_handle_locked_on_upstream_config() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://dprint.dev/test.json",
+      r#"{
+            "test": {
+                "prop": 6,
+                "other": "test"
+            }
+        }"#
+        .as_bytes(),
+    );
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "extends": "https://dprint.dev/test.json",
+            "test": {
+                "locked": true,
+                "prop": 7
+            }
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    let mut expected_config_map = HashMap::new();
+    expected_config_map.insert(
+      String::from("test"),
+      ConfigMapValue::HashMap({
+        let mut obj = HashMap::new();
+        obj.insert(String::from("prop"), ConfigKeyValue::from_i32(7));
         obj.insert(String::from("other"), ConfigKeyValue::from_str("test"));
         obj
       }),
@@ -1149,149 +1808,1009 @@ mod tests {
   }
 
   #[test]
-  fn it_should_handle_incremental_flag_when_not_specified() {
+  fn it_should_resolve_relative_paths_from_real_path_of_symlinked_config() {
     let environment = TestEnvironment::new();
     environment
       .write_file(
-        &PathBuf::from("/test.json"),
+        &PathBuf::from("/dotfiles/dprint.json"),
         r#"{
-            "plugins": ["./testing/asdf.wasm"],
+            "extends": "./base.json",
+            "plugins": ["./plugins/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    environment
+      .write_file(
+        &PathBuf::from("/dotfiles/base.json"),
+        r#"{
+            "plugins": ["./plugins/base.wasm"],
         }"#,
       )
       .unwrap();
+    environment.add_symlink("/project/dprint.json", "/dotfiles/dprint.json");
 
-    let result = get_result("/test.json", &environment).unwrap();
+    let result = get_result("/project/dprint.json", &environment).unwrap();
     assert_eq!(environment.take_logged_messages().len(), 0);
-    assert_eq!(result.incremental, false);
+    assert_eq!(result.base_path, PathBuf::from("/"));
+    assert_eq!(
+      result.plugins,
+      vec![
+        PluginSourceReference::new_local(PathBuf::from("/dotfiles/plugins/asdf.wasm")),
+        PluginSourceReference::new_local(PathBuf::from("/dotfiles/plugins/base.wasm")),
+      ]
+    );
   }
 
   #[test]
-  fn it_should_handle_incremental_flag_when_true() {
+  fn it_should_resolve_relative_paths_from_real_path_of_symlinked_extends() {
     let environment = TestEnvironment::new();
     environment
       .write_file(
         &PathBuf::from("/test.json"),
         r#"{
-            "incremental": true,
+            "extends": "./other/test.json",
+        }"#,
+      )
+      .unwrap();
+    environment
+      .write_file(
+        &PathBuf::from("/dotfiles/test.json"),
+        r#"{
             "plugins": ["./testing/asdf.wasm"],
         }"#,
       )
       .unwrap();
+    environment.add_symlink("/other/test.json", "/dotfiles/test.json");
 
     let result = get_result("/test.json", &environment).unwrap();
     assert_eq!(environment.take_logged_messages().len(), 0);
-    assert_eq!(result.incremental, true);
+    assert_eq!(
+      result.plugins,
+      vec![PluginSourceReference::new_local(PathBuf::from("/dotfiles/testing/asdf.wasm"))]
+    );
   }
 
   #[test]
-  fn it_should_handle_incremental_flag_when_false() {
+  fn it_should_resolve_relative_paths_from_symlink_when_specified() {
     let environment = TestEnvironment::new();
     environment
       .write_file(
-        &PathBuf::from("/test.json"),
+        &PathBuf::from("/dotfiles/dprint.json"),
         r#"{
-            "incremental": false,
-            "plugins": ["./testing/asdf.wasm"],
+            "pathsRelativeToSymlink": true,
+            "plugins": ["./plugins/asdf.wasm"],
         }"#,
       )
       .unwrap();
+    environment.add_symlink("/project/dprint.json", "/dotfiles/dprint.json");
 
-    let result = get_result("/test.json", &environment).unwrap();
+    let result = get_result("/project/dprint.json", &environment).unwrap();
     assert_eq!(environment.take_logged_messages().len(), 0);
-    assert_eq!(result.incremental, false);
-  }
-
-  #[test]
-  fn it_should_ignore_non_wasm_plugins_in_remote_config() {
-    let environment = TestEnvironment::new();
-    environment.add_remote_file(
-      "https://dprint.dev/test.json",
-      r#"{
-            "plugins": ["./test-plugin.exe-plugin@checksum"]
-        }"#
-        .as_bytes(),
+    assert_eq!(result.config_map.contains_key("pathsRelativeToSymlink"), false);
+    assert_eq!(
+      result.plugins,
+      vec![PluginSourceReference::new_local(PathBuf::from("/project/plugins/asdf.wasm"))]
     );
-
-    let result = get_result("https://dprint.dev/test.json", &environment).unwrap();
-    assert_eq!(result.plugins, vec![]);
-    assert_eq!(environment.take_logged_errors(), vec![get_warn_non_wasm_plugins_message()]);
   }
 
   #[test]
-  fn it_should_ignore_non_wasm_plugins_in_remote_extends() {
+  fn it_should_handle_incremental_flag_when_not_specified() {
     let environment = TestEnvironment::new();
     environment
       .write_file(
         &PathBuf::from("/test.json"),
         r#"{
-            "extends": "https://dprint.dev/dir/test.json",
-            "prop1": 1
+            "plugins": ["./testing/asdf.wasm"],
         }"#,
       )
       .unwrap();
-    environment.add_remote_file(
-      "https://dprint.dev/dir/test.json",
-      r#"{
-            "plugins": ["./test-plugin.exe-plugin@checksum"]
-        }"#
-        .as_bytes(),
-    );
 
     let result = get_result("/test.json", &environment).unwrap();
-    assert_eq!(environment.take_logged_errors(), vec![get_warn_non_wasm_plugins_message()]);
-    assert_eq!(result.plugins, vec![]);
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(result.incremental, false);
   }
 
   #[test]
-  fn it_should_not_allow_non_wasm_plugins_in_local_extends() {
+  fn it_should_handle_incremental_flag_when_true() {
     let environment = TestEnvironment::new();
     environment
       .write_file(
         &PathBuf::from("/test.json"),
         r#"{
-            "extends": "dir/test.json",
-            "prop1": 1
+            "incremental": true,
+            "plugins": ["./testing/asdf.wasm"],
         }"#,
       )
       .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(result.incremental, true);
+  }
+
+  #[test]
+  fn it_should_handle_incremental_flag_when_false() {
+    let environment = TestEnvironment::new();
     environment
       .write_file(
-        &PathBuf::from("/dir/test.json"),
+        &PathBuf::from("/test.json"),
         r#"{
-            "plugins": ["./test-plugin.exe-plugin@checksum"]
+            "incremental": false,
+            "plugins": ["./testing/asdf.wasm"],
         }"#,
       )
       .unwrap();
 
     let result = get_result("/test.json", &environment).unwrap();
     assert_eq!(environment.take_logged_messages().len(), 0);
-    assert_eq!(
-      result.plugins,
-      vec![PluginSourceReference {
-        path_source: PathSource::new_local(PathBuf::from("/dir/test-plugin.exe-plugin")),
-        checksum: Some(String::from("checksum")),
-      }]
-    );
+    assert_eq!(result.incremental, false);
   }
 
   #[test]
-  fn it_should_ignore_project_type() {
-    // ignore the projectType property
+  fn it_should_handle_use_default_excludes() {
     let environment = TestEnvironment::new();
     environment
       .write_file(
         &PathBuf::from("/test.json"),
         r#"{
-            "projectType": "openSource",
-            "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"],
-            "includes": ["test"],
-            "excludes": ["test"]
+            "useDefaultExcludes": false,
+            "plugins": ["./testing/asdf.wasm"],
         }"#,
       )
       .unwrap();
 
     let result = get_result("/test.json", &environment).unwrap();
     assert_eq!(environment.take_logged_messages().len(), 0);
-    assert_eq!(result.config_map.is_empty(), true); // should not include projectType
+    assert_eq!(result.use_default_excludes, false);
+  }
+
+  #[test]
+  fn it_should_handle_use_dot_ignore_files() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "useDotIgnoreFiles": true,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(result.use_dot_ignore_files, true);
+    assert_eq!(result.config_map.contains_key("useDotIgnoreFiles"), false);
+  }
+
+  #[test]
+  fn it_should_use_default_excludes_for_remote_config() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://dprint.dev/test.json",
+      r#"{
+            "useDefaultExcludes": false,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#
+        .as_bytes(),
+    );
+
+    let result = get_result("https://dprint.dev/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(result.use_default_excludes, true);
+  }
+
+  #[test]
+  fn it_should_handle_invalid_utf8() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(result.invalid_utf8, InvalidUtf8Policy::Error);
+
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "invalidUtf8": "replace",
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(result.invalid_utf8, InvalidUtf8Policy::Replace);
+    assert_eq!(result.config_map.contains_key("invalidUtf8"), false);
+
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "invalidUtf8": "ignore",
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    assert_eq!(
+      get_result("/test.json", &environment).err().unwrap().to_string(),
+      "Expected \"error\", \"replace\", or \"skip\" in 'invalidUtf8' property, but found \"ignore\"."
+    );
+  }
+
+  #[test]
+  fn it_should_handle_mixed_line_endings() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(result.mixed_line_endings, MixedLineEndingsPolicy::Ignore);
+
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "mixedLineEndings": "crlf",
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(result.mixed_line_endings, MixedLineEndingsPolicy::Crlf);
+    assert_eq!(result.config_map.contains_key("mixedLineEndings"), false);
+
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "mixedLineEndings": "system",
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    assert_eq!(
+      get_result("/test.json", &environment).err().unwrap().to_string(),
+      "Expected \"ignore\", \"report\", \"lf\", \"crlf\", or \"auto\" in 'mixedLineEndings' property, but found \"system\"."
+    );
+  }
+
+  #[test]
+  fn it_should_handle_fallback_on_error() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "fallbackOnError": true,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(result.fallback_on_error, true);
+    assert_eq!(result.config_map.contains_key("fallbackOnError"), false);
+  }
+
+  #[test]
+  fn it_should_handle_plugin_pool_size() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "pluginPoolSize": 2,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(result.plugin_pool_size, Some(2));
+    assert_eq!(result.config_map.contains_key("pluginPoolSize"), false);
+  }
+
+  #[test]
+  fn it_should_error_for_invalid_plugin_pool_size() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "pluginPoolSize": 0,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    assert_eq!(
+      get_result("/test.json", &environment).err().unwrap().to_string(),
+      "Expected positive number in 'pluginPoolSize' property."
+    );
+  }
+
+  #[test]
+  fn it_should_handle_max_line_length() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    assert_eq!(get_result("/test.json", &environment).unwrap().max_line_length, Some(1_000_000));
+
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "maxLineLength": 100,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(result.max_line_length, Some(100));
+    assert_eq!(result.config_map.contains_key("maxLineLength"), false);
+
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "maxLineLength": 0,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    assert_eq!(get_result("/test.json", &environment).unwrap().max_line_length, None);
+  }
+
+  #[test]
+  fn it_should_error_for_invalid_max_line_length() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "maxLineLength": -1,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    assert_eq!(
+      get_result("/test.json", &environment).err().unwrap().to_string(),
+      "Expected non-negative number in 'maxLineLength' property."
+    );
+  }
+
+  #[test]
+  fn it_should_handle_warnings_as_errors() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "warningsAsErrors": true,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(result.warnings_as_errors, true);
+    assert_eq!(result.config_map.contains_key("warningsAsErrors"), false);
+  }
+
+  #[test]
+  fn it_should_handle_encodings() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "encodings": {
+              "legacy/**": "windows-1252",
+              "legacy/utf16/**": "UTF-16LE"
+            },
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(
+      result.encodings,
+      vec![
+        ("legacy/utf16/**".to_string(), TextEncoding::Utf16Le),
+        ("legacy/**".to_string(), TextEncoding::Windows1252),
+      ]
+    );
+    assert_eq!(result.config_map.contains_key("encodings"), false);
+  }
+
+  #[test]
+  fn it_should_error_for_unknown_encoding() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "encodings": { "legacy/**": "shift_jis" },
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    assert_eq!(
+      get_result("/test.json", &environment).err().unwrap().to_string(),
+      "Expected \"utf-8\", \"utf-16le\", \"utf-16be\", \"windows-1252\", or \"iso-8859-1\" for 'legacy/**' in 'encodings' property."
+    );
+  }
+
+  #[test]
+  fn it_should_handle_plugin_associations() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://dprint.dev/test.json",
+      r#"{
+            "typescript": {
+                "associations": ["**/*.mjsx"],
+                "semiColons": "asi"
+            },
+            "json": {
+                "associations": "**/*.jsonx"
+            }
+        }"#
+        .as_bytes(),
+    );
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "extends": "https://dprint.dev/test.json",
+            "typescript": {
+                "associations": ["**/*.mjsx", "Jenkinsfile"]
+            },
+            "json": {
+                "lineWidth": 40
+            },
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    let mut expected_associations = HashMap::new();
+    expected_associations.insert("typescript".to_string(), vec!["**/*.mjsx".to_string(), "Jenkinsfile".to_string()]);
+    expected_associations.insert("json".to_string(), vec!["**/*.jsonx".to_string()]);
+    assert_eq!(result.associations, expected_associations);
+    assert_eq!(
+      result.config_map.get("typescript"),
+      Some(&ConfigMapValue::HashMap({
+        let mut obj = HashMap::new();
+        obj.insert("semiColons".to_string(), ConfigKeyValue::from_str("asi"));
+        obj
+      }))
+    );
+    assert_eq!(
+      result.config_map.get("json"),
+      Some(&ConfigMapValue::HashMap({
+        let mut obj = HashMap::new();
+        obj.insert("lineWidth".to_string(), ConfigKeyValue::from_i32(40));
+        obj
+      }))
+    );
+  }
+
+  #[test]
+  fn it_should_error_for_plugin_associations_in_overrides() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "overrides": [{
+                "files": "legacy/**",
+                "typescript": { "associations": ["**/*.js"] }
+            }],
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    assert_eq!(
+      get_result("/test.json", &environment).err().unwrap().to_string(),
+      "The 'associations' property of 'typescript' is not supported in 'overrides' item 0."
+    );
+  }
+
+  #[test]
+  fn it_should_handle_force_format() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "incremental": true,
+            "forceFormat": ["src/generated/**/*.ts"],
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(result.force_format, vec!["src/generated/**/*.ts"]);
+    assert_eq!(result.config_map.contains_key("forceFormat"), false);
+  }
+
+  #[test]
+  fn it_should_handle_prettier_compat() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "prettierCompat": true,
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(result.prettier_compat, true);
+    assert_eq!(result.config_map.contains_key("prettierCompat"), false);
+  }
+
+  #[test]
+  fn it_should_handle_result_cache() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "experimentalResultCache": "https://cache.dprint.dev/results",
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(result.result_cache, true);
+    assert_eq!(result.result_cache_remote_url, Some(String::from("https://cache.dprint.dev/results")));
+    assert_eq!(result.config_map.contains_key("experimentalResultCache"), false);
+  }
+
+  #[test]
+  fn it_should_handle_overrides() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "extends": "./other.json",
+            "overrides": [{
+                "files": "tests/**/*",
+                "lineWidth": 120,
+                "test": { "prop": 1 }
+            }],
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+    environment
+      .write_file(
+        &PathBuf::from("/other.json"),
+        r#"{
+            "overrides": [{
+                "files": ["src/**/*", "!src/legacy/**/*"],
+                "useTabs": true
+            }]
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(result.config_map.contains_key("overrides"), false);
+    assert_eq!(
+      result.overrides,
+      vec![
+        ConfigOverride {
+          files: vec![String::from("src/**/*"), String::from("!src/legacy/**/*")],
+          config_map: {
+            let mut config_map = HashMap::new();
+            config_map.insert(String::from("useTabs"), ConfigMapValue::from_bool(true));
+            config_map
+          },
+        },
+        ConfigOverride {
+          files: vec![String::from("tests/**/*")],
+          config_map: {
+            let mut config_map = HashMap::new();
+            config_map.insert(String::from("lineWidth"), ConfigMapValue::from_i32(120));
+            config_map.insert(
+              String::from("test"),
+              ConfigMapValue::HashMap({
+                let mut obj = HashMap::new();
+                obj.insert(String::from("prop"), ConfigKeyValue::from_i32(1));
+                obj
+              }),
+            );
+            config_map
+          },
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn it_should_error_when_overrides_item_has_no_files() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(&PathBuf::from("/test.json"), r#"{ "overrides": [{ "lineWidth": 120 }] }"#)
+      .unwrap();
+
+    let err = get_result("/test.json", &environment).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Expected string or non-empty array of strings in 'files' or 'includes' property of 'overrides' item 0."
+    );
+  }
+
+  #[test]
+  fn it_should_handle_overrides_with_includes_and_excludes() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "overrides": [{
+                "includes": ["packages/legacy/**"],
+                "excludes": "packages/legacy/generated/**",
+                "lineWidth": 80
+            }],
+            "plugins": ["./testing/asdf.wasm"],
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(
+      result.overrides,
+      vec![ConfigOverride {
+        files: vec![String::from("packages/legacy/**"), String::from("!packages/legacy/generated/**")],
+        config_map: {
+          let mut config_map = HashMap::new();
+          config_map.insert(String::from("lineWidth"), ConfigMapValue::from_i32(80));
+          config_map
+        },
+      }]
+    );
+  }
+
+  #[test]
+  fn it_should_error_when_overrides_item_has_invalid_excludes() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(&PathBuf::from("/test.json"), r#"{ "overrides": [{ "includes": "src/**", "excludes": [] }] }"#)
+      .unwrap();
+
+    let err = get_result("/test.json", &environment).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Expected string or non-empty array of strings in 'excludes' property of 'overrides' item 0."
+    );
+  }
+
+  #[test]
+  fn it_should_error_when_overrides_item_has_timeout_property() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(&PathBuf::from("/test.json"), r#"{ "overrides": [{ "files": "tests/**/*", "timeoutMs": 1000 }] }"#)
+      .unwrap();
+
+    let err = get_result("/test.json", &environment).err().unwrap();
+    assert_eq!(err.to_string(), "The 'timeoutMs' property is not supported in 'overrides' item 0.");
+  }
+
+  #[test]
+  fn it_should_error_when_overrides_item_has_non_global_property() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(&PathBuf::from("/test.json"), r#"{ "overrides": [{ "files": "tests/**/*", "semiColons": "asi" }] }"#)
+      .unwrap();
+
+    let err = get_result("/test.json", &environment).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Error in 'overrides' item 0. Unknown property in configuration: semiColons"
+    );
+  }
+
+  #[test]
+  fn it_should_ignore_result_cache_remote_url_in_remote_config() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://dprint.dev/test.json",
+      r#"{
+            "experimentalResultCache": "https://cache.dprint.dev/results",
+            "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"]
+        }"#
+        .as_bytes(),
+    );
+
+    let result = get_result("https://dprint.dev/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(result.result_cache, true);
+    assert_eq!(result.result_cache_remote_url, None);
+  }
+
+  #[test]
+  fn it_should_ignore_non_wasm_plugins_in_remote_config() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://dprint.dev/test.json",
+      r#"{
+            "plugins": ["./test-plugin.exe-plugin@checksum"]
+        }"#
+        .as_bytes(),
+    );
+
+    let result = get_result("https://dprint.dev/test.json", &environment).unwrap();
+    assert_eq!(result.plugins, vec![]);
+    assert_eq!(environment.take_logged_errors(), vec![get_warn_non_wasm_plugins_message()]);
+  }
+
+  #[test]
+  fn it_should_ignore_non_wasm_plugins_in_remote_extends() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "extends": "https://dprint.dev/dir/test.json",
+            "prop1": 1
+        }"#,
+      )
+      .unwrap();
+    environment.add_remote_file(
+      "https://dprint.dev/dir/test.json",
+      r#"{
+            "plugins": ["./test-plugin.exe-plugin@checksum"]
+        }"#
+        .as_bytes(),
+    );
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_errors(), vec![get_warn_non_wasm_plugins_message()]);
+    assert_eq!(result.plugins, vec![]);
+  }
+
+  #[test]
+  fn it_should_not_allow_non_wasm_plugins_in_local_extends() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "extends": "dir/test.json",
+            "prop1": 1
+        }"#,
+      )
+      .unwrap();
+    environment
+      .write_file(
+        &PathBuf::from("/dir/test.json"),
+        r#"{
+            "plugins": ["./test-plugin.exe-plugin@checksum"]
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(
+      result.plugins,
+      vec![PluginSourceReference {
+        path_source: PathSource::new_local(PathBuf::from("/dir/test-plugin.exe-plugin")),
+        checksum: Some(String::from("checksum")),
+      }]
+    );
+  }
+
+  #[test]
+  fn it_should_ignore_project_type() {
+    // ignore the projectType property
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "projectType": "openSource",
+            "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"],
+            "includes": ["test"],
+            "excludes": ["test"]
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(result.config_map.is_empty(), true); // should not include projectType
+  }
+
+  #[test]
+  fn it_should_apply_plugin_overrides() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "plugins": ["https://plugins.dprint.dev/test-plugin.wasm", "https://plugins.dprint.dev/test-plugin2.wasm"],
+            "pluginOverrides": {
+                "https://plugins.dprint.dev/test-plugin.wasm": "./fork/test-plugin.wasm",
+                "https://plugins.dprint.dev/other.wasm": "https://plugins.dprint.dev/other2.wasm"
+            }
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Warning: The plugin override for 'https://plugins.dprint.dev/other.wasm' did not match any plugin."]
+    );
+    assert_eq!(
+      result.plugins,
+      vec![
+        PluginSourceReference::new_local(PathBuf::from("/fork/test-plugin.wasm")),
+        PluginSourceReference::new_remote_from_str("https://plugins.dprint.dev/test-plugin2.wasm"),
+      ]
+    );
+    assert_eq!(result.config_map.contains_key("pluginOverrides"), false);
+  }
+
+  #[test]
+  fn it_should_ignore_plugin_overrides_in_remote_config() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://dprint.dev/test.json",
+      r#"{
+          "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"],
+          "pluginOverrides": {
+              "https://plugins.dprint.dev/test-plugin.wasm": "https://plugins.dprint.dev/other.wasm"
+          }
+      }"#
+      .as_bytes(),
+    );
+
+    let result = get_result("https://dprint.dev/test.json", &environment).unwrap();
+    assert_eq!(
+      result.plugins,
+      vec![PluginSourceReference::new_remote_from_str("https://plugins.dprint.dev/test-plugin.wasm")]
+    );
+  }
+
+  #[test]
+  fn it_should_apply_plugin_mirrors() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "plugins": [
+                "https://plugins.dprint.dev/test-plugin.wasm",
+                "https://plugins.dprint.dev/other/test-plugin2.wasm",
+                "https://github.com/dprint/test-plugin3.wasm",
+                "./local-plugin.wasm"
+            ],
+            "pluginMirrors": {
+                "https://plugins.dprint.dev/": "https://mirror.example.com/dprint/",
+                "https://plugins.dprint.dev/other/": "https://mirror.example.com/other/"
+            }
+        }"#,
+      )
+      .unwrap();
+
+    let result = get_result("/test.json", &environment).unwrap();
+    assert_eq!(
+      result.plugins,
+      vec![
+        PluginSourceReference::new_remote_from_str("https://mirror.example.com/dprint/test-plugin.wasm"),
+        PluginSourceReference::new_remote_from_str("https://mirror.example.com/other/test-plugin2.wasm"),
+        PluginSourceReference::new_remote_from_str("https://github.com/dprint/test-plugin3.wasm"),
+        PluginSourceReference::new_local(PathBuf::from("/local-plugin.wasm")),
+      ]
+    );
+    assert_eq!(result.config_map.contains_key("pluginMirrors"), false);
+  }
+
+  #[test]
+  fn it_should_ignore_plugin_mirrors_in_remote_config() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://dprint.dev/test.json",
+      r#"{
+          "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"],
+          "pluginMirrors": {
+              "https://plugins.dprint.dev/": "https://mirror.example.com/"
+          }
+      }"#
+      .as_bytes(),
+    );
+
+    let result = get_result("https://dprint.dev/test.json", &environment).unwrap();
+    assert_eq!(
+      result.plugins,
+      vec![PluginSourceReference::new_remote_from_str("https://plugins.dprint.dev/test-plugin.wasm")]
+    );
+  }
+
+  #[test]
+  fn it_should_apply_config_overrides() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        &PathBuf::from("/test.json"),
+        r#"{
+            "lineWidth": 80,
+            "test": {
+                "prop": 6,
+                "other": "test"
+            }
+        }"#,
+      )
+      .unwrap();
+
+    let mut result = get_result("/test.json", &environment).unwrap();
+    apply_config_overrides(&mut result, r#"{ "lineWidth": 40, "test": { "prop": 2 }, "other": { "locked": true, "value": true } }"#).unwrap();
+    let mut expected_config_map = HashMap::new();
+    expected_config_map.insert(String::from("lineWidth"), ConfigMapValue::from_i32(40));
+    expected_config_map.insert(
+      String::from("test"),
+      ConfigMapValue::HashMap({
+        let mut obj = HashMap::new();
+        obj.insert(String::from("prop"), ConfigKeyValue::from_i32(2));
+        obj.insert(String::from("other"), ConfigKeyValue::from_str("test"));
+        obj
+      }),
+    );
+    expected_config_map.insert(
+      String::from("other"),
+      ConfigMapValue::HashMap({
+        let mut obj = HashMap::new();
+        obj.insert(String::from("value"), ConfigKeyValue::from_bool(true));
+        obj
+      }),
+    );
+    assert_eq!(result.config_map, expected_config_map);
+  }
+
+  #[test]
+  fn it_should_error_when_config_overrides_contain_plugins() {
+    let environment = TestEnvironment::new();
+    environment.write_file(&PathBuf::from("/test.json"), r#"{}"#).unwrap();
+
+    let mut result = get_result("/test.json", &environment).unwrap();
+    let err = apply_config_overrides(&mut result, r#"{ "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"] }"#)
+      .err()
+      .unwrap();
+    assert_eq!(err.to_string(), "The 'plugins' property is not supported in the plugin config overrides.");
   }
 }
@@ -1,19 +1,26 @@
 use std::io::Read;
 use std::io::Write;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use dprint_cli_core::types::ErrBox;
 use dprint_core::plugins::process::{start_parent_process_checker_thread, StdIoMessenger, StdIoReaderWriter};
 
 use super::configuration::resolve_config_from_args;
 use super::configuration::ResolvedConfig;
+use super::editor_service_snapshot::{read_snapshot, write_snapshot};
 use super::format::format_with_plugin_pools;
+use super::incremental::{get_incremental_file, IncrementalFile};
 use super::patterns::FileMatcher;
-use super::plugins::resolve_plugins;
+use super::plugins::{configure_plugins, set_plugin_pools_config};
 use super::{CliArgs, EditorServiceSubCommand};
 use crate::cache::Cache;
 use crate::environment::Environment;
-use crate::plugins::{PluginPools, PluginResolver};
+use crate::plugins::{IdlePluginCheckerThread, PluginPools, PluginResolver};
+
+/// Duration after which the instances of a plugin that hasn't been used are shut down.
+const PLUGIN_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
 
 pub fn run_editor_service<TEnvironment: Environment>(
   args: &CliArgs,
@@ -26,18 +33,32 @@ pub fn run_editor_service<TEnvironment: Environment>(
   // poll for the existence of the parent process and terminate this process when that process no longer exists
   let _handle = start_parent_process_checker_thread(editor_service_cmd.parent_pid);
 
-  let mut editor_service = EditorService::new(args, cache, environment, plugin_resolver, plugin_pools);
-  editor_service.run()
+  let tracer = editor_service_cmd
+    .trace_protocol
+    .as_ref()
+    .map(|file_path| ProtocolTracer::new(environment.cwd().join(file_path), environment.clone()))
+    .transpose()?;
+  let idle_plugin_checker_thread = IdlePluginCheckerThread::new(plugin_pools.clone(), PLUGIN_IDLE_TIMEOUT);
+  idle_plugin_checker_thread.spawn();
+  let mut editor_service = EditorService::new(args, cache, environment, plugin_resolver, plugin_pools, tracer);
+  let result = editor_service.run();
+  idle_plugin_checker_thread.signal_exit();
+  result
 }
 
 struct EditorService<'a, TEnvironment: Environment> {
   messenger: StdIoMessenger<Box<dyn Read + Send>, Box<dyn Write + Send>>,
   config: Option<ResolvedConfig>,
+  /// If the plugins for the current configuration have been resolved.
+  has_plugins: bool,
+  /// The incremental file for the current configuration once it has been read.
+  incremental_file: Option<Option<Arc<IncrementalFile<TEnvironment>>>>,
   args: &'a CliArgs,
   cache: &'a Cache<TEnvironment>,
   environment: &'a TEnvironment,
   plugin_resolver: &'a PluginResolver<TEnvironment>,
   plugin_pools: Arc<PluginPools<TEnvironment>>,
+  tracer: Option<ProtocolTracer<TEnvironment>>,
 }
 
 impl<'a, TEnvironment: Environment> EditorService<'a, TEnvironment> {
@@ -47,6 +68,7 @@ impl<'a, TEnvironment: Environment> EditorService<'a, TEnvironment> {
     environment: &'a TEnvironment,
     plugin_resolver: &'a PluginResolver<TEnvironment>,
     plugin_pools: Arc<PluginPools<TEnvironment>>,
+    tracer: Option<ProtocolTracer<TEnvironment>>,
   ) -> Self {
     let stdin = environment.stdin();
     let stdout = environment.stdout();
@@ -55,32 +77,47 @@ impl<'a, TEnvironment: Environment> EditorService<'a, TEnvironment> {
     Self {
       messenger: StdIoMessenger::new(reader_writer),
       config: None,
+      has_plugins: false,
+      incremental_file: None,
       args,
       cache,
       environment,
       plugin_resolver,
       plugin_pools,
+      tracer,
     }
   }
 
   pub fn run(&mut self) -> Result<(), ErrBox> {
     loop {
       let message_kind = self.messenger.read_code()?;
+      if let Some(tracer) = &mut self.tracer {
+        tracer.start_request();
+      }
       match message_kind {
         // shutdown
-        0 => return Ok(()),
+        0 => {
+          self.trace_request(message_kind, "shutdown", "");
+          return Ok(());
+        }
         // check path
         1 => self.handle_check_path_message()?,
         // format
         2 => self.handle_format_message()?,
+        // check formatted
+        3 => self.handle_check_formatted_message()?,
         // unknown, exit
-        _ => return err!("Unknown message kind: {}", message_kind),
+        _ => {
+          self.trace_request(message_kind, "unknown", "");
+          return err!("Unknown message kind: {}", message_kind);
+        }
       }
     }
   }
 
   fn handle_check_path_message(&mut self) -> Result<(), ErrBox> {
     let file_path = self.messenger.read_single_part_path_buf_message()?;
+    self.trace_request(1, "check path", &file_path.display().to_string());
     self.ensure_latest_config()?;
 
     let file_matcher = FileMatcher::new(&self.config.as_ref().unwrap(), self.args, self.environment)?;
@@ -89,15 +126,13 @@ impl<'a, TEnvironment: Environment> EditorService<'a, TEnvironment> {
     match self.environment.canonicalize(&file_path) {
       Ok(resolved_file_path) => {
         log_verbose!(self.environment, "Checking can format: {}", resolved_file_path.display());
-        self
-          .messenger
-          .send_message(if file_matcher.matches(&resolved_file_path) { 1 } else { 0 }, Vec::new())?;
+        self.send_response(if file_matcher.matches(&resolved_file_path) { 1 } else { 0 }, None)?;
       }
       Err(err) => {
         self
           .environment
           .log_error(&format!("Error canonicalizing file {}: {}", file_path.display(), err.to_string()));
-        self.messenger.send_message(0, Vec::new())?; // don't format, something went wrong
+        self.send_response(0, None)?; // don't format, something went wrong
       }
     }
 
@@ -108,40 +143,82 @@ impl<'a, TEnvironment: Environment> EditorService<'a, TEnvironment> {
     let mut parts = self.messenger.read_multi_part_message(2)?;
     let file_path = parts.take_path_buf()?;
     let file_text = parts.take_string()?;
+    self.trace_request(2, "format", &format!("{} {}", file_path.display(), file_text));
 
     if self.config.is_none() {
       self.ensure_latest_config()?;
     }
+    self.ensure_plugins()?;
 
-    let formatted_text = format_with_plugin_pools(&file_path, &file_text, self.environment, &self.plugin_pools);
+    // hold the file's lock so a concurrent `dprint fmt` doesn't write the file while it's formatted here
+    let formatted_text = self
+      .environment
+      .with_file_lock(&file_path, || {
+        format_with_plugin_pools(&file_path, &file_text, None, self.environment, &self.plugin_pools)
+      })
+      .and_then(|result| result);
+    if let Some(plugin_pool_size) = self.config.as_ref().unwrap().plugin_pool_size {
+      // keep the most recently used plugins warm while capping the number of running instances
+      self.plugin_pools.drop_least_recently_used_instances(plugin_pool_size);
+    }
     match formatted_text {
       Ok(formatted_text) => {
         if formatted_text == file_text {
-          self.messenger.send_message(0, Vec::new())?; // no change
+          self.send_response(0, None)?; // no change
         } else {
-          self.messenger.send_message(
-            1,
-            vec![
-              // change
-              formatted_text.into(),
-            ],
-          )?;
+          self.send_response(1, Some(formatted_text))?; // change
         }
       }
       Err(err) => {
-        self.messenger.send_message(
-          2,
-          vec![
-            // error
-            err.to_string().into(),
-          ],
-        )?;
+        self.send_response(2, Some(err.to_string()))?; // error
       }
     }
 
     Ok(())
   }
 
+  /// Responds with if the incremental file says the file was formatted when it had the provided
+  /// hash of its text, which allows editors to skip formatting files that haven't changed.
+  fn handle_check_formatted_message(&mut self) -> Result<(), ErrBox> {
+    let mut parts = self.messenger.read_multi_part_message(2)?;
+    let file_path = parts.take_path_buf()?;
+    let file_hash = parts.take_string()?;
+    self.trace_request(3, "check formatted", &format!("{} {}", file_path.display(), file_hash));
+
+    if self.config.is_none() {
+      self.ensure_latest_config()?;
+    }
+
+    let file_hash = match file_hash.parse::<u64>() {
+      Ok(file_hash) => file_hash,
+      Err(_) => {
+        self
+          .environment
+          .log_error(&format!("Expected the file hash to be a u64, but found: {}", file_hash));
+        return self.send_response(0, None); // not known, so the file should be formatted
+      }
+    };
+    let is_formatted = match self.get_incremental_file()? {
+      Some(incremental_file) => incremental_file.is_file_hash_same(&file_path, file_hash),
+      None => false,
+    };
+    log_verbose!(self.environment, "Checking formatted: {} ({})", file_path.display(), is_formatted);
+    self.send_response(if is_formatted { 1 } else { 0 }, None)
+  }
+
+  fn send_response(&mut self, code: u32, body: Option<String>) -> Result<(), ErrBox> {
+    if let Some(tracer) = &self.tracer {
+      tracer.trace_response(code, body.as_deref().unwrap_or(""));
+    }
+    self.messenger.send_message(code, body.into_iter().map(|body| body.into()).collect())
+  }
+
+  fn trace_request(&self, code: u32, name: &str, body: &str) {
+    if let Some(tracer) = &self.tracer {
+      tracer.trace_request(code, name, body);
+    }
+  }
+
   fn ensure_latest_config(&mut self) -> Result<(), ErrBox> {
     let last_config = self.config.take();
     let config = resolve_config_from_args(self.args, self.cache, self.environment)?;
@@ -149,12 +226,125 @@ impl<'a, TEnvironment: Environment> EditorService<'a, TEnvironment> {
     let has_config_changed = last_config.is_none() || last_config.unwrap() != config;
     if has_config_changed {
       self.plugin_pools.drop_plugins(); // clear the existing plugins
-      let plugins = resolve_plugins(self.args, &config, self.environment, self.plugin_resolver)?;
-      self.plugin_pools.set_plugins(plugins);
+      self.has_plugins = false;
+      self.incremental_file = None;
+      set_plugin_pools_config(&self.plugin_pools, &config)?;
     }
 
     self.config = Some(config);
 
     Ok(())
   }
+
+  /// Resolves the plugins the first time they're needed for the current configuration. Checking
+  /// paths only requires the configuration, so this allows responding to those right away.
+  fn ensure_plugins(&mut self) -> Result<(), ErrBox> {
+    if self.has_plugins {
+      return Ok(());
+    }
+
+    let config = self.config.as_ref().unwrap();
+    let snapshot_plugins =
+      read_snapshot(config, self.environment).and_then(|cache_items| match self.plugin_resolver.resolve_plugins_from_cache_items(cache_items) {
+        Ok(plugins) => Some(plugins),
+        Err(err) => {
+          log_verbose!(self.environment, "Error resolving plugins from editor service snapshot. {}", err.to_string());
+          None
+        }
+      });
+    let plugins = match snapshot_plugins {
+      Some(plugins) => {
+        log_verbose!(self.environment, "Resolved plugins from editor service snapshot.");
+        plugins
+      }
+      None => {
+        let plugins = self.plugin_resolver.resolve_plugins(config.plugins.clone())?;
+        write_snapshot(config, self.plugin_resolver, self.environment);
+        plugins
+      }
+    };
+    let plugins = configure_plugins(self.args, config, plugins, self.environment)?;
+    self.plugin_pools.set_plugins(plugins, config.fallback_on_error);
+    self.has_plugins = true;
+
+    Ok(())
+  }
+
+  /// Gets the incremental file for the current configuration, which is only read once
+  /// per configuration. It's `None` when incremental formatting isn't enabled.
+  fn get_incremental_file(&mut self) -> Result<Option<Arc<IncrementalFile<TEnvironment>>>, ErrBox> {
+    if self.incremental_file.is_none() {
+      // the plugins are necessary to know if the incremental file is for the current plugins
+      self.ensure_plugins()?;
+      let config = self.config.as_ref().unwrap();
+      self.incremental_file = Some(get_incremental_file(self.args, config, self.cache, &self.plugin_pools, self.environment));
+    }
+    Ok(self.incremental_file.clone().unwrap())
+  }
+}
+
+/// Maximum number of characters of a message body to write to the trace file.
+const MAX_TRACE_BODY_CHARS: usize = 200;
+
+/// Logs the protocol messages to a file so extension authors can debug hangs and slow formats.
+struct ProtocolTracer<TEnvironment: Environment> {
+  file_path: PathBuf,
+  environment: TEnvironment,
+  request_start: Instant,
+}
+
+impl<TEnvironment: Environment> ProtocolTracer<TEnvironment> {
+  pub fn new(file_path: PathBuf, environment: TEnvironment) -> Result<Self, ErrBox> {
+    // start with an empty file for each editor service
+    environment.write_file(&file_path, "")?;
+    Ok(ProtocolTracer {
+      file_path,
+      environment,
+      request_start: Instant::now(),
+    })
+  }
+
+  pub fn start_request(&mut self) {
+    self.request_start = Instant::now();
+  }
+
+  pub fn trace_request(&self, code: u32, name: &str, body: &str) {
+    self.write_line(&format!("--> {} ({}) {}", code, name, truncate_body(body)));
+  }
+
+  pub fn trace_response(&self, code: u32, body: &str) {
+    let duration = self.request_start.elapsed();
+    self.write_line(&format!("<-- {} in {}ms {}", code, duration.as_millis(), truncate_body(body)));
+  }
+
+  fn write_line(&self, text: &str) {
+    let line = format!("[{}] {}\n", self.environment.get_time_secs(), text);
+    if let Err(err) = self.environment.append_file(&self.file_path, &line) {
+      self.environment.log_error(&format!("Error writing protocol trace: {}", err));
+    }
+  }
+}
+
+/// Truncates the body and escapes it so each message is on a single line.
+fn truncate_body(body: &str) -> String {
+  let char_count = body.chars().count();
+  if char_count > MAX_TRACE_BODY_CHARS {
+    let truncated_body = body.chars().take(MAX_TRACE_BODY_CHARS).collect::<String>();
+    format!("{:?}... ({} more chars)", truncated_body, char_count - MAX_TRACE_BODY_CHARS)
+  } else {
+    format!("{:?}", body)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_truncate_body() {
+    assert_eq!(truncate_body(""), "\"\"");
+    assert_eq!(truncate_body("a\nb"), "\"a\\nb\"");
+    assert_eq!(truncate_body(&"a".repeat(205)), format!("\"{}\"... (5 more chars)", "a".repeat(200)));
+  }
 }
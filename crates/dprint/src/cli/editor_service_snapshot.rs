@@ -0,0 +1,137 @@
+use std::path::PathBuf;
+
+use dprint_core::plugins::PluginInfo;
+use serde::{Deserialize, Serialize};
+
+use crate::environment::Environment;
+use crate::plugins::{PluginCacheItem, PluginResolver, PluginSourceReference};
+use crate::utils::{get_bytes_hash, PathSource};
+
+use super::configuration::ResolvedConfig;
+
+/// The plugins resolved by the editor service for a configuration. This is stored so a
+/// restarted editor service may create the plugins without resolving them from the plugin cache.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EditorServiceSnapshot {
+  /// Hash of the plugins in the resolved configuration and the CLI version.
+  config_hash: u64,
+  plugins: Vec<PluginSnapshot>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginSnapshot {
+  /// Path of the compiled Wasm module or process plugin executable in the cache.
+  file_path: PathBuf,
+  /// Hash of the plugin file when it's a local plugin in order to tell when it changes.
+  local_file_hash: Option<u64>,
+  /// Information about the plugin, which includes the file extensions and file names it formats.
+  info: PluginInfo,
+}
+
+/// Gets the plugin cache items from the snapshot for the configuration when it's still valid.
+pub fn read_snapshot(config: &ResolvedConfig, environment: &impl Environment) -> Option<Vec<(PluginSourceReference, PluginCacheItem)>> {
+  let file_path = get_snapshot_file_path(config, environment);
+  if !environment.path_exists(&file_path) {
+    return None;
+  }
+  let snapshot = match environment
+    .read_file(&file_path)
+    .map(|text| serde_json::from_str::<EditorServiceSnapshot>(&text))
+  {
+    Ok(Ok(snapshot)) => snapshot,
+    Ok(Err(err)) => {
+      log_verbose!(environment, "Error deserializing editor service snapshot. {}", err.to_string());
+      return None;
+    }
+    Err(err) => {
+      log_verbose!(environment, "Error reading editor service snapshot. {}", err.to_string());
+      return None;
+    }
+  };
+
+  if snapshot.config_hash != get_config_hash(config) || snapshot.plugins.len() != config.plugins.len() {
+    log_verbose!(
+      environment,
+      "Editor service snapshot is outdated because the plugins in the configuration changed."
+    );
+    return None;
+  }
+
+  let mut cache_items = Vec::new();
+  for (plugin_reference, plugin) in config.plugins.iter().zip(snapshot.plugins.into_iter()) {
+    if !environment.path_exists(&plugin.file_path) || plugin.local_file_hash != get_local_file_hash(plugin_reference, environment) {
+      log_verbose!(
+        environment,
+        "Editor service snapshot is outdated because the files of {} changed.",
+        plugin_reference.display()
+      );
+      return None;
+    }
+    cache_items.push((
+      plugin_reference.clone(),
+      PluginCacheItem {
+        file_path: plugin.file_path,
+        info: plugin.info,
+      },
+    ));
+  }
+  Some(cache_items)
+}
+
+/// Stores the plugin cache items of the configuration's plugins for a restarted editor service.
+pub fn write_snapshot<TEnvironment: Environment>(config: &ResolvedConfig, plugin_resolver: &PluginResolver<TEnvironment>, environment: &TEnvironment) {
+  let mut plugins = Vec::new();
+  for plugin_reference in config.plugins.iter() {
+    match plugin_resolver.get_plugin_cache_item(plugin_reference) {
+      Ok(cache_item) => plugins.push(PluginSnapshot {
+        file_path: cache_item.file_path,
+        local_file_hash: get_local_file_hash(plugin_reference, environment),
+        info: cache_item.info,
+      }),
+      Err(err) => {
+        log_verbose!(environment, "Skipped writing editor service snapshot. {}", err.to_string());
+        return;
+      }
+    }
+  }
+
+  let snapshot = EditorServiceSnapshot {
+    config_hash: get_config_hash(config),
+    plugins,
+  };
+  let file_path = get_snapshot_file_path(config, environment);
+  let result = environment
+    .mk_dir_all(file_path.parent().unwrap())
+    .and_then(|_| environment.write_file(&file_path, &serde_json::to_string(&snapshot).unwrap()));
+  if let Err(err) = result {
+    log_verbose!(environment, "Error writing editor service snapshot. {}", err.to_string());
+  }
+}
+
+fn get_snapshot_file_path(config: &ResolvedConfig, environment: &impl Environment) -> PathBuf {
+  let base_path_hash = get_bytes_hash(config.base_path.to_string_lossy().as_bytes());
+  environment.get_cache_dir().join("editor-service").join(format!("{}.json", base_path_hash))
+}
+
+fn get_config_hash(config: &ResolvedConfig) -> u64 {
+  // the snapshot's format may change between versions
+  let mut text = format!("{}\n", env!("CARGO_PKG_VERSION"));
+  for plugin_reference in config.plugins.iter() {
+    text.push_str(&plugin_reference.display());
+    if let Some(checksum) = &plugin_reference.checksum {
+      text.push('@');
+      text.push_str(checksum);
+    }
+    text.push('\n');
+  }
+  get_bytes_hash(text.as_bytes())
+}
+
+fn get_local_file_hash(plugin_reference: &PluginSourceReference, environment: &impl Environment) -> Option<u64> {
+  match &plugin_reference.path_source {
+    PathSource::Local(local_source) => environment.read_file_bytes(&local_source.path).ok().map(|bytes| get_bytes_hash(&bytes)),
+    PathSource::Remote(_) => None,
+  }
+}
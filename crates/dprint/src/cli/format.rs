@@ -1,32 +1,69 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
 use dprint_cli_core::types::ErrBox;
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::plugins::{ChangedRegion, FormatDiagnosticSeverity};
+use serde_json::json;
 
-use crate::environment::Environment;
-use crate::plugins::{do_batch_format, InitializedPlugin, InitializedPluginPool, PluginPools, TakePluginResult};
-use crate::utils::{ErrorCountLogger, FileText};
+use crate::environment::{Environment, OutputFormat};
+use crate::plugins::{
+  do_batch_format, FormatDiagnosticsError, InitializedPlugin, InitializedPluginPool, PluginErrorDiagnostic, PluginFormatError, PluginPools, TakePluginResult,
+};
+use crate::utils::{
+  apply_file_boundary_policy, apply_new_line_kind, get_long_line, get_text_position, get_verified_changed_regions, normalize_mixed_line_endings,
+  set_crash_report_file_path, ErrorCountLogger, FileEncodings, FileText, InvalidUtf8Policy, MixedLineEndingsPolicy, Phase, TextEncoding,
+};
 
+use super::ignore_ranges::format_with_ignore_ranges;
 use super::incremental::IncrementalFile;
+use super::result_cache::ResultCache;
 
+/// Formats the file text or only the text within the provided byte range, leaving the rest as-is.
 pub fn format_with_plugin_pools<'a, TEnvironment: Environment>(
   file_name: &Path,
   file_text: &'a str,
+  range: Option<Range<usize>>,
   environment: &TEnvironment,
   plugin_pools: &Arc<PluginPools<TEnvironment>>,
 ) -> Result<Cow<'a, str>, ErrBox> {
+  let range = match range {
+    Some(range) => {
+      ensure_valid_range(file_text, &range)?;
+      range
+    }
+    None => 0..file_text.len(),
+  };
   if let Some(plugin_name) = plugin_pools.get_plugin_name_from_file_name(file_name) {
     let plugin_pool = plugin_pools.get_pool(&plugin_name).unwrap();
     let error_logger = ErrorCountLogger::from_environment(environment);
     match plugin_pool.take_or_create_checking_config_diagnostics(&error_logger)? {
       TakePluginResult::Success(mut initialized_plugin) => {
-        let result = initialized_plugin.format_text(file_name, file_text, &HashMap::new());
+        let override_config = plugin_pools.get_override_config(file_name, &plugin_pool);
+        let result = format_text_with_plugin(
+          &plugin_pool,
+          &mut initialized_plugin,
+          file_name,
+          file_text,
+          range.clone(),
+          &override_config,
+          environment,
+        );
         plugin_pool.release(initialized_plugin);
-        Ok(Cow::Owned(result?)) // release plugin above, then propagate this error
+        // release plugin above, then propagate this error
+        let formatted_text = format_with_fallback_plugins(file_name, file_text, range.clone(), &plugin_name, result, environment, plugin_pools)?;
+        Ok(Cow::Owned(format!(
+          "{}{}{}",
+          &file_text[..range.start],
+          formatted_text,
+          &file_text[range.end..]
+        )))
       }
       TakePluginResult::HadDiagnostics => {
         err!("Had {} configuration errors.", error_logger.get_error_count())
@@ -37,50 +74,431 @@ pub fn format_with_plugin_pools<'a, TEnvironment: Environment>(
   }
 }
 
+fn ensure_valid_range(file_text: &str, range: &Range<usize>) -> Result<(), ErrBox> {
+  if range.start > range.end || range.end > file_text.len() {
+    err!(
+      "The range {}-{} is not within the text, which is {} bytes long.",
+      range.start,
+      range.end,
+      file_text.len()
+    )
+  } else if !file_text.is_char_boundary(range.start) || !file_text.is_char_boundary(range.end) {
+    err!("The range {}-{} does not start and end on character boundaries.", range.start, range.end)
+  } else {
+    Ok(())
+  }
+}
+
+/// Formats the text within the byte range of the file text with the plugin and returns the formatted
+/// text of that range. When the plugin supports formatting a portion of a file, any text within
+/// `dprint-ignore-start` and `dprint-ignore-end` directives is left as-is. Otherwise, the range
+/// must be the entire file text.
+///
+/// When a `newLineKind` is configured, the line endings of the formatted text are converted to it
+/// so the line endings are consistent regardless of how the plugin handles this configuration.
+/// The same is done for the `trimLeadingBlankLines` and `singleTrailingNewLine` policies.
+fn format_text_with_plugin<TEnvironment: Environment>(
+  plugin_pool: &InitializedPluginPool<TEnvironment>,
+  initialized_plugin: &mut Box<dyn InitializedPlugin>,
+  file_path: &Path,
+  file_text: &str,
+  range: Range<usize>,
+  override_config: &ConfigKeyMap,
+  environment: &TEnvironment,
+) -> Result<String, ErrBox> {
+  set_crash_report_file_path(file_path);
+  let new_line_kind = plugin_pool.get_new_line_kind(override_config);
+  let apply_new_line_kind = |formatted_text: String| match new_line_kind {
+    Some(new_line_kind) => apply_new_line_kind(file_text, formatted_text, new_line_kind),
+    None => formatted_text,
+  };
+  let formatted_text = if plugin_pool.supports_partial_format() {
+    format_with_ignore_ranges(&file_text[range.clone()], |text, text_start| {
+      let formatted_text = match initialized_plugin.format_text(file_path, text, override_config) {
+        Ok(formatted_text) => formatted_text,
+        Err(err) => {
+          return Err(with_error_diagnostics(
+            plugin_pool,
+            initialized_plugin,
+            file_text,
+            range.start + text_start,
+            err,
+          ))
+        }
+      };
+      output_format_diagnostics(plugin_pool, initialized_plugin, file_path, file_text, range.start + text_start, environment)?;
+      Ok(apply_new_line_kind(formatted_text))
+    })?
+  } else if range.start == 0 && range.end == file_text.len() {
+    let formatted_text = match initialized_plugin.format_text(file_path, file_text, override_config) {
+      Ok(formatted_text) => formatted_text,
+      Err(err) => return Err(with_error_diagnostics(plugin_pool, initialized_plugin, file_text, 0, err)),
+    };
+    output_format_diagnostics(plugin_pool, initialized_plugin, file_path, file_text, 0, environment)?;
+    apply_new_line_kind(formatted_text)
+  } else {
+    return err!("The {} plugin does not support formatting a range of a file.", plugin_pool.name());
+  };
+
+  let mut file_boundary_policy = plugin_pool.get_file_boundary_policy(override_config);
+  // only apply to the boundaries of the file that were formatted
+  file_boundary_policy.trim_leading_blank_lines &= range.start == 0;
+  file_boundary_policy.single_trailing_new_line &= range.end == file_text.len();
+  Ok(apply_file_boundary_policy(formatted_text, file_boundary_policy))
+}
+
+/// Adds the diagnostics with an error severity the plugin found when it failed formatting
+/// the text starting at the provided byte index of the file text.
+fn with_error_diagnostics<TEnvironment: Environment>(
+  plugin_pool: &InitializedPluginPool<TEnvironment>,
+  initialized_plugin: &mut Box<dyn InitializedPlugin>,
+  file_text: &str,
+  text_start: usize,
+  err: ErrBox,
+) -> ErrBox {
+  if !plugin_pool.supports_format_diagnostics() {
+    return err;
+  }
+
+  let diagnostics = match initialized_plugin.take_format_diagnostics() {
+    Ok(diagnostics) => diagnostics,
+    // the plugin may be in a bad state (ex. it panicked), so only provide the original error
+    Err(_) => return err,
+  };
+  let diagnostics = diagnostics
+    .into_iter()
+    .filter(|diagnostic| diagnostic.severity == FormatDiagnosticSeverity::Error)
+    .map(|diagnostic| PluginErrorDiagnostic {
+      message: diagnostic.message,
+      position: diagnostic.span.map(|span| get_text_position(file_text, text_start + span.start)),
+    })
+    .collect::<Vec<_>>();
+  if diagnostics.is_empty() {
+    err
+  } else {
+    Box::new(FormatDiagnosticsError {
+      message: err.to_string(),
+      diagnostics,
+    })
+  }
+}
+
+/// Outputs any non-fatal diagnostics the plugin found while formatting the text
+/// starting at the provided byte index of the file text.
+fn output_format_diagnostics<TEnvironment: Environment>(
+  plugin_pool: &InitializedPluginPool<TEnvironment>,
+  initialized_plugin: &mut Box<dyn InitializedPlugin>,
+  file_path: &Path,
+  file_text: &str,
+  text_start: usize,
+  environment: &TEnvironment,
+) -> Result<(), ErrBox> {
+  if !plugin_pool.supports_format_diagnostics() {
+    return Ok(());
+  }
+
+  for diagnostic in initialized_plugin.take_format_diagnostics()? {
+    let severity_text = match diagnostic.severity {
+      FormatDiagnosticSeverity::Warning => {
+        environment.warning_count().increment();
+        "Warning"
+      }
+      FormatDiagnosticSeverity::Info => "Info",
+      // plugins should only report these when formatting fails
+      FormatDiagnosticSeverity::Error => "Error",
+    };
+    let position = diagnostic.span.map(|span| get_text_position(file_text, text_start + span.start));
+    if environment.output_format() == OutputFormat::Json {
+      let mut value = json!({
+        "kind": "diagnostic",
+        "severity": severity_text.to_lowercase(),
+        "filePath": file_path.display().to_string(),
+        "plugin": plugin_pool.name(),
+        "message": diagnostic.message,
+      });
+      if let Some(position) = position {
+        value["line"] = json!(position.line_number);
+        value["column"] = json!(position.column_number);
+      }
+      environment.log_json(&value);
+      continue;
+    }
+
+    let location = match position {
+      Some(position) => format!("{}:{}:{}", file_path.display(), position.line_number, position.column_number),
+      None => file_path.display().to_string(),
+    };
+    environment.log_error(&format!(
+      "{} formatting {} ({}). Message: {}",
+      severity_text,
+      location,
+      plugin_pool.name(),
+      diagnostic.message
+    ));
+  }
+
+  Ok(())
+}
+
+/// Takes the regions of the file text the plugin changed when formatting it. These are only
+/// returned when they result in the formatted text so that the whole file is written otherwise.
+fn take_changed_regions<TEnvironment: Environment>(
+  plugin_pool: &InitializedPluginPool<TEnvironment>,
+  initialized_plugin: &mut Box<dyn InitializedPlugin>,
+  file_path: &Path,
+  file_text: &str,
+  formatted_text: &str,
+  environment: &TEnvironment,
+) -> Option<Vec<ChangedRegion>> {
+  if !plugin_pool.supports_changed_regions() || formatted_text == file_text {
+    return None;
+  }
+
+  match initialized_plugin.take_changed_regions() {
+    Ok(Some(changed_regions)) => {
+      let changed_regions = get_verified_changed_regions(file_text, formatted_text, changed_regions);
+      if changed_regions.is_none() {
+        log_verbose!(
+          environment,
+          "Ignored the changed regions of {} from the {} plugin because they did not result in the formatted text.",
+          file_path.display(),
+          plugin_pool.name()
+        );
+      }
+      changed_regions
+    }
+    Ok(None) => None,
+    Err(err) => {
+      log_verbose!(
+        environment,
+        "Error getting the changed regions of {} from the {} plugin: {}",
+        file_path.display(),
+        plugin_pool.name(),
+        err.to_string()
+      );
+      None
+    }
+  }
+}
+
+/// Reads the text of a file to format handling invalid UTF-8 based on the policy.
+/// Returns `None` when the file should be skipped and otherwise the text along with
+/// whether invalid UTF-8 was replaced, in which case the text differs from the bytes on disk.
+fn read_file_text(
+  file_path: &Path,
+  encoding: TextEncoding,
+  invalid_utf8: InvalidUtf8Policy,
+  environment: &impl Environment,
+) -> Result<Option<(String, bool)>, ErrBox> {
+  let file_bytes = environment.read_file_bytes(file_path)?;
+  if encoding != TextEncoding::Utf8 {
+    return match encoding.decode(&file_bytes) {
+      Ok(file_text) => Ok(Some((file_text, false))),
+      Err(err) => err!("Error decoding the file as {}. {}", encoding.name(), err),
+    };
+  }
+  let err = match String::from_utf8(file_bytes) {
+    Ok(file_text) => return Ok(Some((file_text, false))),
+    Err(err) => err,
+  };
+
+  match invalid_utf8 {
+    InvalidUtf8Policy::Error => err!("File contained invalid UTF-8. {}", err.utf8_error()),
+    InvalidUtf8Policy::Replace => {
+      environment.log_warning(&format!(
+        "Warning: Replaced invalid UTF-8 in {} with the replacement character.",
+        file_path.display()
+      ));
+      Ok(Some((String::from_utf8_lossy(err.as_bytes()).into_owned(), true)))
+    }
+    InvalidUtf8Policy::Skip => {
+      log_verbose!(environment, "Skipped file with invalid UTF-8: {}", file_path.display());
+      Ok(None)
+    }
+  }
+}
+
+/// Tries formatting with the fallback plugins for the file when the provided result is an error.
+/// If all the fallback plugins fail, then the original error is returned with details about the plugin.
+fn format_with_fallback_plugins<TEnvironment: Environment>(
+  file_path: &Path,
+  file_text: &str,
+  range: Range<usize>,
+  plugin_name: &str,
+  result: Result<String, ErrBox>,
+  environment: &TEnvironment,
+  plugin_pools: &PluginPools<TEnvironment>,
+) -> Result<String, ErrBox> {
+  let err = match result {
+    Ok(formatted_text) => return Ok(formatted_text),
+    Err(err) => err,
+  };
+
+  for fallback_plugin_name in plugin_pools.get_fallback_plugin_names(file_path, plugin_name) {
+    let plugin_pool = plugin_pools.get_pool(&fallback_plugin_name).unwrap();
+    let error_logger = ErrorCountLogger::from_environment(environment);
+    // diagnostics will be output by this, so skip the plugin when it has any
+    if let TakePluginResult::Success(mut initialized_plugin) = plugin_pool.take_or_create_checking_config_diagnostics(&error_logger)? {
+      let override_config = plugin_pools.get_override_config(file_path, &plugin_pool);
+      let result = plugin_pool.format_measuring_time(|| {
+        format_text_with_plugin(
+          &plugin_pool,
+          &mut initialized_plugin,
+          file_path,
+          file_text,
+          range.clone(),
+          &override_config,
+          environment,
+        )
+      });
+      plugin_pool.release(initialized_plugin);
+      match result {
+        Ok(formatted_text) => {
+          log_verbose!(
+            environment,
+            "Formatted file: {} with fallback plugin {} because {} failed. Message: {}",
+            file_path.display(),
+            fallback_plugin_name,
+            plugin_name,
+            err.to_string()
+          );
+          return Ok(formatted_text);
+        }
+        Err(fallback_err) => {
+          log_verbose!(
+            environment,
+            "Fallback plugin {} failed formatting {}. Message: {}",
+            fallback_plugin_name,
+            file_path.display(),
+            fallback_err.to_string()
+          );
+        }
+      }
+    }
+  }
+
+  // include details about the plugin so the user knows where to report the error
+  let plugin_pool = plugin_pools.get_pool(plugin_name).unwrap();
+  Err(Box::new(PluginFormatError::new(&plugin_pool, file_path, file_text, err)))
+}
+
 pub fn run_parallelized<F, TEnvironment: Environment>(
   file_paths_by_plugin: HashMap<String, Vec<PathBuf>>,
   environment: &TEnvironment,
   plugin_pools: Arc<PluginPools<TEnvironment>>,
   incremental_file: Option<Arc<IncrementalFile<TEnvironment>>>,
+  result_cache: Option<Arc<ResultCache<TEnvironment>>>,
+  file_encodings: Arc<FileEncodings>,
+  invalid_utf8: InvalidUtf8Policy,
+  mixed_line_endings: MixedLineEndingsPolicy,
+  max_line_length: Option<usize>,
+  verify_whitespace_only: bool,
   f: F,
 ) -> Result<(), ErrBox>
 where
-  F: Fn(&Path, &str, String, bool, Instant, &TEnvironment) -> Result<(), ErrBox> + Send + 'static + Clone,
+  F: Fn(&Path, &str, String, Option<Vec<ChangedRegion>>, bool, Instant, &TEnvironment) -> Result<(), ErrBox> + Send + 'static + Clone,
 {
   let error_logger = ErrorCountLogger::from_environment(environment);
+  let total_files = file_paths_by_plugin.values().map(|file_paths| file_paths.len()).sum();
+  let progress_bar = environment.create_files_progress_bar("", total_files);
+  let processed_files_count = Arc::new(AtomicUsize::new(0));
 
-  do_batch_format(environment, &error_logger, &plugin_pools, file_paths_by_plugin, {
-    let environment = environment.clone();
-    let incremental_file = incremental_file.clone();
-    let error_logger = error_logger.clone();
-    move |plugin_pool, file_path, plugin| {
-      let result = run_for_file_path(&environment, &incremental_file, plugin_pool, file_path, plugin, f.clone());
-      if let Err(err) = result {
-        error_logger.log_error(&format!("Error formatting {}. Message: {}", file_path.display(), err.to_string()));
+  let start_instant = Instant::now();
+  let result = environment.run_interruptible(|| {
+    do_batch_format(environment, &error_logger, &plugin_pools, file_paths_by_plugin, {
+      let environment = environment.clone();
+      let incremental_file = incremental_file.clone();
+      let result_cache = result_cache.clone();
+      let file_encodings = file_encodings.clone();
+      let error_logger = error_logger.clone();
+      let plugin_pools = plugin_pools.clone();
+      let progress_bar = progress_bar.clone();
+      let processed_files_count = processed_files_count.clone();
+      move |plugin_pool, file_path, plugin| {
+        // skip the remaining files, but let any files currently being formatted finish so they're not partially written
+        if environment.is_interrupted() {
+          return;
+        }
+        if let Some(progress_bar) = &progress_bar {
+          progress_bar.set_message(file_path.display().to_string());
+        }
+        let file_start_instant = Instant::now();
+        let result = run_for_file_path(
+          &environment,
+          &incremental_file,
+          &result_cache,
+          &plugin_pools,
+          plugin_pool,
+          file_path,
+          plugin,
+          file_encodings.get_encoding(file_path),
+          invalid_utf8,
+          mixed_line_endings,
+          max_line_length,
+          verify_whitespace_only,
+          f.clone(),
+        );
+        environment
+          .phase_timings()
+          .profiler()
+          .add_span(|| file_path.display().to_string(), file_start_instant);
+        if let Err(err) = result {
+          error_logger.log_file_error(file_path, &err);
+        }
+        processed_files_count.fetch_add(1, Ordering::SeqCst);
+        if let Some(progress_bar) = &progress_bar {
+          progress_bar.increment_position();
+        }
       }
-    }
-  })?;
+    })
+  });
+  environment.phase_timings().add_since(Phase::Formatting, start_instant);
+  if let Some(progress_bar) = &progress_bar {
+    progress_bar.finish();
+  }
+  error_logger.log_file_error_summary();
+  result?;
+
+  if environment.is_interrupted() {
+    let processed_files_count = processed_files_count.load(Ordering::SeqCst);
+    return err!(
+      "{}",
+      environment
+        .messages()
+        .get_plural("format.interrupted", total_files, &total_files, &[("processed", &processed_files_count)])
+    );
+  }
 
   let error_count = error_logger.get_error_count();
   return if error_count == 0 {
     Ok(())
   } else {
-    err!("Had {0} error(s) formatting.", error_count)
+    err!("{}", environment.messages().get("format.errorCount", &[("count", &error_count)]))
   };
 
   #[inline]
   fn run_for_file_path<F, TEnvironment: Environment>(
     environment: &TEnvironment,
     incremental_file: &Option<Arc<IncrementalFile<TEnvironment>>>,
+    result_cache: &Option<Arc<ResultCache<TEnvironment>>>,
+    plugin_pools: &PluginPools<TEnvironment>,
     plugin_pool: &InitializedPluginPool<TEnvironment>,
     file_path: &Path,
     initialized_plugin: &mut Box<dyn InitializedPlugin>,
+    encoding: TextEncoding,
+    invalid_utf8: InvalidUtf8Policy,
+    mixed_line_endings: MixedLineEndingsPolicy,
+    max_line_length: Option<usize>,
+    verify_whitespace_only: bool,
     f: F,
   ) -> Result<(), ErrBox>
   where
-    F: Fn(&Path, &str, String, bool, Instant, &TEnvironment) -> Result<(), ErrBox> + Send + 'static + Clone,
+    F: Fn(&Path, &str, String, Option<Vec<ChangedRegion>>, bool, Instant, &TEnvironment) -> Result<(), ErrBox> + Send + 'static + Clone,
   {
-    let file_text = FileText::new(environment.read_file(&file_path)?);
+    let (file_text, replaced_invalid_utf8) = match read_file_text(file_path, encoding, invalid_utf8, environment)? {
+      Some((file_text, replaced_invalid_utf8)) => (FileText::new(file_text), replaced_invalid_utf8),
+      None => return Ok(()),
+    };
 
     if let Some(incremental_file) = incremental_file {
       if incremental_file.is_file_same(file_path, file_text.as_str()) {
@@ -89,24 +507,151 @@ where
       }
     }
 
-    let (start_instant, formatted_text) = {
+    // skip files with extremely long lines (ex. minified files) because plugins may take an unreasonable amount of time formatting them
+    if let Some(long_line) = max_line_length.and_then(|max_line_length| get_long_line(file_text.as_str(), max_line_length)) {
+      environment.log_warning(&format!(
+        "Warning: Skipped {} because line {} is {} bytes long. Consider adding it to the \"excludes\" or increasing the \"maxLineLength\" in the configuration file.",
+        file_path.display(),
+        long_line.line_number,
+        long_line.byte_len,
+      ));
+      return Ok(());
+    }
+
+    // normalize the line endings before formatting so this applies regardless of the plugin
+    let text_to_format = normalize_mixed_line_endings(file_text.as_str(), mixed_line_endings);
+    let override_config = plugin_pools.get_override_config(file_path, plugin_pool);
+    let cached_text = result_cache
+      .as_ref()
+      .and_then(|result_cache| result_cache.get(plugin_pool.name(), file_path, text_to_format.as_ref(), &override_config));
+    let (start_instant, formatted_text, changed_regions) = if let Some(formatted_text) = cached_text {
+      log_verbose!(environment, "Cached result: {}", file_path.display());
+      (Instant::now(), formatted_text, None)
+    } else {
       let start_instant = Instant::now();
-      let format_text_result = plugin_pool.format_measuring_time(|| initialized_plugin.format_text(file_path, file_text.as_str(), &HashMap::new()));
+      let format_text_result = plugin_pool.format_measuring_time(|| {
+        format_text_with_plugin(
+          plugin_pool,
+          initialized_plugin,
+          file_path,
+          text_to_format.as_ref(),
+          0..text_to_format.len(),
+          &override_config,
+          environment,
+        )
+      });
       log_verbose!(
         environment,
         "Formatted file: {} in {}ms",
         file_path.display(),
         start_instant.elapsed().as_millis()
       );
-      (start_instant, format_text_result?)
+      let changed_regions = match &format_text_result {
+        // the regions are written at their byte offsets in the file, which don't match the bytes on disk once invalid UTF-8 is replaced
+        Ok(_) if replaced_invalid_utf8 => None,
+        Ok(formatted_text) => take_changed_regions(plugin_pool, initialized_plugin, file_path, file_text.as_str(), formatted_text, environment),
+        Err(_) => None,
+      };
+      let format_text_result = format_with_fallback_plugins(
+        file_path,
+        text_to_format.as_ref(),
+        0..text_to_format.len(),
+        plugin_pool.name(),
+        format_text_result,
+        environment,
+        plugin_pools,
+      )?;
+      if let Some(result_cache) = result_cache {
+        result_cache.set(plugin_pool.name(), file_path, text_to_format.as_ref(), &override_config, &format_text_result);
+      }
+      (start_instant, format_text_result, changed_regions)
     };
 
+    if verify_whitespace_only {
+      if plugin_pool.only_changes_whitespace() {
+        super::verify_whitespace_only::verify_whitespace_only(text_to_format.as_ref(), &formatted_text)?;
+      } else {
+        log_verbose!(
+          environment,
+          "Skipped verifying {} because the {} plugin does not declare that it only changes whitespace.",
+          file_path.display(),
+          plugin_pool.name()
+        );
+      }
+    }
+
     if let Some(incremental_file) = incremental_file {
       incremental_file.update_file(file_path, &formatted_text);
     }
 
-    f(&file_path, file_text.as_str(), formatted_text, file_text.has_bom(), start_instant, &environment)?;
+    f(
+      &file_path,
+      file_text.as_str(),
+      formatted_text,
+      changed_regions,
+      file_text.has_bom(),
+      start_instant,
+      &environment,
+    )?;
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod test {
+  use parking_lot::Mutex;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use crate::plugins::TestPlugin;
+
+  #[test]
+  fn should_not_provide_changed_regions_when_invalid_utf8_was_replaced() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/valid.txt", "ab").unwrap();
+    // the invalid byte is before the changed region at the end of the text
+    environment.write_file_bytes("/invalid.txt", &[b'a', 0xFF, b'b']).unwrap();
+    let plugin_pools = Arc::new(PluginPools::new(environment.clone()));
+    plugin_pools.set_plugins(
+      vec![Box::new(TestPlugin::new("test-plugin", "test", vec!["txt"], vec![]).with_changed_regions())],
+      false,
+    );
+    let mut file_paths_by_plugin = HashMap::new();
+    file_paths_by_plugin.insert(String::from("test-plugin"), vec![PathBuf::from("/valid.txt"), PathBuf::from("/invalid.txt")]);
+    let changed_regions_by_file_path = Arc::new(Mutex::new(HashMap::new()));
+
+    run_parallelized(
+      file_paths_by_plugin,
+      &environment,
+      plugin_pools,
+      None,
+      None,
+      Arc::new(FileEncodings::new(&[], &PathBuf::from("/")).unwrap()),
+      InvalidUtf8Policy::Replace,
+      MixedLineEndingsPolicy::Ignore,
+      None,
+      false,
+      {
+        let changed_regions_by_file_path = changed_regions_by_file_path.clone();
+        move |file_path, _, _, changed_regions, _, _, _| {
+          changed_regions_by_file_path.lock().insert(file_path.to_path_buf(), changed_regions);
+          Ok(())
+        }
+      },
+    )
+    .unwrap();
+
+    let changed_regions_by_file_path = changed_regions_by_file_path.lock();
+    assert_eq!(
+      changed_regions_by_file_path[&PathBuf::from("/valid.txt")],
+      Some(vec![ChangedRegion {
+        start: 2,
+        end: 2,
+        new_text: String::from("_formatted"),
+      }])
+    );
+    assert_eq!(changed_regions_by_file_path[&PathBuf::from("/invalid.txt")], None);
+  }
+}
@@ -0,0 +1,127 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use dprint_core::types::ErrBox;
+
+use crate::cache::Cache;
+use crate::environment::{Environment, OutputFormat, RealEnvironment};
+use crate::plugins::{PluginCache, PluginPools, PluginResolver, PluginsDropper};
+
+use super::configuration::resolve_config_from_args;
+use super::format::format_with_plugin_pools;
+use super::patterns::FileMatcher;
+use super::plugins::{resolve_plugins_and_err_if_empty, set_plugin_pools_config};
+use super::{CliArgs, FmtSubCommand, SubCommand};
+
+/// Formats text with the plugins of a configuration file.
+///
+/// The configuration and plugins are resolved once on creation and the plugin
+/// instances are reused between calls, so create one formatter and reuse it.
+pub struct Formatter<TEnvironment: Environment = RealEnvironment> {
+  environment: TEnvironment,
+  file_matcher: FileMatcher,
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
+  _plugins_dropper: PluginsDropper<TEnvironment>,
+}
+
+impl Formatter<RealEnvironment> {
+  /// Creates a formatter for the configuration file at the provided file path or URL.
+  pub fn new(config_path: &str) -> Result<Self, ErrBox> {
+    Formatter::new_with_environment(config_path, RealEnvironment::new_without_signal_handlers(false, true, OutputFormat::Text)?)
+  }
+}
+
+impl<TEnvironment: Environment> Formatter<TEnvironment> {
+  pub(crate) fn new_with_environment(config_path: &str, environment: TEnvironment) -> Result<Self, ErrBox> {
+    let mut args = CliArgs::new_with_sub_command(SubCommand::Fmt(FmtSubCommand {
+      change_log: None,
+      verify_whitespace_only: false,
+      archive_member_patterns: Vec::new(),
+      profile: None,
+      staged: false,
+    }));
+    args.config = Some(config_path.to_string());
+
+    let cache = Cache::new(environment.clone());
+    let plugin_cache = Arc::new(PluginCache::new(environment.clone()));
+    let plugin_pools = Arc::new(PluginPools::new(environment.clone()));
+    let plugins_dropper = PluginsDropper::new(plugin_pools.clone());
+    let plugin_resolver = PluginResolver::new(environment.clone(), plugin_cache, plugin_pools.clone());
+
+    let config = resolve_config_from_args(&args, &cache, &environment)?;
+    let plugins = resolve_plugins_and_err_if_empty(&args, &config, &environment, &plugin_resolver)?;
+    let file_matcher = FileMatcher::new(&config, &args, &environment)?;
+    plugin_pools.set_plugins(plugins, config.fallback_on_error);
+    set_plugin_pools_config(&plugin_pools, &config)?;
+
+    Ok(Formatter {
+      environment,
+      file_matcher,
+      plugin_pools,
+      _plugins_dropper: plugins_dropper,
+    })
+  }
+
+  /// Formats the text of the file at the provided path. Relative paths are resolved
+  /// based on the current working directory and the path must match the configuration
+  /// file's `includes` in order to be formatted.
+  ///
+  /// Returns `None` when the text doesn't change, which includes files that are
+  /// excluded by the configuration or that no plugin formats.
+  pub fn format_text(&self, file_path: impl AsRef<Path>, file_text: &str) -> Result<Option<String>, ErrBox> {
+    let file_path = self.environment.cwd().join(file_path);
+    if !self.file_matcher.matches(&file_path) {
+      return Ok(None);
+    }
+
+    let formatted_text = format_with_plugin_pools(&file_path, file_text, None, &self.environment, &self.plugin_pools)?;
+    if formatted_text == file_text {
+      Ok(None)
+    } else {
+      Ok(Some(formatted_text.into_owned()))
+    }
+  }
+
+  /// Gets if the text of the file at the provided path is formatted.
+  pub fn check_text(&self, file_path: impl AsRef<Path>, file_text: &str) -> Result<bool, ErrBox> {
+    Ok(self.format_text(file_path, file_text)?.is_none())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironmentBuilder;
+
+  #[test]
+  fn should_format_text() {
+    let environment = TestEnvironmentBuilder::new()
+      .add_remote_wasm_plugin()
+      .with_default_config(|config_file| {
+        config_file.add_remote_wasm_plugin().add_includes("**/*");
+      })
+      .initialize()
+      .build();
+    let formatter = Formatter::new_with_environment("/dprint.json", environment.clone()).unwrap();
+    assert_eq!(formatter.format_text("/file.txt", "text").unwrap(), Some("text_formatted".to_string()));
+    assert_eq!(formatter.format_text("/file.txt", "text_formatted").unwrap(), None);
+    // no plugin formats this file
+    assert_eq!(formatter.format_text("/file.other", "text").unwrap(), None);
+    assert_eq!(formatter.check_text("/file.txt", "text").unwrap(), false);
+    assert_eq!(formatter.check_text("/file.txt", "text_formatted").unwrap(), true);
+  }
+
+  #[test]
+  fn should_not_format_excluded_file() {
+    let environment = TestEnvironmentBuilder::new()
+      .add_remote_wasm_plugin()
+      .with_default_config(|config_file| {
+        config_file.add_remote_wasm_plugin().add_includes("**/*").add_excludes("ignored/**/*");
+      })
+      .initialize()
+      .build();
+    let formatter = Formatter::new_with_environment("/dprint.json", environment.clone()).unwrap();
+    assert_eq!(formatter.format_text("/ignored/file.txt", "text").unwrap(), None);
+    assert_eq!(formatter.format_text("/file.txt", "text").unwrap(), Some("text_formatted".to_string()));
+  }
+}
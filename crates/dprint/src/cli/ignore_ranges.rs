@@ -0,0 +1,177 @@
+use dprint_cli_core::types::ErrBox;
+
+const IGNORE_START_DIRECTIVE: &str = "dprint-ignore-start";
+const IGNORE_END_DIRECTIVE: &str = "dprint-ignore-end";
+/// The start and end of the comments a directive may be written in.
+const COMMENT_DELIMITERS: [(&str, &str); 8] = [
+  ("//", ""),
+  ("#", ""),
+  ("--", ""),
+  (";", ""),
+  ("%", ""),
+  ("/*", "*/"),
+  ("<!--", "-->"),
+  ("{/*", "*/}"),
+];
+
+/// Formats the text outside of any `dprint-ignore-start` and `dprint-ignore-end` ranges
+/// using the provided function, then reassembles it with the ignored text left as-is.
+/// The function is provided each segment of text along with its byte index in the file text.
+///
+/// The ranges span from the start of the line with the start directive to the end of
+/// the line with the end directive. A directive must be the only text of a comment on
+/// its own line (ex. `// dprint-ignore-start`).
+pub fn format_with_ignore_ranges(file_text: &str, mut format_text: impl FnMut(&str, usize) -> Result<String, ErrBox>) -> Result<String, ErrBox> {
+  let ignore_ranges = get_ignore_ranges(file_text)?;
+  if ignore_ranges.is_empty() {
+    return format_text(file_text, 0);
+  }
+
+  let mut final_text = String::with_capacity(file_text.len());
+  let mut last_end = 0;
+  for (start, end) in ignore_ranges.into_iter().chain(std::iter::once((file_text.len(), file_text.len()))) {
+    let segment = &file_text[last_end..start];
+    if segment.trim().is_empty() {
+      final_text.push_str(segment);
+    } else {
+      final_text.push_str(&format_text(segment, last_end)?);
+    }
+    final_text.push_str(&file_text[start..end]);
+    last_end = end;
+  }
+
+  Ok(final_text)
+}
+
+/// Gets the byte ranges of the ignored text.
+fn get_ignore_ranges(file_text: &str) -> Result<Vec<(usize, usize)>, ErrBox> {
+  let mut ranges = Vec::new();
+  let mut current_start: Option<(usize, usize)> = None;
+  let mut line_start = 0;
+
+  for (line_index, line) in file_text.split_inclusive('\n').enumerate() {
+    let line_end = line_start + line.len();
+    if is_directive_line(line, IGNORE_START_DIRECTIVE) {
+      if current_start.is_some() {
+        return err!("Found nested '{}' directive on line {}.", IGNORE_START_DIRECTIVE, line_index + 1);
+      }
+      current_start = Some((line_start, line_index));
+    } else if is_directive_line(line, IGNORE_END_DIRECTIVE) {
+      match current_start.take() {
+        Some((start, _)) => ranges.push((start, line_end)),
+        None => {
+          return err!(
+            "Found '{}' directive without a preceding '{}' on line {}.",
+            IGNORE_END_DIRECTIVE,
+            IGNORE_START_DIRECTIVE,
+            line_index + 1
+          )
+        }
+      }
+    }
+    line_start = line_end;
+  }
+
+  if let Some((_, line_index)) = current_start {
+    return err!(
+      "Expected a '{}' directive for the '{}' on line {}.",
+      IGNORE_END_DIRECTIVE,
+      IGNORE_START_DIRECTIVE,
+      line_index + 1
+    );
+  }
+
+  Ok(ranges)
+}
+
+/// Gets if the line only contains a comment whose text is the directive.
+fn is_directive_line(line: &str, directive: &str) -> bool {
+  let line = line.trim();
+  COMMENT_DELIMITERS.iter().any(|(start, end)| {
+    line.len() >= start.len() + end.len() && line.starts_with(start) && line.ends_with(end) && line[start.len()..line.len() - end.len()].trim() == directive
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn format_upper(text: &str, _: usize) -> Result<String, ErrBox> {
+    Ok(text.to_uppercase())
+  }
+
+  #[test]
+  fn it_should_format_whole_text_when_no_directives() {
+    assert_eq!(format_with_ignore_ranges("a\nb\n", format_upper).unwrap(), "A\nB\n");
+  }
+
+  #[test]
+  fn it_should_not_format_ignored_ranges() {
+    let text = "a\n// dprint-ignore-start\nb\n// dprint-ignore-end\nc\n// dprint-ignore-start\nd\n// dprint-ignore-end";
+    assert_eq!(
+      format_with_ignore_ranges(text, format_upper).unwrap(),
+      "A\n// dprint-ignore-start\nb\n// dprint-ignore-end\nC\n// dprint-ignore-start\nd\n// dprint-ignore-end"
+    );
+  }
+
+  #[test]
+  fn it_should_not_format_whitespace_only_segments() {
+    let mut segments = Vec::new();
+    let text = "<!-- dprint-ignore-start -->\na\n<!-- dprint-ignore-end -->\n\nb\n";
+    let result = format_with_ignore_ranges(text, |text, text_start| {
+      segments.push((text.to_string(), text_start));
+      format_upper(text, text_start)
+    })
+    .unwrap();
+    assert_eq!(result, "<!-- dprint-ignore-start -->\na\n<!-- dprint-ignore-end -->\n\nB\n");
+    assert_eq!(segments, vec![(String::from("\nb\n"), 58)]);
+  }
+
+  #[test]
+  fn it_should_only_use_directives_that_are_the_whole_comment_on_a_line() {
+    let text = concat!(
+      "const a = \"dprint-ignore-start\";\n",
+      "// use dprint-ignore-start to ignore\n",
+      "b // dprint-ignore-start\n",
+      "  /* dprint-ignore-start */\n",
+      "c\n",
+      "{/* dprint-ignore-end */}\n",
+      "d\n",
+    );
+    assert_eq!(
+      format_with_ignore_ranges(text, format_upper).unwrap(),
+      concat!(
+        "CONST A = \"DPRINT-IGNORE-START\";\n",
+        "// USE DPRINT-IGNORE-START TO IGNORE\n",
+        "B // DPRINT-IGNORE-START\n",
+        "  /* dprint-ignore-start */\n",
+        "c\n",
+        "{/* dprint-ignore-end */}\n",
+        "D\n",
+      )
+    );
+  }
+
+  #[test]
+  fn it_should_error_for_unterminated_start() {
+    let err = format_with_ignore_ranges("a\n# dprint-ignore-start\nb", format_upper).err().unwrap();
+    assert_eq!(err.to_string(), "Expected a 'dprint-ignore-end' directive for the 'dprint-ignore-start' on line 2.");
+  }
+
+  #[test]
+  fn it_should_error_for_end_without_start() {
+    let err = format_with_ignore_ranges("a\n# dprint-ignore-end\n", format_upper).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Found 'dprint-ignore-end' directive without a preceding 'dprint-ignore-start' on line 2."
+    );
+  }
+
+  #[test]
+  fn it_should_error_for_nested_start() {
+    let err = format_with_ignore_ranges("// dprint-ignore-start\n// dprint-ignore-start\n", format_upper).err().unwrap();
+    assert_eq!(err.to_string(), "Found nested 'dprint-ignore-start' directive on line 2.");
+  }
+}
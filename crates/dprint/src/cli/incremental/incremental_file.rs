@@ -4,7 +4,10 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use crate::environment::Environment;
-use crate::utils::get_bytes_hash;
+use crate::utils::{get_bytes_hash, GlobMatcher};
+
+/// Directive in the leading comment of a file that makes it always be formatted.
+const FORCE_FORMAT_DIRECTIVE: &str = "dprint-force-format";
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -27,11 +30,13 @@ pub struct IncrementalFile<TEnvironment: Environment> {
   read_data: IncrementalFileData,
   write_data: Mutex<IncrementalFileData>,
   base_dir_path: PathBuf,
+  /// Matches the files that should always be formatted (the `forceFormat` patterns).
+  force_format_matcher: Option<GlobMatcher>,
   environment: TEnvironment,
 }
 
 impl<TEnvironment: Environment> IncrementalFile<TEnvironment> {
-  pub fn new(file_path: PathBuf, plugins_hash: u64, environment: TEnvironment, base_dir_path: PathBuf) -> Self {
+  pub fn new(file_path: PathBuf, plugins_hash: u64, environment: TEnvironment, base_dir_path: PathBuf, force_format_matcher: Option<GlobMatcher>) -> Self {
     let read_data = read_incremental(&file_path, &environment);
     let read_data = if let Some(read_data) = read_data {
       if read_data.plugins_hash == plugins_hash {
@@ -48,12 +53,16 @@ impl<TEnvironment: Environment> IncrementalFile<TEnvironment> {
       read_data,
       write_data: Mutex::new(IncrementalFileData::new(plugins_hash)),
       base_dir_path,
+      force_format_matcher,
       environment,
     }
   }
 
   pub fn is_file_same(&self, file_path: &Path, file_text: &str) -> bool {
     let file_path = self.standardize_path(file_path);
+    if self.is_force_format_path(&file_path) || has_force_format_directive(file_text) {
+      return false;
+    }
     if let Some(hash) = self.read_data.file_hashes.get(&file_path) {
       if *hash == get_bytes_hash(file_text.as_bytes()) {
         // the file is the same, so save it in the write data
@@ -67,6 +76,14 @@ impl<TEnvironment: Environment> IncrementalFile<TEnvironment> {
     }
   }
 
+  /// Gets if the file was formatted when its text had the provided hash without
+  /// recording it for the next write. Used when the text isn't available, so only
+  /// the `forceFormat` patterns and not the directive apply.
+  pub fn is_file_hash_same(&self, file_path: &Path, file_hash: u64) -> bool {
+    let file_path = self.standardize_path(file_path);
+    !self.is_force_format_path(&file_path) && self.read_data.file_hashes.get(&file_path) == Some(&file_hash)
+  }
+
   pub fn update_file(&self, file_path: &Path, file_text: &str) {
     self.add_to_write_data(self.standardize_path(file_path), file_text)
   }
@@ -82,6 +99,13 @@ impl<TEnvironment: Environment> IncrementalFile<TEnvironment> {
     write_incremental(&self.file_path, &write_data, &self.environment);
   }
 
+  fn is_force_format_path(&self, file_path: &Path) -> bool {
+    match &self.force_format_matcher {
+      Some(matcher) => matcher.is_match(file_path.to_string_lossy().replace("\\", "/")),
+      None => false,
+    }
+  }
+
   fn standardize_path(&self, file_path: &Path) -> PathBuf {
     // need to ensure the file is stored as an absolute path
     if self.environment.is_absolute_path(file_path) {
@@ -92,6 +116,19 @@ impl<TEnvironment: Environment> IncrementalFile<TEnvironment> {
   }
 }
 
+/// Gets if the leading comment of the file has the force format directive. This
+/// checks the lines at the top of the file up to the first blank line.
+fn has_force_format_directive(file_text: &str) -> bool {
+  file_text
+    .lines()
+    .take_while(|line| !line.trim().is_empty())
+    .any(|line| line.contains(FORCE_FORMAT_DIRECTIVE))
+}
+
+/// Prefix of the first line of the incremental file, which contains the checksum of the
+/// rest of the file. This is used to detect files that were only partially written.
+const CHECKSUM_HEADER_PREFIX: &'static str = "checksum:";
+
 fn read_incremental(file_path: &Path, environment: &impl Environment) -> Option<IncrementalFileData> {
   let file_text = match environment.read_file(file_path) {
     Ok(file_text) => file_text,
@@ -103,15 +140,53 @@ fn read_incremental(file_path: &Path, environment: &impl Environment) -> Option<
     }
   };
 
-  match serde_json::from_str(&file_text) {
+  let json_text = match get_verified_json_text(&file_text) {
+    Some(Ok(json_text)) => json_text,
+    Some(Err(message)) => {
+      log_corrupt_incremental_file(file_path, message, environment);
+      return None;
+    }
+    None => {
+      // incremental files from previous versions of dprint won't have a checksum
+      log_verbose!(environment, "Incremental file had no checksum. Creating new incremental file.");
+      return None;
+    }
+  };
+
+  match serde_json::from_str(json_text) {
     Ok(file_data) => Some(file_data),
     Err(err) => {
-      environment.log_error(&format!("Error deserializing incremental file {}: {}", file_path.display(), err.to_string()));
+      log_corrupt_incremental_file(file_path, &err.to_string(), environment);
       None
     }
   }
 }
 
+/// Gets the json text after verifying it against the checksum or `None` when there is no checksum header.
+fn get_verified_json_text(file_text: &str) -> Option<Result<&str, &'static str>> {
+  if !file_text.starts_with(CHECKSUM_HEADER_PREFIX) {
+    return None;
+  }
+
+  let (header, json_text) = match file_text.find('\n') {
+    Some(index) => (&file_text[..index], &file_text[index + 1..]),
+    None => return Some(Err("Missing contents.")),
+  };
+  Some(match header[CHECKSUM_HEADER_PREFIX.len()..].parse::<u64>() {
+    Ok(checksum) if checksum == get_bytes_hash(json_text.as_bytes()) => Ok(json_text),
+    Ok(_) => Err("Checksum mismatch."),
+    Err(_) => Err("Invalid checksum."),
+  })
+}
+
+fn log_corrupt_incremental_file(file_path: &Path, message: &str, environment: &impl Environment) {
+  environment.log_warning(&format!(
+    "Warning: The incremental file {} was corrupt and will be recreated. {}",
+    file_path.display(),
+    message
+  ));
+}
+
 fn write_incremental(file_path: &Path, file_data: &IncrementalFileData, environment: &impl Environment) {
   let json_text = match serde_json::to_string(&file_data) {
     Ok(json_text) => json_text,
@@ -120,10 +195,99 @@ fn write_incremental(file_path: &Path, file_data: &IncrementalFileData, environm
       return;
     }
   };
-  match environment.write_file(file_path, &json_text) {
-    Err(err) => {
-      environment.log_error(&format!("Error saving incremental file {}: {}", file_path.display(), err.to_string()));
-    }
-    _ => {}
+  let file_text = format!("{}{}\n{}", CHECKSUM_HEADER_PREFIX, get_bytes_hash(json_text.as_bytes()), json_text);
+
+  // write to a temporary file then rename it so the incremental file is never partially written
+  let temp_file_path = {
+    let mut temp_file_path = file_path.as_os_str().to_owned();
+    temp_file_path.push(format!(".{}.tmp", std::process::id()));
+    PathBuf::from(temp_file_path)
   };
+  let result = environment
+    .write_file(&temp_file_path, &file_text)
+    .and_then(|_| environment.rename(&temp_file_path, file_path));
+  if let Err(err) = result {
+    environment.log_error(&format!("Error saving incremental file {}: {}", file_path.display(), err.to_string()));
+    let _ = environment.remove_file(&temp_file_path);
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use crate::utils::GlobMatcherOptions;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_write_and_read_incremental_file() {
+    let environment = TestEnvironment::new();
+    let file_path = PathBuf::from("/cache/file.incremental");
+    let incremental_file = IncrementalFile::new(file_path.clone(), 1, environment.clone(), PathBuf::from("/"), None);
+    incremental_file.update_file(&PathBuf::from("/file.txt"), "text");
+    incremental_file.write();
+
+    let file_text = environment.read_file(&file_path).unwrap();
+    assert_eq!(file_text.starts_with(CHECKSUM_HEADER_PREFIX), true);
+    assert_eq!(environment.path_exists(format!("/cache/file.incremental.{}.tmp", std::process::id())), false);
+
+    let incremental_file = IncrementalFile::new(file_path, 1, environment.clone(), PathBuf::from("/"), None);
+    assert_eq!(incremental_file.is_file_same(&PathBuf::from("/file.txt"), "text"), true);
+    assert_eq!(incremental_file.is_file_same(&PathBuf::from("/file.txt"), "other"), false);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn should_recreate_when_incremental_file_truncated() {
+    let environment = TestEnvironment::new();
+    let file_path = PathBuf::from("/cache/file.incremental");
+    let incremental_file = IncrementalFile::new(file_path.clone(), 1, environment.clone(), PathBuf::from("/"), None);
+    incremental_file.update_file(&PathBuf::from("/file.txt"), "text");
+    incremental_file.write();
+    let file_text = environment.read_file(&file_path).unwrap();
+    environment.write_file(&file_path, &file_text[..file_text.len() - 5]).unwrap();
+
+    let incremental_file = IncrementalFile::new(file_path, 1, environment.clone(), PathBuf::from("/"), None);
+    assert_eq!(incremental_file.is_file_same(&PathBuf::from("/file.txt"), "text"), false);
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Warning: The incremental file /cache/file.incremental was corrupt and will be recreated. Checksum mismatch."]
+    );
+    assert_eq!(environment.warning_count().get(), 1);
+  }
+
+  #[test]
+  fn should_recreate_without_warning_when_incremental_file_missing_checksum() {
+    let environment = TestEnvironment::new();
+    let file_path = PathBuf::from("/cache/file.incremental");
+    environment
+      .write_file(&file_path, r#"{"pluginsHash":1,"fileHashes":{"/file.txt":1}}"#)
+      .unwrap();
+
+    let incremental_file = IncrementalFile::new(file_path, 1, environment.clone(), PathBuf::from("/"), None);
+    assert_eq!(incremental_file.is_file_same(&PathBuf::from("/file.txt"), "text"), false);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn should_not_be_same_for_force_format_files() {
+    let environment = TestEnvironment::new();
+    let file_path = PathBuf::from("/cache/file.incremental");
+    let incremental_file = IncrementalFile::new(file_path.clone(), 1, environment.clone(), PathBuf::from("/"), None);
+    let directive_text = "// generated\n// dprint-force-format\ntext";
+    incremental_file.update_file(&PathBuf::from("/file.txt"), directive_text);
+    incremental_file.update_file(&PathBuf::from("/generated/file.txt"), "text");
+    let other_text = "// comment\n\n// dprint-force-format\n";
+    incremental_file.update_file(&PathBuf::from("/other.txt"), other_text);
+    incremental_file.write();
+
+    let matcher = GlobMatcher::new(&["/generated/**/*.txt".to_string()], &GlobMatcherOptions { case_insensitive: false }).unwrap();
+    let incremental_file = IncrementalFile::new(file_path, 1, environment.clone(), PathBuf::from("/"), Some(matcher));
+    assert_eq!(incremental_file.is_file_same(&PathBuf::from("/file.txt"), directive_text), false);
+    assert_eq!(incremental_file.is_file_same(&PathBuf::from("/generated/file.txt"), "text"), false);
+    let text_hash = get_bytes_hash(b"text");
+    assert_eq!(incremental_file.is_file_hash_same(&PathBuf::from("/generated/file.txt"), text_hash), false);
+    // the directive is only found in the leading comment
+    assert_eq!(incremental_file.is_file_same(&PathBuf::from("/other.txt"), other_text), true);
+  }
 }
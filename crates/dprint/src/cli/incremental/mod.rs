@@ -2,11 +2,13 @@ mod incremental_file;
 
 pub use incremental_file::IncrementalFile;
 
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::cache::{Cache, CreateCacheItemOptions};
 use crate::environment::Environment;
 use crate::plugins::PluginPools;
+use crate::utils::{get_bytes_hash, to_absolute_globs, GlobMatcher, GlobMatcherOptions};
 
 use super::configuration::ResolvedConfig;
 use super::CliArgs;
@@ -18,8 +20,13 @@ pub fn get_incremental_file<TEnvironment: Environment>(
   plugin_pools: &PluginPools<TEnvironment>,
   environment: &TEnvironment,
 ) -> Option<Arc<IncrementalFile<TEnvironment>>> {
-  if args.incremental || config.incremental {
-    // the incremental file is stored in the cache with a key based on the root directory
+  // only use the incremental setting in the configuration file outside CI so every file is verified there
+  let use_config_incremental = config.incremental && !environment.is_ci();
+  let use_args_incremental = args.incremental || args.incremental_cache_path.is_some();
+  if config.incremental && environment.is_ci() && !use_args_incremental {
+    log_verbose!(environment, "Ignoring incremental configuration in CI environment. Specify --incremental to use it.");
+  }
+  if use_args_incremental || use_config_incremental {
     let base_path = match environment.canonicalize(&config.base_path) {
       Ok(base_path) => base_path,
       Err(err) => {
@@ -27,32 +34,69 @@ pub fn get_incremental_file<TEnvironment: Environment>(
         return None;
       }
     };
-    let key = format!("incremental_cache:{}", base_path.to_string_lossy());
-    let cache_item = if let Some(cache_item) = cache.get_cache_item(&key) {
-      cache_item
+    let file_path = if let Some(incremental_cache_path) = &args.incremental_cache_path {
+      let file_path = environment.cwd().join(incremental_cache_path);
+      if let Some(parent) = file_path.parent() {
+        if let Err(err) = environment.mk_dir_all(parent) {
+          environment.log_error(&format!("Could not create directory for incremental file {}. {}", file_path.display(), err));
+          return None;
+        }
+      }
+      file_path
     } else {
-      let cache_item = cache.create_cache_item(CreateCacheItemOptions {
-        key,
-        extension: "incremental",
-        bytes: None,
-        meta_data: None,
-      });
-      match cache_item {
-        Ok(cache_item) => cache_item,
+      get_cache_item_file_path(&base_path, cache, environment)?
+    };
+    let force_format_matcher = if config.force_format.is_empty() {
+      None
+    } else {
+      let patterns = to_absolute_globs(config.force_format.clone(), &base_path.to_string_lossy());
+      let glob_matcher_options = GlobMatcherOptions {
+        case_insensitive: cfg!(windows),
+      };
+      match GlobMatcher::new(&patterns, &glob_matcher_options) {
+        Ok(matcher) => Some(matcher),
         Err(err) => {
-          environment.log_error(&format!("Could not create cache item for incremental feature. {}", err));
+          environment.log_error(&format!("Could not create matcher for the forceFormat patterns. {}", err));
           return None;
         }
       }
     };
-    let file_path = cache.resolve_cache_item_file_path(&cache_item);
     Some(Arc::new(IncrementalFile::new(
       file_path,
-      plugin_pools.get_plugins_hash(),
+      plugin_pools.get_plugins_hash().wrapping_add(get_file_text_config_hash(config)),
       environment.clone(),
       base_path,
+      force_format_matcher,
     )))
   } else {
     None
   }
 }
+
+/// Gets a hash of the configuration outside the plugins that changes the text a file is formatted to.
+fn get_file_text_config_hash(config: &ResolvedConfig) -> u64 {
+  get_bytes_hash(format!("{:?}{:?}{:?}", config.invalid_utf8, config.mixed_line_endings, config.encodings).as_bytes())
+}
+
+fn get_cache_item_file_path<TEnvironment: Environment>(base_path: &Path, cache: &Cache<TEnvironment>, environment: &TEnvironment) -> Option<PathBuf> {
+  // the incremental file is stored in the cache with a key based on the root directory
+  let key = format!("incremental_cache:{}", base_path.to_string_lossy());
+  let cache_item = if let Some(cache_item) = cache.get_cache_item(&key) {
+    cache_item
+  } else {
+    let cache_item = cache.create_cache_item(CreateCacheItemOptions {
+      key,
+      extension: "incremental",
+      bytes: None,
+      meta_data: None,
+    });
+    match cache_item {
+      Ok(cache_item) => cache_item,
+      Err(err) => {
+        environment.log_error(&format!("Could not create cache item for incremental feature. {}", err));
+        return None;
+      }
+    }
+  };
+  Some(cache.resolve_cache_item_file_path(&cache_item))
+}
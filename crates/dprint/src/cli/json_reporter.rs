@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::environment::Environment;
+use crate::utils::get_iso_8601_timestamp;
+
+/// Outputs the JSON objects of a command (ex. `check` or `fmt`). All the objects
+/// are created here so each command outputs the timings in the same way.
+#[derive(Clone)]
+pub struct JsonReporter<TEnvironment: Environment> {
+  environment: TEnvironment,
+  start_time_secs: u64,
+  start_instant: Instant,
+}
+
+impl<TEnvironment: Environment> JsonReporter<TEnvironment> {
+  /// Creates the reporter, which should be done when the command starts formatting.
+  pub fn new(environment: &TEnvironment) -> Self {
+    JsonReporter {
+      environment: environment.clone(),
+      start_time_secs: environment.get_time_secs(),
+      start_instant: Instant::now(),
+    }
+  }
+
+  /// Outputs an object about a file including the time it took to format it.
+  pub fn log_file(&self, kind: &str, file_path: &Path, duration: Duration, properties: Value) {
+    let mut value = json!({
+      "kind": kind,
+      "filePath": file_path.display().to_string(),
+      "durationMs": duration.as_millis() as u64,
+    });
+    extend_value(&mut value, properties);
+    self.environment.log_json(&value);
+  }
+
+  /// Outputs the summary of the command, which includes when it started and ended.
+  pub fn log_summary(&self, kind: &str, properties: Value) {
+    let mut value = json!({
+      "kind": kind,
+      "startTime": get_iso_8601_timestamp(self.start_time_secs),
+      "endTime": get_iso_8601_timestamp(self.environment.get_time_secs()),
+      "durationMs": self.start_instant.elapsed().as_millis() as u64,
+    });
+    extend_value(&mut value, properties);
+    self.environment.log_json(&value);
+  }
+}
+
+fn extend_value(value: &mut Value, properties: Value) {
+  if let (Value::Object(value), Value::Object(properties)) = (value, properties) {
+    value.extend(properties);
+  }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use dprint_cli_core::styling::{bold, bold_red};
+use dprint_core::configuration::ConfigKeyValue;
+use dprint_core::types::ErrBox;
+use serde_json::json;
+
+use crate::environment::{Environment, OutputFormat};
+use crate::plugins::{InitializedPluginPool, PluginPools, TakePluginResult};
+use crate::utils::ErrorCountLogger;
+
+/// The line width and indent width of a plugin's resolved configuration.
+struct PluginWidths {
+  line_width: Option<u32>,
+  indent_width: u32,
+}
+
+/// Outputs the lines of the files that are longer than the line width of the plugin that
+/// formats them. Plugins may exceed the line width when they can't break up a line
+/// (ex. a long string), so this is useful for enforcing a hard limit.
+pub fn lint_width<TEnvironment: Environment>(
+  file_paths_by_plugin: HashMap<String, Vec<PathBuf>>,
+  environment: &TEnvironment,
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
+) -> Result<(), ErrBox> {
+  let error_logger = ErrorCountLogger::from_environment(environment);
+  let is_json_output = environment.output_format() == OutputFormat::Json;
+  let mut long_lines_count = 0;
+
+  let mut plugin_names = file_paths_by_plugin.keys().collect::<Vec<_>>();
+  plugin_names.sort();
+  for plugin_name in plugin_names {
+    let plugin_pool = plugin_pools.get_pool(plugin_name).unwrap();
+    let plugin_widths = match get_plugin_widths(&plugin_pool, &error_logger)? {
+      Some(plugin_widths) => plugin_widths,
+      None => continue,
+    };
+
+    let mut file_paths = file_paths_by_plugin[plugin_name].clone();
+    file_paths.sort();
+    for file_path in file_paths {
+      let override_config = plugin_pools.get_override_config(&file_path, &plugin_pool);
+      let line_width = match override_config.get("lineWidth") {
+        Some(ConfigKeyValue::Number(value)) => Some(*value as u32),
+        _ => plugin_widths.line_width,
+      };
+      let line_width = match line_width {
+        Some(line_width) => line_width,
+        None => {
+          log_verbose!(
+            environment,
+            "Skipped {} because the {} plugin does not have a lineWidth configuration.",
+            file_path.display(),
+            plugin_name
+          );
+          continue;
+        }
+      };
+      let file_text = match environment.read_file(&file_path) {
+        Ok(file_text) => file_text,
+        Err(err) => {
+          error_logger.log_error(&format!("Error reading {}. {}", file_path.display(), err.to_string()));
+          continue;
+        }
+      };
+
+      for (line_index, line) in file_text.lines().enumerate() {
+        let width = get_line_width(line, plugin_widths.indent_width);
+        if width <= line_width {
+          continue;
+        }
+        long_lines_count += 1;
+        if is_json_output {
+          environment.log_json(&json!({
+            "kind": "longLine",
+            "filePath": file_path.display().to_string(),
+            "line": line_index + 1,
+            "width": width,
+            "lineWidth": line_width,
+            "plugin": plugin_name,
+          }));
+        } else {
+          environment.log(&format!(
+            "{} {}:{}: Line is {} columns, which exceeds the line width of {} ({}).",
+            bold_red("long line"),
+            file_path.display(),
+            line_index + 1,
+            width,
+            line_width,
+            plugin_name,
+          ));
+        }
+      }
+    }
+  }
+
+  let error_count = error_logger.get_error_count();
+  if error_count > 0 {
+    err!("Had {} error(s) linting the line widths.", error_count)
+  } else if long_lines_count > 0 {
+    err!("Found {} line(s) exceeding the line width.", bold(long_lines_count))
+  } else {
+    Ok(())
+  }
+}
+
+fn get_plugin_widths<TEnvironment: Environment>(
+  plugin_pool: &InitializedPluginPool<TEnvironment>,
+  error_logger: &ErrorCountLogger<TEnvironment>,
+) -> Result<Option<PluginWidths>, ErrBox> {
+  let initialized_plugin = match plugin_pool.take_or_create_checking_config_diagnostics(error_logger)? {
+    TakePluginResult::Success(initialized_plugin) => initialized_plugin,
+    TakePluginResult::HadDiagnostics => return Ok(None),
+  };
+  let result = initialized_plugin.get_resolved_config();
+  plugin_pool.release(initialized_plugin);
+  let resolved_config = match serde_json::from_str::<serde_json::Value>(&result?) {
+    Ok(resolved_config) => resolved_config,
+    Err(err) => {
+      return err!(
+        "Error parsing the resolved configuration of the {} plugin. {}",
+        plugin_pool.name(),
+        err.to_string()
+      )
+    }
+  };
+  Ok(Some(PluginWidths {
+    line_width: resolved_config.get("lineWidth").and_then(|value| value.as_u64()).map(|value| value as u32),
+    // tabs are counted the same way the printer counts them
+    indent_width: resolved_config.get("indentWidth").and_then(|value| value.as_u64()).unwrap_or(1) as u32,
+  }))
+}
+
+fn get_line_width(line: &str, indent_width: u32) -> u32 {
+  line.chars().map(|c| if c == '\t' { indent_width } else { 1 }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_get_line_width() {
+    assert_eq!(get_line_width("", 2), 0);
+    assert_eq!(get_line_width("test", 2), 4);
+    assert_eq!(get_line_width("\t\ttest", 4), 12);
+    assert_eq!(get_line_width("√ℹ", 2), 2);
+  }
+}
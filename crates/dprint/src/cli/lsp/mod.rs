@@ -0,0 +1,441 @@
+mod positions;
+mod transport;
+
+pub use transport::LspTransport;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+
+use dprint_cli_core::types::ErrBox;
+use dprint_core::plugins::process::start_parent_process_checker_thread;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::configuration::resolve_config_from_args;
+use super::configuration::ResolvedConfig;
+use super::format::format_with_plugin_pools;
+use super::patterns::FileMatcher;
+use super::plugins::{resolve_plugins, set_plugin_pools_config};
+use super::CliArgs;
+use crate::cache::Cache;
+use crate::environment::Environment;
+use crate::plugins::{PluginPools, PluginResolver};
+use positions::{get_byte_index, get_position, LspRange, Position};
+use transport::{LspReader, LspWriter};
+
+// JSON-RPC and Language Server Protocol error codes
+const METHOD_NOT_FOUND_ERROR_CODE: i32 = -32601;
+const INVALID_PARAMS_ERROR_CODE: i32 = -32602;
+const REQUEST_FAILED_ERROR_CODE: i32 = -32803;
+const REQUEST_CANCELLED_ERROR_CODE: i32 = -32800;
+const CONTENT_MODIFIED_ERROR_CODE: i32 = -32801;
+
+/// Value of `TextDocumentSyncKind.Full`, meaning the client sends the entire text on each change.
+const FULL_TEXT_DOCUMENT_SYNC_KIND: u32 = 1;
+
+pub fn run_lsp<TEnvironment: Environment>(
+  args: &CliArgs,
+  cache: &Cache<TEnvironment>,
+  environment: &TEnvironment,
+  plugin_resolver: &PluginResolver<TEnvironment>,
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
+) -> Result<(), ErrBox> {
+  let mut server = LspServer::new(args, cache, environment, plugin_resolver, plugin_pools);
+  server.run()
+}
+
+#[derive(Deserialize)]
+struct Message {
+  /// Requests have an id and notifications don't.
+  id: Option<Value>,
+  method: String,
+  #[serde(default)]
+  params: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InitializeParams {
+  process_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct TextDocumentIdentifier {
+  uri: String,
+}
+
+#[derive(Deserialize)]
+struct TextDocumentItem {
+  uri: String,
+  text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DidOpenTextDocumentParams {
+  text_document: TextDocumentItem,
+}
+
+#[derive(Deserialize)]
+struct TextDocumentContentChangeEvent {
+  text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DidChangeTextDocumentParams {
+  text_document: TextDocumentIdentifier,
+  content_changes: Vec<TextDocumentContentChangeEvent>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DidCloseTextDocumentParams {
+  text_document: TextDocumentIdentifier,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentFormattingParams {
+  text_document: TextDocumentIdentifier,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentRangeFormattingParams {
+  text_document: TextDocumentIdentifier,
+  range: LspRange,
+}
+
+/// The formatting request being handled, which is cancelled from the thread
+/// reading the messages when the document changes or the request is cancelled.
+struct FormatInProgress {
+  request_id: Option<Value>,
+  uri: String,
+  /// The path of the file being formatted once it's known.
+  file_path: Option<PathBuf>,
+  /// The error code and message to respond with once cancelled.
+  cancellation: Option<(i32, &'static str)>,
+}
+
+struct LspServer<'a, TEnvironment: Environment> {
+  writer: LspWriter,
+  receiver: Receiver<Result<Option<Value>, ErrBox>>,
+  format_in_progress: Arc<Mutex<Option<FormatInProgress>>>,
+  config: Option<ResolvedConfig>,
+  /// Text of the open documents by uri.
+  documents: HashMap<String, String>,
+  has_shutdown: bool,
+  args: &'a CliArgs,
+  cache: &'a Cache<TEnvironment>,
+  environment: &'a TEnvironment,
+  plugin_resolver: &'a PluginResolver<TEnvironment>,
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
+}
+
+impl<'a, TEnvironment: Environment> LspServer<'a, TEnvironment> {
+  pub fn new(
+    args: &'a CliArgs,
+    cache: &'a Cache<TEnvironment>,
+    environment: &'a TEnvironment,
+    plugin_resolver: &'a PluginResolver<TEnvironment>,
+    plugin_pools: Arc<PluginPools<TEnvironment>>,
+  ) -> Self {
+    let (reader, writer) = LspTransport::new(environment.stdin(), environment.stdout()).split();
+    let format_in_progress = Arc::new(Mutex::new(None));
+    LspServer {
+      writer,
+      receiver: start_reader_thread(reader, format_in_progress.clone(), plugin_pools.clone()),
+      format_in_progress,
+      config: None,
+      documents: HashMap::new(),
+      has_shutdown: false,
+      args,
+      cache,
+      environment,
+      plugin_resolver,
+      plugin_pools,
+    }
+  }
+
+  pub fn run(&mut self) -> Result<(), ErrBox> {
+    while let Some(message) = self.receiver.recv()?? {
+      let message: Message = serde_json::from_value(message)?;
+      log_verbose!(self.environment, "LSP message: {}", message.method);
+      if message.method == "exit" {
+        break;
+      }
+
+      let result = self.handle_message(&message);
+      if let Some(id) = message.id {
+        let response = match result {
+          Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+          Err((code, err)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": err.to_string() },
+          }),
+        };
+        self.writer.send_message(&response)?;
+      } else if let Err((_, err)) = result {
+        self.environment.log_error(&format!("Error handling {}: {}", message.method, err));
+      }
+    }
+
+    if self.has_shutdown {
+      Ok(())
+    } else {
+      err!("The language server exited without a shutdown request.")
+    }
+  }
+
+  /// Handles the message returning the result for requests. Notifications have a null result.
+  fn handle_message(&mut self, message: &Message) -> Result<Value, (i32, ErrBox)> {
+    match message.method.as_str() {
+      "initialize" => {
+        let params: InitializeParams = parse_params(&message.params)?;
+        if let Some(process_id) = params.process_id {
+          // poll for the existence of the client process and terminate this process when that process no longer exists
+          start_parent_process_checker_thread(process_id);
+        }
+        Ok(json!({
+          "capabilities": {
+            "textDocumentSync": FULL_TEXT_DOCUMENT_SYNC_KIND,
+            "documentFormattingProvider": true,
+            "documentRangeFormattingProvider": true,
+          },
+          "serverInfo": {
+            "name": "dprint",
+            "version": env!("CARGO_PKG_VERSION"),
+          },
+        }))
+      }
+      "shutdown" => {
+        self.has_shutdown = true;
+        Ok(Value::Null)
+      }
+      "textDocument/didOpen" => {
+        let params: DidOpenTextDocumentParams = parse_params(&message.params)?;
+        self.documents.insert(params.text_document.uri, params.text_document.text);
+        Ok(Value::Null)
+      }
+      "textDocument/didChange" => {
+        let params: DidChangeTextDocumentParams = parse_params(&message.params)?;
+        if let Some(change) = params.content_changes.into_iter().last() {
+          self.documents.insert(params.text_document.uri, change.text);
+        }
+        Ok(Value::Null)
+      }
+      "textDocument/didClose" => {
+        let params: DidCloseTextDocumentParams = parse_params(&message.params)?;
+        self.documents.remove(&params.text_document.uri);
+        Ok(Value::Null)
+      }
+      "textDocument/formatting" => {
+        let params: DocumentFormattingParams = parse_params(&message.params)?;
+        self.format_document(message, &params.text_document.uri, None)
+      }
+      "textDocument/rangeFormatting" => {
+        let params: DocumentRangeFormattingParams = parse_params(&message.params)?;
+        self.format_document(message, &params.text_document.uri, Some(params.range))
+      }
+      method => {
+        if message.id.is_some() {
+          Err((METHOD_NOT_FOUND_ERROR_CODE, format!("Unknown method: {}", method).into()))
+        } else {
+          Ok(Value::Null) // ignore unknown notifications
+        }
+      }
+    }
+  }
+
+  /// Formats the open document returning the text edits. The format is cancelled when the
+  /// document changes or the client cancels the request while it's being formatted.
+  fn format_document(&mut self, message: &Message, uri: &str, range: Option<LspRange>) -> Result<Value, (i32, ErrBox)> {
+    *self.format_in_progress.lock() = Some(FormatInProgress {
+      request_id: message.id.clone(),
+      uri: uri.to_string(),
+      file_path: None,
+      cancellation: None,
+    });
+    let result = self.get_text_edits(uri, range);
+    let cancellation = self.format_in_progress.lock().take().unwrap().cancellation;
+    match cancellation {
+      Some((error_code, cancel_message)) => Err((error_code, cancel_message.into())),
+      None => result.map_err(|err| (REQUEST_FAILED_ERROR_CODE, err)),
+    }
+  }
+
+  fn get_text_edits(&mut self, uri: &str, range: Option<LspRange>) -> Result<Value, ErrBox> {
+    let file_text = match self.documents.get(uri) {
+      Some(file_text) => file_text.clone(),
+      None => return err!("The document is not open: {}", uri),
+    };
+    let file_path = uri_to_file_path(uri)?;
+    self.ensure_latest_config()?;
+
+    let file_matcher = FileMatcher::new(self.config.as_ref().unwrap(), self.args, self.environment)?;
+    // the file may not exist on the file system (ex. unsaved)
+    let resolved_file_path = self.environment.canonicalize(&file_path).unwrap_or(file_path);
+    if !file_matcher.matches(&resolved_file_path) {
+      log_verbose!(self.environment, "Not formatting excluded file: {}", resolved_file_path.display());
+      return Ok(json!([]));
+    }
+    if let Some(format_in_progress) = self.format_in_progress.lock().as_mut() {
+      if format_in_progress.cancellation.is_some() {
+        return Ok(Value::Null); // responds with the cancellation instead
+      }
+      format_in_progress.file_path = Some(resolved_file_path.clone());
+    }
+
+    let byte_range = range.map(|range| get_byte_index(&file_text, range.start)..get_byte_index(&file_text, range.end));
+    // hold the file's lock so a concurrent `dprint fmt` doesn't write the file while it's formatted here
+    let formatted_text = self.environment.with_file_lock(&resolved_file_path, || {
+      format_with_plugin_pools(&resolved_file_path, &file_text, byte_range.clone(), self.environment, &self.plugin_pools)
+    })??;
+    if formatted_text == file_text {
+      return Ok(json!([]));
+    }
+
+    // only replace the range since the text outside it is left as-is
+    let (edit_range, new_text) = match byte_range {
+      Some(byte_range) => {
+        let end_text_len = file_text.len() - byte_range.end;
+        let range = LspRange {
+          start: get_position(&file_text, byte_range.start),
+          end: get_position(&file_text, byte_range.end),
+        };
+        (range, &formatted_text[byte_range.start..formatted_text.len() - end_text_len])
+      }
+      None => {
+        let range = LspRange {
+          start: Position { line: 0, character: 0 },
+          end: get_position(&file_text, file_text.len()),
+        };
+        (range, &formatted_text[..])
+      }
+    };
+    Ok(json!([{ "range": edit_range, "newText": new_text }]))
+  }
+
+  fn ensure_latest_config(&mut self) -> Result<(), ErrBox> {
+    let last_config = self.config.take();
+    let config = resolve_config_from_args(self.args, self.cache, self.environment)?;
+
+    let has_config_changed = last_config.is_none() || last_config.unwrap() != config;
+    if has_config_changed {
+      self.plugin_pools.drop_plugins(); // clear the existing plugins
+      let plugins = resolve_plugins(self.args, &config, self.environment, self.plugin_resolver)?;
+      self.plugin_pools.set_plugins(plugins, config.fallback_on_error);
+      set_plugin_pools_config(&self.plugin_pools, &config)?;
+    }
+
+    self.config = Some(config);
+
+    Ok(())
+  }
+}
+
+/// Reads the messages on a separate thread so the format in progress can be cancelled while formatting.
+fn start_reader_thread<TEnvironment: Environment>(
+  mut reader: LspReader,
+  format_in_progress: Arc<Mutex<Option<FormatInProgress>>>,
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
+) -> Receiver<Result<Option<Value>, ErrBox>> {
+  let (sender, receiver) = channel();
+  std::thread::spawn(move || loop {
+    let result = reader.read_message();
+    if let Ok(Some(message)) = &result {
+      cancel_outdated_format(message, &format_in_progress, &plugin_pools);
+    }
+    let is_end = !matches!(result, Ok(Some(_)));
+    if sender.send(result).is_err() || is_end {
+      return;
+    }
+  });
+  receiver
+}
+
+/// Cancels the format in progress when the message changes its document or cancels its request.
+fn cancel_outdated_format<TEnvironment: Environment>(
+  message: &Value,
+  format_in_progress: &Mutex<Option<FormatInProgress>>,
+  plugin_pools: &PluginPools<TEnvironment>,
+) {
+  let mut format_in_progress = format_in_progress.lock();
+  let format_in_progress = match format_in_progress.as_mut() {
+    Some(format_in_progress) => format_in_progress,
+    None => return,
+  };
+  let params = &message["params"];
+  format_in_progress.cancellation = match message["method"].as_str() {
+    Some("textDocument/didChange") if params["textDocument"]["uri"] == format_in_progress.uri.as_str() => {
+      Some((CONTENT_MODIFIED_ERROR_CODE, "The document changed while it was being formatted."))
+    }
+    Some("$/cancelRequest") if format_in_progress.request_id.as_ref() == Some(&params["id"]) => {
+      Some((REQUEST_CANCELLED_ERROR_CODE, "The request was cancelled."))
+    }
+    _ => return,
+  };
+  if let Some(file_path) = &format_in_progress.file_path {
+    plugin_pools.cancel_formats(file_path);
+  }
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: &Value) -> Result<T, (i32, ErrBox)> {
+  serde_json::from_value(params.clone()).map_err(|err| (INVALID_PARAMS_ERROR_CODE, err.into()))
+}
+
+fn uri_to_file_path(uri: &str) -> Result<PathBuf, ErrBox> {
+  match url::Url::parse(uri).ok().and_then(|url| url.to_file_path().ok()) {
+    Some(file_path) => Ok(file_path),
+    None => err!("Only file URIs are supported: {}", uri),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use crate::plugins::FormatCanceller;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_cancel_outdated_format() {
+    let plugin_pools = PluginPools::new(TestEnvironment::new());
+    let file_path = PathBuf::from("/file.txt");
+    let cancel_count = Arc::new(Mutex::new(0));
+    let canceller: FormatCanceller = {
+      let cancel_count = cancel_count.clone();
+      Arc::new(move || *cancel_count.lock() += 1)
+    };
+    let _registration = plugin_pools.register_format_canceller(&file_path, canceller);
+    let format_in_progress = Mutex::new(Some(FormatInProgress {
+      request_id: Some(json!(2)),
+      uri: String::from("file:///file.txt"),
+      file_path: Some(file_path),
+      cancellation: None,
+    }));
+    let get_cancellation_code = || format_in_progress.lock().as_ref().unwrap().cancellation.map(|(code, _)| code);
+
+    let other_change = json!({ "method": "textDocument/didChange", "params": { "textDocument": { "uri": "file:///other.txt" } } });
+    cancel_outdated_format(&other_change, &format_in_progress, &plugin_pools);
+    let other_cancel = json!({ "method": "$/cancelRequest", "params": { "id": 1 } });
+    cancel_outdated_format(&other_cancel, &format_in_progress, &plugin_pools);
+    assert_eq!(get_cancellation_code(), None);
+    assert_eq!(*cancel_count.lock(), 0);
+
+    let cancel = json!({ "method": "$/cancelRequest", "params": { "id": 2 } });
+    cancel_outdated_format(&cancel, &format_in_progress, &plugin_pools);
+    assert_eq!(get_cancellation_code(), Some(REQUEST_CANCELLED_ERROR_CODE));
+    assert_eq!(*cancel_count.lock(), 1);
+
+    let change = json!({ "method": "textDocument/didChange", "params": { "textDocument": { "uri": "file:///file.txt" } } });
+    cancel_outdated_format(&change, &format_in_progress, &plugin_pools);
+    assert_eq!(get_cancellation_code(), Some(CONTENT_MODIFIED_ERROR_CODE));
+    assert_eq!(*cancel_count.lock(), 2);
+  }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+/// A zero-based line and UTF-16 code unit offset in a document.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+  pub line: u32,
+  pub character: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LspRange {
+  pub start: Position,
+  pub end: Position,
+}
+
+/// Gets the byte index of the position in the text. Positions past the end
+/// of a line resolve to the end of that line.
+pub fn get_byte_index(text: &str, position: Position) -> usize {
+  let mut line_start = 0;
+  for _ in 0..position.line {
+    match text[line_start..].find('\n') {
+      Some(index) => line_start += index + 1,
+      None => return text.len(),
+    }
+  }
+
+  let line_end = text[line_start..].find('\n').map(|index| line_start + index).unwrap_or(text.len());
+  let line_text = text[line_start..line_end].trim_end_matches('\r');
+  let mut utf16_offset = 0;
+  for (index, c) in line_text.char_indices() {
+    if utf16_offset >= position.character as usize {
+      return line_start + index;
+    }
+    utf16_offset += c.len_utf16();
+  }
+  line_start + line_text.len()
+}
+
+/// Gets the position of the byte index in the text.
+pub fn get_position(text: &str, byte_index: usize) -> Position {
+  let text_before = &text[..byte_index];
+  let line_start = text_before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+  Position {
+    line: text_before.matches('\n').count() as u32,
+    character: text_before[line_start..].encode_utf16().count() as u32,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn pos(line: u32, character: u32) -> Position {
+    Position { line, character }
+  }
+
+  #[test]
+  fn should_get_byte_index() {
+    let text = "ab\r\n😀c\nd";
+    assert_eq!(get_byte_index(text, pos(0, 0)), 0);
+    assert_eq!(get_byte_index(text, pos(0, 2)), 2);
+    // past the end of the line
+    assert_eq!(get_byte_index(text, pos(0, 10)), 2);
+    assert_eq!(get_byte_index(text, pos(1, 0)), 4);
+    // the emoji is two UTF-16 code units and four bytes
+    assert_eq!(get_byte_index(text, pos(1, 2)), 8);
+    assert_eq!(get_byte_index(text, pos(2, 1)), 11);
+    // past the last line
+    assert_eq!(get_byte_index(text, pos(5, 0)), 11);
+  }
+
+  #[test]
+  fn should_get_position() {
+    let text = "ab\r\n😀c\nd";
+    assert_eq!(get_position(text, 0), pos(0, 0));
+    assert_eq!(get_position(text, 4), pos(1, 0));
+    assert_eq!(get_position(text, 8), pos(1, 2));
+    assert_eq!(get_position(text, 9), pos(1, 3));
+    assert_eq!(get_position(text, 11), pos(2, 1));
+  }
+}
@@ -0,0 +1,92 @@
+use std::io::{BufRead, BufReader, Read, Write};
+
+use dprint_cli_core::types::ErrBox;
+use serde_json::Value;
+
+/// Reads and writes JSON-RPC messages with `Content-Length` headers as specified by the Language Server Protocol.
+pub struct LspTransport {
+  reader: LspReader,
+  writer: LspWriter,
+}
+
+impl LspTransport {
+  pub fn new(reader: Box<dyn Read + Send>, writer: Box<dyn Write + Send>) -> Self {
+    LspTransport {
+      reader: LspReader::new(reader),
+      writer: LspWriter::new(writer),
+    }
+  }
+
+  /// Splits the transport so messages can be read on another thread.
+  pub fn split(self) -> (LspReader, LspWriter) {
+    (self.reader, self.writer)
+  }
+
+  /// Reads the next message or returns `None` when the input was closed.
+  pub fn read_message(&mut self) -> Result<Option<Value>, ErrBox> {
+    self.reader.read_message()
+  }
+
+  pub fn send_message(&mut self, message: &Value) -> Result<(), ErrBox> {
+    self.writer.send_message(message)
+  }
+}
+
+pub struct LspReader {
+  reader: BufReader<Box<dyn Read + Send>>,
+}
+
+impl LspReader {
+  fn new(reader: Box<dyn Read + Send>) -> Self {
+    LspReader {
+      reader: BufReader::new(reader),
+    }
+  }
+
+  /// Reads the next message or returns `None` when the input was closed.
+  pub fn read_message(&mut self) -> Result<Option<Value>, ErrBox> {
+    let mut content_length = None;
+    loop {
+      let mut line = String::new();
+      if self.reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+      }
+      let line = line.trim_end();
+      if line.is_empty() {
+        if content_length.is_some() {
+          break;
+        }
+        continue; // ignore blank lines between messages
+      }
+      if let Some((name, value)) = line.split_once(':') {
+        if name.trim().eq_ignore_ascii_case("Content-Length") {
+          content_length = match value.trim().parse::<usize>() {
+            Ok(value) => Some(value),
+            Err(_) => return err!("Invalid Content-Length header: {}", line),
+          };
+        }
+      }
+    }
+
+    let mut content = vec![0; content_length.unwrap()];
+    self.reader.read_exact(&mut content)?;
+    Ok(Some(serde_json::from_slice(&content)?))
+  }
+}
+
+pub struct LspWriter {
+  writer: Box<dyn Write + Send>,
+}
+
+impl LspWriter {
+  fn new(writer: Box<dyn Write + Send>) -> Self {
+    LspWriter { writer }
+  }
+
+  pub fn send_message(&mut self, message: &Value) -> Result<(), ErrBox> {
+    let content = serde_json::to_string(message)?;
+    write!(self.writer, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    self.writer.flush()?;
+    Ok(())
+  }
+}
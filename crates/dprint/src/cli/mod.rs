@@ -1,16 +1,34 @@
+mod archives;
 mod arg_parser;
+mod badge;
+mod change_log;
+mod config_normalize;
+mod config_provenance;
 mod configuration;
 mod editor_service;
+mod editor_service_snapshot;
 mod format;
+mod formatter;
+mod ignore_ranges;
 pub mod incremental;
 #[cfg(target_os = "windows")]
 mod install;
+mod json_reporter;
+mod lint_width;
+mod lsp;
+mod output_trace;
 mod paths;
 mod patterns;
+mod plugin_replay;
 mod plugins;
+mod result_cache;
 mod run_cli;
+mod setup_hooks;
 mod stdin_reader;
+mod upgrade_plugins;
+mod verify_whitespace_only;
 
 pub use arg_parser::*;
+pub use formatter::Formatter;
 pub use run_cli::run_cli;
 pub use stdin_reader::*;
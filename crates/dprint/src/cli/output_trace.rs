@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use dprint_core::types::ErrBox;
+
+use crate::cache::Cache;
+use crate::environment::Environment;
+use crate::plugins::{PluginPools, PluginResolver, TakePluginResult};
+use crate::utils::ErrorCountLogger;
+
+use super::configuration::resolve_config_from_args;
+use super::plugins::{resolve_plugins_and_err_if_empty, set_plugin_pools_config};
+use super::{CliArgs, OutputTraceSubCommand};
+
+const TRACE_HTML_TEMPLATE: &str = include_str!("output_trace.html");
+
+/// Formats a single file with the printer's tracing enabled and outputs an HTML
+/// page visualizing the print nodes and writer nodes at each step.
+pub fn output_trace<TEnvironment: Environment>(
+  args: &CliArgs,
+  cmd: &OutputTraceSubCommand,
+  cache: &Cache<TEnvironment>,
+  environment: &TEnvironment,
+  plugin_resolver: &PluginResolver<TEnvironment>,
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
+) -> Result<(), ErrBox> {
+  let file_path = environment.cwd().join(&cmd.file_path);
+  let file_text = environment.read_file(&file_path)?;
+
+  let config = resolve_config_from_args(args, cache, environment)?;
+  let plugins = resolve_plugins_and_err_if_empty(args, &config, environment, plugin_resolver)?;
+  plugin_pools.set_plugins(plugins, false);
+  set_plugin_pools_config(&plugin_pools, &config)?;
+
+  let plugin_name = match plugin_pools.get_plugin_name_from_file_name(&file_path) {
+    Some(plugin_name) => plugin_name,
+    None => return err!("Could not find a plugin to format {}", file_path.display()),
+  };
+  let plugin_pool = plugin_pools.get_pool(&plugin_name).unwrap();
+  let error_logger = ErrorCountLogger::from_environment(environment);
+  let mut initialized_plugin = match plugin_pool.take_or_create_checking_config_diagnostics(&error_logger)? {
+    TakePluginResult::Success(initialized_plugin) => initialized_plugin,
+    TakePluginResult::HadDiagnostics => return err!("Had {} configuration errors.", error_logger.get_error_count()),
+  };
+  let override_config = plugin_pools.get_override_config(&file_path, &plugin_pool);
+  let result = initialized_plugin.trace_text(&file_path, &file_text, &override_config);
+  plugin_pool.release(initialized_plugin);
+  let trace_json = match result {
+    Ok(trace_json) => trace_json,
+    Err(err) => return err!("Error tracing {} with {}. {}", file_path.display(), plugin_name, err.to_string()),
+  };
+
+  let title = format!("{} - {} {}", file_path.display(), plugin_name, plugin_pool.version());
+  environment.log(&get_trace_html(&title, &trace_json)?);
+  Ok(())
+}
+
+fn get_trace_html(title: &str, trace_json: &str) -> Result<String, ErrBox> {
+  if let Err(err) = serde_json::from_str::<serde_json::Value>(trace_json) {
+    return err!("The plugin returned an invalid trace. {}", err.to_string());
+  }
+
+  Ok(
+    TRACE_HTML_TEMPLATE
+      .replace("{{title}}", &escape_html(title))
+      // prevent the data from closing the script element
+      .replace("{{traceData}}", &trace_json.replace("</", "<\\/")),
+  )
+}
+
+fn escape_html(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_get_trace_html() {
+    let html = get_trace_html(
+      "/file.ts - <plugin>",
+      r#"{"traces":[],"writerNodes":[],"printNodes":[{"printNodeId":1,"printItem":{"kind":"string","content":"</script>"}}]}"#,
+    )
+    .unwrap();
+    assert!(html.contains("<title>/file.ts - &lt;plugin&gt;</title>"));
+    assert!(html.contains(r#""content":"<\/script>""#));
+    assert!(!html.contains("{{traceData}}"));
+  }
+
+  #[test]
+  fn should_error_for_invalid_trace() {
+    let err = get_trace_html("title", "{").err().unwrap();
+    assert!(err.to_string().starts_with("The plugin returned an invalid trace."));
+  }
+}
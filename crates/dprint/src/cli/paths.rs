@@ -1,57 +1,100 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 use dprint_cli_core::types::ErrBox;
 
 use crate::environment::Environment;
-use crate::plugins::Plugin;
-use crate::utils::glob;
+use crate::plugins::PluginPools;
+use crate::utils::{get_ignore_file_names, glob, read_code_owners, read_ignore_file_patterns, GlobOutput, Phase};
 
 use super::configuration::ResolvedConfig;
 use super::patterns::get_all_file_patterns;
 use super::CliArgs;
 
-pub fn get_file_paths_by_plugin_and_err_if_empty(plugins: &Vec<Box<dyn Plugin>>, file_paths: Vec<PathBuf>) -> Result<HashMap<String, Vec<PathBuf>>, ErrBox> {
-  let file_paths_by_plugin = get_file_paths_by_plugin(plugins, file_paths);
+pub fn get_file_paths_by_plugin_and_err_if_empty<TEnvironment: Environment>(
+  plugin_pools: &PluginPools<TEnvironment>,
+  file_paths: Vec<PathBuf>,
+  languages: &[String],
+  environment: &TEnvironment,
+) -> Result<HashMap<String, Vec<PathBuf>>, ErrBox> {
+  let file_paths_by_plugin = get_file_paths_by_plugin(plugin_pools, file_paths, languages, environment)?;
   if file_paths_by_plugin.is_empty() {
     return err!("No files found to format with the specified plugins. You may want to try using `dprint output-file-paths` to see which files it's finding.");
   }
   Ok(file_paths_by_plugin)
 }
 
-pub fn get_file_paths_by_plugin(plugins: &Vec<Box<dyn Plugin>>, file_paths: Vec<PathBuf>) -> HashMap<String, Vec<PathBuf>> {
-  let mut plugin_by_file_extension: HashMap<&str, &str> = HashMap::new();
-  let mut plugin_by_file_name: HashMap<&str, &str> = HashMap::new();
-
-  for plugin in plugins.iter() {
-    for file_extension in plugin.file_extensions() {
-      plugin_by_file_extension.entry(file_extension).or_insert(plugin.name());
-    }
-    for file_name in plugin.file_names() {
-      plugin_by_file_name.entry(file_name).or_insert(plugin.name());
+/// Gets the file paths grouped by the name of the plugin of the plugin pools that formats them.
+///
+/// When languages (plugin config keys) are provided, only the file paths of those plugins are returned.
+pub fn get_file_paths_by_plugin<TEnvironment: Environment>(
+  plugin_pools: &PluginPools<TEnvironment>,
+  file_paths: Vec<PathBuf>,
+  languages: &[String],
+  environment: &TEnvironment,
+) -> Result<HashMap<String, Vec<PathBuf>>, ErrBox> {
+  let plugin_config_keys = plugin_pools.get_plugin_config_keys();
+  for language in languages.iter() {
+    if !plugin_config_keys.iter().any(|(_, config_key)| config_key == language) {
+      let mut config_keys = plugin_config_keys.iter().map(|(_, config_key)| config_key.as_str()).collect::<Vec<_>>();
+      config_keys.sort();
+      return err!(
+        "No plugin found for language '{}'. Expected the configuration key of a plugin: {}",
+        language,
+        config_keys.join(", ")
+      );
     }
   }
+  let language_plugin_names = plugin_config_keys
+    .iter()
+    .filter(|(_, config_key)| languages.iter().any(|language| config_key == language))
+    .map(|(plugin_name, _)| plugin_name.as_str())
+    .collect::<HashSet<_>>();
 
   let mut file_paths_by_plugin: HashMap<String, Vec<PathBuf>> = HashMap::new();
 
   for file_path in file_paths.into_iter() {
-    let plugin = if let Some(plugin) = crate::utils::get_lowercase_file_name(&file_path).and_then(|k| plugin_by_file_name.get(k.as_str())) {
-      plugin
-    } else if let Some(plugin) = crate::utils::get_lowercase_file_extension(&file_path).and_then(|k| plugin_by_file_extension.get(k.as_str())) {
-      plugin
-    } else {
-      continue;
+    let (plugin, reason) = match plugin_pools.get_plugin_matches(&file_path).into_iter().next() {
+      Some(plugin_match) => plugin_match,
+      None => {
+        log_verbose!(environment, "No plugin matched {}", file_path.display());
+        continue;
+      }
     };
-    let file_paths = file_paths_by_plugin.entry(plugin.to_string()).or_insert(vec![]);
+    if !languages.is_empty() && !language_plugin_names.contains(plugin.as_str()) {
+      log_verbose!(environment, "Skipped {} because {} is not for a specified language", file_path.display(), plugin);
+      continue;
+    }
+    log_verbose!(environment, "Using {} for {} ({})", plugin, file_path.display(), reason);
+    let file_paths = file_paths_by_plugin.entry(plugin).or_insert(vec![]);
     file_paths.push(file_path);
   }
 
-  file_paths_by_plugin
+  Ok(file_paths_by_plugin)
 }
 
+/// Gets the file paths to format sorted lexicographically so that the
+/// order is the same across runs and machines.
 pub fn get_and_resolve_file_paths(config: &ResolvedConfig, args: &CliArgs, environment: &impl Environment) -> Result<Vec<PathBuf>, ErrBox> {
-  let (file_patterns, absolute_paths) = get_config_file_paths(config, args, environment)?;
-  return resolve_file_paths(&file_patterns, &absolute_paths, args, config, environment);
+  Ok(get_and_resolve_glob_output(config, args, environment)?.file_paths)
+}
+
+/// Gets the sorted file paths to format along with the directories that were excluded.
+pub fn get_and_resolve_glob_output(config: &ResolvedConfig, args: &CliArgs, environment: &impl Environment) -> Result<GlobOutput, ErrBox> {
+  environment.phase_timings().measure(Phase::FileGlobbing, || {
+    let (file_patterns, absolute_paths) = get_config_file_paths(config, args, environment)?;
+    let mut glob_output = resolve_file_paths(&file_patterns, &absolute_paths, args, config, environment)?;
+    if !args.owned_by.is_empty() {
+      let code_owners = read_code_owners(&config.base_path, environment)?;
+      glob_output
+        .file_paths
+        .retain(|file_path| args.owned_by.iter().any(|owner| code_owners.is_owned_by(file_path, owner)));
+    }
+    glob_output.file_paths.sort();
+    glob_output.file_paths.dedup();
+    glob_output.excluded_dir_paths.sort();
+    Ok(glob_output)
+  })
 }
 
 fn get_config_file_paths(config: &ResolvedConfig, args: &CliArgs, environment: &impl Environment) -> Result<(Vec<String>, Vec<PathBuf>), ErrBox> {
@@ -68,22 +111,30 @@ fn resolve_file_paths(
   args: &CliArgs,
   config: &ResolvedConfig,
   environment: &impl Environment,
-) -> Result<Vec<PathBuf>, ErrBox> {
+) -> Result<GlobOutput, ErrBox> {
   let cwd = environment.cwd();
   let is_in_sub_dir = cwd != config.base_path && cwd.starts_with(&config.base_path);
+  let ignore_file_names = get_ignore_file_names(config.use_dot_ignore_files);
   if is_in_sub_dir {
-    let mut file_paths = glob(environment, &cwd, file_patterns)?;
+    // the ignore files of the directories above the cwd won't be found while globbing
+    let mut file_patterns = file_patterns.clone();
+    for dir_path in cwd.ancestors().skip(1).take_while(|dir_path| dir_path.starts_with(&config.base_path)) {
+      file_patterns.extend(read_ignore_file_patterns(dir_path, ignore_file_names, environment)?);
+    }
+    let mut glob_output = glob(environment, &cwd, &file_patterns, ignore_file_names)?;
     if args.file_patterns.is_empty() {
       // filter file paths by cwd if no CLI paths are specified
-      file_paths.extend(absolute_paths.iter().filter(|path| path.starts_with(&cwd)).map(ToOwned::to_owned));
+      glob_output
+        .file_paths
+        .extend(absolute_paths.iter().filter(|path| path.starts_with(&cwd)).map(ToOwned::to_owned));
     } else {
-      file_paths.extend(absolute_paths.iter().map(ToOwned::to_owned));
+      glob_output.file_paths.extend(absolute_paths.iter().map(ToOwned::to_owned));
     }
-    return Ok(file_paths);
+    return Ok(glob_output);
   } else {
-    let mut file_paths = glob(environment, &config.base_path, file_patterns)?;
-    file_paths.extend(absolute_paths.clone());
-    return Ok(file_paths);
+    let mut glob_output = glob(environment, &config.base_path, file_patterns, ignore_file_names)?;
+    glob_output.file_paths.extend(absolute_paths.clone());
+    return Ok(glob_output);
   }
 }
 
@@ -92,7 +143,8 @@ pub fn take_absolute_paths(file_patterns: &mut Vec<String>, environment: &impl E
   let mut file_paths = Vec::new();
   for i in (0..len).rev() {
     if is_absolute_path(&file_patterns[i], environment) {
-      file_paths.push(PathBuf::from(file_patterns.swap_remove(i))); // faster
+      // keep the order of the remaining patterns because the last matching pattern wins
+      file_paths.push(PathBuf::from(file_patterns.remove(i)));
     }
   }
   file_paths
@@ -3,11 +3,18 @@ use std::path::Path;
 use dprint_cli_core::types::ErrBox;
 
 use crate::environment::Environment;
-use crate::utils::{is_absolute_pattern, is_negated_glob, to_absolute_glob, to_absolute_globs, GlobMatcher, GlobMatcherOptions};
+use crate::utils::{
+  get_ignore_file_names, is_absolute_pattern, is_negated_glob, read_ignore_file_patterns, to_absolute_glob, to_absolute_globs, GlobMatcher, GlobMatcherOptions,
+};
 
 use super::configuration::ResolvedConfig;
 use super::CliArgs;
 
+/// Names of directories that are excluded unless `"useDefaultExcludes": false` is specified.
+const DEFAULT_EXCLUDE_DIR_NAMES: [&str; 5] = ["node_modules", "target", "dist", "build", "vendor"];
+/// Files that are excluded unless `"useDefaultExcludes": false` is specified.
+const DEFAULT_EXCLUDE_FILE_PATTERNS: [&str; 1] = ["**/*.min.*"];
+
 pub struct FileMatcher {
   glob_matcher: GlobMatcher,
 }
@@ -16,7 +23,13 @@ impl FileMatcher {
   pub fn new(config: &ResolvedConfig, args: &CliArgs, environment: &impl Environment) -> Result<Self, ErrBox> {
     let cwd = environment.cwd();
     let cwd_str = cwd.to_string_lossy();
-    let patterns = get_all_file_patterns(config, args, &cwd_str);
+    let mut patterns = get_all_file_patterns(config, args, &cwd_str);
+    // only the ignore files beside the configuration file are used here since this doesn't walk the directories
+    patterns.extend(read_ignore_file_patterns(
+      &config.base_path,
+      get_ignore_file_names(config.use_dot_ignore_files),
+      environment,
+    )?);
     let glob_matcher = GlobMatcher::new(
       &patterns,
       &GlobMatcherOptions {
@@ -71,25 +84,50 @@ fn get_exclude_file_patterns(config: &ResolvedConfig, args: &CliArgs, cwd: &str)
       to_absolute_globs(process_cli_patterns(process_file_patterns_slashes(&args.exclude_file_patterns)), cwd)
     }
     .into_iter()
-    .map(|exclude| if exclude.starts_with("!") { exclude } else { format!("!{}", exclude) }),
+    // a negated exclude re-includes the files matched by the excludes before it
+    .map(|exclude| {
+      if is_negated_glob(&exclude) {
+        exclude[1..].to_string()
+      } else {
+        format!("!{}", exclude)
+      }
+    }),
   );
 
-  if !args.allow_node_modules {
-    // glob walker will not search the children of a directory once it's ignored like this
-    let node_modules_exclude = String::from("!**/node_modules");
-    let exclude_node_module_patterns = vec![
-      to_absolute_glob(&node_modules_exclude, cwd),
-      to_absolute_glob(&node_modules_exclude, &config.base_path.to_string_lossy()),
+  // glob walker will not search the children of a directory once it's ignored like this
+  let mut default_excludes = vec!["!**/.git".to_string()];
+  for dir_name in get_default_exclude_dir_names(config, args) {
+    default_excludes.push(format!("!**/{}", dir_name));
+  }
+  if config.use_default_excludes {
+    default_excludes.extend(DEFAULT_EXCLUDE_FILE_PATTERNS.iter().map(|pattern| format!("!{}", pattern)));
+  }
+  for default_exclude in default_excludes {
+    let default_exclude_patterns = vec![
+      to_absolute_glob(&default_exclude, cwd),
+      to_absolute_glob(&default_exclude, &config.base_path.to_string_lossy()),
     ];
-    for node_modules_exclude in exclude_node_module_patterns {
-      if !file_patterns.contains(&node_modules_exclude) {
-        file_patterns.push(node_modules_exclude);
+    for default_exclude_pattern in default_exclude_patterns {
+      if !file_patterns.contains(&default_exclude_pattern) {
+        file_patterns.push(default_exclude_pattern);
       }
     }
   }
   return file_patterns;
 }
 
+/// Gets the names of the directories that are excluded by default.
+pub fn get_default_exclude_dir_names(config: &ResolvedConfig, args: &CliArgs) -> Vec<&'static str> {
+  if !config.use_default_excludes {
+    return Vec::new();
+  }
+  DEFAULT_EXCLUDE_DIR_NAMES
+    .iter()
+    .copied()
+    .filter(|dir_name| *dir_name != "node_modules" || !args.allow_node_modules)
+    .collect()
+}
+
 fn process_file_patterns_slashes(file_patterns: &Vec<String>) -> Vec<String> {
   file_patterns
     .iter()
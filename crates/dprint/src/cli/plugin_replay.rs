@@ -0,0 +1,122 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use dprint_core::types::ErrBox;
+
+use crate::cache::Cache;
+use crate::environment::{DirEntryKind, Environment};
+use crate::plugins::{
+  get_debug_io_config_file_path, get_debug_io_request_file_path, get_debug_io_response_file_path, DebugIoConfig, DebugIoRequest, DebugIoResponse,
+  PluginPools, PluginResolver, TakePluginResult,
+};
+use crate::utils::{get_difference, ErrorCountLogger};
+
+use super::configuration::resolve_config_from_args;
+use super::plugins::resolve_plugins_and_err_if_empty;
+use super::{CliArgs, PluginReplaySubCommand};
+
+const REQUEST_FILE_SUFFIX: &str = ".request.json";
+
+/// Replays the format requests written by `--debug-plugin-io` against the
+/// resolved plugin and outputs any responses that differ from the captured ones.
+pub fn replay_plugin_io<TEnvironment: Environment>(
+  args: &CliArgs,
+  cmd: &PluginReplaySubCommand,
+  cache: &Cache<TEnvironment>,
+  environment: &TEnvironment,
+  plugin_resolver: &PluginResolver<TEnvironment>,
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
+) -> Result<(), ErrBox> {
+  let dir_path = environment.cwd().join(&cmd.dir_path);
+  let config_file_path = get_debug_io_config_file_path(&dir_path);
+  let debug_io_config: DebugIoConfig = match environment.read_file(&config_file_path) {
+    Ok(text) => serde_json::from_str(&text)?,
+    Err(err) => return err!("Error reading {}. Is this a plugin directory created by --debug-plugin-io? {}", config_file_path.display(), err),
+  };
+
+  // find the plugin to replay against
+  let config = resolve_config_from_args(args, cache, environment)?;
+  let mut plugins = resolve_plugins_and_err_if_empty(args, &config, environment, plugin_resolver)?;
+  let dir_name = dir_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+  let plugin_index = match plugins.iter().position(|plugin| plugin.name() == dir_name) {
+    Some(index) => index,
+    None if plugins.len() == 1 => 0,
+    None => {
+      return err!(
+        "Could not find a plugin named '{}'. Specify the plugin to replay against with --plugins.",
+        dir_name
+      )
+    }
+  };
+  let plugin_name = plugins[plugin_index].name().to_string();
+  plugins[plugin_index].set_config(debug_io_config.plugin_config, debug_io_config.global_config);
+  plugin_pools.set_plugins(plugins, false);
+
+  let plugin_pool = plugin_pools.get_pool(&plugin_name).unwrap();
+  let error_logger = ErrorCountLogger::from_environment(environment);
+  let mut initialized_plugin = match plugin_pool.take_or_create_checking_config_diagnostics(&error_logger)? {
+    TakePluginResult::Success(initialized_plugin) => initialized_plugin,
+    TakePluginResult::HadDiagnostics => return err!("Had {} configuration errors.", error_logger.get_error_count()),
+  };
+
+  let request_indexes = get_request_indexes(&dir_path, environment)?;
+  let mut difference_count = 0;
+  for index in request_indexes.iter().copied() {
+    let request: DebugIoRequest = serde_json::from_str(&environment.read_file(&get_debug_io_request_file_path(&dir_path, index))?)?;
+    let expected_response: DebugIoResponse = serde_json::from_str(&environment.read_file(&get_debug_io_response_file_path(&dir_path, index))?)?;
+    let response = match initialized_plugin.format_text(&request.file_path, &request.file_text, &request.override_config) {
+      Ok(formatted_text) => DebugIoResponse::FormattedText(formatted_text),
+      Err(err) => DebugIoResponse::Error(err.to_string()),
+    };
+
+    if response != expected_response {
+      difference_count += 1;
+      environment.log(&format!(
+        "Response differed for request {} ({}):\n{}",
+        index,
+        request.file_path.display(),
+        get_response_difference(&expected_response, &response)
+      ));
+    }
+  }
+
+  plugin_pool.release(initialized_plugin);
+  plugin_pools.release(&plugin_name);
+
+  if difference_count > 0 {
+    err!("Found {} differing response(s) out of {} request(s).", difference_count, request_indexes.len())
+  } else {
+    environment.log(&format!("Replayed {} request(s) with no differences.", request_indexes.len()));
+    Ok(())
+  }
+}
+
+fn get_request_indexes(dir_path: &Path, environment: &impl Environment) -> Result<Vec<usize>, ErrBox> {
+  let mut indexes = Vec::new();
+  for entry in environment.dir_info(dir_path)? {
+    if let DirEntryKind::File = entry.kind {
+      let file_name = entry.path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+      if let Some(index) = file_name.strip_suffix(REQUEST_FILE_SUFFIX).and_then(|index| index.parse::<usize>().ok()) {
+        indexes.push(index);
+      }
+    }
+  }
+  indexes.sort();
+  Ok(indexes)
+}
+
+fn get_response_difference(expected: &DebugIoResponse, actual: &DebugIoResponse) -> String {
+  match (expected, actual) {
+    (DebugIoResponse::FormattedText(expected), DebugIoResponse::FormattedText(actual)) => {
+      get_difference(expected, actual).unwrap_or_else(|err| format!("Error getting difference: {}", err))
+    }
+    _ => format!("  Expected: {}\n  Actual: {}", get_response_display(expected), get_response_display(actual)),
+  }
+}
+
+fn get_response_display(response: &DebugIoResponse) -> String {
+  match response {
+    DebugIoResponse::FormattedText(_) => String::from("formatted text"),
+    DebugIoResponse::Error(message) => format!("error ({})", message),
+  }
+}
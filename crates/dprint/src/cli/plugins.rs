@@ -1,9 +1,9 @@
 use dprint_cli_core::types::ErrBox;
 
 use crate::cache::Cache;
-use crate::configuration::{get_global_config, get_plugin_config_map, GetGlobalConfigOptions};
+use crate::configuration::{apply_prettier_compat, get_global_config, get_plugin_config_map, ConfigOverrides, GetGlobalConfigOptions, PluginAssociations};
 use crate::environment::Environment;
-use crate::plugins::{Plugin, PluginResolver};
+use crate::plugins::{wrap_plugins_with_debug_io, Plugin, PluginPools, PluginResolver};
 
 use super::configuration::{resolve_config_from_args, ResolvedConfig};
 use super::CliArgs;
@@ -20,6 +20,13 @@ pub fn get_plugins_from_args<TEnvironment: Environment>(
   }
 }
 
+/// Sets the configuration the plugin pools use to select the plugin for each file and to configure it.
+pub fn set_plugin_pools_config<TEnvironment: Environment>(plugin_pools: &PluginPools<TEnvironment>, config: &ResolvedConfig) -> Result<(), ErrBox> {
+  plugin_pools.set_config_overrides(ConfigOverrides::new(&config.overrides, &config.base_path)?);
+  plugin_pools.set_plugin_associations(PluginAssociations::new(&config.associations, &config.base_path)?);
+  Ok(())
+}
+
 pub fn resolve_plugins_and_err_if_empty<TEnvironment: Environment>(
   args: &CliArgs,
   config: &ResolvedConfig,
@@ -39,10 +46,24 @@ pub fn resolve_plugins<TEnvironment: Environment>(
   environment: &TEnvironment,
   plugin_resolver: &PluginResolver<TEnvironment>,
 ) -> Result<Vec<Box<dyn Plugin>>, ErrBox> {
-  // resolve the plugins
   let plugins = plugin_resolver.resolve_plugins(config.plugins.clone())?;
+  configure_plugins(args, config, plugins, environment)
+}
+
+/// Sets the configuration of the resolved plugins based on the resolved configuration.
+pub fn configure_plugins<TEnvironment: Environment>(
+  args: &CliArgs,
+  config: &ResolvedConfig,
+  plugins: Vec<Box<dyn Plugin>>,
+  environment: &TEnvironment,
+) -> Result<Vec<Box<dyn Plugin>>, ErrBox> {
   let mut config_map = config.config_map.clone();
 
+  if config.prettier_compat {
+    let plugin_config_keys = plugins.iter().map(|plugin| plugin.config_key().to_string()).collect::<Vec<_>>();
+    apply_prettier_compat(&mut config_map, &plugin_config_keys, &config.base_path, environment)?;
+  }
+
   // resolve each plugin's configuration
   let mut plugins_with_config = Vec::new();
   for plugin in plugins.into_iter() {
@@ -69,5 +90,10 @@ pub fn resolve_plugins<TEnvironment: Environment>(
     plugins.push(plugin);
   }
 
+  if let Some(debug_plugin_io) = &args.debug_plugin_io {
+    let dir_path = environment.cwd().join(debug_plugin_io);
+    plugins = wrap_plugins_with_debug_io(plugins, &dir_path, environment);
+  }
+
   return Ok(plugins);
 }
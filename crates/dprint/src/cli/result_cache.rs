@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use dprint_cli_core::checksums::get_sha256_checksum;
+use dprint_core::configuration::{ConfigKeyMap, ConfigKeyValue};
+use dprint_core::types::ErrBox;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::environment::Environment;
+use crate::plugins::PluginPools;
+
+use super::configuration::ResolvedConfig;
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+/// Results that haven't been used in this many seconds are removed.
+const MAX_AGE_SECS: u64 = 60 * 60 * 24 * 30;
+/// The least recently used results are removed once the results take up more than this many bytes.
+const MAX_SIZE: u64 = 500 * 1024 * 1024;
+
+/// Gets the result cache when the experimental result cache is enabled in the configuration.
+pub fn get_result_cache<TEnvironment: Environment>(
+  config: &ResolvedConfig,
+  plugin_pools: &PluginPools<TEnvironment>,
+  environment: &TEnvironment,
+) -> Option<Arc<ResultCache<TEnvironment>>> {
+  if config.result_cache {
+    Some(Arc::new(ResultCache::new(
+      environment.get_cache_dir().join("results"),
+      config.result_cache_remote_url.clone(),
+      plugin_pools.get_plugins_hash(),
+      environment.clone(),
+    )))
+  } else {
+    None
+  }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ResultCacheManifest(HashMap<String, ResultCacheItem>);
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResultCacheItem {
+  /// Last used time in *seconds* since epoch.
+  last_used_time: u64,
+  size: u64,
+}
+
+/// Stores formatted output keyed by a SHA-256 hash of the file text, the file extension,
+/// the configuration overrides for the file, and the plugins (including their versions and configuration).
+///
+/// Each result is stored with a checksum of the formatted text, which is verified before it's used.
+/// When a remote url is provided, results not found locally are downloaded from
+/// `<remote url>/<key>` and stored in the local cache.
+pub struct ResultCache<TEnvironment: Environment> {
+  dir_path: PathBuf,
+  remote_url: Option<String>,
+  plugins_hash: u64,
+  max_age_secs: u64,
+  max_size: u64,
+  used_items: Mutex<HashMap<String, ResultCacheItem>>,
+  environment: TEnvironment,
+}
+
+impl<TEnvironment: Environment> ResultCache<TEnvironment> {
+  pub fn new(dir_path: PathBuf, remote_url: Option<String>, plugins_hash: u64, environment: TEnvironment) -> Self {
+    ResultCache {
+      dir_path,
+      remote_url: remote_url.map(|url| url.trim_end_matches('/').to_string()),
+      plugins_hash,
+      max_age_secs: MAX_AGE_SECS,
+      max_size: MAX_SIZE,
+      used_items: Mutex::new(HashMap::new()),
+      environment,
+    }
+  }
+
+  pub fn get(&self, plugin_name: &str, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Option<String> {
+    let key = self.get_key(plugin_name, file_path, file_text, override_config);
+    let cache_file_path = self.dir_path.join(&key);
+    if self.environment.path_exists(&cache_file_path) {
+      match self.environment.read_file(&cache_file_path) {
+        Ok(file_text) => match parse_entry(&file_text) {
+          Some(formatted_text) => {
+            self.mark_used(key, file_text.len());
+            return Some(formatted_text.to_string());
+          }
+          None => {
+            log_verbose!(self.environment, "Result cache file {} failed its integrity check.", cache_file_path.display());
+            let _ = self.environment.remove_file(&cache_file_path);
+          }
+        },
+        Err(err) => log_verbose!(
+          self.environment,
+          "Error reading result cache file {}: {}",
+          cache_file_path.display(),
+          err.to_string()
+        ),
+      }
+    }
+
+    let remote_url = self.remote_url.as_ref()?;
+    let bytes = match self.environment.download_file(&format!("{}/{}", remote_url, key)) {
+      Ok(bytes) => bytes,
+      Err(err) => {
+        log_verbose!(self.environment, "Result not found in remote cache. {}", err.to_string());
+        return None;
+      }
+    };
+    let file_text = match String::from_utf8(bytes) {
+      Ok(file_text) => file_text,
+      Err(err) => {
+        log_verbose!(self.environment, "Invalid text in remote cache for {}: {}", key, err.to_string());
+        return None;
+      }
+    };
+    match parse_entry(&file_text) {
+      Some(formatted_text) => {
+        let formatted_text = formatted_text.to_string();
+        self.write_cache_file(key, &cache_file_path, &file_text);
+        Some(formatted_text)
+      }
+      None => {
+        log_verbose!(self.environment, "Result in remote cache for {} failed its integrity check.", key);
+        None
+      }
+    }
+  }
+
+  pub fn set(&self, plugin_name: &str, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap, formatted_text: &str) {
+    let key = self.get_key(plugin_name, file_path, file_text, override_config);
+    let cache_file_path = self.dir_path.join(&key);
+    self.write_cache_file(key, &cache_file_path, &create_entry(formatted_text));
+  }
+
+  /// Records the results used in this run and removes the results that are older than the maximum age
+  /// followed by the least recently used results until the cache is within its maximum size.
+  pub fn write(&self) {
+    let used_items = std::mem::take(&mut *self.used_items.lock());
+    if used_items.is_empty() {
+      return;
+    }
+    let manifest_path = self.dir_path.join(MANIFEST_FILE_NAME);
+    // lock the manifest because other processes may be using the same cache directory
+    let result = self
+      .environment
+      .with_file_lock(&manifest_path, || -> Result<(), ErrBox> {
+        let mut items = self.read_manifest(&manifest_path).0;
+        items.extend(used_items);
+        let mut items = items.into_iter().collect::<Vec<_>>();
+        items.sort_by(|a, b| b.1.last_used_time.cmp(&a.1.last_used_time));
+
+        let now = self.environment.get_time_secs();
+        let mut total_size = 0;
+        let mut kept_items = HashMap::new();
+        for (key, item) in items {
+          if now.saturating_sub(item.last_used_time) > self.max_age_secs || total_size + item.size > self.max_size {
+            let _ = self.environment.remove_file(self.dir_path.join(&key));
+          } else {
+            total_size += item.size;
+            kept_items.insert(key, item);
+          }
+        }
+        self
+          .environment
+          .write_file(&manifest_path, &serde_json::to_string(&ResultCacheManifest(kept_items))?)
+      })
+      .and_then(|result| result);
+    if let Err(err) = result {
+      log_verbose!(
+        self.environment,
+        "Error saving result cache manifest {}: {}",
+        manifest_path.display(),
+        err.to_string()
+      );
+    }
+  }
+
+  fn read_manifest(&self, manifest_path: &Path) -> ResultCacheManifest {
+    match self.environment.read_file(manifest_path) {
+      Ok(text) => serde_json::from_str(&text).unwrap_or_else(|err| {
+        log_verbose!(self.environment, "Resetting result cache manifest. Message: {}", err);
+        ResultCacheManifest::default()
+      }),
+      Err(_) => ResultCacheManifest::default(),
+    }
+  }
+
+  fn mark_used(&self, key: String, size: usize) {
+    let item = ResultCacheItem {
+      last_used_time: self.environment.get_time_secs(),
+      size: size as u64,
+    };
+    self.used_items.lock().insert(key, item);
+  }
+
+  fn write_cache_file(&self, key: String, cache_file_path: &Path, file_text: &str) {
+    // write to a temporary file then rename it so a result is never partially written
+    let temp_file_path = {
+      let mut temp_file_path = cache_file_path.as_os_str().to_owned();
+      temp_file_path.push(format!(".{}.tmp", std::process::id()));
+      PathBuf::from(temp_file_path)
+    };
+    let result = self
+      .environment
+      .mk_dir_all(&self.dir_path)
+      .and_then(|_| self.environment.write_file(&temp_file_path, file_text))
+      .and_then(|_| self.environment.rename(&temp_file_path, cache_file_path));
+    match result {
+      Ok(()) => self.mark_used(key, file_text.len()),
+      Err(err) => {
+        log_verbose!(
+          self.environment,
+          "Error saving result cache file {}: {}",
+          cache_file_path.display(),
+          err.to_string()
+        );
+        let _ = self.environment.remove_file(&temp_file_path);
+      }
+    }
+  }
+
+  fn get_key(&self, plugin_name: &str, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> String {
+    // the plugin may format differently based on the extension (ex. .ts vs .tsx)
+    let extension = file_path.extension().map(|ext| ext.to_string_lossy().to_string()).unwrap_or_default();
+    // use a cryptographic hash because results may come from a shared remote cache
+    let mut hasher = Sha256::new();
+    hasher.update(&self.plugins_hash.to_le_bytes());
+    hasher.update(plugin_name.as_bytes());
+    hasher.update(&[0]);
+    hasher.update(extension.as_bytes());
+    hasher.update(&[0]);
+    // files in different directories may be formatted with different configuration
+    if !override_config.is_empty() {
+      let sorted_override_config: std::collections::BTreeMap<&String, &ConfigKeyValue> = override_config.iter().collect();
+      hasher.update(serde_json::to_string(&sorted_override_config).unwrap().as_bytes());
+      hasher.update(&[0]);
+    }
+    hasher.update(file_text.as_bytes());
+    format!("{:x}", hasher.finalize())
+  }
+}
+
+/// Creates the text of a cache entry, which is the checksum of the formatted text on the first line followed by the formatted text.
+fn create_entry(formatted_text: &str) -> String {
+  format!("{}\n{}", get_sha256_checksum(formatted_text.as_bytes()), formatted_text)
+}
+
+/// Gets the formatted text of a cache entry when its checksum matches.
+fn parse_entry(file_text: &str) -> Option<&str> {
+  let index = file_text.find('\n')?;
+  let (checksum, formatted_text) = (&file_text[..index], &file_text[index + 1..]);
+  if checksum == get_sha256_checksum(formatted_text.as_bytes()) {
+    Some(formatted_text)
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use pretty_assertions::assert_eq;
+  use std::collections::HashMap;
+
+  #[test]
+  fn should_store_and_get_results() {
+    let environment = TestEnvironment::new();
+    let result_cache = ResultCache::new(PathBuf::from("/cache/results"), None, 1, environment.clone());
+    let file_path = PathBuf::from("/file.txt");
+    assert_eq!(result_cache.get("plugin", &file_path, "text", &HashMap::new()), None);
+    result_cache.set("plugin", &file_path, "text", &HashMap::new(), "formatted");
+    assert_eq!(result_cache.get("plugin", &file_path, "text", &HashMap::new()), Some(String::from("formatted")));
+    // different file path with the same extension and text
+    assert_eq!(
+      result_cache.get("plugin", &PathBuf::from("/other/file.txt"), "text", &HashMap::new()),
+      Some(String::from("formatted"))
+    );
+
+    // changed text, extension, plugin, or plugins hash
+    assert_eq!(result_cache.get("plugin", &file_path, "other", &HashMap::new()), None);
+    assert_eq!(result_cache.get("plugin", &PathBuf::from("/file.md"), "text", &HashMap::new()), None);
+    assert_eq!(result_cache.get("other-plugin", &file_path, "text", &HashMap::new()), None);
+    let result_cache = ResultCache::new(PathBuf::from("/cache/results"), None, 2, environment.clone());
+    assert_eq!(result_cache.get("plugin", &file_path, "text", &HashMap::new()), None);
+  }
+
+  #[test]
+  fn should_store_results_by_override_config() {
+    let environment = TestEnvironment::new();
+    let result_cache = ResultCache::new(PathBuf::from("/cache/results"), None, 1, environment.clone());
+    let file_path = PathBuf::from("/file.txt");
+    let mut override_config = HashMap::new();
+    override_config.insert(String::from("lineWidth"), ConfigKeyValue::from_i32(120));
+    result_cache.set("plugin", &file_path, "text", &override_config, "formatted");
+    assert_eq!(result_cache.get("plugin", &file_path, "text", &HashMap::new()), None);
+    assert_eq!(
+      result_cache.get("plugin", &file_path, "text", &override_config),
+      Some(String::from("formatted"))
+    );
+  }
+
+  #[test]
+  fn should_get_results_from_remote_cache() {
+    let environment = TestEnvironment::new();
+    let file_path = PathBuf::from("/file.txt");
+    let remote_url = "https://cache.dprint.dev/results/";
+    let key = ResultCache::new(PathBuf::from("/cache/results"), None, 1, environment.clone()).get_key("plugin", &file_path, "text", &HashMap::new());
+    environment.add_remote_file_bytes(&format!("https://cache.dprint.dev/results/{}", key), create_entry("formatted").into_bytes());
+
+    let result_cache = ResultCache::new(PathBuf::from("/cache/results"), Some(remote_url.to_string()), 1, environment.clone());
+    assert_eq!(result_cache.get("plugin", &file_path, "text", &HashMap::new()), Some(String::from("formatted")));
+    assert_eq!(result_cache.get("plugin", &file_path, "other", &HashMap::new()), None);
+
+    // should have stored it locally
+    let result_cache = ResultCache::new(PathBuf::from("/cache/results"), None, 1, environment.clone());
+    assert_eq!(result_cache.get("plugin", &file_path, "text", &HashMap::new()), Some(String::from("formatted")));
+  }
+
+  #[test]
+  fn should_not_use_results_that_fail_the_integrity_check() {
+    let environment = TestEnvironment::new();
+    let file_path = PathBuf::from("/file.txt");
+    let remote_url = "https://cache.dprint.dev/results";
+    let result_cache = ResultCache::new(PathBuf::from("/cache/results"), Some(remote_url.to_string()), 1, environment.clone());
+    let key = result_cache.get_key("plugin", &file_path, "text", &HashMap::new());
+    let invalid_entry = format!("{}\nother", get_sha256_checksum("formatted".as_bytes()));
+    environment.add_remote_file_bytes(&format!("{}/{}", remote_url, key), invalid_entry.clone().into_bytes());
+    assert_eq!(result_cache.get("plugin", &file_path, "text", &HashMap::new()), None);
+    assert_eq!(environment.path_exists(&PathBuf::from("/cache/results").join(&key)), false);
+
+    environment.write_file(&PathBuf::from("/cache/results").join(&key), &invalid_entry).unwrap();
+    let result_cache = ResultCache::new(PathBuf::from("/cache/results"), None, 1, environment.clone());
+    assert_eq!(result_cache.get("plugin", &file_path, "text", &HashMap::new()), None);
+    assert_eq!(environment.path_exists(&PathBuf::from("/cache/results").join(&key)), false);
+  }
+
+  #[test]
+  fn should_remove_old_results() {
+    let environment = TestEnvironment::new();
+    let dir_path = PathBuf::from("/cache/results");
+    environment.write_file(&dir_path.join("old"), &create_entry("old")).unwrap();
+    environment
+      .write_file(&dir_path.join(MANIFEST_FILE_NAME), r#"{ "old": { "lastUsedTime": 100, "size": 68 } }"#)
+      .unwrap();
+
+    let mut result_cache = ResultCache::new(dir_path.clone(), None, 1, environment.clone());
+    result_cache.max_age_secs = 1000;
+    let file_path = PathBuf::from("/file.txt");
+    result_cache.set("plugin", &file_path, "text", &HashMap::new(), "formatted");
+    result_cache.write();
+
+    let key = result_cache.get_key("plugin", &file_path, "text", &HashMap::new());
+    assert_eq!(environment.path_exists(&dir_path.join("old")), false);
+    assert_eq!(environment.path_exists(&dir_path.join(&key)), true);
+    assert_eq!(
+      environment.read_file(&dir_path.join(MANIFEST_FILE_NAME)).unwrap(),
+      format!(r#"{{"{}":{{"lastUsedTime":123456,"size":74}}}}"#, key)
+    );
+  }
+
+  #[test]
+  fn should_remove_least_recently_used_results_when_exceeding_max_size() {
+    let environment = TestEnvironment::new();
+    let dir_path = PathBuf::from("/cache/results");
+    environment.write_file(&dir_path.join("older"), &create_entry("older")).unwrap();
+    environment.write_file(&dir_path.join("newer"), &create_entry("newer")).unwrap();
+    environment
+      .write_file(
+        &dir_path.join(MANIFEST_FILE_NAME),
+        r#"{ "older": { "lastUsedTime": 123000, "size": 70 }, "newer": { "lastUsedTime": 123400, "size": 70 } }"#,
+      )
+      .unwrap();
+
+    let mut result_cache = ResultCache::new(dir_path.clone(), None, 1, environment.clone());
+    result_cache.max_size = 150;
+    let file_path = PathBuf::from("/file.txt");
+    result_cache.set("plugin", &file_path, "text", &HashMap::new(), "formatted");
+    result_cache.write();
+
+    let key = result_cache.get_key("plugin", &file_path, "text", &HashMap::new());
+    assert_eq!(environment.path_exists(&dir_path.join("older")), false);
+    assert_eq!(environment.path_exists(&dir_path.join("newer")), true);
+    assert_eq!(environment.path_exists(&dir_path.join(&key)), true);
+  }
+}
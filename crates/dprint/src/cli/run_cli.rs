@@ -1,26 +1,50 @@
 use crate::cli::patterns::FileMatcher;
 use crate::cli::plugins::get_plugins_from_args;
-use crossterm::style::Stylize;
+use dprint_cli_core::styling::{bold, bold_red, red};
+use dprint_core::configuration::{ConfigKeyMap, GlobalConfiguration};
+use dprint_core::plugins::ChangedRegion;
 use dprint_core::types::ErrBox;
 use parking_lot::Mutex;
-use std::collections::HashMap;
+use serde::Deserialize;
+use serde_json::json;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::cache::Cache;
 use crate::configuration;
-use crate::environment::Environment;
+use crate::environment::{Environment, GitStagedFile, OutputFormat};
 use crate::plugins::{output_plugin_config_diagnostics, Plugin, PluginPools, PluginResolver};
-use crate::utils::{get_difference, get_table_text, pretty_print_json_text, ErrorCountLogger, BOM_CHAR};
-
-use super::configuration::resolve_config_from_args;
+use crate::utils::{
+  get_difference, get_file_region_writes, get_first_difference_position, get_table_text, get_text_position, get_unified_diff, pretty_print_json_text,
+  ErrorCountLogger, FileEncodings, FileRegionWrite, InvalidUtf8Policy, LineEndingCounts, MixedLineEndingsPolicy, Phase, TextEncoding, BOM_CHAR,
+};
+
+use super::archives::{check_archive_members, format_archive_members};
+use super::badge::{write_check_badge, CheckStats};
+use super::change_log::{write_change_log, ChangeLogEntry};
+use super::config_normalize::normalize_config_file;
+use super::config_provenance::{get_resolved_config_values, get_uninherited_global_values};
+use super::configuration::{apply_config_overrides, resolve_config_from_args};
 use super::editor_service::run_editor_service;
 use super::format::{format_with_plugin_pools, run_parallelized};
 use super::incremental::{get_incremental_file, IncrementalFile};
-use super::paths::{get_and_resolve_file_paths, get_file_paths_by_plugin, get_file_paths_by_plugin_and_err_if_empty};
-use super::plugins::{resolve_plugins, resolve_plugins_and_err_if_empty};
-use super::{CliArgs, SubCommand};
+use super::json_reporter::JsonReporter;
+use super::lint_width::lint_width;
+use super::lsp::run_lsp;
+use super::result_cache::{get_result_cache, ResultCache};
+use super::paths::{get_and_resolve_file_paths, get_and_resolve_glob_output, get_file_paths_by_plugin, get_file_paths_by_plugin_and_err_if_empty};
+use super::patterns::get_default_exclude_dir_names;
+use super::output_trace::output_trace;
+use super::plugin_replay::replay_plugin_io;
+use super::plugins::{resolve_plugins, resolve_plugins_and_err_if_empty, set_plugin_pools_config};
+use super::setup_hooks::setup_hooks;
+use super::upgrade_plugins::upgrade_plugins;
+use super::{CheckOutputKind, CliArgs, ConfigSubCommand, InitSubCommand, PluginSubCommand, SubCommand};
 
 pub fn run_cli<TEnvironment: Environment>(
   args: &CliArgs,
@@ -28,6 +52,43 @@ pub fn run_cli<TEnvironment: Environment>(
   cache: &Cache<TEnvironment>,
   plugin_resolver: &PluginResolver<TEnvironment>,
   plugin_pools: Arc<PluginPools<TEnvironment>>,
+) -> Result<(), ErrBox> {
+  // start profiling before the configuration is resolved so that all the phases are included
+  let profile_path = match &args.sub_command {
+    SubCommand::Fmt(cmd) => cmd.profile.as_ref().map(|profile| environment.cwd().join(profile)),
+    _ => None,
+  };
+  if profile_path.is_some() {
+    environment.phase_timings().profiler().enable();
+  }
+  let result = run_sub_command(args, environment, cache, plugin_resolver, plugin_pools);
+  if let Some(phase_timings_text) = environment.phase_timings().get_text() {
+    log_verbose!(environment, "{}", phase_timings_text);
+  }
+  // write the profile even when formatting failed since slow or failing runs are the ones worth profiling
+  if let Some(profile_path) = &profile_path {
+    let profile_result = write_profile(profile_path, environment);
+    return result.and(profile_result);
+  }
+  result
+}
+
+fn write_profile(profile_path: &Path, environment: &impl Environment) -> Result<(), ErrBox> {
+  let profile_text = environment.phase_timings().profiler().get_speedscope_text("dprint fmt")?;
+  if let Some(parent) = profile_path.parent() {
+    environment.mk_dir_all(parent)?;
+  }
+  environment.write_file(profile_path, &profile_text)?;
+  log_verbose!(environment, "Wrote profile to {}", profile_path.display());
+  Ok(())
+}
+
+fn run_sub_command<TEnvironment: Environment>(
+  args: &CliArgs,
+  environment: &TEnvironment,
+  cache: &Cache<TEnvironment>,
+  plugin_resolver: &PluginResolver<TEnvironment>,
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
 ) -> Result<(), ErrBox> {
   // todo: reduce code duplication in this function
   match &args.sub_command {
@@ -35,13 +96,23 @@ pub fn run_cli<TEnvironment: Environment>(
     SubCommand::License => output_license(&args, cache, environment, plugin_resolver),
     SubCommand::EditorInfo => output_editor_info(&args, cache, environment, plugin_resolver),
     SubCommand::EditorService(cmd) => run_editor_service(&args, cache, environment, plugin_resolver, plugin_pools, cmd),
+    SubCommand::Lsp => run_lsp(&args, cache, environment, plugin_resolver, plugin_pools),
     SubCommand::ClearCache => clear_cache(environment),
-    SubCommand::Init => init_config_file(environment, &args.config),
+    SubCommand::UpgradePlugins => upgrade_plugins(args, cache, environment),
+    SubCommand::Init(cmd) => init_config_file(environment, &args.config, cmd),
+    SubCommand::SetupHooks(cmd) => setup_hooks(environment, cmd),
     SubCommand::Version => output_version(environment),
+    SubCommand::OutputTrace(cmd) => output_trace(&args, cmd, cache, environment, plugin_resolver, plugin_pools),
+    SubCommand::Plugin(PluginSubCommand::Replay(cmd)) => replay_plugin_io(&args, cmd, cache, environment, plugin_resolver, plugin_pools),
+    SubCommand::Config(ConfigSubCommand::Normalize) => normalize_config_file(args, cache, environment),
     SubCommand::StdInFmt(cmd) => {
-      let config = resolve_config_from_args(&args, cache, environment)?;
+      let mut config = resolve_config_from_args(&args, cache, environment)?;
+      if let Some(plugin_config) = &cmd.plugin_config {
+        apply_config_overrides(&mut config, plugin_config)?;
+      }
       let plugins = resolve_plugins_and_err_if_empty(&args, &config, environment, plugin_resolver)?;
-      plugin_pools.set_plugins(plugins);
+      plugin_pools.set_plugins(plugins, config.fallback_on_error);
+      set_plugin_pools_config(&plugin_pools, &config)?;
       // if the path is absolute, then apply exclusion rules
       if environment.is_absolute_path(&cmd.file_name_or_path) {
         let file_matcher = FileMatcher::new(&config, args, environment)?;
@@ -57,48 +128,154 @@ pub fn run_cli<TEnvironment: Environment>(
           Err(err) => return err!("Error canonicalizing file {}: {}", cmd.file_name_or_path, err.to_string()),
         }
       }
-      output_stdin_format(&PathBuf::from(&cmd.file_name_or_path), &cmd.file_text, environment, plugin_pools)
+      let file_path = PathBuf::from(&cmd.file_name_or_path);
+      // format as if the file had the provided extension in order to select the plugin by it
+      let file_path = match &cmd.ext {
+        Some(ext) => file_path.with_extension(ext),
+        None => file_path,
+      };
+      output_stdin_format(&file_path, &cmd.file_text, cmd.range.clone(), environment, plugin_pools)
+    }
+    SubCommand::StdInJsonFmt(cmd) => {
+      let config = resolve_config_from_args(&args, cache, environment)?;
+      let plugins = resolve_plugins_and_err_if_empty(&args, &config, environment, plugin_resolver)?;
+      plugin_pools.set_plugins(plugins, config.fallback_on_error);
+      set_plugin_pools_config(&plugin_pools, &config)?;
+      let file_matcher = FileMatcher::new(&config, args, environment)?;
+      output_stdin_json_format(&cmd.json_text, &file_matcher, environment, plugin_pools)
+    }
+    SubCommand::CheckConfig => {
+      let config = resolve_config_from_args(args, cache, environment)?;
+      let plugins = resolve_plugins(args, &config, environment, plugin_resolver)?;
+      check_config(plugins, environment)
     }
-    SubCommand::OutputResolvedConfig => {
+    SubCommand::OutputResolvedConfig(cmd) => {
       let config = resolve_config_from_args(args, cache, environment)?;
       let plugins = resolve_plugins(args, &config, environment, plugin_resolver)?;
-      output_resolved_config(plugins, environment)
+      match &cmd.plugin {
+        Some(plugin_name) => output_plugin_resolved_config(plugins, plugin_name, environment),
+        None => output_resolved_config(plugins, environment),
+      }
     }
     SubCommand::OutputFilePaths => {
       let config = resolve_config_from_args(args, cache, environment)?;
       let plugins = resolve_plugins_and_err_if_empty(args, &config, environment, plugin_resolver)?;
-      let file_paths = get_and_resolve_file_paths(&config, args, environment)?;
-      let file_paths_by_plugin = get_file_paths_by_plugin(&plugins, file_paths);
+      plugin_pools.set_plugins(plugins, config.fallback_on_error);
+      set_plugin_pools_config(&plugin_pools, &config)?;
+      let glob_output = get_and_resolve_glob_output(&config, args, environment)?;
+      let file_paths_by_plugin = get_file_paths_by_plugin(&plugin_pools, glob_output.file_paths, &args.languages, environment)?;
       output_file_paths(file_paths_by_plugin.values().flat_map(|x| x.iter()), environment);
+      output_default_excluded_dirs(&glob_output.excluded_dir_paths, &get_default_exclude_dir_names(&config, args), environment);
       Ok(())
     }
+    SubCommand::LintWidth => {
+      let config = resolve_config_from_args(args, cache, environment)?;
+      let plugins = resolve_plugins_and_err_if_empty(args, &config, environment, plugin_resolver)?;
+      plugin_pools.set_plugins(plugins, config.fallback_on_error);
+      set_plugin_pools_config(&plugin_pools, &config)?;
+      let file_paths = get_and_resolve_file_paths(&config, args, environment)?;
+      let file_paths_by_plugin = get_file_paths_by_plugin_and_err_if_empty(&plugin_pools, file_paths, &args.languages, environment)?;
+      lint_width(file_paths_by_plugin, environment, plugin_pools)
+    }
     SubCommand::OutputFormatTimes => {
       let config = resolve_config_from_args(args, cache, environment)?;
       let plugins = resolve_plugins_and_err_if_empty(args, &config, environment, plugin_resolver)?;
+      plugin_pools.set_plugins(plugins, config.fallback_on_error);
+      set_plugin_pools_config(&plugin_pools, &config)?;
       let file_paths = get_and_resolve_file_paths(&config, args, environment)?;
-      let file_paths_by_plugin = get_file_paths_by_plugin_and_err_if_empty(&plugins, file_paths)?;
-      plugin_pools.set_plugins(plugins);
-      output_format_times(file_paths_by_plugin, environment, plugin_pools)
+      let file_paths_by_plugin = get_file_paths_by_plugin_and_err_if_empty(&plugin_pools, file_paths, &args.languages, environment)?;
+      let file_encodings = Arc::new(FileEncodings::new(&config.encodings, &config.base_path)?);
+      output_format_times(
+        file_paths_by_plugin,
+        environment,
+        plugin_pools,
+        file_encodings,
+        config.invalid_utf8,
+        config.mixed_line_endings,
+        config.max_line_length,
+      )
     }
-    SubCommand::Check => {
+    SubCommand::Check(cmd) => {
       let config = resolve_config_from_args(args, cache, environment)?;
       let plugins = resolve_plugins_and_err_if_empty(args, &config, environment, plugin_resolver)?;
+      plugin_pools.set_plugins(plugins, config.fallback_on_error);
+      set_plugin_pools_config(&plugin_pools, &config)?;
+      if !cmd.archive_member_patterns.is_empty() {
+        return check_archive_members(&cmd.archive_member_patterns, environment, &plugin_pools);
+      }
       let file_paths = get_and_resolve_file_paths(&config, args, environment)?;
-      let file_paths_by_plugin = get_file_paths_by_plugin_and_err_if_empty(&plugins, file_paths)?;
-      plugin_pools.set_plugins(plugins);
+      let file_paths_by_plugin = get_file_paths_by_plugin_and_err_if_empty(&plugin_pools, file_paths, &args.languages, environment)?;
 
       let incremental_file = get_incremental_file(args, &config, &cache, &plugin_pools, &environment);
-      check_files(file_paths_by_plugin, environment, plugin_pools, incremental_file)
+      let result_cache = get_result_cache(&config, &plugin_pools, &environment);
+      let file_encodings = Arc::new(FileEncodings::new(&config.encodings, &config.base_path)?);
+      let badge_path = cmd.badge.as_ref().map(|badge| environment.cwd().join(badge));
+      check_files(
+        file_paths_by_plugin,
+        environment,
+        plugin_pools,
+        incremental_file,
+        result_cache,
+        file_encodings,
+        config.invalid_utf8,
+        config.mixed_line_endings,
+        config.max_line_length,
+        badge_path,
+        cmd.output_kind,
+        cmd.verify_whitespace_only,
+      )?;
+      err_if_warnings_as_errors(args.warnings_as_errors || config.warnings_as_errors, environment)
     }
-    SubCommand::Fmt => {
+    SubCommand::Fmt(cmd) => {
       let config = resolve_config_from_args(args, cache, environment)?;
       let plugins = resolve_plugins_and_err_if_empty(args, &config, environment, plugin_resolver)?;
-      let file_paths = get_and_resolve_file_paths(&config, args, environment)?;
-      let file_paths_by_plugin = get_file_paths_by_plugin_and_err_if_empty(&plugins, file_paths)?;
-      plugin_pools.set_plugins(plugins);
+      plugin_pools.set_plugins(plugins, config.fallback_on_error);
+      set_plugin_pools_config(&plugin_pools, &config)?;
+      if !cmd.archive_member_patterns.is_empty() {
+        return format_archive_members(&cmd.archive_member_patterns, environment, &plugin_pools);
+      }
+      let mut file_paths = get_and_resolve_file_paths(&config, args, environment)?;
+      let staged_files = if cmd.staged {
+        let staged_files = environment.get_git_staged_files()?;
+        let staged_file_paths = staged_files.iter().map(|staged_file| &staged_file.path).collect::<HashSet<_>>();
+        file_paths.retain(|file_path| staged_file_paths.contains(file_path));
+        Some(staged_files)
+      } else {
+        None
+      };
+      let file_paths_by_plugin = if cmd.staged {
+        let file_paths_by_plugin = get_file_paths_by_plugin(&plugin_pools, file_paths, &args.languages, environment)?;
+        if file_paths_by_plugin.is_empty() {
+          // committing only files that aren't formatted shouldn't fail a pre-commit hook
+          log_verbose!(environment, "No staged files to format.");
+          return Ok(());
+        }
+        file_paths_by_plugin
+      } else {
+        get_file_paths_by_plugin_and_err_if_empty(&plugin_pools, file_paths, &args.languages, environment)?
+      };
 
       let incremental_file = get_incremental_file(args, &config, &cache, &plugin_pools, &environment);
-      format_files(file_paths_by_plugin, environment, plugin_pools, incremental_file)
+      let result_cache = get_result_cache(&config, &plugin_pools, &environment);
+      let file_encodings = Arc::new(FileEncodings::new(&config.encodings, &config.base_path)?);
+      let change_log_path = cmd.change_log.as_ref().map(|change_log| environment.cwd().join(change_log));
+      let formatted_file_paths = format_files(
+        file_paths_by_plugin,
+        environment,
+        plugin_pools,
+        incremental_file,
+        result_cache,
+        file_encodings,
+        config.invalid_utf8,
+        config.mixed_line_endings,
+        config.max_line_length,
+        change_log_path,
+        cmd.verify_whitespace_only,
+      )?;
+      if let Some(staged_files) = &staged_files {
+        stage_formatted_files(staged_files, &formatted_file_paths, environment)?;
+      }
+      err_if_warnings_as_errors(args.warnings_as_errors || config.warnings_as_errors, environment)
     }
     #[cfg(target_os = "windows")]
     SubCommand::Hidden(hidden_command) => match hidden_command {
@@ -230,11 +407,34 @@ fn clear_cache(environment: &impl Environment) -> Result<(), ErrBox> {
 }
 
 fn output_file_paths<'a>(file_paths: impl Iterator<Item = &'a PathBuf>, environment: &impl Environment) {
+  // sort so the output is the same across runs and machines
+  let mut file_paths = file_paths.collect::<Vec<_>>();
+  file_paths.sort();
   for file_path in file_paths {
     environment.log(&file_path.display().to_string())
   }
 }
 
+/// Outputs the directories that were pruned by the default excludes to stderr so the file paths can still be piped.
+fn output_default_excluded_dirs(excluded_dir_paths: &[PathBuf], default_exclude_dir_names: &[&str], environment: &impl Environment) {
+  let default_excluded_dirs = excluded_dir_paths
+    .iter()
+    .filter_map(|dir_path| {
+      let dir_name = dir_path.file_name()?.to_string_lossy();
+      let default_dir_name = default_exclude_dir_names.iter().find(|name| dir_name.eq_ignore_ascii_case(name))?;
+      Some((dir_path, default_dir_name))
+    })
+    .collect::<Vec<_>>();
+  if default_excluded_dirs.is_empty() {
+    return;
+  }
+
+  environment.log_error("Directories excluded by default (specify \"useDefaultExcludes\": false in the configuration file to include them):");
+  for (dir_path, default_dir_name) in default_excluded_dirs {
+    environment.log_error(&format!("  {} (**/{})", dir_path.display(), default_dir_name));
+  }
+}
+
 fn output_resolved_config(plugins: Vec<Box<dyn Plugin>>, environment: &impl Environment) -> Result<(), ErrBox> {
   let mut plugin_jsons = Vec::new();
   for plugin in plugins {
@@ -259,15 +459,109 @@ fn output_resolved_config(plugins: Vec<Box<dyn Plugin>>, environment: &impl Envi
   Ok(())
 }
 
-fn init_config_file(environment: &impl Environment, config_arg: &Option<String>) -> Result<(), ErrBox> {
+/// Outputs the resolved configuration of a single plugin along with where each value came from.
+fn output_plugin_resolved_config(plugins: Vec<Box<dyn Plugin>>, plugin_name: &str, environment: &impl Environment) -> Result<(), ErrBox> {
+  let plugin = match plugins.iter().find(|plugin| plugin.name() == plugin_name || plugin.config_key() == plugin_name) {
+    Some(plugin) => plugin,
+    None => {
+      let mut config_keys = plugins.iter().map(|plugin| plugin.config_key()).collect::<Vec<_>>();
+      config_keys.sort();
+      return err!(
+        "No plugin found for '{}'. Expected the name or configuration key of a plugin: {}",
+        plugin_name,
+        config_keys.join(", ")
+      );
+    }
+  };
+
+  let initialized_plugin = plugin.initialize()?;
+  output_plugin_config_diagnostics(plugin.name(), &initialized_plugin, &ErrorCountLogger::from_environment(environment))?;
+
+  let resolved_config_text = initialized_plugin.get_resolved_config()?;
+  let (plugin_config, global_config) = plugin.get_config();
+  output_uninherited_global_values(plugin.name(), &resolved_config_text, plugin_config, global_config, environment)?;
+  let mut config_values = serde_json::Map::new();
+  for config_value in get_resolved_config_values(&resolved_config_text, plugin_config, global_config)? {
+    config_values.insert(
+      config_value.key,
+      json!({
+        "value": config_value.value,
+        "source": config_value.source.as_str(),
+      }),
+    );
+  }
+  let mut output = serde_json::Map::new();
+  output.insert(plugin.config_key().to_string(), serde_json::Value::Object(config_values));
+  environment.log(&serde_json::to_string_pretty(&output)?);
+
+  Ok(())
+}
+
+/// Warns about the global configuration properties a plugin didn't inherit, which
+/// usually means the plugin doesn't support the global configuration.
+fn output_uninherited_global_values(
+  plugin_name: &str,
+  resolved_config_text: &str,
+  plugin_config: &ConfigKeyMap,
+  global_config: &GlobalConfiguration,
+  environment: &impl Environment,
+) -> Result<(), ErrBox> {
+  for value in get_uninherited_global_values(resolved_config_text, plugin_config, global_config)? {
+    environment.log_warning(&format!(
+      "Warning: [{}]: Did not inherit the global '{}' value of {}, so it is {}. Specify it in the plugin's configuration to override this.",
+      plugin_name, value.key, value.global_value, value.resolved_value
+    ));
+  }
+  Ok(())
+}
+
+/// Initializes each plugin with the resolved configuration and outputs the
+/// configuration diagnostics of all the plugins without formatting any files.
+fn check_config(plugins: Vec<Box<dyn Plugin>>, environment: &impl Environment) -> Result<(), ErrBox> {
+  let error_logger = ErrorCountLogger::from_environment(environment);
+  for plugin in plugins {
+    let initialized_plugin = plugin.initialize()?;
+    // keep going in order to output the diagnostics of every plugin
+    if let Err(err) = output_plugin_config_diagnostics(plugin.name(), &initialized_plugin, &error_logger) {
+      environment.log_error(&err.to_string());
+    } else {
+      let (plugin_config, global_config) = plugin.get_config();
+      let resolved_config_text = initialized_plugin.get_resolved_config()?;
+      output_uninherited_global_values(plugin.name(), &resolved_config_text, plugin_config, global_config, environment)?;
+    }
+  }
+
+  let diagnostic_count = error_logger.get_error_count();
+  if diagnostic_count > 0 {
+    err!("Had {} configuration diagnostic(s).", diagnostic_count)
+  } else {
+    Ok(())
+  }
+}
+
+fn init_config_file(environment: &impl Environment, config_arg: &Option<String>, cmd: &InitSubCommand) -> Result<(), ErrBox> {
+  if environment.is_ci() {
+    return err!("{}", environment.messages().get("init.inCi", &[]));
+  }
+
   let config_file_path = get_config_path(config_arg)?;
   return if !environment.path_exists(&config_file_path) {
-    environment.write_file(&config_file_path, &configuration::get_init_config_file_text(environment)?)?;
-    environment.log(&format!("\nCreated {}", config_file_path.display()));
-    environment.log("\nIf you are working in a commercial environment please consider sponsoring dprint: https://dprint.dev/sponsor");
+    let init_config_file = match &cmd.template {
+      Some(template) => configuration::get_init_config_file_from_template(template, environment)?,
+      None => configuration::get_init_config_file(environment)?,
+    };
+    environment.write_file(&config_file_path, &init_config_file.text)?;
+    environment.log(&environment.messages().get("init.created", &[("path", &config_file_path.display())]));
+    if cmd.vscode {
+      let dir_path = config_file_path.parent().map(|p| p.to_path_buf()).unwrap_or_else(|| PathBuf::from("."));
+      for file_path in configuration::write_init_vscode_settings(environment, &dir_path, &init_config_file.selected_plugins)? {
+        environment.log(&environment.messages().get("init.updated", &[("path", &file_path.display())]));
+      }
+    }
+    environment.log(&environment.messages().get("init.sponsor", &[]));
     Ok(())
   } else {
-    err!("Configuration file '{}' already exists.", config_file_path.display())
+    err!("{}", environment.messages().get("init.alreadyExists", &[("path", &config_file_path.display())]))
   };
 
   fn get_config_path(config_arg: &Option<String>) -> Result<PathBuf, ErrBox> {
@@ -282,111 +576,513 @@ fn init_config_file(environment: &impl Environment, config_arg: &Option<String>)
 fn output_stdin_format<TEnvironment: Environment>(
   file_name: &Path,
   file_text: &str,
+  range: Option<Range<usize>>,
   environment: &TEnvironment,
   plugin_pools: Arc<PluginPools<TEnvironment>>,
 ) -> Result<(), ErrBox> {
-  let formatted_text = format_with_plugin_pools(file_name, file_text, environment, &plugin_pools)?;
+  let formatted_text = format_with_plugin_pools(file_name, file_text, range, environment, &plugin_pools)?;
   environment.log_silent(&formatted_text);
   Ok(())
 }
 
+#[derive(Deserialize)]
+struct StdInJsonFile {
+  path: String,
+  text: String,
+}
+
+/// Formats the files provided as JSON via stdin and outputs the results as a JSON array
+/// in the same order with either the formatted text or an error message for each file.
+fn output_stdin_json_format<TEnvironment: Environment>(
+  json_text: &str,
+  file_matcher: &FileMatcher,
+  environment: &TEnvironment,
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
+) -> Result<(), ErrBox> {
+  let files: Vec<StdInJsonFile> = match serde_json::from_str(json_text) {
+    Ok(files) => files,
+    Err(err) => {
+      return err!(
+        "Error parsing the JSON provided to stdin. Expected an array of objects with \"path\" and \"text\" properties. {}",
+        err.to_string()
+      )
+    }
+  };
+
+  let mut error_count = 0;
+  let mut results = Vec::with_capacity(files.len());
+  for file in files.iter() {
+    results.push(match format_stdin_json_file(file, file_matcher, environment, &plugin_pools) {
+      Ok(formatted_text) => json!({ "path": file.path, "text": formatted_text }),
+      Err(err) => {
+        error_count += 1;
+        json!({ "path": file.path, "error": err.to_string() })
+      }
+    });
+  }
+  environment.log_silent(&serde_json::to_string(&results).unwrap());
+
+  if error_count > 0 {
+    err!("Error formatting {} of the files provided to stdin.", error_count)
+  } else {
+    Ok(())
+  }
+}
+
+fn format_stdin_json_file<TEnvironment: Environment>(
+  file: &StdInJsonFile,
+  file_matcher: &FileMatcher,
+  environment: &TEnvironment,
+  plugin_pools: &Arc<PluginPools<TEnvironment>>,
+) -> Result<String, ErrBox> {
+  // like with --stdin, only apply the exclusion rules when the path is absolute
+  if environment.is_absolute_path(&file.path) {
+    match environment.canonicalize(&file.path) {
+      Ok(resolved_file_path) => {
+        if !file_matcher.matches(&resolved_file_path) {
+          return Ok(file.text.clone());
+        }
+      }
+      Err(err) => return err!("Error canonicalizing file {}: {}", file.path, err.to_string()),
+    }
+  }
+  Ok(format_with_plugin_pools(Path::new(&file.path), &file.text, None, environment, plugin_pools)?.into_owned())
+}
+
 fn check_files<TEnvironment: Environment>(
   file_paths_by_plugin: HashMap<String, Vec<PathBuf>>,
   environment: &TEnvironment,
   plugin_pools: Arc<PluginPools<TEnvironment>>,
   incremental_file: Option<Arc<IncrementalFile<TEnvironment>>>,
+  result_cache: Option<Arc<ResultCache<TEnvironment>>>,
+  file_encodings: Arc<FileEncodings>,
+  invalid_utf8: InvalidUtf8Policy,
+  mixed_line_endings: MixedLineEndingsPolicy,
+  max_line_length: Option<usize>,
+  badge_path: Option<PathBuf>,
+  output_kind: CheckOutputKind,
+  verify_whitespace_only: bool,
 ) -> Result<(), ErrBox> {
+  let checked_files_count = Arc::new(AtomicUsize::new(0));
   let not_formatted_files_count = Arc::new(AtomicUsize::new(0));
-
-  run_parallelized(file_paths_by_plugin, environment, plugin_pools, incremental_file, {
-    let not_formatted_files_count = not_formatted_files_count.clone();
-    move |file_path, file_text, formatted_text, _, _, environment| {
-      if formatted_text != file_text {
-        not_formatted_files_count.fetch_add(1, Ordering::SeqCst);
-        match get_difference(&file_text, &formatted_text) {
-          Ok(difference_text) => {
-            environment.log(&format!(
-              "{} {}:\n{}\n--",
-              "from".bold().red().to_string(),
+  let file_outputs = Arc::new(Mutex::new(Vec::new()));
+  let json_reporter = JsonReporter::new(environment);
+
+  let result = run_parallelized(
+    file_paths_by_plugin,
+    environment,
+    plugin_pools,
+    incremental_file.clone(),
+    result_cache.clone(),
+    file_encodings.clone(),
+    invalid_utf8,
+    mixed_line_endings,
+    max_line_length,
+    verify_whitespace_only,
+    {
+      let checked_files_count = checked_files_count.clone();
+      let not_formatted_files_count = not_formatted_files_count.clone();
+      let file_outputs = file_outputs.clone();
+      move |file_path, file_text, formatted_text, changed_regions, _, start_instant, environment| {
+        let duration = start_instant.elapsed();
+        checked_files_count.fetch_add(1, Ordering::SeqCst);
+        let line_ending_counts = LineEndingCounts::new(file_text);
+        let is_json_output = environment.output_format() == OutputFormat::Json;
+        let add_output = |output: CheckFileOutput| file_outputs.lock().push((file_path.to_path_buf(), output));
+        if line_ending_counts.is_mixed() && mixed_line_endings != MixedLineEndingsPolicy::Ignore {
+          // report these separately because the difference would be hard to see
+          not_formatted_files_count.fetch_add(1, Ordering::SeqCst);
+          if is_json_output {
+            add_output(CheckFileOutput::Json {
+              kind: "mixedLineEndings",
+              duration,
+              properties: json!({
+                "crlf": line_ending_counts.crlf,
+                "lf": line_ending_counts.lf,
+              }),
+            });
+          } else if output_kind == CheckOutputKind::ListDifferent {
+            add_output(CheckFileOutput::Text(file_path.display().to_string()));
+          } else if output_kind != CheckOutputKind::Summary {
+            add_output(CheckFileOutput::Text(format!(
+              "{} {}: Found {} CRLF and {} LF line endings.\n--",
+              bold_red("mixed line endings"),
               file_path.display(),
-              difference_text,
-            ));
+              line_ending_counts.crlf,
+              line_ending_counts.lf,
+            )));
           }
-          Err(err) => {
-            environment.log(&format!(
-              "{} {}:\nError getting difference, but this file needs formatting.\n\nError message: {}\n--",
-              "from".bold().red().to_string(),
-              file_path.display(),
-              err.to_string().red().to_string(),
-            ));
+        } else if formatted_text != file_text {
+          not_formatted_files_count.fetch_add(1, Ordering::SeqCst);
+          let first_difference_position = get_first_difference_position(&file_text, &formatted_text);
+          if is_json_output {
+            let mut properties = json!({});
+            if let Some(position) = first_difference_position {
+              properties["line"] = json!(position.line_number);
+              properties["column"] = json!(position.column_number);
+            }
+            if output_kind == CheckOutputKind::UnifiedDiff {
+              properties["diff"] = json!(get_unified_diff(&file_path.display().to_string(), &file_text, &formatted_text));
+            }
+            if let Some(changed_regions) = &changed_regions {
+              properties["changes"] = get_changed_region_positions_json(file_text, changed_regions);
+            }
+            add_output(CheckFileOutput::Json {
+              kind: "notFormatted",
+              duration,
+              properties,
+            });
+          } else if output_kind == CheckOutputKind::UnifiedDiff {
+            let diff_text = get_unified_diff(&file_path.display().to_string(), &file_text, &formatted_text);
+            add_output(CheckFileOutput::Text(diff_text));
+          } else if output_kind == CheckOutputKind::ListDifferent {
+            add_output(CheckFileOutput::Text(file_path.display().to_string()));
+          } else if output_kind == CheckOutputKind::Summary {
+            // only the counts are output once all the files are checked
+          } else {
+            // include the position of the first difference so editors and terminals can link to it
+            let file_location = match first_difference_position {
+              Some(position) => format!("{}:{}:{}", file_path.display(), position.line_number, position.column_number),
+              None => file_path.display().to_string(),
+            };
+            add_output(CheckFileOutput::Text(match get_difference(&file_text, &formatted_text) {
+              Ok(difference_text) => format!("{} {}:\n{}\n--", bold_red("from"), file_location, difference_text,),
+              Err(err) => format!(
+                "{} {}:\nError getting difference, but this file needs formatting.\n\nError message: {}\n--",
+                bold_red("from"),
+                file_location,
+                red(err),
+              ),
+            }));
           }
         }
+        Ok(())
       }
-      Ok(())
+    },
+  );
+
+  // log once all the files are checked so the output is in the same order across runs
+  let mut file_outputs = std::mem::take(&mut *file_outputs.lock());
+  file_outputs.sort_by(|a, b| a.0.cmp(&b.0));
+  for (file_path, output) in file_outputs {
+    match output {
+      CheckFileOutput::Text(text) => environment.log(&text),
+      CheckFileOutput::Json { kind, duration, properties } => json_reporter.log_file(kind, &file_path, duration, properties),
     }
-  })?;
+  }
+  // store the files that were formatted so they're skipped the next time, which
+  // includes the progress made before being interrupted
+  if let Some(incremental_file) = &incremental_file {
+    if result.is_ok() || environment.is_interrupted() {
+      incremental_file.write();
+    }
+  }
+  if let Some(result_cache) = &result_cache {
+    result_cache.write();
+  }
+  result?;
 
+  let checked_files_count = checked_files_count.load(Ordering::SeqCst);
   let not_formatted_files_count = not_formatted_files_count.load(Ordering::SeqCst);
+  if environment.output_format() == OutputFormat::Json {
+    json_reporter.log_summary(
+      "checkSummary",
+      json!({
+        "checkedFiles": checked_files_count,
+        "notFormattedFiles": not_formatted_files_count,
+      }),
+    );
+  } else if output_kind == CheckOutputKind::Summary {
+    let summary_text = environment
+      .messages()
+      .get_plural("check.summary", checked_files_count, &checked_files_count, &[]);
+    environment.log(&summary_text);
+  }
+  if let Some(badge_path) = badge_path {
+    let stats = CheckStats::new(checked_files_count, not_formatted_files_count);
+    write_check_badge(&badge_path, &stats, environment)?;
+  }
   if not_formatted_files_count == 0 {
     Ok(())
   } else {
-    let f = if not_formatted_files_count == 1 { "file" } else { "files" };
-    err!("Found {} not formatted {}.", not_formatted_files_count.to_string().bold().to_string(), f)
+    let count_text = bold(not_formatted_files_count);
+    err!("{}", environment.messages().get_plural("check.notFormatted", not_formatted_files_count, &count_text, &[]))
   }
 }
 
+/// The output of checking a file.
+enum CheckFileOutput {
+  Text(String),
+  Json {
+    kind: &'static str,
+    duration: Duration,
+    properties: serde_json::Value,
+  },
+}
+
+/// Writes only the changed regions of the file, which is faster for large files (ex. on network file systems).
+fn write_changed_regions<TEnvironment: Environment>(
+  environment: &TEnvironment,
+  file_path: &Path,
+  new_text: &str,
+  changed_regions: &[ChangedRegion],
+  had_bom: bool,
+) -> Result<(), ErrBox> {
+  // the regions are relative to the text without the BOM
+  let bom_len = if had_bom { BOM_CHAR.len_utf8() } else { 0 };
+  let writes = get_file_region_writes(&new_text[bom_len..], changed_regions)
+    .into_iter()
+    .map(|write| FileRegionWrite {
+      offset: write.offset + bom_len,
+      text: write.text,
+    })
+    .collect::<Vec<_>>();
+  environment.write_file_regions(file_path, &writes, new_text.len())
+}
+
+/// Gets the line and column of each changed region in the file text for the JSON output.
+fn get_changed_region_positions_json(file_text: &str, changed_regions: &[ChangedRegion]) -> serde_json::Value {
+  changed_regions
+    .iter()
+    .map(|region| {
+      let position = get_text_position(file_text, region.start);
+      json!({
+        "line": position.line_number,
+        "column": position.column_number,
+      })
+    })
+    .collect()
+}
+
 fn format_files<TEnvironment: Environment>(
   file_paths_by_plugin: HashMap<String, Vec<PathBuf>>,
   environment: &TEnvironment,
   plugin_pools: Arc<PluginPools<TEnvironment>>,
   incremental_file: Option<Arc<IncrementalFile<TEnvironment>>>,
-) -> Result<(), ErrBox> {
-  let formatted_files_count = Arc::new(AtomicUsize::new(0));
-  let files_count: usize = file_paths_by_plugin.values().map(|x| x.len()).sum();
-
-  run_parallelized(file_paths_by_plugin, environment, plugin_pools, incremental_file.clone(), {
-    let formatted_files_count = formatted_files_count.clone();
-    move |file_path, file_text, formatted_text, had_bom, _, environment| {
-      if formatted_text != file_text {
-        let new_text = if had_bom {
-          // add back the BOM
-          format!("{}{}", BOM_CHAR, formatted_text)
-        } else {
-          formatted_text
-        };
-
-        formatted_files_count.fetch_add(1, Ordering::SeqCst);
-        environment.write_file(&file_path, &new_text)?;
+  result_cache: Option<Arc<ResultCache<TEnvironment>>>,
+  file_encodings: Arc<FileEncodings>,
+  invalid_utf8: InvalidUtf8Policy,
+  mixed_line_endings: MixedLineEndingsPolicy,
+  max_line_length: Option<usize>,
+  change_log_path: Option<PathBuf>,
+  verify_whitespace_only: bool,
+) -> Result<Vec<PathBuf>, ErrBox> {
+  let formatted_file_paths = Arc::new(Mutex::new(Vec::new()));
+  let modified_files_count = Arc::new(AtomicUsize::new(0));
+  let change_log_entries = Arc::new(Mutex::new(Vec::new()));
+  let json_reporter = JsonReporter::new(environment);
+  let plugin_names_by_file_path = Arc::new(if change_log_path.is_some() {
+    get_plugin_names_by_file_path(&file_paths_by_plugin)
+  } else {
+    HashMap::new()
+  });
+
+  let result = run_parallelized(
+    file_paths_by_plugin,
+    environment,
+    plugin_pools,
+    incremental_file.clone(),
+    result_cache.clone(),
+    file_encodings.clone(),
+    invalid_utf8,
+    mixed_line_endings,
+    max_line_length,
+    verify_whitespace_only,
+    {
+      let formatted_file_paths = formatted_file_paths.clone();
+      let modified_files_count = modified_files_count.clone();
+      let change_log_entries = change_log_entries.clone();
+      let is_change_log_enabled = change_log_path.is_some();
+      let json_reporter = json_reporter.clone();
+      move |file_path, file_text, formatted_text, changed_regions, had_bom, start_instant, environment| {
+        if formatted_text != file_text {
+          let duration = start_instant.elapsed();
+          let new_text = if had_bom {
+            // add back the BOM
+            format!("{}{}", BOM_CHAR, formatted_text)
+          } else {
+            formatted_text
+          };
+
+          // lock the file so writes from other dprint processes are serialized, then re-read
+          // it under the lock to not clobber any changes made while formatting (ex. by the user's editor)
+          let encoding = file_encodings.get_encoding(file_path);
+          let was_written = environment.with_file_lock(&file_path, || -> Result<bool, ErrBox> {
+            if was_file_modified(environment, file_path, file_text, had_bom, encoding) {
+              return Ok(false);
+            }
+            environment.phase_timings().measure(Phase::Writing, || match &changed_regions {
+              // the regions are byte offsets in the UTF-8 text, so they can't be used for other encodings
+              Some(changed_regions) if encoding == TextEncoding::Utf8 => write_changed_regions(environment, file_path, &new_text, changed_regions, had_bom),
+              _ if encoding == TextEncoding::Utf8 => environment.write_file(&file_path, &new_text),
+              _ => match encoding.encode(&new_text) {
+                Ok(bytes) => environment.write_file_bytes(&file_path, &bytes),
+                Err(err) => err!("Error encoding {} as {}. {}", file_path.display(), encoding.name(), err),
+              },
+            })?;
+            Ok(true)
+          })??;
+
+          let is_json_output = environment.output_format() == OutputFormat::Json;
+          if was_written {
+            formatted_file_paths.lock().push(file_path.to_path_buf());
+            if is_change_log_enabled {
+              let old_text = if had_bom {
+                format!("{}{}", BOM_CHAR, file_text)
+              } else {
+                file_text.to_string()
+              };
+              let plugin_name = plugin_names_by_file_path.get(file_path).cloned().unwrap_or_default();
+              change_log_entries
+                .lock()
+                .push(ChangeLogEntry::new(file_path, plugin_name, &old_text, &new_text, duration));
+            }
+            if is_json_output {
+              let mut properties = json!({});
+              if let Some(changed_regions) = &changed_regions {
+                properties["changes"] = get_changed_region_positions_json(file_text, changed_regions);
+              }
+              json_reporter.log_file("formatted", file_path, duration, properties);
+            }
+          } else {
+            modified_files_count.fetch_add(1, Ordering::SeqCst);
+            if is_json_output {
+              json_reporter.log_file("modifiedWhileFormatting", file_path, duration, json!({}));
+            } else {
+              environment.log_warning(&format!(
+                "Warning: Skipped writing {} because it was modified while formatting.",
+                file_path.display()
+              ));
+            }
+          }
+        }
+
+        Ok(())
       }
+    },
+  );
+
+  let formatted_file_paths = std::mem::take(&mut *formatted_file_paths.lock());
+  let formatted_files_count = formatted_file_paths.len();
+  let count_text = bold(formatted_files_count);
+  if environment.output_format() == OutputFormat::Json {
+    json_reporter.log_summary("fmtSummary", json!({ "formattedFiles": formatted_files_count }));
+  } else if formatted_files_count > 0 && (result.is_ok() || environment.is_interrupted()) {
+    // when interrupted, still report the files that were formatted
+    environment.log(&environment.messages().get_plural("fmt.formatted", formatted_files_count, &count_text, &[]));
+  }
+  // write the change log even on failure so it records the files that were changed
+  if let Some(change_log_path) = &change_log_path {
+    let change_log_entries = std::mem::take(&mut *change_log_entries.lock());
+    write_change_log(change_log_path, change_log_entries, environment)?;
+  }
+  // keep the progress made before being interrupted
+  if let Some(incremental_file) = &incremental_file {
+    if result.is_ok() || environment.is_interrupted() {
+      incremental_file.write();
+    }
+  }
+  if let Some(result_cache) = &result_cache {
+    result_cache.write();
+  }
+  result?;
+
+  let modified_files_count = modified_files_count.load(Ordering::SeqCst);
+  if modified_files_count > 0 {
+    return err!(
+      "{}",
+      environment
+        .messages()
+        .get_plural("fmt.modifiedWhileFormatting", modified_files_count, &bold(modified_files_count), &[])
+    );
+  }
 
-      Ok(())
+  // files should already be formatted in CI, but jobs that format and commit the changes are common
+  if environment.is_ci() && formatted_files_count > 0 {
+    environment.log_warning(&environment.messages().get_plural("fmt.formattedInCi", formatted_files_count, &count_text, &[]));
+  }
+
+  Ok(formatted_file_paths)
+}
+
+/// Stages the formatting changes of the staged files. Files that also have unstaged changes
+/// are skipped because staging them would stage those changes as well.
+fn stage_formatted_files(staged_files: &[GitStagedFile], formatted_file_paths: &[PathBuf], environment: &impl Environment) -> Result<(), ErrBox> {
+  let formatted_file_paths = formatted_file_paths.iter().collect::<HashSet<_>>();
+  let mut file_paths_to_stage = Vec::new();
+  for staged_file in staged_files.iter().filter(|staged_file| formatted_file_paths.contains(&staged_file.path)) {
+    if staged_file.has_unstaged_changes {
+      environment.log_warning(&format!(
+        "Warning: Did not stage the formatting changes of {} because it has unstaged changes.",
+        staged_file.path.display()
+      ));
+    } else {
+      file_paths_to_stage.push(staged_file.path.clone());
     }
-  })?;
+  }
+  if !file_paths_to_stage.is_empty() {
+    environment.git_add(&file_paths_to_stage)?;
+  }
+  Ok(())
+}
 
-  let formatted_files_count = formatted_files_count.load(Ordering::SeqCst);
-  if formatted_files_count > 0 {
-    let suffix = if files_count == 1 { "file" } else { "files" };
-    environment.log(&format!("Formatted {} {}.", formatted_files_count.to_string().bold().to_string(), suffix));
+fn get_plugin_names_by_file_path(file_paths_by_plugin: &HashMap<String, Vec<PathBuf>>) -> HashMap<PathBuf, String> {
+  let mut plugin_names_by_file_path = HashMap::new();
+  for (plugin_name, file_paths) in file_paths_by_plugin {
+    for file_path in file_paths {
+      plugin_names_by_file_path.insert(file_path.clone(), plugin_name.clone());
+    }
   }
+  plugin_names_by_file_path
+}
 
-  if let Some(incremental_file) = &incremental_file {
-    incremental_file.write();
+/// Gets if the file no longer has the text that was formatted. A file that can't be read is considered modified.
+fn was_file_modified(environment: &impl Environment, file_path: &Path, file_text: &str, had_bom: bool, encoding: TextEncoding) -> bool {
+  let current_bytes = match environment.read_file_bytes(file_path) {
+    Ok(bytes) => bytes,
+    Err(_) => return true,
+  };
+  let current_text = if encoding == TextEncoding::Utf8 {
+    // decode the same way as when the file was read in order to handle the invalid UTF-8 policy
+    String::from_utf8_lossy(&current_bytes)
+  } else {
+    match encoding.decode(&current_bytes) {
+      Ok(current_text) => Cow::Owned(current_text),
+      Err(_) => return true,
+    }
+  };
+  match current_text.strip_prefix(BOM_CHAR) {
+    Some(current_text) => !had_bom || current_text != file_text,
+    None => had_bom || current_text != file_text,
   }
+}
 
-  Ok(())
+/// Fails the command when warnings were output and they should be treated as errors.
+fn err_if_warnings_as_errors(warnings_as_errors: bool, environment: &impl Environment) -> Result<(), ErrBox> {
+  let warning_count = environment.warning_count().get();
+  if warnings_as_errors && warning_count > 0 {
+    err!("Had {} warning(s), which are treated as errors.", bold(warning_count))
+  } else {
+    Ok(())
+  }
 }
 
 fn output_format_times<TEnvironment: Environment>(
   file_paths_by_plugin: HashMap<String, Vec<PathBuf>>,
   environment: &TEnvironment,
   plugin_pools: Arc<PluginPools<TEnvironment>>,
+  file_encodings: Arc<FileEncodings>,
+  invalid_utf8: InvalidUtf8Policy,
+  mixed_line_endings: MixedLineEndingsPolicy,
+  max_line_length: Option<usize>,
 ) -> Result<(), ErrBox> {
-  let durations: Arc<Mutex<Vec<(PathBuf, u128)>>> = Arc::new(Mutex::new(Vec::new()));
+  let durations: Arc<Mutex<Vec<(PathBuf, Duration)>>> = Arc::new(Mutex::new(Vec::new()));
+  let json_reporter = JsonReporter::new(environment);
 
-  run_parallelized(file_paths_by_plugin, environment, plugin_pools, None, {
+  run_parallelized(file_paths_by_plugin, environment, plugin_pools, None, None, file_encodings, invalid_utf8, mixed_line_endings, max_line_length, false, {
     let durations = durations.clone();
-    move |file_path, _, _, _, start_instant, _| {
-      let duration = start_instant.elapsed().as_millis();
+    move |file_path, _, _, _, _, start_instant, _| {
+      let duration = start_instant.elapsed();
       let mut durations = durations.lock();
       durations.push((file_path.to_owned(), duration));
       Ok(())
@@ -394,9 +1090,15 @@ fn output_format_times<TEnvironment: Environment>(
   })?;
 
   let mut durations = durations.lock();
-  durations.sort_by_key(|k| k.1);
+  durations.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
   for (file_path, duration) in durations.iter() {
-    environment.log(&format!("{}ms - {}", duration, file_path.display()));
+    match environment.output_format() {
+      OutputFormat::Text => environment.log(&format!("{}ms - {}", duration.as_millis(), file_path.display())),
+      OutputFormat::Json => json_reporter.log_file("formatTime", file_path, *duration, json!({})),
+    }
+  }
+  if environment.output_format() == OutputFormat::Json {
+    json_reporter.log_summary("formatTimesSummary", json!({ "files": durations.len() }));
   }
 
   Ok(())
@@ -409,13 +1111,15 @@ mod tests {
   use dprint_core::types::ErrBox;
   use pretty_assertions::assert_eq;
   use std::io::{Read, Write};
+  use serde_json::{json, Value};
   use std::path::{Path, PathBuf};
 
+  use crate::cli::lsp::LspTransport;
   use crate::cli::TestStdInReader;
   use crate::configuration::*;
-  use crate::environment::{Environment, TestEnvironment, TestEnvironmentBuilder};
+  use crate::environment::{Environment, GitStagedFile, TestEnvironment, TestEnvironmentBuilder};
   use crate::test_helpers::{self, run_test_cli, run_test_cli_with_stdin};
-  use crate::utils::get_difference;
+  use crate::utils::{create_tar, get_bytes_hash, get_difference, TextEncoding};
 
   #[test]
   fn it_should_output_version_with_v() {
@@ -500,6 +1204,46 @@ mod tests {
     assert_eq!(environment.take_logged_messages(), vec!["{}"]);
   }
 
+  #[test]
+  fn it_should_output_resolved_config_for_plugin() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin()
+          .add_remote_process_plugin()
+          .add_config_section("lineWidth", "80")
+          .add_config_section("test-plugin", r#"{ "ending": "custom" }"#);
+      })
+      .build();
+    run_test_cli(vec!["output-resolved-config", "--plugin", "test-plugin"], &environment).unwrap();
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec![concat!(
+        "{\n",
+        "  \"test-plugin\": {\n",
+        "    \"ending\": {\n",
+        "      \"source\": \"plugin\",\n",
+        "      \"value\": \"custom\"\n",
+        "    },\n",
+        "    \"lineWidth\": {\n",
+        "      \"source\": \"global\",\n",
+        "      \"value\": 80\n",
+        "    }\n",
+        "  }\n",
+        "}",
+      )]
+    );
+  }
+
+  #[test]
+  fn it_should_error_output_resolved_config_for_unknown_plugin() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin().build();
+    let error_message = run_test_cli(vec!["output-resolved-config", "--plugin", "other"], &environment).err().unwrap();
+    assert_eq!(
+      error_message.to_string(),
+      "No plugin found for 'other'. Expected the name or configuration key of a plugin: test-plugin, testProcessPlugin"
+    );
+  }
+
   #[test]
   fn it_should_output_resolved_file_paths() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
@@ -514,69 +1258,265 @@ mod tests {
   }
 
   #[test]
-  fn it_should_not_output_file_paths_not_supported_by_plugins() {
+  fn it_should_output_resolved_file_paths_in_sorted_order() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
-      .write_file("/file.ts", "const t=4;")
-      .write_file("/file2.ts", "const t=4;")
+      .write_file("/sub/c.txt", "const t=4;")
+      .write_file("/b.txt_ps", "const t=4;")
+      .write_file("/sub/a.txt_ps", "const t=4;")
+      .write_file("/c.txt", "const t=4;")
+      .write_file("/a.txt", "const t=4;")
       .build();
     run_test_cli(vec!["output-file-paths", "**/*.*"], &environment).unwrap();
-    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec!["/a.txt", "/b.txt_ps", "/c.txt", "/sub/a.txt_ps", "/sub/c.txt"]
+    );
   }
 
   #[test]
-  fn it_should_output_resolved_file_paths_when_using_backslashes() {
+  fn it_should_output_file_paths_for_language() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
       .write_file("/file.txt", "const t=4;")
-      .write_file("/file2.txt", "const t=4;")
-      .write_file("/file3.txt_ps", "const t=4;")
+      .write_file("/file2.txt_ps", "const t=4;")
       .build();
-    run_test_cli(vec!["output-file-paths", "**\\*.*"], &environment).unwrap();
-    let mut logged_messages = environment.take_logged_messages();
-    logged_messages.sort();
-    assert_eq!(logged_messages, vec!["/file.txt", "/file2.txt", "/file3.txt_ps"]);
+    run_test_cli(vec!["output-file-paths", "--language", "testProcessPlugin", "**/*.*"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/file2.txt_ps"]);
+
+    run_test_cli(
+      vec!["output-file-paths", "--language", "testProcessPlugin", "--language", "test-plugin", "**/*.*"],
+      &environment,
+    )
+    .unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/file.txt", "/file2.txt_ps"]);
   }
 
   #[test]
-  fn it_should_filter_by_cwd_in_sub_dir() {
-    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+  fn it_should_format_files_for_language() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .write_file("/file.txt", "text")
+      .write_file("/file2.txt_ps", "text")
+      .build();
+    run_test_cli(vec!["fmt", "--language", "test-plugin", "**/*.*"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "text_formatted");
+    assert_eq!(environment.read_file("/file2.txt_ps").unwrap(), "text");
+  }
+
+  #[test]
+  fn it_should_format_files_matching_plugin_associations() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
       .with_default_config(|c| {
-        c.add_includes("**/*.txt");
+        c.add_remote_wasm_plugin()
+          .add_remote_process_plugin()
+          .add_config_section("testProcessPlugin", r#"{ "associations": ["Jenkinsfile", "**/*.txt"] }"#);
       })
-      .write_file("/file.txt", "const t=4;")
-      .write_file("/file2.txt", "const t=4;")
-      .write_file("/sub/file3.txt", "const t=4;")
-      .write_file("/sub2/file4.txt", "const t=4;")
-      .set_cwd("/sub")
+      .write_file("/file.txt", "text")
+      .write_file("/file2.txt_ps", "text")
+      .write_file("/ci/Jenkinsfile", "text")
       .build();
-    run_test_cli(vec!["output-file-paths"], &environment).unwrap();
-    let mut logged_messages = environment.take_logged_messages();
-    logged_messages.sort();
-    assert_eq!(logged_messages, vec!["/sub/file3.txt"]);
+    run_test_cli(vec!["output-file-paths", "**/*"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/ci/Jenkinsfile", "/file.txt"]);
+
+    run_test_cli(vec!["fmt", "**/*"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_plural_formatted_text(2)]);
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "text_formatted_process");
+    assert_eq!(environment.read_file("/ci/Jenkinsfile").unwrap(), "text_formatted_process");
+    // the plugin's default file extensions are no longer used
+    assert_eq!(environment.read_file("/file2.txt_ps").unwrap(), "text");
+
+    // associations are also used when formatting stdin
+    let test_std_in = TestStdInReader::new_with_text("text");
+    run_test_cli_with_stdin(vec!["fmt", "--stdin", "/ci/Jenkinsfile", "--", "**/*"], &environment, test_std_in).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["text_formatted_process"]);
   }
 
   #[test]
-  fn it_should_output_format_times() {
+  fn it_should_output_file_paths_owned_by() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .write_file("/.github/CODEOWNERS", "* @org/everyone\n/sub/ @org/sub-team\n*.txt_ps @user")
       .write_file("/file.txt", "const t=4;")
-      .write_file("/file2.txt", "const t=4;")
-      .write_file("/file3.txt_ps", "const t=4;")
+      .write_file("/file2.txt_ps", "const t=4;")
+      .write_file("/sub/file.txt", "const t=4;")
       .build();
-    run_test_cli(vec!["output-format-times", "**/*.*"], &environment).unwrap();
-    let logged_messages = environment.take_logged_messages();
-    assert_eq!(logged_messages.len(), 3); // good enough
+    run_test_cli(vec!["output-file-paths", "--owned-by", "@org/sub-team", "**/*.*"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/sub/file.txt"]);
+
+    run_test_cli(
+      vec!["output-file-paths", "--owned-by", "@org/everyone", "--owned-by", "user", "**/*.*"],
+      &environment,
+    )
+    .unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/file.txt", "/file2.txt_ps"]);
   }
 
   #[test]
-  fn it_should_format_file() {
-    let file_path1 = "/file.txt";
+  fn it_should_error_owned_by_without_code_owners_file() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
-      .write_file(file_path1, "text")
+      .write_file("/file.txt", "text")
       .build();
-    run_test_cli(vec!["fmt", "/file.txt"], &environment).unwrap();
-    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
-    assert_eq!(environment.take_logged_errors().len(), 0);
-    assert_eq!(environment.read_file(&file_path1).unwrap(), "text_formatted");
-  }
+    let error_message = run_test_cli(vec!["fmt", "--owned-by", "@user", "**/*.*"], &environment).err().unwrap();
+    assert_eq!(
+      error_message.to_string(),
+      "Could not find a CODEOWNERS file in /. Expected one of: CODEOWNERS, .github/CODEOWNERS, .gitlab/CODEOWNERS, docs/CODEOWNERS"
+    );
+  }
+
+  #[test]
+  fn it_should_error_for_unknown_language() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .write_file("/file.txt", "text")
+      .build();
+    let error_message = run_test_cli(vec!["check", "--language", "json", "**/*.*"], &environment).err().unwrap();
+    assert_eq!(
+      error_message.to_string(),
+      "No plugin found for language 'json'. Expected the configuration key of a plugin: test-plugin, testProcessPlugin"
+    );
+  }
+
+  #[test]
+  fn it_should_not_output_file_paths_not_supported_by_plugins() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .write_file("/file.ts", "const t=4;")
+      .write_file("/file2.ts", "const t=4;")
+      .build();
+    run_test_cli(vec!["output-file-paths", "**/*.*"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+  }
+
+  #[test]
+  fn it_should_output_resolved_file_paths_when_using_backslashes() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .write_file("/file.txt", "const t=4;")
+      .write_file("/file2.txt", "const t=4;")
+      .write_file("/file3.txt_ps", "const t=4;")
+      .build();
+    run_test_cli(vec!["output-file-paths", "**\\*.*"], &environment).unwrap();
+    let mut logged_messages = environment.take_logged_messages();
+    logged_messages.sort();
+    assert_eq!(logged_messages, vec!["/file.txt", "/file2.txt", "/file3.txt_ps"]);
+  }
+
+  #[test]
+  fn it_should_filter_by_cwd_in_sub_dir() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_includes("**/*.txt");
+      })
+      .write_file("/file.txt", "const t=4;")
+      .write_file("/file2.txt", "const t=4;")
+      .write_file("/sub/file3.txt", "const t=4;")
+      .write_file("/sub2/file4.txt", "const t=4;")
+      .set_cwd("/sub")
+      .build();
+    run_test_cli(vec!["output-file-paths"], &environment).unwrap();
+    let mut logged_messages = environment.take_logged_messages();
+    logged_messages.sort();
+    assert_eq!(logged_messages, vec!["/sub/file3.txt"]);
+  }
+
+  #[test]
+  fn it_should_output_format_times() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .write_file("/file.txt", "const t=4;")
+      .write_file("/file2.txt", "const t=4;")
+      .write_file("/file3.txt_ps", "const t=4;")
+      .build();
+    run_test_cli(vec!["output-format-times", "**/*.*"], &environment).unwrap();
+    let logged_messages = environment.take_logged_messages();
+    assert_eq!(logged_messages.len(), 3); // good enough
+  }
+
+  #[test]
+  fn it_should_lint_width() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", &format!("a\n{}\n{}\n", "b".repeat(120), "c".repeat(121)))
+      .write_file("/file2.txt", "a\n")
+      .build();
+    let error_message = run_test_cli(vec!["lint-width", "**/*.txt"], &environment).err().unwrap();
+    assert_eq!(
+      error_message.to_string(),
+      format!("Found {} line(s) exceeding the line width.", "1".bold().to_string())
+    );
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec![format!(
+        "{} /file.txt:3: Line is 121 columns, which exceeds the line width of 120 (test-plugin).",
+        "long line".bold().red().to_string()
+      )]
+    );
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_lint_width_using_configured_line_width() {
+    let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_config_section("test-plugin", r#"{ "line_width": 2 }"#);
+      })
+      .write_file("/file.txt", "ab\nabc\n")
+      .initialize()
+      .build();
+    let error_message = run_test_cli(vec!["lint-width", "/file.txt"], &environment).err().unwrap();
+    assert_eq!(
+      error_message.to_string(),
+      format!("Found {} line(s) exceeding the line width.", "1".bold().to_string())
+    );
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec![format!(
+        "{} /file.txt:2: Line is 3 columns, which exceeds the line width of 2 (test-plugin).",
+        "long line".bold().red().to_string()
+      )]
+    );
+  }
+
+  #[test]
+  fn it_should_format_file() {
+    let file_path1 = "/file.txt";
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file(file_path1, "text")
+      .build();
+    run_test_cli(vec!["fmt", "/file.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.read_file(&file_path1).unwrap(), "text_formatted");
+  }
+
+  #[test]
+  fn it_should_format_archive_members_when_specified() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin().build();
+    let archive_bytes = create_tar(&[("data/a.txt", b"text"), ("data/b.dat", b"text"), ("c.txt", b"text")]);
+    environment.write_file_bytes("/assets.tar", &archive_bytes).unwrap();
+
+    let error_message = run_test_cli(vec!["check", "--experimental-archives", "/assets.tar!data/*.txt"], &environment)
+      .err()
+      .unwrap();
+    assert_eq!(error_message.to_string(), get_singular_check_text());
+    assert_eq!(environment.take_logged_messages(), vec!["/assets.tar!data/a.txt"]);
+
+    run_test_cli(vec!["fmt", "--experimental-archives", "/assets.tar!data/*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(
+      environment.read_file_bytes("/assets.tar").unwrap(),
+      create_tar(&[("data/a.txt", b"text_formatted"), ("data/b.dat", b"text"), ("c.txt", b"text")])
+    );
+
+    run_test_cli(vec!["check", "--experimental-archives", "/assets.tar!data/*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+  }
+
+  #[test]
+  fn it_should_error_for_non_archive_patterns_with_experimental_archives() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin().build();
+    let error_message = run_test_cli(vec!["fmt", "--experimental-archives", "/assets.tar!**/*.txt", "**/*.txt"], &environment)
+      .err()
+      .unwrap();
+    assert_eq!(
+      error_message.to_string(),
+      "Expected only archive member patterns (ex. assets.zip!**/*.json) when specifying --experimental-archives, but found: **/*.txt"
+    );
+  }
 
   #[test]
   fn it_should_format_files() {
@@ -593,6 +1533,23 @@ mod tests {
     assert_eq!(environment.read_file(&file_path2).unwrap(), "text2_formatted_process");
   }
 
+  #[test]
+  fn it_should_skip_verifying_whitespace_only_for_plugin_without_guarantee() {
+    let file_path1 = "/file.txt";
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file(file_path1, "text")
+      .build();
+    run_test_cli(vec!["fmt", "/file.txt", "--verify-whitespace-only", "--verbose"], &environment).unwrap();
+    assert_eq!(
+      environment
+        .take_logged_errors()
+        .iter()
+        .any(|msg| msg.contains("Skipped verifying /file.txt because the test-plugin plugin does not declare that it only changes whitespace.")),
+      true
+    );
+    assert_eq!(environment.read_file(&file_path1).unwrap(), "text_formatted");
+  }
+
   #[test]
   fn it_should_format_plugin_explicitly_specified_files() {
     // this file name is mentioned in test-process-plugin's PluginInfo
@@ -632,11 +1589,41 @@ mod tests {
     assert_eq!(environment.take_logged_messages().len(), 0);
     assert_eq!(
       environment.take_logged_errors(),
-      vec![String::from("Error formatting /file.txt. Message: Did error.")]
+      vec![String::from(
+        "Error formatting /file.txt. Message: Did error.\n  Plugin: test-plugin 0.1.0\n  File: /file.txt (sha256: 91777ec7845e)\n  Report issues at: https://dprint.dev/plugins/test"
+      )]
     );
     assert_eq!(error_message.to_string(), "Had 1 error(s) formatting.");
   }
 
+  #[test]
+  fn it_should_summarize_files_with_the_same_error() {
+    let mut builder = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin();
+    for i in 1..=7 {
+      builder.write_file(&format!("/file{}.txt", i), "should_error");
+    }
+    let environment = builder.build();
+    let error_message = run_test_cli(vec!["fmt", "**/*.txt"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), "Had 7 error(s) formatting.");
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    let logged_errors = environment.take_logged_errors();
+    // the file that's logged in full depends on the order the files were formatted in
+    assert!(logged_errors[0].starts_with("Error formatting /file"));
+    assert!(logged_errors[0].contains("Message: Did error."));
+    let first_file_path = logged_errors[0]["Error formatting ".len()..].split(". Message").next().unwrap();
+    let mut expected_errors =
+      vec!["Had the same error formatting 6 other file(s) (test-plugin: Did error.). Run with --verbose to see each error.".to_string()];
+    expected_errors.extend(
+      (1..=7)
+        .map(|i| format!("/file{}.txt", i))
+        .filter(|file_path| file_path != first_file_path)
+        .take(5)
+        .map(|file_path| format!("  {}", file_path)),
+    );
+    expected_errors.push("  ...and 1 more".to_string());
+    assert_eq!(logged_errors[1..].to_vec(), expected_errors);
+  }
+
   #[test]
   fn it_should_handle_process_plugin_erroring() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_process_plugin()
@@ -646,11 +1633,29 @@ mod tests {
     assert_eq!(environment.take_logged_messages().len(), 0);
     assert_eq!(
       environment.take_logged_errors(),
-      vec![String::from("Error formatting /file.txt_ps. Message: Did error.")]
+      vec![String::from(
+        "Error formatting /file.txt_ps. Message: Did error.\n  Plugin: test-process-plugin 0.1.0\n  File: /file.txt_ps (sha256: 91777ec7845e)\n  Report issues at: https://dprint.dev/plugins/test-process"
+      )]
     );
     assert_eq!(error_message.to_string(), "Had 1 error(s) formatting.");
   }
 
+  #[test]
+  fn it_should_restart_process_plugin_that_exited_while_formatting() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_process_plugin()
+      .write_file("/file1.txt_ps", "should_crash") // special text that makes the plugin exit
+      .write_file("/file2.txt_ps", "text")
+      .build();
+    let error_message = run_test_cli(vec!["fmt", "/file1.txt_ps", "/file2.txt_ps"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), "Had 1 error(s) formatting.");
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    let logged_errors = environment.take_logged_errors();
+    assert_eq!(logged_errors.len(), 1);
+    assert!(logged_errors[0].starts_with("Error formatting /file1.txt_ps. Message: The process plugin exited unexpectedly and was restarted 3 times."));
+    // the restarted process is used for the other files
+    assert_eq!(environment.read_file("/file2.txt_ps").unwrap(), "text_formatted_process");
+  }
+
   #[test]
   fn it_should_handle_wasm_plugin_panicking() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
@@ -717,7 +1722,9 @@ mod tests {
     assert_eq!(error_message.to_string(), "Had 1 error(s) formatting.");
     assert_eq!(
       environment.take_logged_errors(),
-      vec![String::from("Error formatting /file.txt. Message: Did error.")]
+      vec![String::from(
+        "Error formatting /file.txt. Message: Did error.\n  Plugin: test-plugin 0.1.0\n  File: /file.txt (sha256: 05d9fd7f19f4)\n  Report issues at: https://dprint.dev/plugins/test"
+      )]
     );
   }
 
@@ -769,7 +1776,9 @@ mod tests {
     assert_eq!(error_message.to_string(), "Had 1 error(s) formatting.");
     assert_eq!(
       environment.take_logged_errors(),
-      vec![String::from("Error formatting /file.txt_ps. Message: Did error.")]
+      vec![String::from(
+        "Error formatting /file.txt_ps. Message: Did error.\n  Plugin: test-process-plugin 0.1.0\n  File: /file.txt_ps (sha256: 05d9fd7f19f4)\n  Report issues at: https://dprint.dev/plugins/test-process"
+      )]
     );
   }
 
@@ -823,6 +1832,148 @@ mod tests {
     assert_eq!(environment.take_logged_errors().len(), 0);
   }
 
+  #[test]
+  fn it_should_ignore_files_in_git_dir() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/.git/file.txt", "")
+      .write_file("/test/.git/file.txt", "")
+      .write_file("/file.txt", "")
+      .build();
+    run_test_cli(vec!["fmt", "--allow-node-modules", "**/*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_not_search_dirs_whose_descendants_are_excluded() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/target/file.txt", "")
+      .write_file("/sub/target/file.txt", "")
+      .write_file("/out/file.txt", "")
+      .write_file("/file.txt", "")
+      .build();
+    run_test_cli(vec!["fmt", "**/*.txt", "--excludes", "**/target/**", "--excludes", "out/"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_support_negated_cli_file_patterns() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/src/file.txt", "text")
+      .write_file("/src/generated/file.txt", "text")
+      .write_file("/src/generated/keep.txt", "text")
+      .build();
+    run_test_cli(vec!["fmt", "src/**/*.txt", "!src/generated/**", "src/generated/keep.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_plural_formatted_text(2)]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.read_file("/src/file.txt").unwrap(), "text_formatted");
+    assert_eq!(environment.read_file("/src/generated/file.txt").unwrap(), "text");
+    assert_eq!(environment.read_file("/src/generated/keep.txt").unwrap(), "text_formatted");
+  }
+
+  #[test]
+  fn it_should_exclude_files_in_dprintignore_files() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/.dprintignore", "# generated files\n*.generated.txt\n/out\n")
+      .write_file("/file.txt", "")
+      .write_file("/file.generated.txt", "")
+      .write_file("/out/file.txt", "")
+      .write_file("/sub/.dprintignore", "ignored.txt")
+      .write_file("/sub/ignored.txt", "")
+      .write_file("/sub/file.generated.txt", "")
+      .write_file("/sub/out/file.txt", "")
+      .write_file("/other/ignored.txt", "")
+      .build();
+    run_test_cli(vec!["output-file-paths", "**/*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/file.txt", "/other/ignored.txt", "/sub/out/file.txt"]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_exclude_files_in_dprintignore_files_of_ancestor_dirs() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_includes("**/*.txt");
+      })
+      .write_file("/.dprintignore", "*.generated.txt")
+      .write_file("/sub/file.txt", "")
+      .write_file("/sub/file.generated.txt", "")
+      .set_cwd("/sub")
+      .build();
+    run_test_cli(vec!["output-file-paths"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/sub/file.txt"]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_exclude_files_in_dot_ignore_files_when_enabled() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_includes("**/*.txt").add_config_section("useDotIgnoreFiles", "true");
+      })
+      .write_file("/.ignore", "/out")
+      .write_file("/file.txt", "")
+      .write_file("/out/file.txt", "")
+      .write_file("/sub/.ignore", "*.generated.txt")
+      .write_file("/sub/file.generated.txt", "")
+      .build();
+    run_test_cli(vec!["output-file-paths"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/file.txt"]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_not_exclude_files_in_dot_ignore_files_by_default() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_includes("**/*.txt");
+      })
+      .write_file("/.ignore", "/out")
+      .write_file("/file.txt", "")
+      .write_file("/out/file.txt", "")
+      .build();
+    run_test_cli(vec!["output-file-paths"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/file.txt", "/out/file.txt"]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_ignore_default_excludes() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/dist/file.txt", "")
+      .write_file("/sub/build/file.txt", "")
+      .write_file("/sub/file.min.txt", "")
+      .write_file("/file.txt", "")
+      .build();
+    run_test_cli(vec!["output-file-paths", "**/*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/file.txt"]);
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec![
+        "Directories excluded by default (specify \"useDefaultExcludes\": false in the configuration file to include them):",
+        "  /dist (**/dist)",
+        "  /sub/build (**/build)",
+      ]
+    );
+  }
+
+  #[test]
+  fn it_should_not_ignore_default_excludes_when_disabled() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_config_section("useDefaultExcludes", "false");
+      })
+      .write_file("/dist/file.txt", "")
+      .write_file("/sub/file.min.txt", "")
+      .write_file("/.git/file.txt", "")
+      .write_file("/file.txt", "")
+      .build();
+    run_test_cli(vec!["output-file-paths", "**/*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["/dist/file.txt", "/file.txt", "/sub/file.min.txt"]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
   #[test]
   fn it_should_format_files_with_config() {
     let file_path1 = "/file1.txt";
@@ -878,16 +2029,356 @@ mod tests {
   }
 
   #[test]
-  fn it_should_error_when_config_file_does_not_exist() {
-    let environment = TestEnvironment::new();
-    environment.write_file("/test.txt", "test").unwrap();
-
-    let error_message = run_test_cli(vec!["fmt", "**/*.txt"], &environment).err().unwrap();
-
-    assert_eq!(
-      error_message.to_string(),
-      concat!(
-        "No config file found at /dprint.json. Did you mean to create (dprint init) or specify one (--config <path>)?\n",
+  fn it_should_format_files_with_config_overrides() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/src/file.txt", "text")
+      .write_file("/src/legacy/file.txt", "text")
+      .write_file("/tests/file.txt", "text")
+      .write_file(
+        "/dprint.json",
+        r#"{
+                "test-plugin": { "ending": "custom-formatted" },
+                "overrides": [{
+                    "files": "src/legacy/**/*.txt",
+                    "test-plugin": { "ending": "legacy" }
+                }, {
+                    "files": ["src/**/*.txt", "tests/**/*.txt"],
+                    "test-plugin": { "ending": "overridden" }
+                }],
+                "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"]
+            }"#,
+      )
+      .build();
+
+    run_test_cli(vec!["fmt", "**/*.txt"], &environment).unwrap();
+
+    assert_eq!(environment.take_logged_messages(), vec![get_plural_formatted_text(3)]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.read_file("/src/file.txt").unwrap(), "text_overridden");
+    // the closest match takes precedence
+    assert_eq!(environment.read_file("/src/legacy/file.txt").unwrap(), "text_legacy");
+    assert_eq!(environment.read_file("/tests/file.txt").unwrap(), "text_overridden");
+  }
+
+  #[test]
+  fn it_should_error_for_files_with_invalid_utf8() {
+    let environment = get_invalid_utf8_environment(None);
+    let error_message = run_test_cli(vec!["fmt", "*.txt"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), "Had 1 error(s) formatting.");
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Error formatting /file.txt. Message: File contained invalid UTF-8. invalid utf-8 sequence of 1 bytes from index 1"]
+    );
+  }
+
+  #[test]
+  fn it_should_skip_files_with_invalid_utf8() {
+    let environment = get_invalid_utf8_environment(Some("skip"));
+    run_test_cli(vec!["fmt", "*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.read_file_bytes("/file.txt").unwrap(), vec![b't', 0xFF]);
+  }
+
+  #[test]
+  fn it_should_replace_invalid_utf8_in_files() {
+    let environment = get_invalid_utf8_environment(Some("replace"));
+    run_test_cli(vec!["fmt", "*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Warning: Replaced invalid UTF-8 in /file.txt with the replacement character."]
+    );
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "t\u{FFFD}_formatted");
+  }
+
+  #[test]
+  fn it_should_transcode_files_with_configured_encoding() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_config_section("encodings", r#"{ "legacy/**": "windows-1252" }"#);
+      })
+      .build();
+    environment.write_file_bytes("/legacy/file.txt", &[b'c', b'a', b'f', 0xE9, 0x80]).unwrap();
+    environment.write_file("/file.txt", "café").unwrap();
+    run_test_cli(vec!["fmt", "**/*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_plural_formatted_text(2)]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    let mut expected_bytes = vec![b'c', b'a', b'f', 0xE9, 0x80];
+    expected_bytes.extend("_formatted".as_bytes());
+    assert_eq!(environment.read_file_bytes("/legacy/file.txt").unwrap(), expected_bytes);
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "café_formatted");
+  }
+
+  #[test]
+  fn it_should_skip_files_with_extremely_long_lines() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .write_file("/file1.txt", "text")
+      .build();
+    let long_text = format!("text\n{}", "a".repeat(1_000_001));
+    environment.write_file("/file2.txt", &long_text).unwrap();
+    run_test_cli(vec!["fmt", "*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Warning: Skipped /file2.txt because line 2 is 1000001 bytes long. Consider adding it to the \"excludes\" or increasing the \"maxLineLength\" in the configuration file."]
+    );
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text_formatted");
+    assert_eq!(environment.read_file("/file2.txt").unwrap(), long_text);
+  }
+
+  #[test]
+  fn it_should_format_files_with_extremely_long_lines_when_max_line_length_is_zero() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_config_section("maxLineLength", "0");
+      })
+      .build();
+    environment.write_file("/file.txt", &format!("text\n{}", "a".repeat(1_000_001))).unwrap();
+    run_test_cli(vec!["fmt", "*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_not_warn_for_long_lines_in_files_unchanged_since_the_last_incremental_run() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_includes("**/*.txt").add_remote_wasm_plugin();
+      })
+      .write_file("/file1.txt", &format!("text\n{}", "a".repeat(101)))
+      .build();
+    run_test_cli(vec!["fmt", "--incremental"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+
+    environment
+      .write_file(
+        "./dprint.json",
+        r#"{
+            "maxLineLength": 100,
+            "includes": ["**/*.txt"],
+            "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"]
+        }"#,
+      )
+      .unwrap();
+    run_test_cli(vec!["fmt", "--incremental"], &environment).unwrap();
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.warning_count().get(), 0);
+  }
+
+  #[test]
+  fn it_should_error_for_warnings_when_warnings_as_errors_specified() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .write_file("/file1.txt", "text")
+      .build();
+    environment.write_file("/file2.txt", &format!("text\n{}", "a".repeat(1_000_001))).unwrap();
+    let error_message = run_test_cli(vec!["fmt", "--warnings-as-errors", "*.txt"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), format!("Had {} warning(s), which are treated as errors.", "1".bold().to_string()));
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors().len(), 1);
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text_formatted");
+  }
+
+  #[test]
+  fn it_should_error_for_warnings_when_warnings_as_errors_configured() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_config_section("warningsAsErrors", "true");
+      })
+      .write_file("/file1.txt", "text_formatted")
+      .build();
+    environment.write_file("/file2.txt", &format!("text\n{}", "a".repeat(1_000_001))).unwrap();
+    let error_message = run_test_cli(vec!["check", "*.txt"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), format!("Had {} warning(s), which are treated as errors.", "1".bold().to_string()));
+    assert_eq!(environment.take_logged_errors().len(), 1);
+  }
+
+  #[test]
+  fn it_should_not_error_for_warnings_when_warnings_as_errors_not_specified() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .write_file("/file1.txt", "text_formatted")
+      .build();
+    environment.write_file("/file2.txt", &format!("text\n{}", "a".repeat(1_000_001))).unwrap();
+    run_test_cli(vec!["check", "*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_errors().len(), 1);
+  }
+
+  #[test]
+  fn it_should_output_unified_diff_in_check_when_specified() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .write_file("/file.txt", "text\n")
+      .build();
+    let error_message = run_test_cli(vec!["check", "--diff"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), get_singular_check_text());
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec!["--- /file.txt\n+++ /file.txt\n@@ -1 +1,2 @@\n text\n+_formatted\n\\ No newline at end of file"]
+    );
+  }
+
+  #[test]
+  fn it_should_output_only_file_paths_in_check_when_listing_different() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .write_file("/file1.txt", "text")
+      .write_file("/file2.txt", "text_formatted")
+      .write_file("/file3.txt", "a\r\nb\n")
+      .write_file("/a.txt", "text")
+      .build();
+    let error_message = run_test_cli(vec!["check", "--list-different"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), get_plural_check_text(3));
+    // sorted by file path regardless of the order the files were checked in
+    assert_eq!(environment.take_logged_messages(), vec!["/a.txt", "/file1.txt", "/file3.txt"]);
+  }
+
+  #[test]
+  fn it_should_output_only_summary_in_check_when_specified() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .write_file("/file1.txt", "text")
+      .write_file("/file2.txt", "text_formatted")
+      .build();
+    let error_message = run_test_cli(vec!["check", "--summary"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), get_singular_check_text());
+    assert_eq!(environment.take_logged_messages(), vec!["Checked 2 files."]);
+
+    run_test_cli(vec!["check", "--summary", "/file2.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["Checked 1 file."]);
+  }
+
+  #[test]
+  fn it_should_report_mixed_line_endings_in_check() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_config_section("mixedLineEndings", "\"report\"");
+      })
+      .write_file("/file.txt", "a\r\nb\r\nc\n")
+      .build();
+    let error_message = run_test_cli(vec!["check"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), get_singular_check_text());
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec![format!("{} /file.txt: Found 2 CRLF and 1 LF line endings.\n--", "mixed line endings".bold().red().to_string())]
+    );
+  }
+
+  #[test]
+  fn it_should_not_report_mixed_line_endings_in_check_by_default() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "a\r\nb\nc_formatted")
+      .build();
+    run_test_cli(vec!["check"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_normalize_mixed_line_endings_when_configured() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_config_section("mixedLineEndings", "\"lf\"");
+      })
+      .write_file("/file.txt", "a\r\nb\n")
+      .build();
+    run_test_cli(vec!["fmt", "*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "a\nb\n_formatted");
+  }
+
+  #[test]
+  fn it_should_apply_configured_new_line_kind_to_formatted_text() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_config_section("newLineKind", "\"crlf\"");
+      })
+      .write_file("/file.txt", "a\nb\n")
+      .build();
+    run_test_cli(vec!["fmt", "*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "a\r\nb\r\n_formatted");
+  }
+
+  #[test]
+  fn it_should_apply_file_boundary_policies_to_formatted_text() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin()
+          .add_config_section("trimLeadingBlankLines", "true")
+          .add_config_section("singleTrailingNewLine", "true");
+      })
+      .write_file("/file.txt", "\n\na")
+      .build();
+    run_test_cli(vec!["fmt", "*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "a_formatted\n");
+  }
+
+  #[test]
+  fn it_should_apply_file_boundary_policies_from_plugin_config_and_overrides() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "\n\na")
+      .write_file("/src/file.txt", "\n\na")
+      .write_file(
+        "/dprint.json",
+        r#"{
+                "singleTrailingNewLine": true,
+                "test-plugin": { "trimLeadingBlankLines": true },
+                "overrides": [{
+                    "files": "src/**/*.txt",
+                    "singleTrailingNewLine": false,
+                    "test-plugin": { "trimLeadingBlankLines": false }
+                }],
+                "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"]
+            }"#,
+      )
+      .build();
+    run_test_cli(vec!["fmt", "**/*.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_plural_formatted_text(2)]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.read_file("/file.txt").unwrap(), "a_formatted\n");
+    assert_eq!(environment.read_file("/src/file.txt").unwrap(), "\n\na_formatted");
+  }
+
+  fn get_invalid_utf8_environment(policy: Option<&str>) -> TestEnvironment {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+        if let Some(policy) = policy {
+          c.add_config_section("invalidUtf8", &format!("\"{}\"", policy));
+        }
+      })
+      .build();
+    environment.write_file_bytes("/file.txt", &[b't', 0xFF]).unwrap();
+    environment
+  }
+
+  #[test]
+  fn it_should_error_when_config_file_does_not_exist() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/test.txt", "test").unwrap();
+
+    let error_message = run_test_cli(vec!["fmt", "**/*.txt"], &environment).err().unwrap();
+
+    assert_eq!(
+      error_message.to_string(),
+      concat!(
+        "No config file found at /dprint.json. Did you mean to create (dprint init) or specify one (--config <path>)?\n",
         "  Error: Could not find file at path /dprint.json"
       )
     );
@@ -942,6 +2433,49 @@ mod tests {
     );
   }
 
+  #[test]
+  fn it_should_check_config() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .with_default_config(|c| {
+        c.add_config_section("test-plugin", r#"{ "ending": "custom" }"#);
+      })
+      .write_file("/test.txt", "test")
+      .build();
+
+    run_test_cli(vec!["check-config"], &environment).unwrap();
+
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.read_file("/test.txt").unwrap(), "test");
+  }
+
+  #[test]
+  fn it_should_output_diagnostics_of_each_plugin_when_checking_config() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .with_default_config(|c| {
+        c.add_config_section("test-plugin", r#"{ "non-existent": 25 }"#)
+          .add_config_section("testProcessPlugin", r#"{ "other": 1, "other2": 2 }"#);
+      })
+      .build();
+
+    let error_message = run_test_cli(vec!["check-config"], &environment).err().unwrap();
+
+    assert_eq!(error_message.to_string(), "Had 3 configuration diagnostic(s).");
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    let mut logged_errors = environment.take_logged_errors();
+    logged_errors.sort();
+    assert_eq!(
+      logged_errors,
+      vec![
+        "[test-plugin]: Error initializing from configuration file. Had 1 diagnostic(s).",
+        "[test-plugin]: Unknown property in configuration: non-existent",
+        "[test-process-plugin]: Error initializing from configuration file. Had 2 diagnostic(s).",
+        "[test-process-plugin]: Unknown property in configuration: other",
+        "[test-process-plugin]: Unknown property in configuration: other2",
+      ]
+    );
+  }
+
   #[test]
   fn it_should_error_on_process_plugin_config_diagnostic() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_process_plugin()
@@ -1160,24 +2694,107 @@ mod tests {
     assert_eq!(environment.read_file(&file_path2).unwrap(), "text2_formatted");
   }
 
-  #[cfg(target_os = "windows")]
   #[test]
-  fn it_should_format_files_with_config_includes_when_using_back_slashes() {
-    let file_path1 = "/file1.txt";
+  fn it_should_format_and_stage_staged_files() {
     let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .write_file("/file1.txt", "text1")
+      .write_file("/file2.txt", "text2")
+      .write_file("/file3.txt", "text3")
+      .write_file("/file4.dat", "text4")
       .with_default_config(|c| {
-        c.add_includes("**\\\\*.txt") // escape for the json
-          .add_remote_wasm_plugin();
+        c.add_includes("**/*.*").add_remote_wasm_plugin();
       })
-      .write_file(file_path1, "text1")
       .initialize()
       .build();
+    environment.set_git_staged_files(vec![
+      GitStagedFile {
+        path: PathBuf::from("/file1.txt"),
+        has_unstaged_changes: false,
+      },
+      GitStagedFile {
+        path: PathBuf::from("/file2.txt"),
+        has_unstaged_changes: true,
+      },
+      GitStagedFile {
+        path: PathBuf::from("/file4.dat"),
+        has_unstaged_changes: false,
+      },
+    ]);
 
-    run_test_cli(vec!["fmt"], &environment).unwrap();
+    run_test_cli(vec!["fmt", "--staged"], &environment).unwrap();
 
-    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
-    assert_eq!(environment.take_logged_errors().len(), 0);
-    assert_eq!(environment.read_file(&file_path1).unwrap(), "text1_formatted");
+    assert_eq!(environment.take_logged_messages(), vec![get_plural_formatted_text(2)]);
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Warning: Did not stage the formatting changes of /file2.txt because it has unstaged changes."]
+    );
+    assert_eq!(environment.take_git_added_files(), vec![PathBuf::from("/file1.txt")]);
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text1_formatted");
+    assert_eq!(environment.read_file("/file2.txt").unwrap(), "text2_formatted");
+    assert_eq!(environment.read_file("/file3.txt").unwrap(), "text3");
+    assert_eq!(environment.read_file("/file4.dat").unwrap(), "text4");
+  }
+
+  #[test]
+  fn it_should_not_error_when_no_staged_files_to_format() {
+    let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .write_file("/file1.txt", "text1")
+      .write_file("/file2.dat", "text2")
+      .with_default_config(|c| {
+        c.add_includes("**/*.*").add_remote_wasm_plugin();
+      })
+      .initialize()
+      .build();
+    environment.set_git_staged_files(vec![GitStagedFile {
+      path: PathBuf::from("/file2.dat"),
+      has_unstaged_changes: false,
+    }]);
+
+    run_test_cli(vec!["fmt", "--staged"], &environment).unwrap();
+
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.take_git_added_files().len(), 0);
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text1");
+  }
+
+  #[test]
+  fn it_should_error_formatting_staged_files_outside_git_repository() {
+    let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .write_file("/file1.txt", "text1")
+      .with_default_config(|c| {
+        c.add_includes("**/*.txt").add_remote_wasm_plugin();
+      })
+      .initialize()
+      .build();
+
+    let error_message = run_test_cli(vec!["fmt", "--staged"], &environment).err().unwrap();
+
+    assert_eq!(
+      error_message.to_string(),
+      "Error running `git rev-parse --show-toplevel`: fatal: not a git repository"
+    );
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text1");
+  }
+
+  #[cfg(target_os = "windows")]
+  #[test]
+  fn it_should_format_files_with_config_includes_when_using_back_slashes() {
+    let file_path1 = "/file1.txt";
+    let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_includes("**\\\\*.txt") // escape for the json
+          .add_remote_wasm_plugin();
+      })
+      .write_file(file_path1, "text1")
+      .initialize()
+      .build();
+
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.read_file(&file_path1).unwrap(), "text1_formatted");
   }
 
   #[test]
@@ -1310,6 +2927,34 @@ mod tests {
     assert_eq!(environment.read_file(&sub_dir_file_path3).unwrap(), "text");
   }
 
+  #[test]
+  fn it_should_use_last_matching_pattern_of_config_includes_and_excludes() {
+    let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .write_file("/src/file.txt", "text")
+      .write_file("/src/file.test.txt", "text")
+      .write_file("/src/generated/file.txt", "text")
+      .write_file("/src/generated/keep.txt", "text")
+      .with_default_config(|c| {
+        c.add_includes("src/**/*.txt")
+          .add_includes("!src/generated/**")
+          .add_includes("src/generated/keep.txt")
+          .add_excludes("**/*.test.txt")
+          .add_excludes("!src/file.test.txt")
+          .add_remote_wasm_plugin();
+      })
+      .initialize()
+      .build();
+
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+
+    assert_eq!(environment.take_logged_messages(), vec![get_plural_formatted_text(3)]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.read_file("/src/file.txt").unwrap(), "text_formatted");
+    assert_eq!(environment.read_file("/src/file.test.txt").unwrap(), "text_formatted");
+    assert_eq!(environment.read_file("/src/generated/file.txt").unwrap(), "text");
+    assert_eq!(environment.read_file("/src/generated/keep.txt").unwrap(), "text_formatted");
+  }
+
   #[test]
   fn it_should_format_using_hidden_config_file_name() {
     let file_path = "/test/other/file.txt";
@@ -1490,6 +3135,41 @@ mod tests {
     );
   }
 
+  #[test]
+  fn it_should_format_again_incrementally_when_file_text_config_changes() {
+    let no_change_msg = "No change: /file1.txt";
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_includes("**/*.txt").add_remote_wasm_plugin();
+      })
+      .write_file("/file1.txt", "text1_formatted")
+      .build();
+    run_test_cli(vec!["fmt", "--incremental"], &environment).unwrap();
+    run_test_cli(vec!["fmt", "--incremental", "--verbose"], &environment).unwrap();
+    assert_eq!(environment.take_logged_errors().iter().any(|msg| msg.contains(no_change_msg)), true);
+
+    for config_property in [r#""invalidUtf8": "replace""#, r#""encodings": { "**/*.txt": "utf-8" }"#].iter() {
+      environment
+        .write_file(
+          "./dprint.json",
+          &format!(
+            r#"{{
+                {},
+                "includes": ["**/*.txt"],
+                "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"]
+            }}"#,
+            config_property
+          ),
+        )
+        .unwrap();
+      environment.clear_logs();
+      run_test_cli(vec!["fmt", "--incremental", "--verbose"], &environment).unwrap();
+      assert_eq!(environment.take_logged_errors().iter().any(|msg| msg.contains(no_change_msg)), false);
+      run_test_cli(vec!["fmt", "--incremental", "--verbose"], &environment).unwrap();
+      assert_eq!(environment.take_logged_errors().iter().any(|msg| msg.contains(no_change_msg)), true);
+    }
+  }
+
   #[test]
   fn it_should_format_incrementally_when_specified_via_config() {
     let file_path1 = "/file1.txt";
@@ -1512,6 +3192,238 @@ mod tests {
     assert_eq!(environment.take_logged_errors().iter().any(|msg| msg.contains("No change: /file1.txt")), true);
   }
 
+  #[test]
+  fn it_should_always_format_force_format_files_when_incremental() {
+    let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin()
+          .add_includes("**/*.txt")
+          .set_incremental(true)
+          .add_config_section("forceFormat", r#"["generated/**/*.txt"]"#);
+      })
+      .initialize()
+      .write_file("/file1.txt", "text1_formatted")
+      .write_file("/file2.txt", "dprint-force-format_formatted")
+      .write_file("/generated/file3.txt", "text3_formatted")
+      .build();
+
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+    environment.clear_logs();
+    run_test_cli(vec!["fmt", "--verbose"], &environment).unwrap();
+    let logged_errors = environment.take_logged_errors();
+    assert_eq!(logged_errors.iter().any(|msg| msg.contains("No change: /file1.txt")), true);
+    assert_eq!(logged_errors.iter().any(|msg| msg.contains("No change: /file2.txt")), false);
+    assert_eq!(logged_errors.iter().any(|msg| msg.contains("No change: /generated/file3.txt")), false);
+  }
+
+  #[test]
+  fn it_should_not_format_incrementally_via_config_in_ci() {
+    let file_path1 = "/file1.txt";
+    let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_includes("**/*.txt").set_incremental(true);
+      })
+      .initialize()
+      .write_file(&file_path1, "text1_formatted")
+      .build();
+    environment.set_ci(true);
+
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+    environment.clear_logs();
+    run_test_cli(vec!["fmt", "--verbose"], &environment).unwrap();
+    let logged_errors = environment.take_logged_errors();
+    assert_eq!(logged_errors.iter().any(|msg| msg.contains("No change: /file1.txt")), false);
+    assert_eq!(
+      logged_errors
+        .iter()
+        .any(|msg| msg.contains("Ignoring incremental configuration in CI environment. Specify --incremental to use it.")),
+      true
+    );
+    environment.clear_logs();
+
+    // explicitly enabled
+    run_test_cli(vec!["fmt", "--incremental"], &environment).unwrap();
+    run_test_cli(vec!["fmt", "--incremental", "--verbose"], &environment).unwrap();
+    assert_eq!(environment.take_logged_errors().iter().any(|msg| msg.contains("No change: /file1.txt")), true);
+    environment.clear_logs();
+  }
+
+  #[test]
+  fn it_should_check_incrementally_with_incremental_cache_path() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_includes("**/*.txt");
+      })
+      .write_file("/file1.txt", "text1_formatted")
+      .build();
+    environment.set_ci(true);
+
+    run_test_cli(vec!["check", "--incremental-cache-path", "artifacts/dprint.incremental"], &environment).unwrap();
+    assert_eq!(environment.path_exists("/artifacts/dprint.incremental"), true);
+    environment.clear_logs();
+    run_test_cli(vec!["check", "--incremental-cache-path", "artifacts/dprint.incremental", "--verbose"], &environment).unwrap();
+    assert_eq!(environment.take_logged_errors().iter().any(|msg| msg.contains("No change: /file1.txt")), true);
+
+    // changed files are checked again
+    environment.write_file("/file1.txt", "text1").unwrap();
+    environment.clear_logs();
+    let error_message = run_test_cli(vec!["check", "--incremental-cache-path", "artifacts/dprint.incremental"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), get_singular_check_text());
+  }
+
+  #[test]
+  fn it_should_warn_when_formatting_files_in_ci() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_includes("**/*.txt");
+      })
+      .write_file("/file1.txt", "text1")
+      .write_file("/file2.txt", "text2_formatted")
+      .write_file("/file3.txt", "text3")
+      .build();
+    environment.set_ci(true);
+    let get_warning_text = || {
+      format!(
+        "Warning: Formatted {} file in a CI environment. Files should already be formatted when committed (specify --warnings-as-errors to fail when this happens).",
+        "1".bold().to_string()
+      )
+    };
+
+    // fails when warnings are treated as errors
+    let error_message = run_test_cli(vec!["fmt", "--warnings-as-errors", "/file1.txt"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), format!("Had {} warning(s), which are treated as errors.", "1".bold().to_string()));
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors(), vec![get_warning_text()]);
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text1_formatted");
+
+    run_test_cli(vec!["fmt", "/file3.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors(), vec![get_warning_text()]);
+    assert_eq!(environment.read_file("/file3.txt").unwrap(), "text3_formatted");
+
+    // no warning when nothing was formatted
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_stop_formatting_when_interrupted() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_includes("**/*.txt");
+      })
+      .write_file("/file1.txt", "text1")
+      .write_file("/file2.txt", "text2")
+      .build();
+    environment.set_interrupted(true);
+
+    let error_message = run_test_cli(vec!["fmt"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), "Interrupted after processing 0 of 2 files.");
+    assert_eq!(environment.take_logged_messages().len(), 0);
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text1");
+    assert_eq!(environment.read_file("/file2.txt").unwrap(), "text2");
+  }
+
+  #[test]
+  fn it_should_write_incremental_file_when_interrupted() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_includes("**/*.txt");
+      })
+      .write_file("/file1.txt", "text1")
+      .build();
+    environment.set_interrupted(true);
+
+    let error_message = run_test_cli(vec!["fmt", "--incremental-cache-path", "artifacts/dprint.incremental"], &environment)
+      .err()
+      .unwrap();
+    assert_eq!(error_message.to_string(), "Interrupted after processing 0 of 1 file.");
+    assert_eq!(environment.path_exists("/artifacts/dprint.incremental"), true);
+  }
+
+  #[test]
+  fn it_should_log_plugin_selection_reason_when_verbose() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "text_formatted")
+      .write_file("/file.md", "text")
+      .build();
+    run_test_cli(vec!["fmt", "--verbose", "/file.txt", "/file.md"], &environment).unwrap();
+    let logged_errors = environment.take_logged_errors();
+    assert!(logged_errors
+      .iter()
+      .any(|msg| msg == "[VERBOSE]: Using test-plugin for /file.txt (matched file extension 'txt')"));
+    assert!(logged_errors.iter().any(|msg| msg == "[VERBOSE]: No plugin matched /file.md"));
+  }
+
+  #[test]
+  fn it_should_log_phase_timings_when_verbose() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .write_file("/file.txt", "text")
+      .build();
+    run_test_cli(vec!["fmt", "--verbose", "/file.txt"], &environment).unwrap();
+    let logged_errors = environment.take_logged_errors();
+    let phase_timings_text = logged_errors.iter().find(|msg| msg.starts_with("[VERBOSE]: Phase timings:\n")).unwrap();
+    for phase_name in ["config resolution", "file globbing", "formatting", "writing"].iter() {
+      assert!(phase_timings_text.contains(&format!("\n  {}: ", phase_name)), "Missing {}", phase_name);
+    }
+  }
+
+  #[test]
+  fn it_should_use_result_cache_when_specified_via_config() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file(
+        "./dprint.json",
+        r#"{
+            "experimentalResultCache": true,
+            "includes": ["**/*.txt"],
+            "plugins": ["https://plugins.dprint.dev/test-plugin.wasm"]
+        }"#,
+      )
+      .write_file("/file1.txt", "text1")
+      .build();
+
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(environment.read_file("/file1.txt").unwrap(), "text1_formatted");
+
+    environment.write_file("/file2.txt", "text1").unwrap();
+    run_test_cli(vec!["fmt", "--verbose", "/file2.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(environment.take_logged_errors().iter().any(|msg| msg.contains("Cached result: /file2.txt")), true);
+    assert_eq!(environment.read_file("/file2.txt").unwrap(), "text1_formatted");
+  }
+
+  #[test]
+  fn it_should_replay_plugin_io() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "text")
+      .build();
+    run_test_cli(vec!["fmt", "/file.txt", "--debug-plugin-io", "/debug"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Writing plugin requests and responses to /debug. These contain the text of the formatted files."]
+    );
+
+    run_test_cli(vec!["plugin", "replay", "/debug/test-plugin"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["Replayed 1 request(s) with no differences."]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+
+    environment
+      .write_file("/debug/test-plugin/000000.response.json", r#"{ "formattedText": "other" }"#)
+      .unwrap();
+    let error_message = run_test_cli(vec!["plugin", "replay", "/debug/test-plugin"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), "Found 1 differing response(s) out of 1 request(s).");
+    let logged_messages = environment.take_logged_messages();
+    assert_eq!(logged_messages.len(), 1);
+    assert!(logged_messages[0].starts_with("Response differed for request 0 (/file.txt):\n"));
+  }
+
   #[test]
   fn it_should_not_output_when_no_files_need_formatting() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
@@ -1533,6 +3445,164 @@ mod tests {
     assert_eq!(environment.take_logged_errors().len(), 0);
   }
 
+  #[test]
+  fn it_should_output_json_for_check() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_config_section("mixedLineEndings", "\"report\"");
+      })
+      .write_file("/file.txt", "const t=4;")
+      .write_file("/file2.txt", "text_formatted")
+      .write_file("/file3.txt", "a\r\nb\n")
+      .build();
+    let error_message = run_test_cli(vec!["check", "--output-format", "json", "**/*.txt"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), get_plural_check_text(2));
+    let logged_values = take_logged_json_values_without_durations(&environment);
+    assert_eq!(logged_values.len(), 3);
+    assert!(logged_values.contains(&json!({ "kind": "notFormatted", "filePath": "/file.txt", "line": 1, "column": 11 })));
+    assert!(logged_values.contains(&json!({ "kind": "mixedLineEndings", "filePath": "/file3.txt", "crlf": 1, "lf": 1 })));
+    assert_eq!(
+      logged_values[2],
+      json!({
+        "kind": "checkSummary",
+        "startTime": "1970-01-02T10:17:36Z",
+        "endTime": "1970-01-02T10:17:36Z",
+        "checkedFiles": 3,
+        "notFormattedFiles": 2,
+      })
+    );
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_output_json_for_fmt() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "text")
+      .write_file("/file2.txt", "should_error")
+      .build();
+    let error_message = run_test_cli(vec!["fmt", "--output-format", "json", "**/*.txt"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), "Had 1 error(s) formatting.");
+    let logged_values = take_logged_json_values_without_durations(&environment);
+    assert_eq!(logged_values.len(), 3);
+    assert!(logged_values.contains(&json!({ "kind": "formatted", "filePath": "/file.txt" })));
+    assert!(logged_values.contains(&json!({
+      "kind": "error",
+      "filePath": "/file2.txt",
+      "message": "Did error.",
+      "plugin": "test-plugin",
+      "pluginVersion": "0.1.0",
+      "helpUrl": "https://dprint.dev/plugins/test",
+      "fileHash": "91777ec7845e",
+    })));
+    assert_eq!(
+      logged_values[2],
+      json!({
+        "kind": "fmtSummary",
+        "startTime": "1970-01-02T10:17:36Z",
+        "endTime": "1970-01-02T10:17:36Z",
+        "formattedFiles": 1,
+      })
+    );
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_write_change_log_for_fmt() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "text")
+      .write_file("/file2.txt", "text_formatted")
+      .build();
+    run_test_cli(vec!["fmt", "--change-log", "logs/changes.json", "/file.txt", "/file2.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    let mut change_log: Value = serde_json::from_str(&environment.read_file("/logs/changes.json").unwrap()).unwrap();
+    // the duration varies between runs
+    assert!(change_log["files"][0]["durationMs"].take().is_u64());
+    assert_eq!(
+      change_log,
+      json!({
+        "files": [{
+          "filePath": "/file.txt",
+          "plugin": "test-plugin",
+          "bytesBefore": 4,
+          "bytesAfter": 14,
+          "durationMs": null,
+          "hashBefore": dprint_cli_core::checksums::get_sha256_checksum(b"text"),
+          "hashAfter": dprint_cli_core::checksums::get_sha256_checksum(b"text_formatted"),
+        }]
+      })
+    );
+  }
+
+  #[test]
+  fn it_should_write_profile_for_fmt() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "text")
+      .build();
+    run_test_cli(vec!["fmt", "--profile", "logs/profile.json", "/file.txt"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec![get_singular_formatted_text()]);
+    let profile: Value = serde_json::from_str(&environment.read_file("/logs/profile.json").unwrap()).unwrap();
+    assert_eq!(profile["$schema"], "https://www.speedscope.app/file-format-schema.json");
+    let frame_names = profile["shared"]["frames"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|frame| frame["name"].as_str().unwrap())
+      .collect::<Vec<_>>();
+    let expected_names = [
+      "config resolution",
+      "file globbing",
+      "formatting",
+      "/file.txt",
+      "format (test-plugin)",
+      "writing",
+    ];
+    for name in expected_names.iter() {
+      assert!(frame_names.contains(name), "Missing {}", name);
+    }
+    for profile in profile["profiles"].as_array().unwrap() {
+      let events = profile["events"].as_array().unwrap();
+      let open_count = events.iter().filter(|event| event["type"] == "O").count();
+      assert_eq!(open_count * 2, events.len());
+    }
+  }
+
+  #[test]
+  fn it_should_output_json_for_format_times() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "text")
+      .build();
+    run_test_cli(vec!["output-format-times", "--output-format", "json", "**/*.txt"], &environment).unwrap();
+    let logged_values = take_logged_json_values_without_durations(&environment);
+    assert_eq!(
+      logged_values,
+      vec![
+        json!({ "kind": "formatTime", "filePath": "/file.txt" }),
+        json!({
+          "kind": "formatTimesSummary",
+          "startTime": "1970-01-02T10:17:36Z",
+          "endTime": "1970-01-02T10:17:36Z",
+          "files": 1,
+        }),
+      ]
+    );
+  }
+
+  fn take_logged_json_values(environment: &TestEnvironment) -> Vec<Value> {
+    let logged_messages = environment.take_logged_messages();
+    logged_messages.iter().map(|message| serde_json::from_str(message).unwrap()).collect()
+  }
+
+  /// Takes the logged JSON values removing the durations, which vary between runs.
+  fn take_logged_json_values_without_durations(environment: &TestEnvironment) -> Vec<Value> {
+    let mut logged_values = take_logged_json_values(environment);
+    for value in logged_values.iter_mut() {
+      if value["kind"] != "error" {
+        assert!(value.as_object_mut().unwrap().remove("durationMs").unwrap().is_u64());
+      }
+    }
+    logged_values
+  }
+
   #[test]
   fn it_should_output_when_a_file_need_formatting_for_check() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
@@ -1544,13 +3614,31 @@ mod tests {
       environment.take_logged_messages(),
       vec![format!(
         "{}\n{}\n--",
-        format!("{} /file.txt:", "from".bold().red().to_string()),
+        format!("{} /file.txt:1:11:", "from".bold().red().to_string()),
         get_difference("const t=4;", "const t=4;_formatted").unwrap(),
       ),]
     );
     assert_eq!(environment.take_logged_errors().len(), 0);
   }
 
+  #[test]
+  fn it_should_write_badge_for_check() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file1.txt", "const t=4;")
+      .write_file("/file2.txt", "text_formatted")
+      .build();
+    let error_message = run_test_cli(vec!["check", "--badge", "badges/formatting.svg", "/file1.txt", "/file2.txt"], &environment)
+      .err()
+      .unwrap();
+    assert_eq!(error_message.to_string(), get_singular_check_text());
+    environment.take_logged_messages();
+    assert!(environment.read_file("/badges/formatting.svg").unwrap().contains("formatting: 50% clean"));
+    assert_eq!(
+      environment.read_file("/badges/formatting.json").unwrap(),
+      "{\n  \"checkedFiles\": 2,\n  \"notFormattedFiles\": 1,\n  \"cleanPercentage\": 50\n}"
+    );
+  }
+
   #[test]
   fn it_should_output_when_files_need_formatting_for_check() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
@@ -1563,25 +3651,243 @@ mod tests {
     let mut logged_messages = environment.take_logged_messages();
     logged_messages.sort(); // seems like the order is not deterministic
     assert_eq!(
-      logged_messages,
-      vec![
-        format!(
-          "{}\n{}\n--",
-          format!("{} /file1.txt:", "from".bold().red().to_string()),
-          get_difference("const t=4;", "const t=4;_formatted").unwrap(),
-        ),
-        format!(
-          "{}\n{}\n--",
-          format!("{} /file2.txt:", "from".bold().red().to_string()),
-          get_difference("const t=5;", "const t=5;_formatted").unwrap(),
-        ),
-      ]
+      logged_messages,
+      vec![
+        format!(
+          "{}\n{}\n--",
+          format!("{} /file1.txt:1:11:", "from".bold().red().to_string()),
+          get_difference("const t=4;", "const t=4;_formatted").unwrap(),
+        ),
+        format!(
+          "{}\n{}\n--",
+          format!("{} /file2.txt:1:11:", "from".bold().red().to_string()),
+          get_difference("const t=5;", "const t=5;_formatted").unwrap(),
+        ),
+      ]
+    );
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_initialize() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      crate::plugins::REMOTE_INFO_URL,
+      r#"{
+            "schemaVersion": 3,
+            "pluginSystemSchemaVersion": 3,
+            "latest": [{
+                "name": "dprint-plugin-typescript",
+                "version": "0.17.2",
+                "url": "https://plugins.dprint.dev/typescript-0.17.2.wasm",
+                "fileExtensions": ["ts"],
+                "configKey": "typescript",
+                "configExcludes": []
+            }, {
+                "name": "dprint-plugin-jsonc",
+                "version": "0.2.3",
+                "url": "https://plugins.dprint.dev/json-0.2.3.wasm",
+                "fileExtensions": ["json"],
+                "fileNames": [],
+                "configKey": "json",
+                "configExcludes": []
+            }]
+        }"#
+        .as_bytes(),
+    );
+    let expected_text = get_init_config_file(&environment).unwrap().text;
+    environment.clear_logs();
+    run_test_cli(vec!["init"], &environment).unwrap();
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Select plugins (use the spacebar to select/deselect and then press enter when finished):"]
+    );
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec![
+        "\nCreated ./dprint.json",
+        "\nIf you are working in a commercial environment please consider sponsoring dprint: https://dprint.dev/sponsor"
+      ]
+    );
+    assert_eq!(environment.read_file("./dprint.json").unwrap(), expected_text);
+  }
+
+  #[test]
+  fn it_should_error_initializing_in_ci() {
+    let environment = TestEnvironment::new();
+    environment.set_ci(true);
+    let error_message = run_test_cli(vec!["init"], &environment).err().unwrap();
+    assert_eq!(
+      error_message.to_string(),
+      concat!(
+        "Cannot create a configuration file in a CI environment because it requires selecting plugins interactively. ",
+        "Run `dprint init` locally and commit the file or create it manually (see https://dprint.dev/config)."
+      )
+    );
+    assert_eq!(environment.path_exists("./dprint.json"), false);
+  }
+
+  #[test]
+  fn it_should_initialize_with_specified_config_path() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      crate::plugins::REMOTE_INFO_URL,
+      r#"{
+            "schemaVersion": 3,
+            "pluginSystemSchemaVersion": 3,
+            "latest": [{
+                "name": "dprint-plugin-typescript",
+                "version": "0.17.2",
+                "url": "https://plugins.dprint.dev/typescript-0.17.2.wasm",
+                "fileExtensions": ["json"],
+                "configKey": "typescript",
+                "configExcludes": []
+            }]
+        }"#
+        .as_bytes(),
+    );
+    let expected_text = get_init_config_file(&environment).unwrap().text;
+    environment.clear_logs();
+    run_test_cli(vec!["init", "--config", "./test.config.json"], &environment).unwrap();
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Select plugins (use the spacebar to select/deselect and then press enter when finished):"]
+    );
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec![
+        "\nCreated ./test.config.json",
+        "\nIf you are working in a commercial environment please consider sponsoring dprint: https://dprint.dev/sponsor"
+      ]
+    );
+    assert_eq!(environment.read_file("./test.config.json").unwrap(), expected_text);
+  }
+
+  #[test]
+  fn it_should_initialize_with_vscode_settings() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      crate::plugins::REMOTE_INFO_URL,
+      r#"{
+            "schemaVersion": 3,
+            "pluginSystemSchemaVersion": 3,
+            "latest": [{
+                "name": "dprint-plugin-typescript",
+                "version": "0.17.2",
+                "url": "https://plugins.dprint.dev/typescript-0.17.2.wasm",
+                "fileExtensions": ["ts"],
+                "configKey": "typescript",
+                "configExcludes": []
+            }]
+        }"#
+        .as_bytes(),
+    );
+    run_test_cli(vec!["init", "--vscode"], &environment).unwrap();
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Select plugins (use the spacebar to select/deselect and then press enter when finished):"]
+    );
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec![
+        "\nCreated ./dprint.json",
+        "Updated ./.vscode/settings.json",
+        "Updated ./.vscode/extensions.json",
+        "\nIf you are working in a commercial environment please consider sponsoring dprint: https://dprint.dev/sponsor"
+      ]
+    );
+    assert_eq!(
+      environment.read_file("./.vscode/settings.json").unwrap(),
+      r#"{
+  "[typescript]": {
+    "editor.defaultFormatter": "dprint.dprint"
+  }
+}
+"#
+    );
+    assert_eq!(
+      environment.read_file("./.vscode/extensions.json").unwrap(),
+      r#"{
+  "recommendations": [
+    "dprint.dprint"
+  ]
+}
+"#
+    );
+  }
+
+  #[test]
+  fn it_should_initialize_from_template() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://dprint.dev/templates/org.json",
+      r#"{
+  "lineWidth": {{lineWidth:120}},
+  "plugins": ["https://plugins.dprint.dev/typescript-0.17.2.wasm"]
+}"#
+        .as_bytes(),
+    );
+    environment.set_text_input_results(vec!["80"]);
+    run_test_cli(vec!["init", "--template", "https://dprint.dev/templates/org.json"], &environment).unwrap();
+    assert_eq!(environment.take_logged_errors(), vec!["Enter a value for lineWidth (default: 120):"]);
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec![
+        "\nCreated ./dprint.json",
+        "\nIf you are working in a commercial environment please consider sponsoring dprint: https://dprint.dev/sponsor"
+      ]
+    );
+    assert_eq!(
+      environment.read_file("./dprint.json").unwrap(),
+      "{\n  \"lineWidth\": 80,\n  \"plugins\": [\"https://plugins.dprint.dev/typescript-0.17.2.wasm\"]\n}"
+    );
+  }
+
+  #[test]
+  fn it_should_error_when_config_file_exists_on_initialize() {
+    let environment = TestEnvironmentBuilder::new()
+      .with_default_config(|c| {
+        c.add_includes("**/*.txt");
+      })
+      .build();
+    let error_message = run_test_cli(vec!["init"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), "Configuration file './dprint.json' already exists.");
+  }
+
+  #[test]
+  fn it_should_setup_hooks_with_specified_framework() {
+    let environment = TestEnvironment::new();
+    run_test_cli(vec!["setup-hooks", "--framework", "lefthook"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["Updated ./lefthook.yml to run `dprint fmt --staged`."]);
+    assert_eq!(
+      environment.read_file("./lefthook.yml").unwrap(),
+      "pre-commit:\n  commands:\n    dprint:\n      run: dprint fmt --staged\n"
+    );
+  }
+
+  #[test]
+  fn it_should_error_output_trace_when_plugin_does_not_support_tracing() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.txt", "text")
+      .build();
+    let error_message = run_test_cli(vec!["output-trace", "file.txt"], &environment).err().unwrap();
+    assert_eq!(
+      error_message.to_string(),
+      "Error tracing /file.txt with test-plugin. This plugin does not support tracing."
     );
-    assert_eq!(environment.take_logged_errors().len(), 0);
   }
 
   #[test]
-  fn it_should_initialize() {
+  fn it_should_error_output_trace_when_no_plugin_for_file() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .write_file("/file.other", "text")
+      .build();
+    let error_message = run_test_cli(vec!["output-trace", "file.other"], &environment).err().unwrap();
+    assert_eq!(error_message.to_string(), "Could not find a plugin to format /file.other");
+  }
+
+  #[test]
+  fn it_should_upgrade_plugins() {
     let environment = TestEnvironment::new();
     environment.add_remote_file(
       crate::plugins::REMOTE_INFO_URL,
@@ -1590,8 +3896,8 @@ mod tests {
             "pluginSystemSchemaVersion": 3,
             "latest": [{
                 "name": "dprint-plugin-typescript",
-                "version": "0.17.2",
-                "url": "https://plugins.dprint.dev/typescript-0.17.2.wasm",
+                "version": "0.19.0",
+                "url": "https://plugins.dprint.dev/typescript-0.19.0.wasm",
                 "fileExtensions": ["ts"],
                 "configKey": "typescript",
                 "configExcludes": []
@@ -1600,75 +3906,95 @@ mod tests {
                 "version": "0.2.3",
                 "url": "https://plugins.dprint.dev/json-0.2.3.wasm",
                 "fileExtensions": ["json"],
-                "fileNames": [],
                 "configKey": "json",
                 "configExcludes": []
+            }, {
+                "name": "dprint-plugin-process",
+                "version": "0.2.0",
+                "url": "https://plugins.dprint.dev/process-0.2.0.exe-plugin",
+                "fileExtensions": ["txt"],
+                "configKey": "process",
+                "configExcludes": [],
+                "checksum": "new-checksum"
             }]
         }"#
         .as_bytes(),
     );
-    let expected_text = get_init_config_file_text(&environment).unwrap();
-    environment.clear_logs();
-    run_test_cli(vec!["init"], &environment).unwrap();
-    assert_eq!(
-      environment.take_logged_errors(),
-      vec!["Select plugins (use the spacebar to select/deselect and then press enter when finished):"]
-    );
+    environment
+      .write_file(
+        "/dprint.json",
+        r#"{
+  // the plugins
+  "plugins": [
+    "https://plugins.dprint.dev/typescript-0.17.2.wasm",
+    "https://plugins.dprint.dev/json-0.2.3.wasm", // latest
+    "https://plugins.dprint.dev/process-0.1.0.exe-plugin@old-checksum",
+    "https://example.com/other-1.0.0.wasm"
+  ]
+}"#,
+      )
+      .unwrap();
+    run_test_cli(vec!["upgrade-plugins"], &environment).unwrap();
     assert_eq!(
       environment.take_logged_messages(),
       vec![
-        "\nCreated ./dprint.json",
-        "\nIf you are working in a commercial environment please consider sponsoring dprint: https://dprint.dev/sponsor"
+        "Upgraded dprint-plugin-typescript from 0.17.2 to 0.19.0",
+        "Upgraded dprint-plugin-process from 0.1.0 to 0.2.0",
+        "\nUpdated /dprint.json",
       ]
     );
-    assert_eq!(environment.read_file("./dprint.json").unwrap(), expected_text);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+    assert_eq!(
+      environment.read_file("/dprint.json").unwrap(),
+      r#"{
+  // the plugins
+  "plugins": [
+    "https://plugins.dprint.dev/typescript-0.19.0.wasm",
+    "https://plugins.dprint.dev/json-0.2.3.wasm", // latest
+    "https://plugins.dprint.dev/process-0.2.0.exe-plugin@new-checksum",
+    "https://example.com/other-1.0.0.wasm"
+  ]
+}"#
+    );
+
+    // running again does nothing
+    run_test_cli(vec!["upgrade-plugins"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["All plugins are up to date."]);
   }
 
   #[test]
-  fn it_should_initialize_with_specified_config_path() {
+  fn it_should_normalize_config_file() {
     let environment = TestEnvironment::new();
-    environment.add_remote_file(
-      crate::plugins::REMOTE_INFO_URL,
-      r#"{
-            "schemaVersion": 3,
-            "pluginSystemSchemaVersion": 3,
-            "latest": [{
-                "name": "dprint-plugin-typescript",
-                "version": "0.17.2",
-                "url": "https://plugins.dprint.dev/typescript-0.17.2.wasm",
-                "fileExtensions": ["json"],
-                "configKey": "typescript",
-                "configExcludes": []
-            }]
-        }"#
-        .as_bytes(),
-    );
-    let expected_text = get_init_config_file_text(&environment).unwrap();
-    environment.clear_logs();
-    run_test_cli(vec!["init", "--config", "./test.config.json"], &environment).unwrap();
-    assert_eq!(
-      environment.take_logged_errors(),
-      vec!["Select plugins (use the spacebar to select/deselect and then press enter when finished):"]
-    );
+    environment
+      .write_file(
+        "/dprint.json",
+        r#"{
+  "includes": ["**/*.ts", "**/*.ts"],
+  "plugins": [
+    "https://plugins.dprint.dev/typescript-0.19.0.wasm",
+    "https://plugins.dprint.dev/json-0.2.3.wasm"
+  ]
+}"#,
+      )
+      .unwrap();
+    run_test_cli(vec!["config", "normalize"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["Normalized /dprint.json"]);
     assert_eq!(
-      environment.take_logged_messages(),
-      vec![
-        "\nCreated ./test.config.json",
-        "\nIf you are working in a commercial environment please consider sponsoring dprint: https://dprint.dev/sponsor"
-      ]
+      environment.read_file("/dprint.json").unwrap(),
+      r#"{
+  "includes": [
+    "**/*.ts"
+  ],
+  "plugins": [
+    "https://plugins.dprint.dev/json-0.2.3.wasm",
+    "https://plugins.dprint.dev/typescript-0.19.0.wasm"
+  ]
+}"#
     );
-    assert_eq!(environment.read_file("./test.config.json").unwrap(), expected_text);
-  }
 
-  #[test]
-  fn it_should_error_when_config_file_exists_on_initialize() {
-    let environment = TestEnvironmentBuilder::new()
-      .with_default_config(|c| {
-        c.add_includes("**/*.txt");
-      })
-      .build();
-    let error_message = run_test_cli(vec!["init"], &environment).err().unwrap();
-    assert_eq!(error_message.to_string(), "Configuration file './dprint.json' already exists.");
+    // running again does nothing
+    run_test_cli(vec!["config", "normalize"], &environment).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["The configuration file is already normalized."]);
   }
 
   #[test]
@@ -1691,6 +4017,21 @@ mod tests {
     assert_eq!(environment.read_file(&file_path).unwrap(), "\u{FEFF}text_formatted");
   }
 
+  #[test]
+  fn it_should_get_if_file_was_modified_while_formatting() {
+    let file_path = Path::new("/file.txt");
+    let environment = TestEnvironmentBuilder::new().write_file(&file_path, "text").build();
+    assert_eq!(super::was_file_modified(&environment, &file_path, "text", false, TextEncoding::Utf8), false);
+    assert_eq!(super::was_file_modified(&environment, &file_path, "text", true, TextEncoding::Utf8), true);
+    assert_eq!(super::was_file_modified(&environment, &file_path, "other", false, TextEncoding::Utf8), true);
+    environment.write_file(&file_path, "\u{FEFF}text").unwrap();
+    assert_eq!(super::was_file_modified(&environment, &file_path, "text", true, TextEncoding::Utf8), false);
+    assert_eq!(super::was_file_modified(&environment, &file_path, "text", false, TextEncoding::Utf8), true);
+    // deleted
+    environment.remove_file(&file_path).unwrap();
+    assert_eq!(super::was_file_modified(&environment, &file_path, "text", false, TextEncoding::Utf8), true);
+  }
+
   #[test]
   fn it_should_output_license_for_sub_command_with_no_plugins() {
     let environment = TestEnvironment::new();
@@ -1798,6 +4139,13 @@ SOFTWARE.
       }
     }
 
+    pub fn check_formatted(&mut self, file_path: &Path, file_hash: &str) -> Result<bool, ErrBox> {
+      self.messenger.send_message(3, vec![file_path.into(), file_hash.into()])?;
+      let response_code = self.messenger.read_code()?;
+      self.messenger.read_zero_part_message()?;
+      Ok(response_code == 1)
+    }
+
     pub fn exit(&mut self) {
       self.messenger.send_message(0, vec![]).unwrap();
     }
@@ -1887,6 +4235,231 @@ SOFTWARE.
     result.join().unwrap();
   }
 
+  #[test]
+  fn it_should_check_formatted_with_incremental_file_for_editor_service() {
+    let file_path = PathBuf::from("/file.txt");
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_includes("**/*.txt").set_incremental(true);
+      })
+      .write_file(&file_path, "text")
+      .build();
+    run_test_cli(vec!["fmt"], &environment).unwrap();
+    environment.clear_logs();
+    let stdin = environment.stdin_writer();
+    let stdout = environment.stdout_reader();
+
+    let result = std::thread::spawn(move || {
+      let mut communicator = EditorServiceCommunicator::new(stdin, stdout);
+      let formatted_hash = get_bytes_hash("text_formatted".as_bytes()).to_string();
+      let other_hash = get_bytes_hash("text".as_bytes()).to_string();
+      assert_eq!(communicator.check_formatted(&file_path, &formatted_hash).unwrap(), true);
+      assert_eq!(communicator.check_formatted(&file_path, &other_hash).unwrap(), false);
+      assert_eq!(communicator.check_formatted(&PathBuf::from("/other.txt"), &formatted_hash).unwrap(), false);
+      assert_eq!(communicator.check_formatted(&file_path, "invalid").unwrap(), false);
+      communicator.exit();
+    });
+
+    let pid = std::process::id().to_string();
+    run_test_cli(vec!["editor-service", "--parent-pid", &pid], &environment).unwrap();
+
+    result.join().unwrap();
+    assert_eq!(environment.take_logged_errors(), vec!["Expected the file hash to be a u64, but found: invalid"]);
+  }
+
+  #[test]
+  fn it_should_trace_editor_service_protocol() {
+    let txt_file_path = PathBuf::from("/file.txt");
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_includes("**/*.txt");
+      })
+      .write_file(&txt_file_path, "")
+      .build();
+    let stdin = environment.stdin_writer();
+    let stdout = environment.stdout_reader();
+
+    let result = std::thread::spawn(move || {
+      let mut communicator = EditorServiceCommunicator::new(stdin, stdout);
+      assert_eq!(communicator.check_file(&txt_file_path).unwrap(), true);
+      assert_eq!(communicator.format_text(&txt_file_path, "testing").unwrap().unwrap(), "testing_formatted");
+      communicator.exit();
+    });
+
+    let pid = std::process::id().to_string();
+    run_test_cli(vec!["editor-service", "--parent-pid", &pid, "--trace-protocol", "trace.log"], &environment).unwrap();
+    result.join().unwrap();
+
+    let trace_text = environment.read_file("/trace.log").unwrap();
+    let lines = trace_text.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], "[123456] --> 1 (check path) \"/file.txt\"");
+    assert_eq!(lines[1].starts_with("[123456] <-- 1 in "), true);
+    assert_eq!(lines[2], "[123456] --> 2 (format) \"/file.txt testing\"");
+    assert_eq!(lines[3].starts_with("[123456] <-- 1 in "), true);
+    assert_eq!(lines[3].ends_with("ms \"testing_formatted\""), true);
+    assert_eq!(lines[4], "[123456] --> 0 (shutdown) \"\"");
+  }
+
+  #[test]
+  fn it_should_use_editor_service_snapshot_when_restarted() {
+    let txt_file_path = PathBuf::from("/file.txt");
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_includes("**/*.txt");
+      })
+      .write_file(&txt_file_path, "")
+      .build();
+    let run_editor_service = |environment: &TestEnvironment| {
+      let stdin = environment.stdin_writer();
+      let stdout = environment.stdout_reader();
+      let result = std::thread::spawn({
+        let txt_file_path = txt_file_path.clone();
+        move || {
+          let mut communicator = EditorServiceCommunicator::new(stdin, stdout);
+          assert_eq!(communicator.check_file(&txt_file_path).unwrap(), true);
+          assert_eq!(communicator.format_text(&txt_file_path, "testing").unwrap().unwrap(), "testing_formatted");
+          communicator.exit();
+        }
+      });
+      let pid = std::process::id().to_string();
+      run_test_cli(vec!["editor-service", "--parent-pid", &pid, "--verbose"], environment).unwrap();
+      result.join().unwrap();
+      environment
+        .take_logged_errors()
+        .iter()
+        .any(|msg| msg.contains("Resolved plugins from editor service snapshot."))
+    };
+
+    assert_eq!(run_editor_service(&environment), false);
+    assert_eq!(run_editor_service(&environment), true);
+
+    // changing the plugins should invalidate the snapshot
+    let checksum = test_helpers::get_test_process_plugin_checksum(&environment);
+    environment
+      .write_file(
+        &PathBuf::from("/dprint.json"),
+        &format!(
+          r#"{{
+            "includes": ["**/*.txt"],
+            "plugins": [
+              "https://plugins.dprint.dev/test-plugin.wasm",
+              "https://plugins.dprint.dev/test-process.exe-plugin@{}"
+            ]
+          }}"#,
+          checksum
+        ),
+      )
+      .unwrap();
+    assert_eq!(run_editor_service(&environment), false);
+    assert_eq!(run_editor_service(&environment), true);
+  }
+
+  fn send_lsp_request(client: &mut LspTransport, id: u32, method: &str, params: Value) -> Value {
+    client
+      .send_message(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+      .unwrap();
+    let response = client.read_message().unwrap().unwrap();
+    assert_eq!(response["id"], id);
+    response
+  }
+
+  fn send_lsp_notification(client: &mut LspTransport, method: &str, params: Value) {
+    client.send_message(&json!({ "jsonrpc": "2.0", "method": method, "params": params })).unwrap();
+  }
+
+  #[test]
+  fn it_should_format_for_lsp() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_includes("**/*.txt").add_excludes("ignored.txt");
+      })
+      .build();
+    let stdin = environment.stdin_writer();
+    let stdout = environment.stdout_reader();
+
+    let result = std::thread::spawn(move || {
+      let mut client = LspTransport::new(stdout, stdin);
+      let response = send_lsp_request(&mut client, 1, "initialize", json!({ "processId": null, "capabilities": {} }));
+      assert_eq!(response["result"]["capabilities"]["documentFormattingProvider"], true);
+      assert_eq!(response["result"]["capabilities"]["documentRangeFormattingProvider"], true);
+      send_lsp_notification(&mut client, "initialized", json!({}));
+
+      let text_document = json!({ "uri": "file:///file.txt" });
+      send_lsp_notification(
+        &mut client,
+        "textDocument/didOpen",
+        json!({ "textDocument": { "uri": "file:///file.txt", "languageId": "plaintext", "version": 1, "text": "text" } }),
+      );
+      let response = send_lsp_request(
+        &mut client,
+        2,
+        "textDocument/formatting",
+        json!({ "textDocument": text_document, "options": {} }),
+      );
+      assert_eq!(
+        response["result"],
+        json!([{ "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 4 } }, "newText": "text_formatted" }])
+      );
+
+      // uses the latest text
+      send_lsp_notification(
+        &mut client,
+        "textDocument/didChange",
+        json!({ "textDocument": { "uri": "file:///file.txt", "version": 2 }, "contentChanges": [{ "text": "other" }] }),
+      );
+      let range = json!({ "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 5 } });
+      let response = send_lsp_request(
+        &mut client,
+        3,
+        "textDocument/rangeFormatting",
+        json!({ "textDocument": text_document, "range": range, "options": {} }),
+      );
+      assert_eq!(response["result"], json!([{ "range": range, "newText": "other_formatted" }]));
+
+      // the test plugin doesn't support formatting a portion of a file
+      let response = send_lsp_request(
+        &mut client,
+        4,
+        "textDocument/rangeFormatting",
+        json!({
+          "textDocument": text_document,
+          "range": { "start": { "line": 0, "character": 1 }, "end": { "line": 0, "character": 3 } },
+          "options": {},
+        }),
+      );
+      assert_eq!(
+        response["error"],
+        json!({ "code": -32803, "message": "The test-plugin plugin does not support formatting a range of a file." })
+      );
+
+      // excluded file
+      send_lsp_notification(
+        &mut client,
+        "textDocument/didOpen",
+        json!({ "textDocument": { "uri": "file:///ignored.txt", "languageId": "plaintext", "version": 1, "text": "text" } }),
+      );
+      let response = send_lsp_request(
+        &mut client,
+        5,
+        "textDocument/formatting",
+        json!({ "textDocument": { "uri": "file:///ignored.txt" }, "options": {} }),
+      );
+      assert_eq!(response["result"], json!([]));
+
+      let response = send_lsp_request(&mut client, 6, "textDocument/hover", json!({}));
+      assert_eq!(response["error"]["code"], -32601);
+
+      let response = send_lsp_request(&mut client, 7, "shutdown", Value::Null);
+      assert_eq!(response["result"], Value::Null);
+      send_lsp_notification(&mut client, "exit", Value::Null);
+    });
+
+    run_test_cli(vec!["lsp"], &environment).unwrap();
+    result.join().unwrap();
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
   #[test]
   fn it_should_format_for_stdin_fmt_with_file_name() {
     // it should not output anything when downloading plugins
@@ -1918,6 +4491,82 @@ SOFTWARE.
     assert_eq!(environment.take_logged_errors().len(), 0);
   }
 
+  #[test]
+  fn it_should_format_for_stdin_fmt_with_plugin_config() {
+    let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin().add_config_section("test-plugin", r#"{ "ending": "new_ending" }"#);
+      })
+      .build();
+
+    let test_std_in = TestStdInReader::new_with_text("text");
+    run_test_cli_with_stdin(
+      vec!["fmt", "--stdin", "txt", "--plugin-config", r#"{ "test-plugin": { "ending": "override" } }"#],
+      &environment,
+      test_std_in,
+    )
+    .unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["text_override"]);
+    assert_eq!(environment.take_logged_errors().len(), 0);
+  }
+
+  #[test]
+  fn it_should_format_for_stdin_fmt_with_range_of_entire_text() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .build();
+    let test_std_in = TestStdInReader::new_with_text("text");
+    run_test_cli_with_stdin(vec!["fmt", "--stdin", "txt", "--range", "0-4"], &environment, test_std_in).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["text_formatted"]);
+  }
+
+  #[test]
+  fn it_should_error_for_stdin_fmt_with_range_when_plugin_not_supports_partial_format() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .build();
+    let test_std_in = TestStdInReader::new_with_text("text");
+    let error_message = run_test_cli_with_stdin(vec!["fmt", "--stdin", "txt", "--range", "1-3"], &environment, test_std_in)
+      .err()
+      .unwrap();
+    assert_eq!(
+      error_message.to_string(),
+      "The test-plugin plugin does not support formatting a range of a file."
+    );
+  }
+
+  #[test]
+  fn it_should_error_for_stdin_fmt_with_invalid_range() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_remote_wasm_plugin();
+      })
+      .build();
+    let error_message = run_test_cli_with_stdin(
+      vec!["fmt", "--stdin", "txt", "--range", "4-1"],
+      &environment,
+      TestStdInReader::new_with_text("text"),
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+      error_message.to_string(),
+      "Invalid range '4-1'. Expected the format <start>-<end> where the start is less than or equal to the end (ex. 10-20)."
+    );
+    let error_message = run_test_cli_with_stdin(
+      vec!["fmt", "--stdin", "txt", "--range", "0-10"],
+      &environment,
+      TestStdInReader::new_with_text("text"),
+    )
+    .err()
+    .unwrap();
+    assert_eq!(error_message.to_string(), "The range 0-10 is not within the text, which is 4 bytes long.");
+  }
+
   #[test]
   fn it_should_stdin_fmt_calling_other_plugin() {
     let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin().build();
@@ -1966,6 +4615,69 @@ SOFTWARE.
     assert_eq!(environment.take_logged_messages(), vec!["text_formatted"]);
   }
 
+  #[test]
+  fn it_should_format_for_stdin_with_path_and_extension() {
+    let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
+      .with_default_config(|c| {
+        c.add_includes("/src/**.*").add_remote_wasm_plugin();
+      })
+      .write_file("/file.md", "")
+      .write_file("/src/file.md", "")
+      .build();
+    let test_std_in = TestStdInReader::new_with_text("text");
+    // not matching file path
+    run_test_cli_with_stdin(vec!["fmt", "--stdin", "/file.md", "--ext", "txt"], &environment, test_std_in.clone()).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["text"]);
+
+    // matching file path, but formatted based on the extension
+    run_test_cli_with_stdin(vec!["fmt", "--stdin", "/src/file.md", "--ext", ".txt"], &environment, test_std_in).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["text_formatted"]);
+  }
+
+  #[test]
+  fn it_should_format_multiple_files_for_stdin_json_fmt() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_and_process_plugin()
+      .with_default_config(|c| {
+        c.add_includes("/src/**.*").add_remote_wasm_plugin().add_remote_process_plugin();
+      })
+      .write_file("/file.txt", "")
+      .write_file("/src/file.txt", "")
+      .build();
+    let test_std_in = TestStdInReader::new_with_text(
+      r#"[
+        { "path": "file.txt", "text": "text1" },
+        { "path": "/file.txt", "text": "text2" },
+        { "path": "file.txt_ps", "text": "text3" },
+        { "path": "/src/file.txt", "text": "should_error" }
+      ]"#,
+    );
+    let error_message = run_test_cli_with_stdin(vec!["fmt", "--stdin-json"], &environment, test_std_in).err().unwrap();
+    assert_eq!(error_message.to_string(), "Error formatting 1 of the files provided to stdin.");
+    let logged_messages = environment.take_logged_messages();
+    assert_eq!(logged_messages.len(), 1);
+    assert_eq!(
+      serde_json::from_str::<Value>(&logged_messages[0]).unwrap(),
+      json!([
+        { "path": "file.txt", "text": "text1_formatted" },
+        // not matched by the includes
+        { "path": "/file.txt", "text": "text2" },
+        { "path": "file.txt_ps", "text": "text3_formatted_process" },
+        { "path": "/src/file.txt", "error": "Did error." },
+      ])
+    );
+  }
+
+  #[test]
+  fn it_should_error_for_stdin_json_fmt_with_invalid_json() {
+    let environment = TestEnvironmentBuilder::with_initialized_remote_wasm_plugin().build();
+    let test_std_in = TestStdInReader::new_with_text(r#"{ "path": "file.txt" }"#);
+    let error_message = run_test_cli_with_stdin(vec!["fmt", "--stdin-json"], &environment, test_std_in).err().unwrap();
+    assert!(error_message
+      .to_string()
+      .starts_with("Error parsing the JSON provided to stdin. Expected an array of objects with \"path\" and \"text\" properties."));
+    assert_eq!(environment.take_logged_messages().len(), 0);
+  }
+
   #[test]
   fn it_should_not_format_stdin_resolving_config_file_from_provided_path_when_relative() {
     let environment = TestEnvironmentBuilder::with_remote_wasm_plugin()
@@ -2206,27 +4918,45 @@ USAGE:
 
 SUBCOMMANDS:
     init                      Initializes a configuration file in the current directory.
+    setup-hooks               Sets up a git pre-commit hook that formats the staged files.
     fmt                       Formats the source files and writes the result to the file system.
     check                     Checks for any files that haven't been formatted.
+    check-config              Checks the configuration of each plugin for any diagnostics without formatting files.
+    lint-width                Reports the lines of the files that exceed the line width of the plugin formatting them.
     output-file-paths         Prints the resolved file paths for the plugins based on the args and configuration.
     output-resolved-config    Prints the resolved configuration for the plugins based on the args and configuration.
     output-format-times       Prints the amount of time it takes to format each file. Use this for debugging.
+    output-trace              Prints an HTML page visualizing how a plugin printed a file. Use this for debugging plugins.
     clear-cache               Deletes the plugin cache directory.
+    upgrade-plugins           Upgrades the plugins in the configuration file to their latest versions.
+    config                    Commands for maintaining the configuration file.
+    plugin                    Commands for debugging plugins.
     license                   Outputs the software license.
+    lsp                       Starts a Language Server Protocol server over stdio for formatting in editors.
 
 More details at `dprint help <SUBCOMMAND>`
 
 OPTIONS:
-    -c, --config <config>            Path or url to JSON configuration file. Defaults to dprint.json or .dprint.json in
-                                     current or ancestor directory when not provided.
-        --plugins <urls/files>...    List of urls or file paths of plugins to use. This overrides what is specified in
-                                     the config file.
-        --verbose                    Prints additional diagnostic information.
-    -v, --version                    Prints the version.
+    -c, --config <config>...             Path or url to JSON configuration file. Defaults to dprint.json or .dprint.json
+                                         in current or ancestor directory when not provided. Specify multiple times or
+                                         as a comma separated list to merge configuration files in order.
+        --plugins <urls/files>...        List of urls or file paths of plugins to use. This overrides what is specified
+                                         in the config file.
+        --verbose                        Prints additional diagnostic information.
+        --color <when>                   When to output colors. [possible values: always, auto, never]
+        --output-format <format>         How to output the results of check, fmt, and output-format-times. The json
+                                         format outputs a JSON object per line. [possible values: text, json]
+        --debug-plugin-io <directory>    Writes each plugin's format requests and responses to the specified directory.
+                                         Use this for debugging plugins. Note that this writes the text of the formatted
+                                         files.
+    -v, --version                        Prints the version.
 
 ENVIRONMENT VARIABLES:
-    DPRINT_CACHE_DIR    The directory to store the dprint cache. Note that
-                        this directory may be periodically deleted by the CLI.
+    DPRINT_CACHE_DIR      The directory to store the dprint cache. Note that
+                          this directory may be periodically deleted by the CLI.
+    DPRINT_MAX_THREADS    Limits the number of threads the CLI will use for
+                          formatting. Defaults to the number of available CPUs.
+    NO_COLOR              Disables colored output when `--color` is `auto`.
 
 GETTING STARTED:
     1. Navigate to the root directory of a code repository.
@@ -0,0 +1,342 @@
+use dprint_core::types::ErrBox;
+use std::path::PathBuf;
+
+use crate::environment::Environment;
+
+use super::{HookFramework, SetupHooksSubCommand};
+
+const HOOK_COMMAND: &'static str = "dprint fmt --staged";
+const LEFTHOOK_FILE_NAMES: [&'static str; 4] = ["lefthook.yml", ".lefthook.yml", "lefthook.yaml", ".lefthook.yaml"];
+
+pub fn setup_hooks(environment: &impl Environment, cmd: &SetupHooksSubCommand) -> Result<(), ErrBox> {
+  let framework = match cmd.framework {
+    Some(framework) => framework,
+    None => {
+      let detected_frameworks = detect_hook_frameworks(environment);
+      match detected_frameworks.len() {
+        0 => return err!("Could not detect a git hook manager in the current directory. Please specify one with --framework <husky|pre-commit|lefthook>."),
+        1 => detected_frameworks[0],
+        _ => {
+          return err!(
+            "Detected multiple git hook managers ({}). Please specify one with --framework <husky|pre-commit|lefthook>.",
+            detected_frameworks.iter().map(|f| f.name()).collect::<Vec<_>>().join(", ")
+          )
+        }
+      }
+    }
+  };
+
+  let file_path = match framework {
+    HookFramework::Husky => setup_husky(environment)?,
+    HookFramework::PreCommit => setup_pre_commit(environment)?,
+    HookFramework::Lefthook => setup_lefthook(environment)?,
+  };
+
+  match file_path {
+    Some(file_path) => environment.log(&format!("Updated {} to run `{}`.", file_path.display(), HOOK_COMMAND)),
+    None => environment.log(&format!("The {} pre-commit hook already runs `{}`.", framework.name(), HOOK_COMMAND)),
+  }
+
+  Ok(())
+}
+
+fn detect_hook_frameworks(environment: &impl Environment) -> Vec<HookFramework> {
+  let mut frameworks = Vec::new();
+  if is_husky_used(environment) {
+    frameworks.push(HookFramework::Husky);
+  }
+  if environment.path_exists(get_pre_commit_file_path()) {
+    frameworks.push(HookFramework::PreCommit);
+  }
+  if get_existing_lefthook_file_path(environment).is_some() {
+    frameworks.push(HookFramework::Lefthook);
+  }
+  frameworks
+}
+
+fn is_husky_used(environment: &impl Environment) -> bool {
+  if environment.path_exists("./.husky/_/husky.sh") || environment.path_exists(get_husky_file_path()) {
+    return true;
+  }
+
+  match environment.read_file("./package.json") {
+    Ok(text) => text.contains("\"husky\""),
+    Err(_) => false,
+  }
+}
+
+/// Adds the command to the .husky/pre-commit file and returns the path when modified.
+fn setup_husky(environment: &impl Environment) -> Result<Option<PathBuf>, ErrBox> {
+  let file_path = get_husky_file_path();
+  let file_text = if environment.path_exists(&file_path) {
+    let file_text = environment.read_file(&file_path)?;
+    if file_text.contains(HOOK_COMMAND) {
+      return Ok(None);
+    }
+    format!("{}{}\n", with_trailing_newline(file_text), HOOK_COMMAND)
+  } else {
+    environment.mk_dir_all(file_path.parent().unwrap())?;
+    environment.log(&format!(
+      "Note: Ensure {} is executable (ex. `chmod +x {}`).",
+      file_path.display(),
+      file_path.display()
+    ));
+    format!("#!/bin/sh\n. \"$(dirname \"$0\")/_/husky.sh\"\n\n{}\n", HOOK_COMMAND)
+  };
+  environment.write_file(&file_path, &file_text)?;
+  Ok(Some(file_path))
+}
+
+/// Adds a local hook to the .pre-commit-config.yaml file and returns the path when modified.
+fn setup_pre_commit(environment: &impl Environment) -> Result<Option<PathBuf>, ErrBox> {
+  let file_path = get_pre_commit_file_path();
+  let hook_text = format!(
+    "  - repo: local\n    hooks:\n      - id: dprint\n        name: dprint\n        entry: {}\n        language: system\n        pass_filenames: false\n",
+    HOOK_COMMAND
+  );
+  let file_text = if environment.path_exists(&file_path) {
+    let file_text = environment.read_file(&file_path)?;
+    if file_text.contains(HOOK_COMMAND) {
+      return Ok(None);
+    }
+    // only append when `repos` is the last top level key, otherwise the hook would end up under another key
+    if get_last_top_level_key(&file_text) != Some("repos") {
+      return err!(
+        "Could not update {}. Please add a local hook that runs `{}` to the repos manually.",
+        file_path.display(),
+        HOOK_COMMAND
+      );
+    }
+    format!("{}{}", with_trailing_newline(file_text), hook_text)
+  } else {
+    format!("repos:\n{}", hook_text)
+  };
+  environment.write_file(&file_path, &file_text)?;
+  Ok(Some(file_path))
+}
+
+/// Adds a pre-commit command to the lefthook configuration file and returns the path when modified.
+fn setup_lefthook(environment: &impl Environment) -> Result<Option<PathBuf>, ErrBox> {
+  let hook_text = format!("pre-commit:\n  commands:\n    dprint:\n      run: {}\n", HOOK_COMMAND);
+  let (file_path, file_text) = if let Some(file_path) = get_existing_lefthook_file_path(environment) {
+    let file_text = environment.read_file(&file_path)?;
+    if file_text.contains(HOOK_COMMAND) {
+      return Ok(None);
+    }
+    if file_text.lines().any(|line| line.trim_end() == "pre-commit:") {
+      return err!(
+        "Could not update {} because it already has a pre-commit hook. Please add a command that runs `{}` to it manually.",
+        file_path.display(),
+        HOOK_COMMAND
+      );
+    }
+    let file_text = format!("{}\n{}", with_trailing_newline(file_text), hook_text);
+    (file_path, file_text)
+  } else {
+    (PathBuf::from("./lefthook.yml"), hook_text)
+  };
+  environment.write_file(&file_path, &file_text)?;
+  Ok(Some(file_path))
+}
+
+fn get_husky_file_path() -> PathBuf {
+  PathBuf::from("./.husky/pre-commit")
+}
+
+fn get_pre_commit_file_path() -> PathBuf {
+  PathBuf::from("./.pre-commit-config.yaml")
+}
+
+fn get_existing_lefthook_file_path(environment: &impl Environment) -> Option<PathBuf> {
+  LEFTHOOK_FILE_NAMES
+    .iter()
+    .map(|file_name| PathBuf::from(format!("./{}", file_name)))
+    .find(|file_path| environment.path_exists(file_path))
+}
+
+fn get_last_top_level_key(yaml_text: &str) -> Option<&str> {
+  yaml_text
+    .lines()
+    .filter(|line| !line.is_empty() && !line.starts_with(char::is_whitespace) && !line.starts_with('#') && !line.starts_with('-'))
+    .last()
+    .and_then(|line| line.split(':').next())
+    .map(|key| key.trim())
+}
+
+fn with_trailing_newline(mut text: String) -> String {
+  if !text.is_empty() && !text.ends_with('\n') {
+    text.push('\n');
+  }
+  text
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::cli::{parse_args, SubCommand, TestStdInReader};
+  use crate::environment::TestEnvironment;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_use_hook_command_that_parses() {
+    // the first argument is the executable's name
+    let args = HOOK_COMMAND.split(' ').map(String::from).collect::<Vec<_>>();
+    let args = parse_args(args, &TestStdInReader::new()).unwrap();
+    match args.sub_command {
+      SubCommand::Fmt(cmd) => assert!(cmd.staged),
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn should_error_when_no_framework_detected() {
+    let environment = TestEnvironment::new();
+    let err = setup_hooks(&environment, &SetupHooksSubCommand { framework: None }).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Could not detect a git hook manager in the current directory. Please specify one with --framework <husky|pre-commit|lefthook>."
+    );
+  }
+
+  #[test]
+  fn should_error_when_multiple_frameworks_detected() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/package.json", r#"{ "devDependencies": { "husky": "^7.0.0" } }"#).unwrap();
+    environment.write_file("/lefthook.yml", "").unwrap();
+    let err = setup_hooks(&environment, &SetupHooksSubCommand { framework: None }).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Detected multiple git hook managers (husky, lefthook). Please specify one with --framework <husky|pre-commit|lefthook>."
+    );
+  }
+
+  #[test]
+  fn should_create_husky_hook() {
+    let environment = TestEnvironment::new();
+    setup_hooks(&environment, &SetupHooksSubCommand { framework: Some(HookFramework::Husky) }).unwrap();
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec![
+        "Note: Ensure ./.husky/pre-commit is executable (ex. `chmod +x ./.husky/pre-commit`).",
+        "Updated ./.husky/pre-commit to run `dprint fmt --staged`."
+      ]
+    );
+    assert_eq!(
+      environment.read_file("/.husky/pre-commit").unwrap(),
+      "#!/bin/sh\n. \"$(dirname \"$0\")/_/husky.sh\"\n\ndprint fmt --staged\n"
+    );
+  }
+
+  #[test]
+  fn should_append_to_detected_husky_hook() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/.husky/pre-commit", "#!/bin/sh\nnpm test").unwrap();
+    setup_hooks(&environment, &SetupHooksSubCommand { framework: None }).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["Updated ./.husky/pre-commit to run `dprint fmt --staged`."]);
+    assert_eq!(environment.read_file("/.husky/pre-commit").unwrap(), "#!/bin/sh\nnpm test\ndprint fmt --staged\n");
+
+    // running again should not add it twice
+    setup_hooks(&environment, &SetupHooksSubCommand { framework: None }).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["The husky pre-commit hook already runs `dprint fmt --staged`."]);
+    assert_eq!(environment.read_file("/.husky/pre-commit").unwrap(), "#!/bin/sh\nnpm test\ndprint fmt --staged\n");
+  }
+
+  #[test]
+  fn should_create_pre_commit_config() {
+    let environment = TestEnvironment::new();
+    setup_hooks(&environment, &SetupHooksSubCommand { framework: Some(HookFramework::PreCommit) }).unwrap();
+    assert_eq!(
+      environment.take_logged_messages(),
+      vec!["Updated ./.pre-commit-config.yaml to run `dprint fmt --staged`."]
+    );
+    assert_eq!(
+      environment.read_file("/.pre-commit-config.yaml").unwrap(),
+      r#"repos:
+  - repo: local
+    hooks:
+      - id: dprint
+        name: dprint
+        entry: dprint fmt --staged
+        language: system
+        pass_filenames: false
+"#
+    );
+  }
+
+  #[test]
+  fn should_append_to_detected_pre_commit_config() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        "/.pre-commit-config.yaml",
+        "default_stages: [commit]\nrepos:\n  - repo: https://github.com/pre-commit/pre-commit-hooks\n    rev: v4.0.1\n    hooks:\n      - id: trailing-whitespace",
+      )
+      .unwrap();
+    setup_hooks(&environment, &SetupHooksSubCommand { framework: None }).unwrap();
+    environment.take_logged_messages();
+    assert_eq!(
+      environment.read_file("/.pre-commit-config.yaml").unwrap(),
+      r#"default_stages: [commit]
+repos:
+  - repo: https://github.com/pre-commit/pre-commit-hooks
+    rev: v4.0.1
+    hooks:
+      - id: trailing-whitespace
+  - repo: local
+    hooks:
+      - id: dprint
+        name: dprint
+        entry: dprint fmt --staged
+        language: system
+        pass_filenames: false
+"#
+    );
+  }
+
+  #[test]
+  fn should_error_when_pre_commit_repos_not_last_key() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file("/.pre-commit-config.yaml", "repos:\n  - repo: local\n    hooks: []\nfail_fast: true\n")
+      .unwrap();
+    let err = setup_hooks(&environment, &SetupHooksSubCommand { framework: None }).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Could not update ./.pre-commit-config.yaml. Please add a local hook that runs `dprint fmt --staged` to the repos manually."
+    );
+  }
+
+  #[test]
+  fn should_create_lefthook_config() {
+    let environment = TestEnvironment::new();
+    setup_hooks(&environment, &SetupHooksSubCommand { framework: Some(HookFramework::Lefthook) }).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["Updated ./lefthook.yml to run `dprint fmt --staged`."]);
+    assert_eq!(
+      environment.read_file("/lefthook.yml").unwrap(),
+      "pre-commit:\n  commands:\n    dprint:\n      run: dprint fmt --staged\n"
+    );
+  }
+
+  #[test]
+  fn should_append_to_detected_lefthook_config() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/.lefthook.yml", "pre-push:\n  commands:\n    test:\n      run: npm test\n").unwrap();
+    setup_hooks(&environment, &SetupHooksSubCommand { framework: None }).unwrap();
+    assert_eq!(environment.take_logged_messages(), vec!["Updated ./.lefthook.yml to run `dprint fmt --staged`."]);
+    assert_eq!(
+      environment.read_file("/.lefthook.yml").unwrap(),
+      "pre-push:\n  commands:\n    test:\n      run: npm test\n\npre-commit:\n  commands:\n    dprint:\n      run: dprint fmt --staged\n"
+    );
+  }
+
+  #[test]
+  fn should_error_when_lefthook_has_pre_commit_hook() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/lefthook.yml", "pre-commit:\n  commands:\n    test:\n      run: npm test\n").unwrap();
+    let err = setup_hooks(&environment, &SetupHooksSubCommand { framework: None }).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Could not update ./lefthook.yml because it already has a pre-commit hook. Please add a command that runs `dprint fmt --staged` to it manually."
+    );
+  }
+}
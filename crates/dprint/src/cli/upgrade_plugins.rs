@@ -0,0 +1,201 @@
+use dprint_core::plugins::wasm::PLUGIN_SYSTEM_SCHEMA_VERSION;
+use dprint_core::types::ErrBox;
+
+use crate::cache::Cache;
+use crate::configuration::{deserialize_config, ConfigMapValue};
+use crate::environment::Environment;
+use crate::plugins::{read_info_file, InfoFilePluginInfo};
+
+use super::configuration::resolve_main_config_path;
+use super::CliArgs;
+
+struct PluginUpgrade<'a> {
+  plugin: &'a InfoFilePluginInfo,
+  old_version: String,
+  old_url: String,
+  new_url: String,
+}
+
+/// Updates the urls of the plugins in the configuration file to the latest versions
+/// in the plugin info file. The rest of the configuration file's text is left as-is.
+pub fn upgrade_plugins<TEnvironment: Environment>(args: &CliArgs, cache: &Cache<TEnvironment>, environment: &TEnvironment) -> Result<(), ErrBox> {
+  let config_path = resolve_main_config_path(args, cache, environment)?;
+  if config_path.resolved_path.is_remote() {
+    return err!("Cannot upgrade the plugins of a remote configuration file.");
+  }
+  let config_file_path = config_path.resolved_path.file_path;
+  let config_file_text = match environment.read_file(&config_file_path) {
+    Ok(text) => text,
+    Err(err) => return err!("Error reading configuration file {}. {}", config_file_path.display(), err.to_string()),
+  };
+  let plugin_urls = match deserialize_config(&config_file_text)?.remove("plugins") {
+    Some(ConfigMapValue::Vec(plugin_urls)) => plugin_urls,
+    Some(_) => return err!("Expected the \"plugins\" property to be an array of strings."),
+    None => Vec::new(),
+  };
+
+  let info_file = match read_info_file(environment) {
+    Ok(info_file) => info_file,
+    Err(err) => return err!("Error getting the latest plugin info. {}", err.to_string()),
+  };
+  if info_file.plugin_system_schema_version != PLUGIN_SYSTEM_SCHEMA_VERSION {
+    return err!(
+      "The latest plugins require a newer version of dprint. Upgrade dprint, then try again. Plugin system schema version is {}, latest is {}.",
+      PLUGIN_SYSTEM_SCHEMA_VERSION,
+      info_file.plugin_system_schema_version,
+    );
+  }
+
+  let upgrades = get_plugin_upgrades(&plugin_urls, &info_file.latest_plugins, environment);
+  if upgrades.is_empty() {
+    environment.log("All plugins are up to date.");
+    return Ok(());
+  }
+
+  let mut new_config_file_text = config_file_text.clone();
+  for upgrade in upgrades.iter() {
+    // replace the string literal in order to maintain the formatting and comments
+    new_config_file_text = new_config_file_text.replace(&format!("\"{}\"", upgrade.old_url), &format!("\"{}\"", upgrade.new_url));
+  }
+  environment.write_file(&config_file_path, &new_config_file_text)?;
+
+  for upgrade in upgrades.iter() {
+    environment.log(&format!(
+      "Upgraded {} from {} to {}",
+      upgrade.plugin.name, upgrade.old_version, upgrade.plugin.version
+    ));
+  }
+  environment.log(&format!("\nUpdated {}", config_file_path.display()));
+
+  Ok(())
+}
+
+fn get_plugin_upgrades<'a>(plugin_urls: &[String], latest_plugins: &'a [InfoFilePluginInfo], environment: &impl Environment) -> Vec<PluginUpgrade<'a>> {
+  let mut upgrades = Vec::new();
+  for plugin_url in plugin_urls {
+    let (url, _) = split_checksum(plugin_url);
+    let (url_template, version) = match get_url_template_and_version(url) {
+      Some(value) => value,
+      None => {
+        log_verbose!(environment, "Skipping {} because the url does not contain a version.", plugin_url);
+        continue;
+      }
+    };
+    let latest_plugin = latest_plugins.iter().find(|plugin| {
+      get_url_template_and_version(split_checksum(&plugin.url).0)
+        .map(|(latest_url_template, _)| latest_url_template == url_template)
+        .unwrap_or(false)
+    });
+    let latest_plugin = match latest_plugin {
+      Some(plugin) => plugin,
+      None => {
+        log_verbose!(environment, "Skipping {} because it's not a known plugin.", plugin_url);
+        continue;
+      }
+    };
+    if !is_newer_version(&latest_plugin.version, &version) {
+      log_verbose!(environment, "Skipping {} because it's the latest version.", plugin_url);
+      continue;
+    }
+    let new_url = match &latest_plugin.checksum {
+      Some(checksum) if latest_plugin.is_process_plugin() => format!("{}@{}", latest_plugin.url, checksum),
+      _ => latest_plugin.url.clone(),
+    };
+    upgrades.push(PluginUpgrade {
+      plugin: latest_plugin,
+      old_version: version,
+      old_url: plugin_url.clone(),
+      new_url,
+    });
+  }
+  upgrades
+}
+
+/// Splits the checksum off a plugin url (ex. `https://plugins.dprint.dev/plugin.exe-plugin@checksum`).
+fn split_checksum(plugin_url: &str) -> (&str, Option<&str>) {
+  let file_name_start = plugin_url.rfind('/').map(|index| index + 1).unwrap_or(0);
+  match plugin_url[file_name_start..].rfind('@') {
+    Some(index) => {
+      let index = file_name_start + index;
+      (&plugin_url[..index], Some(&plugin_url[index + 1..]))
+    }
+    None => (plugin_url, None),
+  }
+}
+
+/// Gets the url with the version replaced by a placeholder along with the version
+/// (ex. `https://plugins.dprint.dev/typescript-0.44.1.wasm` or `https://example.com/0.3.0/plugin.wasm`).
+fn get_url_template_and_version(url: &str) -> Option<(String, String)> {
+  let mut result = None;
+  for (index, c) in url.char_indices() {
+    if c != '-' && c != '/' {
+      continue;
+    }
+    let version_start = index + 1;
+    let version_len = url[version_start..].chars().take_while(|c| c.is_ascii_digit() || *c == '.').count();
+    let version = url[version_start..version_start + version_len].trim_end_matches('.');
+    let version_end = version_start + version.len();
+    let is_version = version.split('.').count() == 3 && version.split('.').all(|part| !part.is_empty());
+    let is_end = matches!(url[version_end..].chars().next(), None | Some('.') | Some('/'));
+    if is_version && is_end {
+      result = Some((format!("{}{{version}}{}", &url[..version_start], &url[version_end..]), version.to_string()));
+    }
+  }
+  result
+}
+
+fn is_newer_version(version: &str, other_version: &str) -> bool {
+  fn parse_version(version: &str) -> Vec<u64> {
+    version.split('.').map(|part| part.parse::<u64>().unwrap_or(0)).collect()
+  }
+
+  parse_version(version) > parse_version(other_version)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_split_checksum() {
+    assert_eq!(
+      split_checksum("https://plugins.dprint.dev/test.wasm"),
+      ("https://plugins.dprint.dev/test.wasm", None)
+    );
+    assert_eq!(
+      split_checksum("https://plugins.dprint.dev/test.exe-plugin@checksum"),
+      ("https://plugins.dprint.dev/test.exe-plugin", Some("checksum"))
+    );
+    assert_eq!(split_checksum("https://user@dprint.dev/test.wasm"), ("https://user@dprint.dev/test.wasm", None));
+  }
+
+  #[test]
+  fn should_get_url_template_and_version() {
+    assert_eq!(
+      get_url_template_and_version("https://plugins.dprint.dev/typescript-0.44.1.wasm"),
+      Some(("https://plugins.dprint.dev/typescript-{version}.wasm".to_string(), "0.44.1".to_string()))
+    );
+    assert_eq!(
+      get_url_template_and_version("https://plugins.dprint.dev/prettier-0.3.0.exe-plugin"),
+      Some(("https://plugins.dprint.dev/prettier-{version}.exe-plugin".to_string(), "0.3.0".to_string()))
+    );
+    assert_eq!(
+      get_url_template_and_version("https://github.com/dprint/plugin/releases/download/10.2.3/plugin.wasm"),
+      Some((
+        "https://github.com/dprint/plugin/releases/download/{version}/plugin.wasm".to_string(),
+        "10.2.3".to_string()
+      ))
+    );
+    assert_eq!(get_url_template_and_version("https://plugins.dprint.dev/typescript.wasm"), None);
+    assert_eq!(get_url_template_and_version("https://plugins.dprint.dev/typescript-1.2.wasm"), None);
+  }
+
+  #[test]
+  fn should_get_if_newer_version() {
+    assert_eq!(is_newer_version("0.2.0", "0.1.0"), true);
+    assert_eq!(is_newer_version("0.10.0", "0.9.0"), true);
+    assert_eq!(is_newer_version("1.0.0", "0.99.99"), true);
+    assert_eq!(is_newer_version("0.1.0", "0.1.0"), false);
+    assert_eq!(is_newer_version("0.1.0", "0.2.0"), false);
+  }
+}
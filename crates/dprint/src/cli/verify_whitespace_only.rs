@@ -0,0 +1,80 @@
+use dprint_core::types::ErrBox;
+
+use crate::utils::get_text_position;
+
+/// Verifies the formatted text only differs from the original text in whitespace by
+/// comparing the non-whitespace characters of both texts in order.
+pub fn verify_whitespace_only(original_text: &str, formatted_text: &str) -> Result<(), ErrBox> {
+  let mut original_chars = get_non_whitespace_char_indices(original_text);
+  let mut formatted_chars = get_non_whitespace_char_indices(formatted_text);
+
+  loop {
+    match (original_chars.next(), formatted_chars.next()) {
+      (None, None) => return Ok(()),
+      (Some((_, original_char)), Some((_, formatted_char))) if original_char == formatted_char => continue,
+      (original, formatted) => {
+        let original_position = get_text_position(original_text, original.map(|(index, _)| index).unwrap_or(original_text.len()));
+        let formatted_position = get_text_position(formatted_text, formatted.map(|(index, _)| index).unwrap_or(formatted_text.len()));
+        return err!(
+          "Formatting changed more than whitespace. Expected {} at {}:{} in the original text, but found {} at {}:{} in the formatted text.",
+          describe_char(original.map(|(_, c)| c)),
+          original_position.line_number,
+          original_position.column_number,
+          describe_char(formatted.map(|(_, c)| c)),
+          formatted_position.line_number,
+          formatted_position.column_number,
+        );
+      }
+    }
+  }
+}
+
+fn get_non_whitespace_char_indices(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+  text.char_indices().filter(|(_, c)| !c.is_whitespace())
+}
+
+fn describe_char(c: Option<char>) -> String {
+  match c {
+    Some(c) => format!("{:?}", c),
+    None => "the end of the text".to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_allow_whitespace_changes() {
+    assert!(verify_whitespace_only("", "").is_ok());
+    assert!(verify_whitespace_only("a+b", "a + b\n").is_ok());
+    assert!(verify_whitespace_only("call(a,\n\t\tb)\r\n", "call(a, b)\n").is_ok());
+  }
+
+  #[test]
+  fn should_error_when_changing_text() {
+    assert_eq!(
+      verify_whitespace_only("let a = 5;\nlet b = 6;", "let a = 5;\nlet b = 7;")
+        .err()
+        .unwrap()
+        .to_string(),
+      "Formatting changed more than whitespace. Expected '6' at 2:9 in the original text, but found '7' at 2:9 in the formatted text."
+    );
+  }
+
+  #[test]
+  fn should_error_when_dropping_text() {
+    assert_eq!(
+      verify_whitespace_only("a;\nb;", "a;\n").err().unwrap().to_string(),
+      "Formatting changed more than whitespace. Expected 'b' at 2:1 in the original text, but found the end of the text at 2:1 in the formatted text."
+    );
+  }
+
+  #[test]
+  fn should_error_when_adding_text() {
+    assert_eq!(
+      verify_whitespace_only("a", "a;").err().unwrap().to_string(),
+      "Formatting changed more than whitespace. Expected the end of the text at 1:2 in the original text, but found ';' at 1:2 in the formatted text."
+    );
+  }
+}
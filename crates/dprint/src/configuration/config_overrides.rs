@@ -0,0 +1,297 @@
+use dprint_core::configuration::{ConfigKeyMap, ConfigKeyValue};
+use dprint_core::types::ErrBox;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use super::{ConfigMap, ConfigMapValue};
+use crate::utils::{get_bytes_hash, is_negated_glob, to_absolute_glob, GlobMatcher, GlobMatcherOptions};
+
+/// An entry of the "overrides" property in the configuration file.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ConfigOverride {
+  /// File patterns relative to the base path.
+  pub files: Vec<String>,
+  /// Global configuration properties and plugin configuration objects.
+  pub config_map: ConfigMap,
+}
+
+/// Resolves the configuration from the "overrides" property that applies to a file.
+///
+/// When multiple entries match a file, the entry with the closest match takes precedence.
+/// This is the entry whose matching pattern has the most directory names before any glob
+/// characters (ex. `src/legacy/**` is closer than `src/**`, which is closer than `**/*.ts`).
+/// Entries that are equally close are applied in order, so the last one wins.
+#[derive(Default)]
+pub struct ConfigOverrides {
+  entries: Vec<ConfigOverridesEntry>,
+  hash: u64,
+}
+
+struct ConfigOverridesEntry {
+  patterns: Vec<(GlobMatcher, usize)>,
+  excludes: GlobMatcher,
+  config_map: ConfigMap,
+}
+
+impl ConfigOverridesEntry {
+  /// Gets how close of a match the file is or `None` when it doesn't match.
+  fn get_match_closeness(&self, file_path: &str) -> Option<usize> {
+    if self.excludes.is_ignored(file_path) {
+      return None;
+    }
+
+    self
+      .patterns
+      .iter()
+      .filter(|(glob_matcher, _)| glob_matcher.is_match(file_path))
+      .map(|(_, closeness)| *closeness)
+      .max()
+  }
+}
+
+impl ConfigOverrides {
+  pub fn new(overrides: &[ConfigOverride], base_path: &Path) -> Result<Self, ErrBox> {
+    let base_path = base_path.to_string_lossy();
+    let glob_matcher_options = GlobMatcherOptions {
+      case_insensitive: cfg!(windows),
+    };
+    let mut entries = Vec::with_capacity(overrides.len());
+    let mut hash_str = String::new();
+
+    for config_override in overrides {
+      let mut patterns = Vec::new();
+      let mut excludes = Vec::new();
+      for pattern in config_override.files.iter() {
+        let pattern = process_pattern(pattern);
+        let absolute_pattern = to_absolute_glob(&pattern, &base_path);
+        if is_negated_glob(&pattern) {
+          excludes.push(absolute_pattern);
+        } else {
+          let closeness = get_pattern_closeness(&pattern);
+          patterns.push((GlobMatcher::new(&[absolute_pattern], &glob_matcher_options)?, closeness));
+        }
+      }
+      entries.push(ConfigOverridesEntry {
+        patterns,
+        excludes: GlobMatcher::new(&excludes, &glob_matcher_options)?,
+        config_map: config_override.config_map.clone(),
+      });
+
+      hash_str.push_str(&serde_json::to_string(&config_override.files).unwrap());
+      hash_str.push_str(&get_config_map_hash_text(&config_override.config_map));
+    }
+
+    Ok(ConfigOverrides {
+      entries,
+      hash: if overrides.is_empty() { 0 } else { get_bytes_hash(hash_str.as_bytes()) },
+    })
+  }
+
+  /// Gets a hash of the overrides to tell when they have changed. This is 0 when there are no overrides.
+  pub fn get_hash(&self) -> u64 {
+    self.hash
+  }
+
+  /// Gets the configuration that should override the plugin's configuration when formatting the file.
+  ///
+  /// The global properties of an entry are provided to the plugin along with the properties
+  /// in its plugin configuration object, which take precedence over the entry's global properties.
+  pub fn get_plugin_override_config(&self, file_path: &Path, plugin_config_key: &str) -> ConfigKeyMap {
+    let mut result = HashMap::new();
+    if self.entries.is_empty() {
+      return result;
+    }
+
+    let file_path = file_path.to_string_lossy().replace("\\", "/");
+    let mut matched_entries = self
+      .entries
+      .iter()
+      .enumerate()
+      .filter_map(|(index, entry)| entry.get_match_closeness(&file_path).map(|closeness| (closeness, index, entry)))
+      .collect::<Vec<_>>();
+    // apply the closest match last
+    matched_entries.sort_by_key(|(closeness, index, _)| (*closeness, *index));
+
+    for (_, _, entry) in matched_entries {
+      for (key, value) in entry.config_map.iter() {
+        if let ConfigMapValue::KeyValue(value) = value {
+          result.insert(key.clone(), value.clone());
+        }
+      }
+      if let Some(ConfigMapValue::HashMap(plugin_config)) = entry.config_map.get(plugin_config_key) {
+        for (key, value) in plugin_config.iter() {
+          if key != "locked" {
+            result.insert(key.clone(), value.clone());
+          }
+        }
+      }
+    }
+
+    result
+  }
+}
+
+fn process_pattern(pattern: &str) -> String {
+  let pattern = pattern.replace("\\", "/");
+  // make patterns that start with `/` be relative to the base path like the includes and excludes
+  if pattern.starts_with("/") {
+    format!(".{}", pattern)
+  } else if pattern.starts_with("!/") {
+    format!("!.{}", &pattern[1..])
+  } else {
+    pattern
+  }
+}
+
+/// Gets the number of directory names at the start of the pattern that don't contain glob characters.
+fn get_pattern_closeness(pattern: &str) -> usize {
+  let components = pattern.split('/').filter(|c| !c.is_empty() && *c != ".").collect::<Vec<_>>();
+  // the last component is the file name
+  let dir_components = &components[..components.len().saturating_sub(1)];
+  dir_components
+    .iter()
+    .take_while(|component| !component.contains(|c: char| matches!(c, '*' | '?' | '[' | '{')))
+    .count()
+}
+
+fn get_config_map_hash_text(config_map: &ConfigMap) -> String {
+  let sorted_config_map: BTreeMap<&String, &ConfigMapValue> = config_map.iter().collect();
+  let mut text = String::new();
+  for (key, value) in sorted_config_map {
+    text.push_str(&serde_json::to_string(key).unwrap());
+    match value {
+      ConfigMapValue::KeyValue(value) => text.push_str(&serde_json::to_string(value).unwrap()),
+      ConfigMapValue::HashMap(obj) => {
+        let sorted_obj: BTreeMap<&String, &ConfigKeyValue> = obj.iter().collect();
+        text.push_str(&serde_json::to_string(&sorted_obj).unwrap());
+      }
+      ConfigMapValue::PluginConfig(plugin_config) => {
+        let sorted_obj: BTreeMap<&String, &ConfigKeyValue> = plugin_config.properties.iter().collect();
+        text.push_str(&serde_json::to_string(&plugin_config.associations).unwrap());
+        text.push_str(&serde_json::to_string(&sorted_obj).unwrap());
+      }
+      ConfigMapValue::Vec(items) => text.push_str(&serde_json::to_string(items).unwrap()),
+      ConfigMapValue::MapVec(maps) => {
+        for map in maps {
+          text.push_str(&get_config_map_hash_text(map));
+        }
+      }
+    }
+  }
+  text
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use pretty_assertions::assert_eq;
+  use std::path::PathBuf;
+
+  #[test]
+  fn should_get_closest_match_last() {
+    let config_overrides = ConfigOverrides::new(
+      &[
+        get_override(&["src/legacy/**/*"], &[("lineWidth", ConfigKeyValue::from_i32(80))], None),
+        get_override(&["**/*.ts"], &[("lineWidth", ConfigKeyValue::from_i32(90))], None),
+        get_override(
+          &["src/**/*"],
+          &[("lineWidth", ConfigKeyValue::from_i32(100)), ("useTabs", ConfigKeyValue::from_bool(true))],
+          None,
+        ),
+        get_override(
+          &["tests/**/*"],
+          &[("lineWidth", ConfigKeyValue::from_i32(120))],
+          Some(("typescript", &[("lineWidth", ConfigKeyValue::from_i32(110))])),
+        ),
+      ],
+      &PathBuf::from("/project"),
+    )
+    .unwrap();
+
+    assert_eq!(
+      config_overrides.get_plugin_override_config(&PathBuf::from("/project/src/legacy/file.ts"), "typescript"),
+      get_config_key_map(&[("lineWidth", ConfigKeyValue::from_i32(80)), ("useTabs", ConfigKeyValue::from_bool(true))])
+    );
+    assert_eq!(
+      config_overrides.get_plugin_override_config(&PathBuf::from("/project/src/file.ts"), "typescript"),
+      get_config_key_map(&[("lineWidth", ConfigKeyValue::from_i32(100)), ("useTabs", ConfigKeyValue::from_bool(true))])
+    );
+    assert_eq!(
+      config_overrides.get_plugin_override_config(&PathBuf::from("/project/other/file.ts"), "typescript"),
+      get_config_key_map(&[("lineWidth", ConfigKeyValue::from_i32(90))])
+    );
+    // plugin configuration takes precedence over the entry's global configuration
+    assert_eq!(
+      config_overrides.get_plugin_override_config(&PathBuf::from("/project/tests/file.ts"), "typescript"),
+      get_config_key_map(&[("lineWidth", ConfigKeyValue::from_i32(110))])
+    );
+    assert_eq!(
+      config_overrides.get_plugin_override_config(&PathBuf::from("/project/tests/file.json"), "json"),
+      get_config_key_map(&[("lineWidth", ConfigKeyValue::from_i32(120))])
+    );
+    assert_eq!(
+      config_overrides.get_plugin_override_config(&PathBuf::from("/project/file.json"), "json"),
+      HashMap::new()
+    );
+  }
+
+  #[test]
+  fn should_apply_equally_close_matches_in_order() {
+    let config_overrides = ConfigOverrides::new(
+      &[
+        get_override(&["src/**/*"], &[("lineWidth", ConfigKeyValue::from_i32(80))], None),
+        get_override(&["src/*.ts", "!src/file.ts"], &[("lineWidth", ConfigKeyValue::from_i32(90))], None),
+      ],
+      &PathBuf::from("/project"),
+    )
+    .unwrap();
+
+    assert_eq!(
+      config_overrides.get_plugin_override_config(&PathBuf::from("/project/src/other.ts"), "typescript"),
+      get_config_key_map(&[("lineWidth", ConfigKeyValue::from_i32(90))])
+    );
+    assert_eq!(
+      config_overrides.get_plugin_override_config(&PathBuf::from("/project/src/file.ts"), "typescript"),
+      get_config_key_map(&[("lineWidth", ConfigKeyValue::from_i32(80))])
+    );
+  }
+
+  #[test]
+  fn should_get_pattern_closeness() {
+    assert_eq!(get_pattern_closeness("**/*.ts"), 0);
+    assert_eq!(get_pattern_closeness("*.ts"), 0);
+    assert_eq!(get_pattern_closeness("src/**/*.ts"), 1);
+    assert_eq!(get_pattern_closeness("./src/legacy/*.ts"), 2);
+    assert_eq!(get_pattern_closeness("src/legacy/file.ts"), 2);
+    assert_eq!(get_pattern_closeness("src/*/legacy/*.ts"), 1);
+  }
+
+  #[test]
+  fn should_have_zero_hash_when_empty() {
+    assert_eq!(ConfigOverrides::new(&[], &PathBuf::from("/project")).unwrap().get_hash(), 0);
+    let config_overrides = ConfigOverrides::new(
+      &[get_override(&["src/**/*"], &[("lineWidth", ConfigKeyValue::from_i32(80))], None)],
+      &PathBuf::from("/project"),
+    )
+    .unwrap();
+    assert_ne!(config_overrides.get_hash(), 0);
+  }
+
+  fn get_override(files: &[&str], global_config: &[(&str, ConfigKeyValue)], plugin_config: Option<(&str, &[(&str, ConfigKeyValue)])>) -> ConfigOverride {
+    let mut config_map = HashMap::new();
+    for (key, value) in global_config {
+      config_map.insert(key.to_string(), ConfigMapValue::KeyValue(value.clone()));
+    }
+    if let Some((config_key, plugin_config)) = plugin_config {
+      config_map.insert(config_key.to_string(), ConfigMapValue::HashMap(get_config_key_map(plugin_config)));
+    }
+    ConfigOverride {
+      files: files.iter().map(|file| file.to_string()).collect(),
+      config_map,
+    }
+  }
+
+  fn get_config_key_map(items: &[(&str, ConfigKeyValue)]) -> ConfigKeyMap {
+    items.iter().map(|(key, value)| (key.to_string(), value.clone())).collect()
+  }
+}
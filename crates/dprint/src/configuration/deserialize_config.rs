@@ -1,5 +1,5 @@
-use super::{ConfigMap, ConfigMapValue};
-use dprint_core::configuration::{ConfigKeyMap, ConfigKeyValue};
+use super::{ConfigMap, ConfigMapValue, RawPluginConfig};
+use dprint_core::configuration::ConfigKeyValue;
 use dprint_core::types::ErrBox;
 use jsonc_parser::{JsonArray, JsonObject, JsonValue};
 use std::collections::HashMap;
@@ -12,13 +12,19 @@ pub fn deserialize_config(config_file_text: &str) -> Result<ConfigMap, ErrBox> {
     _ => return err!("Expected a root object in the json"),
   };
 
+  json_obj_to_config_map(root_object_node, true)
+}
+
+/// Converts an object to a config map. Arrays of objects are only allowed
+/// in the root object (ex. the "overrides" property).
+fn json_obj_to_config_map(obj: JsonObject, allow_object_arrays: bool) -> Result<ConfigMap, ErrBox> {
   let mut properties = HashMap::new();
 
-  for (key, value) in root_object_node.into_iter() {
+  for (key, value) in obj.into_iter() {
     let property_name = key;
     let property_value = match value {
-      JsonValue::Object(obj) => ConfigMapValue::HashMap(json_obj_to_hash_map(&property_name, obj)?),
-      JsonValue::Array(arr) => ConfigMapValue::Vec(json_array_to_vec(&property_name, arr)?),
+      JsonValue::Object(obj) => json_obj_to_object_config_map_value(&property_name, obj)?,
+      JsonValue::Array(arr) => json_array_to_config_map_value(&property_name, arr, allow_object_arrays)?,
       JsonValue::Boolean(value) => ConfigMapValue::from_bool(value),
       JsonValue::String(value) => ConfigMapValue::KeyValue(ConfigKeyValue::String(value.into_owned())),
       JsonValue::Number(value) => ConfigMapValue::from_i32(match value.parse::<i32>() {
@@ -40,11 +46,28 @@ pub fn deserialize_config(config_file_text: &str) -> Result<ConfigMap, ErrBox> {
   Ok(properties)
 }
 
-fn json_obj_to_hash_map(parent_prop_name: &str, obj: JsonObject) -> Result<ConfigKeyMap, ErrBox> {
+/// Converts an object to a config map value. The `associations` property of plugin
+/// configuration objects is the only property allowed to be an array.
+fn json_obj_to_object_config_map_value(parent_prop_name: &str, obj: JsonObject) -> Result<ConfigMapValue, ErrBox> {
+  let mut associations = None;
   let mut properties = HashMap::new();
 
   for (key, value) in obj.into_iter() {
     let property_name = key;
+    if property_name == "associations" {
+      associations = Some(match value {
+        JsonValue::Array(array) => json_values_to_vec(&format!("{} -> {}", parent_prop_name, property_name), array.into_iter().collect())?,
+        JsonValue::String(pattern) => vec![pattern.into_owned()],
+        _ => {
+          return err!(
+            "Expected a string or array of strings in object property '{} -> {}'",
+            parent_prop_name,
+            property_name
+          )
+        }
+      });
+      continue;
+    }
     let property_value = match value_to_plugin_config_key_value(value) {
       Ok(result) => result,
       Err(err) => return err!("{} in object property '{} -> {}'", err, parent_prop_name, property_name),
@@ -52,13 +75,42 @@ fn json_obj_to_hash_map(parent_prop_name: &str, obj: JsonObject) -> Result<Confi
     properties.insert(property_name, property_value);
   }
 
-  Ok(properties)
+  Ok(match associations {
+    Some(associations) => ConfigMapValue::PluginConfig(RawPluginConfig { associations, properties }),
+    None => ConfigMapValue::HashMap(properties),
+  })
 }
 
-fn json_array_to_vec(parent_prop_name: &str, array: JsonArray) -> Result<Vec<String>, ErrBox> {
+fn json_array_to_config_map_value(parent_prop_name: &str, array: JsonArray, allow_object_arrays: bool) -> Result<ConfigMapValue, ErrBox> {
+  let elements = array.into_iter().collect::<Vec<_>>();
+  if allow_object_arrays && matches!(elements.first(), Some(JsonValue::Object(_))) {
+    Ok(ConfigMapValue::MapVec(json_values_to_config_maps(parent_prop_name, elements)?))
+  } else {
+    Ok(ConfigMapValue::Vec(json_values_to_vec(parent_prop_name, elements)?))
+  }
+}
+
+fn json_values_to_config_maps(parent_prop_name: &str, values: Vec<JsonValue>) -> Result<Vec<ConfigMap>, ErrBox> {
   let mut elements = Vec::new();
 
-  for element in array.into_iter() {
+  for element in values.into_iter() {
+    let value = match element {
+      JsonValue::Object(obj) => match json_obj_to_config_map(obj, false) {
+        Ok(result) => result,
+        Err(err) => return err!("{} in array '{}'", err, parent_prop_name),
+      },
+      _ => return err!("Expected an object in array '{}'", parent_prop_name),
+    };
+    elements.push(value);
+  }
+
+  Ok(elements)
+}
+
+fn json_values_to_vec(parent_prop_name: &str, values: Vec<JsonValue>) -> Result<Vec<String>, ErrBox> {
+  let mut elements = Vec::new();
+
+  for element in values.into_iter() {
     let value = match value_to_string(element) {
       Ok(result) => result,
       Err(err) => return err!("{} in array '{}'", err, parent_prop_name),
@@ -87,7 +139,7 @@ fn value_to_plugin_config_key_value(value: JsonValue) -> Result<ConfigKeyValue,
 
 #[cfg(test)]
 mod tests {
-  use super::super::{ConfigMap, ConfigMapValue};
+  use super::super::{ConfigMap, ConfigMapValue, RawPluginConfig};
   use super::deserialize_config;
   use dprint_core::configuration::ConfigKeyValue;
   use std::collections::HashMap;
@@ -138,6 +190,61 @@ mod tests {
     );
   }
 
+  #[test]
+  fn it_should_deserialize_plugin_associations() {
+    let mut expected_props = HashMap::new();
+    let mut ts_hash_map = HashMap::new();
+    ts_hash_map.insert(String::from("lineWidth"), ConfigKeyValue::from_i32(40));
+    expected_props.insert(
+      String::from("typescript"),
+      ConfigMapValue::PluginConfig(RawPluginConfig {
+        associations: vec![String::from("**/*.mjsx"), String::from("Jenkinsfile")],
+        properties: ts_hash_map,
+      }),
+    );
+    assert_deserializes(
+      "{'typescript': { 'associations': ['**/*.mjsx', 'Jenkinsfile'], 'lineWidth': 40 }}",
+      expected_props,
+    );
+  }
+
+  #[test]
+  fn it_should_error_when_plugin_associations_has_non_string() {
+    assert_error(
+      "{'typescript': { 'associations': [5] }}",
+      "Expected a string in array 'typescript -> associations'",
+    );
+  }
+
+  #[test]
+  fn it_should_deserialize_array_of_objects() {
+    let mut expected_props = HashMap::new();
+    let mut override_props = HashMap::new();
+    override_props.insert(String::from("files"), ConfigMapValue::Vec(vec![String::from("tests/**")]));
+    override_props.insert(String::from("lineWidth"), ConfigMapValue::from_i32(120));
+    let mut ts_hash_map = HashMap::new();
+    ts_hash_map.insert(String::from("semiColons"), ConfigKeyValue::from_str("asi"));
+    override_props.insert(String::from("typescript"), ConfigMapValue::HashMap(ts_hash_map));
+    expected_props.insert(String::from("overrides"), ConfigMapValue::MapVec(vec![override_props]));
+    assert_deserializes(
+      "{'overrides': [{ 'files': ['tests/**'], 'lineWidth': 120, 'typescript': { 'semiColons': 'asi' } }]}",
+      expected_props,
+    );
+  }
+
+  #[test]
+  fn it_should_error_when_array_of_objects_has_non_object() {
+    assert_error("{'overrides': [{}, 'test']}", "Expected an object in array 'overrides'");
+  }
+
+  #[test]
+  fn it_should_error_when_array_of_objects_is_nested() {
+    assert_error(
+      "{'overrides': [{ 'other': [{}] }]}",
+      "Expected a string in array 'other' in array 'overrides'",
+    );
+  }
+
   fn assert_deserializes(text: &str, expected_map: ConfigMap) {
     match deserialize_config(text) {
       Ok(result) => assert_eq!(result, expected_map),
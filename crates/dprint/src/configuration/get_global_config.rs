@@ -79,6 +79,9 @@ mod tests {
         use_tabs: None,
         indent_width: None,
         new_line_kind: None,
+        trim_leading_blank_lines: None,
+        single_trailing_new_line: None,
+        timeout_ms: None,
       },
     );
   }
@@ -108,6 +111,9 @@ mod tests {
         use_tabs: None,
         indent_width: None,
         new_line_kind: None,
+        trim_leading_blank_lines: None,
+        single_trailing_new_line: None,
+        timeout_ms: None,
       },
       &GetGlobalConfigOptions {
         check_unknown_property_diagnostics: false,
@@ -141,6 +147,9 @@ mod tests {
         use_tabs: None,
         indent_width: None,
         new_line_kind: None,
+        trim_leading_blank_lines: None,
+        single_trailing_new_line: None,
+        timeout_ms: None,
       },
     );
   }
@@ -1,10 +1,17 @@
+use dprint_core::jsonc::format_jsonc_text;
 use dprint_core::plugins::wasm::{self};
 use dprint_core::types::ErrBox;
 
 use crate::environment::Environment;
-use crate::plugins::read_info_file;
+use crate::plugins::{read_info_file, InfoFilePluginInfo};
 
-pub fn get_init_config_file_text(environment: &impl Environment) -> Result<String, ErrBox> {
+pub struct InitConfigFile {
+  pub text: String,
+  /// The plugins the user selected or `None` when the latest plugin information could not be retrieved.
+  pub selected_plugins: Option<Vec<InfoFilePluginInfo>>,
+}
+
+pub fn get_init_config_file(environment: &impl Environment) -> Result<InitConfigFile, ErrBox> {
   let info = match read_info_file(environment) {
     Ok(info) => {
       // ok to only check wasm here because the configuration file is only ever initialized with wasm plugins
@@ -95,7 +102,7 @@ pub fn get_init_config_file_text(environment: &impl Environment) -> Result<Strin
     if json_includes.is_empty() {
       json_text.push_str("\"**/*.*\"");
     } else {
-      json_text.push_str(&json_includes.join(","));
+      json_text.push_str(&json_includes.join(", "));
     }
     json_text.push_str("],\n");
     json_text.push_str("  \"excludes\": [");
@@ -138,7 +145,11 @@ pub fn get_init_config_file_text(environment: &impl Environment) -> Result<Strin
     json_text.push_str("  ]\n}\n");
   }
 
-  Ok(json_text)
+  Ok(InitConfigFile {
+    // format in case the generated text isn't consistent with the rest of the file
+    text: format_jsonc_text(&json_text)?,
+    selected_plugins,
+  })
 }
 
 /// Gets the unique items in the vector in the same order
@@ -169,7 +180,7 @@ mod test {
     let environment = TestEnvironment::new();
     environment.add_remote_file(REMOTE_INFO_URL, get_multi_plugins_config().as_bytes());
     environment.set_multi_selection_result(vec![0, 1, 2]);
-    let text = get_init_config_file_text(&environment).unwrap();
+    let text = get_init_config_file(&environment).unwrap().text;
     assert_eq!(
       text,
       r#"{
@@ -178,7 +189,7 @@ mod test {
   },
   "json": {
   },
-  "includes": ["**/*.{ts,tsx,json,rs}","**/{Cargo.toml}"],
+  "includes": ["**/*.{ts,tsx,json,rs}", "**/{Cargo.toml}"],
   "excludes": [
     "**/something",
     "**/*-asdf.json",
@@ -201,7 +212,7 @@ mod test {
     let environment = TestEnvironment::new();
     environment.add_remote_file(REMOTE_INFO_URL, get_multi_plugins_config().as_bytes());
     environment.set_multi_selection_result(vec![1]);
-    let text = get_init_config_file_text(&environment).unwrap();
+    let text = get_init_config_file(&environment).unwrap().text;
     assert_eq!(
       text,
       r#"{
@@ -227,7 +238,7 @@ mod test {
     let environment = TestEnvironment::new();
     environment.add_remote_file(REMOTE_INFO_URL, get_multi_plugins_config().as_bytes());
     environment.set_multi_selection_result(vec![]);
-    let text = get_init_config_file_text(&environment).unwrap();
+    let text = get_init_config_file(&environment).unwrap().text;
     assert_eq!(
       text,
       r#"{
@@ -249,7 +260,7 @@ mod test {
     let environment = TestEnvironment::new();
     environment.add_remote_file(REMOTE_INFO_URL, get_multi_plugins_config().as_bytes());
     environment.set_multi_selection_result(vec![3]);
-    let text = get_init_config_file_text(&environment).unwrap();
+    let text = get_init_config_file(&environment).unwrap().text;
     assert_eq!(
       text,
       r#"{
@@ -269,7 +280,7 @@ mod test {
   #[test]
   fn should_get_initialization_text_when_cannot_access_url() {
     let environment = TestEnvironment::new();
-    let text = get_init_config_file_text(&environment).unwrap();
+    let text = get_init_config_file(&environment).unwrap().text;
     assert_eq!(
       text,
       r#"{
@@ -315,7 +326,7 @@ mod test {
         .as_bytes(),
     );
     environment.set_multi_selection_result(vec![0]);
-    let text = get_init_config_file_text(&environment).unwrap();
+    let text = get_init_config_file(&environment).unwrap().text;
     assert_eq!(
       text,
       r#"{
@@ -356,7 +367,7 @@ mod test {
         .as_bytes(),
     );
     environment.set_multi_selection_result(vec![0]);
-    let text = get_init_config_file_text(&environment).unwrap();
+    let text = get_init_config_file(&environment).unwrap().text;
     assert_eq!(
       text,
       r#"{
@@ -1,9 +1,10 @@
-use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::{ConfigKeyMap, ConfigKeyValue};
 use dprint_core::types::ErrBox;
 use std::collections::HashMap;
 
 use super::{ConfigMap, ConfigMapValue};
 use crate::plugins::Plugin;
+use crate::utils::FILE_BOUNDARY_POLICY_KEYS;
 
 pub fn get_plugin_config_map(plugin: &Box<dyn Plugin>, config_map: &mut ConfigMap) -> Result<ConfigKeyMap, ErrBox> {
   match get_plugin_config_map_inner(plugin, config_map) {
@@ -17,6 +18,17 @@ fn get_plugin_config_map_inner(plugin: &Box<dyn Plugin>, config_map: &mut Config
 
   if let Some(plugin_config_map) = config_map.remove(config_key) {
     if let ConfigMapValue::HashMap(plugin_config_map) = plugin_config_map {
+      // this is used by the CLI to limit how long Wasm plugins may take to format a file
+      match plugin_config_map.get("timeoutMs") {
+        None | Some(ConfigKeyValue::Number(1..=i32::MAX)) => {}
+        Some(_) => return err!("Expected the 'timeoutMs' property of '{}' to be a positive number.", config_key),
+      }
+      for key in FILE_BOUNDARY_POLICY_KEYS.iter() {
+        match plugin_config_map.get(*key) {
+          None | Some(ConfigKeyValue::Bool(_)) => {}
+          Some(_) => return err!("Expected the '{}' property of '{}' to be a boolean.", key, config_key),
+        }
+      }
       Ok(plugin_config_map)
     } else {
       err!("Expected the configuration property '{}' to be an object.", config_key)
@@ -57,6 +69,27 @@ mod tests {
     assert_errors(&mut config_map, "Expected the configuration property 'typescript' to be an object.");
   }
 
+  #[test]
+  fn it_should_error_when_timeout_is_not_positive_number() {
+    let mut config_map = HashMap::new();
+    let mut ts_config_map = HashMap::new();
+    ts_config_map.insert(String::from("timeoutMs"), ConfigKeyValue::from_i32(0));
+    config_map.insert(String::from("typescript"), ConfigMapValue::HashMap(ts_config_map));
+    assert_errors(&mut config_map, "Expected the 'timeoutMs' property of 'typescript' to be a positive number.");
+  }
+
+  #[test]
+  fn it_should_error_when_file_boundary_policy_is_not_boolean() {
+    let mut config_map = HashMap::new();
+    let mut ts_config_map = HashMap::new();
+    ts_config_map.insert(String::from("singleTrailingNewLine"), ConfigKeyValue::from_str("true"));
+    config_map.insert(String::from("typescript"), ConfigMapValue::HashMap(ts_config_map));
+    assert_errors(
+      &mut config_map,
+      "Expected the 'singleTrailingNewLine' property of 'typescript' to be a boolean.",
+    );
+  }
+
   fn assert_errors(config_map: &mut ConfigMap, message: &str) {
     let test_plugin = Box::new(create_plugin()) as Box<dyn Plugin>;
     let result = get_plugin_config_map(&test_plugin, config_map);
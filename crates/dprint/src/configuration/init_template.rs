@@ -0,0 +1,194 @@
+use dprint_core::types::ErrBox;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::{deserialize_config, InitConfigFile};
+use crate::environment::Environment;
+
+/// Url of the directory containing the templates that may be referenced by name (ex. `dprint init --template typescript`).
+pub const REMOTE_TEMPLATES_URL: &'static str = "https://plugins.dprint.dev/templates";
+
+/// Gets the configuration file for the provided template name, url, or file path.
+///
+/// Templates may contain placeholders in the format `{{name}}` or `{{name:default value}}`,
+/// which are replaced with the values the user enters when prompted.
+pub fn get_init_config_file_from_template(template: &str, environment: &impl Environment) -> Result<InitConfigFile, ErrBox> {
+  let template_text = match read_template_text(template, environment) {
+    Ok(text) => text,
+    Err(err) => return err!("Error getting template '{}'. {}", template, err.to_string()),
+  };
+  let text = fill_placeholders(&template_text, environment)?;
+  // ensure the result is a valid configuration file
+  if let Err(err) = deserialize_config(&text) {
+    return err!("Error deserializing template '{}'. {}", template, err.to_string());
+  }
+
+  Ok(InitConfigFile { text, selected_plugins: None })
+}
+
+fn read_template_text(template: &str, environment: &impl Environment) -> Result<String, ErrBox> {
+  let lower_template = template.to_lowercase();
+  let bytes = if lower_template.starts_with("https://") || lower_template.starts_with("http://") {
+    environment.download_file(template)?
+  } else if environment.path_exists(template) {
+    environment.read_file_bytes(template)?
+  } else if is_valid_template_name(template) {
+    environment.download_file(&format!("{}/{}.json", REMOTE_TEMPLATES_URL, template))?
+  } else {
+    return err!("Could not find a file at the path.");
+  };
+  Ok(String::from_utf8(bytes)?)
+}
+
+fn is_valid_template_name(name: &str) -> bool {
+  !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+struct Placeholder<'a> {
+  range: Range<usize>,
+  name: &'a str,
+  default_value: Option<&'a str>,
+}
+
+fn fill_placeholders(text: &str, environment: &impl Environment) -> Result<String, ErrBox> {
+  let placeholders = get_placeholders(text);
+  let mut values: HashMap<&str, String> = HashMap::new();
+  for placeholder in placeholders.iter() {
+    if values.contains_key(placeholder.name) {
+      continue;
+    }
+    let prompt_message = match placeholder.default_value {
+      Some(default_value) => format!("Enter a value for {} (default: {}):", placeholder.name, default_value),
+      None => format!("Enter a value for {}:", placeholder.name),
+    };
+    let value = environment.get_text_input(&prompt_message)?;
+    let value = match placeholder.default_value {
+      Some(default_value) if value.is_empty() => default_value.to_string(),
+      _ => value,
+    };
+    values.insert(placeholder.name, value);
+  }
+
+  let mut result = String::with_capacity(text.len());
+  let mut last_index = 0;
+  for placeholder in placeholders.iter() {
+    result.push_str(&text[last_index..placeholder.range.start]);
+    result.push_str(&values[placeholder.name]);
+    last_index = placeholder.range.end;
+  }
+  result.push_str(&text[last_index..]);
+  Ok(result)
+}
+
+/// Gets the `{{name}}` and `{{name:default value}}` placeholders in the text.
+fn get_placeholders(text: &str) -> Vec<Placeholder> {
+  let mut placeholders = Vec::new();
+  let mut search_index = 0;
+  while let Some(start) = text[search_index..].find("{{").map(|index| search_index + index) {
+    let end = match text[start..].find("}}") {
+      Some(index) => start + index + 2,
+      None => break,
+    };
+    let inner_text = &text[start + 2..end - 2];
+    let (name, default_value) = match inner_text.find(':') {
+      Some(index) => (inner_text[..index].trim(), Some(&inner_text[index + 1..])),
+      None => (inner_text.trim(), None),
+    };
+    if is_valid_placeholder_name(name) {
+      placeholders.push(Placeholder {
+        range: start..end,
+        name,
+        default_value,
+      });
+      search_index = end;
+    } else {
+      search_index = start + 2;
+    }
+  }
+  placeholders
+}
+
+fn is_valid_placeholder_name(name: &str) -> bool {
+  !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_get_config_file_from_named_template() {
+    let environment = TestEnvironment::new();
+    environment.add_remote_file(
+      "https://plugins.dprint.dev/templates/org.json",
+      r#"{
+  // {{ignored text}}
+  "lineWidth": {{lineWidth:120}},
+  "indentWidth": {{indentWidth}},
+  "typescript": {
+    "quoteStyle": "{{quoteStyle:preferDouble}}"
+  },
+  "json": {
+    "lineWidth": {{lineWidth}}
+  }
+}"#
+        .as_bytes(),
+    );
+    environment.set_text_input_results(vec!["", "4", "preferSingle"]);
+    let result = get_init_config_file_from_template("org", &environment).unwrap();
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec![
+        "Enter a value for lineWidth (default: 120):",
+        "Enter a value for indentWidth:",
+        "Enter a value for quoteStyle (default: preferDouble):",
+      ]
+    );
+    assert_eq!(
+      result.text,
+      r#"{
+  // {{ignored text}}
+  "lineWidth": 120,
+  "indentWidth": 4,
+  "typescript": {
+    "quoteStyle": "preferSingle"
+  },
+  "json": {
+    "lineWidth": 120
+  }
+}"#
+    );
+    assert!(result.selected_plugins.is_none());
+  }
+
+  #[test]
+  fn should_get_config_file_from_local_template() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/templates/base.json", r#"{ "lineWidth": 80 }"#).unwrap();
+    let result = get_init_config_file_from_template("/templates/base.json", &environment).unwrap();
+    assert_eq!(result.text, r#"{ "lineWidth": 80 }"#);
+  }
+
+  #[test]
+  fn should_error_when_template_not_found() {
+    let environment = TestEnvironment::new();
+    let err = get_init_config_file_from_template("https://dprint.dev/template.json", &environment)
+      .err()
+      .unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Error getting template 'https://dprint.dev/template.json'. Could not find file at url https://dprint.dev/template.json"
+    );
+  }
+
+  #[test]
+  fn should_error_when_filled_template_is_invalid() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/template.json", r#"{ "lineWidth": {{lineWidth}} }"#).unwrap();
+    let err = get_init_config_file_from_template("/template.json", &environment).err().unwrap();
+    assert!(err.to_string().starts_with("Error deserializing template '/template.json'."));
+    assert_eq!(environment.take_logged_errors(), vec!["Enter a value for lineWidth:"]);
+  }
+}
@@ -0,0 +1,466 @@
+use dprint_core::types::ErrBox;
+use jsonc_parser::ast::{ObjectPropName, Value};
+use jsonc_parser::{parse_to_ast, ParseOptions};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::environment::Environment;
+use crate::plugins::InfoFilePluginInfo;
+
+pub const VSCODE_EXTENSION_ID: &'static str = "dprint.dprint";
+
+/// File extensions used for the default configuration file when the latest plugin info can't be retrieved.
+const DEFAULT_FILE_EXTENSIONS: [&'static str; 5] = ["ts", "tsx", "js", "jsx", "json"];
+
+/// Writes or updates the `.vscode/settings.json` and `.vscode/extensions.json` files in the
+/// provided directory so the dprint extension is recommended and set as the default formatter
+/// for the languages of the selected plugins.
+///
+/// Existing files are updated by changing only the necessary parts of their text, which
+/// preserves their comments and the order of their properties.
+pub fn write_init_vscode_settings(
+  environment: &impl Environment,
+  dir_path: &Path,
+  selected_plugins: &Option<Vec<InfoFilePluginInfo>>,
+) -> Result<Vec<PathBuf>, ErrBox> {
+  let vscode_dir_path = dir_path.join(".vscode");
+  environment.mk_dir_all(&vscode_dir_path)?;
+
+  let settings_file_path = vscode_dir_path.join("settings.json");
+  let language_ids = get_vscode_language_ids(selected_plugins);
+  update_json_file(environment, &settings_file_path, |text| {
+    get_settings_text_changes(text, &settings_file_path, &language_ids)
+  })?;
+
+  let extensions_file_path = vscode_dir_path.join("extensions.json");
+  update_json_file(environment, &extensions_file_path, |text| {
+    get_extensions_text_changes(text, &extensions_file_path)
+  })?;
+
+  Ok(vec![settings_file_path, extensions_file_path])
+}
+
+/// A replacement of the text in a range of a file.
+struct TextChange {
+  range: Range<usize>,
+  new_text: String,
+}
+
+/// The lines of a property or element to insert, each with its number of indentation
+/// levels past the indentation of the first line.
+type InsertLines = Vec<(usize, String)>;
+
+fn update_json_file(environment: &impl Environment, file_path: &Path, get_changes: impl FnOnce(&str) -> Result<Vec<TextChange>, ErrBox>) -> Result<(), ErrBox> {
+  let file_exists = environment.path_exists(file_path);
+  let file_text = if file_exists { environment.read_file(file_path)? } else { String::new() };
+  let file_text = if file_text.trim().is_empty() { "{}\n".to_string() } else { file_text };
+  let mut changes = get_changes(&file_text)?;
+  if file_exists && changes.is_empty() {
+    return Ok(());
+  }
+
+  let mut new_text = file_text;
+  // apply from the end so the positions of the earlier changes stay the same
+  changes.sort_by_key(|change| change.range.start);
+  for change in changes.into_iter().rev() {
+    new_text.replace_range(change.range, &change.new_text);
+  }
+  environment.write_file(file_path, &new_text)
+}
+
+fn get_settings_text_changes(text: &str, file_path: &Path, language_ids: &[&'static str]) -> Result<Vec<TextChange>, ErrBox> {
+  let root = match parse_to_ast(
+    text,
+    &ParseOptions {
+      comments: false,
+      tokens: false,
+    },
+  ) {
+    Ok(parse_result) => match parse_result.value {
+      Some(Value::Object(root)) => root,
+      _ => return err!("Expected a root object in {}", file_path.display()),
+    },
+    Err(err) => return err!("Error parsing {}. {}", file_path.display(), err.to_string()),
+  };
+  let indent_unit = get_indent_unit(text);
+  let default_formatter_prop_text = format!("\"editor.defaultFormatter\": \"{}\"", VSCODE_EXTENSION_ID);
+  let mut changes = Vec::new();
+  let mut new_props = Vec::new();
+
+  for language_id in language_ids.iter() {
+    let language_key = format!("[{}]", language_id);
+    match root.properties.iter().find(|prop| get_prop_name(&prop.name) == language_key) {
+      Some(language_prop) => match &language_prop.value {
+        Value::Object(language_settings) => {
+          let formatter_prop = language_settings
+            .properties
+            .iter()
+            .find(|prop| get_prop_name(&prop.name) == "editor.defaultFormatter");
+          match formatter_prop {
+            Some(prop) if matches!(&prop.value, Value::StringLit(lit) if &*lit.value == VSCODE_EXTENSION_ID) => {}
+            Some(prop) => changes.push(TextChange {
+              range: prop.range.start..prop.range.end,
+              new_text: default_formatter_prop_text.clone(),
+            }),
+            None => changes.push(get_insert_change(
+              text,
+              language_settings.range.start..language_settings.range.end,
+              language_settings.properties.last().map(|prop| prop.range.start..prop.range.end),
+              vec![vec![(0, default_formatter_prop_text.clone())]],
+              indent_unit,
+            )),
+          }
+        }
+        _ => return err!("Expected property '{}' in {} to be an object.", language_key, file_path.display()),
+      },
+      None => new_props.push(vec![
+        (0, format!("\"{}\": {{", language_key)),
+        (1, default_formatter_prop_text.clone()),
+        (0, "}".to_string()),
+      ]),
+    }
+  }
+
+  if !new_props.is_empty() {
+    changes.push(get_insert_change(
+      text,
+      root.range.start..root.range.end,
+      root.properties.last().map(|prop| prop.range.start..prop.range.end),
+      new_props,
+      indent_unit,
+    ));
+  }
+  Ok(changes)
+}
+
+fn get_extensions_text_changes(text: &str, file_path: &Path) -> Result<Vec<TextChange>, ErrBox> {
+  let root = match parse_to_ast(
+    text,
+    &ParseOptions {
+      comments: false,
+      tokens: false,
+    },
+  ) {
+    Ok(parse_result) => match parse_result.value {
+      Some(Value::Object(root)) => root,
+      _ => return err!("Expected a root object in {}", file_path.display()),
+    },
+    Err(err) => return err!("Error parsing {}. {}", file_path.display(), err.to_string()),
+  };
+  let indent_unit = get_indent_unit(text);
+  let extension_id_text = format!("\"{}\"", VSCODE_EXTENSION_ID);
+
+  let change = match root.properties.iter().find(|prop| get_prop_name(&prop.name) == "recommendations") {
+    Some(prop) => match &prop.value {
+      Value::Array(recommendations) => {
+        let mut last_element_range = None;
+        for element in recommendations.elements.iter() {
+          match element {
+            Value::StringLit(lit) if &*lit.value == VSCODE_EXTENSION_ID => return Ok(Vec::new()),
+            Value::StringLit(lit) => last_element_range = Some(lit.range.start..lit.range.end),
+            _ => return err!("Expected property 'recommendations' in {} to be an array of strings.", file_path.display()),
+          }
+        }
+        get_insert_change(
+          text,
+          recommendations.range.start..recommendations.range.end,
+          last_element_range,
+          vec![vec![(0, extension_id_text)]],
+          indent_unit,
+        )
+      }
+      _ => return err!("Expected property 'recommendations' in {} to be an array.", file_path.display()),
+    },
+    None => get_insert_change(
+      text,
+      root.range.start..root.range.end,
+      root.properties.last().map(|prop| prop.range.start..prop.range.end),
+      vec![vec![(0, "\"recommendations\": [".to_string()), (1, extension_id_text), (0, "]".to_string())]],
+      indent_unit,
+    ),
+  };
+  Ok(vec![change])
+}
+
+/// Gets the change that inserts the items after the last item of the object or array, keeping
+/// the container on one line when it's written on one line.
+fn get_insert_change(
+  text: &str,
+  container_range: Range<usize>,
+  last_item_range: Option<Range<usize>>,
+  items: Vec<InsertLines>,
+  indent_unit: &str,
+) -> TextChange {
+  let new_line = if text.contains("\r\n") { "\r\n" } else { "\n" };
+  let inner_range = container_range.start + 1..container_range.end - 1;
+  match last_item_range {
+    Some(last_item_range) if !text[inner_range.clone()].contains('\n') => TextChange {
+      range: last_item_range.end..last_item_range.end,
+      new_text: items
+        .iter()
+        .map(|lines| format!(", {}", lines.iter().map(|(_, line)| line.as_str()).collect::<Vec<_>>().join(" ")))
+        .collect(),
+    },
+    Some(last_item_range) => {
+      let item_indent = get_line_indent(text, last_item_range.start);
+      TextChange {
+        range: last_item_range.end..last_item_range.end,
+        new_text: items
+          .iter()
+          .map(|lines| format!(",{}{}", new_line, get_lines_text(lines, item_indent, indent_unit, new_line)))
+          .collect(),
+      }
+    }
+    None => {
+      let container_indent = get_line_indent(text, container_range.start);
+      let item_indent = format!("{}{}", container_indent, indent_unit);
+      let items_text = items
+        .iter()
+        .map(|lines| get_lines_text(lines, &item_indent, indent_unit, new_line))
+        .collect::<Vec<_>>()
+        .join(&format!(",{}", new_line));
+      if text[inner_range.clone()].trim().is_empty() {
+        TextChange {
+          range: inner_range,
+          new_text: format!("{}{}{}{}", new_line, items_text, new_line, container_indent),
+        }
+      } else {
+        // the container only has comments, so keep them after the items
+        TextChange {
+          range: inner_range.start..inner_range.start,
+          new_text: format!("{}{}", new_line, items_text),
+        }
+      }
+    }
+  }
+}
+
+fn get_lines_text(lines: &InsertLines, indent: &str, indent_unit: &str, new_line: &str) -> String {
+  lines
+    .iter()
+    .map(|(level, line)| format!("{}{}{}", indent, indent_unit.repeat(*level), line))
+    .collect::<Vec<_>>()
+    .join(new_line)
+}
+
+fn get_prop_name<'a>(name: &'a ObjectPropName) -> &'a str {
+  match name {
+    ObjectPropName::String(lit) => &*lit.value,
+    ObjectPropName::Word(lit) => &*lit.value,
+  }
+}
+
+/// Gets the text of one level of indentation, which is the indentation of the first indented line.
+fn get_indent_unit(text: &str) -> &str {
+  text
+    .lines()
+    .filter(|line| !line.trim().is_empty())
+    .map(|line| &line[..line.len() - line.trim_start().len()])
+    .find(|indent| !indent.is_empty())
+    .unwrap_or("  ")
+}
+
+fn get_line_indent(text: &str, pos: usize) -> &str {
+  let line_start = text[..pos].rfind('\n').map(|index| index + 1).unwrap_or(0);
+  let line_text = &text[line_start..pos];
+  &line_text[..line_text.len() - line_text.trim_start().len()]
+}
+
+/// Gets the VS Code language identifiers for the file extensions and file names of the plugins.
+fn get_vscode_language_ids(selected_plugins: &Option<Vec<InfoFilePluginInfo>>) -> Vec<&'static str> {
+  let mut language_ids = Vec::new();
+  let mut add_language_id = |language_id: Option<&'static str>| {
+    if let Some(language_id) = language_id {
+      if !language_ids.contains(&language_id) {
+        language_ids.push(language_id);
+      }
+    }
+  };
+
+  if let Some(selected_plugins) = selected_plugins {
+    for plugin in selected_plugins.iter() {
+      for file_extension in plugin.file_extensions.iter() {
+        add_language_id(get_language_id_from_extension(&file_extension.to_lowercase()));
+      }
+      for file_name in plugin.file_names.iter() {
+        add_language_id(get_language_id_from_file_name(&file_name.to_lowercase()));
+      }
+    }
+  } else {
+    for file_extension in DEFAULT_FILE_EXTENSIONS.iter() {
+      add_language_id(get_language_id_from_extension(file_extension));
+    }
+  }
+
+  language_ids
+}
+
+fn get_language_id_from_extension(file_extension: &str) -> Option<&'static str> {
+  Some(match file_extension {
+    "ts" | "mts" | "cts" => "typescript",
+    "tsx" => "typescriptreact",
+    "js" | "mjs" | "cjs" => "javascript",
+    "jsx" => "javascriptreact",
+    "json" => "json",
+    "jsonc" => "jsonc",
+    "md" | "markdown" => "markdown",
+    "toml" => "toml",
+    "rs" => "rust",
+    "css" => "css",
+    "scss" => "scss",
+    "less" => "less",
+    "html" | "htm" => "html",
+    "vue" => "vue",
+    "svelte" => "svelte",
+    "yml" | "yaml" => "yaml",
+    "xml" => "xml",
+    "py" => "python",
+    "go" => "go",
+    "sql" => "sql",
+    "graphql" | "gql" => "graphql",
+    "dockerfile" => "dockerfile",
+    _ => return None,
+  })
+}
+
+fn get_language_id_from_file_name(file_name: &str) -> Option<&'static str> {
+  match file_name {
+    "dockerfile" => Some("dockerfile"),
+    _ => get_language_id_from_extension(file_name.rsplit('.').next().unwrap_or(file_name)),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_write_settings_when_no_files_exist() {
+    let environment = TestEnvironment::new();
+    let file_paths = write_init_vscode_settings(&environment, &PathBuf::from("/"), &Some(vec![create_plugin_info(vec!["ts", "tsx", "json"], vec![])])).unwrap();
+    assert_eq!(file_paths, vec![PathBuf::from("/.vscode/settings.json"), PathBuf::from("/.vscode/extensions.json")]);
+    assert_eq!(
+      environment.read_file("/.vscode/settings.json").unwrap(),
+      r#"{
+  "[typescript]": {
+    "editor.defaultFormatter": "dprint.dprint"
+  },
+  "[typescriptreact]": {
+    "editor.defaultFormatter": "dprint.dprint"
+  },
+  "[json]": {
+    "editor.defaultFormatter": "dprint.dprint"
+  }
+}
+"#
+    );
+    assert_eq!(
+      environment.read_file("/.vscode/extensions.json").unwrap(),
+      r#"{
+  "recommendations": [
+    "dprint.dprint"
+  ]
+}
+"#
+    );
+  }
+
+  #[test]
+  fn should_update_existing_settings_preserving_text() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        "/.vscode/settings.json",
+        r#"{
+  // some comment
+  "editor.formatOnSave": true,
+  "[typescript]": { "editor.tabSize": 4, "editor.defaultFormatter": "other.formatter" }
+}"#,
+      )
+      .unwrap();
+    environment
+      .write_file("/.vscode/extensions.json", r#"{ "recommendations": ["other.extension"] }"#)
+      .unwrap();
+    write_init_vscode_settings(&environment, &PathBuf::from("/"), &Some(vec![create_plugin_info(vec!["ts"], vec!["Dockerfile"])])).unwrap();
+    assert_eq!(
+      environment.read_file("/.vscode/settings.json").unwrap(),
+      r#"{
+  // some comment
+  "editor.formatOnSave": true,
+  "[typescript]": { "editor.tabSize": 4, "editor.defaultFormatter": "dprint.dprint" },
+  "[dockerfile]": {
+    "editor.defaultFormatter": "dprint.dprint"
+  }
+}"#
+    );
+    assert_eq!(
+      environment.read_file("/.vscode/extensions.json").unwrap(),
+      r#"{ "recommendations": ["other.extension", "dprint.dprint"] }"#
+    );
+  }
+
+  #[test]
+  fn should_insert_into_empty_objects_and_arrays() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file("/.vscode/settings.json", "{\n\t\"[json]\": {}\n\t// trailing comment\n}\n")
+      .unwrap();
+    environment.write_file("/.vscode/extensions.json", "{\n\t\"recommendations\": []\n}\n").unwrap();
+    write_init_vscode_settings(&environment, &PathBuf::from("/"), &Some(vec![create_plugin_info(vec!["json"], vec![])])).unwrap();
+    assert_eq!(
+      environment.read_file("/.vscode/settings.json").unwrap(),
+      "{\n\t\"[json]\": {\n\t\t\"editor.defaultFormatter\": \"dprint.dprint\"\n\t}\n\t// trailing comment\n}\n"
+    );
+    assert_eq!(
+      environment.read_file("/.vscode/extensions.json").unwrap(),
+      "{\n\t\"recommendations\": [\n\t\t\"dprint.dprint\"\n\t]\n}\n"
+    );
+  }
+
+  #[test]
+  fn should_not_change_files_already_set_up() {
+    let environment = TestEnvironment::new();
+    let settings_text = "{\n  // formatter\n  \"[json]\": { \"editor.defaultFormatter\": \"dprint.dprint\" }\n}\n";
+    let extensions_text = "{\n  \"recommendations\": [\"dprint.dprint\"] // recommended\n}\n";
+    environment.write_file("/.vscode/settings.json", settings_text).unwrap();
+    environment.write_file("/.vscode/extensions.json", extensions_text).unwrap();
+    write_init_vscode_settings(&environment, &PathBuf::from("/"), &Some(vec![create_plugin_info(vec!["json"], vec![])])).unwrap();
+    assert_eq!(environment.read_file("/.vscode/settings.json").unwrap(), settings_text);
+    assert_eq!(environment.read_file("/.vscode/extensions.json").unwrap(), extensions_text);
+  }
+
+  #[test]
+  fn should_use_default_extensions_when_no_plugin_info() {
+    let environment = TestEnvironment::new();
+    write_init_vscode_settings(&environment, &PathBuf::from("/"), &None).unwrap();
+    let text = environment.read_file("/.vscode/settings.json").unwrap();
+    for language_id in ["typescript", "typescriptreact", "javascript", "javascriptreact", "json"].iter() {
+      assert!(text.contains(&format!("\"[{}]\"", language_id)));
+    }
+  }
+
+  #[test]
+  fn should_error_when_language_settings_not_object() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/.vscode/settings.json", r#"{ "[typescript]": 5 }"#).unwrap();
+    let err = write_init_vscode_settings(&environment, &PathBuf::from("/"), &Some(vec![create_plugin_info(vec!["ts"], vec![])]))
+      .err()
+      .unwrap();
+    assert_eq!(err.to_string(), "Expected property '[typescript]' in /.vscode/settings.json to be an object.");
+  }
+
+  fn create_plugin_info(file_extensions: Vec<&str>, file_names: Vec<&str>) -> InfoFilePluginInfo {
+    InfoFilePluginInfo {
+      name: "dprint-plugin-test".to_string(),
+      version: "0.1.0".to_string(),
+      url: "https://plugins.dprint.dev/test-0.1.0.wasm".to_string(),
+      config_key: Some("test".to_string()),
+      file_extensions: file_extensions.into_iter().map(String::from).collect(),
+      file_names: file_names.into_iter().map(String::from).collect(),
+      config_excludes: Vec::new(),
+      checksum: None,
+    }
+  }
+}
@@ -1,11 +1,21 @@
+mod config_overrides;
 mod deserialize_config;
 mod get_global_config;
 mod get_init_config_file_text;
 mod get_plugin_config_map;
+mod init_template;
+mod init_vscode_settings;
+mod plugin_associations;
+mod prettier_compat;
 mod types;
 
+pub use config_overrides::*;
 pub use deserialize_config::*;
 pub use get_global_config::*;
 pub use get_init_config_file_text::*;
 pub use get_plugin_config_map::*;
+pub use init_template::*;
+pub use init_vscode_settings::*;
+pub use plugin_associations::*;
+pub use prettier_compat::*;
 pub use types::*;
@@ -0,0 +1,79 @@
+use dprint_core::types::ErrBox;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::utils::{get_bytes_hash, to_absolute_globs, GlobMatcher, GlobMatcherOptions};
+
+/// Matches files to the plugins whose configuration has an "associations" property.
+///
+/// These plugins only format the files matching their patterns instead of the files with
+/// their default file extensions and names.
+#[derive(Default)]
+pub struct PluginAssociations {
+  matchers: HashMap<String, GlobMatcher>,
+  hash: u64,
+}
+
+impl PluginAssociations {
+  /// Creates the matchers for each plugin's patterns, which are relative to the base path.
+  pub fn new(associations: &HashMap<String, Vec<String>>, base_path: &Path) -> Result<Self, ErrBox> {
+    let base_path = base_path.to_string_lossy();
+    let glob_matcher_options = GlobMatcherOptions {
+      case_insensitive: cfg!(windows),
+    };
+    let mut matchers = HashMap::with_capacity(associations.len());
+    for (config_key, patterns) in associations {
+      let patterns = to_absolute_globs(patterns.clone(), &base_path);
+      matchers.insert(config_key.clone(), GlobMatcher::new(&patterns, &glob_matcher_options)?);
+    }
+    let sorted_associations = associations.iter().collect::<BTreeMap<_, _>>();
+    Ok(PluginAssociations {
+      matchers,
+      hash: if associations.is_empty() {
+        0
+      } else {
+        get_bytes_hash(serde_json::to_string(&sorted_associations).unwrap().as_bytes())
+      },
+    })
+  }
+
+  /// Gets a hash of the associations to tell when they have changed. This is 0 when there are no associations.
+  pub fn get_hash(&self) -> u64 {
+    self.hash
+  }
+
+  /// Gets if the plugin's default file extensions and names were replaced by associations.
+  pub fn has_associations(&self, config_key: &str) -> bool {
+    self.matchers.contains_key(config_key)
+  }
+
+  /// Gets if the file path matches the associations of the plugin.
+  pub fn is_match(&self, config_key: &str, file_path: &Path) -> bool {
+    match self.matchers.get(config_key) {
+      Some(matcher) => matcher.is_match(file_path.to_string_lossy().replace("\\", "/")),
+      None => false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_match_plugin_associations() {
+    let mut associations = HashMap::new();
+    associations.insert(
+      "typescript".to_string(),
+      vec!["**/*.mjsx".to_string(), "Jenkinsfile".to_string(), "!legacy/**".to_string()],
+    );
+    let plugin_associations = PluginAssociations::new(&associations, Path::new("/project")).unwrap();
+    assert_eq!(plugin_associations.has_associations("typescript"), true);
+    assert_eq!(plugin_associations.has_associations("json"), false);
+    assert_eq!(plugin_associations.is_match("typescript", Path::new("/project/src/file.mjsx")), true);
+    assert_eq!(plugin_associations.is_match("typescript", Path::new("/project/ci/Jenkinsfile")), true);
+    assert_eq!(plugin_associations.is_match("typescript", Path::new("/project/legacy/file.mjsx")), false);
+    assert_eq!(plugin_associations.is_match("typescript", Path::new("/project/src/file.ts")), false);
+    assert_eq!(plugin_associations.is_match("json", Path::new("/project/src/file.mjsx")), false);
+  }
+}
@@ -0,0 +1,361 @@
+use dprint_core::configuration::{ConfigKeyMap, ConfigKeyValue};
+use dprint_core::types::ErrBox;
+use jsonc_parser::JsonValue;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{ConfigMap, ConfigMapValue};
+use crate::environment::Environment;
+
+const PRETTIER_CONFIG_FILE_NAMES: [&str; 2] = [".prettierrc", ".prettierrc.json"];
+const EDITOR_CONFIG_FILE_NAME: &str = ".editorconfig";
+
+/// Options recognized from a Prettier configuration file or an .editorconfig file.
+#[derive(Default, Debug, PartialEq)]
+struct CompatOptions {
+  print_width: Option<i32>,
+  tab_width: Option<i32>,
+  use_tabs: Option<bool>,
+  semi: Option<bool>,
+  single_quote: Option<bool>,
+}
+
+impl CompatOptions {
+  fn or(self, other: CompatOptions) -> CompatOptions {
+    CompatOptions {
+      print_width: self.print_width.or(other.print_width),
+      tab_width: self.tab_width.or(other.tab_width),
+      use_tabs: self.use_tabs.or(other.use_tabs),
+      semi: self.semi.or(other.semi),
+      single_quote: self.single_quote.or(other.single_quote),
+    }
+  }
+}
+
+/// Fills in configuration from the `.prettierrc` and `.editorconfig` files found in the
+/// provided directory. Properties already specified in the dprint configuration take precedence,
+/// followed by the Prettier configuration, then the .editorconfig.
+pub fn apply_prettier_compat(config_map: &mut ConfigMap, plugin_config_keys: &[String], dir_path: &Path, environment: &impl Environment) -> Result<(), ErrBox> {
+  let prettier_options = match read_prettier_options(dir_path, environment) {
+    Ok(options) => options,
+    Err(err) => return err!("Error reading Prettier configuration. {}", err.to_string()),
+  };
+  let editor_config_options = match read_editor_config_options(dir_path, environment) {
+    Ok(options) => options,
+    Err(err) => return err!("Error reading {}. {}", EDITOR_CONFIG_FILE_NAME, err.to_string()),
+  };
+  let options = prettier_options.or(editor_config_options);
+
+  // global config
+  set_if_not_exists(config_map, "lineWidth", options.print_width.map(ConfigKeyValue::from_i32));
+  set_if_not_exists(config_map, "indentWidth", options.tab_width.map(ConfigKeyValue::from_i32));
+  set_if_not_exists(config_map, "useTabs", options.use_tabs.map(ConfigKeyValue::from_bool));
+
+  // known plugin config
+  for plugin_config_key in plugin_config_keys {
+    let mut plugin_properties = Vec::new();
+    if plugin_config_key == "typescript" {
+      if let Some(semi) = options.semi {
+        plugin_properties.push(("semiColons", ConfigKeyValue::from_str(if semi { "prefer" } else { "asi" })));
+      }
+      if let Some(single_quote) = options.single_quote {
+        plugin_properties.push(("quoteStyle", ConfigKeyValue::from_str(if single_quote { "preferSingle" } else { "preferDouble" })));
+      }
+    }
+
+    if plugin_properties.is_empty() {
+      continue;
+    }
+
+    let plugin_config = config_map
+      .entry(plugin_config_key.to_string())
+      .or_insert_with(|| ConfigMapValue::HashMap(HashMap::new()));
+    if let ConfigMapValue::HashMap(plugin_config) = plugin_config {
+      for (key, value) in plugin_properties {
+        if !plugin_config.contains_key(key) {
+          plugin_config.insert(key.to_string(), value);
+        }
+      }
+    }
+  }
+
+  Ok(())
+}
+
+fn set_if_not_exists(config_map: &mut ConfigMap, key: &str, value: Option<ConfigKeyValue>) {
+  if let Some(value) = value {
+    if !config_map.contains_key(key) {
+      config_map.insert(key.to_string(), ConfigMapValue::KeyValue(value));
+    }
+  }
+}
+
+fn read_prettier_options(dir_path: &Path, environment: &impl Environment) -> Result<CompatOptions, ErrBox> {
+  for file_name in PRETTIER_CONFIG_FILE_NAMES.iter() {
+    let file_path = dir_path.join(file_name);
+    if environment.path_exists(&file_path) {
+      let file_text = environment.read_file(&file_path)?;
+      let properties = parse_prettier_config(&file_text)?;
+      return Ok(CompatOptions {
+        print_width: get_i32(&properties, "printWidth")?,
+        tab_width: get_i32(&properties, "tabWidth")?,
+        use_tabs: get_bool(&properties, "useTabs")?,
+        semi: get_bool(&properties, "semi")?,
+        single_quote: get_bool(&properties, "singleQuote")?,
+      });
+    }
+  }
+
+  Ok(CompatOptions::default())
+}
+
+/// Parses the top level properties of a Prettier configuration file, which may either be
+/// JSON or YAML. Only flat YAML files are supported.
+fn parse_prettier_config(text: &str) -> Result<ConfigKeyMap, ErrBox> {
+  let mut properties = HashMap::new();
+
+  if let Ok(Some(JsonValue::Object(obj))) = jsonc_parser::parse_to_value(text) {
+    for (key, value) in obj.into_iter() {
+      let value = match value {
+        JsonValue::Boolean(value) => ConfigKeyValue::from_bool(value),
+        JsonValue::String(value) => ConfigKeyValue::from_str(&value),
+        JsonValue::Number(value) => match value.parse::<i32>() {
+          Ok(value) => ConfigKeyValue::from_i32(value),
+          Err(_) => continue,
+        },
+        _ => continue, // ignore properties that aren't supported (ex. overrides)
+      };
+      properties.insert(key, value);
+    }
+    return Ok(properties);
+  }
+
+  for line in text.lines() {
+    // ignore comments, blank lines, and nested values
+    if line.trim().is_empty() || line.trim_start().starts_with('#') || line.starts_with(char::is_whitespace) || line.starts_with('-') {
+      continue;
+    }
+    let (key, value) = match line.find(':') {
+      Some(index) => (line[..index].trim(), line[index + 1..].trim()),
+      None => return err!("Could not parse line: {}", line),
+    };
+    if value.is_empty() {
+      continue;
+    }
+    properties.insert(key.to_string(), parse_scalar_value(value.trim_matches(|c| c == '"' || c == '\'')));
+  }
+
+  Ok(properties)
+}
+
+fn read_editor_config_options(dir_path: &Path, environment: &impl Environment) -> Result<CompatOptions, ErrBox> {
+  let file_path = dir_path.join(EDITOR_CONFIG_FILE_NAME);
+  if !environment.path_exists(&file_path) {
+    return Ok(CompatOptions::default());
+  }
+
+  let properties = parse_editor_config_wildcard_section(&environment.read_file(&file_path)?);
+  let use_tabs = match properties.get("indent_style") {
+    Some(ConfigKeyValue::String(value)) if value == "tab" => Some(true),
+    Some(ConfigKeyValue::String(value)) if value == "space" => Some(false),
+    _ => None,
+  };
+  let tab_width = match properties.get("indent_size") {
+    Some(ConfigKeyValue::Number(value)) => Some(*value),
+    _ => get_i32(&properties, "tab_width")?,
+  };
+  let print_width = match properties.get("max_line_length") {
+    Some(ConfigKeyValue::Number(value)) => Some(*value),
+    _ => None, // ex. "off"
+  };
+
+  Ok(CompatOptions {
+    print_width,
+    tab_width,
+    use_tabs,
+    semi: None,
+    single_quote: None,
+  })
+}
+
+/// Gets the properties of the `[*]` section, which applies to all files.
+fn parse_editor_config_wildcard_section(text: &str) -> ConfigKeyMap {
+  let mut properties = HashMap::new();
+  let mut is_in_wildcard_section = false;
+
+  for line in text.lines() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+      continue;
+    }
+    if line.starts_with('[') {
+      is_in_wildcard_section = line == "[*]";
+      continue;
+    }
+    if !is_in_wildcard_section {
+      continue;
+    }
+    if let Some(index) = line.find('=') {
+      let key = line[..index].trim().to_lowercase();
+      let value = line[index + 1..].trim().to_lowercase();
+      properties.insert(key, parse_scalar_value(&value));
+    }
+  }
+
+  properties
+}
+
+fn parse_scalar_value(value: &str) -> ConfigKeyValue {
+  match value {
+    "true" => ConfigKeyValue::from_bool(true),
+    "false" => ConfigKeyValue::from_bool(false),
+    _ => match value.parse::<i32>() {
+      Ok(value) => ConfigKeyValue::from_i32(value),
+      Err(_) => ConfigKeyValue::from_str(value),
+    },
+  }
+}
+
+fn get_i32(properties: &ConfigKeyMap, key: &str) -> Result<Option<i32>, ErrBox> {
+  match properties.get(key) {
+    Some(ConfigKeyValue::Number(value)) => Ok(Some(*value)),
+    Some(_) => err!("Expected '{}' to be a number.", key),
+    None => Ok(None),
+  }
+}
+
+fn get_bool(properties: &ConfigKeyMap, key: &str) -> Result<Option<bool>, ErrBox> {
+  match properties.get(key) {
+    Some(ConfigKeyValue::Bool(value)) => Ok(Some(*value)),
+    Some(_) => err!("Expected '{}' to be a boolean.", key),
+    None => Ok(None),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::environment::TestEnvironment;
+  use pretty_assertions::assert_eq;
+  use std::path::PathBuf;
+
+  use super::*;
+
+  #[test]
+  fn it_should_apply_prettier_json_config() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        "/project/.prettierrc",
+        r#"{
+          "printWidth": 100,
+          "tabWidth": 4,
+          "useTabs": true,
+          "semi": false,
+          "singleQuote": true,
+          "overrides": [{ "files": "*.md", "options": { "printWidth": 80 } }]
+        }"#,
+      )
+      .unwrap();
+    let mut config_map = HashMap::new();
+    apply_prettier_compat(&mut config_map, &[String::from("typescript")], &PathBuf::from("/project"), &environment).unwrap();
+
+    let mut expected_ts_config = HashMap::new();
+    expected_ts_config.insert(String::from("semiColons"), ConfigKeyValue::from_str("asi"));
+    expected_ts_config.insert(String::from("quoteStyle"), ConfigKeyValue::from_str("preferSingle"));
+    let mut expected = HashMap::new();
+    expected.insert(String::from("lineWidth"), ConfigMapValue::from_i32(100));
+    expected.insert(String::from("indentWidth"), ConfigMapValue::from_i32(4));
+    expected.insert(String::from("useTabs"), ConfigMapValue::from_bool(true));
+    expected.insert(String::from("typescript"), ConfigMapValue::HashMap(expected_ts_config));
+    assert_eq!(config_map, expected);
+  }
+
+  #[test]
+  fn it_should_not_override_existing_config() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/.prettierrc.json", r#"{ "printWidth": 100, "semi": false }"#).unwrap();
+    let mut ts_config = HashMap::new();
+    ts_config.insert(String::from("semiColons"), ConfigKeyValue::from_str("always"));
+    let mut config_map = HashMap::new();
+    config_map.insert(String::from("lineWidth"), ConfigMapValue::from_i32(80));
+    config_map.insert(String::from("typescript"), ConfigMapValue::HashMap(ts_config.clone()));
+    let expected = config_map.clone();
+
+    apply_prettier_compat(&mut config_map, &[String::from("typescript")], &PathBuf::from("/"), &environment).unwrap();
+    assert_eq!(config_map, expected);
+  }
+
+  #[test]
+  fn it_should_not_add_config_for_plugins_not_used() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/.prettierrc", r#"{ "semi": false }"#).unwrap();
+    let mut config_map = HashMap::new();
+    apply_prettier_compat(&mut config_map, &[String::from("json")], &PathBuf::from("/"), &environment).unwrap();
+    assert_eq!(config_map, HashMap::new());
+  }
+
+  #[test]
+  fn it_should_apply_prettier_yaml_config() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file(
+        "/.prettierrc",
+        r#"# comment
+printWidth: 90
+singleQuote: false
+overrides:
+  - files: "*.md"
+    options:
+      printWidth: 80
+"#,
+      )
+      .unwrap();
+    let mut config_map = HashMap::new();
+    apply_prettier_compat(&mut config_map, &[String::from("typescript")], &PathBuf::from("/"), &environment).unwrap();
+
+    let mut expected_ts_config = HashMap::new();
+    expected_ts_config.insert(String::from("quoteStyle"), ConfigKeyValue::from_str("preferDouble"));
+    let mut expected = HashMap::new();
+    expected.insert(String::from("lineWidth"), ConfigMapValue::from_i32(90));
+    expected.insert(String::from("typescript"), ConfigMapValue::HashMap(expected_ts_config));
+    assert_eq!(config_map, expected);
+  }
+
+  #[test]
+  fn it_should_apply_editor_config_below_prettier_config() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/.prettierrc", r#"{ "tabWidth": 4 }"#).unwrap();
+    environment
+      .write_file(
+        "/.editorconfig",
+        r#"root = true
+
+[*]
+indent_style = space
+indent_size = 2
+max_line_length = 120
+
+[*.md]
+max_line_length = off
+"#,
+      )
+      .unwrap();
+    let mut config_map = HashMap::new();
+    apply_prettier_compat(&mut config_map, &[], &PathBuf::from("/"), &environment).unwrap();
+
+    let mut expected = HashMap::new();
+    expected.insert(String::from("lineWidth"), ConfigMapValue::from_i32(120));
+    expected.insert(String::from("indentWidth"), ConfigMapValue::from_i32(4));
+    expected.insert(String::from("useTabs"), ConfigMapValue::from_bool(false));
+    assert_eq!(config_map, expected);
+  }
+
+  #[test]
+  fn it_should_error_for_invalid_prettier_property_type() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/.prettierrc", r#"{ "printWidth": "wide" }"#).unwrap();
+    let mut config_map = HashMap::new();
+    let err = apply_prettier_compat(&mut config_map, &[], &PathBuf::from("/"), &environment).err().unwrap();
+    assert_eq!(err.to_string(), "Error reading Prettier configuration. Expected 'printWidth' to be a number.");
+  }
+}
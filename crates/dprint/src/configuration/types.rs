@@ -5,7 +5,10 @@ use std::collections::HashMap;
 pub enum ConfigMapValue {
   KeyValue(ConfigKeyValue),
   HashMap(ConfigKeyMap),
+  /// A plugin's configuration that specifies the `associations` property.
+  PluginConfig(RawPluginConfig),
   Vec(Vec<String>),
+  MapVec(Vec<ConfigMap>),
 }
 
 impl ConfigMapValue {
@@ -24,3 +27,12 @@ impl ConfigMapValue {
 }
 
 pub type ConfigMap = HashMap<String, ConfigMapValue>;
+
+/// The configuration of a plugin along with the file patterns it should format.
+///
+/// The patterns are separate because the plugin configuration can't contain arrays.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RawPluginConfig {
+  pub associations: Vec<String>,
+  pub properties: ConfigKeyMap,
+}
@@ -1,8 +1,12 @@
+use dprint_cli_core::logging::ProgressBar;
+use dprint_cli_core::FetchResponse;
 use dprint_core::types::ErrBox;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
+use crate::messages::MessageCatalog;
 use crate::plugins::CompilationResult;
+use crate::utils::{FileRegionWrite, PhaseTimings, WarningCount};
 
 #[derive(Debug)]
 pub struct DirEntry {
@@ -16,13 +20,39 @@ pub enum DirEntryKind {
   File,
 }
 
+/// A file staged in the git repository of the current directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitStagedFile {
+  pub path: PathBuf,
+  /// If the file has changes in the working tree that aren't staged.
+  pub has_unstaged_changes: bool,
+}
+
+/// How the results of commands are output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+  /// Human readable text.
+  Text,
+  /// A JSON object per line for CI systems and other tools to parse.
+  Json,
+}
+
 pub trait Environment: Clone + std::marker::Send + std::marker::Sync + 'static {
   fn is_real(&self) -> bool;
   fn read_file(&self, file_path: impl AsRef<Path>) -> Result<String, ErrBox>;
   fn read_file_bytes(&self, file_path: impl AsRef<Path>) -> Result<Vec<u8>, ErrBox>;
   fn write_file(&self, file_path: impl AsRef<Path>, file_text: &str) -> Result<(), ErrBox>;
   fn write_file_bytes(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<(), ErrBox>;
+  /// Writes the text at the byte offsets of an existing file, then truncates or extends it to the length.
+  fn write_file_regions(&self, file_path: impl AsRef<Path>, writes: &[FileRegionWrite], len: usize) -> Result<(), ErrBox>;
+  /// Appends the text to the end of the file, creating it if it doesn't exist.
+  fn append_file(&self, file_path: impl AsRef<Path>, file_text: &str) -> Result<(), ErrBox>;
   fn remove_file(&self, file_path: impl AsRef<Path>) -> Result<(), ErrBox>;
+  /// Runs the action while holding an advisory lock on the file path, which
+  /// serializes writes to the file across dprint processes.
+  fn with_file_lock<TResult>(&self, file_path: impl AsRef<Path>, action: impl FnOnce() -> TResult) -> Result<TResult, ErrBox>;
+  /// Renames a file, replacing the destination if it exists.
+  fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<(), ErrBox>;
   fn remove_dir_all(&self, dir_path: impl AsRef<Path>) -> Result<(), ErrBox>;
   fn dir_info(&self, dir_path: impl AsRef<Path>) -> Result<Vec<DirEntry>, ErrBox>;
   fn path_exists(&self, file_path: impl AsRef<Path>) -> bool;
@@ -40,6 +70,15 @@ pub trait Environment: Clone + std::marker::Send + std::marker::Sync + 'static {
   fn log_error_with_context(&self, text: &str, context_name: &str);
   /// Information to output when logging is silent.
   fn log_silent(&self, text: &str);
+  /// Logs a warning, which fails the command when warnings are treated as errors.
+  fn log_warning(&self, text: &str) {
+    self.warning_count().increment();
+    self.log_error(text);
+  }
+  /// Logs the value as a single line of JSON. Used when the output format is JSON.
+  fn log_json(&self, value: &serde_json::Value) {
+    self.log(&value.to_string());
+  }
   fn log_action_with_progress<
     TResult: std::marker::Send + std::marker::Sync,
     TCreate: FnOnce(Box<dyn Fn(usize)>) -> TResult + std::marker::Send + std::marker::Sync,
@@ -49,13 +88,41 @@ pub trait Environment: Clone + std::marker::Send + std::marker::Sync + 'static {
     action: TCreate,
     total_size: usize,
   ) -> TResult;
+  /// Creates a progress bar for processing the specified number of files or returns `None` when progress bars aren't shown.
+  fn create_files_progress_bar(&self, message: &str, total_files: usize) -> Option<ProgressBar>;
   fn download_file(&self, url: &str) -> Result<Vec<u8>, ErrBox>;
+  /// Fetches the url with the provided request headers returning the response regardless of its status code.
+  /// The `Authorization` header is not sent when redirected to another origin.
+  fn fetch_url(&self, url: &str, headers: &[(&str, &str)]) -> Result<FetchResponse, ErrBox>;
+  /// Gets the added, copied, modified, and renamed files staged in the git repository of the current directory.
+  fn get_git_staged_files(&self) -> Result<Vec<GitStagedFile>, ErrBox>;
+  /// Adds the current contents of the files to the git index.
+  fn git_add(&self, file_paths: &[PathBuf]) -> Result<(), ErrBox>;
   fn get_cache_dir(&self) -> PathBuf;
   fn get_time_secs(&self) -> u64;
   fn get_selection(&self, prompt_message: &str, item_indent_width: u16, items: &Vec<String>) -> Result<usize, ErrBox>;
   fn get_multi_selection(&self, prompt_message: &str, item_indent_width: u16, items: &Vec<(bool, String)>) -> Result<Vec<usize>, ErrBox>;
+  /// Prompts for a line of text.
+  fn get_text_input(&self, prompt_message: &str) -> Result<String, ErrBox>;
   fn get_terminal_width(&self) -> u16;
+  /// Gets the maximum number of threads to use when formatting.
+  fn get_max_threads(&self) -> usize;
   fn is_verbose(&self) -> bool;
+  /// Gets if running in a continuous integration environment, which is detected via the `CI` environment variable.
+  fn is_ci(&self) -> bool;
+  /// Gets how command results should be output (`--output-format`).
+  fn output_format(&self) -> OutputFormat;
+  /// Runs an action that may be interrupted by SIGINT (ctrl+c) or SIGTERM, which the action should check via `is_interrupted`.
+  /// A second signal while running the action exits the process. Outside of these actions, signals exit the process.
+  fn run_interruptible<TResult>(&self, action: impl FnOnce() -> TResult) -> TResult;
+  /// Gets if an interruptible action was interrupted.
+  fn is_interrupted(&self) -> bool;
+  /// Gets the user facing messages for the selected locale.
+  fn messages(&self) -> &MessageCatalog;
+  /// Gets the durations of the phases of the run for the verbose output.
+  fn phase_timings(&self) -> &PhaseTimings;
+  /// Gets the number of warnings output during the run.
+  fn warning_count(&self) -> &WarningCount;
   fn compile_wasm(&self, wasm_bytes: &[u8]) -> Result<CompilationResult, ErrBox>;
   fn stdout(&self) -> Box<dyn Write + Send>;
   fn stdin(&self) -> Box<dyn Read + Send>;
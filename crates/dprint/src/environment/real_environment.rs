@@ -1,28 +1,72 @@
-use dprint_cli_core::download_url;
-use dprint_cli_core::logging::{log_action_with_progress, show_multi_select, show_select, Logger, ProgressBars};
+use dprint_cli_core::{download_url, fetch_url, FetchResponse};
+use dprint_cli_core::logging::{log_action_with_progress, show_multi_select, show_select, show_text_input, Logger, ProgressBar, ProgressBarStyle, ProgressBars};
 use dprint_core::types::ErrBox;
+use std::collections::HashSet;
 use std::fs;
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
-use super::{DirEntry, DirEntryKind, Environment};
+use super::{DirEntry, DirEntryKind, Environment, GitStagedFile, OutputFormat};
+use crate::messages::MessageCatalog;
 use crate::plugins::CompilationResult;
+use crate::utils::{get_bytes_hash, FileRegionWrite, PhaseTimings, WarningCount};
 
 #[derive(Clone)]
 pub struct RealEnvironment {
   logger: Logger,
   progress_bars: Option<ProgressBars>,
   is_verbose: bool,
+  is_ci: bool,
+  output_format: OutputFormat,
+  interrupt_signals: Arc<InterruptSignals>,
+  max_threads: usize,
+  messages: Arc<MessageCatalog>,
+  phase_timings: Arc<PhaseTimings>,
+  warning_count: Arc<WarningCount>,
 }
 
 impl RealEnvironment {
-  pub fn new(is_verbose: bool, is_silent: bool) -> Result<RealEnvironment, ErrBox> {
+  pub fn new(is_verbose: bool, is_silent: bool, output_format: OutputFormat) -> Result<RealEnvironment, ErrBox> {
+    RealEnvironment::new_with_interrupt_signals(is_verbose, is_silent, output_format, InterruptSignals::register()?)
+  }
+
+  /// Creates an environment that doesn't register SIGINT and SIGTERM handlers, which is
+  /// for when dprint is used as a library and the process's signals belong to the host.
+  pub fn new_without_signal_handlers(is_verbose: bool, is_silent: bool, output_format: OutputFormat) -> Result<RealEnvironment, ErrBox> {
+    RealEnvironment::new_with_interrupt_signals(is_verbose, is_silent, output_format, InterruptSignals::unregistered())
+  }
+
+  fn new_with_interrupt_signals(
+    is_verbose: bool,
+    is_silent: bool,
+    output_format: OutputFormat,
+    interrupt_signals: InterruptSignals,
+  ) -> Result<RealEnvironment, ErrBox> {
     let logger = Logger::new("dprint", is_silent);
-    let progress_bars = if is_silent { None } else { ProgressBars::new(&logger) };
+    let is_ci = get_is_ci();
+    // progress bars only add noise to the logs in CI and to output that's parsed
+    let progress_bars = if is_silent || is_ci || output_format == OutputFormat::Json {
+      None
+    } else {
+      ProgressBars::new(&logger)
+    };
     let environment = RealEnvironment {
       logger,
       progress_bars,
       is_verbose,
+      is_ci,
+      output_format,
+      interrupt_signals: Arc::new(interrupt_signals),
+      max_threads: get_max_threads()?,
+      messages: Arc::new(MessageCatalog::from_env_vars(
+        |var_name| std::env::var(var_name).ok(),
+        |file_path| Ok(fs::read_to_string(file_path)?),
+      )?),
+      phase_timings: Arc::new(PhaseTimings::default()),
+      warning_count: Arc::new(WarningCount::default()),
     };
 
     // ensure the cache directory is created
@@ -63,6 +107,33 @@ impl Environment for RealEnvironment {
     }
   }
 
+  fn write_file_regions(&self, file_path: impl AsRef<Path>, writes: &[FileRegionWrite], len: usize) -> Result<(), ErrBox> {
+    log_verbose!(self, "Writing {} region(s) of file: {}", writes.len(), file_path.as_ref().display());
+    let result = fs::OpenOptions::new().write(true).open(&file_path).and_then(|mut file| {
+      for write in writes {
+        file.seek(SeekFrom::Start(write.offset as u64))?;
+        file.write_all(write.text.as_bytes())?;
+      }
+      file.set_len(len as u64)
+    });
+    match result {
+      Ok(_) => Ok(()),
+      Err(err) => err!("Error writing file {}: {}", file_path.as_ref().display(), err.to_string()),
+    }
+  }
+
+  fn append_file(&self, file_path: impl AsRef<Path>, file_text: &str) -> Result<(), ErrBox> {
+    let result = fs::OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&file_path)
+      .and_then(|mut file| file.write_all(file_text.as_bytes()));
+    match result {
+      Ok(_) => Ok(()),
+      Err(err) => err!("Error appending to file {}: {}", file_path.as_ref().display(), err.to_string()),
+    }
+  }
+
   fn remove_file(&self, file_path: impl AsRef<Path>) -> Result<(), ErrBox> {
     log_verbose!(self, "Deleting file: {}", file_path.as_ref().display());
     match fs::remove_file(&file_path) {
@@ -72,6 +143,22 @@ impl Environment for RealEnvironment {
     }
   }
 
+  fn with_file_lock<TResult>(&self, file_path: impl AsRef<Path>, action: impl FnOnce() -> TResult) -> Result<TResult, ErrBox> {
+    // canonicalize so different spellings of the same file path use the same lock
+    let file_path = self.canonicalize(&file_path).unwrap_or_else(|_| file_path.as_ref().to_path_buf());
+    let lock_path = get_file_lock_path(&self.get_cache_dir(), &file_path);
+    let _lock = FileLock::acquire(lock_path)?;
+    Ok(action())
+  }
+
+  fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<(), ErrBox> {
+    log_verbose!(self, "Renaming file: {} to {}", from.as_ref().display(), to.as_ref().display());
+    match fs::rename(&from, &to) {
+      Ok(_) => Ok(()),
+      Err(err) => err!("Error renaming file {} to {}: {}", from.as_ref().display(), to.as_ref().display(), err.to_string()),
+    }
+  }
+
   fn remove_dir_all(&self, dir_path: impl AsRef<Path>) -> Result<(), ErrBox> {
     log_verbose!(self, "Deleting directory: {}", dir_path.as_ref().display());
     match fs::remove_dir_all(&dir_path) {
@@ -87,6 +174,12 @@ impl Environment for RealEnvironment {
     download_url(url, &self.progress_bars, |env_var_name| std::env::var(env_var_name).ok())
   }
 
+  fn fetch_url(&self, url: &str, headers: &[(&str, &str)]) -> Result<FetchResponse, ErrBox> {
+    log_verbose!(self, "Fetching url: {}", url);
+
+    fetch_url(url, headers, &self.progress_bars, |env_var_name| std::env::var(env_var_name).ok())
+  }
+
   fn dir_info(&self, dir_path: impl AsRef<Path>) -> Result<Vec<DirEntry>, ErrBox> {
     let mut entries = Vec::new();
 
@@ -159,6 +252,40 @@ impl Environment for RealEnvironment {
     log_action_with_progress(&self.progress_bars, message, action, total_size)
   }
 
+  fn create_files_progress_bar(&self, message: &str, total_files: usize) -> Option<ProgressBar> {
+    self
+      .progress_bars
+      .as_ref()
+      .map(|progress_bars| progress_bars.add_progress(message.to_string(), ProgressBarStyle::Files, total_files))
+  }
+
+  fn get_git_staged_files(&self) -> Result<Vec<GitStagedFile>, ErrBox> {
+    // the paths git outputs are relative to the root of the repository
+    let root_dir_path = PathBuf::from(run_git(&["rev-parse", "--show-toplevel"])?.trim_end());
+    let unstaged_output = run_git(&["diff", "--name-only", "-z"])?;
+    let unstaged_file_paths = unstaged_output.split('\0').collect::<HashSet<_>>();
+    let staged_output = run_git(&["diff", "--cached", "--name-only", "-z", "--diff-filter=ACMR"])?;
+    Ok(
+      staged_output
+        .split('\0')
+        .filter(|file_path| !file_path.is_empty())
+        .map(|file_path| GitStagedFile {
+          path: root_dir_path.join(file_path),
+          has_unstaged_changes: unstaged_file_paths.contains(file_path),
+        })
+        .collect(),
+    )
+  }
+
+  fn git_add(&self, file_paths: &[PathBuf]) -> Result<(), ErrBox> {
+    log_verbose!(self, "Staging {} file(s) in git.", file_paths.len());
+    let mut args = vec!["add", "--"];
+    let file_paths = file_paths.iter().map(|file_path| file_path.to_string_lossy()).collect::<Vec<_>>();
+    args.extend(file_paths.iter().map(|file_path| file_path.as_ref()));
+    run_git(&args)?;
+    Ok(())
+  }
+
   fn get_cache_dir(&self) -> PathBuf {
     // this would have errored in the constructor so it's ok to unwrap here
     get_cache_dir().unwrap()
@@ -182,15 +309,54 @@ impl Environment for RealEnvironment {
     )
   }
 
+  fn get_text_input(&self, prompt_message: &str) -> Result<String, ErrBox> {
+    show_text_input(&self.logger, "dprint", prompt_message)
+  }
+
   fn get_terminal_width(&self) -> u16 {
     dprint_cli_core::terminal::get_terminal_width().unwrap_or(60)
   }
 
+  fn get_max_threads(&self) -> usize {
+    self.max_threads
+  }
+
   #[inline]
   fn is_verbose(&self) -> bool {
     self.is_verbose
   }
 
+  fn is_ci(&self) -> bool {
+    self.is_ci
+  }
+
+  fn output_format(&self) -> OutputFormat {
+    self.output_format
+  }
+
+  fn run_interruptible<TResult>(&self, action: impl FnOnce() -> TResult) -> TResult {
+    self.interrupt_signals.exit_on_signal.store(false, Ordering::SeqCst);
+    let result = action();
+    self.interrupt_signals.exit_on_signal.store(true, Ordering::SeqCst);
+    result
+  }
+
+  fn is_interrupted(&self) -> bool {
+    self.interrupt_signals.is_interrupted.load(Ordering::SeqCst)
+  }
+
+  fn messages(&self) -> &MessageCatalog {
+    &self.messages
+  }
+
+  fn phase_timings(&self) -> &PhaseTimings {
+    &self.phase_timings
+  }
+
+  fn warning_count(&self) -> &WarningCount {
+    &self.warning_count
+  }
+
   fn compile_wasm(&self, wasm_bytes: &[u8]) -> Result<CompilationResult, ErrBox> {
     crate::plugins::compile_wasm(wasm_bytes)
   }
@@ -246,7 +412,77 @@ impl Environment for RealEnvironment {
   }
 }
 
+/// How long to wait on a lock held by another process before giving up.
+const FILE_LOCK_TIMEOUT: Duration = Duration::from_secs(60);
+/// Lock files older than this were left behind by a process that exited without cleaning up.
+const STALE_FILE_LOCK_DURATION: Duration = Duration::from_secs(30);
+
+fn get_file_lock_path(cache_dir: &Path, file_path: &Path) -> PathBuf {
+  let hash = get_bytes_hash(file_path.to_string_lossy().as_bytes());
+  cache_dir.join("locks").join(format!("{:x}.lock", hash))
+}
+
+/// An advisory lock that's held for as long as the lock file exists. The file is removed on drop.
+struct FileLock {
+  lock_path: PathBuf,
+}
+
+impl FileLock {
+  pub fn acquire(lock_path: PathBuf) -> Result<FileLock, ErrBox> {
+    let start_instant = Instant::now();
+    loop {
+      match fs::OpenOptions::new().write(true).create_new(true).open(&lock_path) {
+        Ok(_) => return Ok(FileLock { lock_path }),
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+          if let Some(parent) = lock_path.parent() {
+            fs::create_dir_all(parent)?;
+          }
+        }
+        // windows may give permission denied while the other process is deleting the file
+        Err(err) if err.kind() == ErrorKind::AlreadyExists || err.kind() == ErrorKind::PermissionDenied => {
+          if is_stale_lock_file(&lock_path) {
+            let _ = fs::remove_file(&lock_path);
+          } else if start_instant.elapsed() > FILE_LOCK_TIMEOUT {
+            return err!("Timed out waiting for the lock at {}.", lock_path.display());
+          } else {
+            std::thread::sleep(Duration::from_millis(10));
+          }
+        }
+        Err(err) => return err!("Error creating lock file {}: {}", lock_path.display(), err.to_string()),
+      }
+    }
+  }
+}
+
+impl Drop for FileLock {
+  fn drop(&mut self) {
+    let _ = fs::remove_file(&self.lock_path);
+  }
+}
+
+fn is_stale_lock_file(lock_path: &Path) -> bool {
+  fs::metadata(lock_path)
+    .and_then(|metadata| metadata.modified())
+    .ok()
+    .and_then(|modified| modified.elapsed().ok())
+    .map(|elapsed| elapsed > STALE_FILE_LOCK_DURATION)
+    .unwrap_or(false)
+}
+
 const CACHE_DIR_ENV_VAR_NAME: &str = "DPRINT_CACHE_DIR";
+const MAX_THREADS_ENV_VAR_NAME: &str = "DPRINT_MAX_THREADS";
+const CI_ENV_VAR_NAME: &str = "CI";
+
+fn run_git(args: &[&str]) -> Result<String, ErrBox> {
+  let output = match std::process::Command::new("git").args(args).output() {
+    Ok(output) => output,
+    Err(err) => return err!("Error running git. Is it installed? {}", err),
+  };
+  if !output.status.success() {
+    return err!("Error running `git {}`: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(String::from_utf8(output.stdout)?)
+}
 
 fn get_cache_dir() -> Result<PathBuf, ErrBox> {
   get_cache_dir_internal(|var_name| std::env::var(var_name).ok())
@@ -271,6 +507,108 @@ fn get_cache_dir_internal(get_env_var: impl Fn(&str) -> Option<String>) -> Resul
   }
 }
 
+fn get_max_threads() -> Result<usize, ErrBox> {
+  get_max_threads_internal(|var_name| std::env::var(var_name).ok(), get_available_cpu_count)
+}
+
+fn get_max_threads_internal(get_env_var: impl Fn(&str) -> Option<String>, get_cpu_count: impl Fn() -> usize) -> Result<usize, ErrBox> {
+  if let Some(value) = get_env_var(MAX_THREADS_ENV_VAR_NAME) {
+    if !value.trim().is_empty() {
+      return match value.trim().parse::<usize>() {
+        Ok(max_threads) if max_threads > 0 => Ok(max_threads),
+        _ => err!("The {} environment variable must specify a positive integer.", MAX_THREADS_ENV_VAR_NAME),
+      };
+    }
+  }
+
+  Ok(std::cmp::max(1, get_cpu_count()))
+}
+
+/// Handles SIGINT and SIGTERM so that interruptible actions can stop gracefully.
+struct InterruptSignals {
+  is_interrupted: Arc<AtomicBool>,
+  exit_on_signal: Arc<AtomicBool>,
+}
+
+impl InterruptSignals {
+  /// Gets flags that no signal handler sets, so actions are never interrupted.
+  fn unregistered() -> Self {
+    InterruptSignals {
+      is_interrupted: Arc::new(AtomicBool::new(false)),
+      exit_on_signal: Arc::new(AtomicBool::new(true)),
+    }
+  }
+
+  fn register() -> Result<Self, ErrBox> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+
+    let signals = InterruptSignals::unregistered();
+    for signal in [SIGINT, SIGTERM].iter().copied() {
+      // the order is important here as the handlers are run in order of registration
+      signal_hook::flag::register_conditional_shutdown(signal, 128 + signal, signals.exit_on_signal.clone())?;
+      signal_hook::flag::register(signal, signals.is_interrupted.clone())?;
+      // exit on the next signal
+      signal_hook::flag::register(signal, signals.exit_on_signal.clone())?;
+    }
+    Ok(signals)
+  }
+}
+
+fn get_is_ci() -> bool {
+  get_is_ci_internal(|var_name| std::env::var(var_name).ok())
+}
+
+fn get_is_ci_internal(get_env_var: impl Fn(&str) -> Option<String>) -> bool {
+  match get_env_var(CI_ENV_VAR_NAME) {
+    // most CI services set this to "true", but some set it to other values (ex. "1" or "woodpecker")
+    Some(value) => {
+      let value = value.trim().to_lowercase();
+      !value.is_empty() && value != "false" && value != "0"
+    }
+    None => false,
+  }
+}
+
+/// Gets the number of logical cores (same as Rayon) limited by the cgroup's CPU quota.
+fn get_available_cpu_count() -> usize {
+  // num_cpus only takes into account cgroups v1 quotas
+  let cpu_count = num_cpus::get();
+  match get_cgroup_v2_cpu_limit() {
+    Some(cpu_limit) => std::cmp::min(cpu_count, cpu_limit),
+    None => cpu_count,
+  }
+}
+
+#[cfg(target_os = "linux")]
+fn get_cgroup_v2_cpu_limit() -> Option<usize> {
+  // the unified hierarchy is listed as "0::/path/to/cgroup"
+  let cgroup_path = fs::read_to_string("/proc/self/cgroup")
+    .ok()?
+    .lines()
+    .find_map(|line| line.strip_prefix("0::").map(|path| path.trim().trim_start_matches('/').to_string()))?;
+  let cgroup_dir = PathBuf::from("/sys/fs/cgroup").join(cgroup_path);
+  let cpu_max_text = fs::read_to_string(cgroup_dir.join("cpu.max")).or_else(|_| fs::read_to_string("/sys/fs/cgroup/cpu.max")).ok()?;
+  parse_cgroup_v2_cpu_max(&cpu_max_text)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_cgroup_v2_cpu_limit() -> Option<usize> {
+  None
+}
+
+/// Parses the contents of a cgroup v2 cpu.max file (ex. "200000 100000" or "max 100000").
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_cgroup_v2_cpu_max(text: &str) -> Option<usize> {
+  let mut parts = text.split_whitespace();
+  let quota = parts.next()?.parse::<u64>().ok()?; // will be "max" when not limited
+  let period = parts.next().and_then(|period| period.parse::<u64>().ok()).unwrap_or(100_000);
+  if quota == 0 || period == 0 {
+    return None;
+  }
+  // round up so a quota of 1.5 CPUs will use 2 threads
+  Some(std::cmp::max(1, ((quota + period - 1) / period) as usize))
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -297,4 +635,65 @@ mod test {
       "The DPRINT_CACHE_DIR environment variable must specify an absolute path."
     );
   }
+
+  #[test]
+  fn should_get_max_threads_based_on_env_var() {
+    assert_eq!(get_max_threads_internal(|_| Some("2".to_string()), || 8).unwrap(), 2);
+    assert_eq!(get_max_threads_internal(|_| Some(" 16 ".to_string()), || 8).unwrap(), 16);
+    assert_eq!(get_max_threads_internal(|_| Some("".to_string()), || 8).unwrap(), 8);
+    assert_eq!(get_max_threads_internal(|_| None, || 8).unwrap(), 8);
+    assert_eq!(get_max_threads_internal(|_| None, || 0).unwrap(), 1);
+  }
+
+  #[test]
+  fn should_error_when_max_threads_env_var_invalid() {
+    for value in ["0", "-1", "two"].iter() {
+      let result = get_max_threads_internal(|_| Some(value.to_string()), || 8).err();
+      assert_eq!(
+        result.unwrap().to_string(),
+        "The DPRINT_MAX_THREADS environment variable must specify a positive integer."
+      );
+    }
+  }
+
+  #[test]
+  fn should_get_is_ci_based_on_env_var() {
+    assert_eq!(get_is_ci_internal(|_| Some("true".to_string())), true);
+    assert_eq!(get_is_ci_internal(|_| Some("1".to_string())), true);
+    assert_eq!(get_is_ci_internal(|_| Some("woodpecker".to_string())), true);
+    assert_eq!(get_is_ci_internal(|_| Some("False".to_string())), false);
+    assert_eq!(get_is_ci_internal(|_| Some("0".to_string())), false);
+    assert_eq!(get_is_ci_internal(|_| Some(" ".to_string())), false);
+    assert_eq!(get_is_ci_internal(|_| None), false);
+  }
+
+  #[test]
+  fn should_hold_file_lock_until_dropped() {
+    let lock_path = get_file_lock_path(&std::env::temp_dir().join("dprint-test-locks"), Path::new("/project/file.txt"));
+    let lock = FileLock::acquire(lock_path.clone()).unwrap();
+    assert!(lock_path.exists());
+    // another lock for the same file waits until the first is released
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let handle = std::thread::spawn({
+      let lock_path = lock_path.clone();
+      move || {
+        let lock = FileLock::acquire(lock_path).unwrap();
+        sender.send(()).unwrap();
+        lock
+      }
+    });
+    assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+    drop(lock);
+    drop(handle.join().unwrap());
+    assert!(!lock_path.exists());
+  }
+
+  #[test]
+  fn should_parse_cgroup_v2_cpu_max() {
+    assert_eq!(parse_cgroup_v2_cpu_max("max 100000\n"), None);
+    assert_eq!(parse_cgroup_v2_cpu_max("200000 100000\n"), Some(2));
+    assert_eq!(parse_cgroup_v2_cpu_max("150000 100000"), Some(2));
+    assert_eq!(parse_cgroup_v2_cpu_max("50000 100000"), Some(1));
+    assert_eq!(parse_cgroup_v2_cpu_max(""), None);
+  }
 }
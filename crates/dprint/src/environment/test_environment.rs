@@ -1,14 +1,18 @@
+use dprint_cli_core::logging::ProgressBar;
+use dprint_cli_core::FetchResponse;
 use dprint_core::types::ErrBox;
 use parking_lot::Mutex;
 use path_clean::PathClean;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::{Error, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::Arc;
 
-use super::{DirEntry, DirEntryKind, Environment};
+use super::{DirEntry, DirEntryKind, Environment, GitStagedFile, OutputFormat};
+use crate::messages::MessageCatalog;
 use crate::plugins::CompilationResult;
+use crate::utils::{FileRegionWrite, PhaseTimings, WarningCount};
 
 struct BufferData {
   data: Vec<u8>,
@@ -34,19 +38,24 @@ impl MockStdInOut {
 }
 
 impl Read for MockStdInOut {
-  fn read(&mut self, _: &mut [u8]) -> Result<usize, Error> {
-    panic!("Not implemented");
-  }
-
-  fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
-    let rx = self.receiver.lock();
-    rx.recv().unwrap();
-
-    let mut buffer_data = self.buffer_data.lock();
-    buf.copy_from_slice(&buffer_data.data[buffer_data.read_pos..buffer_data.read_pos + buf.len()]);
-    buffer_data.read_pos += buf.len();
+  fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+    if buf.is_empty() {
+      return Ok(0);
+    }
 
-    Ok(())
+    loop {
+      {
+        let mut buffer_data = self.buffer_data.lock();
+        let len = std::cmp::min(buf.len(), buffer_data.data.len() - buffer_data.read_pos);
+        if len > 0 {
+          buf[..len].copy_from_slice(&buffer_data.data[buffer_data.read_pos..buffer_data.read_pos + len]);
+          buffer_data.read_pos += len;
+          return Ok(len);
+        }
+      }
+      // block until more data is written
+      self.receiver.lock().recv().unwrap();
+    }
   }
 }
 
@@ -71,13 +80,24 @@ pub struct TestEnvironment {
   is_verbose: Arc<Mutex<bool>>,
   cwd: Arc<Mutex<String>>,
   files: Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>,
+  symlinks: Arc<Mutex<HashMap<PathBuf, PathBuf>>>,
   logged_messages: Arc<Mutex<Vec<String>>>,
   logged_errors: Arc<Mutex<Vec<String>>>,
   remote_files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
   deleted_directories: Arc<Mutex<Vec<PathBuf>>>,
   selection_result: Arc<Mutex<usize>>,
   multi_selection_result: Arc<Mutex<Vec<usize>>>,
+  text_input_results: Arc<Mutex<VecDeque<String>>>,
   is_silent: Arc<Mutex<bool>>,
+  is_ci: Arc<Mutex<bool>>,
+  output_format: Arc<Mutex<OutputFormat>>,
+  is_interrupted: Arc<Mutex<bool>>,
+  locked_files: Arc<Mutex<HashSet<PathBuf>>>,
+  git_staged_files: Arc<Mutex<Option<Vec<GitStagedFile>>>>,
+  git_added_files: Arc<Mutex<Vec<PathBuf>>>,
+  messages: Arc<MessageCatalog>,
+  phase_timings: Arc<PhaseTimings>,
+  warning_count: Arc<WarningCount>,
   wasm_compile_result: Arc<Mutex<Option<CompilationResult>>>,
   std_in: MockStdInOut,
   std_out: MockStdInOut,
@@ -91,13 +111,24 @@ impl TestEnvironment {
       is_verbose: Arc::new(Mutex::new(false)),
       cwd: Arc::new(Mutex::new(String::from("/"))),
       files: Arc::new(Mutex::new(HashMap::new())),
+      symlinks: Arc::new(Mutex::new(HashMap::new())),
       logged_messages: Arc::new(Mutex::new(Vec::new())),
       logged_errors: Arc::new(Mutex::new(Vec::new())),
       remote_files: Arc::new(Mutex::new(HashMap::new())),
       deleted_directories: Arc::new(Mutex::new(Vec::new())),
       selection_result: Arc::new(Mutex::new(0)),
       multi_selection_result: Arc::new(Mutex::new(Vec::new())),
+      text_input_results: Arc::new(Mutex::new(VecDeque::new())),
       is_silent: Arc::new(Mutex::new(false)),
+      is_ci: Arc::new(Mutex::new(false)),
+      output_format: Arc::new(Mutex::new(OutputFormat::Text)),
+      is_interrupted: Arc::new(Mutex::new(false)),
+      locked_files: Arc::new(Mutex::new(HashSet::new())),
+      git_staged_files: Arc::new(Mutex::new(None)),
+      git_added_files: Arc::new(Mutex::new(Vec::new())),
+      messages: Arc::new(MessageCatalog::default()),
+      phase_timings: Arc::new(PhaseTimings::default()),
+      warning_count: Arc::new(WarningCount::default()),
       wasm_compile_result: Arc::new(Mutex::new(None)),
       std_in: MockStdInOut::new(),
       std_out: MockStdInOut::new(),
@@ -128,6 +159,13 @@ impl TestEnvironment {
     remote_files.insert(String::from(path), bytes);
   }
 
+  /// Adds a symlink to a file at the provided path that points to the target path.
+  pub fn add_symlink(&self, path: impl AsRef<Path>, target: impl AsRef<Path>) {
+    let path = self.clean_path(path);
+    let target = self.clean_path(target);
+    self.symlinks.lock().insert(path, target);
+  }
+
   pub fn is_dir_deleted(&self, path: impl AsRef<Path>) -> bool {
     let deleted_directories = self.deleted_directories.lock();
     deleted_directories.contains(&path.as_ref().to_path_buf())
@@ -143,6 +181,12 @@ impl TestEnvironment {
     *multi_selection_result = indexes;
   }
 
+  /// Sets the text to return for each subsequent text input prompt.
+  pub fn set_text_input_results(&self, values: Vec<&str>) {
+    let mut text_input_results = self.text_input_results.lock();
+    *text_input_results = values.into_iter().map(String::from).collect();
+  }
+
   pub fn set_cwd(&self, new_path: &str) {
     let mut cwd = self.cwd.lock();
     *cwd = String::from(new_path);
@@ -158,6 +202,31 @@ impl TestEnvironment {
     *is_verbose = value;
   }
 
+  pub fn set_ci(&self, value: bool) {
+    let mut is_ci = self.is_ci.lock();
+    *is_ci = value;
+  }
+
+  pub fn set_output_format(&self, value: OutputFormat) {
+    let mut output_format = self.output_format.lock();
+    *output_format = value;
+  }
+
+  /// Sets the files staged in git. Getting the staged files errors when not set, like outside a git repository.
+  pub fn set_git_staged_files(&self, staged_files: Vec<GitStagedFile>) {
+    let mut git_staged_files = self.git_staged_files.lock();
+    *git_staged_files = Some(staged_files);
+  }
+
+  pub fn take_git_added_files(&self) -> Vec<PathBuf> {
+    self.git_added_files.lock().drain(..).collect()
+  }
+
+  pub fn set_interrupted(&self, value: bool) {
+    let mut is_interrupted = self.is_interrupted.lock();
+    *is_interrupted = value;
+  }
+
   pub fn set_wasm_compile_result(&self, value: CompilationResult) {
     let mut wasm_compile_result = self.wasm_compile_result.lock();
     *wasm_compile_result = Some(value);
@@ -186,6 +255,14 @@ impl TestEnvironment {
     }
     .clean()
   }
+
+  fn resolve_symlink(&self, path: impl AsRef<Path>) -> PathBuf {
+    let path = self.clean_path(path);
+    match self.symlinks.lock().get(&path) {
+      Some(target) => target.clone(),
+      None => path,
+    }
+  }
 }
 
 impl Drop for TestEnvironment {
@@ -218,7 +295,7 @@ impl Environment for TestEnvironment {
   }
 
   fn read_file_bytes(&self, file_path: impl AsRef<Path>) -> Result<Vec<u8>, ErrBox> {
-    let file_path = self.clean_path(file_path);
+    let file_path = self.resolve_symlink(file_path);
     let files = self.files.lock();
     match files.get(&file_path) {
       Some(text) => Ok(text.clone()),
@@ -237,6 +314,31 @@ impl Environment for TestEnvironment {
     Ok(())
   }
 
+  fn write_file_regions(&self, file_path: impl AsRef<Path>, writes: &[FileRegionWrite], len: usize) -> Result<(), ErrBox> {
+    let file_path = self.clean_path(file_path);
+    let mut files = self.files.lock();
+    let bytes = match files.get_mut(&file_path) {
+      Some(bytes) => bytes,
+      None => return err!("Could not find file at path {}", file_path.display()),
+    };
+    for write in writes {
+      let end = write.offset + write.text.len();
+      if bytes.len() < end {
+        bytes.resize(end, 0);
+      }
+      bytes[write.offset..end].copy_from_slice(write.text.as_bytes());
+    }
+    bytes.resize(len, 0);
+    Ok(())
+  }
+
+  fn append_file(&self, file_path: impl AsRef<Path>, file_text: &str) -> Result<(), ErrBox> {
+    let file_path = self.clean_path(file_path);
+    let mut files = self.files.lock();
+    files.entry(file_path).or_insert_with(Vec::new).extend_from_slice(file_text.as_bytes());
+    Ok(())
+  }
+
   fn remove_file(&self, file_path: impl AsRef<Path>) -> Result<(), ErrBox> {
     let file_path = self.clean_path(file_path);
     let mut files = self.files.lock();
@@ -244,6 +346,28 @@ impl Environment for TestEnvironment {
     Ok(())
   }
 
+  fn with_file_lock<TResult>(&self, file_path: impl AsRef<Path>, action: impl FnOnce() -> TResult) -> Result<TResult, ErrBox> {
+    let file_path = self.resolve_symlink(file_path);
+    while !self.locked_files.lock().insert(file_path.clone()) {
+      std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    let result = action();
+    self.locked_files.lock().remove(&file_path);
+    Ok(result)
+  }
+
+  fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<(), ErrBox> {
+    let from = self.clean_path(from);
+    let mut files = self.files.lock();
+    match files.remove(&from) {
+      Some(bytes) => {
+        files.insert(self.clean_path(to), bytes);
+        Ok(())
+      }
+      None => err!("Could not find file at path {}", from.display()),
+    }
+  }
+
   fn remove_dir_all(&self, dir_path: impl AsRef<Path>) -> Result<(), ErrBox> {
     let dir_path = self.clean_path(dir_path);
     {
@@ -271,6 +395,22 @@ impl Environment for TestEnvironment {
     }
   }
 
+  fn fetch_url(&self, url: &str, _: &[(&str, &str)]) -> Result<FetchResponse, ErrBox> {
+    let remote_files = self.remote_files.lock();
+    Ok(match remote_files.get(&String::from(url)) {
+      Some(bytes) => FetchResponse {
+        status: 200,
+        headers: Vec::new(),
+        bytes: bytes.clone(),
+      },
+      None => FetchResponse {
+        status: 404,
+        headers: Vec::new(),
+        bytes: Vec::new(),
+      },
+    })
+  }
+
   fn dir_info(&self, dir_path: impl AsRef<Path>) -> Result<Vec<DirEntry>, ErrBox> {
     let mut entries = Vec::new();
     let mut found_directories = HashSet::new();
@@ -308,11 +448,11 @@ impl Environment for TestEnvironment {
 
   fn path_exists(&self, file_path: impl AsRef<Path>) -> bool {
     let files = self.files.lock();
-    files.contains_key(&self.clean_path(file_path))
+    files.contains_key(&self.resolve_symlink(file_path))
   }
 
   fn canonicalize(&self, path: impl AsRef<Path>) -> Result<PathBuf, ErrBox> {
-    Ok(self.clean_path(path))
+    Ok(self.resolve_symlink(path))
   }
 
   fn is_absolute_path(&self, path: impl AsRef<Path>) -> bool {
@@ -360,6 +500,22 @@ impl Environment for TestEnvironment {
     action(Box::new(|_| {}))
   }
 
+  fn create_files_progress_bar(&self, _: &str, _: usize) -> Option<ProgressBar> {
+    None
+  }
+
+  fn get_git_staged_files(&self) -> Result<Vec<GitStagedFile>, ErrBox> {
+    match &*self.git_staged_files.lock() {
+      Some(staged_files) => Ok(staged_files.clone()),
+      None => err!("Error running `git rev-parse --show-toplevel`: fatal: not a git repository"),
+    }
+  }
+
+  fn git_add(&self, file_paths: &[PathBuf]) -> Result<(), ErrBox> {
+    self.git_added_files.lock().extend(file_paths.iter().cloned());
+    Ok(())
+  }
+
   fn get_cache_dir(&self) -> PathBuf {
     PathBuf::from("/cache")
   }
@@ -372,6 +528,10 @@ impl Environment for TestEnvironment {
     60
   }
 
+  fn get_max_threads(&self) -> usize {
+    std::cmp::max(1, num_cpus::get())
+  }
+
   fn get_selection(&self, prompt_message: &str, _: u16, _: &Vec<String>) -> Result<usize, ErrBox> {
     self.log_error(prompt_message);
     Ok(*self.selection_result.lock())
@@ -382,10 +542,43 @@ impl Environment for TestEnvironment {
     Ok(self.multi_selection_result.lock().clone())
   }
 
+  fn get_text_input(&self, prompt_message: &str) -> Result<String, ErrBox> {
+    self.log_error(prompt_message);
+    Ok(self.text_input_results.lock().pop_front().unwrap_or_default())
+  }
+
   fn is_verbose(&self) -> bool {
     *self.is_verbose.lock()
   }
 
+  fn is_ci(&self) -> bool {
+    *self.is_ci.lock()
+  }
+
+  fn output_format(&self) -> OutputFormat {
+    *self.output_format.lock()
+  }
+
+  fn run_interruptible<TResult>(&self, action: impl FnOnce() -> TResult) -> TResult {
+    action()
+  }
+
+  fn is_interrupted(&self) -> bool {
+    *self.is_interrupted.lock()
+  }
+
+  fn messages(&self) -> &MessageCatalog {
+    &self.messages
+  }
+
+  fn phase_timings(&self) -> &PhaseTimings {
+    &self.phase_timings
+  }
+
+  fn warning_count(&self) -> &WarningCount {
+    &self.warning_count
+  }
+
   fn compile_wasm(&self, _: &[u8]) -> Result<CompilationResult, ErrBox> {
     let wasm_compile_result = self.wasm_compile_result.lock();
     Ok(wasm_compile_result.clone().expect("Expected compilation result to be set."))
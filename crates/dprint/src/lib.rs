@@ -0,0 +1,54 @@
+//! Library for the dprint code formatter.
+//!
+//! Use [`Formatter`] to format text with the plugins of a configuration file from
+//! Rust code without spawning the CLI:
+//!
+//! ```no_run
+//! let formatter = dprint::Formatter::new("dprint.json").unwrap();
+//! if let Some(formatted_text) = formatter.format_text("src/main.ts", "const t  = 5").unwrap() {
+//!   println!("{}", formatted_text);
+//! }
+//! ```
+
+#[macro_use(err_obj)]
+#[macro_use(err)]
+extern crate dprint_core;
+#[cfg(test)]
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+mod environment;
+
+use dprint_core::types::ErrBox;
+use environment::RealEnvironment;
+use std::sync::Arc;
+
+mod cache;
+mod cli;
+mod configuration;
+mod messages;
+mod plugins;
+mod utils;
+
+#[cfg(test)]
+mod test_helpers;
+
+pub use cli::Formatter;
+
+/// Runs the CLI with the arguments of the current process.
+#[doc(hidden)]
+pub fn run_cli() -> Result<(), ErrBox> {
+  let stdin_reader = cli::RealStdInReader::new();
+  let raw_args = wild::args().collect::<Vec<_>>();
+  let args = cli::parse_args(raw_args.clone(), &stdin_reader)?;
+  dprint_cli_core::styling::set_color_mode(args.color_mode);
+  let environment = RealEnvironment::new(args.verbose, args.is_silent_output(), args.output_format)?;
+  utils::install_crash_report_hook(raw_args, &environment);
+  let cache = Arc::new(cache::Cache::new(environment.clone()));
+  let plugin_cache = Arc::new(plugins::PluginCache::new(environment.clone()));
+  let plugin_pools = Arc::new(plugins::PluginPools::new(environment.clone()));
+  let _plugins_dropper = plugins::PluginsDropper::new(plugin_pools.clone());
+  let plugin_resolver = plugins::PluginResolver::new(environment.clone(), plugin_cache, plugin_pools.clone());
+
+  cli::run_cli(&args, &environment, &cache, &plugin_resolver, plugin_pools.clone())
+}
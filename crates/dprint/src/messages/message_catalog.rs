@@ -0,0 +1,197 @@
+use dprint_core::types::ErrBox;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// The translations that ship with the CLI keyed by locale.
+///
+/// To add a translation, add a file to the `locales` directory with the same keys
+/// as `en.json` and add it here. Missing keys fall back to the English messages.
+const LOCALES: &[(&str, &str)] = &[("en", include_str!("locales/en.json"))];
+const DEFAULT_LOCALE: &str = "en";
+
+const LOCALE_ENV_VAR_NAME: &str = "DPRINT_LOCALE";
+const MESSAGES_FILE_ENV_VAR_NAME: &str = "DPRINT_MESSAGES_FILE";
+/// Standard environment variables to get the locale from when `DPRINT_LOCALE` is not set, in order of precedence.
+const SYSTEM_LOCALE_ENV_VAR_NAMES: &[&str] = &["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// The user facing messages of the CLI.
+///
+/// Messages may contain `{name}` placeholders that are replaced with the provided arguments.
+/// Messages that depend on a count have a `.one` and `.other` variant.
+pub struct MessageCatalog {
+  locale: String,
+  messages: HashMap<String, String>,
+  default_messages: HashMap<String, String>,
+}
+
+impl Default for MessageCatalog {
+  fn default() -> Self {
+    MessageCatalog {
+      locale: DEFAULT_LOCALE.to_string(),
+      messages: HashMap::new(),
+      default_messages: parse_messages(get_locale_messages_text(DEFAULT_LOCALE).unwrap()).unwrap(),
+    }
+  }
+}
+
+impl MessageCatalog {
+  /// Creates the catalog for the locale selected by the `DPRINT_LOCALE` environment variable (falling
+  /// back to the system locale) with any messages in the file specified by `DPRINT_MESSAGES_FILE`.
+  pub fn from_env_vars(get_env_var: impl Fn(&str) -> Option<String>, read_file: impl Fn(&str) -> Result<String, ErrBox>) -> Result<Self, ErrBox> {
+    let mut catalog = MessageCatalog::default();
+    let locale = get_env_var(LOCALE_ENV_VAR_NAME)
+      .into_iter()
+      .chain(SYSTEM_LOCALE_ENV_VAR_NAMES.iter().filter_map(|name| get_env_var(name)))
+      .find(|value| !value.trim().is_empty());
+    if let Some(locale) = locale {
+      catalog.set_locale(&locale);
+    }
+
+    if let Some(file_path) = get_env_var(MESSAGES_FILE_ENV_VAR_NAME).filter(|value| !value.trim().is_empty()) {
+      let messages = read_file(&file_path).and_then(|text| parse_messages(&text));
+      match messages {
+        Ok(messages) => catalog.messages.extend(messages),
+        Err(err) => return err!("Error loading the {} environment variable's file {}. {}", MESSAGES_FILE_ENV_VAR_NAME, file_path, err.to_string()),
+      }
+    }
+
+    Ok(catalog)
+  }
+
+  pub fn locale(&self) -> &str {
+    &self.locale
+  }
+
+  /// Gets the message with its placeholders replaced by the provided arguments.
+  pub fn get(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let message = self
+      .messages
+      .get(key)
+      .or_else(|| self.default_messages.get(key))
+      .map(|message| message.as_str())
+      .unwrap_or(key);
+    let mut text = message.to_string();
+    for (name, value) in args {
+      text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+  }
+
+  /// Gets the `.one` or `.other` variant of the message depending on the count.
+  /// The count is provided to the message as the `{count}` placeholder.
+  pub fn get_plural(&self, key: &str, count: usize, count_display: &dyn Display, args: &[(&str, &dyn Display)]) -> String {
+    let key = format!("{}.{}", key, if count == 1 { "one" } else { "other" });
+    let mut all_args = vec![("count", count_display)];
+    all_args.extend(args.iter().copied());
+    self.get(&key, &all_args)
+  }
+
+  /// Sets the locale, which may be in the format of an environment variable (ex. `pt_BR.UTF-8`).
+  /// The English messages are used when there is no translation for the locale.
+  fn set_locale(&mut self, locale: &str) {
+    let locale = normalize_locale(locale);
+    let language = locale.split('-').next().unwrap_or_default().to_string();
+    for locale in [locale, language].iter() {
+      if let Some(text) = get_locale_messages_text(locale) {
+        self.locale = locale.to_string();
+        self.messages = parse_messages(text).unwrap();
+        return;
+      }
+    }
+  }
+}
+
+fn get_locale_messages_text(locale: &str) -> Option<&'static str> {
+  LOCALES.iter().find(|(name, _)| *name == locale).map(|(_, text)| *text)
+}
+
+fn normalize_locale(locale: &str) -> String {
+  // ex. "pt_BR.UTF-8@euro" -> "pt-br"
+  let locale = locale.trim();
+  let locale = locale.split(|c| c == '.' || c == '@').next().unwrap_or_default();
+  locale.replace('_', "-").to_lowercase()
+}
+
+fn parse_messages(text: &str) -> Result<HashMap<String, String>, ErrBox> {
+  Ok(serde_json::from_str(text)?)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_get_messages() {
+    let catalog = MessageCatalog::default();
+    assert_eq!(catalog.get("init.alreadyExists", &[("path", &"./dprint.json")]), "Configuration file './dprint.json' already exists.");
+    assert_eq!(catalog.get_plural("fmt.formatted", 1, &1, &[]), "Formatted 1 file.");
+    assert_eq!(catalog.get_plural("fmt.formatted", 2, &"two", &[]), "Formatted two files.");
+    assert_eq!(catalog.get("unknown.key", &[]), "unknown.key");
+  }
+
+  #[test]
+  fn should_have_plural_variants_for_each_plural_message() {
+    let catalog = MessageCatalog::default();
+    for key in catalog.default_messages.keys() {
+      if let Some(key) = key.strip_suffix(".one") {
+        assert!(catalog.default_messages.contains_key(&format!("{}.other", key)), "Missing .other variant for {}", key);
+      }
+    }
+  }
+
+  #[test]
+  fn should_only_have_known_keys_in_translations() {
+    let catalog = MessageCatalog::default();
+    for (locale, text) in LOCALES.iter() {
+      for key in parse_messages(text).unwrap().keys() {
+        assert!(catalog.default_messages.contains_key(key), "Unknown key {} in {} translation", key, locale);
+      }
+    }
+  }
+
+  #[test]
+  fn should_select_locale_from_env_vars() {
+    let catalog = MessageCatalog::from_env_vars(|name| if name == "LANG" { Some("en_US.UTF-8".to_string()) } else { None }, |_| unreachable!()).unwrap();
+    assert_eq!(catalog.locale(), "en");
+
+    // falls back to the default locale when there is no translation
+    let catalog = MessageCatalog::from_env_vars(|name| if name == "DPRINT_LOCALE" { Some("xx".to_string()) } else { None }, |_| unreachable!()).unwrap();
+    assert_eq!(catalog.locale(), "en");
+    assert_eq!(catalog.get_plural("fmt.formatted", 1, &1, &[]), "Formatted 1 file.");
+  }
+
+  #[test]
+  fn should_override_messages_with_messages_file() {
+    let catalog = MessageCatalog::from_env_vars(
+      |name| if name == "DPRINT_MESSAGES_FILE" { Some("/messages.json".to_string()) } else { None },
+      |file_path| {
+        assert_eq!(file_path, "/messages.json");
+        Ok(r#"{ "fmt.formatted.one": "Reformatted {count} file." }"#.to_string())
+      },
+    )
+    .unwrap();
+    assert_eq!(catalog.get_plural("fmt.formatted", 1, &1, &[]), "Reformatted 1 file.");
+    assert_eq!(catalog.get_plural("fmt.formatted", 2, &2, &[]), "Formatted 2 files.");
+  }
+
+  #[test]
+  fn should_error_when_messages_file_invalid() {
+    let result = MessageCatalog::from_env_vars(
+      |name| if name == "DPRINT_MESSAGES_FILE" { Some("/messages.json".to_string()) } else { None },
+      |_| Ok("[]".to_string()),
+    );
+    assert!(result
+      .err()
+      .unwrap()
+      .to_string()
+      .starts_with("Error loading the DPRINT_MESSAGES_FILE environment variable's file /messages.json."));
+  }
+
+  #[test]
+  fn should_normalize_locale() {
+    assert_eq!(normalize_locale("pt_BR.UTF-8"), "pt-br");
+    assert_eq!(normalize_locale("de_DE@euro"), "de-de");
+    assert_eq!(normalize_locale(" fr "), "fr");
+  }
+}
@@ -0,0 +1,3 @@
+mod message_catalog;
+
+pub use message_catalog::*;
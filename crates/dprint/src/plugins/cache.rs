@@ -5,11 +5,14 @@ use dprint_cli_core::checksums::verify_sha256_checksum;
 use dprint_core::plugins::PluginInfo;
 use dprint_core::types::ErrBox;
 
+use super::crates_io::download_crate_plugin;
 use super::implementations::{cleanup_plugin, get_file_path_from_plugin_info, setup_plugin};
+use super::npm::download_npm_plugin;
+use super::oci::download_oci_plugin;
 use super::{read_manifest, write_manifest, PluginCacheManifest, PluginCacheManifestItem};
 use crate::environment::Environment;
 use crate::plugins::PluginSourceReference;
-use crate::utils::{get_bytes_hash, PathSource};
+use crate::utils::{get_bytes_hash, PathSource, Phase};
 
 pub struct PluginCacheItem {
   pub file_path: PathBuf,
@@ -37,7 +40,7 @@ where
     write_manifest(&manifest, &self.environment)?;
 
     if let Some(cache_item) = cache_item {
-      match cleanup_plugin(&source_reference.path_source, &cache_item.info, &self.environment) {
+      match cleanup_plugin(source_reference, &cache_item.info, &self.environment) {
         Err(err) => self.environment.log_error(&format!("Error forgetting plugin: {}", err.to_string())),
         _ => {}
       }
@@ -57,15 +60,15 @@ where
     &self,
     source_reference: PluginSourceReference,
     check_file_hash: bool,
-    read_bytes: impl Fn(PathSource, TEnvironment) -> Result<Vec<u8>, ErrBox>,
+    read_bytes: impl Fn(&PluginSourceReference, TEnvironment) -> Result<Vec<u8>, ErrBox>,
   ) -> Result<PluginCacheItem, ErrBox> {
     let cache_key = self.get_cache_key(&source_reference.path_source)?;
     let cache_item = self.manifest.read().get_item(&cache_key).map(|x| x.to_owned()); // drop lock
     if let Some(cache_item) = cache_item {
-      let file_path = get_file_path_from_plugin_info(&source_reference.path_source, &cache_item.info, &self.environment)?;
+      let file_path = get_file_path_from_plugin_info(&source_reference, &cache_item.info, &self.environment)?;
 
       if check_file_hash {
-        let file_bytes = read_bytes(source_reference.path_source.clone(), self.environment.clone())?;
+        let file_bytes = read_bytes(&source_reference, self.environment.clone())?;
         let file_hash = get_bytes_hash(&file_bytes);
         let cache_file_hash = match &cache_item.file_hash {
           Some(file_hash) => *file_hash,
@@ -89,14 +92,14 @@ where
     }
 
     // get bytes
-    let file_bytes = read_bytes(source_reference.path_source.clone(), self.environment.clone())?;
+    let file_bytes = read_bytes(&source_reference, self.environment.clone())?;
 
     // check checksum only if provided (not required for Wasm plugins)
     if let Some(checksum) = &source_reference.checksum {
       verify_sha256_checksum(&file_bytes, checksum)?;
     }
 
-    let setup_result = setup_plugin(&source_reference.path_source, &file_bytes, &self.environment)?;
+    let setup_result = setup_plugin(&source_reference, &file_bytes, &self.environment)?;
     let cache_item = PluginCacheManifestItem {
       info: setup_result.plugin_info.clone(),
       file_hash: if check_file_hash { Some(get_bytes_hash(&file_bytes)) } else { None },
@@ -124,12 +127,24 @@ where
   }
 }
 
-fn download_url<TEnvironment: Environment>(path_source: PathSource, environment: TEnvironment) -> Result<Vec<u8>, ErrBox> {
-  environment.download_file(path_source.unwrap_remote().url.as_str())
+fn download_url<TEnvironment: Environment>(source_reference: &PluginSourceReference, environment: TEnvironment) -> Result<Vec<u8>, ErrBox> {
+  let path_source = &source_reference.path_source;
+  let url = path_source.unwrap_remote().url;
+  environment.phase_timings().measure(Phase::PluginDownload, || {
+    if path_source.is_oci_plugin() {
+      download_oci_plugin(&url, &environment)
+    } else if path_source.is_npm_plugin() {
+      download_npm_plugin(&url, source_reference.is_process_plugin(), &environment)
+    } else if path_source.is_crate_plugin() {
+      download_crate_plugin(&url, &environment)
+    } else {
+      environment.download_file(url.as_str())
+    }
+  })
 }
 
-fn get_file_bytes<TEnvironment: Environment>(path_source: PathSource, environment: TEnvironment) -> Result<Vec<u8>, ErrBox> {
-  environment.read_file_bytes(&path_source.unwrap_local().path)
+fn get_file_bytes<TEnvironment: Environment>(source_reference: &PluginSourceReference, environment: TEnvironment) -> Result<Vec<u8>, ErrBox> {
+  environment.read_file_bytes(&source_reference.path_source.unwrap_local().path)
 }
 
 #[cfg(test)]
@@ -163,7 +178,7 @@ mod test {
     // should have saved the manifest
     assert_eq!(
       environment.read_file(&environment.get_cache_dir().join("plugin-cache-manifest.json")).unwrap(),
-      r#"{"schemaVersion":3,"plugins":{"remote:https://plugins.dprint.dev/test.wasm":{"createdTime":123456,"info":{"name":"test-plugin","version":"0.1.0","configKey":"test-plugin","fileExtensions":["txt","dat"],"fileNames":[],"helpUrl":"test-url","configSchemaUrl":"schema-url"}}}}"#,
+      r#"{"schemaVersion":4,"plugins":{"remote:https://plugins.dprint.dev/test.wasm":{"createdTime":123456,"info":{"name":"test-plugin","version":"0.1.0","configKey":"test-plugin","fileExtensions":["txt","dat"],"fileNames":[],"helpUrl":"test-url","configSchemaUrl":"schema-url","supportsPartialFormat":false,"supportsFormatDiagnostics":false,"onlyChangesWhitespace":false,"supportsChangedRegions":false}}}}"#,
     );
 
     // should forget it afterwards
@@ -173,7 +188,7 @@ mod test {
     // should have saved the manifest
     assert_eq!(
       environment.read_file(&environment.get_cache_dir().join("plugin-cache-manifest.json")).unwrap(),
-      r#"{"schemaVersion":3,"plugins":{}}"#,
+      r#"{"schemaVersion":4,"plugins":{}}"#,
     );
 
     Ok(())
@@ -204,9 +219,9 @@ mod test {
     assert_eq!(
       environment.read_file(&environment.get_cache_dir().join("plugin-cache-manifest.json")).unwrap(),
       concat!(
-        r#"{"schemaVersion":3,"plugins":{"local:/test.wasm":{"createdTime":123456,"fileHash":10632242795325663332,"info":{"#,
+        r#"{"schemaVersion":4,"plugins":{"local:/test.wasm":{"createdTime":123456,"fileHash":10632242795325663332,"info":{"#,
         r#""name":"test-plugin","version":"0.1.0","configKey":"test-plugin","#,
-        r#""fileExtensions":["txt","dat"],"fileNames":[],"helpUrl":"test-url","configSchemaUrl":"schema-url"}}}}"#,
+        r#""fileExtensions":["txt","dat"],"fileNames":[],"helpUrl":"test-url","configSchemaUrl":"schema-url","supportsPartialFormat":false,"supportsFormatDiagnostics":false,"onlyChangesWhitespace":false,"supportsChangedRegions":false}}}}"#,
       )
     );
 
@@ -225,9 +240,9 @@ mod test {
     assert_eq!(
       environment.read_file(&environment.get_cache_dir().join("plugin-cache-manifest.json")).unwrap(),
       concat!(
-        r#"{"schemaVersion":3,"plugins":{"local:/test.wasm":{"createdTime":123456,"fileHash":6989588595861227504,"info":{"#,
+        r#"{"schemaVersion":4,"plugins":{"local:/test.wasm":{"createdTime":123456,"fileHash":6989588595861227504,"info":{"#,
         r#""name":"test-plugin","version":"0.1.0","configKey":"test-plugin","#,
-        r#""fileExtensions":["txt","dat"],"fileNames":[],"helpUrl":"test-url","configSchemaUrl":"schema-url"}}}}"#,
+        r#""fileExtensions":["txt","dat"],"fileNames":[],"helpUrl":"test-url","configSchemaUrl":"schema-url","supportsPartialFormat":false,"supportsFormatDiagnostics":false,"onlyChangesWhitespace":false,"supportsChangedRegions":false}}}}"#,
       )
     );
 
@@ -240,7 +255,7 @@ mod test {
     // should have saved the manifest
     assert_eq!(
       environment.read_file(&environment.get_cache_dir().join("plugin-cache-manifest.json")).unwrap(),
-      r#"{"schemaVersion":3,"plugins":{}}"#,
+      r#"{"schemaVersion":4,"plugins":{}}"#,
     );
 
     Ok(())
@@ -254,14 +269,14 @@ mod test {
   }
 
   fn get_test_plugin_info() -> PluginInfo {
-    PluginInfo {
-      name: String::from("test-plugin"),
-      version: String::from("0.1.0"),
-      config_key: String::from("test-plugin"),
-      file_extensions: vec![String::from("txt"), String::from("dat")],
-      file_names: vec![],
-      help_url: String::from("test-url"),
-      config_schema_url: String::from("schema-url"),
-    }
+    PluginInfo::new(
+      String::from("test-plugin"),
+      String::from("0.1.0"),
+      String::from("test-plugin"),
+      vec![String::from("txt"), String::from("dat")],
+      vec![],
+      String::from("test-url"),
+      String::from("schema-url"),
+    )
   }
 }
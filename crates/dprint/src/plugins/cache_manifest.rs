@@ -7,7 +7,7 @@ use dprint_core::types::ErrBox;
 
 use crate::environment::Environment;
 
-const PLUGIN_SCHEMA_VERSION: usize = 3;
+const PLUGIN_SCHEMA_VERSION: usize = 4;
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -95,7 +95,7 @@ mod test {
       .write_file(
         &environment.get_cache_dir().join("plugin-cache-manifest.json"),
         r#"{
-    "schemaVersion": 3,
+    "schemaVersion": 4,
     "plugins": {
         "a": {
             "createdTime": 123,
@@ -144,15 +144,15 @@ mod test {
       PluginCacheManifestItem {
         created_time: 123,
         file_hash: None,
-        info: PluginInfo {
-          name: "dprint-plugin-typescript".to_string(),
-          version: "0.1.0".to_string(),
-          config_key: "typescript".to_string(),
-          file_extensions: vec![".ts".to_string()],
-          file_names: vec![],
-          help_url: "help url".to_string(),
-          config_schema_url: "schema url".to_string(),
-        },
+        info: PluginInfo::new(
+          "dprint-plugin-typescript".to_string(),
+          "0.1.0".to_string(),
+          "typescript".to_string(),
+          vec![".ts".to_string()],
+          vec![],
+          "help url".to_string(),
+          "schema url".to_string(),
+        ),
       },
     );
     expected_manifest.add_item(
@@ -160,15 +160,15 @@ mod test {
       PluginCacheManifestItem {
         created_time: 456,
         file_hash: Some(10),
-        info: PluginInfo {
-          name: "dprint-plugin-json".to_string(),
-          version: "0.2.0".to_string(),
-          config_key: "json".to_string(),
-          file_extensions: vec![".json".to_string()],
-          file_names: vec![],
-          help_url: "help url 2".to_string(),
-          config_schema_url: "schema url 2".to_string(),
-        },
+        info: PluginInfo::new(
+          "dprint-plugin-json".to_string(),
+          "0.2.0".to_string(),
+          "json".to_string(),
+          vec![".json".to_string()],
+          vec![],
+          "help url 2".to_string(),
+          "schema url 2".to_string(),
+        ),
       },
     );
     expected_manifest.add_item(
@@ -176,15 +176,15 @@ mod test {
       PluginCacheManifestItem {
         created_time: 210530,
         file_hash: Some(1226),
-        info: PluginInfo {
-          name: "dprint-plugin-cargo".to_string(),
-          version: "0.2.1".to_string(),
-          config_key: "cargo".to_string(),
-          file_extensions: vec![],
-          file_names: vec!["Cargo.toml".to_string()],
-          help_url: "cargo help url".to_string(),
-          config_schema_url: "cargo schema url".to_string(),
-        },
+        info: PluginInfo::new(
+          "dprint-plugin-cargo".to_string(),
+          "0.2.1".to_string(),
+          "cargo".to_string(),
+          vec![],
+          vec!["Cargo.toml".to_string()],
+          "cargo help url".to_string(),
+          "cargo schema url".to_string(),
+        ),
       },
     );
 
@@ -250,15 +250,15 @@ mod test {
       PluginCacheManifestItem {
         created_time: 456,
         file_hash: Some(256),
-        info: PluginInfo {
-          name: "dprint-plugin-typescript".to_string(),
-          version: "0.1.0".to_string(),
-          config_key: "typescript".to_string(),
-          file_extensions: vec![".ts".to_string()],
-          file_names: vec![],
-          help_url: "help url".to_string(),
-          config_schema_url: "schema url".to_string(),
-        },
+        info: PluginInfo::new(
+          "dprint-plugin-typescript".to_string(),
+          "0.1.0".to_string(),
+          "typescript".to_string(),
+          vec![".ts".to_string()],
+          vec![],
+          "help url".to_string(),
+          "schema url".to_string(),
+        ),
       },
     );
     manifest.add_item(
@@ -266,15 +266,15 @@ mod test {
       PluginCacheManifestItem {
         created_time: 456,
         file_hash: None,
-        info: PluginInfo {
-          name: "dprint-plugin-json".to_string(),
-          version: "0.2.0".to_string(),
-          config_key: "json".to_string(),
-          file_extensions: vec![".json".to_string()],
-          file_names: vec!["file.test".to_string()],
-          help_url: "help url 2".to_string(),
-          config_schema_url: "schema url 2".to_string(),
-        },
+        info: PluginInfo::new(
+          "dprint-plugin-json".to_string(),
+          "0.2.0".to_string(),
+          "json".to_string(),
+          vec![".json".to_string()],
+          vec!["file.test".to_string()],
+          "help url 2".to_string(),
+          "schema url 2".to_string(),
+        ),
       },
     );
     write_manifest(&manifest, &environment).unwrap();
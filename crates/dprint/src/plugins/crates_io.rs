@@ -0,0 +1,450 @@
+use dprint_cli_core::checksums::verify_sha256_checksum;
+use dprint_core::types::ErrBox;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+use url::Url;
+
+use super::tarball::{read_tarball_files, TarballFile};
+use crate::environment::Environment;
+
+const INDEX_URL: &str = "https://index.crates.io";
+const DOWNLOAD_URL: &str = "https://static.crates.io/crates";
+const BUILD_ENV_VAR_NAME: &str = "DPRINT_BUILD_CRATE_PLUGINS";
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// A plugin specifier for a crate on crates.io (ex. `crate:dprint-plugin-foo@0.3`).
+#[derive(Debug, PartialEq)]
+struct CrateSpecifier {
+  name: String,
+  /// Cargo style version requirement (ex. `0.3`, `=0.3.1`, or `~1.2`).
+  version_req: Option<VersionReq>,
+}
+
+impl CrateSpecifier {
+  fn parse(text: &str) -> Result<CrateSpecifier, ErrBox> {
+    let text = match text.strip_prefix("crate:") {
+      Some(text) => text,
+      None => return err!("Expected a crate specifier starting with crate: {}", text),
+    };
+    let (name, version_req) = match text.find('@') {
+      Some(index) => (&text[..index], Some(&text[index + 1..])),
+      None => (text, None),
+    };
+    let is_valid_name = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    let version_req = match version_req {
+      Some(version_req) => VersionReq::parse(version_req),
+      None => None,
+    };
+    if !is_valid_name || (text.contains('@') && version_req.is_none()) {
+      return err!("Expected a crate specifier in the format crate:<name>@<version> (ex. crate:dprint-plugin-foo@0.3): crate:{}", text);
+    }
+    Ok(CrateSpecifier {
+      name: name.to_string(),
+      version_req,
+    })
+  }
+}
+
+#[derive(Debug, PartialEq)]
+struct Version {
+  major: u64,
+  minor: u64,
+  patch: u64,
+  pre: Option<String>,
+}
+
+impl Version {
+  fn parse(text: &str) -> Option<Version> {
+    let text = text.split('+').next().unwrap();
+    let (text, pre) = match text.find('-') {
+      Some(index) => (&text[..index], Some(text[index + 1..].to_string())),
+      None => (text, None),
+    };
+    let parts = text.split('.').map(|part| part.parse::<u64>().ok()).collect::<Option<Vec<_>>>()?;
+    if parts.len() != 3 {
+      return None;
+    }
+    Some(Version {
+      major: parts[0],
+      minor: parts[1],
+      patch: parts[2],
+      pre,
+    })
+  }
+
+  /// Compares the version numbers, ordering pre-releases before the release.
+  fn cmp_precedence(&self, other: &Version) -> std::cmp::Ordering {
+    (self.major, self.minor, self.patch)
+      .cmp(&(other.major, other.minor, other.patch))
+      .then_with(|| match (&self.pre, &other.pre) {
+        (None, None) => std::cmp::Ordering::Equal,
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (Some(a), Some(b)) => a.cmp(b),
+      })
+  }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum VersionReqOp {
+  Caret,
+  Tilde,
+  Exact,
+}
+
+#[derive(Debug, PartialEq)]
+struct VersionReq {
+  op: VersionReqOp,
+  /// The provided major, minor, and patch numbers.
+  parts: Vec<u64>,
+  pre: Option<String>,
+}
+
+impl VersionReq {
+  fn parse(text: &str) -> Option<VersionReq> {
+    let text = text.trim();
+    let (op, text) = if let Some(text) = text.strip_prefix('^') {
+      (VersionReqOp::Caret, text)
+    } else if let Some(text) = text.strip_prefix('~') {
+      (VersionReqOp::Tilde, text)
+    } else if let Some(text) = text.strip_prefix('=') {
+      (VersionReqOp::Exact, text)
+    } else {
+      (VersionReqOp::Caret, text)
+    };
+    let (text, pre) = match text.find('-') {
+      Some(index) => (&text[..index], Some(text[index + 1..].to_string())),
+      None => (text, None),
+    };
+    let parts = text.split('.').map(|part| part.parse::<u64>().ok()).collect::<Option<Vec<_>>>()?;
+    if parts.is_empty() || parts.len() > 3 || (pre.is_some() && parts.len() != 3) {
+      return None;
+    }
+    Some(VersionReq { op, parts, pre })
+  }
+
+  /// Gets if the version matches the same way Cargo matches dependency requirements.
+  fn matches(&self, version: &Version) -> bool {
+    // pre-releases only match when explicitly requested
+    if version.pre.is_some() {
+      return self.pre.is_some() && self.parts == [version.major, version.minor, version.patch];
+    }
+
+    let numbers = [version.major, version.minor, version.patch];
+    let lower = Version {
+      major: self.parts[0],
+      minor: self.parts.get(1).copied().unwrap_or(0),
+      patch: self.parts.get(2).copied().unwrap_or(0),
+      pre: self.pre.clone(),
+    };
+    if version.cmp_precedence(&lower) == std::cmp::Ordering::Less {
+      return false;
+    }
+
+    // the number of leading parts that must be equal
+    let equal_parts = match self.op {
+      VersionReqOp::Exact => self.parts.len(),
+      VersionReqOp::Tilde => std::cmp::min(self.parts.len(), 2),
+      VersionReqOp::Caret => match self.parts.iter().position(|part| *part != 0) {
+        Some(index) => index + 1,
+        None => self.parts.len(),
+      },
+    };
+    numbers[..equal_parts] == self.parts[..equal_parts]
+  }
+}
+
+/// An entry in the crates.io index.
+#[derive(Deserialize)]
+struct IndexEntry {
+  vers: String,
+  cksum: String,
+  #[serde(default)]
+  yanked: bool,
+}
+
+/// Downloads a Wasm plugin from a crate on crates.io.
+///
+/// The plugin is the Wasm file in the published crate specified by the `package.metadata.dprint.plugin`
+/// property in its Cargo.toml or otherwise the only Wasm file in the crate. When the crate does not
+/// contain a Wasm file, it's built from source only when the `DPRINT_BUILD_CRATE_PLUGINS` environment
+/// variable is set.
+pub fn download_crate_plugin(url: &Url, environment: &impl Environment) -> Result<Vec<u8>, ErrBox> {
+  let allow_build = std::env::var(BUILD_ENV_VAR_NAME).map(|value| value == "1" || value == "true").unwrap_or(false);
+  download_crate_plugin_inner(url, allow_build, environment)
+}
+
+fn download_crate_plugin_inner(url: &Url, allow_build: bool, environment: &impl Environment) -> Result<Vec<u8>, ErrBox> {
+  let specifier = CrateSpecifier::parse(url.as_str())?;
+  let index_text = String::from_utf8(fetch(&format!("{}/{}", INDEX_URL, get_index_path(&specifier.name)), environment)?)?;
+  let mut entries = Vec::new();
+  for line in index_text.lines().filter(|line| !line.trim().is_empty()) {
+    let entry: IndexEntry = serde_json::from_str(line)?;
+    if let Some(version) = Version::parse(&entry.vers) {
+      entries.push((version, entry));
+    }
+  }
+  let (_, entry) = match get_matching_entry(&specifier, entries) {
+    Some(entry) => entry,
+    None => {
+      return err!(
+        "Could not find a version of crate {} matching {}.",
+        specifier.name,
+        url.as_str().splitn(2, '@').nth(1).unwrap_or("*")
+      )
+    }
+  };
+
+  let crate_bytes = fetch(&format!("{0}/{1}/{1}-{2}.crate", DOWNLOAD_URL, specifier.name, entry.vers), environment)?;
+  verify_sha256_checksum(&crate_bytes, &entry.cksum)?;
+  let files = read_tarball_files(&crate_bytes)?;
+
+  if let Some(bytes) = get_plugin_file(&files)? {
+    return Ok(bytes);
+  }
+  if allow_build {
+    build_crate(&specifier.name, &entry.vers, &files, environment)
+  } else {
+    err!(
+      concat!(
+        "Could not find a Wasm file in crate {}@{}. Specify it with the package.metadata.dprint.plugin ",
+        "property in the crate's Cargo.toml or set the {} environment variable to 1 to build the crate from source."
+      ),
+      specifier.name,
+      entry.vers,
+      BUILD_ENV_VAR_NAME
+    )
+  }
+}
+
+fn fetch(url: &str, environment: &impl Environment) -> Result<Vec<u8>, ErrBox> {
+  let response = environment.fetch_url(url, &[("User-Agent", "dprint")])?;
+  match response.status {
+    200 => Ok(response.bytes),
+    status => err!("Error downloading {}. Status: {}", url, status),
+  }
+}
+
+fn get_matching_entry(specifier: &CrateSpecifier, entries: Vec<(Version, IndexEntry)>) -> Option<(Version, IndexEntry)> {
+  entries
+    .into_iter()
+    .filter(|(version, entry)| {
+      !entry.yanked
+        && match &specifier.version_req {
+          Some(version_req) => version_req.matches(version),
+          None => version.pre.is_none(),
+        }
+    })
+    .max_by(|(a, _), (b, _)| a.cmp_precedence(b))
+}
+
+/// Gets the path of the crate in the crates.io index (https://doc.rust-lang.org/cargo/reference/registry-index.html#index-files).
+fn get_index_path(name: &str) -> String {
+  let name = name.to_lowercase();
+  match name.len() {
+    1 => format!("1/{}", name),
+    2 => format!("2/{}", name),
+    3 => format!("3/{}/{}", &name[..1], name),
+    _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+  }
+}
+
+fn get_plugin_file(files: &[TarballFile]) -> Result<Option<Vec<u8>>, ErrBox> {
+  let cargo_toml = files.iter().find(|file| file.path == "Cargo.toml");
+  let plugin_path = cargo_toml.and_then(|file| get_metadata_plugin_path(&String::from_utf8_lossy(&file.bytes)));
+  if let Some(plugin_path) = plugin_path {
+    let plugin_path = plugin_path.trim_start_matches("./");
+    return match files.iter().find(|file| file.path == plugin_path) {
+      Some(file) => Ok(Some(file.bytes.clone())),
+      None => err!("Could not find the plugin file {} specified in the crate's Cargo.toml.", plugin_path),
+    };
+  }
+
+  let mut wasm_files = files.iter().filter(|file| file.path.to_lowercase().ends_with(".wasm"));
+  match (wasm_files.next(), wasm_files.next()) {
+    (Some(file), None) => Ok(Some(file.bytes.clone())),
+    (Some(_), Some(_)) => err!("Found multiple Wasm files in the crate. Specify the plugin with the package.metadata.dprint.plugin property in its Cargo.toml."),
+    _ => Ok(None),
+  }
+}
+
+/// Gets the `plugin` property of the `[package.metadata.dprint]` table in the Cargo.toml text.
+fn get_metadata_plugin_path(cargo_toml_text: &str) -> Option<String> {
+  let mut is_in_table = false;
+  for line in cargo_toml_text.lines() {
+    let line = line.trim();
+    if line.starts_with('[') {
+      is_in_table = line == "[package.metadata.dprint]";
+    } else if is_in_table {
+      if let Some(index) = line.find('=') {
+        if line[..index].trim() == "plugin" {
+          return Some(line[index + 1..].trim().trim_matches(|c| c == '"' || c == '\'').to_string());
+        }
+      }
+    }
+  }
+  None
+}
+
+/// Builds the crate with Cargo for the Wasm target.
+fn build_crate(name: &str, version: &str, files: &[TarballFile], environment: &impl Environment) -> Result<Vec<u8>, ErrBox> {
+  let build_dir = environment.get_cache_dir().join("crate-builds").join(format!("{}-{}", name, version));
+  let _ = environment.remove_dir_all(&build_dir);
+  for file in files {
+    let file_path = build_dir.join(&file.path);
+    if let Some(parent) = file_path.parent() {
+      environment.mk_dir_all(parent)?;
+    }
+    environment.write_file_bytes(&file_path, &file.bytes)?;
+  }
+
+  environment.log(&format!("Building crate {}. This may take a while...", name));
+  let result = run_cargo_build(&build_dir).and_then(|_| {
+    let wasm_file_path = build_dir
+      .join("target")
+      .join(WASM_TARGET)
+      .join("release")
+      .join(format!("{}.wasm", name.replace('-', "_")));
+    environment.read_file_bytes(&wasm_file_path)
+  });
+  let _ = environment.remove_dir_all(&build_dir);
+  result
+}
+
+fn run_cargo_build(dir_path: &Path) -> Result<(), ErrBox> {
+  let status = match Command::new("cargo")
+    .args(&["build", "--release", "--lib", "--target", WASM_TARGET])
+    .current_dir(dir_path)
+    .status()
+  {
+    Ok(status) => status,
+    Err(err) => return err!("Error running cargo to build the crate. Ensure Rust is installed. {}", err.to_string()),
+  };
+  if status.success() {
+    Ok(())
+  } else {
+    err!(
+      "Error building the crate. Ensure the {} target is installed (rustup target add {}).",
+      WASM_TARGET,
+      WASM_TARGET
+    )
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use crate::plugins::tarball::create_tarball;
+  use dprint_cli_core::checksums::get_sha256_checksum;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_parse_crate_specifier() {
+    assert_eq!(
+      CrateSpecifier::parse("crate:dprint-plugin-foo@0.3").unwrap(),
+      CrateSpecifier {
+        name: String::from("dprint-plugin-foo"),
+        version_req: Some(VersionReq {
+          op: VersionReqOp::Caret,
+          parts: vec![0, 3],
+          pre: None,
+        }),
+      }
+    );
+    assert_eq!(
+      CrateSpecifier::parse("crate:dprint-plugin-foo").unwrap(),
+      CrateSpecifier {
+        name: String::from("dprint-plugin-foo"),
+        version_req: None,
+      }
+    );
+    assert_eq!(
+      CrateSpecifier::parse("crate:dprint-plugin-foo@latest").err().unwrap().to_string(),
+      "Expected a crate specifier in the format crate:<name>@<version> (ex. crate:dprint-plugin-foo@0.3): crate:dprint-plugin-foo@latest"
+    );
+  }
+
+  #[test]
+  fn should_match_version_requirements() {
+    let matches = |req: &str, version: &str| VersionReq::parse(req).unwrap().matches(&Version::parse(version).unwrap());
+    assert!(matches("0.3", "0.3.0"));
+    assert!(matches("0.3", "0.3.5"));
+    assert!(!matches("0.3", "0.4.0"));
+    assert!(!matches("0.3", "0.2.9"));
+    assert!(matches("1.2", "1.9.0"));
+    assert!(!matches("1.2", "2.0.0"));
+    assert!(matches("^0.0.3", "0.0.3"));
+    assert!(!matches("^0.0.3", "0.0.4"));
+    assert!(matches("~1.2", "1.2.9"));
+    assert!(!matches("~1.2", "1.3.0"));
+    assert!(matches("=1.2.3", "1.2.3"));
+    assert!(!matches("=1.2.3", "1.2.4"));
+    assert!(!matches("1.2", "1.3.0-alpha"));
+    assert!(matches("1.3.0-alpha", "1.3.0-alpha"));
+  }
+
+  #[test]
+  fn should_get_index_path() {
+    assert_eq!(get_index_path("a"), "1/a");
+    assert_eq!(get_index_path("ab"), "2/ab");
+    assert_eq!(get_index_path("abc"), "3/a/abc");
+    assert_eq!(get_index_path("Dprint-Plugin-Foo"), "dp/ri/dprint-plugin-foo");
+  }
+
+  #[test]
+  fn should_get_metadata_plugin_path() {
+    let text = "[package]\nname = \"foo\"\n\n[package.metadata.dprint]\nplugin = \"dist/plugin.wasm\"\n\n[dependencies]\nplugin = \"1\"\n";
+    assert_eq!(get_metadata_plugin_path(text), Some(String::from("dist/plugin.wasm")));
+    assert_eq!(get_metadata_plugin_path("[dependencies]\nplugin = \"1\"\n"), None);
+  }
+
+  #[test]
+  fn should_download_highest_matching_version() {
+    let environment = TestEnvironment::new();
+    let crate_bytes = create_tarball(&[
+      ("dprint-plugin-foo-0.3.2/Cargo.toml", b"[package.metadata.dprint]\nplugin = \"plugin.wasm\"\n"),
+      ("dprint-plugin-foo-0.3.2/plugin.wasm", b"wasm"),
+    ]);
+    environment.add_remote_file_bytes(
+      "https://index.crates.io/dp/ri/dprint-plugin-foo",
+      format!(
+        concat!(
+          "{{\"vers\":\"0.3.1\",\"cksum\":\"other\"}}\n",
+          "{{\"vers\":\"0.3.2\",\"cksum\":\"{}\"}}\n",
+          "{{\"vers\":\"0.3.3\",\"cksum\":\"other\",\"yanked\":true}}\n",
+          "{{\"vers\":\"0.4.0\",\"cksum\":\"other\"}}\n",
+        ),
+        get_sha256_checksum(&crate_bytes)
+      )
+      .into_bytes(),
+    );
+    environment.add_remote_file_bytes("https://static.crates.io/crates/dprint-plugin-foo/dprint-plugin-foo-0.3.2.crate", crate_bytes);
+
+    let result = download_crate_plugin_inner(&Url::parse("crate:dprint-plugin-foo@0.3").unwrap(), false, &environment).unwrap();
+    assert_eq!(result, b"wasm");
+  }
+
+  #[test]
+  fn should_error_when_no_wasm_file_and_build_not_allowed() {
+    let environment = TestEnvironment::new();
+    let crate_bytes = create_tarball(&[("dprint-plugin-foo-0.3.0/Cargo.toml", b"[package]\n")]);
+    environment.add_remote_file_bytes(
+      "https://index.crates.io/dp/ri/dprint-plugin-foo",
+      format!("{{\"vers\":\"0.3.0\",\"cksum\":\"{}\"}}\n", get_sha256_checksum(&crate_bytes)).into_bytes(),
+    );
+    environment.add_remote_file_bytes("https://static.crates.io/crates/dprint-plugin-foo/dprint-plugin-foo-0.3.0.crate", crate_bytes);
+
+    let err = download_crate_plugin_inner(&Url::parse("crate:dprint-plugin-foo").unwrap(), false, &environment)
+      .err()
+      .unwrap();
+    assert_eq!(
+      err.to_string(),
+      concat!(
+        "Could not find a Wasm file in crate dprint-plugin-foo@0.3.0. Specify it with the package.metadata.dprint.plugin ",
+        "property in the crate's Cargo.toml or set the DPRINT_BUILD_CRATE_PLUGINS environment variable to 1 to build the crate from source."
+      )
+    );
+  }
+}
@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use dprint_core::configuration::{ConfigKeyMap, ConfigurationDiagnostic, GlobalConfiguration};
+use dprint_core::plugins::{ChangedRegion, FormatDiagnostic};
+use dprint_core::types::ErrBox;
+
+use super::{InitializedPlugin, Plugin};
+use crate::environment::Environment;
+
+/// The configuration the plugin was initialized with.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugIoConfig {
+  pub plugin_config: ConfigKeyMap,
+  pub global_config: GlobalConfiguration,
+}
+
+/// A format request sent to the plugin.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugIoRequest {
+  pub file_path: PathBuf,
+  pub file_text: String,
+  pub override_config: ConfigKeyMap,
+}
+
+/// The plugin's response to a format request.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DebugIoResponse {
+  FormattedText(String),
+  Error(String),
+}
+
+pub fn get_debug_io_config_file_path(plugin_dir_path: &Path) -> PathBuf {
+  plugin_dir_path.join("config.json")
+}
+
+pub fn get_debug_io_request_file_path(plugin_dir_path: &Path, index: usize) -> PathBuf {
+  plugin_dir_path.join(format!("{:06}.request.json", index))
+}
+
+pub fn get_debug_io_response_file_path(plugin_dir_path: &Path, index: usize) -> PathBuf {
+  plugin_dir_path.join(format!("{:06}.response.json", index))
+}
+
+/// Wraps the plugins so that each format request and response is written
+/// to a sub directory of the provided directory named after the plugin.
+pub fn wrap_plugins_with_debug_io<TEnvironment: Environment>(plugins: Vec<Box<dyn Plugin>>, dir_path: &Path, environment: &TEnvironment) -> Vec<Box<dyn Plugin>> {
+  environment.log_error(&format!(
+    "Writing plugin requests and responses to {}. These contain the text of the formatted files.",
+    dir_path.display()
+  ));
+  plugins
+    .into_iter()
+    .map(|plugin| {
+      let plugin_dir_path = dir_path.join(plugin.name());
+      Box::new(DebugIoPlugin {
+        inner: plugin,
+        dir_path: plugin_dir_path,
+        request_count: Arc::new(AtomicUsize::new(0)),
+        environment: environment.clone(),
+      }) as Box<dyn Plugin>
+    })
+    .collect()
+}
+
+struct DebugIoPlugin<TEnvironment: Environment> {
+  inner: Box<dyn Plugin>,
+  dir_path: PathBuf,
+  request_count: Arc<AtomicUsize>,
+  environment: TEnvironment,
+}
+
+impl<TEnvironment: Environment> Plugin for DebugIoPlugin<TEnvironment> {
+  fn name(&self) -> &str {
+    self.inner.name()
+  }
+
+  fn version(&self) -> &str {
+    self.inner.version()
+  }
+
+  fn config_key(&self) -> &str {
+    self.inner.config_key()
+  }
+
+  fn file_extensions(&self) -> &Vec<String> {
+    self.inner.file_extensions()
+  }
+
+  fn file_names(&self) -> &Vec<String> {
+    self.inner.file_names()
+  }
+
+  fn help_url(&self) -> &str {
+    self.inner.help_url()
+  }
+
+  fn config_schema_url(&self) -> &str {
+    self.inner.config_schema_url()
+  }
+
+  fn supports_partial_format(&self) -> bool {
+    self.inner.supports_partial_format()
+  }
+
+  fn supports_format_diagnostics(&self) -> bool {
+    self.inner.supports_format_diagnostics()
+  }
+
+  fn only_changes_whitespace(&self) -> bool {
+    self.inner.only_changes_whitespace()
+  }
+
+  fn supports_changed_regions(&self) -> bool {
+    self.inner.supports_changed_regions()
+  }
+
+  fn set_config(&mut self, plugin_config: ConfigKeyMap, global_config: GlobalConfiguration) {
+    self.inner.set_config(plugin_config, global_config)
+  }
+
+  fn initialize(&self) -> Result<Box<dyn InitializedPlugin>, ErrBox> {
+    let (plugin_config, global_config) = self.inner.get_config();
+    let config = DebugIoConfig {
+      plugin_config: plugin_config.clone(),
+      global_config: global_config.clone(),
+    };
+    self.environment.mk_dir_all(&self.dir_path)?;
+    self
+      .environment
+      .write_file(&get_debug_io_config_file_path(&self.dir_path), &serde_json::to_string_pretty(&config)?)?;
+
+    Ok(Box::new(DebugIoInitializedPlugin {
+      inner: self.inner.initialize()?,
+      dir_path: self.dir_path.clone(),
+      request_count: self.request_count.clone(),
+      environment: self.environment.clone(),
+    }))
+  }
+
+  fn get_config(&self) -> &(ConfigKeyMap, GlobalConfiguration) {
+    self.inner.get_config()
+  }
+
+  fn get_hash(&self) -> u64 {
+    self.inner.get_hash()
+  }
+}
+
+struct DebugIoInitializedPlugin<TEnvironment: Environment> {
+  inner: Box<dyn InitializedPlugin>,
+  dir_path: PathBuf,
+  request_count: Arc<AtomicUsize>,
+  environment: TEnvironment,
+}
+
+impl<TEnvironment: Environment> DebugIoInitializedPlugin<TEnvironment> {
+  fn write_json_file(&self, file_path: &Path, value: &impl Serialize) {
+    let result = serde_json::to_string_pretty(value)
+      .map_err(ErrBox::from)
+      .and_then(|text| self.environment.write_file(file_path, &text));
+    if let Err(err) = result {
+      self
+        .environment
+        .log_error(&format!("Error writing plugin debug file {}: {}", file_path.display(), err.to_string()));
+    }
+  }
+}
+
+impl<TEnvironment: Environment> InitializedPlugin for DebugIoInitializedPlugin<TEnvironment> {
+  fn get_license_text(&self) -> Result<String, ErrBox> {
+    self.inner.get_license_text()
+  }
+
+  fn get_resolved_config(&self) -> Result<String, ErrBox> {
+    self.inner.get_resolved_config()
+  }
+
+  fn get_config_diagnostics(&self) -> Result<Vec<ConfigurationDiagnostic>, ErrBox> {
+    self.inner.get_config_diagnostics()
+  }
+
+  fn format_text(&mut self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox> {
+    let index = self.request_count.fetch_add(1, Ordering::SeqCst);
+    self.write_json_file(
+      &get_debug_io_request_file_path(&self.dir_path, index),
+      &DebugIoRequest {
+        file_path: file_path.to_path_buf(),
+        file_text: file_text.to_string(),
+        override_config: override_config.clone(),
+      },
+    );
+
+    let result = self.inner.format_text(file_path, file_text, override_config);
+    let response = match &result {
+      Ok(formatted_text) => DebugIoResponse::FormattedText(formatted_text.clone()),
+      Err(err) => DebugIoResponse::Error(err.to_string()),
+    };
+    self.write_json_file(&get_debug_io_response_file_path(&self.dir_path, index), &response);
+
+    result
+  }
+
+  fn take_format_diagnostics(&mut self) -> Result<Vec<FormatDiagnostic>, ErrBox> {
+    self.inner.take_format_diagnostics()
+  }
+
+  fn take_changed_regions(&mut self) -> Result<Option<Vec<ChangedRegion>>, ErrBox> {
+    self.inner.take_changed_regions()
+  }
+
+  fn trace_text(&mut self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox> {
+    self.inner.trace_text(file_path, file_text, override_config)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use crate::plugins::TestPlugin;
+  use pretty_assertions::assert_eq;
+  use std::collections::HashMap;
+
+  #[test]
+  fn should_write_requests_and_responses() {
+    let environment = TestEnvironment::new();
+    let plugins: Vec<Box<dyn Plugin>> = vec![Box::new(TestPlugin::new("test-plugin", "test", vec!["txt"], vec![]))];
+    let plugins = wrap_plugins_with_debug_io(plugins, &PathBuf::from("/debug"), &environment);
+    let mut initialized_plugin = plugins[0].initialize().unwrap();
+    assert_eq!(
+      initialized_plugin.format_text(&PathBuf::from("/file.txt"), "text", &HashMap::new()).unwrap(),
+      "text_formatted"
+    );
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Writing plugin requests and responses to /debug. These contain the text of the formatted files."]
+    );
+
+    let plugin_dir_path = PathBuf::from("/debug/test-plugin");
+    let config: DebugIoConfig = serde_json::from_str(&environment.read_file(get_debug_io_config_file_path(&plugin_dir_path)).unwrap()).unwrap();
+    assert_eq!(config.plugin_config, HashMap::new());
+    let request: DebugIoRequest = serde_json::from_str(&environment.read_file(get_debug_io_request_file_path(&plugin_dir_path, 0)).unwrap()).unwrap();
+    assert_eq!(
+      request,
+      DebugIoRequest {
+        file_path: PathBuf::from("/file.txt"),
+        file_text: String::from("text"),
+        override_config: HashMap::new(),
+      }
+    );
+    let response: DebugIoResponse = serde_json::from_str(&environment.read_file(get_debug_io_response_file_path(&plugin_dir_path, 0)).unwrap()).unwrap();
+    assert_eq!(response, DebugIoResponse::FormattedText(String::from("text_formatted")));
+  }
+}
@@ -0,0 +1,139 @@
+use dprint_cli_core::checksums::get_sha256_checksum;
+use dprint_cli_core::types::ErrBox;
+use std::fmt;
+use std::path::Path;
+
+use super::InitializedPluginPool;
+use crate::environment::Environment;
+use crate::utils::TextPosition;
+
+/// Number of characters of the file text's SHA-256 hash to include in the error.
+const FILE_HASH_LEN: usize = 12;
+
+/// An error a plugin had formatting a file along with details about the plugin
+/// so the user knows where to report it.
+#[derive(Debug)]
+pub struct PluginFormatError {
+  pub plugin_name: String,
+  pub plugin_version: String,
+  pub help_url: String,
+  pub file_path: String,
+  /// Start of the SHA-256 hash of the text that was formatted.
+  pub file_hash: String,
+  pub message: String,
+  /// Diagnostics the plugin reported about why formatting failed.
+  pub diagnostics: Vec<PluginErrorDiagnostic>,
+}
+
+/// A diagnostic with an error severity the plugin reported when it failed formatting a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginErrorDiagnostic {
+  pub message: String,
+  /// Position in the file text, which is `None` when the plugin didn't provide a span.
+  pub position: Option<TextPosition>,
+}
+
+/// An error formatting the text along with the plugin's error diagnostics.
+#[derive(Debug)]
+pub struct FormatDiagnosticsError {
+  pub message: String,
+  pub diagnostics: Vec<PluginErrorDiagnostic>,
+}
+
+impl std::error::Error for FormatDiagnosticsError {}
+
+impl fmt::Display for FormatDiagnosticsError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.message)
+  }
+}
+
+impl PluginFormatError {
+  pub fn new<TEnvironment: Environment>(
+    plugin_pool: &InitializedPluginPool<TEnvironment>,
+    file_path: &Path,
+    file_text: &str,
+    err: ErrBox,
+  ) -> PluginFormatError {
+    let mut file_hash = get_sha256_checksum(file_text.as_bytes());
+    file_hash.truncate(FILE_HASH_LEN);
+    let diagnostics = match err.downcast_ref::<FormatDiagnosticsError>() {
+      Some(err) => err.diagnostics.clone(),
+      None => Vec::new(),
+    };
+    PluginFormatError {
+      plugin_name: plugin_pool.name().to_string(),
+      plugin_version: plugin_pool.version().to_string(),
+      help_url: plugin_pool.help_url().to_string(),
+      file_path: file_path.display().to_string(),
+      file_hash,
+      message: err.to_string(),
+      diagnostics,
+    }
+  }
+
+  /// Gets the location of the diagnostic in the file (ex. `src/file.ts:1:5`).
+  pub fn get_diagnostic_location(&self, diagnostic: &PluginErrorDiagnostic) -> String {
+    match &diagnostic.position {
+      Some(position) => format!("{}:{}:{}", self.file_path, position.line_number, position.column_number),
+      None => self.file_path.clone(),
+    }
+  }
+}
+
+impl std::error::Error for PluginFormatError {}
+
+impl fmt::Display for PluginFormatError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.message)?;
+    for diagnostic in self.diagnostics.iter() {
+      write!(f, "\n  {}: {}", self.get_diagnostic_location(diagnostic), diagnostic.message)?;
+    }
+    write!(
+      f,
+      "\n  Plugin: {} {}\n  File: {} (sha256: {})\n  Report issues at: {}",
+      self.plugin_name, self.plugin_version, self.file_path, self.file_hash, self.help_url
+    )
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_display_diagnostics() {
+    let err = PluginFormatError {
+      plugin_name: "test-plugin".to_string(),
+      plugin_version: "0.1.0".to_string(),
+      help_url: "https://dprint.dev/plugins/test".to_string(),
+      file_path: "/file.ts".to_string(),
+      file_hash: "abc".to_string(),
+      message: "Could not parse file.".to_string(),
+      diagnostics: vec![
+        PluginErrorDiagnostic {
+          message: "Expected a semi-colon.".to_string(),
+          position: Some(TextPosition {
+            line_number: 2,
+            column_number: 5,
+          }),
+        },
+        PluginErrorDiagnostic {
+          message: "Unexpected end of file.".to_string(),
+          position: None,
+        },
+      ],
+    };
+    assert_eq!(
+      err.to_string(),
+      concat!(
+        "Could not parse file.\n",
+        "  /file.ts:2:5: Expected a semi-colon.\n",
+        "  /file.ts: Unexpected end of file.\n",
+        "  Plugin: test-plugin 0.1.0\n",
+        "  File: /file.ts (sha256: abc)\n",
+        "  Report issues at: https://dprint.dev/plugins/test"
+      )
+    );
+  }
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::environment::Environment;
+use crate::utils::ThreadExitSignal;
+
+use super::PluginPools;
+
+/// Thread that periodically drops the plugin instances that haven't been
+/// used in a while so long running processes don't keep them alive.
+pub struct IdlePluginCheckerThread<TEnvironment: Environment> {
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
+  idle_timeout: Duration,
+  thread_exit_signal: Arc<ThreadExitSignal>,
+}
+
+impl<TEnvironment: Environment> IdlePluginCheckerThread<TEnvironment> {
+  pub fn new(plugin_pools: Arc<PluginPools<TEnvironment>>, idle_timeout: Duration) -> Self {
+    IdlePluginCheckerThread {
+      plugin_pools,
+      idle_timeout,
+      thread_exit_signal: Arc::new(ThreadExitSignal::new()),
+    }
+  }
+
+  /// Spawns a thread to watch the plugin pools.
+  pub fn spawn(&self) {
+    let exit_signal = self.thread_exit_signal.clone();
+    let plugin_pools = self.plugin_pools.clone();
+    let idle_timeout = self.idle_timeout;
+    // check a few times within the timeout so instances aren't kept alive for much longer than it
+    let check_interval = std::cmp::min(idle_timeout / 4, Duration::from_secs(30));
+    thread::spawn(move || loop {
+      if !exit_signal.sleep_with_cancellation(check_interval) {
+        return;
+      }
+
+      plugin_pools.drop_idle_instances(idle_timeout);
+    });
+  }
+
+  pub fn signal_exit(&self) {
+    self.thread_exit_signal.signal_exit();
+  }
+}
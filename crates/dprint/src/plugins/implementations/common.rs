@@ -4,6 +4,16 @@ use std::path::Path;
 
 use crate::environment::Environment;
 use crate::plugins::pool::PluginPools;
+use crate::utils::FILE_BOUNDARY_POLICY_KEYS;
+
+/// Gets the plugin's configuration without the properties that are handled by the CLI.
+pub fn get_config_for_plugin(plugin_config: &ConfigKeyMap) -> ConfigKeyMap {
+  let mut plugin_config = plugin_config.clone();
+  for key in FILE_BOUNDARY_POLICY_KEYS.iter() {
+    plugin_config.remove(*key);
+  }
+  plugin_config
+}
 
 pub fn format_with_plugin_pool<TEnvironment: Environment>(
   parent_plugin_name: &str,
@@ -1,6 +1,7 @@
 use crate::environment::Environment;
 use dprint_core::configuration::{ConfigKeyMap, ConfigurationDiagnostic, GlobalConfiguration};
-use dprint_core::plugins::process::ProcessPluginCommunicator;
+use dprint_core::plugins::{ChangedRegion, FormatDiagnostic};
+use dprint_core::plugins::process::{ProcessPluginCommunicator, ProcessPluginFormatCanceller};
 use dprint_core::types::ErrBox;
 use std::cell::RefCell;
 use std::path::{Path, PathBuf};
@@ -46,16 +47,30 @@ impl<TEnvironment: Environment> InitializedProcessPluginCommunicator<TEnvironmen
     self.communicator.borrow_mut().get_config_diagnostics()
   }
 
-  pub fn recreate_process_if_dead(&self) -> Result<bool, ErrBox> {
-    let is_process_alive = { self.communicator.borrow_mut().is_process_alive() };
-    if is_process_alive {
-      Ok(false)
-    } else {
-      self.force_recreate_process()?;
-      Ok(true)
-    }
+  pub fn get_format_diagnostics(&self) -> Result<Vec<FormatDiagnostic>, ErrBox> {
+    self.communicator.borrow_mut().get_format_diagnostics()
   }
 
+  pub fn get_changed_regions(&self) -> Result<Option<Vec<ChangedRegion>>, ErrBox> {
+    self.communicator.borrow_mut().get_changed_regions()
+  }
+
+  pub fn trace_text(&self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox> {
+    self.communicator.borrow_mut().trace_text(file_path, file_text, override_config)
+  }
+
+  /// Gets a canceller for the format in progress. A new one should be created for each
+  /// format because the process may be recreated.
+  pub fn create_format_canceller(&self) -> ProcessPluginFormatCanceller {
+    self.communicator.borrow().create_format_canceller()
+  }
+
+  /// Gets if the process is running and responsive. Only use this after an error has occurred.
+  pub fn is_process_alive(&self) -> bool {
+    self.communicator.borrow_mut().is_process_alive()
+  }
+
+  /// Starts a new process and sends it the configuration.
   pub fn force_recreate_process(&self) -> Result<(), ErrBox> {
     let new_communicator = create_new_communicator(self.environment.clone(), self.plugin_name.clone(), &self.executable_file_path, &self.config)?;
     let mut communicator = self.communicator.borrow_mut();
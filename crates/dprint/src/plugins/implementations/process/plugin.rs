@@ -1,15 +1,21 @@
 use dprint_core::configuration::{ConfigKeyMap, ConfigurationDiagnostic, GlobalConfiguration};
-use dprint_core::plugins::PluginInfo;
+use dprint_core::plugins::{ChangedRegion, FormatDiagnostic, PluginInfo};
 use dprint_core::types::ErrBox;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::environment::Environment;
-use crate::plugins::{InitializedPlugin, Plugin, PluginPools};
+use crate::plugins::{FormatCanceller, InitializedPlugin, Plugin, PluginPools};
 
-use super::super::format_with_plugin_pool;
+use super::super::{format_with_plugin_pool, get_config_for_plugin};
 use super::InitializedProcessPluginCommunicator;
 
+/// The number of times a process plugin that exited unexpectedly is restarted to retry formatting a file.
+const MAX_PROCESS_RESTARTS: u32 = 3;
+/// The delay before restarting a process plugin, which doubles on each subsequent restart.
+const PROCESS_RESTART_INITIAL_DELAY: Duration = Duration::from_millis(50);
+
 static PLUGIN_FILE_INITIALIZE: std::sync::Once = std::sync::Once::new();
 
 /// Use this to get an executable file name that also works in the tests.
@@ -83,6 +89,22 @@ impl<TEnvironment: Environment> Plugin for ProcessPlugin<TEnvironment> {
     &self.plugin_info.config_schema_url
   }
 
+  fn supports_partial_format(&self) -> bool {
+    self.plugin_info.supports_partial_format
+  }
+
+  fn supports_format_diagnostics(&self) -> bool {
+    self.plugin_info.supports_format_diagnostics
+  }
+
+  fn only_changes_whitespace(&self) -> bool {
+    self.plugin_info.only_changes_whitespace
+  }
+
+  fn supports_changed_regions(&self) -> bool {
+    self.plugin_info.supports_changed_regions
+  }
+
   fn set_config(&mut self, plugin_config: ConfigKeyMap, global_config: GlobalConfiguration) {
     self.config = Some((plugin_config, global_config));
   }
@@ -92,12 +114,12 @@ impl<TEnvironment: Environment> Plugin for ProcessPlugin<TEnvironment> {
   }
 
   fn initialize(&self) -> Result<Box<dyn InitializedPlugin>, ErrBox> {
-    let config = self.config.as_ref().expect("Call set_config first.");
+    let (plugin_config, global_config) = self.config.as_ref().expect("Call set_config first.");
     let communicator = InitializedProcessPluginCommunicator::new(
       self.environment.clone(),
       self.plugin_info.name.clone(),
       self.executable_file_path.clone(),
-      config.clone(),
+      (get_config_for_plugin(plugin_config), global_config.clone()),
     )?;
     let process_plugin = InitializedProcessPlugin::new(self.name().to_string(), self.environment.clone(), communicator, self.plugin_pools.clone())?;
 
@@ -130,6 +152,16 @@ impl<TEnvironment: Environment> InitializedProcessPlugin<TEnvironment> {
   }
 
   fn inner_format_text(&self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox> {
+    // allow the format to be cancelled from another thread (ex. when an editor's buffer changed)
+    let canceller = self.communicator.create_format_canceller();
+    let environment = self.environment.clone();
+    let canceller: FormatCanceller = Arc::new(move || {
+      if let Err(err) = canceller.cancel() {
+        log_verbose!(environment, "Error cancelling format. {}", err.to_string());
+      }
+    });
+    let _registration = self.plugin_pools.register_format_canceller(file_path, canceller);
+
     self
       .communicator
       .format_text(file_path, file_text, override_config, |file_path, file_text, override_config| {
@@ -152,30 +184,61 @@ impl<TEnvironment: Environment> InitializedPlugin for InitializedProcessPlugin<T
   }
 
   fn format_text(&mut self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox> {
-    let result = self.inner_format_text(file_path, file_text, override_config);
-
-    match result {
-      Ok(result) => Ok(result),
-      Err(original_err) => {
-        // todo: tests for this somehow
-        let process_recreated = match self.communicator.recreate_process_if_dead() {
-          Ok(process_recreated) => process_recreated,
-          Err(err) => {
-            self.environment.log_error(&format!(
-              "Failed to recreate child process plugin after it was unresponsive: {}",
-              err.to_string()
-            ));
-            return Err(original_err);
-          }
-        };
-
-        if process_recreated {
-          // attempt formatting again
-          self.inner_format_text(file_path, file_text, override_config)
-        } else {
-          return Err(original_err);
+    let mut restart_count = 0;
+    loop {
+      let original_err = match self.inner_format_text(file_path, file_text, override_config) {
+        Ok(result) => return Ok(result),
+        Err(err) => err,
+      };
+      if self.communicator.is_process_alive() {
+        return Err(original_err);
+      }
+
+      // the process exited or is unresponsive, so restart it then retry formatting the file
+      loop {
+        if restart_count == MAX_PROCESS_RESTARTS {
+          return err!(
+            "The process plugin exited unexpectedly and was restarted {} times. {}",
+            MAX_PROCESS_RESTARTS,
+            original_err.to_string()
+          );
+        }
+        std::thread::sleep(PROCESS_RESTART_INITIAL_DELAY * 2u32.pow(restart_count));
+        restart_count += 1;
+        log_verbose!(
+          self.environment,
+          "Restarting process plugin {} ({}/{}).",
+          self.name,
+          restart_count,
+          MAX_PROCESS_RESTARTS
+        );
+        match self.communicator.force_recreate_process() {
+          Ok(()) => break,
+          Err(err) => self
+            .environment
+            .log_error(&format!("Failed to restart process plugin {}: {}", self.name, err.to_string())),
         }
       }
     }
   }
+
+  fn trace_text(&mut self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox> {
+    match self.communicator.trace_text(file_path, file_text, override_config) {
+      Ok(trace_json) => Ok(trace_json),
+      Err(err) if !self.communicator.is_process_alive() => {
+        // plugins built with an older version of dprint-core exit on unknown messages
+        self.communicator.force_recreate_process()?;
+        err!("The plugin may not support tracing. Upgrade the plugin, then try again. {}", err.to_string())
+      }
+      Err(err) => Err(err),
+    }
+  }
+
+  fn take_format_diagnostics(&mut self) -> Result<Vec<FormatDiagnostic>, ErrBox> {
+    self.communicator.get_format_diagnostics()
+  }
+
+  fn take_changed_regions(&mut self) -> Result<Option<Vec<ChangedRegion>>, ErrBox> {
+    self.communicator.get_changed_regions()
+  }
 }
@@ -5,6 +5,7 @@ use dprint_core::types::ErrBox;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::str;
+use url::Url;
 
 use crate::environment::Environment;
 use crate::utils::{extract_zip, fetch_file_or_url_bytes, resolve_url_or_file_path_to_path_source, PathSource};
@@ -122,7 +123,20 @@ fn get_plugin_zip_bytes<TEnvironment: Environment>(
 ) -> Result<ProcessPluginZipBytes, ErrBox> {
   let plugin_file = deserialize_file(&plugin_file_bytes)?;
   let plugin_path = get_os_path(&plugin_file)?;
-  let plugin_zip_path = resolve_url_or_file_path_to_path_source(&plugin_path.reference, &url_or_file_path.parent())?;
+  let plugin_zip_path = if url_or_file_path.is_npm_plugin() {
+    // the zip files aren't extracted from the npm package, so they must be at a url
+    match Url::parse(&plugin_path.reference) {
+      Ok(url) if !url.cannot_be_a_base() => PathSource::new_remote(url),
+      _ => {
+        return err!(
+          "Expected the zip file of a process plugin in an npm package to be a url: {}",
+          plugin_path.reference
+        )
+      }
+    }
+  } else {
+    resolve_url_or_file_path_to_path_source(&plugin_path.reference, &url_or_file_path.parent())?
+  };
   let plugin_zip_bytes = fetch_file_or_url_bytes(&plugin_zip_path, environment)?;
   verify_sha256_checksum(&plugin_zip_bytes, &plugin_path.checksum)?;
 
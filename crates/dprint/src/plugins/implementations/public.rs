@@ -7,8 +7,7 @@ use dprint_core::types::ErrBox;
 use super::process;
 use super::wasm;
 use crate::environment::Environment;
-use crate::plugins::{Plugin, PluginCache, PluginPools, PluginSourceReference};
-use crate::utils::PathSource;
+use crate::plugins::{Plugin, PluginCache, PluginCacheItem, PluginPools, PluginSourceReference};
 
 pub struct SetupPluginResult {
   pub file_path: PathBuf,
@@ -16,41 +15,45 @@ pub struct SetupPluginResult {
 }
 
 pub fn setup_plugin<TEnvironment: Environment>(
-  url_or_file_path: &PathSource,
+  plugin_reference: &PluginSourceReference,
   file_bytes: &[u8],
   environment: &TEnvironment,
 ) -> Result<SetupPluginResult, ErrBox> {
-  if url_or_file_path.is_wasm_plugin() {
-    wasm::setup_wasm_plugin(url_or_file_path, file_bytes, environment)
-  } else if url_or_file_path.is_process_plugin() {
-    process::setup_process_plugin(url_or_file_path, file_bytes, environment)
+  if plugin_reference.is_wasm_plugin() {
+    wasm::setup_wasm_plugin(&plugin_reference.path_source, file_bytes, environment)
+  } else if plugin_reference.is_process_plugin() {
+    process::setup_process_plugin(&plugin_reference.path_source, file_bytes, environment)
   } else {
-    return err!("Could not resolve plugin type from url or file path: {}", url_or_file_path.display());
+    return err!("Could not resolve plugin type from url or file path: {}", plugin_reference.display());
   }
 }
 
 pub fn get_file_path_from_plugin_info<TEnvironment: Environment>(
-  url_or_file_path: &PathSource,
+  plugin_reference: &PluginSourceReference,
   plugin_info: &PluginInfo,
   environment: &TEnvironment,
 ) -> Result<PathBuf, ErrBox> {
-  if url_or_file_path.is_wasm_plugin() {
+  if plugin_reference.is_wasm_plugin() {
     Ok(wasm::get_file_path_from_plugin_info(plugin_info, environment))
-  } else if url_or_file_path.is_process_plugin() {
+  } else if plugin_reference.is_process_plugin() {
     Ok(process::get_file_path_from_plugin_info(plugin_info, environment))
   } else {
-    return err!("Could not resolve plugin type from url or file path: {}", url_or_file_path.display());
+    return err!("Could not resolve plugin type from url or file path: {}", plugin_reference.display());
   }
 }
 
 /// Deletes the plugin from the cache.
-pub fn cleanup_plugin<TEnvironment: Environment>(url_or_file_path: &PathSource, plugin_info: &PluginInfo, environment: &TEnvironment) -> Result<(), ErrBox> {
-  if url_or_file_path.is_wasm_plugin() {
+pub fn cleanup_plugin<TEnvironment: Environment>(
+  plugin_reference: &PluginSourceReference,
+  plugin_info: &PluginInfo,
+  environment: &TEnvironment,
+) -> Result<(), ErrBox> {
+  if plugin_reference.is_wasm_plugin() {
     wasm::cleanup_wasm_plugin(plugin_info, environment)
-  } else if url_or_file_path.is_process_plugin() {
+  } else if plugin_reference.is_process_plugin() {
     process::cleanup_process_plugin(plugin_info, environment)
   } else {
-    return err!("Could not resolve plugin type from url or file path: {}", url_or_file_path.display());
+    return err!("Could not resolve plugin type from url or file path: {}", plugin_reference.display());
   }
 }
 
@@ -120,3 +123,29 @@ pub fn create_plugin<TEnvironment: Environment>(
     return err!("Could not resolve plugin type from url or file path: {}", plugin_reference.display());
   }
 }
+
+/// Creates a plugin from a previously resolved cache item without checking the plugin cache.
+pub fn create_plugin_from_cache_item<TEnvironment: Environment>(
+  plugin_pools: Arc<PluginPools<TEnvironment>>,
+  environment: TEnvironment,
+  plugin_reference: &PluginSourceReference,
+  cache_item: PluginCacheItem,
+) -> Result<Box<dyn Plugin>, ErrBox> {
+  if plugin_reference.is_wasm_plugin() {
+    let file_bytes = environment.read_file_bytes(&cache_item.file_path)?;
+    Ok(Box::new(wasm::WasmPlugin::new(file_bytes, cache_item.info, plugin_pools)?))
+  } else if plugin_reference.is_process_plugin() {
+    if !environment.path_exists(&cache_item.file_path) {
+      return err!("Could not find process plugin at {}.", cache_item.file_path.display());
+    }
+    let executable_path = super::process::get_test_safe_executable_path(cache_item.file_path, &environment);
+    Ok(Box::new(process::ProcessPlugin::new(
+      environment.clone(),
+      executable_path,
+      cache_item.info,
+      plugin_pools,
+    )))
+  } else {
+    return err!("Could not resolve plugin type from url or file path: {}", plugin_reference.display());
+  }
+}
@@ -1,12 +1,12 @@
 use dprint_core::types::ErrBox;
-use wasmer::{Module, Store};
+use wasmer::Module;
 
-use super::{create_identity_import_object, InitializedWasmPlugin};
+use super::{create_identity_import_object, create_interruptible_store, InitializedWasmPlugin};
 use crate::plugins::CompilationResult;
 
 /// Compiles a Wasm module.
 pub fn compile(wasm_bytes: &[u8]) -> Result<CompilationResult, ErrBox> {
-  let store = Store::default();
+  let store = create_interruptible_store();
   let module = Module::new(&store, wasm_bytes)?;
   let bytes = match module.serialize() {
     Ok(bytes) => Ok(bytes),
@@ -103,6 +103,12 @@ impl WasmFunctions {
     wasm_runtime_error_to_err_box(format_func.call()).map(|value| value.into())
   }
 
+  #[inline]
+  pub fn trace(&self) -> Result<FormatResult, ErrBox> {
+    let trace_func = self.get_export::<(), u8>("trace")?;
+    wasm_runtime_error_to_err_box(trace_func.call()).map(|value| value.into())
+  }
+
   #[inline]
   pub fn get_formatted_text(&self) -> Result<usize, ErrBox> {
     let get_formatted_text_func = self.get_export::<(), u32>("get_formatted_text")?;
@@ -115,6 +121,29 @@ impl WasmFunctions {
     wasm_runtime_error_to_err_box(get_error_text_func.call()).map(|value| value as usize)
   }
 
+  #[inline]
+  pub fn get_format_diagnostics(&self) -> Result<usize, ErrBox> {
+    let get_format_diagnostics_func = self.get_export::<(), u32>("get_format_diagnostics")?;
+    wasm_runtime_error_to_err_box(get_format_diagnostics_func.call()).map(|value| value as usize)
+  }
+
+  #[inline]
+  pub fn get_changed_regions(&self) -> Result<usize, ErrBox> {
+    let get_changed_regions_func = self.get_export::<(), u32>("get_changed_regions")?;
+    wasm_runtime_error_to_err_box(get_changed_regions_func.call()).map(|value| value as usize)
+  }
+
+  #[inline]
+  pub fn shutdown(&self) -> Result<(), ErrBox> {
+    let shutdown_func = self.get_export::<(), ()>("shutdown")?;
+    wasm_runtime_error_to_err_box(shutdown_func.call())
+  }
+
+  #[inline]
+  pub fn get_instance(&self) -> &Instance {
+    &self.instance
+  }
+
   #[inline]
   pub fn get_memory(&self) -> &Memory {
     &self.memory
@@ -4,6 +4,7 @@ mod import_object;
 mod load_instance;
 mod plugin;
 mod setup_wasm_plugin;
+mod timeout;
 
 pub use compile::*;
 use functions::*;
@@ -11,3 +12,4 @@ pub use import_object::*;
 use load_instance::*;
 pub use plugin::*;
 pub use setup_wasm_plugin::*;
+use timeout::*;
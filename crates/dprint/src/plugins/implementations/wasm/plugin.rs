@@ -1,12 +1,14 @@
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
-use dprint_core::configuration::{ConfigKeyMap, ConfigurationDiagnostic, GlobalConfiguration};
-use dprint_core::plugins::PluginInfo;
+use dprint_core::configuration::{ConfigKeyMap, ConfigKeyValue, ConfigurationDiagnostic, GlobalConfiguration};
+use dprint_core::plugins::{ChangedRegion, FormatDiagnostic, PluginInfo};
 use dprint_core::types::ErrBox;
 
-use super::{create_module, create_pools_import_object, load_instance, FormatResult, ImportObjectEnvironment, WasmFunctions};
+use super::super::get_config_for_plugin;
+use super::{create_module, create_pools_import_object, load_instance, run_with_timeout, FormatResult, ImportObjectEnvironment, WasmFunctions};
 use crate::environment::Environment;
 use crate::plugins::{InitializedPlugin, Plugin, PluginPools};
 
@@ -14,6 +16,8 @@ pub struct WasmPlugin<TEnvironment: Environment> {
   module: wasmer::Module,
   plugin_info: PluginInfo,
   config: Option<(ConfigKeyMap, GlobalConfiguration)>,
+  /// The maximum time a call to format a file may take.
+  timeout: Option<Duration>,
   plugin_pools: Arc<PluginPools<TEnvironment>>,
 }
 
@@ -24,6 +28,7 @@ impl<TEnvironment: Environment> WasmPlugin<TEnvironment> {
       module,
       plugin_info,
       config: None,
+      timeout: None,
       plugin_pools,
     })
   }
@@ -58,7 +63,29 @@ impl<TEnvironment: Environment> Plugin for WasmPlugin<TEnvironment> {
     &self.plugin_info.config_schema_url
   }
 
-  fn set_config(&mut self, plugin_config: ConfigKeyMap, global_config: GlobalConfiguration) {
+  fn supports_partial_format(&self) -> bool {
+    self.plugin_info.supports_partial_format
+  }
+
+  fn supports_format_diagnostics(&self) -> bool {
+    self.plugin_info.supports_format_diagnostics
+  }
+
+  fn only_changes_whitespace(&self) -> bool {
+    self.plugin_info.only_changes_whitespace
+  }
+
+  fn supports_changed_regions(&self) -> bool {
+    self.plugin_info.supports_changed_regions
+  }
+
+  fn set_config(&mut self, mut plugin_config: ConfigKeyMap, global_config: GlobalConfiguration) {
+    // the timeout is handled by the CLI, so don't provide it to the plugin (it's validated when resolving the plugin's config)
+    let timeout_ms = match plugin_config.remove("timeoutMs") {
+      Some(ConfigKeyValue::Number(value)) => Some(value as u64),
+      _ => global_config.timeout_ms.map(|value| value as u64),
+    };
+    self.timeout = timeout_ms.map(Duration::from_millis);
     self.config = Some((plugin_config, global_config));
   }
 
@@ -82,7 +109,8 @@ impl<TEnvironment: Environment> Plugin for WasmPlugin<TEnvironment> {
     let (plugin_config, global_config) = self.config.as_ref().expect("Call set_config first.");
 
     wasm_plugin.set_global_config(&global_config)?;
-    wasm_plugin.set_plugin_config(&plugin_config)?;
+    wasm_plugin.set_plugin_config(&get_config_for_plugin(plugin_config))?;
+    wasm_plugin.set_timeout(self.timeout);
 
     Ok(Box::new(wasm_plugin))
   }
@@ -97,6 +125,7 @@ pub struct InitializedWasmPlugin {
   create_import_object: Box<dyn Fn() -> wasmer::ImportObject + Send>,
   global_config: GlobalConfiguration,
   plugin_config: ConfigKeyMap,
+  timeout: Option<Duration>,
 }
 
 impl InitializedWasmPlugin {
@@ -115,8 +144,12 @@ impl InitializedWasmPlugin {
         use_tabs: None,
         indent_width: None,
         new_line_kind: None,
+        trim_leading_blank_lines: None,
+        single_trailing_new_line: None,
+        timeout_ms: None,
       },
       plugin_config: HashMap::new(),
+      timeout: None,
     })
   }
 
@@ -135,6 +168,11 @@ impl InitializedWasmPlugin {
     Ok(())
   }
 
+  /// Sets the maximum time formatting a file may take before the plugin is stopped.
+  pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+    self.timeout = timeout;
+  }
+
   pub fn get_plugin_info(&self) -> Result<PluginInfo, ErrBox> {
     let len = self.wasm_functions.get_plugin_info()?;
     let json_text = self.receive_string(len)?;
@@ -180,6 +218,68 @@ impl InitializedWasmPlugin {
     Ok(String::from_utf8(bytes)?)
   }
 
+  /// Sends the override configuration, file path, and file text in preparation for formatting.
+  fn send_file(&mut self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<(), ErrBox> {
+    // send override config if necessary
+    if !override_config.is_empty() {
+      self.send_string(&serde_json::to_string(override_config)?);
+      if let Err(err) = self.wasm_functions.set_override_config() {
+        self.reinitialize_due_to_panic(&err);
+        return Err(err);
+      }
+    }
+
+    // send file path
+    self.send_string(&file_path.to_string_lossy());
+
+    if let Err(err) = self.wasm_functions.set_file_path() {
+      self.reinitialize_due_to_panic(&err);
+      return Err(err);
+    }
+
+    // send file text
+    self.send_string(file_text);
+    Ok(())
+  }
+
+  fn receive_format_result(&mut self, response_code: FormatResult, file_text: &str) -> Result<String, ErrBox> {
+    match response_code {
+      FormatResult::NoChange => Ok(String::from(file_text)),
+      FormatResult::Change => {
+        let len = match self.wasm_functions.get_formatted_text() {
+          Ok(len) => len,
+          Err(err) => {
+            self.reinitialize_due_to_panic(&err);
+            return Err(err);
+          }
+        };
+        match self.receive_string(len) {
+          Ok(text) => Ok(text),
+          Err(err) => {
+            self.reinitialize_due_to_panic(&err);
+            Err(err)
+          }
+        }
+      }
+      FormatResult::Error => {
+        let len = match self.wasm_functions.get_error_text() {
+          Ok(len) => len,
+          Err(err) => {
+            self.reinitialize_due_to_panic(&err);
+            return Err(err);
+          }
+        };
+        match self.receive_string(len) {
+          Ok(text) => err!("{}", text),
+          Err(err) => {
+            self.reinitialize_due_to_panic(&err);
+            Err(err)
+          }
+        }
+      }
+    }
+  }
+
   fn read_bytes_from_memory_buffer(&self, bytes: &mut [u8]) {
     let length = bytes.len();
     let wasm_buffer_pointer = self.wasm_functions.get_wasm_memory_buffer_ptr().unwrap();
@@ -214,6 +314,13 @@ impl InitializedWasmPlugin {
   }
 }
 
+impl Drop for InitializedWasmPlugin {
+  fn drop(&mut self) {
+    // give the plugin a chance to clean up (older plugins won't have this export)
+    let _ignore = self.wasm_functions.shutdown();
+  }
+}
+
 impl InitializedPlugin for InitializedWasmPlugin {
   fn get_license_text(&self) -> Result<String, ErrBox> {
     let len = self.wasm_functions.get_license_text()?;
@@ -232,68 +339,40 @@ impl InitializedPlugin for InitializedWasmPlugin {
   }
 
   fn format_text(&mut self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox> {
-    // send override config if necessary
-    if !override_config.is_empty() {
-      self.send_string(&serde_json::to_string(override_config)?);
-      if let Err(err) = self.wasm_functions.set_override_config() {
+    self.send_file(file_path, file_text, override_config)?;
+    let wasm_functions = &self.wasm_functions;
+    let response_code = match run_with_timeout(wasm_functions.get_instance(), self.timeout, || wasm_functions.format()) {
+      Ok(code) => code,
+      Err(err) => {
         self.reinitialize_due_to_panic(&err);
         return Err(err);
       }
-    }
-
-    // send file path
-    self.send_string(&file_path.to_string_lossy());
-
-    if let Err(err) = self.wasm_functions.set_file_path() {
-      self.reinitialize_due_to_panic(&err);
-      return Err(err);
-    }
+    };
+    self.receive_format_result(response_code, file_text)
+  }
 
-    // send file text and format
-    self.send_string(file_text);
-    let response_code = match self.wasm_functions.format() {
+  fn trace_text(&mut self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox> {
+    self.send_file(file_path, file_text, override_config)?;
+    let wasm_functions = &self.wasm_functions;
+    let response_code = match run_with_timeout(wasm_functions.get_instance(), self.timeout, || wasm_functions.trace()) {
       Ok(code) => code,
       Err(err) => {
         self.reinitialize_due_to_panic(&err);
-        return Err(err);
+        return err!("The plugin may not support tracing. Upgrade the plugin, then try again. {}", err.to_string());
       }
     };
+    self.receive_format_result(response_code, file_text)
+  }
 
-    // handle the response
-    match response_code {
-      FormatResult::NoChange => Ok(String::from(file_text)),
-      FormatResult::Change => {
-        let len = match self.wasm_functions.get_formatted_text() {
-          Ok(len) => len,
-          Err(err) => {
-            self.reinitialize_due_to_panic(&err);
-            return Err(err);
-          }
-        };
-        match self.receive_string(len) {
-          Ok(text) => Ok(text),
-          Err(err) => {
-            self.reinitialize_due_to_panic(&err);
-            return Err(err);
-          }
-        }
-      }
-      FormatResult::Error => {
-        let len = match self.wasm_functions.get_error_text() {
-          Ok(len) => len,
-          Err(err) => {
-            self.reinitialize_due_to_panic(&err);
-            return Err(err);
-          }
-        };
-        match self.receive_string(len) {
-          Ok(text) => err!("{}", text),
-          Err(err) => {
-            self.reinitialize_due_to_panic(&err);
-            return Err(err);
-          }
-        }
-      }
-    }
+  fn take_format_diagnostics(&mut self) -> Result<Vec<FormatDiagnostic>, ErrBox> {
+    let len = self.wasm_functions.get_format_diagnostics()?;
+    let json_text = self.receive_string(len)?;
+    Ok(serde_json::from_str(&json_text)?)
+  }
+
+  fn take_changed_regions(&mut self) -> Result<Option<Vec<ChangedRegion>>, ErrBox> {
+    let len = self.wasm_functions.get_changed_regions()?;
+    let json_text = self.receive_string(len)?;
+    Ok(serde_json::from_str(&json_text)?)
   }
 }
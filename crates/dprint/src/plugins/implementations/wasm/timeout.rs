@@ -0,0 +1,99 @@
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use dprint_core::types::ErrBox;
+use wasmer::wasmparser::{Operator, Type as WpType, TypeOrFuncType};
+use wasmer::{CompilerConfig, Cranelift, FunctionMiddleware, Instance, MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Store, Universal, Val};
+use wasmer_types::{ExportIndex, GlobalIndex, GlobalInit, GlobalType, LocalFunctionIndex, ModuleInfo, Mutability, Type};
+
+/// Global exported by modules compiled with the `ExecutionInterrupt` middleware.
+const INTERRUPT_GLOBAL_NAME: &str = "dprint_interrupt";
+
+/// Creates a store for compiling modules whose execution may be interrupted from
+/// another thread (ex. when a plugin is stuck in an infinite loop).
+pub fn create_interruptible_store() -> Store {
+  let mut compiler_config = Cranelift::default();
+  compiler_config.push_middleware(Arc::new(ExecutionInterrupt::default()));
+  Store::new(&Universal::new(compiler_config).engine())
+}
+
+/// Runs the action, interrupting the execution of the instance when it takes longer than the timeout.
+pub fn run_with_timeout<T>(instance: &Instance, timeout: Option<Duration>, action: impl FnOnce() -> Result<T, ErrBox>) -> Result<T, ErrBox> {
+  let timeout = match timeout {
+    Some(timeout) => timeout,
+    None => return action(),
+  };
+  let interrupt_global = match instance.exports.get_global(INTERRUPT_GLOBAL_NAME) {
+    Ok(global) => global.clone(),
+    // the module was compiled without the middleware
+    Err(_) => return action(),
+  };
+
+  interrupt_global.set(Val::I32(0))?;
+  let (sender, receiver) = channel::<()>();
+  let watchdog = std::thread::spawn({
+    let interrupt_global = interrupt_global.clone();
+    move || {
+      if let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(timeout) {
+        // the plugin traps the next time it checks the global
+        let _ = interrupt_global.set(Val::I32(1));
+      }
+    }
+  });
+  let result = action();
+  drop(sender);
+  let _ = watchdog.join();
+
+  match result {
+    Err(_) if matches!(interrupt_global.get(), Val::I32(1)) => err!("Formatting timed out after {}ms.", timeout.as_millis()),
+    result => result,
+  }
+}
+
+/// Adds a global to the module that's checked at the start of every loop, trapping when it's set.
+#[derive(Debug, Default)]
+struct ExecutionInterrupt {
+  global_index: Mutex<Option<GlobalIndex>>,
+}
+
+impl ModuleMiddleware for ExecutionInterrupt {
+  fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+    Box::new(FunctionExecutionInterrupt {
+      global_index: self.global_index.lock().unwrap().expect("Expected the module info to be transformed first."),
+    })
+  }
+
+  fn transform_module_info(&self, module_info: &mut ModuleInfo) {
+    let global_index = module_info.globals.push(GlobalType::new(Type::I32, Mutability::Var));
+    module_info.global_initializers.push(GlobalInit::I32Const(0));
+    module_info.exports.insert(INTERRUPT_GLOBAL_NAME.to_string(), ExportIndex::Global(global_index));
+    *self.global_index.lock().unwrap() = Some(global_index);
+  }
+}
+
+#[derive(Debug)]
+struct FunctionExecutionInterrupt {
+  global_index: GlobalIndex,
+}
+
+impl FunctionMiddleware for FunctionExecutionInterrupt {
+  fn feed<'a>(&mut self, operator: Operator<'a>, state: &mut MiddlewareReaderState<'a>) -> Result<(), MiddlewareError> {
+    let is_loop = matches!(operator, Operator::Loop { .. });
+    state.push_operator(operator);
+    if is_loop {
+      // branching to a loop goes to the start of its body, so this is checked on every iteration
+      state.extend(&[
+        Operator::GlobalGet {
+          global_index: self.global_index.as_u32(),
+        },
+        Operator::If {
+          ty: TypeOrFuncType::Type(WpType::EmptyBlockType),
+        },
+        Operator::Unreachable,
+        Operator::End,
+      ]);
+    }
+    Ok(())
+  }
+}
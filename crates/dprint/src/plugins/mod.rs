@@ -1,17 +1,27 @@
 mod cache;
 mod cache_manifest;
+mod crates_io;
+mod debug_io;
+mod format_error;
 mod helpers;
+mod idle_plugin_checker_thread;
 mod implementations;
+mod npm;
+mod oci;
 mod plugin;
 mod pool;
 mod repo;
 mod resolver;
+mod tarball;
 mod types;
 mod worker;
 
 pub use cache::*;
+pub use debug_io::*;
+pub use format_error::*;
 use cache_manifest::*;
 pub use helpers::*;
+pub use idle_plugin_checker_thread::*;
 pub use plugin::*;
 pub use pool::*;
 pub use repo::*;
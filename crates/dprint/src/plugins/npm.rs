@@ -0,0 +1,462 @@
+use dprint_core::types::ErrBox;
+use serde::Deserialize;
+use std::collections::HashMap;
+use url::Url;
+
+use super::tarball::extract_tarball_file;
+use crate::environment::Environment;
+
+const DEFAULT_REGISTRY_URL: &str = "https://registry.npmjs.org/";
+/// The file in the package to use when the package.json does not have a `dprint.plugin` property.
+const DEFAULT_PLUGIN_FILE: &str = "plugin.wasm";
+/// The file in the package to use for a process plugin when the package.json does not have a `dprint.plugin` property.
+const DEFAULT_PROCESS_PLUGIN_FILE: &str = "plugin.exe-plugin";
+
+/// A plugin specifier for a package in an npm registry (ex. `npm:@scope/plugin@1.2.3`).
+#[derive(Debug, PartialEq)]
+struct NpmSpecifier {
+  name: String,
+  /// Version or dist-tag (ex. `1.2.3` or `latest`).
+  version: String,
+}
+
+impl NpmSpecifier {
+  fn parse(text: &str) -> Result<NpmSpecifier, ErrBox> {
+    let text = match text.strip_prefix("npm:") {
+      Some(text) => text,
+      None => return err!("Expected an npm specifier starting with npm: {}", text),
+    };
+    // skip the @ of a scoped package name
+    let (name, version) = match text.chars().skip(1).position(|c| c == '@') {
+      Some(index) => (&text[..index + 1], &text[index + 2..]),
+      None => (text, "latest"),
+    };
+    let is_valid_name = if let Some(scoped_name) = name.strip_prefix('@') {
+      scoped_name.split('/').count() == 2 && scoped_name.split('/').all(|part| !part.is_empty())
+    } else {
+      !name.is_empty() && !name.contains('/')
+    };
+    if !is_valid_name || version.is_empty() {
+      return err!(
+        "Expected an npm specifier in the format npm:<package>@<version> (ex. npm:@scope/plugin@1.2.3): npm:{}",
+        text
+      );
+    }
+    Ok(NpmSpecifier {
+      name: name.to_string(),
+      version: version.to_string(),
+    })
+  }
+
+  fn scope(&self) -> Option<&str> {
+    if self.name.starts_with('@') {
+      self.name.split('/').next()
+    } else {
+      None
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct PackageInfo {
+  #[serde(rename = "dist-tags", default)]
+  dist_tags: HashMap<String, String>,
+  #[serde(default)]
+  versions: HashMap<String, PackageVersionInfo>,
+}
+
+#[derive(Deserialize)]
+struct PackageVersionInfo {
+  dist: PackageDistInfo,
+  #[serde(default)]
+  dprint: Option<PackageDprintInfo>,
+}
+
+#[derive(Deserialize)]
+struct PackageDistInfo {
+  tarball: String,
+  integrity: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PackageDprintInfo {
+  plugin: Option<String>,
+}
+
+/// Downloads the Wasm plugin or process plugin file (`.exe-plugin`) from an npm package.
+///
+/// The registry and credentials are taken from the project's and user's `.npmrc` files
+/// along with the `NPM_CONFIG_REGISTRY` environment variable.
+pub fn download_npm_plugin(url: &Url, is_process_plugin: bool, environment: &impl Environment) -> Result<Vec<u8>, ErrBox> {
+  let mut npmrc_texts = Vec::new();
+  let npmrc_file_paths = vec![Some(environment.cwd().join(".npmrc")), dirs::home_dir().map(|dir| dir.join(".npmrc"))];
+  for file_path in npmrc_file_paths.into_iter().flatten() {
+    if let Ok(text) = environment.read_file(&file_path) {
+      npmrc_texts.push(text);
+    }
+  }
+  let config = NpmConfig::new(&npmrc_texts, |name| std::env::var(name).ok());
+  download_npm_plugin_with_config(url, is_process_plugin, &config, environment)
+}
+
+fn download_npm_plugin_with_config(url: &Url, is_process_plugin: bool, config: &NpmConfig, environment: &impl Environment) -> Result<Vec<u8>, ErrBox> {
+  let specifier = NpmSpecifier::parse(url.as_str())?;
+  let registry_url = config.get_registry_url(specifier.scope());
+  // scoped package names have their slash encoded (ex. @scope%2fplugin)
+  let package_info_url = format!("{}/{}", registry_url.trim_end_matches('/'), specifier.name.replace('/', "%2f"));
+  let response = fetch_with_auth(&package_info_url, "application/json", config, environment)?;
+  let package_info: PackageInfo = match serde_json::from_slice(&response) {
+    Ok(package_info) => package_info,
+    Err(err) => return err!("Error deserializing the package information of {}. {}", specifier.name, err.to_string()),
+  };
+
+  let version = package_info.dist_tags.get(&specifier.version).unwrap_or(&specifier.version);
+  let version_info = match package_info.versions.get(version) {
+    Some(version_info) => version_info,
+    None => return err!("Could not find version {} of npm package {}.", specifier.version, specifier.name),
+  };
+  let default_plugin_file = if is_process_plugin {
+    DEFAULT_PROCESS_PLUGIN_FILE
+  } else {
+    DEFAULT_PLUGIN_FILE
+  };
+  let plugin_file = version_info
+    .dprint
+    .as_ref()
+    .and_then(|info| info.plugin.as_deref())
+    .unwrap_or(default_plugin_file);
+  let is_process_plugin_file = plugin_file.to_lowercase().ends_with(".exe-plugin");
+  if is_process_plugin_file && !is_process_plugin {
+    return err!(
+      concat!(
+        "The plugin file of {0}@{1} is the process plugin {2}, which must have a checksum specified for security reasons. ",
+        "You may specify one by writing \"npm:{0}@{1}@checksum-goes-here\" in the configuration file ",
+        "where the checksum is the SHA-256 checksum of {2}."
+      ),
+      specifier.name,
+      version,
+      plugin_file
+    );
+  } else if !is_process_plugin_file && is_process_plugin {
+    return err!(
+      "Expected the plugin file of {}@{} to be a process plugin (.exe-plugin) because a checksum was specified, but it was {}.",
+      specifier.name,
+      version,
+      plugin_file
+    );
+  } else if !is_process_plugin_file && !plugin_file.to_lowercase().ends_with(".wasm") {
+    return err!(
+      "Expected the plugin file of {}@{} to be a Wasm plugin (.wasm) or process plugin (.exe-plugin), but it was {}.",
+      specifier.name,
+      version,
+      plugin_file
+    );
+  }
+
+  let tarball = fetch_with_auth(&version_info.dist.tarball, "*/*", config, environment)?;
+  match &version_info.dist.integrity {
+    Some(integrity) => verify_integrity(&tarball, integrity)?,
+    None => return err!("Expected the npm package {}@{} to have an integrity.", specifier.name, version),
+  }
+  match extract_tarball_file(&tarball, plugin_file)? {
+    Some(bytes) => Ok(bytes),
+    None => err!("Could not find the plugin file {} in npm package {}@{}.", plugin_file, specifier.name, version),
+  }
+}
+
+fn fetch_with_auth(url: &str, accept: &str, config: &NpmConfig, environment: &impl Environment) -> Result<Vec<u8>, ErrBox> {
+  let mut headers = vec![("Accept", accept)];
+  let authorization = config.get_authorization(url);
+  if let Some(authorization) = &authorization {
+    headers.push(("Authorization", authorization));
+  }
+  let response = environment.fetch_url(url, &headers)?;
+  match response.status {
+    200 => Ok(response.bytes),
+    401 | 403 => err!(
+      "Error downloading {}. Status: {}. Ensure a token for the registry is configured in an .npmrc file.",
+      url,
+      response.status
+    ),
+    status => err!("Error downloading {}. Status: {}", url, status),
+  }
+}
+
+/// Verifies the bytes against a subresource integrity string (ex. `sha512-<base64>`).
+fn verify_integrity(bytes: &[u8], integrity: &str) -> Result<(), ErrBox> {
+  use sha2::{Digest, Sha512};
+
+  // an integrity may have multiple hashes separated by whitespace
+  for hash in integrity.split_whitespace() {
+    if let Some(expected) = hash.strip_prefix("sha512-") {
+      let actual = base64::encode(Sha512::digest(bytes));
+      return if actual == expected {
+        Ok(())
+      } else {
+        err!(
+          "The integrity of the downloaded npm package did not match. Expected: {}, Actual: sha512-{}",
+          hash,
+          actual
+        )
+      };
+    }
+  }
+  err!("Unsupported integrity for npm package: {}", integrity)
+}
+
+/// The npm configuration from `.npmrc` files.
+struct NpmConfig {
+  values: HashMap<String, String>,
+}
+
+impl NpmConfig {
+  /// Creates the configuration from the text of `.npmrc` files in order of precedence.
+  fn new(npmrc_texts: &[String], read_env_var: impl Fn(&str) -> Option<String>) -> NpmConfig {
+    let mut values = HashMap::new();
+    for text in npmrc_texts.iter().rev() {
+      for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+          continue;
+        }
+        if let Some(index) = line.find('=') {
+          let key = line[..index].trim().to_string();
+          let value = replace_env_vars(line[index + 1..].trim().trim_matches('"'), &read_env_var);
+          values.insert(key, value);
+        }
+      }
+    }
+    if let Some(registry) = read_env_var("NPM_CONFIG_REGISTRY").or_else(|| read_env_var("npm_config_registry")) {
+      if !registry.trim().is_empty() {
+        values.insert(String::from("registry"), registry);
+      }
+    }
+    NpmConfig { values }
+  }
+
+  fn get_registry_url(&self, scope: Option<&str>) -> &str {
+    scope
+      .and_then(|scope| self.values.get(&format!("{}:registry", scope)))
+      .or_else(|| self.values.get("registry"))
+      .map(|registry| registry.as_str())
+      .unwrap_or(DEFAULT_REGISTRY_URL)
+  }
+
+  /// Gets the authorization header value for the url, which is configured in keys
+  /// with the registry url without the protocol (ex. `//registry.npmjs.org/:_authToken`).
+  fn get_authorization(&self, url: &str) -> Option<String> {
+    let url = url.trim_start_matches("https:").trim_start_matches("http:");
+    let mut best_match: Option<(&str, String)> = None;
+    for (key, value) in self.values.iter() {
+      let (prefix, authorization) = if let Some(prefix) = key.strip_suffix(":_authToken") {
+        (prefix, format!("Bearer {}", value))
+      } else if let Some(prefix) = key.strip_suffix(":_auth") {
+        (prefix, format!("Basic {}", value))
+      } else {
+        continue;
+      };
+      // use the most specific match
+      if url.starts_with(prefix) && best_match.as_ref().map(|(best_prefix, _)| prefix.len() > best_prefix.len()).unwrap_or(true) {
+        best_match = Some((prefix, authorization));
+      }
+    }
+    best_match.map(|(_, authorization)| authorization)
+  }
+}
+
+/// Replaces `${NAME}` with the value of the environment variable.
+fn replace_env_vars(text: &str, read_env_var: &impl Fn(&str) -> Option<String>) -> String {
+  let mut result = String::new();
+  let mut remaining = text;
+  while let Some(start) = remaining.find("${") {
+    result.push_str(&remaining[..start]);
+    match remaining[start..].find('}') {
+      Some(end) => {
+        let name = &remaining[start + 2..start + end];
+        result.push_str(&read_env_var(name).unwrap_or_default());
+        remaining = &remaining[start + end + 1..];
+      }
+      None => {
+        result.push_str(&remaining[start..]);
+        remaining = "";
+      }
+    }
+  }
+  result.push_str(remaining);
+  result
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use crate::plugins::tarball::create_tarball;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_parse_npm_specifier() {
+    assert_eq!(
+      NpmSpecifier::parse("npm:@scope/plugin@1.2.3").unwrap(),
+      NpmSpecifier {
+        name: String::from("@scope/plugin"),
+        version: String::from("1.2.3"),
+      }
+    );
+    assert_eq!(
+      NpmSpecifier::parse("npm:plugin").unwrap(),
+      NpmSpecifier {
+        name: String::from("plugin"),
+        version: String::from("latest"),
+      }
+    );
+    assert_eq!(NpmSpecifier::parse("npm:@scope/plugin").unwrap().scope(), Some("@scope"));
+    assert_eq!(NpmSpecifier::parse("npm:plugin@next").unwrap().scope(), None);
+    assert_eq!(
+      NpmSpecifier::parse("npm:@scope@1.0.0").err().unwrap().to_string(),
+      "Expected an npm specifier in the format npm:<package>@<version> (ex. npm:@scope/plugin@1.2.3): npm:@scope@1.0.0"
+    );
+  }
+
+  #[test]
+  fn should_get_registry_and_authorization_from_config() {
+    let config = NpmConfig::new(
+      &[
+        String::from("@scope:registry=https://npm.example.com/\n//npm.example.com/:_authToken=${NPM_TOKEN}"),
+        String::from("registry=https://other.example.com/\n//npm.example.com/:_authToken=overwritten\n//registry.npmjs.org/:_auth=dXNlcjpwYXNz"),
+      ],
+      |name| if name == "NPM_TOKEN" { Some(String::from("token")) } else { None },
+    );
+    assert_eq!(config.get_registry_url(Some("@scope")), "https://npm.example.com/");
+    assert_eq!(config.get_registry_url(Some("@other")), "https://other.example.com/");
+    assert_eq!(config.get_registry_url(None), "https://other.example.com/");
+    assert_eq!(
+      config.get_authorization("https://npm.example.com/@scope%2fplugin"),
+      Some(String::from("Bearer token"))
+    );
+    assert_eq!(
+      config.get_authorization("https://registry.npmjs.org/plugin"),
+      Some(String::from("Basic dXNlcjpwYXNz"))
+    );
+    assert_eq!(config.get_authorization("https://other.example.com/plugin"), None);
+
+    let config = NpmConfig::new(&[], |name| {
+      if name == "NPM_CONFIG_REGISTRY" {
+        Some(String::from("https://env.example.com/"))
+      } else {
+        None
+      }
+    });
+    assert_eq!(config.get_registry_url(None), "https://env.example.com/");
+    assert_eq!(NpmConfig::new(&[], |_| None).get_registry_url(None), DEFAULT_REGISTRY_URL);
+  }
+
+  #[test]
+  fn should_download_plugin() {
+    let environment = TestEnvironment::new();
+    let tarball = create_tarball(&[("package/package.json", b"{}"), ("package/dist/plugin.wasm", b"wasm")]);
+    add_package(
+      &environment,
+      &tarball,
+      &get_integrity(&tarball),
+      r#", "dprint": { "plugin": "./dist/plugin.wasm" }"#,
+    );
+
+    let result = download_npm_plugin_with_config(
+      &Url::parse("npm:@scope/plugin@latest").unwrap(),
+      false,
+      &NpmConfig::new(&[], |_| None),
+      &environment,
+    )
+    .unwrap();
+    assert_eq!(result, b"wasm");
+  }
+
+  #[test]
+  fn should_error_when_integrity_does_not_match() {
+    let environment = TestEnvironment::new();
+    let tarball = create_tarball(&[("package/plugin.wasm", b"wasm")]);
+    add_package(&environment, &tarball, "sha512-other", "");
+
+    let url = Url::parse("npm:@scope/plugin@1.2.3").unwrap();
+    let err = download_npm_plugin_with_config(&url, false, &NpmConfig::new(&[], |_| None), &environment)
+      .err()
+      .unwrap();
+    assert_eq!(
+      err.to_string(),
+      format!(
+        "The integrity of the downloaded npm package did not match. Expected: sha512-other, Actual: {}",
+        get_integrity(&tarball)
+      )
+    );
+  }
+
+  #[test]
+  fn should_download_process_plugin() {
+    let environment = TestEnvironment::new();
+    let tarball = create_tarball(&[("package/plugin.exe-plugin", b"{}")]);
+    add_package(&environment, &tarball, &get_integrity(&tarball), "");
+
+    let url = Url::parse("npm:@scope/plugin@1.2.3").unwrap();
+    let result = download_npm_plugin_with_config(&url, true, &NpmConfig::new(&[], |_| None), &environment).unwrap();
+    assert_eq!(result, b"{}");
+  }
+
+  #[test]
+  fn should_error_for_process_plugin_without_checksum() {
+    let environment = TestEnvironment::new();
+    let tarball = create_tarball(&[("package/plugin.exe-plugin", b"{}")]);
+    add_package(
+      &environment,
+      &tarball,
+      &get_integrity(&tarball),
+      r#", "dprint": { "plugin": "plugin.exe-plugin" }"#,
+    );
+
+    let url = Url::parse("npm:@scope/plugin@1.2.3").unwrap();
+    let err = download_npm_plugin_with_config(&url, false, &NpmConfig::new(&[], |_| None), &environment)
+      .err()
+      .unwrap();
+    assert_eq!(
+      err.to_string(),
+      concat!(
+        "The plugin file of @scope/plugin@1.2.3 is the process plugin plugin.exe-plugin, which must have a checksum specified for security reasons. ",
+        "You may specify one by writing \"npm:@scope/plugin@1.2.3@checksum-goes-here\" in the configuration file ",
+        "where the checksum is the SHA-256 checksum of plugin.exe-plugin."
+      )
+    );
+  }
+
+  #[test]
+  fn should_error_for_wasm_plugin_with_checksum() {
+    let environment = TestEnvironment::new();
+    let tarball = create_tarball(&[("package/plugin.wasm", b"wasm")]);
+    add_package(&environment, &tarball, &get_integrity(&tarball), r#", "dprint": { "plugin": "plugin.wasm" }"#);
+
+    let url = Url::parse("npm:@scope/plugin@1.2.3").unwrap();
+    let err = download_npm_plugin_with_config(&url, true, &NpmConfig::new(&[], |_| None), &environment)
+      .err()
+      .unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Expected the plugin file of @scope/plugin@1.2.3 to be a process plugin (.exe-plugin) because a checksum was specified, but it was plugin.wasm."
+    );
+  }
+
+  fn add_package(environment: &TestEnvironment, tarball: &[u8], integrity: &str, extra_properties: &str) {
+    let tarball_url = "https://registry.npmjs.org/@scope/plugin/-/plugin-1.2.3.tgz";
+    environment.add_remote_file_bytes(
+      "https://registry.npmjs.org/@scope%2fplugin",
+      format!(
+        r#"{{ "dist-tags": {{ "latest": "1.2.3" }}, "versions": {{ "1.2.3": {{ "dist": {{ "tarball": "{}", "integrity": "{}" }}{} }} }} }}"#,
+        tarball_url, integrity, extra_properties
+      )
+      .into_bytes(),
+    );
+    environment.add_remote_file_bytes(tarball_url, tarball.to_vec());
+  }
+
+  fn get_integrity(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha512};
+    format!("sha512-{}", base64::encode(Sha512::digest(bytes)))
+  }
+}
@@ -0,0 +1,500 @@
+use dprint_cli_core::checksums::get_sha256_checksum;
+use dprint_cli_core::FetchResponse;
+use dprint_core::types::ErrBox;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use url::Url;
+
+use crate::environment::Environment;
+
+const MANIFEST_MEDIA_TYPES: &str = "application/vnd.oci.image.manifest.v1+json, application/vnd.docker.distribution.manifest.v2+json";
+const WASM_LAYER_MEDIA_TYPES: &[&str] = &["application/wasm", "application/vnd.wasm.content.layer.v1+wasm", "application/vnd.module.wasm.content.layer.v1+wasm"];
+
+/// A reference to an artifact in an OCI registry (ex. `oci://ghcr.io/org/dprint-plugin-x:1.2.3`).
+#[derive(Debug, PartialEq)]
+struct OciReference {
+  /// Host of the registry's API (ex. `ghcr.io`).
+  registry: String,
+  /// Repository in the registry (ex. `org/dprint-plugin-x`).
+  repository: String,
+  tag: String,
+}
+
+impl OciReference {
+  fn parse(url: &Url) -> Result<OciReference, ErrBox> {
+    let registry = match url.host_str() {
+      Some(host) => match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+      },
+      None => return err!("Expected a registry in the OCI reference: {}", url),
+    };
+    let path = url.path().trim_matches('/');
+    let name_start = path.rfind('/').map(|index| index + 1).unwrap_or(0);
+    let (repository, tag) = match path[name_start..].find(':') {
+      Some(index) => (&path[..name_start + index], &path[name_start + index + 1..]),
+      None => (path, "latest"),
+    };
+    if repository.is_empty() || tag.is_empty() {
+      return err!("Expected a repository and tag in the OCI reference (ex. oci://ghcr.io/org/plugin:1.0.0): {}", url);
+    }
+
+    // Docker Hub uses a different host for its API and puts official images in the library namespace
+    if registry == "docker.io" {
+      Ok(OciReference {
+        registry: String::from("registry-1.docker.io"),
+        repository: if repository.contains('/') {
+          repository.to_string()
+        } else {
+          format!("library/{}", repository)
+        },
+        tag: tag.to_string(),
+      })
+    } else {
+      Ok(OciReference {
+        registry,
+        repository: repository.to_string(),
+        tag: tag.to_string(),
+      })
+    }
+  }
+
+  fn manifest_url(&self) -> String {
+    format!("https://{}/v2/{}/manifests/{}", self.registry, self.repository, self.tag)
+  }
+
+  fn blob_url(&self, digest: &str) -> String {
+    format!("https://{}/v2/{}/blobs/{}", self.registry, self.repository, digest)
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OciManifest {
+  layers: Vec<OciDescriptor>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OciDescriptor {
+  media_type: String,
+  digest: String,
+}
+
+/// Downloads the Wasm plugin from the OCI registry using the OCI distribution API.
+///
+/// Credentials are taken from the Docker configuration file, which includes using
+/// any configured Docker credential helpers.
+pub fn download_oci_plugin(url: &Url, environment: &impl Environment) -> Result<Vec<u8>, ErrBox> {
+  let reference = OciReference::parse(url)?;
+  let mut client = OciClient {
+    reference: &reference,
+    authorization: None,
+    environment,
+  };
+
+  let manifest_response = client.get(&reference.manifest_url(), MANIFEST_MEDIA_TYPES)?;
+  let manifest: OciManifest = match serde_json::from_slice(&manifest_response.bytes) {
+    Ok(manifest) => manifest,
+    Err(err) => return err!("Error deserializing the manifest of {}. {}", url, err.to_string()),
+  };
+  let layer = get_wasm_layer(&manifest.layers)?;
+
+  let blob_response = client.get(&reference.blob_url(&layer.digest), "*/*")?;
+  verify_digest(&blob_response.bytes, &layer.digest)?;
+  Ok(blob_response.bytes)
+}
+
+fn get_wasm_layer(layers: &[OciDescriptor]) -> Result<&OciDescriptor, ErrBox> {
+  if let Some(layer) = layers.iter().find(|layer| WASM_LAYER_MEDIA_TYPES.contains(&layer.media_type.as_str())) {
+    Ok(layer)
+  } else if layers.len() == 1 {
+    Ok(&layers[0])
+  } else {
+    err!(
+      "Could not find the Wasm plugin in the OCI artifact. Expected a single layer or a layer with one of the media types: {}",
+      WASM_LAYER_MEDIA_TYPES.join(", ")
+    )
+  }
+}
+
+fn verify_digest(bytes: &[u8], digest: &str) -> Result<(), ErrBox> {
+  match digest.strip_prefix("sha256:") {
+    Some(expected_checksum) => {
+      let checksum = get_sha256_checksum(bytes);
+      if checksum != expected_checksum {
+        err!("The digest of the downloaded plugin did not match. Expected: {}, Actual: sha256:{}", digest, checksum)
+      } else {
+        Ok(())
+      }
+    }
+    None => err!("Unsupported digest algorithm in OCI artifact: {}", digest),
+  }
+}
+
+struct OciClient<'a, TEnvironment: Environment> {
+  reference: &'a OciReference,
+  authorization: Option<String>,
+  environment: &'a TEnvironment,
+}
+
+impl<'a, TEnvironment: Environment> OciClient<'a, TEnvironment> {
+  /// Gets the url authenticating with the registry when it responds with a challenge.
+  fn get(&mut self, url: &str, accept: &str) -> Result<FetchResponse, ErrBox> {
+    let mut response = self.fetch(url, accept)?;
+    if response.status == 401 {
+      if let Some(challenge) = response.header("WWW-Authenticate") {
+        self.authorization = Some(self.get_authorization(challenge)?);
+        response = self.fetch(url, accept)?;
+      }
+    }
+
+    match response.status {
+      200 => Ok(response),
+      401 | 403 => err!(
+        "Error downloading {}. Status: {}. Ensure you are logged in to the registry (ex. docker login {}).",
+        url,
+        response.status,
+        self.reference.registry
+      ),
+      status => err!("Error downloading {}. Status: {}", url, status),
+    }
+  }
+
+  fn fetch(&self, url: &str, accept: &str) -> Result<FetchResponse, ErrBox> {
+    let mut headers = vec![("Accept", accept)];
+    if let Some(authorization) = &self.authorization {
+      headers.push(("Authorization", authorization));
+    }
+    self.environment.fetch_url(url, &headers)
+  }
+
+  fn get_authorization(&self, challenge: &str) -> Result<String, ErrBox> {
+    let credentials = get_registry_credentials(&self.reference.registry, self.environment)?;
+    let basic_auth = credentials.map(|(username, secret)| format!("Basic {}", base64::encode(format!("{}:{}", username, secret))));
+    let (scheme, params) = parse_auth_challenge(challenge);
+    if !scheme.eq_ignore_ascii_case("bearer") {
+      return match basic_auth {
+        Some(basic_auth) => Ok(basic_auth),
+        None => err!("No credentials found for registry {}. Log in to it via docker login.", self.reference.registry),
+      };
+    }
+
+    // get a token from the registry's token service
+    let realm = match params.get("realm") {
+      Some(realm) => realm,
+      None => return err!("Expected a realm in the authentication challenge from registry {}.", self.reference.registry),
+    };
+    let mut token_url = Url::parse(realm)?;
+    verify_token_url(&token_url, &self.reference.registry)?;
+    {
+      let mut query = token_url.query_pairs_mut();
+      if let Some(service) = params.get("service") {
+        query.append_pair("service", service);
+      }
+      query.append_pair(
+        "scope",
+        params
+          .get("scope")
+          .map(|scope| scope.to_string())
+          .unwrap_or_else(|| format!("repository:{}:pull", self.reference.repository))
+          .as_str(),
+      );
+    }
+    let mut headers = Vec::new();
+    if let Some(basic_auth) = &basic_auth {
+      headers.push(("Authorization", basic_auth.as_str()));
+    }
+    let response = self.environment.fetch_url(token_url.as_str(), &headers)?;
+    if response.status != 200 {
+      return err!(
+        "Error authenticating with registry {}. Status: {}. Ensure you are logged in to the registry (ex. docker login {}).",
+        self.reference.registry,
+        response.status,
+        self.reference.registry
+      );
+    }
+
+    #[derive(Deserialize)]
+    struct TokenResponse {
+      token: Option<String>,
+      access_token: Option<String>,
+    }
+    let token_response: TokenResponse = serde_json::from_slice(&response.bytes)?;
+    match token_response.token.or(token_response.access_token) {
+      Some(token) => Ok(format!("Bearer {}", token)),
+      None => err!("Expected a token in the response from {}.", realm),
+    }
+  }
+}
+
+/// Ensures the registry's token service won't receive the credentials over an insecure connection.
+fn verify_token_url(token_url: &Url, registry: &str) -> Result<(), ErrBox> {
+  let is_localhost = matches!(token_url.host_str(), Some("localhost") | Some("127.0.0.1") | Some("[::1]"));
+  if token_url.scheme() == "https" || (token_url.scheme() == "http" && is_localhost) {
+    Ok(())
+  } else {
+    err!("Expected the authentication realm of registry {} to use https: {}", registry, token_url)
+  }
+}
+
+/// Parses a `WWW-Authenticate` header value (ex. `Bearer realm="https://ghcr.io/token",service="ghcr.io"`).
+fn parse_auth_challenge(challenge: &str) -> (&str, HashMap<String, String>) {
+  let challenge = challenge.trim();
+  let (scheme, params_text) = match challenge.find(' ') {
+    Some(index) => (&challenge[..index], &challenge[index + 1..]),
+    None => (challenge, ""),
+  };
+  let mut params = HashMap::new();
+  let mut chars = params_text.chars().peekable();
+  loop {
+    let key = chars.by_ref().skip_while(|c| *c == ',' || c.is_whitespace()).take_while(|c| *c != '=').collect::<String>();
+    if key.is_empty() {
+      break;
+    }
+    let mut value = String::new();
+    if chars.peek() == Some(&'"') {
+      chars.next();
+      while let Some(c) = chars.next() {
+        match c {
+          '"' => break,
+          '\\' => value.extend(chars.next()),
+          c => value.push(c),
+        }
+      }
+    } else {
+      value = chars.by_ref().take_while(|c| *c != ',').collect();
+    }
+    params.insert(key.trim().to_lowercase(), value);
+  }
+  (scheme, params)
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DockerConfig {
+  #[serde(default)]
+  auths: HashMap<String, DockerConfigAuth>,
+  #[serde(default)]
+  cred_helpers: HashMap<String, String>,
+  creds_store: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DockerConfigAuth {
+  auth: Option<String>,
+}
+
+/// Gets the username and secret for the registry from the Docker configuration file.
+fn get_registry_credentials(registry: &str, environment: &impl Environment) -> Result<Option<(String, String)>, ErrBox> {
+  let config_file_path = match get_docker_config_file_path() {
+    Some(file_path) => file_path,
+    None => return Ok(None),
+  };
+  let config: DockerConfig = match environment.read_file(&config_file_path) {
+    Ok(text) => match serde_json::from_str(&text) {
+      Ok(config) => config,
+      Err(err) => return err!("Error deserializing {}. {}", config_file_path.display(), err.to_string()),
+    },
+    Err(_) => return Ok(None),
+  };
+  get_credentials_from_docker_config(&config, registry, run_docker_credential_helper)
+}
+
+fn get_credentials_from_docker_config(
+  config: &DockerConfig,
+  registry: &str,
+  run_credential_helper: impl Fn(&str, &str) -> Result<Option<(String, String)>, ErrBox>,
+) -> Result<Option<(String, String)>, ErrBox> {
+  // docker stores the credentials for docker hub under this key
+  let server = if registry == "registry-1.docker.io" { "https://index.docker.io/v1/" } else { registry };
+  if let Some(helper) = config.cred_helpers.get(server) {
+    return run_credential_helper(helper, server);
+  }
+
+  let auth = config
+    .auths
+    .iter()
+    .find(|(key, _)| get_docker_config_auth_host(key) == get_docker_config_auth_host(server))
+    .and_then(|(_, auth)| auth.auth.as_ref());
+  if let Some(auth) = auth {
+    let decoded = String::from_utf8(base64::decode(auth)?)?;
+    return match decoded.find(':') {
+      Some(index) => Ok(Some((decoded[..index].to_string(), decoded[index + 1..].to_string()))),
+      None => err!("Expected the auth for {} in the Docker configuration file to be in the format username:password.", server),
+    };
+  }
+
+  if let Some(helper) = &config.creds_store {
+    return run_credential_helper(helper, server);
+  }
+
+  Ok(None)
+}
+
+fn get_docker_config_auth_host(key: &str) -> &str {
+  // keys may be urls (ex. https://ghcr.io/v1/)
+  let key = key.trim_start_matches("https://").trim_start_matches("http://");
+  key.split('/').next().unwrap_or(key)
+}
+
+fn get_docker_config_file_path() -> Option<PathBuf> {
+  match std::env::var("DOCKER_CONFIG") {
+    Ok(dir_path) if !dir_path.trim().is_empty() => Some(PathBuf::from(dir_path).join("config.json")),
+    _ => dirs::home_dir().map(|home_dir| home_dir.join(".docker").join("config.json")),
+  }
+}
+
+/// Gets the credentials via a Docker credential helper (ex. `docker-credential-desktop`).
+fn run_docker_credential_helper(helper: &str, server: &str) -> Result<Option<(String, String)>, ErrBox> {
+  #[derive(Deserialize)]
+  #[serde(rename_all = "PascalCase")]
+  struct CredentialHelperOutput {
+    username: String,
+    secret: String,
+  }
+
+  let program = format!("docker-credential-{}", helper);
+  let mut child = match Command::new(&program).arg("get").stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn() {
+    Ok(child) => child,
+    Err(err) => return err!("Error running Docker credential helper {}. {}", program, err.to_string()),
+  };
+  child.stdin.take().unwrap().write_all(server.as_bytes())?;
+  let output = child.wait_with_output()?;
+  if !output.status.success() {
+    // the helper exits with an error when it has no credentials for the server
+    return Ok(None);
+  }
+  let output: CredentialHelperOutput = serde_json::from_slice(&output.stdout)?;
+  Ok(Some((output.username, output.secret)))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_parse_oci_reference() {
+    assert_eq!(
+      OciReference::parse(&Url::parse("oci://ghcr.io/org/dprint-plugin-x:1.2.3").unwrap()).unwrap(),
+      OciReference {
+        registry: String::from("ghcr.io"),
+        repository: String::from("org/dprint-plugin-x"),
+        tag: String::from("1.2.3"),
+      }
+    );
+    assert_eq!(
+      OciReference::parse(&Url::parse("oci://localhost:5000/plugins/test").unwrap()).unwrap(),
+      OciReference {
+        registry: String::from("localhost:5000"),
+        repository: String::from("plugins/test"),
+        tag: String::from("latest"),
+      }
+    );
+    assert_eq!(
+      OciReference::parse(&Url::parse("oci://docker.io/plugin:1.0.0").unwrap()).unwrap(),
+      OciReference {
+        registry: String::from("registry-1.docker.io"),
+        repository: String::from("library/plugin"),
+        tag: String::from("1.0.0"),
+      }
+    );
+  }
+
+  #[test]
+  fn should_parse_auth_challenge() {
+    let (scheme, params) = parse_auth_challenge(r#"Bearer realm="https://ghcr.io/token",service="ghcr.io",scope="repository:org/x:pull""#);
+    assert_eq!(scheme, "Bearer");
+    assert_eq!(params.get("realm").unwrap(), "https://ghcr.io/token");
+    assert_eq!(params.get("service").unwrap(), "ghcr.io");
+    assert_eq!(params.get("scope").unwrap(), "repository:org/x:pull");
+
+    let (scheme, params) = parse_auth_challenge(r#"Basic realm="Registry""#);
+    assert_eq!(scheme, "Basic");
+    assert_eq!(params.get("realm").unwrap(), "Registry");
+  }
+
+  #[test]
+  fn should_only_allow_https_token_urls_except_for_localhost() {
+    let verify = |url: &str| verify_token_url(&Url::parse(url).unwrap(), "registry.io");
+    assert!(verify("https://registry.io/token").is_ok());
+    assert!(verify("http://localhost:5000/token").is_ok());
+    assert!(verify("http://127.0.0.1/token").is_ok());
+    assert!(verify("http://[::1]/token").is_ok());
+    assert!(verify("ftp://localhost/token").is_err());
+    let err = verify("http://other.io/token").err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Expected the authentication realm of registry registry.io to use https: http://other.io/token"
+    );
+  }
+
+  #[test]
+  fn should_get_credentials_from_docker_config() {
+    let config: DockerConfig = serde_json::from_str(
+      r#"{
+        "auths": {
+          "https://ghcr.io/v1/": { "auth": "dXNlcjpwYXNz" },
+          "other.io": {}
+        },
+        "credHelpers": { "gcr.io": "gcloud" },
+        "credsStore": "desktop"
+      }"#,
+    )
+    .unwrap();
+    let run_helper = |helper: &str, server: &str| Ok(Some((helper.to_string(), server.to_string())));
+
+    assert_eq!(
+      get_credentials_from_docker_config(&config, "ghcr.io", run_helper).unwrap(),
+      Some((String::from("user"), String::from("pass")))
+    );
+    assert_eq!(
+      get_credentials_from_docker_config(&config, "gcr.io", run_helper).unwrap(),
+      Some((String::from("gcloud"), String::from("gcr.io")))
+    );
+    assert_eq!(
+      get_credentials_from_docker_config(&config, "other.io", run_helper).unwrap(),
+      Some((String::from("desktop"), String::from("other.io")))
+    );
+    assert_eq!(
+      get_credentials_from_docker_config(&DockerConfig::default(), "ghcr.io", run_helper).unwrap(),
+      None
+    );
+  }
+
+  #[test]
+  fn should_download_plugin() {
+    let environment = TestEnvironment::new();
+    let plugin_bytes = b"wasm".to_vec();
+    let digest = format!("sha256:{}", get_sha256_checksum(&plugin_bytes));
+    environment.add_remote_file_bytes(
+      "https://ghcr.io/v2/org/plugin/manifests/1.0.0",
+      format!(
+        r#"{{ "layers": [{{ "mediaType": "application/vnd.oci.image.config.v1+json", "digest": "sha256:other" }}, {{ "mediaType": "application/wasm", "digest": "{}" }}] }}"#,
+        digest
+      )
+      .into_bytes(),
+    );
+    environment.add_remote_file_bytes(&format!("https://ghcr.io/v2/org/plugin/blobs/{}", digest), plugin_bytes.clone());
+
+    let result = download_oci_plugin(&Url::parse("oci://ghcr.io/org/plugin:1.0.0").unwrap(), &environment).unwrap();
+    assert_eq!(result, plugin_bytes);
+  }
+
+  #[test]
+  fn should_error_when_digest_does_not_match() {
+    let err = verify_digest(b"wasm", "sha256:other").err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      format!(
+        "The digest of the downloaded plugin did not match. Expected: sha256:other, Actual: sha256:{}",
+        get_sha256_checksum(b"wasm")
+      )
+    );
+  }
+}
@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use dprint_core::configuration::{ConfigKeyMap, ConfigKeyValue, ConfigurationDiagnostic, GlobalConfiguration};
+use dprint_core::plugins::{ChangedRegion, FormatDiagnostic};
 use dprint_core::types::ErrBox;
 
 pub trait Plugin: std::marker::Send + std::marker::Sync {
@@ -18,6 +19,14 @@ pub trait Plugin: std::marker::Send + std::marker::Sync {
   fn help_url(&self) -> &str;
   /// Gets the configuration schema url.
   fn config_schema_url(&self) -> &str;
+  /// Gets if the plugin can format a portion of a file.
+  fn supports_partial_format(&self) -> bool;
+  /// Gets if the plugin reports non-fatal diagnostics after formatting.
+  fn supports_format_diagnostics(&self) -> bool;
+  /// Gets if the plugin only changes whitespace when formatting.
+  fn only_changes_whitespace(&self) -> bool;
+  /// Gets if the plugin reports the regions of the file text that changed when formatting.
+  fn supports_changed_regions(&self) -> bool;
   /// Sets the configuration for the plugin.
   fn set_config(&mut self, plugin_config: ConfigKeyMap, global_config: GlobalConfiguration);
   /// Initializes the plugin.
@@ -54,6 +63,12 @@ pub trait InitializedPlugin: std::marker::Send {
   fn get_config_diagnostics(&self) -> Result<Vec<ConfigurationDiagnostic>, ErrBox>;
   /// Formats the text in memory based on the file path and file text.
   fn format_text(&mut self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox>;
+  /// Takes the non-fatal diagnostics found while formatting the last file.
+  fn take_format_diagnostics(&mut self) -> Result<Vec<FormatDiagnostic>, ErrBox>;
+  /// Takes the regions of the file text changed when formatting the last file.
+  fn take_changed_regions(&mut self) -> Result<Option<Vec<ChangedRegion>>, ErrBox>;
+  /// Formats the text and gets the JSON serialized printer trace.
+  fn trace_text(&mut self, file_path: &Path, file_text: &str, override_config: &ConfigKeyMap) -> Result<String, ErrBox>;
 }
 
 #[cfg(test)]
@@ -64,6 +79,7 @@ pub struct TestPlugin {
   file_names: Vec<String>,
   initialized_test_plugin: Option<InitializedTestPlugin>,
   config: (ConfigKeyMap, GlobalConfiguration),
+  supports_changed_regions: bool,
 }
 
 #[cfg(test)]
@@ -82,10 +98,20 @@ impl TestPlugin {
           use_tabs: None,
           indent_width: None,
           new_line_kind: None,
+          trim_leading_blank_lines: None,
+          single_trailing_new_line: None,
+          timeout_ms: None,
         },
       ),
+      supports_changed_regions: false,
     }
   }
+
+  /// Reports the appended text as a changed region.
+  pub fn with_changed_regions(mut self) -> TestPlugin {
+    self.supports_changed_regions = true;
+    self
+  }
 }
 
 #[cfg(test)]
@@ -102,6 +128,18 @@ impl Plugin for TestPlugin {
   fn config_schema_url(&self) -> &str {
     "https://plugins.dprint.dev/schemas/test.json"
   }
+  fn supports_partial_format(&self) -> bool {
+    false
+  }
+  fn supports_format_diagnostics(&self) -> bool {
+    false
+  }
+  fn only_changes_whitespace(&self) -> bool {
+    false
+  }
+  fn supports_changed_regions(&self) -> bool {
+    self.supports_changed_regions
+  }
   fn config_key(&self) -> &str {
     &self.config_key
   }
@@ -122,12 +160,14 @@ impl Plugin for TestPlugin {
 
 #[cfg(test)]
 #[derive(Clone)]
-pub struct InitializedTestPlugin {}
+pub struct InitializedTestPlugin {
+  last_text_len: usize,
+}
 
 #[cfg(test)]
 impl InitializedTestPlugin {
   pub fn new() -> InitializedTestPlugin {
-    InitializedTestPlugin {}
+    InitializedTestPlugin { last_text_len: 0 }
   }
 }
 
@@ -143,6 +183,20 @@ impl InitializedPlugin for InitializedTestPlugin {
     Ok(vec![])
   }
   fn format_text(&mut self, _: &Path, text: &str, _: &ConfigKeyMap) -> Result<String, ErrBox> {
+    self.last_text_len = text.len();
     Ok(format!("{}_formatted", text))
   }
+  fn take_format_diagnostics(&mut self) -> Result<Vec<FormatDiagnostic>, ErrBox> {
+    Ok(vec![])
+  }
+  fn take_changed_regions(&mut self) -> Result<Option<Vec<ChangedRegion>>, ErrBox> {
+    Ok(Some(vec![ChangedRegion {
+      start: self.last_text_len,
+      end: self.last_text_len,
+      new_text: String::from("_formatted"),
+    }]))
+  }
+  fn trace_text(&mut self, _: &Path, _: &str, _: &ConfigKeyMap) -> Result<String, ErrBox> {
+    err!("This plugin does not support tracing.")
+  }
 }
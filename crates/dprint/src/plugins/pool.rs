@@ -1,14 +1,16 @@
 use parking_lot::{Mutex, RwLock};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use dprint_core::configuration::{ConfigKeyMap, ConfigKeyValue, NewLineKind};
 use dprint_core::types::ErrBox;
 
 use super::{output_plugin_config_diagnostics, InitializedPlugin, Plugin};
+use crate::configuration::{ConfigOverrides, PluginAssociations};
 use crate::environment::Environment;
-use crate::utils::ErrorCountLogger;
+use crate::utils::{ErrorCountLogger, FileBoundaryPolicy, Phase};
 use crate::utils::{get_lowercase_file_extension, get_lowercase_file_name};
 
 /// This is necessary because of a circular reference where
@@ -29,9 +31,55 @@ impl<TEnvironment: Environment> PluginsDropper<TEnvironment> {
   }
 }
 
+/// Cancels a format that's in progress (ex. by telling a process plugin to stop formatting).
+pub type FormatCanceller = Arc<dyn Fn() + Send + Sync>;
+
+/// Keeps a format canceller registered while a file is being formatted.
+pub struct FormatCancellerRegistration<'a, TEnvironment: Environment> {
+  pools: &'a PluginPools<TEnvironment>,
+  file_path: PathBuf,
+  canceller: FormatCanceller,
+}
+
+impl<'a, TEnvironment: Environment> Drop for FormatCancellerRegistration<'a, TEnvironment> {
+  fn drop(&mut self) {
+    let mut format_cancellers = self.pools.format_cancellers.lock();
+    if let Some(cancellers) = format_cancellers.get_mut(&self.file_path) {
+      cancellers.retain(|canceller| !Arc::ptr_eq(canceller, &self.canceller));
+      if cancellers.is_empty() {
+        format_cancellers.remove(&self.file_path);
+      }
+    }
+  }
+}
+
+/// Maps file extensions and file names to the names of the plugins that
+/// may format them, ordered by precedence.
 struct PluginNameResolutionMaps {
-  extension_to_plugin_name_map: HashMap<String, String>,
-  file_name_to_plugin_name_map: HashMap<String, String>,
+  /// The name and configuration key of each plugin in the order they were added.
+  plugin_config_keys: Vec<(String, String)>,
+  extension_to_plugin_names_map: HashMap<String, Vec<String>>,
+  file_name_to_plugin_names_map: HashMap<String, Vec<String>>,
+  /// If the next plugin for a file should be used when a plugin errors formatting it.
+  fallback_on_error: bool,
+}
+
+/// Why a file was matched to a plugin.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PluginMatchReason {
+  Associations,
+  FileName(String),
+  FileExtension(String),
+}
+
+impl std::fmt::Display for PluginMatchReason {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PluginMatchReason::Associations => write!(f, "matched associations"),
+      PluginMatchReason::FileName(file_name) => write!(f, "matched file name '{}'", file_name),
+      PluginMatchReason::FileExtension(file_extension) => write!(f, "matched file extension '{}'", file_extension),
+    }
+  }
 }
 
 pub struct PluginPools<TEnvironment: Environment> {
@@ -41,6 +89,10 @@ pub struct PluginPools<TEnvironment: Environment> {
   /// Plugins may format using other plugins. If so, they should have a locally
   /// owned plugin instance that will be created on demand.
   plugins_for_plugins: Mutex<HashMap<String, HashMap<String, Vec<Box<dyn InitializedPlugin>>>>>,
+  config_overrides: RwLock<ConfigOverrides>,
+  associations: RwLock<PluginAssociations>,
+  /// Cancellers of the formats in progress by file path.
+  format_cancellers: Mutex<HashMap<PathBuf, Vec<FormatCanceller>>>,
 }
 
 impl<TEnvironment: Environment> PluginPools<TEnvironment> {
@@ -49,13 +101,22 @@ impl<TEnvironment: Environment> PluginPools<TEnvironment> {
       environment,
       pools: Mutex::new(HashMap::new()),
       plugin_name_maps: RwLock::new(PluginNameResolutionMaps {
-        extension_to_plugin_name_map: HashMap::new(),
-        file_name_to_plugin_name_map: HashMap::new(),
+        plugin_config_keys: Vec::new(),
+        extension_to_plugin_names_map: HashMap::new(),
+        file_name_to_plugin_names_map: HashMap::new(),
+        fallback_on_error: false,
       }),
       plugins_for_plugins: Mutex::new(HashMap::new()),
+      config_overrides: RwLock::new(Default::default()),
+      associations: RwLock::new(Default::default()),
+      format_cancellers: Mutex::new(HashMap::new()),
     }
   }
 
+  pub fn environment(&self) -> &TEnvironment {
+    &self.environment
+  }
+
   pub fn drop_plugins(&self) {
     {
       let mut pools = self.pools.lock();
@@ -70,35 +131,89 @@ impl<TEnvironment: Environment> PluginPools<TEnvironment> {
     }
   }
 
-  pub fn set_plugins(&self, plugins: Vec<Box<dyn Plugin>>) {
+  pub fn set_plugins(&self, plugins: Vec<Box<dyn Plugin>>, fallback_on_error: bool) {
     let mut pools = self.pools.lock();
     let mut plugin_name_maps = self.plugin_name_maps.write();
+    plugin_name_maps.fallback_on_error = fallback_on_error;
     for plugin in plugins {
       let plugin_name = String::from(plugin.name());
+      if !plugin_name_maps.plugin_config_keys.iter().any(|(name, _)| *name == plugin_name) {
+        plugin_name_maps.plugin_config_keys.push((plugin_name.clone(), plugin.config_key().to_string()));
+      }
       let plugin_extensions = plugin.file_extensions().clone();
       let plugin_file_names = plugin.file_names().clone();
       pools.insert(plugin_name.clone(), Arc::new(InitializedPluginPool::new(plugin, self.environment.clone())));
       for extension in plugin_extensions.iter() {
         // first added plugin takes precedence
-        plugin_name_maps
-          .extension_to_plugin_name_map
-          .entry(extension.to_owned())
-          .or_insert(plugin_name.clone());
+        add_plugin_name(&mut plugin_name_maps.extension_to_plugin_names_map, extension, &plugin_name);
       }
       for file_name in plugin_file_names.iter() {
         // first added plugin takes precedence
-        plugin_name_maps
-          .file_name_to_plugin_name_map
-          .entry(file_name.to_owned())
-          .or_insert(plugin_name.clone());
+        add_plugin_name(&mut plugin_name_maps.file_name_to_plugin_names_map, file_name, &plugin_name);
+      }
+    }
+
+    fn add_plugin_name(map: &mut HashMap<String, Vec<String>>, key: &str, plugin_name: &str) {
+      let plugin_names = map.entry(key.to_owned()).or_insert_with(Vec::new);
+      if !plugin_names.iter().any(|name| name == plugin_name) {
+        plugin_names.push(plugin_name.to_owned());
       }
     }
   }
 
+  pub fn set_config_overrides(&self, config_overrides: ConfigOverrides) {
+    *self.config_overrides.write() = config_overrides;
+  }
+
+  /// Sets the plugin associations, which replace the default file extensions and names of their plugins.
+  pub fn set_plugin_associations(&self, associations: PluginAssociations) {
+    *self.associations.write() = associations;
+  }
+
+  /// Gets the name and configuration key of each plugin in the order they were added.
+  pub fn get_plugin_config_keys(&self) -> Vec<(String, String)> {
+    self.plugin_name_maps.read().plugin_config_keys.clone()
+  }
+
+  /// Gets the configuration from the "overrides" property that applies to the plugin when formatting the file.
+  pub fn get_override_config(&self, file_path: &Path, plugin_pool: &InitializedPluginPool<TEnvironment>) -> ConfigKeyMap {
+    self.config_overrides.read().get_plugin_override_config(file_path, plugin_pool.config_key())
+  }
+
   pub fn get_pool(&self, plugin_name: &str) -> Option<Arc<InitializedPluginPool<TEnvironment>>> {
     self.pools.lock().get(plugin_name).map(|p| p.clone())
   }
 
+  /// Drops the instances of the least recently used plugins so that at most
+  /// the provided number of plugins have initialized instances.
+  pub fn drop_least_recently_used_instances(&self, max_pool_count: usize) {
+    let mut pools = self.get_pools_with_instances();
+    // most recently used first
+    pools.sort_by_key(|pool| std::cmp::Reverse(pool.last_used()));
+    for pool in pools.into_iter().skip(max_pool_count) {
+      log_verbose!(
+        self.environment,
+        "Dropping instances of {} because the plugin pool size was exceeded.",
+        pool.name()
+      );
+      pool.drop_plugins();
+    }
+  }
+
+  /// Drops the instances of the plugins that haven't been used for the provided duration.
+  pub fn drop_idle_instances(&self, idle_timeout: Duration) {
+    for pool in self.get_pools_with_instances() {
+      if pool.last_used().elapsed() >= idle_timeout {
+        log_verbose!(self.environment, "Dropping instances of {} because it was idle.", pool.name());
+        pool.drop_plugins();
+      }
+    }
+  }
+
+  fn get_pools_with_instances(&self) -> Vec<Arc<InitializedPluginPool<TEnvironment>>> {
+    self.pools.lock().values().filter(|pool| pool.has_instances()).cloned().collect()
+  }
+
   pub fn take_instance_for_plugin(&self, parent_plugin_name: &str, sub_plugin_name: &str) -> Result<Box<dyn InitializedPlugin>, ErrBox> {
     let plugin = self.with_plugins_for_parent_and_sub_plugin(parent_plugin_name, sub_plugin_name, |plugins| plugins.pop());
 
@@ -147,16 +262,64 @@ impl<TEnvironment: Environment> PluginPools<TEnvironment> {
   }
 
   pub fn get_plugin_name_from_file_name(&self, file_name: &Path) -> Option<String> {
+    self.get_plugin_names_from_file_name(file_name).into_iter().next()
+  }
+
+  /// Gets the names of the plugins that may format the file ordered by precedence.
+  pub fn get_plugin_names_from_file_name(&self, file_name: &Path) -> Vec<String> {
+    self.get_plugin_matches(file_name).into_iter().map(|(plugin_name, _)| plugin_name).collect()
+  }
+
+  /// Gets the names of the plugins that may format the file ordered by precedence along with why each matched.
+  ///
+  /// Plugins with associations only match the files matching them and come first. Then plugins
+  /// matching on the file name come before plugins matching on the extension.
+  pub fn get_plugin_matches(&self, file_path: &Path) -> Vec<(String, PluginMatchReason)> {
     let plugin_name_maps = self.plugin_name_maps.read();
-    get_lowercase_file_name(file_name)
-      .map(|file_name| plugin_name_maps.file_name_to_plugin_name_map.get(&file_name))
-      .flatten()
-      .or_else(|| {
-        get_lowercase_file_extension(file_name)
-          .map(|ext| plugin_name_maps.extension_to_plugin_name_map.get(&ext))
-          .flatten()
-      })
-      .map(|name| name.to_owned())
+    let associations = self.associations.read();
+    let has_associations = |plugin_name: &str| {
+      plugin_name_maps
+        .plugin_config_keys
+        .iter()
+        .any(|(name, config_key)| name == plugin_name && associations.has_associations(config_key))
+    };
+    let mut matches: Vec<(String, PluginMatchReason)> = Vec::new();
+    for (plugin_name, config_key) in plugin_name_maps.plugin_config_keys.iter() {
+      if associations.is_match(config_key, file_path) {
+        matches.push((plugin_name.to_owned(), PluginMatchReason::Associations));
+      }
+    }
+    let file_name_plugin_names = get_lowercase_file_name(file_path).and_then(|file_name| {
+      let plugin_names = plugin_name_maps.file_name_to_plugin_names_map.get(&file_name)?;
+      Some((plugin_names, PluginMatchReason::FileName(file_name)))
+    });
+    let extension_plugin_names = get_lowercase_file_extension(file_path).and_then(|ext| {
+      let plugin_names = plugin_name_maps.extension_to_plugin_names_map.get(&ext)?;
+      Some((plugin_names, PluginMatchReason::FileExtension(ext)))
+    });
+    for (plugin_names, reason) in file_name_plugin_names.into_iter().chain(extension_plugin_names.into_iter()) {
+      for plugin_name in plugin_names {
+        if !has_associations(plugin_name) && !matches.iter().any(|(name, _)| name == plugin_name) {
+          matches.push((plugin_name.to_owned(), reason.clone()));
+        }
+      }
+    }
+    matches
+  }
+
+  /// Gets the plugins to try in order when the specified plugin fails formatting the file.
+  /// This will be empty when falling back on error is not enabled.
+  pub fn get_fallback_plugin_names(&self, file_name: &Path, failed_plugin_name: &str) -> Vec<String> {
+    if !self.plugin_name_maps.read().fallback_on_error {
+      return Vec::new();
+    }
+
+    self
+      .get_plugin_names_from_file_name(file_name)
+      .into_iter()
+      .skip_while(|plugin_name| plugin_name != failed_plugin_name)
+      .skip(1)
+      .collect()
   }
 
   pub fn release(&self, parent_plugin_name: &str) {
@@ -170,7 +333,33 @@ impl<TEnvironment: Environment> PluginPools<TEnvironment> {
     }
   }
 
-  /// Gets a hash to be used for the "incremental" feature to tell if any plugins have changed.
+  /// Registers a canceller for the format of the file that's in progress until the returned registration is dropped.
+  pub fn register_format_canceller(&self, file_path: &Path, canceller: FormatCanceller) -> FormatCancellerRegistration<'_, TEnvironment> {
+    self
+      .format_cancellers
+      .lock()
+      .entry(file_path.to_path_buf())
+      .or_default()
+      .push(canceller.clone());
+    FormatCancellerRegistration {
+      pools: self,
+      file_path: file_path.to_path_buf(),
+      canceller,
+    }
+  }
+
+  /// Cancels the formats of the file that are in progress (ex. when the text of an
+  /// editor's buffer changed while it was being formatted). Plugins that can't be
+  /// cancelled finish formatting.
+  pub fn cancel_formats(&self, file_path: &Path) {
+    let cancellers = self.format_cancellers.lock().get(file_path).cloned().unwrap_or_default();
+    for canceller in cancellers {
+      canceller();
+    }
+  }
+
+  /// Gets a hash to be used for the "incremental" feature to tell if any plugins,
+  /// the configuration overrides, or the plugin associations have changed.
   pub fn get_plugins_hash(&self) -> u64 {
     use std::num::Wrapping;
     // yeah, I know adding hashes isn't right, but the chance of this not working
@@ -180,6 +369,8 @@ impl<TEnvironment: Environment> PluginPools<TEnvironment> {
     for (_, pool) in pools.iter() {
       hash_sum += Wrapping(pool.plugin.get_hash());
     }
+    hash_sum += Wrapping(self.config_overrides.read().get_hash());
+    hash_sum += Wrapping(self.associations.read().get_hash());
     hash_sum.0
   }
 }
@@ -206,6 +397,8 @@ pub struct InitializedPluginPool<TEnvironment: Environment> {
   name: String,
   plugin: Box<dyn Plugin>,
   items: Mutex<Vec<Box<dyn InitializedPlugin>>>, // todo: RwLock
+  /// When an instance was last released back into the pool.
+  last_used: Mutex<Instant>,
   time_stats: RwLock<PluginTimeStats>,
   checked_diagnostics: Mutex<Option<bool>>,
 }
@@ -217,6 +410,7 @@ impl<TEnvironment: Environment> InitializedPluginPool<TEnvironment> {
       name: plugin.name().to_string(),
       plugin: plugin,
       items: Mutex::new(Vec::new()),
+      last_used: Mutex::new(Instant::now()),
       time_stats: RwLock::new(PluginTimeStats {
         // assume this if never created
         startup_time: 250,
@@ -232,6 +426,58 @@ impl<TEnvironment: Environment> InitializedPluginPool<TEnvironment> {
     self.name.as_str()
   }
 
+  pub fn version(&self) -> &str {
+    self.plugin.version()
+  }
+
+  pub fn help_url(&self) -> &str {
+    self.plugin.help_url()
+  }
+
+  pub fn config_key(&self) -> &str {
+    self.plugin.config_key()
+  }
+
+  pub fn supports_partial_format(&self) -> bool {
+    self.plugin.supports_partial_format()
+  }
+
+  pub fn supports_format_diagnostics(&self) -> bool {
+    self.plugin.supports_format_diagnostics()
+  }
+
+  pub fn only_changes_whitespace(&self) -> bool {
+    self.plugin.only_changes_whitespace()
+  }
+
+  pub fn supports_changed_regions(&self) -> bool {
+    self.plugin.supports_changed_regions()
+  }
+
+  /// Gets the configured new line kind for a file, which is the plugin's `newLineKind` falling
+  /// back to the global one. Returns `None` when not configured so the plugin's default is used.
+  pub fn get_new_line_kind(&self, override_config: &ConfigKeyMap) -> Option<NewLineKind> {
+    let (plugin_config, global_config) = self.plugin.get_config();
+    match override_config.get("newLineKind").or_else(|| plugin_config.get("newLineKind")) {
+      // the plugin reports a diagnostic when this is invalid
+      Some(ConfigKeyValue::String(value)) => value.parse().ok(),
+      _ => global_config.new_line_kind,
+    }
+  }
+
+  /// Gets the policies for the start and end of files, which are resolved the same way as the new line kind.
+  pub fn get_file_boundary_policy(&self, override_config: &ConfigKeyMap) -> FileBoundaryPolicy {
+    let (plugin_config, global_config) = self.plugin.get_config();
+    let get_value = |key: &str, global_value: Option<bool>| match override_config.get(key).or_else(|| plugin_config.get(key)) {
+      Some(ConfigKeyValue::Bool(value)) => *value,
+      _ => global_value.unwrap_or(false),
+    };
+    FileBoundaryPolicy {
+      trim_leading_blank_lines: get_value("trimLeadingBlankLines", global_config.trim_leading_blank_lines),
+      single_trailing_new_line: get_value("singleTrailingNewLine", global_config.single_trailing_new_line),
+    }
+  }
+
   pub fn drop_plugins(&self) {
     let mut items = self.items.lock();
     items.clear();
@@ -273,11 +519,22 @@ impl<TEnvironment: Environment> InitializedPluginPool<TEnvironment> {
   pub fn release(&self, plugin: Box<dyn InitializedPlugin>) {
     let mut items = self.items.lock();
     items.push(plugin);
+    *self.last_used.lock() = Instant::now();
   }
 
   pub fn release_all(&self, plugins: Vec<Box<dyn InitializedPlugin>>) {
     let mut items = self.items.lock();
     items.extend(plugins);
+    *self.last_used.lock() = Instant::now();
+  }
+
+  /// Gets if the pool has initialized instances that aren't in use.
+  pub fn has_instances(&self) -> bool {
+    !self.items.lock().is_empty()
+  }
+
+  fn last_used(&self) -> Instant {
+    *self.last_used.lock()
   }
 
   pub fn get_time_snapshot(&self) -> PoolTimeSnapshot {
@@ -295,6 +552,7 @@ impl<TEnvironment: Environment> InitializedPluginPool<TEnvironment> {
     let start_instant = Instant::now();
     log_verbose!(self.environment, "Creating instance of {}", self.plugin.name());
     let plugin = self.plugin.initialize()?;
+    self.environment.phase_timings().add_since(Phase::PluginInstantiation, start_instant);
     let startup_duration = start_instant.elapsed().as_millis() as u64;
     log_verbose!(self.environment, "Created instance of {} in {}ms", self.plugin.name(), startup_duration);
     self.time_stats.write().startup_time = startup_duration; // store the latest duration
@@ -305,9 +563,159 @@ impl<TEnvironment: Environment> InitializedPluginPool<TEnvironment> {
     let start_instant = Instant::now();
     let result = action();
     let elapsed_time = start_instant.elapsed();
+    self
+      .environment
+      .phase_timings()
+      .profiler()
+      .add_span(|| format!("format ({})", self.name), start_instant);
     let mut time_stats = self.time_stats.write();
     time_stats.total_format_time += elapsed_time.as_millis() as u64;
     time_stats.format_count += 1;
     result
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use crate::plugins::TestPlugin;
+  use pretty_assertions::assert_eq;
+  use std::path::PathBuf;
+
+  #[test]
+  fn should_get_plugin_names_by_precedence() {
+    let plugin_pools = create_plugin_pools(true);
+    assert_eq!(plugin_pools.get_plugin_name_from_file_name(&PathBuf::from("/file.ts")), Some(String::from("first")));
+    assert_eq!(plugin_pools.get_plugin_names_from_file_name(&PathBuf::from("/file.ts")), vec!["first", "second", "third"]);
+    assert_eq!(plugin_pools.get_plugin_names_from_file_name(&PathBuf::from("/file.js")), vec!["second"]);
+    assert_eq!(plugin_pools.get_plugin_names_from_file_name(&PathBuf::from("/special.ts")), vec!["third", "first", "second"]);
+    assert_eq!(plugin_pools.get_plugin_names_from_file_name(&PathBuf::from("/file.txt")).len(), 0);
+  }
+
+  #[test]
+  fn should_get_plugin_names_using_associations() {
+    let plugin_pools = create_plugin_pools(true);
+    let mut associations = HashMap::new();
+    associations.insert("second".to_string(), vec!["**/*.mjs".to_string(), "**/special.ts".to_string()]);
+    plugin_pools.set_plugin_associations(PluginAssociations::new(&associations, Path::new("/")).unwrap());
+    assert_eq!(
+      plugin_pools.get_plugin_matches(&PathBuf::from("/file.mjs")),
+      vec![(String::from("second"), PluginMatchReason::Associations)]
+    );
+    assert_eq!(plugin_pools.get_plugin_names_from_file_name(&PathBuf::from("/special.ts")), vec!["second", "third", "first"]);
+    // the default file extensions of plugins with associations are no longer used
+    assert_eq!(plugin_pools.get_plugin_names_from_file_name(&PathBuf::from("/file.ts")), vec!["first", "third"]);
+    assert_eq!(plugin_pools.get_plugin_names_from_file_name(&PathBuf::from("/file.js")).len(), 0);
+    assert_eq!(
+      plugin_pools.get_plugin_matches(&PathBuf::from("/other/special.ts")),
+      vec![
+        (String::from("second"), PluginMatchReason::Associations),
+        (String::from("third"), PluginMatchReason::FileName(String::from("special.ts"))),
+        (String::from("first"), PluginMatchReason::FileExtension(String::from("ts"))),
+      ]
+    );
+  }
+
+  #[test]
+  fn should_change_plugins_hash_when_associations_change() {
+    let plugin_pools = create_plugin_pools(true);
+    let original_hash = plugin_pools.get_plugins_hash();
+    let mut associations = HashMap::new();
+    associations.insert("second".to_string(), vec!["**/*.mjs".to_string()]);
+    plugin_pools.set_plugin_associations(PluginAssociations::new(&associations, Path::new("/")).unwrap());
+    let hash = plugin_pools.get_plugins_hash();
+    assert_ne!(hash, original_hash);
+
+    associations.insert("second".to_string(), vec!["**/*.cjs".to_string()]);
+    plugin_pools.set_plugin_associations(PluginAssociations::new(&associations, Path::new("/")).unwrap());
+    assert_ne!(plugin_pools.get_plugins_hash(), hash);
+    plugin_pools.set_plugin_associations(PluginAssociations::new(&HashMap::new(), Path::new("/")).unwrap());
+    assert_eq!(plugin_pools.get_plugins_hash(), original_hash);
+  }
+
+  #[test]
+  fn should_get_fallback_plugin_names() {
+    let plugin_pools = create_plugin_pools(true);
+    assert_eq!(plugin_pools.get_fallback_plugin_names(&PathBuf::from("/file.ts"), "first"), vec!["second", "third"]);
+    assert_eq!(plugin_pools.get_fallback_plugin_names(&PathBuf::from("/file.ts"), "second"), vec!["third"]);
+    assert_eq!(plugin_pools.get_fallback_plugin_names(&PathBuf::from("/file.ts"), "third").len(), 0);
+    assert_eq!(plugin_pools.get_fallback_plugin_names(&PathBuf::from("/file.js"), "second").len(), 0);
+  }
+
+  #[test]
+  fn should_not_get_fallback_plugin_names_when_not_enabled() {
+    let plugin_pools = create_plugin_pools(false);
+    assert_eq!(plugin_pools.get_fallback_plugin_names(&PathBuf::from("/file.ts"), "first").len(), 0);
+  }
+
+  #[test]
+  fn should_drop_least_recently_used_instances() {
+    let plugin_pools = create_plugin_pools(false);
+    for plugin_name in &["first", "second", "third"] {
+      let pool = plugin_pools.get_pool(plugin_name).unwrap();
+      pool.release(pool.create_instance().unwrap());
+      std::thread::sleep(Duration::from_millis(1));
+    }
+    plugin_pools.drop_least_recently_used_instances(2);
+    assert_eq!(get_pools_with_instances(&plugin_pools), vec!["second", "third"]);
+
+    // using a plugin makes it the most recently used
+    let pool = plugin_pools.get_pool("second").unwrap();
+    pool.release(pool.take_if_available().unwrap());
+    plugin_pools.drop_least_recently_used_instances(1);
+    assert_eq!(get_pools_with_instances(&plugin_pools), vec!["second"]);
+  }
+
+  #[test]
+  fn should_drop_idle_instances() {
+    let plugin_pools = create_plugin_pools(false);
+    let pool = plugin_pools.get_pool("first").unwrap();
+    pool.release(pool.create_instance().unwrap());
+    plugin_pools.drop_idle_instances(Duration::from_secs(60));
+    assert_eq!(get_pools_with_instances(&plugin_pools), vec!["first"]);
+    plugin_pools.drop_idle_instances(Duration::from_secs(0));
+    assert_eq!(get_pools_with_instances(&plugin_pools).len(), 0);
+  }
+
+  #[test]
+  fn should_cancel_formats_for_file() {
+    let plugin_pools = create_plugin_pools(false);
+    let cancel_count = Arc::new(Mutex::new(0));
+    let canceller: FormatCanceller = {
+      let cancel_count = cancel_count.clone();
+      Arc::new(move || *cancel_count.lock() += 1)
+    };
+    let file_path = PathBuf::from("/file.ts");
+    {
+      let _registration = plugin_pools.register_format_canceller(&file_path, canceller);
+      plugin_pools.cancel_formats(&PathBuf::from("/other.ts"));
+      assert_eq!(*cancel_count.lock(), 0);
+      plugin_pools.cancel_formats(&file_path);
+      assert_eq!(*cancel_count.lock(), 1);
+    }
+
+    // does nothing once the format is done
+    plugin_pools.cancel_formats(&file_path);
+    assert_eq!(*cancel_count.lock(), 1);
+  }
+
+  fn get_pools_with_instances(plugin_pools: &PluginPools<TestEnvironment>) -> Vec<String> {
+    let mut plugin_names = plugin_pools.get_pools_with_instances().iter().map(|pool| pool.name().to_string()).collect::<Vec<_>>();
+    plugin_names.sort();
+    plugin_names
+  }
+
+  fn create_plugin_pools(fallback_on_error: bool) -> PluginPools<TestEnvironment> {
+    let plugin_pools = PluginPools::new(TestEnvironment::new());
+    plugin_pools.set_plugins(
+      vec![
+        Box::new(TestPlugin::new("first", "first", vec!["ts"], vec![])),
+        Box::new(TestPlugin::new("second", "second", vec!["ts", "js"], vec![])),
+        Box::new(TestPlugin::new("third", "third", vec!["ts"], vec!["special.ts"])),
+      ],
+      fallback_on_error,
+    );
+    plugin_pools
+  }
+}
@@ -3,9 +3,9 @@ use std::sync::Arc;
 
 use dprint_core::types::ErrBox;
 
-use super::implementations::create_plugin;
+use super::implementations::{create_plugin, create_plugin_from_cache_item};
 use crate::environment::Environment;
-use crate::plugins::{Plugin, PluginCache, PluginPools, PluginSourceReference};
+use crate::plugins::{Plugin, PluginCache, PluginCacheItem, PluginPools, PluginSourceReference};
 
 pub struct PluginResolver<TEnvironment: Environment> {
   environment: TEnvironment,
@@ -22,6 +22,8 @@ impl<TEnvironment: Environment> PluginResolver<TEnvironment> {
     }
   }
 
+  /// Resolves the plugins in parallel. The returned plugins are in the same order as
+  /// the provided references (configuration order), which determines their precedence.
   pub fn resolve_plugins(&self, plugin_references: Vec<PluginSourceReference>) -> Result<Vec<Box<dyn Plugin>>, ErrBox> {
     let plugins = plugin_references
       .into_par_iter()
@@ -31,6 +33,20 @@ impl<TEnvironment: Environment> PluginResolver<TEnvironment> {
     Ok(plugins)
   }
 
+  /// Resolves the plugins from previously resolved cache items (ex. in the editor service's
+  /// snapshot) without checking the plugin cache. The returned plugins are in the same order.
+  pub fn resolve_plugins_from_cache_items(&self, plugin_items: Vec<(PluginSourceReference, PluginCacheItem)>) -> Result<Vec<Box<dyn Plugin>>, ErrBox> {
+    plugin_items
+      .into_par_iter()
+      .map(|(plugin_reference, cache_item)| create_plugin_from_cache_item(self.plugin_pools.clone(), self.environment.clone(), &plugin_reference, cache_item))
+      .collect::<Result<Vec<Box<dyn Plugin>>, ErrBox>>()
+  }
+
+  /// Gets the file path and information of the plugin from the plugin cache.
+  pub fn get_plugin_cache_item(&self, plugin_reference: &PluginSourceReference) -> Result<PluginCacheItem, ErrBox> {
+    self.plugin_cache.get_plugin_cache_item(plugin_reference)
+  }
+
   fn resolve_plugin(&self, plugin_reference: PluginSourceReference) -> Result<Box<dyn Plugin>, ErrBox> {
     match create_plugin(self.plugin_pools.clone(), &self.plugin_cache, self.environment.clone(), &plugin_reference) {
       Ok(plugin) => Ok(plugin),
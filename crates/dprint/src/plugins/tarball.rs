@@ -0,0 +1,187 @@
+use dprint_core::types::ErrBox;
+use flate2::read::GzDecoder;
+use std::io::Read;
+
+use crate::utils::{read_tar_octal, read_tar_string};
+
+/// A file in a package tarball.
+pub struct TarballFile {
+  /// Path of the file relative to the package's root directory, which is never absolute
+  /// and never has `..` components.
+  pub path: String,
+  pub bytes: Vec<u8>,
+}
+
+/// Reads the files of a gzipped package tarball (ex. npm packages or crates), which
+/// has all its files in a root directory (ex. `package` or `<name>-<version>`).
+///
+/// Errors for paths that could escape the directory the files are written to.
+pub fn read_tarball_files(tarball: &[u8]) -> Result<Vec<TarballFile>, ErrBox> {
+  let mut bytes = Vec::new();
+  GzDecoder::new(tarball).read_to_end(&mut bytes)?;
+
+  let mut files = Vec::new();
+  let mut offset = 0;
+  // the path of the next entry from a pax extended header or GNU long name entry
+  let mut next_entry_path = None;
+  while offset + 512 <= bytes.len() {
+    let header = &bytes[offset..offset + 512];
+    if header.iter().all(|b| *b == 0) {
+      break;
+    }
+    let name = read_tar_string(&header[0..100]);
+    let prefix = read_tar_string(&header[345..500]);
+    let size = read_tar_octal(&header[124..136])?;
+    let type_flag = header[156];
+    let data_start = offset + 512;
+    let data_end = data_start + size;
+    if data_end > bytes.len() {
+      return err!("Unexpected end of package tarball.");
+    }
+    let data = &bytes[data_start..data_end];
+    // data is padded to 512 byte blocks
+    offset = data_start + (size + 511) / 512 * 512;
+
+    match type_flag {
+      b'x' => {
+        if let Some(path) = get_pax_path(data)? {
+          next_entry_path = Some(path);
+        }
+        continue;
+      }
+      b'L' => {
+        next_entry_path = Some(read_tar_string(data));
+        continue;
+      }
+      _ => {}
+    }
+
+    let entry_path = match next_entry_path.take() {
+      Some(path) => path,
+      None if prefix.is_empty() => name,
+      None => format!("{}/{}", prefix, name),
+    };
+    if !is_safe_entry_path(&entry_path) {
+      return err!("Invalid path in package tarball: {}", entry_path);
+    }
+    let is_file = type_flag == b'0' || type_flag == 0;
+    if is_file {
+      if let Some(path) = entry_path.splitn(2, '/').nth(1) {
+        files.push(TarballFile {
+          path: path.to_string(),
+          bytes: data.to_vec(),
+        });
+      }
+    }
+  }
+  Ok(files)
+}
+
+/// Gets the path from the records of a pax extended header, which are each
+/// formatted as `<length> <key>=<value>\n` (ex. `30 path=package/long/path.wasm\n`).
+fn get_pax_path(data: &[u8]) -> Result<Option<String>, ErrBox> {
+  let mut path = None;
+  let mut remaining = data;
+  while !remaining.is_empty() {
+    let space_index = remaining.iter().position(|b| *b == b' ');
+    let length = space_index.and_then(|index| std::str::from_utf8(&remaining[..index]).ok()?.parse::<usize>().ok());
+    let (space_index, length) = match (space_index, length) {
+      (Some(space_index), Some(length)) if length > space_index && length <= remaining.len() => (space_index, length),
+      _ => return err!("Invalid pax extended header in package tarball."),
+    };
+    let record = String::from_utf8_lossy(&remaining[space_index + 1..length]);
+    if let Some(value) = record.trim_end_matches('\n').strip_prefix("path=") {
+      path = Some(value.to_string());
+    }
+    remaining = &remaining[length..];
+  }
+  Ok(path)
+}
+
+/// Gets if the path is relative and does not have any `..` components.
+fn is_safe_entry_path(path: &str) -> bool {
+  // includes windows paths with a drive (ex. `C:/file`)
+  let is_absolute = path.starts_with('/') || path.starts_with('\\') || path.get(1..2) == Some(":");
+  !is_absolute && !path.split(|c| c == '/' || c == '\\').any(|component| component == "..")
+}
+
+/// Gets a file from a gzipped package tarball by its path relative to the package's root directory.
+pub fn extract_tarball_file(tarball: &[u8], file_path: &str) -> Result<Option<Vec<u8>>, ErrBox> {
+  let file_path = file_path.trim_start_matches("./");
+  Ok(read_tarball_files(tarball)?.into_iter().find(|file| file.path == file_path).map(|file| file.bytes))
+}
+
+#[cfg(test)]
+pub fn create_tarball(files: &[(&str, &[u8])]) -> Vec<u8> {
+  create_tarball_with_entries(&files.iter().map(|(path, data)| (*path, b'0', *data)).collect::<Vec<_>>())
+}
+
+/// Creates a tarball with entries of the provided type flags (ex. `b'x'` for a pax extended header).
+#[cfg(test)]
+fn create_tarball_with_entries(entries: &[(&str, u8, &[u8])]) -> Vec<u8> {
+  use flate2::write::GzEncoder;
+  use std::io::Write;
+
+  let mut tar = Vec::new();
+  for (path, type_flag, data) in entries {
+    let mut header = [0u8; 512];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    let size = format!("{:011o}", data.len());
+    header[124..124 + size.len()].copy_from_slice(size.as_bytes());
+    header[156] = *type_flag;
+    tar.extend_from_slice(&header);
+    tar.extend_from_slice(data);
+    tar.resize((tar.len() + 511) / 512 * 512, 0);
+  }
+  tar.extend_from_slice(&[0u8; 1024]);
+
+  let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+  encoder.write_all(&tar).unwrap();
+  encoder.finish().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_read_tarball_files() {
+    let tarball = create_tarball(&[("package/package.json", b"{}"), ("package/dist/plugin.wasm", b"wasm")]);
+    let files = read_tarball_files(&tarball).unwrap();
+    assert_eq!(files.iter().map(|file| file.path.as_str()).collect::<Vec<_>>(), vec!["package.json", "dist/plugin.wasm"]);
+    assert_eq!(extract_tarball_file(&tarball, "./dist/plugin.wasm").unwrap(), Some(b"wasm".to_vec()));
+    assert_eq!(extract_tarball_file(&tarball, "other.wasm").unwrap(), None);
+  }
+
+  #[test]
+  fn should_read_long_paths_from_pax_and_gnu_headers() {
+    let long_dir = "a".repeat(120);
+    let pax_path = format!("package/{}/pax.wasm", long_dir);
+    // the length includes the length digits, space, and new line
+    let pax_record = format!("{} path={}\n", pax_path.len() + 10, pax_path);
+    let gnu_path = format!("package/{}/gnu.wasm", long_dir);
+    let tarball = create_tarball_with_entries(&[
+      ("PaxHeader", b'x', pax_record.as_bytes()),
+      ("package/truncated", b'0', b"pax"),
+      ("././@LongLink", b'L', gnu_path.as_bytes()),
+      ("package/truncated", b'0', b"gnu"),
+      ("package/short.wasm", b'0', b"short"),
+    ]);
+    let files = read_tarball_files(&tarball).unwrap();
+    assert_eq!(
+      files.iter().map(|file| file.path.as_str()).collect::<Vec<_>>(),
+      vec![format!("{}/pax.wasm", long_dir), format!("{}/gnu.wasm", long_dir), "short.wasm".to_string()]
+    );
+    assert_eq!(files[0].bytes, b"pax".to_vec());
+  }
+
+  #[test]
+  fn should_error_for_paths_outside_root_directory() {
+    for path in ["package/../../escaped.rs", "/etc/escaped.rs", "package/src/..\\..\\escaped.rs", "C:/escaped.rs"].iter() {
+      let tarball = create_tarball(&[("package/Cargo.toml", b""), (path, b"")]);
+      let err = read_tarball_files(&tarball).err().unwrap();
+      assert_eq!(err.to_string(), format!("Invalid path in package tarball: {}", path));
+    }
+  }
+}
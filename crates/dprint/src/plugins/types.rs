@@ -1,6 +1,7 @@
 use dprint_cli_core::checksums::parse_checksum_path_or_url;
 use dprint_core::plugins::PluginInfo;
 use dprint_core::types::ErrBox;
+use url::Url;
 
 use crate::utils::{resolve_url_or_file_path_to_path_source, PathSource};
 
@@ -22,11 +23,17 @@ impl PluginSourceReference {
   }
 
   pub fn is_wasm_plugin(&self) -> bool {
-    self.path_source.is_wasm_plugin()
+    self.path_source.is_wasm_plugin() && !self.is_npm_process_plugin()
   }
 
   pub fn is_process_plugin(&self) -> bool {
-    self.path_source.is_process_plugin()
+    self.path_source.is_process_plugin() || self.is_npm_process_plugin()
+  }
+
+  /// Process plugins in npm packages are specified with the checksum of their
+  /// plugin file, like process plugins at a url (ex. `npm:@scope/plugin@1.2.3@checksum`).
+  fn is_npm_process_plugin(&self) -> bool {
+    self.path_source.is_npm_plugin() && self.checksum.is_some()
   }
 
   #[cfg(test)]
@@ -47,6 +54,14 @@ impl PluginSourceReference {
 }
 
 pub fn parse_plugin_source_reference(text: &str, base: &PathSource) -> Result<PluginSourceReference, ErrBox> {
+  if is_registry_specifier(text) {
+    let (text, checksum) = parse_npm_checksum(text);
+    return Ok(PluginSourceReference {
+      path_source: PathSource::new_remote(Url::parse(text)?),
+      checksum,
+    });
+  }
+
   let checksum_reference = parse_checksum_path_or_url(text);
   let path_source = resolve_url_or_file_path_to_path_source(&checksum_reference.path_or_url, base)?;
 
@@ -68,6 +83,30 @@ pub fn parse_plugin_source_reference(text: &str, base: &PathSource) -> Result<Pl
   })
 }
 
+/// Parses the url or file path of a plugin ignoring any checksum.
+pub fn parse_plugin_path_source(text: &str, base: &PathSource) -> Result<PathSource, ErrBox> {
+  if is_registry_specifier(text) {
+    Ok(PathSource::new_remote(Url::parse(parse_npm_checksum(text).0)?))
+  } else {
+    resolve_url_or_file_path_to_path_source(&parse_checksum_path_or_url(text).path_or_url, base)
+  }
+}
+
+/// npm and crate specifiers use @ for the version and are verified with the registry's checksum instead.
+fn is_registry_specifier(text: &str) -> bool {
+  text.starts_with("npm:") || text.starts_with("crate:")
+}
+
+/// Splits the checksum from an npm specifier that has one after the version (ex. `npm:@scope/plugin@1.2.3@checksum`).
+fn parse_npm_checksum(text: &str) -> (&str, Option<String>) {
+  // skip the @ of a scoped package name
+  let is_npm_with_checksum = text.starts_with("npm:") && text.get("npm:".len() + 1..).map(|text| text.matches('@').count() == 2).unwrap_or(false);
+  match text.rfind('@') {
+    Some(index) if is_npm_with_checksum => (&text[..index], Some(text[index + 1..].to_string())),
+    _ => (text, None),
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -110,6 +149,52 @@ mod tests {
     );
   }
 
+  #[test]
+  fn it_should_parse_npm_plugin() {
+    let result = parse_plugin_source_reference("npm:@scope/plugin@1.2.3", &PathSource::new_local(PathBuf::from("./"))).unwrap();
+    assert_eq!(
+      result,
+      PluginSourceReference {
+        path_source: PathSource::new_remote_from_str("npm:@scope/plugin@1.2.3"),
+        checksum: None,
+      }
+    );
+  }
+
+  #[test]
+  fn it_should_parse_npm_process_plugin_with_checksum() {
+    let result = parse_plugin_source_reference("npm:@scope/plugin@1.2.3@checksum", &PathSource::new_local(PathBuf::from("./"))).unwrap();
+    assert_eq!(
+      result,
+      PluginSourceReference {
+        path_source: PathSource::new_remote_from_str("npm:@scope/plugin@1.2.3"),
+        checksum: Some(String::from("checksum")),
+      }
+    );
+    assert!(result.is_process_plugin());
+    assert!(!result.is_wasm_plugin());
+
+    let result = parse_plugin_source_reference("npm:plugin@latest@checksum", &PathSource::new_local(PathBuf::from("./"))).unwrap();
+    assert_eq!(result.path_source, PathSource::new_remote_from_str("npm:plugin@latest"));
+    assert_eq!(result.checksum, Some(String::from("checksum")));
+
+    let result = parse_plugin_source_reference("npm:@scope/plugin@1.2.3", &PathSource::new_local(PathBuf::from("./"))).unwrap();
+    assert!(result.is_wasm_plugin());
+    assert!(!result.is_process_plugin());
+  }
+
+  #[test]
+  fn it_should_parse_crate_plugin() {
+    let result = parse_plugin_source_reference("crate:dprint-plugin-foo@0.3", &PathSource::new_local(PathBuf::from("./"))).unwrap();
+    assert_eq!(
+      result,
+      PluginSourceReference {
+        path_source: PathSource::new_remote_from_str("crate:dprint-plugin-foo@0.3"),
+        checksum: None,
+      }
+    );
+  }
+
   #[test]
   fn it_should_parse_non_wasm_plugin_with_checksum() {
     let result = parse_plugin_source_reference("http://dprint.dev/plugin.exe-plugin@checksum", &PathSource::new_local(PathBuf::from("./"))).unwrap();
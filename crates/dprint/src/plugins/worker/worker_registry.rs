@@ -21,7 +21,7 @@ impl<TEnvironment: Environment> WorkerRegistry<TEnvironment> {
       plugin_pools: &PluginPools<TEnvironment>,
       file_paths_by_plugin: HashMap<String, Vec<PathBuf>>,
     ) -> Vec<Arc<Worker<TEnvironment>>> {
-      let number_threads = std::cmp::max(1, num_cpus::get()); // use logical cores (same as Rayon)
+      let number_threads = plugin_pools.environment().get_max_threads();
       let mut workers = Vec::with_capacity(number_threads);
 
       // initially divide work by plugins
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+
+use dprint_core::types::ErrBox;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ArchiveKind {
+  Zip,
+  Tar,
+}
+
+impl ArchiveKind {
+  /// Gets the kind of archive based on the extension of the path.
+  pub fn from_path(path: &str) -> Option<ArchiveKind> {
+    let lower_path = path.to_lowercase();
+    if lower_path.ends_with(".zip") {
+      Some(ArchiveKind::Zip)
+    } else if lower_path.ends_with(".tar") {
+      Some(ArchiveKind::Tar)
+    } else {
+      None
+    }
+  }
+}
+
+/// A file inside an archive.
+pub struct ArchiveMember {
+  /// Path of the file within the archive.
+  pub name: String,
+  pub bytes: Vec<u8>,
+}
+
+/// Reads the files inside the archive. Directories and other kinds of entries are skipped.
+pub fn read_archive_members(kind: ArchiveKind, archive_bytes: &[u8]) -> Result<Vec<ArchiveMember>, ErrBox> {
+  match kind {
+    ArchiveKind::Zip => {
+      let mut zip = zip::ZipArchive::new(Cursor::new(archive_bytes))?;
+      let mut members = Vec::with_capacity(zip.len());
+      for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if !file.is_dir() {
+          let mut bytes = Vec::with_capacity(file.size() as usize);
+          file.read_to_end(&mut bytes)?;
+          members.push(ArchiveMember {
+            name: file.name().to_string(),
+            bytes,
+          });
+        }
+      }
+      Ok(members)
+    }
+    ArchiveKind::Tar => Ok(
+      read_tar_entries(archive_bytes)?
+        .into_iter()
+        .filter(|entry| entry.is_file)
+        .map(|entry| ArchiveMember {
+          name: entry.name,
+          bytes: archive_bytes[entry.data_range].to_vec(),
+        })
+        .collect(),
+    ),
+  }
+}
+
+/// Writes the archive with the text of the changed files replaced. Everything else (ex. the order
+/// of the entries and their modified times) is kept the same so the output is deterministic.
+pub fn write_archive_members(kind: ArchiveKind, archive_bytes: &[u8], changed_members: &HashMap<String, Vec<u8>>) -> Result<Vec<u8>, ErrBox> {
+  match kind {
+    ArchiveKind::Zip => {
+      let mut zip = zip::ZipArchive::new(Cursor::new(archive_bytes))?;
+      let mut writer = zip::ZipWriter::new(Cursor::new(Vec::with_capacity(archive_bytes.len())));
+      for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let mut options = zip::write::FileOptions::default()
+          .compression_method(file.compression())
+          .last_modified_time(file.last_modified());
+        if let Some(mode) = file.unix_mode() {
+          options = options.unix_permissions(mode);
+        }
+        if file.is_dir() {
+          writer.add_directory(file.name(), options)?;
+        } else {
+          writer.start_file(file.name(), options)?;
+          let changed_bytes = changed_members.get(file.name());
+          match changed_bytes {
+            Some(bytes) => writer.write_all(bytes)?,
+            None => {
+              std::io::copy(&mut file, &mut writer)?;
+            }
+          }
+        }
+      }
+      Ok(writer.finish()?.into_inner())
+    }
+    ArchiveKind::Tar => {
+      let entries = read_tar_entries(archive_bytes)?;
+      let mut result = Vec::with_capacity(archive_bytes.len());
+      let mut end = 0;
+      for entry in entries.into_iter() {
+        let padded_end = get_tar_padded_len(entry.data_range.end);
+        match changed_members.get(&entry.name).filter(|_| entry.is_file) {
+          Some(bytes) => {
+            let mut header = archive_bytes[entry.header_start..entry.header_start + 512].to_vec();
+            header[124..136].copy_from_slice(format!("{:011o}\0", bytes.len()).as_bytes());
+            set_tar_header_checksum(&mut header);
+            result.extend(header);
+            result.extend(bytes);
+            result.resize(get_tar_padded_len(result.len()), 0);
+          }
+          None => result.extend(&archive_bytes[entry.header_start..padded_end]),
+        }
+        end = padded_end;
+      }
+      // the end of archive blocks
+      result.extend(&archive_bytes[end..]);
+      Ok(result)
+    }
+  }
+}
+
+struct TarEntry {
+  name: String,
+  is_file: bool,
+  header_start: usize,
+  data_range: std::ops::Range<usize>,
+}
+
+fn read_tar_entries(bytes: &[u8]) -> Result<Vec<TarEntry>, ErrBox> {
+  let mut entries = Vec::new();
+  let mut offset = 0;
+  while offset + 512 <= bytes.len() {
+    let header = &bytes[offset..offset + 512];
+    if header.iter().all(|b| *b == 0) {
+      break;
+    }
+    let name = read_tar_string(&header[0..100]);
+    let prefix = read_tar_string(&header[345..500]);
+    let size = read_tar_octal(&header[124..136])?;
+    let type_flag = header[156];
+    let data_start = offset + 512;
+    let data_end = data_start + size;
+    if data_end > bytes.len() {
+      return err!("Unexpected end of tar archive.");
+    }
+
+    entries.push(TarEntry {
+      name: if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) },
+      is_file: type_flag == b'0' || type_flag == 0,
+      header_start: offset,
+      data_range: data_start..data_end,
+    });
+    offset = get_tar_padded_len(data_end);
+  }
+  Ok(entries)
+}
+
+/// Data in tar archives is padded to 512 byte blocks.
+fn get_tar_padded_len(len: usize) -> usize {
+  (len + 511) / 512 * 512
+}
+
+fn set_tar_header_checksum(header: &mut [u8]) {
+  // the checksum is calculated with its own field filled with spaces
+  header[148..156].copy_from_slice(b"        ");
+  let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+  header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+}
+
+pub fn read_tar_string(bytes: &[u8]) -> String {
+  let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+  String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+pub fn read_tar_octal(bytes: &[u8]) -> Result<usize, ErrBox> {
+  let text = read_tar_string(bytes);
+  let text = text.trim();
+  if text.is_empty() {
+    Ok(0)
+  } else {
+    match usize::from_str_radix(text, 8) {
+      Ok(value) => Ok(value),
+      Err(_) => err!("Invalid size in tar archive: {}", text),
+    }
+  }
+}
+
+#[cfg(test)]
+pub fn create_tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+  let mut tar = Vec::new();
+  for (path, data) in files {
+    let mut header = [0u8; 512];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    header[124..136].copy_from_slice(format!("{:011o}\0", data.len()).as_bytes());
+    header[156] = b'0';
+    set_tar_header_checksum(&mut header);
+    tar.extend_from_slice(&header);
+    tar.extend_from_slice(data);
+    tar.resize(get_tar_padded_len(tar.len()), 0);
+  }
+  tar.extend_from_slice(&[0u8; 1024]);
+  tar
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_get_archive_kind_from_path() {
+    assert_eq!(ArchiveKind::from_path("assets.zip"), Some(ArchiveKind::Zip));
+    assert_eq!(ArchiveKind::from_path("dir/Fixtures.TAR"), Some(ArchiveKind::Tar));
+    assert_eq!(ArchiveKind::from_path("assets.tar.gz"), None);
+  }
+
+  #[test]
+  fn should_write_changed_tar_members() {
+    let tar = create_tar(&[("a.json", b"{ }"), ("dir/b.txt", b"text")]);
+    let members = read_archive_members(ArchiveKind::Tar, &tar).unwrap();
+    assert_eq!(
+      members.iter().map(|member| member.name.as_str()).collect::<Vec<_>>(),
+      vec!["a.json", "dir/b.txt"]
+    );
+
+    let mut changed_members = HashMap::new();
+    changed_members.insert("a.json".to_string(), b"{}\n".to_vec());
+    let new_tar = write_archive_members(ArchiveKind::Tar, &tar, &changed_members).unwrap();
+    assert_eq!(new_tar, create_tar(&[("a.json", b"{}\n"), ("dir/b.txt", b"text")]));
+  }
+
+  #[test]
+  fn should_write_changed_zip_members() {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::FileOptions::default().last_modified_time(zip::DateTime::default());
+    writer.add_directory("dir/", options).unwrap();
+    writer.start_file("dir/a.json", options).unwrap();
+    writer.write_all(b"{ }").unwrap();
+    writer.start_file("b.txt", options).unwrap();
+    writer.write_all(b"text").unwrap();
+    let zip_bytes = writer.finish().unwrap().into_inner();
+
+    let mut changed_members = HashMap::new();
+    changed_members.insert("dir/a.json".to_string(), b"{}\n".to_vec());
+    let new_zip_bytes = write_archive_members(ArchiveKind::Zip, &zip_bytes, &changed_members).unwrap();
+    let members = read_archive_members(ArchiveKind::Zip, &new_zip_bytes).unwrap();
+    assert_eq!(
+      members.iter().map(|member| (member.name.as_str(), member.bytes.as_slice())).collect::<Vec<_>>(),
+      vec![("dir/a.json", b"{}\n".as_ref()), ("b.txt", b"text".as_ref())]
+    );
+    // should be deterministic
+    assert_eq!(write_archive_members(ArchiveKind::Zip, &zip_bytes, &changed_members).unwrap(), new_zip_bytes);
+  }
+}
@@ -0,0 +1,100 @@
+use dprint_core::plugins::ChangedRegion;
+
+/// Text to write at a byte offset of a file.
+#[derive(Debug, PartialEq)]
+pub struct FileRegionWrite<'a> {
+  pub offset: usize,
+  pub text: &'a str,
+}
+
+/// Gets the changed regions if applying them to the file text results in the formatted text.
+///
+/// Plugins report the regions relative to the text they were provided, so they're not used when
+/// the CLI changed the text before or after formatting (ex. when normalizing line endings).
+pub fn get_verified_changed_regions(file_text: &str, formatted_text: &str, changed_regions: Vec<ChangedRegion>) -> Option<Vec<ChangedRegion>> {
+  let mut new_text = String::with_capacity(formatted_text.len());
+  let mut last_end = 0;
+  for region in changed_regions.iter() {
+    if region.start < last_end || region.start > region.end || !file_text.is_char_boundary(region.start) || !file_text.is_char_boundary(region.end) {
+      return None;
+    }
+    new_text.push_str(&file_text[last_end..region.start]);
+    new_text.push_str(&region.new_text);
+    last_end = region.end;
+  }
+  new_text.push_str(&file_text[last_end..]);
+
+  if new_text == formatted_text {
+    Some(changed_regions)
+  } else {
+    None
+  }
+}
+
+/// Gets the writes to do to a file containing the file text in order for it to contain the formatted text.
+///
+/// Regions that don't change the length of the text are written in place. Once a region changes the
+/// length, the rest of the file is shifted, so the formatted text is written from that region onwards.
+pub fn get_file_region_writes<'a>(formatted_text: &'a str, changed_regions: &'a [ChangedRegion]) -> Vec<FileRegionWrite<'a>> {
+  let mut writes = Vec::new();
+  for region in changed_regions {
+    if region.new_text.len() == region.end - region.start {
+      if !region.new_text.is_empty() {
+        writes.push(FileRegionWrite {
+          offset: region.start,
+          text: &region.new_text,
+        });
+      }
+    } else {
+      // the start is the same in the formatted text because all the previous regions kept their length
+      writes.push(FileRegionWrite {
+        offset: region.start,
+        text: &formatted_text[region.start..],
+      });
+      break;
+    }
+  }
+  writes
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn region(start: usize, end: usize, new_text: &str) -> ChangedRegion {
+    ChangedRegion {
+      start,
+      end,
+      new_text: new_text.to_string(),
+    }
+  }
+
+  #[test]
+  fn should_verify_changed_regions() {
+    let regions = vec![region(0, 1, "A"), region(4, 6, "")];
+    assert_eq!(get_verified_changed_regions("abc\n  d", "Abc\nd", regions.clone()), Some(regions));
+    // doesn't result in the formatted text
+    assert_eq!(get_verified_changed_regions("abc", "Abcd", vec![region(0, 1, "A")]), None);
+    // out of order
+    assert_eq!(get_verified_changed_regions("abc", "ABC", vec![region(1, 3, "BC"), region(0, 1, "A")]), None);
+    // out of bounds
+    assert_eq!(get_verified_changed_regions("abc", "abc", vec![region(2, 4, "c")]), None);
+    // not on a char boundary
+    assert_eq!(get_verified_changed_regions("é", "e", vec![region(0, 1, "e")]), None);
+  }
+
+  #[test]
+  fn should_get_file_region_writes() {
+    let regions = vec![region(0, 1, "A"), region(2, 3, "C")];
+    assert_eq!(
+      get_file_region_writes("AbC d", &regions),
+      vec![FileRegionWrite { offset: 0, text: "A" }, FileRegionWrite { offset: 2, text: "C" }],
+    );
+
+    let regions = vec![region(0, 1, "A"), region(2, 4, ""), region(5, 6, "E")];
+    assert_eq!(
+      get_file_region_writes("AbeE", &regions),
+      vec![FileRegionWrite { offset: 0, text: "A" }, FileRegionWrite { offset: 2, text: "eE" }],
+    );
+  }
+}
@@ -0,0 +1,214 @@
+use std::path::{Path, PathBuf};
+
+use dprint_cli_core::types::ErrBox;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::environment::Environment;
+
+use super::to_absolute_glob;
+
+/// Paths relative to the repository directory where a CODEOWNERS file may be found.
+const CODE_OWNERS_FILE_PATHS: [&str; 4] = ["CODEOWNERS", ".github/CODEOWNERS", ".gitlab/CODEOWNERS", "docs/CODEOWNERS"];
+
+/// The rules of a CODEOWNERS file for finding who owns a file.
+pub struct CodeOwners {
+  sections: Vec<CodeOwnersSection>,
+}
+
+/// Rules of the file that are evaluated separately. GitHub CODEOWNERS files only have one
+/// section while GitLab ones may have multiple (ex. `[Documentation] @docs-team`).
+struct CodeOwnersSection {
+  rules: Vec<CodeOwnersRule>,
+}
+
+struct CodeOwnersRule {
+  glob_set: GlobSet,
+  /// Owners of the matching files. A rule without owners makes the files unowned.
+  owners: Vec<String>,
+}
+
+impl CodeOwners {
+  /// Parses the text of a CODEOWNERS file with patterns relative to the provided directory.
+  pub fn new(file_text: &str, dir_path: &Path) -> Result<CodeOwners, ErrBox> {
+    let dir_path = dir_path.to_string_lossy();
+    let mut sections = vec![CodeOwnersSection { rules: Vec::new() }];
+    let mut section_default_owners = Vec::new();
+    for line in file_text.lines() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      if let Some(default_owners) = parse_section_header(line) {
+        sections.push(CodeOwnersSection { rules: Vec::new() });
+        section_default_owners = default_owners;
+        continue;
+      }
+
+      let mut parts = line.split_whitespace();
+      let pattern = parts.next().unwrap();
+      // a backslash escapes a leading # that isn't a comment
+      let pattern = pattern.strip_prefix('\\').unwrap_or(pattern);
+      let mut owners = parts.take_while(|part| !part.starts_with('#')).map(String::from).collect::<Vec<_>>();
+      if owners.is_empty() {
+        owners = section_default_owners.clone();
+      }
+      sections.last_mut().unwrap().rules.push(CodeOwnersRule {
+        glob_set: build_glob_set(pattern, &dir_path)?,
+        owners,
+      });
+    }
+    Ok(CodeOwners { sections })
+  }
+
+  /// Gets if the file is owned by the provided owner (ex. `@org/team`, `@user`, or an email).
+  /// The leading `@` of the owner is optional.
+  pub fn is_owned_by(&self, file_path: &Path, owner: &str) -> bool {
+    let file_path = file_path.to_string_lossy().replace("\\", "/");
+    let owner = owner.trim_start_matches('@');
+    // the last matching rule of each section takes precedence
+    self.sections.iter().any(|section| {
+      section
+        .rules
+        .iter()
+        .rev()
+        .find(|rule| rule.glob_set.is_match(&file_path))
+        .map(|rule| {
+          rule
+            .owners
+            .iter()
+            .any(|rule_owner| rule_owner.trim_start_matches('@').eq_ignore_ascii_case(owner))
+        })
+        .unwrap_or(false)
+    })
+  }
+}
+
+/// Reads the CODEOWNERS file of the directory.
+pub fn read_code_owners(dir_path: &Path, environment: &impl Environment) -> Result<CodeOwners, ErrBox> {
+  let file_path = match get_code_owners_file_path(dir_path, environment) {
+    Some(file_path) => file_path,
+    None => {
+      return err!(
+        "Could not find a CODEOWNERS file in {}. Expected one of: {}",
+        dir_path.display(),
+        CODE_OWNERS_FILE_PATHS.join(", ")
+      )
+    }
+  };
+  log_verbose!(environment, "Reading code owners file: {}", file_path.display());
+  let file_text = environment.read_file(&file_path)?;
+  match CodeOwners::new(&file_text, dir_path) {
+    Ok(code_owners) => Ok(code_owners),
+    Err(err) => err!("Error parsing {}. {}", file_path.display(), err.to_string()),
+  }
+}
+
+fn get_code_owners_file_path(dir_path: &Path, environment: &impl Environment) -> Option<PathBuf> {
+  CODE_OWNERS_FILE_PATHS
+    .iter()
+    .map(|file_path| dir_path.join(file_path))
+    .find(|file_path| environment.path_exists(file_path))
+}
+
+/// Parses a GitLab section header (ex. `[Section]`, `^[Optional Section][2] @owner`)
+/// returning its default owners.
+fn parse_section_header(line: &str) -> Option<Vec<String>> {
+  let line = line.strip_prefix('^').unwrap_or(line);
+  let line = line.strip_prefix('[')?;
+  let end_index = line.find(']')?;
+  let mut remaining = &line[end_index + 1..];
+  // skip the number of required approvals
+  if let Some(approvals_text) = remaining.strip_prefix('[') {
+    remaining = &approvals_text[approvals_text.find(']')? + 1..];
+  }
+  Some(remaining.split_whitespace().map(String::from).collect())
+}
+
+fn build_glob_set(pattern: &str, dir_path: &str) -> Result<GlobSet, ErrBox> {
+  // uses the same pattern semantics as gitignore files where a leading slash is relative to the directory
+  let pattern = match pattern.strip_prefix('/') {
+    Some(pattern) => format!("./{}", pattern),
+    None => pattern.to_string(),
+  };
+  let pattern = to_absolute_glob(&pattern, dir_path);
+  let patterns = if let Some(dir_pattern) = pattern.strip_suffix('/') {
+    // only match the descendants of directories
+    vec![format!("{}/**", dir_pattern)]
+  } else if pattern.ends_with("/*") {
+    // only match the files directly in the directory
+    vec![pattern]
+  } else {
+    vec![format!("{}/**", pattern), pattern]
+  };
+  let mut builder = GlobSetBuilder::new();
+  for pattern in patterns {
+    builder.add(GlobBuilder::new(&pattern).literal_separator(true).case_insensitive(cfg!(windows)).build()?);
+  }
+  Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::environment::TestEnvironment;
+
+  #[test]
+  fn it_should_match_last_rule() {
+    let code_owners = CodeOwners::new(
+      "# comment\n* @org/everyone\n*.ts @org/frontend\n/docs/ @docs-user\napps/api/** @org/backend\nbuild/ \n",
+      &PathBuf::from("/repo"),
+    )
+    .unwrap();
+    let is_owned_by = |file_path: &str, owner: &str| code_owners.is_owned_by(&PathBuf::from(file_path), owner);
+    assert!(is_owned_by("/repo/README.md", "@org/everyone"));
+    assert!(is_owned_by("/repo/src/main.ts", "@org/frontend"));
+    assert!(!is_owned_by("/repo/src/main.ts", "@org/everyone"));
+    assert!(is_owned_by("/repo/docs/sub/file.md", "docs-user"));
+    assert!(is_owned_by("/repo/docs/sub/file.ts", "@DOCS-USER"));
+    assert!(is_owned_by("/repo/apps/api/src/main.ts", "@org/backend"));
+    assert!(is_owned_by("/repo/other/apps/api/main.rs", "@org/everyone"));
+    // a rule without owners makes the files unowned
+    assert!(!is_owned_by("/repo/build/file.js", "@org/everyone"));
+  }
+
+  #[test]
+  fn it_should_match_directory_files_only_for_single_star() {
+    let code_owners = CodeOwners::new("docs/* @docs-user\n", &PathBuf::from("/repo")).unwrap();
+    assert!(code_owners.is_owned_by(&PathBuf::from("/repo/docs/file.md"), "@docs-user"));
+    assert!(!code_owners.is_owned_by(&PathBuf::from("/repo/docs/sub/file.md"), "@docs-user"));
+  }
+
+  #[test]
+  fn it_should_match_each_section() {
+    let code_owners = CodeOwners::new(
+      "* @everyone\n[Docs] @docs-team\n*.md\n^[Frontend][2] @frontend-team\n*.ts\n*.test.ts @testers\n",
+      &PathBuf::from("/repo"),
+    )
+    .unwrap();
+    let is_owned_by = |file_path: &str, owner: &str| code_owners.is_owned_by(&PathBuf::from(file_path), owner);
+    assert!(is_owned_by("/repo/README.md", "@everyone"));
+    assert!(is_owned_by("/repo/README.md", "@docs-team"));
+    assert!(!is_owned_by("/repo/README.md", "@frontend-team"));
+    assert!(is_owned_by("/repo/main.ts", "@frontend-team"));
+    assert!(is_owned_by("/repo/main.test.ts", "@testers"));
+    assert!(!is_owned_by("/repo/main.test.ts", "@frontend-team"));
+  }
+
+  #[test]
+  fn it_should_read_code_owners_in_github_dir() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/repo/.github/CODEOWNERS", "*.ts @user").unwrap();
+    let code_owners = read_code_owners(&PathBuf::from("/repo"), &environment).unwrap();
+    assert!(code_owners.is_owned_by(&PathBuf::from("/repo/file.ts"), "@user"));
+  }
+
+  #[test]
+  fn it_should_error_when_no_code_owners_file() {
+    let environment = TestEnvironment::new();
+    let err = read_code_owners(&PathBuf::from("/repo"), &environment).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Could not find a CODEOWNERS file in /repo. Expected one of: CODEOWNERS, .github/CODEOWNERS, .gitlab/CODEOWNERS, docs/CODEOWNERS"
+    );
+  }
+}
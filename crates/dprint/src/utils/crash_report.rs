@@ -0,0 +1,131 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+
+use dprint_core::types::ErrBox;
+
+use crate::environment::Environment;
+
+thread_local! {
+  /// The last file processed on the current thread, which is included in the crash report.
+  static CURRENT_FILE_PATH: RefCell<Option<PathBuf>> = RefCell::new(None);
+}
+
+/// Stores the file being processed on the current thread for a crash report.
+pub fn set_crash_report_file_path(file_path: &Path) {
+  CURRENT_FILE_PATH.with(|current_file_path| {
+    *current_file_path.borrow_mut() = Some(file_path.to_path_buf());
+  });
+}
+
+/// Information about a panic that is written to a crash report.
+pub struct CrashReport {
+  /// The command line arguments. These never include the file contents because those are provided via stdin.
+  pub args: Vec<String>,
+  pub file_path: Option<PathBuf>,
+  pub thread_name: Option<String>,
+  pub message: String,
+  pub backtrace: String,
+}
+
+/// Installs a panic hook that writes a crash report to the cache directory and outputs its path.
+pub fn install_crash_report_hook(args: Vec<String>, environment: &impl Environment) {
+  let environment = environment.clone();
+  let previous_hook = std::panic::take_hook();
+  std::panic::set_hook(Box::new(move |panic_info| {
+    previous_hook(panic_info);
+
+    let report = CrashReport {
+      args: args.clone(),
+      file_path: CURRENT_FILE_PATH.with(|file_path| file_path.borrow().clone()),
+      thread_name: std::thread::current().name().map(|name| name.to_string()),
+      message: get_panic_message(panic_info.payload(), panic_info.location()),
+      backtrace: Backtrace::force_capture().to_string(),
+    };
+    // don't use the environment's logger here because the panic may have occurred while it was locked
+    match write_crash_report(&report, &environment) {
+      Ok(file_path) => eprintln!(
+        "A crash report was written to {}. Please include it when reporting this as a bug.",
+        file_path.display()
+      ),
+      Err(err) => eprintln!("Error writing crash report. {}", err.to_string()),
+    }
+  }));
+}
+
+/// Writes the crash report to the cache directory and returns its file path.
+pub fn write_crash_report(report: &CrashReport, environment: &impl Environment) -> Result<PathBuf, ErrBox> {
+  let dir_path = environment.get_cache_dir().join("crash-reports");
+  let file_path = dir_path.join(format!("crash-{}-{}.txt", environment.get_time_secs(), std::process::id()));
+  environment.mk_dir_all(&dir_path)?;
+  environment.write_file(&file_path, &get_crash_report_text(report))?;
+  Ok(file_path)
+}
+
+fn get_crash_report_text(report: &CrashReport) -> String {
+  let mut text = String::new();
+  text.push_str(&format!("dprint {}\n", env!("CARGO_PKG_VERSION")));
+  text.push_str(&format!("Platform: {} {}\n", std::env::consts::OS, std::env::consts::ARCH));
+  text.push_str(&format!("Args: {}\n", report.args.join(" ")));
+  text.push_str(&format!(
+    "File: {}\n",
+    report
+      .file_path
+      .as_ref()
+      .map(|file_path| file_path.display().to_string())
+      .unwrap_or_else(|| "None".to_string())
+  ));
+  text.push_str(&format!("Thread: {}\n", report.thread_name.as_deref().unwrap_or("<unnamed>")));
+  text.push_str(&format!("Panic: {}\n\n", report.message));
+  text.push_str("Backtrace:\n");
+  text.push_str(&report.backtrace);
+  text
+}
+
+fn get_panic_message(payload: &(dyn Any + Send), location: Option<&Location>) -> String {
+  let message = if let Some(message) = payload.downcast_ref::<&str>() {
+    message.to_string()
+  } else if let Some(message) = payload.downcast_ref::<String>() {
+    message.clone()
+  } else {
+    "Unknown panic payload.".to_string()
+  };
+  match location {
+    Some(location) => format!("{} ({}:{}:{})", message, location.file(), location.line(), location.column()),
+    None => message,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::environment::TestEnvironment;
+
+  #[test]
+  fn should_write_crash_report_to_cache_dir() {
+    let environment = TestEnvironment::new();
+    let report = CrashReport {
+      args: vec!["dprint".to_string(), "fmt".to_string()],
+      file_path: Some(PathBuf::from("/file.txt")),
+      thread_name: Some("main".to_string()),
+      message: "Test panic.".to_string(),
+      backtrace: "0: test\n".to_string(),
+    };
+    let file_path = write_crash_report(&report, &environment).unwrap();
+    assert_eq!(
+      file_path,
+      PathBuf::from(format!("/cache/crash-reports/crash-123456-{}.txt", std::process::id()))
+    );
+    assert_eq!(
+      environment.read_file(&file_path).unwrap(),
+      format!(
+        "dprint {}\nPlatform: {} {}\nArgs: dprint fmt\nFile: /file.txt\nThread: main\nPanic: Test panic.\n\nBacktrace:\n0: test\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+      )
+    );
+  }
+}
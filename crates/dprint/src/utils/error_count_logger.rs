@@ -1,11 +1,27 @@
-use crate::environment::Environment;
+use crate::environment::{Environment, OutputFormat};
+use crate::plugins::PluginFormatError;
+use dprint_cli_core::types::ErrBox;
+use parking_lot::Mutex;
+use serde_json::json;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+/// Maximum number of file paths to list when summarizing the files that had the same error.
+const MAX_DUPLICATE_FILE_PATHS: usize = 5;
+
+/// Files that had the same error message as the first file.
+struct FileErrorGroup {
+  /// Name of the plugin (if any) along with the error message.
+  key: String,
+  duplicate_file_paths: Vec<PathBuf>,
+}
+
 /// Logger that keeps track of how many errors it's logged.
 #[derive(Clone)]
 pub struct ErrorCountLogger<TEnvironment: Environment> {
   error_count: Arc<AtomicUsize>,
+  file_error_groups: Arc<Mutex<Vec<FileErrorGroup>>>,
   environment: TEnvironment,
 }
 
@@ -13,6 +29,7 @@ impl<TEnvironment: Environment> ErrorCountLogger<TEnvironment> {
   pub fn from_environment(environment: &TEnvironment) -> Self {
     ErrorCountLogger {
       error_count: Arc::new(AtomicUsize::new(0)),
+      file_error_groups: Arc::new(Mutex::new(Vec::new())),
       environment: environment.clone(),
     }
   }
@@ -22,7 +39,137 @@ impl<TEnvironment: Environment> ErrorCountLogger<TEnvironment> {
     self.error_count.fetch_add(1, Ordering::SeqCst);
   }
 
+  /// Logs an error formatting a file, which is a line of JSON when the output format is JSON.
+  ///
+  /// In text output, an error that's the same as an earlier one (ex. a plugin failing the same way
+  /// on every file) is only logged the first time unless verbose. Call `log_file_error_summary`
+  /// after formatting in order to log the files that weren't logged.
+  pub fn log_file_error(&self, file_path: &Path, err: &ErrBox) {
+    match self.environment.output_format() {
+      OutputFormat::Text => {
+        if !self.environment.is_verbose() && self.is_duplicate_file_error(file_path, err) {
+          self.error_count.fetch_add(1, Ordering::SeqCst);
+          return;
+        }
+        let text = format!("Error formatting {}. Message: {}", file_path.display(), err.to_string());
+        self.environment.log_error(&text);
+      }
+      OutputFormat::Json => {
+        let mut value = json!({
+          "kind": "error",
+          "filePath": file_path.display().to_string(),
+        });
+        if let Some(err) = err.downcast_ref::<PluginFormatError>() {
+          value["message"] = json!(err.message);
+          value["plugin"] = json!(err.plugin_name);
+          value["pluginVersion"] = json!(err.plugin_version);
+          value["helpUrl"] = json!(err.help_url);
+          value["fileHash"] = json!(err.file_hash);
+          if !err.diagnostics.is_empty() {
+            value["diagnostics"] = json!(err
+              .diagnostics
+              .iter()
+              .map(|diagnostic| {
+                let mut value = json!({ "message": diagnostic.message });
+                if let Some(position) = &diagnostic.position {
+                  value["line"] = json!(position.line_number);
+                  value["column"] = json!(position.column_number);
+                }
+                value
+              })
+              .collect::<Vec<_>>());
+          }
+        } else {
+          value["message"] = json!(err.to_string());
+        }
+        self.environment.log_json(&value);
+      }
+    }
+    self.error_count.fetch_add(1, Ordering::SeqCst);
+  }
+
+  /// Logs how many files had the same error as an earlier file along with some of their paths.
+  pub fn log_file_error_summary(&self) {
+    let file_error_groups = std::mem::take(&mut *self.file_error_groups.lock());
+    for mut group in file_error_groups.into_iter().filter(|group| !group.duplicate_file_paths.is_empty()) {
+      group.duplicate_file_paths.sort();
+      let count = group.duplicate_file_paths.len();
+      // keep the summary to a single line for multi-line errors
+      let error_text = group.key.lines().next().unwrap_or("").trim_end();
+      self.environment.log_error(&format!(
+        "Had the same error formatting {} other file(s) ({}). Run with --verbose to see each error.",
+        count, error_text
+      ));
+      for file_path in group.duplicate_file_paths.iter().take(MAX_DUPLICATE_FILE_PATHS) {
+        self.environment.log_error(&format!("  {}", file_path.display()));
+      }
+      if count > MAX_DUPLICATE_FILE_PATHS {
+        self.environment.log_error(&format!("  ...and {} more", count - MAX_DUPLICATE_FILE_PATHS));
+      }
+    }
+  }
+
+  /// Records the file path when it had the same error as an earlier file.
+  fn is_duplicate_file_error(&self, file_path: &Path, err: &ErrBox) -> bool {
+    // plugin errors include the file path and hash, so only compare the plugin and message
+    let key = match err.downcast_ref::<PluginFormatError>() {
+      Some(err) => format!("{}: {}", err.plugin_name, err.message),
+      None => err.to_string(),
+    };
+    let mut file_error_groups = self.file_error_groups.lock();
+    match file_error_groups.iter_mut().find(|group| group.key == key) {
+      Some(group) => {
+        group.duplicate_file_paths.push(file_path.to_path_buf());
+        true
+      }
+      None => {
+        file_error_groups.push(FileErrorGroup {
+          key,
+          duplicate_file_paths: Vec::new(),
+        });
+        false
+      }
+    }
+  }
+
   pub fn get_error_count(&self) -> usize {
     self.error_count.load(Ordering::SeqCst)
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::environment::TestEnvironment;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_summarize_each_group_of_file_errors() {
+    let environment = TestEnvironment::new();
+    let logger = ErrorCountLogger::from_environment(&environment);
+    let errors = [
+      ("/a.txt", "First error."),
+      ("/b.txt", "Second error."),
+      ("/c.txt", "First error."),
+      ("/e.txt", "Second error."),
+      ("/d.txt", "Second error."),
+    ];
+    for (file_path, message) in errors.iter() {
+      logger.log_file_error(Path::new(file_path), &ErrBox::from(*message));
+    }
+    logger.log_file_error_summary();
+    assert_eq!(logger.get_error_count(), 5);
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec![
+        "Error formatting /a.txt. Message: First error.",
+        "Error formatting /b.txt. Message: Second error.",
+        "Had the same error formatting 1 other file(s) (First error.). Run with --verbose to see each error.",
+        "  /c.txt",
+        "Had the same error formatting 2 other file(s) (Second error.). Run with --verbose to see each error.",
+        "  /d.txt",
+        "  /e.txt",
+      ]
+    );
+  }
+}
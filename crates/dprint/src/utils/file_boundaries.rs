@@ -0,0 +1,83 @@
+/// Policies for the start and end of a file, which are applied after a plugin
+/// formats it so the file boundaries don't vary based on the plugin.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FileBoundaryPolicy {
+  /// Removes any blank lines at the start of the file.
+  pub trim_leading_blank_lines: bool,
+  /// Ensures the file ends with exactly one newline.
+  pub single_trailing_new_line: bool,
+}
+
+/// Properties of a plugin's configuration for the file boundary policies. These are applied
+/// by the CLI, so they aren't provided to the plugin.
+pub const FILE_BOUNDARY_POLICY_KEYS: [&str; 2] = ["trimLeadingBlankLines", "singleTrailingNewLine"];
+
+pub fn apply_file_boundary_policy(formatted_text: String, policy: FileBoundaryPolicy) -> String {
+  if !policy.trim_leading_blank_lines && !policy.single_trailing_new_line {
+    return formatted_text;
+  }
+
+  // keep the byte order mark at the start of the file
+  let (bom, text) = match formatted_text.strip_prefix('\u{FEFF}') {
+    Some(text) => ("\u{FEFF}", text),
+    None => ("", formatted_text.as_str()),
+  };
+  let text = if policy.trim_leading_blank_lines {
+    trim_leading_blank_lines(text)
+  } else {
+    text
+  };
+  let text = if policy.single_trailing_new_line {
+    let trimmed_text = text.trim_end_matches(|c| c == '\r' || c == '\n');
+    if trimmed_text.is_empty() {
+      // don't add a newline to an empty file
+      String::new()
+    } else {
+      let new_line = if text.contains("\r\n") { "\r\n" } else { "\n" };
+      format!("{}{}", trimmed_text, new_line)
+    }
+  } else {
+    text.to_string()
+  };
+
+  format!("{}{}", bom, text)
+}
+
+fn trim_leading_blank_lines(text: &str) -> &str {
+  match text.find(|c: char| !c.is_whitespace()) {
+    // keep the indentation of the first line with text
+    Some(index) => &text[text[..index].rfind('\n').map(|index| index + 1).unwrap_or(0)..],
+    None => "",
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_trim_leading_blank_lines() {
+    let policy = FileBoundaryPolicy {
+      trim_leading_blank_lines: true,
+      single_trailing_new_line: false,
+    };
+    assert_eq!(apply_file_boundary_policy("\n  \r\n  a\n\n".to_string(), policy), "  a\n\n");
+    assert_eq!(apply_file_boundary_policy("\u{FEFF}\na\n".to_string(), policy), "\u{FEFF}a\n");
+    assert_eq!(apply_file_boundary_policy("a\n".to_string(), policy), "a\n");
+    assert_eq!(apply_file_boundary_policy("\n \n".to_string(), policy), "");
+  }
+
+  #[test]
+  fn should_ensure_single_trailing_new_line() {
+    let policy = FileBoundaryPolicy {
+      trim_leading_blank_lines: false,
+      single_trailing_new_line: true,
+    };
+    assert_eq!(apply_file_boundary_policy("\na".to_string(), policy), "\na\n");
+    assert_eq!(apply_file_boundary_policy("a\n\n\n".to_string(), policy), "a\n");
+    assert_eq!(apply_file_boundary_policy("a\r\nb\r\n\r\n".to_string(), policy), "a\r\nb\r\n");
+    assert_eq!(apply_file_boundary_policy("a  \n".to_string(), policy), "a  \n");
+    assert_eq!(apply_file_boundary_policy("".to_string(), policy), "");
+    assert_eq!(apply_file_boundary_policy("\n\n".to_string(), policy), "");
+  }
+}
@@ -1,5 +1,27 @@
 pub const BOM_CHAR: char = '\u{FEFF}';
 
+/// What to do with a file that contains invalid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InvalidUtf8Policy {
+  /// Error for the file.
+  Error,
+  /// Replace the invalid bytes with the replacement character (U+FFFD) and warn.
+  Replace,
+  /// Skip formatting the file.
+  Skip,
+}
+
+impl InvalidUtf8Policy {
+  pub fn parse(text: &str) -> Option<InvalidUtf8Policy> {
+    match text {
+      "error" => Some(InvalidUtf8Policy::Error),
+      "replace" => Some(InvalidUtf8Policy::Replace),
+      "skip" => Some(InvalidUtf8Policy::Skip),
+      _ => None,
+    }
+  }
+}
+
 pub struct FileText {
   text: String,
 }
@@ -1,4 +1,4 @@
-use crossterm::style::Stylize;
+use dprint_cli_core::styling::{white_on_green, white_on_red};
 use dissimilar::*;
 
 use dprint_core::types::ErrBox;
@@ -82,6 +82,52 @@ pub fn get_difference(text1: &str, text2: &str) -> Result<String, ErrBox> {
   Ok(text)
 }
 
+/// A one-based line and column position in a text.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TextPosition {
+  pub line_number: usize,
+  pub column_number: usize,
+}
+
+/// Gets the position in the first text where it first differs from the second text.
+pub fn get_first_difference_position(text1: &str, text2: &str) -> Option<TextPosition> {
+  let mut position = TextPosition {
+    line_number: 1,
+    column_number: 1,
+  };
+  let mut chars1 = text1.chars();
+  let mut chars2 = text2.chars();
+
+  loop {
+    match (chars1.next(), chars2.next()) {
+      (None, None) => return None,
+      (Some(c1), Some(c2)) if c1 == c2 => {
+        if c1 == '\n' {
+          position.line_number += 1;
+          position.column_number = 1;
+        } else {
+          position.column_number += 1;
+        }
+      }
+      _ => return Some(position),
+    }
+  }
+}
+
+/// Gets the position of the provided byte index in the text.
+pub fn get_text_position(text: &str, byte_index: usize) -> TextPosition {
+  let mut byte_index = std::cmp::min(byte_index, text.len());
+  while !text.is_char_boundary(byte_index) {
+    byte_index -= 1;
+  }
+  let text_before = &text[..byte_index];
+  let line_start = text_before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+  TextPosition {
+    line_number: text_before.matches('\n').count() + 1,
+    column_number: text_before[line_start..].chars().count() + 1,
+  }
+}
+
 fn get_line_start_text<'a>(text: &'a str, index: usize) -> Result<&'a str, ErrBox> {
   let new_line_byte = '\n' as u8;
   let text_bytes = text.as_bytes();
@@ -372,12 +418,12 @@ fn get_pre_processed_chunks<'a>(text1: &'a str, text2: &'a str) -> Vec<dissimila
 }
 
 fn get_addition_text(text: &str) -> String {
-  text.white().on_green().to_string()
+  white_on_green(text)
 }
 
 fn get_removal_text(text: &str) -> String {
   let text = text.replace("\t", "\u{21E5}");
-  text.white().on_red().to_string()
+  white_on_red(&text)
 }
 
 fn annotate_whitespace(text: &str) -> String {
@@ -389,6 +435,64 @@ mod test {
   use super::*;
   use pretty_assertions::assert_eq;
 
+  #[test]
+  fn it_should_get_first_difference_position() {
+    assert_eq!(get_first_difference_position("test", "test"), None);
+    assert_eq!(
+      get_first_difference_position("test", "tEst"),
+      Some(TextPosition {
+        line_number: 1,
+        column_number: 2
+      })
+    );
+    assert_eq!(
+      get_first_difference_position("a\nb\nc d", "a\nb\nc  d"),
+      Some(TextPosition {
+        line_number: 3,
+        column_number: 3
+      })
+    );
+    assert_eq!(
+      get_first_difference_position("a\r\n", "a\n"),
+      Some(TextPosition {
+        line_number: 1,
+        column_number: 2
+      })
+    );
+    assert_eq!(
+      get_first_difference_position("ab", "ab\n"),
+      Some(TextPosition {
+        line_number: 1,
+        column_number: 3
+      })
+    );
+  }
+
+  #[test]
+  fn it_should_get_text_position() {
+    assert_eq!(
+      get_text_position("test", 0),
+      TextPosition {
+        line_number: 1,
+        column_number: 1
+      }
+    );
+    assert_eq!(
+      get_text_position("a\nbé c", 6),
+      TextPosition {
+        line_number: 2,
+        column_number: 4
+      }
+    );
+    assert_eq!(
+      get_text_position("a\n", 10),
+      TextPosition {
+        line_number: 2,
+        column_number: 1
+      }
+    );
+  }
+
   #[test]
   fn it_should_get_when_differs_by_line_endings() {
     assert_eq!(get_difference("test\r\n", "test\n").unwrap(), " | Text differed by line endings.");
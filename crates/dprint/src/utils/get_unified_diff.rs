@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+/// Number of unchanged lines to show around each change.
+const CONTEXT_LINE_COUNT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DiffLineKind {
+  Unchanged,
+  Removed,
+  Added,
+}
+
+struct DiffLine<'a> {
+  kind: DiffLineKind,
+  /// The line text including its newline, if any.
+  text: &'a str,
+}
+
+/// Gets a unified diff (like `diff -u`) between the text of the file and the formatted text.
+pub fn get_unified_diff(file_path: &str, file_text: &str, formatted_text: &str) -> String {
+  let diff_lines = get_diff_lines(file_text, formatted_text);
+  let mut text = format!("--- {}\n+++ {}", file_path, file_path);
+
+  for (start, end) in get_hunk_ranges(&diff_lines) {
+    let (old_start, new_start) = get_line_numbers_at(&diff_lines, start);
+    let old_count = diff_lines[start..end].iter().filter(|line| line.kind != DiffLineKind::Added).count();
+    let new_count = diff_lines[start..end].iter().filter(|line| line.kind != DiffLineKind::Removed).count();
+    text.push_str(&format!(
+      "\n@@ -{} +{} @@",
+      get_hunk_range_text(old_start, old_count),
+      get_hunk_range_text(new_start, new_count)
+    ));
+
+    for line in diff_lines[start..end].iter() {
+      let prefix = match line.kind {
+        DiffLineKind::Unchanged => ' ',
+        DiffLineKind::Removed => '-',
+        DiffLineKind::Added => '+',
+      };
+      text.push('\n');
+      text.push(prefix);
+      text.push_str(line.text.trim_end_matches(&['\r', '\n'][..]));
+      if !line.text.ends_with('\n') {
+        text.push_str("\n\\ No newline at end of file");
+      }
+    }
+  }
+
+  text
+}
+
+fn get_diff_lines<'a>(file_text: &'a str, formatted_text: &'a str) -> Vec<DiffLine<'a>> {
+  let old_lines = split_lines(file_text);
+  let new_lines = split_lines(formatted_text);
+
+  // represent each unique line as a character in order to do a line based diff
+  let mut line_chars = HashMap::new();
+  let mut get_line_char = |line: &'a str| {
+    let next_index = line_chars.len() as u32;
+    *line_chars.entry(line).or_insert_with(|| index_to_char(next_index))
+  };
+  let old_chars = old_lines.iter().map(|line| get_line_char(line)).collect::<String>();
+  let new_chars = new_lines.iter().map(|line| get_line_char(line)).collect::<String>();
+
+  let mut diff_lines = Vec::new();
+  let mut old_index = 0;
+  let mut new_index = 0;
+  for chunk in dissimilar::diff(&old_chars, &new_chars) {
+    let (kind, text) = match chunk {
+      dissimilar::Chunk::Equal(text) => (DiffLineKind::Unchanged, text),
+      dissimilar::Chunk::Delete(text) => (DiffLineKind::Removed, text),
+      dissimilar::Chunk::Insert(text) => (DiffLineKind::Added, text),
+    };
+    for _ in text.chars() {
+      let text = if kind == DiffLineKind::Added {
+        new_lines[new_index]
+      } else {
+        old_lines[old_index]
+      };
+      if kind != DiffLineKind::Added {
+        old_index += 1;
+      }
+      if kind != DiffLineKind::Removed {
+        new_index += 1;
+      }
+      diff_lines.push(DiffLine { kind, text });
+    }
+  }
+  diff_lines
+}
+
+fn index_to_char(index: u32) -> char {
+  // skip over the surrogate code points, which aren't valid chars
+  let code_point = if index >= 0xD800 { index + 0x800 } else { index };
+  std::char::from_u32(code_point).unwrap()
+}
+
+fn split_lines(text: &str) -> Vec<&str> {
+  let mut lines = Vec::new();
+  let mut start = 0;
+  for (index, _) in text.match_indices('\n') {
+    lines.push(&text[start..index + 1]);
+    start = index + 1;
+  }
+  if start < text.len() {
+    lines.push(&text[start..]);
+  }
+  lines
+}
+
+/// Gets the start (inclusive) and end (exclusive) indexes of the diff lines for each hunk.
+fn get_hunk_ranges(diff_lines: &[DiffLine]) -> Vec<(usize, usize)> {
+  let mut ranges: Vec<(usize, usize)> = Vec::new();
+  for (index, line) in diff_lines.iter().enumerate() {
+    if line.kind == DiffLineKind::Unchanged {
+      continue;
+    }
+    let start = index.saturating_sub(CONTEXT_LINE_COUNT);
+    let end = std::cmp::min(index + 1 + CONTEXT_LINE_COUNT, diff_lines.len());
+    match ranges.last_mut() {
+      Some(last_range) if last_range.1 >= start => last_range.1 = end,
+      _ => ranges.push((start, end)),
+    }
+  }
+  ranges
+}
+
+/// Gets the zero-based old and new line numbers of the diff line at the provided index.
+fn get_line_numbers_at(diff_lines: &[DiffLine], index: usize) -> (usize, usize) {
+  let old_line_number = diff_lines[..index].iter().filter(|line| line.kind != DiffLineKind::Added).count();
+  let new_line_number = diff_lines[..index].iter().filter(|line| line.kind != DiffLineKind::Removed).count();
+  (old_line_number, new_line_number)
+}
+
+fn get_hunk_range_text(start: usize, count: usize) -> String {
+  match count {
+    // an empty range refers to the line before it
+    0 => format!("{},0", start),
+    1 => format!("{}", start + 1),
+    _ => format!("{},{}", start + 1, count),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_get_unified_diff() {
+    assert_eq!(
+      get_unified_diff("/file.txt", "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\n", "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n"),
+      concat!(
+        "--- /file.txt\n+++ /file.txt\n",
+        "@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n",
+        "@@ -9,3 +9,4 @@\n i\n j\n k\n+l",
+      )
+    );
+  }
+
+  #[test]
+  fn should_merge_close_changes_into_one_hunk() {
+    assert_eq!(
+      get_unified_diff("/file.txt", "a\nb\nc\nd\ne\n", "A\nb\nc\nd\nE\n"),
+      "--- /file.txt\n+++ /file.txt\n@@ -1,5 +1,5 @@\n-a\n+A\n b\n c\n d\n-e\n+E"
+    );
+  }
+
+  #[test]
+  fn should_handle_missing_newline_at_end_of_file() {
+    assert_eq!(
+      get_unified_diff("/file.txt", "a", "a\n"),
+      "--- /file.txt\n+++ /file.txt\n@@ -1 +1 @@\n-a\n\\ No newline at end of file\n+a"
+    );
+    assert_eq!(get_unified_diff("/file.txt", "", "a\n"), "--- /file.txt\n+++ /file.txt\n@@ -0,0 +1 @@\n+a");
+  }
+}
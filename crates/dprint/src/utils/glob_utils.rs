@@ -1,53 +1,135 @@
 use std::borrow::Cow;
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use dprint_cli_core::types::ErrBox;
 use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
 
 use crate::environment::{DirEntryKind, Environment};
 
-pub fn glob(environment: &impl Environment, base: impl AsRef<Path>, file_patterns: &Vec<String>) -> Result<Vec<PathBuf>, ErrBox> {
+use super::read_ignore_file_patterns;
+
+pub struct GlobOutput {
+  pub file_paths: Vec<PathBuf>,
+  /// Directories that weren't searched because they were excluded.
+  pub excluded_dir_paths: Vec<PathBuf>,
+}
+
+/// Gets the file paths matching the patterns, excluding the files matching
+/// the patterns of any ignore files (ex. `.dprintignore`) found along the way.
+pub fn glob(environment: &impl Environment, base: impl AsRef<Path>, file_patterns: &Vec<String>, ignore_file_names: &[&str]) -> Result<GlobOutput, ErrBox> {
   if file_patterns.iter().all(|p| is_negated_glob(p)) {
     // performance improvement (see issue #379)
     log_verbose!(environment, "Skipping negated globs: {:?}", file_patterns);
-    return Ok(Vec::with_capacity(0));
+    return Ok(GlobOutput {
+      file_paths: Vec::with_capacity(0),
+      excluded_dir_paths: Vec::with_capacity(0),
+    });
   }
 
   let start_instant = std::time::Instant::now();
   log_verbose!(environment, "Globbing: {:?}", file_patterns);
 
-  let glob_matcher = GlobMatcher::new(
+  let glob_matcher = Arc::new(GlobMatcher::new(
     file_patterns,
     &GlobMatcherOptions {
       case_insensitive: cfg!(windows),
     },
-  )?;
+  )?);
   let mut results = Vec::new();
+  let mut excluded_dir_paths = Vec::new();
+  let mut visited_dirs_count = 0;
+  let mut pending_dirs = vec![(base.as_ref().to_path_buf(), glob_matcher)];
+  // the global pool would use every cpu regardless of the max threads
+  let thread_pool = rayon::ThreadPoolBuilder::new().num_threads(environment.get_max_threads()).build()?;
 
-  let mut pending_dirs = vec![base.as_ref().to_path_buf()];
-
+  // read the directories of each level in parallel
   while !pending_dirs.is_empty() {
-    let entries = environment.dir_info(pending_dirs.pop().unwrap())?;
-    for entry in entries.into_iter() {
-      match entry.kind {
-        DirEntryKind::Directory => {
-          if !glob_matcher.is_ignored(&entry.path) {
-            pending_dirs.push(entry.path);
-          }
-        }
-        DirEntryKind::File => {
-          if glob_matcher.is_match(&entry.path) {
-            results.push(entry.path);
-          }
-        }
-      }
+    visited_dirs_count += pending_dirs.len();
+    let dir_matches = thread_pool.install(|| {
+      pending_dirs
+        .into_par_iter()
+        .map(|(dir_path, glob_matcher)| get_dir_matches(environment, &glob_matcher, dir_path, ignore_file_names))
+        .collect::<Result<Vec<_>, ErrBox>>()
+    })?;
+    pending_dirs = Vec::new();
+    for dir_matches in dir_matches {
+      results.extend(dir_matches.file_paths);
+      let glob_matcher = dir_matches.glob_matcher;
+      pending_dirs.extend(dir_matches.dir_paths.into_iter().map(|dir_path| (dir_path, glob_matcher.clone())));
+      excluded_dir_paths.extend(dir_matches.excluded_dir_paths);
     }
   }
 
   log_verbose!(environment, "File(s) matched: {:?}", results);
-  log_verbose!(environment, "Finished globbing in {}ms", start_instant.elapsed().as_millis());
+  log_verbose!(
+    environment,
+    "Finished globbing in {}ms. Visited {} directories.",
+    start_instant.elapsed().as_millis(),
+    visited_dirs_count
+  );
+
+  Ok(GlobOutput {
+    file_paths: results,
+    excluded_dir_paths,
+  })
+}
+
+struct DirMatches {
+  file_paths: Vec<PathBuf>,
+  /// Sub directories that aren't excluded.
+  dir_paths: Vec<PathBuf>,
+  excluded_dir_paths: Vec<PathBuf>,
+  /// Matcher for the sub directories, which includes the patterns of this directory's ignore file.
+  glob_matcher: Arc<GlobMatcher>,
+}
 
-  Ok(results)
+fn get_dir_matches(
+  environment: &impl Environment,
+  glob_matcher: &Arc<GlobMatcher>,
+  dir_path: PathBuf,
+  ignore_file_names: &[&str],
+) -> Result<DirMatches, ErrBox> {
+  let entries = environment.dir_info(&dir_path)?;
+  let has_ignore_file = entries.iter().any(|entry| {
+    matches!(entry.kind, DirEntryKind::File)
+      && entry
+        .path
+        .file_name()
+        .map(|file_name| ignore_file_names.iter().any(|name| file_name == OsStr::new(name)))
+        .unwrap_or(false)
+  });
+  let glob_matcher = if has_ignore_file {
+    Arc::new(glob_matcher.with_patterns(&read_ignore_file_patterns(&dir_path, ignore_file_names, environment)?)?)
+  } else {
+    glob_matcher.clone()
+  };
+  let mut matches = DirMatches {
+    file_paths: Vec::new(),
+    dir_paths: Vec::new(),
+    excluded_dir_paths: Vec::new(),
+    glob_matcher: glob_matcher.clone(),
+  };
+  for entry in entries {
+    match entry.kind {
+      DirEntryKind::Directory => {
+        // prune excluded directories before descending into them
+        if glob_matcher.is_ignored(&entry.path) {
+          matches.excluded_dir_paths.push(entry.path);
+        } else {
+          matches.dir_paths.push(entry.path);
+        }
+      }
+      DirEntryKind::File => {
+        if glob_matcher.is_match(&entry.path) {
+          matches.file_paths.push(entry.path);
+        }
+      }
+    }
+  }
+  Ok(matches)
 }
 
 pub fn to_absolute_globs(file_patterns: Vec<String>, base_dir: &str) -> Vec<String> {
@@ -174,34 +256,94 @@ pub struct GlobMatcherOptions {
   pub case_insensitive: bool,
 }
 
+/// Matches paths against patterns where the last matching pattern wins (like gitignore files),
+/// so a pattern may re-include files that an earlier negated pattern (ex. `!**/generated/**`) excluded.
 pub struct GlobMatcher {
-  include_globset: GlobSet,
-  exclude_globset: GlobSet,
+  patterns: Vec<String>,
+  case_insensitive: bool,
+  /// The patterns without their leading `!` in order.
+  globset: GlobSet,
+  /// If the pattern at each index of the glob set was negated.
+  negated: Vec<bool>,
+  /// Directories whose descendants are all excluded (ex. `**/target/**` or `**/target/`).
+  exclude_dir_globset: GlobSet,
+  /// The index in the patterns of each pattern in the exclude directory glob set.
+  exclude_dir_pattern_indexes: Vec<usize>,
+  /// Index of the last pattern that isn't negated.
+  last_include_index: Option<usize>,
 }
 
 impl GlobMatcher {
   pub fn new(patterns: &[String], opts: &GlobMatcherOptions) -> Result<GlobMatcher, ErrBox> {
-    let mut match_patterns = Vec::new();
-    let mut ignore_patterns = Vec::new();
-    for pattern in patterns {
+    let mut match_patterns = Vec::with_capacity(patterns.len());
+    let mut negated = Vec::with_capacity(patterns.len());
+    let mut ignore_dir_patterns = Vec::new();
+    let mut exclude_dir_pattern_indexes = Vec::new();
+    let mut last_include_index = None;
+    for (index, pattern) in patterns.iter().enumerate() {
       if is_negated_glob(pattern) {
-        ignore_patterns.push(pattern[1..].to_string());
+        let pattern = &pattern[1..];
+        if let Some(dir_pattern) = pattern.strip_suffix("/**").or_else(|| pattern.strip_suffix('/')) {
+          ignore_dir_patterns.push(dir_pattern.to_string());
+          exclude_dir_pattern_indexes.push(index);
+        }
+        match_patterns.push(pattern.to_string());
+        negated.push(true);
       } else {
         match_patterns.push(pattern.to_string());
+        negated.push(false);
+        last_include_index = Some(index);
       }
     }
     Ok(GlobMatcher {
-      include_globset: build_glob_set(&match_patterns, opts)?,
-      exclude_globset: build_glob_set(&ignore_patterns, opts)?,
+      patterns: patterns.to_vec(),
+      case_insensitive: opts.case_insensitive,
+      globset: build_glob_set(&match_patterns, opts)?,
+      negated,
+      exclude_dir_globset: build_glob_set(&ignore_dir_patterns, opts)?,
+      exclude_dir_pattern_indexes,
+      last_include_index,
     })
   }
 
+  /// Creates a matcher with the patterns of this matcher and the provided patterns.
+  pub fn with_patterns(&self, patterns: &[String]) -> Result<GlobMatcher, ErrBox> {
+    let mut all_patterns = self.patterns.clone();
+    all_patterns.extend(patterns.iter().cloned());
+    GlobMatcher::new(
+      &all_patterns,
+      &GlobMatcherOptions {
+        case_insensitive: self.case_insensitive,
+      },
+    )
+  }
+
   pub fn is_match(&self, pattern: impl AsRef<Path>) -> bool {
-    self.include_globset.is_match(&pattern) && !self.exclude_globset.is_match(&pattern)
+    match self.get_last_match_index(&pattern) {
+      Some(index) => !self.negated[index],
+      None => false,
+    }
   }
 
+  /// Gets if the directory is excluded, meaning its descendants don't need to be searched.
   pub fn is_ignored(&self, pattern: impl AsRef<Path>) -> bool {
-    self.exclude_globset.is_match(&pattern)
+    // like gitignore files, the descendants of an excluded directory can't be re-included
+    if let Some(index) = self.get_last_match_index(&pattern) {
+      if self.negated[index] {
+        return true;
+      }
+    }
+    // the descendants are all excluded unless a later pattern may re-include some of them
+    self
+      .exclude_dir_globset
+      .matches(&pattern)
+      .into_iter()
+      .map(|match_index| self.exclude_dir_pattern_indexes[match_index])
+      .any(|index| self.last_include_index.map(|include_index| include_index < index).unwrap_or(true))
+  }
+
+  fn get_last_match_index(&self, pattern: impl AsRef<Path>) -> Option<usize> {
+    self.globset.matches(pattern.as_ref()).into_iter().max()
   }
 }
 
@@ -261,4 +403,54 @@ mod tests {
     // has a slash in the middle, so it's relative
     assert_eq!(to_absolute_glob("test/test.ts", "/test/"), "/test/test/test.ts");
   }
+
+  #[test]
+  fn it_should_ignore_dirs_whose_descendants_are_excluded() {
+    let patterns = vec![
+      "/test/**/*.ts".to_string(),
+      "!/test/**/target/**".to_string(),
+      "!/test/**/out/".to_string(),
+      "!/test/**/dist/*.ts".to_string(),
+    ];
+    let glob_matcher = GlobMatcher::new(&patterns, &GlobMatcherOptions { case_insensitive: false }).unwrap();
+    assert_eq!(glob_matcher.is_ignored("/test/target"), true);
+    assert_eq!(glob_matcher.is_ignored("/test/sub/target"), true);
+    assert_eq!(glob_matcher.is_ignored("/test/out"), true);
+    // only some descendants are excluded
+    assert_eq!(glob_matcher.is_ignored("/test/dist"), false);
+    assert_eq!(glob_matcher.is_ignored("/test/src"), false);
+    assert_eq!(glob_matcher.is_match("/test/target/file.ts"), false);
+    assert_eq!(glob_matcher.is_match("/test/dist/file.ts"), false);
+    assert_eq!(glob_matcher.is_match("/test/src/file.ts"), true);
+  }
+
+  #[test]
+  fn it_should_use_last_matching_pattern() {
+    let patterns = vec![
+      "/test/**/*.ts".to_string(),
+      "!/test/generated/**".to_string(),
+      "/test/generated/keep.ts".to_string(),
+      "!/test/**/*.d.ts".to_string(),
+    ];
+    let glob_matcher = GlobMatcher::new(&patterns, &GlobMatcherOptions { case_insensitive: false }).unwrap();
+    assert_eq!(glob_matcher.is_match("/test/src/file.ts"), true);
+    assert_eq!(glob_matcher.is_match("/test/src/file.d.ts"), false);
+    assert_eq!(glob_matcher.is_match("/test/generated/file.ts"), false);
+    assert_eq!(glob_matcher.is_match("/test/generated/keep.ts"), true);
+    assert_eq!(glob_matcher.is_match("/test/file.js"), false);
+    // a later pattern may re-include a descendant, so the directory needs to be searched
+    assert_eq!(glob_matcher.is_ignored("/test/generated"), false);
+  }
+
+  #[test]
+  fn it_should_not_re_include_descendants_of_excluded_dir() {
+    let patterns = vec![
+      "/test/**/*.ts".to_string(),
+      "!/test/**/generated".to_string(),
+      "/test/generated/keep.ts".to_string(),
+    ];
+    let glob_matcher = GlobMatcher::new(&patterns, &GlobMatcherOptions { case_insensitive: false }).unwrap();
+    assert_eq!(glob_matcher.is_ignored("/test/generated"), true);
+    assert_eq!(glob_matcher.is_ignored("/test/src"), false);
+  }
 }
@@ -0,0 +1,129 @@
+use std::path::Path;
+
+use dprint_cli_core::types::ErrBox;
+
+use crate::environment::Environment;
+
+use super::to_absolute_glob;
+
+/// Name of the file with gitignore-style patterns of the files to exclude
+/// in the directory it's in and its descendants.
+pub const IGNORE_FILE_NAME: &str = ".dprintignore";
+/// Name of the ignore file used by other tools (ex. ripgrep), which is only
+/// read when `"useDotIgnoreFiles": true` is specified.
+pub const DOT_IGNORE_FILE_NAME: &str = ".ignore";
+
+/// Gets the names of the ignore files to read in each directory.
+pub fn get_ignore_file_names(use_dot_ignore_files: bool) -> &'static [&'static str] {
+  if use_dot_ignore_files {
+    &[IGNORE_FILE_NAME, DOT_IGNORE_FILE_NAME]
+  } else {
+    &[IGNORE_FILE_NAME]
+  }
+}
+
+/// Reads the patterns of the ignore files in the directory as absolute exclude
+/// glob patterns (ex. `!/dir/**/file.ts`). Returns no patterns when the files don't exist.
+pub fn read_ignore_file_patterns(dir_path: &Path, ignore_file_names: &[&str], environment: &impl Environment) -> Result<Vec<String>, ErrBox> {
+  let mut patterns = Vec::new();
+  for ignore_file_name in ignore_file_names {
+    let file_path = dir_path.join(ignore_file_name);
+    if environment.path_exists(&file_path) {
+      patterns.extend(read_ignore_file(&file_path, dir_path, environment)?);
+    }
+  }
+  Ok(patterns)
+}
+
+fn read_ignore_file(file_path: &Path, dir_path: &Path, environment: &impl Environment) -> Result<Vec<String>, ErrBox> {
+  log_verbose!(environment, "Reading ignore file: {}", file_path.display());
+  let file_text = environment.read_file(&file_path)?;
+  let mut patterns = Vec::new();
+  for pattern in get_ignore_file_patterns(&file_text) {
+    if pattern.starts_with('!') {
+      environment.log_warning(&format!(
+        "Warning: Negated patterns are not supported in {}. Ignoring: {}",
+        file_path.display(),
+        pattern
+      ));
+    } else {
+      patterns.push(format!("!{}", to_absolute_glob(&pattern, &dir_path.to_string_lossy())));
+    }
+  }
+  Ok(patterns)
+}
+
+/// Gets the patterns of the text of an ignore file relative to its directory.
+fn get_ignore_file_patterns(file_text: &str) -> Vec<String> {
+  let mut patterns = Vec::new();
+  for line in file_text.lines() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    // a backslash escapes a leading # that isn't a comment
+    let pattern = line
+      .strip_prefix("\\#")
+      .map(|pattern| format!("#{}", pattern))
+      .unwrap_or_else(|| line.to_string());
+    let pattern = pattern.replace("\\", "/");
+    // patterns starting with a slash are relative to the directory of the ignore file
+    patterns.push(match pattern.strip_prefix('/') {
+      Some(pattern) => format!("./{}", pattern),
+      None => pattern,
+    });
+  }
+  patterns
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::environment::TestEnvironment;
+
+  #[test]
+  fn it_should_get_ignore_file_patterns() {
+    let patterns = get_ignore_file_patterns("# comment\n\n*.generated.ts\n/build\nsub/dir/\n\\#file.ts\n!keep.ts\r\n");
+    assert_eq!(patterns, vec!["*.generated.ts", "./build", "sub/dir/", "#file.ts", "!keep.ts"]);
+  }
+
+  #[test]
+  fn it_should_read_ignore_file_patterns_as_absolute_excludes() {
+    let environment = TestEnvironment::new();
+    environment
+      .write_file("/project/.dprintignore", "*.generated.ts\n/build\nsub/dir/\n!keep.ts")
+      .unwrap();
+    let patterns = read_ignore_file_patterns(&Path::new("/project"), &[IGNORE_FILE_NAME], &environment).unwrap();
+    assert_eq!(patterns, vec!["!/project/**/*.generated.ts", "!/project/build", "!/project/sub/dir/"]);
+    assert_eq!(
+      environment.take_logged_errors(),
+      vec!["Warning: Negated patterns are not supported in /project/.dprintignore. Ignoring: !keep.ts"]
+    );
+  }
+
+  #[test]
+  fn it_should_read_patterns_of_each_ignore_file() {
+    let environment = TestEnvironment::new();
+    environment.write_file("/project/.dprintignore", "*.generated.ts").unwrap();
+    environment.write_file("/project/.ignore", "/build").unwrap();
+    assert_eq!(
+      read_ignore_file_patterns(&Path::new("/project"), get_ignore_file_names(false), &environment).unwrap(),
+      vec!["!/project/**/*.generated.ts"]
+    );
+    assert_eq!(
+      read_ignore_file_patterns(&Path::new("/project"), get_ignore_file_names(true), &environment).unwrap(),
+      vec!["!/project/**/*.generated.ts", "!/project/build"]
+    );
+  }
+
+  #[test]
+  fn it_should_not_get_patterns_when_no_ignore_file() {
+    let environment = TestEnvironment::new();
+    assert_eq!(
+      read_ignore_file_patterns(&Path::new("/project"), &[IGNORE_FILE_NAME], &environment)
+        .unwrap()
+        .len(),
+      0
+    );
+  }
+}
@@ -0,0 +1,131 @@
+use dprint_core::configuration::{resolve_new_line_kind, NewLineKind};
+use std::borrow::Cow;
+
+/// What to do with a file that contains both CRLF and LF line endings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MixedLineEndingsPolicy {
+  /// Leave the line endings to the plugin.
+  Ignore,
+  /// Report the file when checking and leave the line endings to the plugin when formatting.
+  Report,
+  /// Normalize the line endings to LF before formatting.
+  Lf,
+  /// Normalize the line endings to CRLF before formatting.
+  Crlf,
+  /// Normalize the line endings to the line ending used most in the file before formatting.
+  Auto,
+}
+
+impl MixedLineEndingsPolicy {
+  pub fn parse(text: &str) -> Option<MixedLineEndingsPolicy> {
+    match text {
+      "ignore" => Some(MixedLineEndingsPolicy::Ignore),
+      "report" => Some(MixedLineEndingsPolicy::Report),
+      "lf" => Some(MixedLineEndingsPolicy::Lf),
+      "crlf" => Some(MixedLineEndingsPolicy::Crlf),
+      "auto" => Some(MixedLineEndingsPolicy::Auto),
+      _ => None,
+    }
+  }
+}
+
+/// The number of each kind of line ending in a text.
+#[derive(Debug, PartialEq)]
+pub struct LineEndingCounts {
+  pub crlf: usize,
+  pub lf: usize,
+}
+
+impl LineEndingCounts {
+  pub fn new(text: &str) -> LineEndingCounts {
+    let bytes = text.as_bytes();
+    let mut counts = LineEndingCounts { crlf: 0, lf: 0 };
+    for (i, byte) in bytes.iter().enumerate() {
+      if *byte == b'\n' {
+        if i > 0 && bytes[i - 1] == b'\r' {
+          counts.crlf += 1;
+        } else {
+          counts.lf += 1;
+        }
+      }
+    }
+    counts
+  }
+
+  pub fn is_mixed(&self) -> bool {
+    self.crlf > 0 && self.lf > 0
+  }
+}
+
+/// Normalizes the line endings of text that has mixed line endings according to the policy.
+pub fn normalize_mixed_line_endings(text: &str, policy: MixedLineEndingsPolicy) -> Cow<str> {
+  let counts = LineEndingCounts::new(text);
+  if !counts.is_mixed() {
+    return Cow::Borrowed(text);
+  }
+
+  let use_crlf = match policy {
+    MixedLineEndingsPolicy::Ignore | MixedLineEndingsPolicy::Report => return Cow::Borrowed(text),
+    MixedLineEndingsPolicy::Lf => false,
+    MixedLineEndingsPolicy::Crlf => true,
+    MixedLineEndingsPolicy::Auto => counts.crlf > counts.lf,
+  };
+  let text = text.replace("\r\n", "\n");
+  if use_crlf {
+    Cow::Owned(text.replace("\n", "\r\n"))
+  } else {
+    Cow::Owned(text)
+  }
+}
+
+/// Converts the line endings of the formatted text to the new line kind. The `auto` kind
+/// uses the line ending of the last line in the original text.
+pub fn apply_new_line_kind(original_text: &str, formatted_text: String, new_line_kind: NewLineKind) -> String {
+  let counts = LineEndingCounts::new(&formatted_text);
+  match resolve_new_line_kind(original_text, new_line_kind) {
+    "\r\n" if counts.lf > 0 => formatted_text.replace("\r\n", "\n").replace("\n", "\r\n"),
+    "\n" if counts.crlf > 0 => formatted_text.replace("\r\n", "\n"),
+    _ => formatted_text,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_get_line_ending_counts() {
+    assert_eq!(LineEndingCounts::new(""), LineEndingCounts { crlf: 0, lf: 0 });
+    assert_eq!(LineEndingCounts::new("a\r\nb\nc\r\n\n"), LineEndingCounts { crlf: 2, lf: 2 });
+    assert_eq!(LineEndingCounts::new("\na\r"), LineEndingCounts { crlf: 0, lf: 1 });
+  }
+
+  #[test]
+  fn should_normalize_mixed_line_endings() {
+    let text = "a\r\nb\nc\r\n";
+    assert_eq!(normalize_mixed_line_endings(text, MixedLineEndingsPolicy::Ignore), text);
+    assert_eq!(normalize_mixed_line_endings(text, MixedLineEndingsPolicy::Report), text);
+    assert_eq!(normalize_mixed_line_endings(text, MixedLineEndingsPolicy::Lf), "a\nb\nc\n");
+    assert_eq!(normalize_mixed_line_endings(text, MixedLineEndingsPolicy::Crlf), "a\r\nb\r\nc\r\n");
+    assert_eq!(normalize_mixed_line_endings(text, MixedLineEndingsPolicy::Auto), "a\r\nb\r\nc\r\n");
+    assert_eq!(normalize_mixed_line_endings("a\nb\r\nc\n", MixedLineEndingsPolicy::Auto), "a\nb\nc\n");
+    // not mixed
+    assert_eq!(normalize_mixed_line_endings("a\r\nb\r\n", MixedLineEndingsPolicy::Lf), "a\r\nb\r\n");
+  }
+
+  #[test]
+  fn should_apply_new_line_kind() {
+    assert_eq!(apply_new_line_kind("", "a\r\nb\n".to_string(), NewLineKind::LineFeed), "a\nb\n");
+    assert_eq!(
+      apply_new_line_kind("", "a\r\nb\n".to_string(), NewLineKind::CarriageReturnLineFeed),
+      "a\r\nb\r\n"
+    );
+    assert_eq!(
+      apply_new_line_kind("", "a\r\nb\r\n".to_string(), NewLineKind::CarriageReturnLineFeed),
+      "a\r\nb\r\n"
+    );
+    // auto uses the original text's line endings
+    assert_eq!(apply_new_line_kind("a\r\nb", "a\nb\n".to_string(), NewLineKind::Auto), "a\r\nb\r\n");
+    assert_eq!(apply_new_line_kind("a\nb", "a\r\nb\r\n".to_string(), NewLineKind::Auto), "a\nb\n");
+  }
+}
@@ -0,0 +1,41 @@
+/// A line in a file that is too long to reasonably format.
+#[derive(Debug, PartialEq)]
+pub struct LongLine {
+  /// The 1-indexed line number.
+  pub line_number: usize,
+  /// The length of the line in bytes.
+  pub byte_len: usize,
+}
+
+/// Gets the first line in the text that is longer than the provided number of bytes.
+///
+/// This only scans the bytes of the text once, so it's cheap to call before formatting.
+pub fn get_long_line(text: &str, max_byte_len: usize) -> Option<LongLine> {
+  if text.len() <= max_byte_len {
+    return None;
+  }
+
+  for (index, line) in text.as_bytes().split(|b| *b == b'\n').enumerate() {
+    if line.len() > max_byte_len {
+      return Some(LongLine {
+        line_number: index + 1,
+        byte_len: line.len(),
+      });
+    }
+  }
+
+  None
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_get_long_line() {
+    assert_eq!(get_long_line("", 5), None);
+    assert_eq!(get_long_line("12345\n12345", 5), None);
+    assert_eq!(get_long_line("12345\n123456\n1234567", 5), Some(LongLine { line_number: 2, byte_len: 6 }));
+    assert_eq!(get_long_line("123456", 5), Some(LongLine { line_number: 1, byte_len: 6 }));
+  }
+}
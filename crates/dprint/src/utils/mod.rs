@@ -1,27 +1,55 @@
+mod archive;
+mod changed_regions;
+mod code_owners;
+mod crash_report;
 mod error_count_logger;
 mod extract_zip;
+mod file_boundaries;
 mod file_path_utils;
 mod file_text;
 mod get_bytes_hash;
 mod get_difference;
+mod get_unified_diff;
 mod glob_utils;
+mod ignore_file;
+mod line_endings;
+mod long_line;
 mod path_source;
+mod phase_timings;
 mod pretty_print_json_text;
+mod profiler;
 mod reset_events;
 mod resolve_url_or_file_path;
 mod table_text;
+mod text_encoding;
 mod thread_exit_signal;
+mod timestamp;
+mod warning_count;
 
+pub use archive::*;
+pub use changed_regions::*;
+pub use code_owners::*;
+pub use crash_report::*;
 pub use error_count_logger::*;
 pub use extract_zip::*;
+pub use file_boundaries::*;
 pub use file_path_utils::*;
 pub use file_text::*;
 pub use get_bytes_hash::*;
 pub use get_difference::*;
+pub use get_unified_diff::*;
 pub use glob_utils::*;
+pub use ignore_file::*;
+pub use line_endings::*;
+pub use long_line::*;
 pub use path_source::*;
+pub use phase_timings::*;
 pub use pretty_print_json_text::*;
+pub use profiler::*;
 pub use reset_events::*;
 pub use resolve_url_or_file_path::*;
 pub use table_text::*;
+pub use text_encoding::*;
 pub use thread_exit_signal::*;
+pub use timestamp::*;
+pub use warning_count::*;
@@ -64,7 +64,31 @@ impl PathSource {
   }
 
   pub fn is_wasm_plugin(&self) -> bool {
-    self.display().to_lowercase().ends_with(".wasm")
+    self.is_oci_plugin() || self.is_npm_plugin() || self.is_crate_plugin() || self.display().to_lowercase().ends_with(".wasm")
+  }
+
+  /// Gets if this is a Wasm plugin stored in an OCI registry (ex. `oci://ghcr.io/org/plugin:1.0.0`).
+  pub fn is_oci_plugin(&self) -> bool {
+    match self {
+      PathSource::Remote(remote) => remote.url.scheme() == "oci",
+      PathSource::Local(_) => false,
+    }
+  }
+
+  /// Gets if this is a Wasm plugin in an npm package (ex. `npm:@scope/plugin@1.2.3`).
+  pub fn is_npm_plugin(&self) -> bool {
+    match self {
+      PathSource::Remote(remote) => remote.url.scheme() == "npm",
+      PathSource::Local(_) => false,
+    }
+  }
+
+  /// Gets if this is a Wasm plugin in a crate on crates.io (ex. `crate:dprint-plugin-foo@0.3`).
+  pub fn is_crate_plugin(&self) -> bool {
+    match self {
+      PathSource::Remote(remote) => remote.url.scheme() == "crate",
+      PathSource::Local(_) => false,
+    }
   }
 
   pub fn is_process_plugin(&self) -> bool {
@@ -94,6 +118,31 @@ mod tests {
     assert_eq!(parent, PathSource::new_remote(Url::parse("https://dprint.dev/test/").unwrap()))
   }
 
+  #[test]
+  fn it_should_get_if_oci_plugin() {
+    let source = PathSource::new_remote(Url::parse("oci://ghcr.io/org/plugin:1.0.0").unwrap());
+    assert!(source.is_oci_plugin());
+    assert!(source.is_wasm_plugin());
+    assert!(!source.is_process_plugin());
+    assert!(!PathSource::new_remote(Url::parse("https://dprint.dev/plugin.wasm").unwrap()).is_oci_plugin());
+  }
+
+  #[test]
+  fn it_should_get_if_npm_plugin() {
+    let source = PathSource::new_remote(Url::parse("npm:@scope/plugin@1.2.3").unwrap());
+    assert!(source.is_npm_plugin());
+    assert!(source.is_wasm_plugin());
+    assert_eq!(source.display(), "npm:@scope/plugin@1.2.3");
+  }
+
+  #[test]
+  fn it_should_get_if_crate_plugin() {
+    let source = PathSource::new_remote(Url::parse("crate:dprint-plugin-foo@0.3").unwrap());
+    assert!(source.is_crate_plugin());
+    assert!(source.is_wasm_plugin());
+    assert!(!source.is_npm_plugin());
+  }
+
   #[test]
   fn it_should_get_parent_for_file_path() {
     let source = PathSource::new_local(PathBuf::from("/test/test/asdf.json"));
@@ -0,0 +1,117 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use super::Profiler;
+
+/// A phase of a run whose duration is output in the verbose output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+  ConfigResolution,
+  PluginDownload,
+  PluginInstantiation,
+  FileGlobbing,
+  Formatting,
+  Writing,
+}
+
+const PHASES: [Phase; 6] = [
+  Phase::ConfigResolution,
+  Phase::PluginDownload,
+  Phase::PluginInstantiation,
+  Phase::FileGlobbing,
+  Phase::Formatting,
+  Phase::Writing,
+];
+
+impl Phase {
+  fn name(&self) -> &'static str {
+    match self {
+      Phase::ConfigResolution => "config resolution",
+      Phase::PluginDownload => "plugin download",
+      Phase::PluginInstantiation => "plugin instantiation",
+      Phase::FileGlobbing => "file globbing",
+      Phase::Formatting => "formatting",
+      Phase::Writing => "writing",
+    }
+  }
+
+  /// Gets if the phase happens on multiple threads at the same time, so its duration is the sum of each thread's duration.
+  fn is_summed_across_threads(&self) -> bool {
+    matches!(self, Phase::PluginInstantiation | Phase::Writing)
+  }
+
+  fn index(&self) -> usize {
+    PHASES.iter().position(|phase| phase == self).unwrap()
+  }
+}
+
+/// Accumulates the time spent in each phase of a run.
+#[derive(Default)]
+pub struct PhaseTimings {
+  micros: [AtomicU64; PHASES.len()],
+  measure_counts: [AtomicU64; PHASES.len()],
+  profiler: Profiler,
+}
+
+impl PhaseTimings {
+  pub fn add(&self, phase: Phase, duration: Duration) {
+    self.micros[phase.index()].fetch_add(duration.as_micros() as u64, Ordering::SeqCst);
+    self.measure_counts[phase.index()].fetch_add(1, Ordering::SeqCst);
+  }
+
+  /// Adds the time since the start instant to the phase and records it in the profile when profiling.
+  pub fn add_since(&self, phase: Phase, start_instant: Instant) {
+    self.add(phase, start_instant.elapsed());
+    self.profiler.add_span(|| phase.name().to_string(), start_instant);
+  }
+
+  pub fn measure<TResult>(&self, phase: Phase, action: impl FnOnce() -> TResult) -> TResult {
+    let start_instant = Instant::now();
+    let result = action();
+    self.add_since(phase, start_instant);
+    result
+  }
+
+  /// Gets the profiler that records the phases along with any other spans for `dprint fmt --profile`.
+  pub fn profiler(&self) -> &Profiler {
+    &self.profiler
+  }
+
+  /// Gets the text describing the duration of each phase or `None` when nothing was measured.
+  pub fn get_text(&self) -> Option<String> {
+    let lines = PHASES
+      .iter()
+      .filter(|phase| self.measure_counts[phase.index()].load(Ordering::SeqCst) > 0)
+      .map(|phase| {
+        let millis = self.micros[phase.index()].load(Ordering::SeqCst) / 1000;
+        let suffix = if phase.is_summed_across_threads() { " (summed across threads)" } else { "" };
+        format!("  {}: {}ms{}", phase.name(), millis, suffix)
+      })
+      .collect::<Vec<_>>();
+    if lines.is_empty() {
+      None
+    } else {
+      Some(format!("Phase timings:\n{}", lines.join("\n")))
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn should_get_text_of_measured_phases() {
+    let timings = PhaseTimings::default();
+    assert_eq!(timings.get_text(), None);
+    timings.add(Phase::ConfigResolution, Duration::from_micros(2500));
+    timings.add(Phase::Writing, Duration::from_millis(3));
+    timings.add(Phase::Writing, Duration::from_millis(4));
+    assert_eq!(timings.measure(Phase::FileGlobbing, || 5), 5);
+    assert_eq!(
+      timings.get_text().unwrap(),
+      "Phase timings:\n  config resolution: 2ms\n  file globbing: 0ms\n  writing: 7ms (summed across threads)"
+    );
+  }
+}
@@ -0,0 +1,241 @@
+use dprint_core::types::ErrBox;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/// Records spans of time on each thread for `dprint fmt --profile`.
+///
+/// Nothing is recorded until the profiler is enabled.
+pub struct Profiler {
+  start_instant: Instant,
+  is_enabled: AtomicBool,
+  spans: Mutex<Vec<ProfileSpan>>,
+}
+
+struct ProfileSpan {
+  name: String,
+  thread_name: String,
+  start_micros: u64,
+  end_micros: u64,
+}
+
+impl Default for Profiler {
+  fn default() -> Self {
+    Profiler {
+      start_instant: Instant::now(),
+      is_enabled: AtomicBool::new(false),
+      spans: Mutex::new(Vec::new()),
+    }
+  }
+}
+
+impl Profiler {
+  pub fn enable(&self) {
+    self.is_enabled.store(true, Ordering::SeqCst);
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.is_enabled.load(Ordering::SeqCst)
+  }
+
+  /// Records a span on the current thread from the start instant until now. The name
+  /// is only created when the profiler is enabled.
+  pub fn add_span(&self, get_name: impl FnOnce() -> String, start_instant: Instant) {
+    if !self.is_enabled() {
+      return;
+    }
+    let current_thread = std::thread::current();
+    let thread_name = match current_thread.name() {
+      Some(name) => name.to_string(),
+      None => format!("{:?}", current_thread.id()),
+    };
+    let span = ProfileSpan {
+      name: get_name(),
+      thread_name,
+      start_micros: self.get_micros_since_start(start_instant),
+      end_micros: self.get_micros_since_start(Instant::now()),
+    };
+    self.spans.lock().push(span);
+  }
+
+  fn get_micros_since_start(&self, instant: Instant) -> u64 {
+    instant.saturating_duration_since(self.start_instant).as_micros() as u64
+  }
+
+  /// Gets the recorded spans as a speedscope file (https://www.speedscope.app) with
+  /// an evented profile for each thread.
+  pub fn get_speedscope_text(&self, name: &str) -> Result<String, ErrBox> {
+    let spans = self.spans.lock();
+    let mut frames = Vec::new();
+    let mut frame_indexes = HashMap::new();
+    let mut spans_by_thread: Vec<(&str, Vec<&ProfileSpan>)> = Vec::new();
+    for span in spans.iter() {
+      if !frame_indexes.contains_key(span.name.as_str()) {
+        frame_indexes.insert(span.name.as_str(), frames.len());
+        frames.push(SpeedscopeFrame { name: &span.name });
+      }
+      match spans_by_thread.iter_mut().find(|(thread_name, _)| *thread_name == span.thread_name) {
+        Some((_, thread_spans)) => thread_spans.push(span),
+        None => spans_by_thread.push((&span.thread_name, vec![span])),
+      }
+    }
+
+    let profiles = spans_by_thread
+      .into_iter()
+      .map(|(thread_name, mut thread_spans)| {
+        // outer spans first so that spans measured within them are nested
+        thread_spans.sort_by(|a, b| a.start_micros.cmp(&b.start_micros).then(b.end_micros.cmp(&a.end_micros)));
+        let mut events = Vec::new();
+        let mut open_spans: Vec<(usize, u64)> = Vec::new();
+        for span in thread_spans.iter() {
+          close_spans_ending_before(&mut open_spans, &mut events, span.start_micros);
+          // keep the span within its parent in case they were measured separately
+          let end_micros = match open_spans.last() {
+            Some((_, parent_end_micros)) => std::cmp::min(span.end_micros, *parent_end_micros),
+            None => span.end_micros,
+          };
+          let frame = frame_indexes[span.name.as_str()];
+          events.push(SpeedscopeEvent {
+            kind: "O",
+            frame,
+            at: span.start_micros,
+          });
+          open_spans.push((frame, end_micros));
+        }
+        close_spans_ending_before(&mut open_spans, &mut events, u64::MAX);
+        SpeedscopeProfile {
+          kind: "evented",
+          name: thread_name,
+          unit: "microseconds",
+          start_value: thread_spans.first().map(|span| span.start_micros).unwrap_or(0),
+          end_value: events.last().map(|event| event.at).unwrap_or(0),
+          events,
+        }
+      })
+      .collect();
+
+    Ok(serde_json::to_string(&SpeedscopeFile {
+      schema: "https://www.speedscope.app/file-format-schema.json",
+      name,
+      exporter: concat!("dprint ", env!("CARGO_PKG_VERSION")),
+      shared: SpeedscopeShared { frames },
+      profiles,
+    })?)
+  }
+}
+
+fn close_spans_ending_before(open_spans: &mut Vec<(usize, u64)>, events: &mut Vec<SpeedscopeEvent>, micros: u64) {
+  while let Some((frame, end_micros)) = open_spans.last().copied() {
+    if end_micros > micros {
+      break;
+    }
+    open_spans.pop();
+    events.push(SpeedscopeEvent {
+      kind: "C",
+      frame,
+      at: end_micros,
+    });
+  }
+}
+
+#[derive(Serialize)]
+struct SpeedscopeFile<'a> {
+  #[serde(rename = "$schema")]
+  schema: &'static str,
+  name: &'a str,
+  exporter: &'static str,
+  shared: SpeedscopeShared<'a>,
+  profiles: Vec<SpeedscopeProfile<'a>>,
+}
+
+#[derive(Serialize)]
+struct SpeedscopeShared<'a> {
+  frames: Vec<SpeedscopeFrame<'a>>,
+}
+
+#[derive(Serialize)]
+struct SpeedscopeFrame<'a> {
+  name: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpeedscopeProfile<'a> {
+  #[serde(rename = "type")]
+  kind: &'static str,
+  name: &'a str,
+  unit: &'static str,
+  start_value: u64,
+  end_value: u64,
+  events: Vec<SpeedscopeEvent>,
+}
+
+#[derive(Serialize)]
+struct SpeedscopeEvent {
+  #[serde(rename = "type")]
+  kind: &'static str,
+  frame: usize,
+  at: u64,
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use pretty_assertions::assert_eq;
+  use serde_json::Value;
+
+  #[test]
+  fn should_only_record_spans_when_enabled() {
+    let profiler = Profiler::default();
+    profiler.add_span(|| unreachable!(), Instant::now());
+    assert_eq!(profiler.spans.lock().len(), 0);
+    profiler.enable();
+    profiler.add_span(|| "span".to_string(), Instant::now());
+    assert_eq!(profiler.spans.lock().len(), 1);
+  }
+
+  #[test]
+  fn should_get_speedscope_text_with_nested_events() {
+    let profiler = Profiler::default();
+    let add_span = |name: &str, start_micros: u64, end_micros: u64| {
+      profiler.spans.lock().push(ProfileSpan {
+        name: name.to_string(),
+        thread_name: "main".to_string(),
+        start_micros,
+        end_micros,
+      });
+    };
+    // recorded in the order the spans end
+    add_span("format (plugin)", 20, 40);
+    add_span("/file.txt", 10, 60);
+    add_span("format (plugin)", 70, 80);
+    add_span("/other.txt", 70, 90);
+    add_span("formatting", 5, 100);
+
+    let value: Value = serde_json::from_str(&profiler.get_speedscope_text("dprint fmt").unwrap()).unwrap();
+    assert_eq!(value["$schema"], "https://www.speedscope.app/file-format-schema.json");
+    assert_eq!(value["name"], "dprint fmt");
+    assert_eq!(
+      value["shared"]["frames"],
+      serde_json::json!([{ "name": "format (plugin)" }, { "name": "/file.txt" }, { "name": "/other.txt" }, { "name": "formatting" }])
+    );
+    let profile = &value["profiles"][0];
+    assert_eq!(profile["type"], "evented");
+    assert_eq!(profile["name"], "main");
+    assert_eq!(profile["unit"], "microseconds");
+    assert_eq!(profile["startValue"], 5);
+    assert_eq!(profile["endValue"], 100);
+    let events = profile["events"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|event| format!("{}{}@{}", event["type"].as_str().unwrap(), event["frame"], event["at"]))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      events,
+      vec!["O3@5", "O1@10", "O0@20", "C0@40", "C1@60", "O2@70", "O0@70", "C0@80", "C2@90", "C3@100"]
+    );
+  }
+}
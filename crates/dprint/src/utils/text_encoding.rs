@@ -0,0 +1,214 @@
+use std::path::Path;
+
+use dprint_core::types::ErrBox;
+
+use super::{to_absolute_globs, GlobMatcher, GlobMatcherOptions};
+
+/// The characters of bytes 0x80 to 0x9F in windows-1252. The other bytes map to the same code point.
+const WINDOWS_1252_HIGH_CHARS: [char; 32] = [
+  '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}', '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}',
+  '\u{008D}', '\u{017D}', '\u{008F}', '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}', '\u{02DC}', '\u{2122}',
+  '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// The encoding of the text of a file on the file system.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextEncoding {
+  Utf8,
+  Utf16Le,
+  Utf16Be,
+  Windows1252,
+  Iso8859_1,
+}
+
+impl TextEncoding {
+  pub fn parse(text: &str) -> Option<TextEncoding> {
+    match text.to_lowercase().as_str() {
+      "utf-8" | "utf8" => Some(TextEncoding::Utf8),
+      "utf-16le" => Some(TextEncoding::Utf16Le),
+      "utf-16be" => Some(TextEncoding::Utf16Be),
+      "windows-1252" | "cp1252" => Some(TextEncoding::Windows1252),
+      "iso-8859-1" | "latin1" => Some(TextEncoding::Iso8859_1),
+      _ => None,
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      TextEncoding::Utf8 => "utf-8",
+      TextEncoding::Utf16Le => "utf-16le",
+      TextEncoding::Utf16Be => "utf-16be",
+      TextEncoding::Windows1252 => "windows-1252",
+      TextEncoding::Iso8859_1 => "iso-8859-1",
+    }
+  }
+
+  /// Decodes the bytes of a file. A byte order mark is kept as the BOM character.
+  pub fn decode(&self, bytes: &[u8]) -> Result<String, ErrBox> {
+    match self {
+      TextEncoding::Utf8 => Ok(String::from_utf8(bytes.to_vec())?),
+      TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+        if bytes.len() % 2 != 0 {
+          return err!("Expected an even number of bytes for {} text.", self.name());
+        }
+        let is_le = *self == TextEncoding::Utf16Le;
+        let units = bytes.chunks(2).map(|pair| {
+          if is_le {
+            u16::from_le_bytes([pair[0], pair[1]])
+          } else {
+            u16::from_be_bytes([pair[0], pair[1]])
+          }
+        });
+        match std::char::decode_utf16(units).collect::<Result<String, _>>() {
+          Ok(text) => Ok(text),
+          Err(err) => err!("Invalid {} text. {}", self.name(), err),
+        }
+      }
+      TextEncoding::Windows1252 => Ok(
+        bytes
+          .iter()
+          .map(|byte| match byte {
+            0x80..=0x9F => WINDOWS_1252_HIGH_CHARS[(byte - 0x80) as usize],
+            _ => *byte as char,
+          })
+          .collect(),
+      ),
+      TextEncoding::Iso8859_1 => Ok(bytes.iter().map(|byte| *byte as char).collect()),
+    }
+  }
+
+  /// Encodes the text, erroring when it contains a character the encoding can't represent.
+  pub fn encode(&self, text: &str) -> Result<Vec<u8>, ErrBox> {
+    match self {
+      TextEncoding::Utf8 => Ok(text.as_bytes().to_vec()),
+      TextEncoding::Utf16Le => Ok(text.encode_utf16().flat_map(|unit| unit.to_le_bytes().to_vec()).collect()),
+      TextEncoding::Utf16Be => Ok(text.encode_utf16().flat_map(|unit| unit.to_be_bytes().to_vec()).collect()),
+      TextEncoding::Windows1252 | TextEncoding::Iso8859_1 => {
+        let mut bytes = Vec::with_capacity(text.len());
+        for c in text.chars() {
+          match self.encode_single_byte_char(c) {
+            Some(byte) => bytes.push(byte),
+            None => return err!("The character '{}' (U+{:04X}) can't be represented in {}.", c, c as u32, self.name()),
+          }
+        }
+        Ok(bytes)
+      }
+    }
+  }
+
+  fn encode_single_byte_char(&self, c: char) -> Option<u8> {
+    if *self == TextEncoding::Windows1252 {
+      if let Some(index) = WINDOWS_1252_HIGH_CHARS.iter().position(|high_char| *high_char == c) {
+        return Some(0x80 + index as u8);
+      }
+      if ('\u{80}'..='\u{9F}').contains(&c) {
+        return None;
+      }
+    }
+    if (c as u32) <= 0xFF {
+      Some(c as u32 as u8)
+    } else {
+      None
+    }
+  }
+}
+
+/// Gets the encoding of files based on the glob patterns in the `encodings` configuration.
+#[derive(Default)]
+pub struct FileEncodings {
+  matchers: Vec<(GlobMatcher, TextEncoding)>,
+}
+
+impl FileEncodings {
+  /// Creates the matchers for the patterns, which are relative to the base directory. The first
+  /// matching pattern is used, so the patterns should be ordered from most to least specific.
+  pub fn new(encodings: &[(String, TextEncoding)], base_dir: &Path) -> Result<Self, ErrBox> {
+    let base_dir = base_dir.to_string_lossy();
+    let glob_matcher_options = GlobMatcherOptions {
+      case_insensitive: cfg!(windows),
+    };
+    let mut matchers = Vec::with_capacity(encodings.len());
+    for (pattern, encoding) in encodings {
+      let patterns = to_absolute_globs(vec![pattern.clone()], &base_dir);
+      matchers.push((GlobMatcher::new(&patterns, &glob_matcher_options)?, *encoding));
+    }
+    Ok(FileEncodings { matchers })
+  }
+
+  pub fn get_encoding(&self, file_path: &Path) -> TextEncoding {
+    let file_path = file_path.to_string_lossy().replace("\\", "/");
+    self
+      .matchers
+      .iter()
+      .find(|(matcher, _)| matcher.is_match(&file_path))
+      .map(|(_, encoding)| *encoding)
+      .unwrap_or(TextEncoding::Utf8)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_decode_and_encode_single_byte_encodings() {
+    let bytes = vec![b'a', 0xE9, 0x80, 0x93, 0x94];
+    let text = TextEncoding::Windows1252.decode(&bytes).unwrap();
+    assert_eq!(text, "aé€\u{201C}\u{201D}");
+    assert_eq!(TextEncoding::Windows1252.encode(&text).unwrap(), bytes);
+
+    let text = TextEncoding::Iso8859_1.decode(&bytes).unwrap();
+    assert_eq!(text, "aé\u{80}\u{93}\u{94}");
+    assert_eq!(TextEncoding::Iso8859_1.encode(&text).unwrap(), bytes);
+
+    assert_eq!(
+      TextEncoding::Windows1252.encode("a\u{80}").err().unwrap().to_string(),
+      "The character '\u{80}' (U+0080) can't be represented in windows-1252."
+    );
+    assert_eq!(
+      TextEncoding::Iso8859_1.encode("€").err().unwrap().to_string(),
+      "The character '€' (U+20AC) can't be represented in iso-8859-1."
+    );
+  }
+
+  #[test]
+  fn should_decode_and_encode_utf16() {
+    let text = "\u{FEFF}a😀";
+    let le_bytes = TextEncoding::Utf16Le.encode(text).unwrap();
+    assert_eq!(le_bytes, vec![0xFF, 0xFE, b'a', 0, 0x3D, 0xD8, 0x00, 0xDE]);
+    assert_eq!(TextEncoding::Utf16Le.decode(&le_bytes).unwrap(), text);
+    let be_bytes = TextEncoding::Utf16Be.encode(text).unwrap();
+    assert_eq!(be_bytes, vec![0xFE, 0xFF, 0, b'a', 0xD8, 0x3D, 0xDE, 0x00]);
+    assert_eq!(TextEncoding::Utf16Be.decode(&be_bytes).unwrap(), text);
+
+    assert_eq!(
+      TextEncoding::Utf16Le.decode(&[b'a']).err().unwrap().to_string(),
+      "Expected an even number of bytes for utf-16le text."
+    );
+    assert!(TextEncoding::Utf16Le.decode(&[0x3D, 0xD8]).is_err());
+  }
+
+  #[test]
+  fn should_parse_encodings() {
+    assert_eq!(TextEncoding::parse("UTF-8"), Some(TextEncoding::Utf8));
+    assert_eq!(TextEncoding::parse("cp1252"), Some(TextEncoding::Windows1252));
+    assert_eq!(TextEncoding::parse("latin1"), Some(TextEncoding::Iso8859_1));
+    assert_eq!(TextEncoding::parse("shift_jis"), None);
+  }
+
+  #[test]
+  fn should_get_encoding_of_file() {
+    let file_encodings = FileEncodings::new(
+      &[
+        ("legacy/utf8/**".to_string(), TextEncoding::Utf8),
+        ("legacy/**".to_string(), TextEncoding::Windows1252),
+      ],
+      Path::new("/project"),
+    )
+    .unwrap();
+    assert_eq!(file_encodings.get_encoding(Path::new("/project/legacy/a.txt")), TextEncoding::Windows1252);
+    assert_eq!(file_encodings.get_encoding(Path::new("/project/legacy/utf8/a.txt")), TextEncoding::Utf8);
+    assert_eq!(file_encodings.get_encoding(Path::new("/project/src/a.txt")), TextEncoding::Utf8);
+    assert_eq!(FileEncodings::default().get_encoding(Path::new("/project/legacy/a.txt")), TextEncoding::Utf8);
+  }
+}
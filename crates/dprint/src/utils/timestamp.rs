@@ -0,0 +1,44 @@
+/// Gets the ISO-8601 UTC timestamp (ex. `2021-05-01T13:45:10Z`) of the seconds since the Unix epoch.
+pub fn get_iso_8601_timestamp(secs: u64) -> String {
+  let days = (secs / 86_400) as i64;
+  let secs_of_day = secs % 86_400;
+  let (year, month, day) = get_civil_date(days);
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+    year,
+    month,
+    day,
+    secs_of_day / 3600,
+    secs_of_day % 3600 / 60,
+    secs_of_day % 60
+  )
+}
+
+/// Gets the year, month, and day of the days since the Unix epoch.
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn get_civil_date(days: i64) -> (i64, u32, u32) {
+  let days = days + 719_468;
+  let era = days.div_euclid(146_097);
+  let day_of_era = days.rem_euclid(146_097);
+  let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+  let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+  (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn should_get_iso_8601_timestamp() {
+    assert_eq!(get_iso_8601_timestamp(0), "1970-01-01T00:00:00Z");
+    assert_eq!(get_iso_8601_timestamp(123_456), "1970-01-02T10:17:36Z");
+    assert_eq!(get_iso_8601_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+    assert_eq!(get_iso_8601_timestamp(1_619_876_710), "2021-05-01T13:45:10Z");
+    assert_eq!(get_iso_8601_timestamp(4_102_444_799), "2099-12-31T23:59:59Z");
+  }
+}
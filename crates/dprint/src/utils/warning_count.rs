@@ -0,0 +1,17 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the warnings output during the run so they may be treated as errors.
+#[derive(Default)]
+pub struct WarningCount {
+  count: AtomicUsize,
+}
+
+impl WarningCount {
+  pub fn increment(&self) {
+    self.count.fetch_add(1, Ordering::SeqCst);
+  }
+
+  pub fn get(&self) -> usize {
+    self.count.load(Ordering::SeqCst)
+  }
+}
@@ -43,15 +43,15 @@ impl PluginHandler<Configuration> for TestWasmPlugin {
   }
 
   fn get_plugin_info(&mut self) -> PluginInfo {
-    PluginInfo {
-      name: env!("CARGO_PKG_NAME").to_string(),
-      version: env!("CARGO_PKG_VERSION").to_string(),
-      config_key: "test-plugin".to_string(),
-      file_extensions: vec!["txt".to_string()],
-      file_names: vec![],
-      help_url: "https://dprint.dev/plugins/test".to_string(),
-      config_schema_url: "https://plugins.dprint.dev/schemas/test.json".to_string(),
-    }
+    PluginInfo::new(
+      env!("CARGO_PKG_NAME").to_string(),
+      env!("CARGO_PKG_VERSION").to_string(),
+      "test-plugin".to_string(),
+      vec!["txt".to_string()],
+      vec![],
+      "https://dprint.dev/plugins/test".to_string(),
+      "https://plugins.dprint.dev/schemas/test.json".to_string(),
+    )
   }
 
   fn get_license_text(&mut self) -> String {
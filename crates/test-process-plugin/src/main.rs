@@ -33,15 +33,15 @@ impl TestProcessPluginHandler {
 
 impl PluginHandler<Configuration> for TestProcessPluginHandler {
   fn get_plugin_info(&mut self) -> PluginInfo {
-    PluginInfo {
-      name: String::from(env!("CARGO_PKG_NAME")),
-      version: String::from(env!("CARGO_PKG_VERSION")),
-      config_key: "testProcessPlugin".to_string(),
-      file_extensions: vec!["txt_ps".to_string()],
-      file_names: vec!["test-process-plugin-exact-file".to_string()],
-      help_url: "https://dprint.dev/plugins/test-process".to_string(),
-      config_schema_url: "".to_string(),
-    }
+    PluginInfo::new(
+      String::from(env!("CARGO_PKG_NAME")),
+      String::from(env!("CARGO_PKG_VERSION")),
+      "testProcessPlugin".to_string(),
+      vec!["txt_ps".to_string()],
+      vec!["test-process-plugin-exact-file".to_string()],
+      "https://dprint.dev/plugins/test-process".to_string(),
+      "".to_string(),
+    )
   }
 
   fn get_license_text(&mut self) -> String {
@@ -77,6 +77,8 @@ impl PluginHandler<Configuration> for TestProcessPluginHandler {
       format_with_host(&PathBuf::from("./test.txt"), file_text.replace("plugin-config: ", ""), &config_map)
     } else if file_text == "should_error" {
       err!("Did error.")
+    } else if file_text == "should_crash" {
+      std::process::exit(1)
     } else if file_text.ends_with(&config.ending) {
       Ok(String::from(file_text))
     } else {